@@ -6,6 +6,7 @@ use flux_compiler::ffi::*;
 use flux_compiler::ffi::marshaling::*;
 use flux_compiler::ffi::safety::*;
 use flux_compiler::parser::ast::{Type as FluxType, ExternFunction as ASTExternFunction, Parameter, Visibility};
+use flux_compiler::position::{Position, Span};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Flux FFI System Demo ===\n");
@@ -21,11 +22,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             name: "str".to_string(),
             type_: FluxType::String,
             is_mutable: false,
+            span: Span::single(Position::start()),
         }],
         return_type: Some(FluxType::Int),
         library: Some("C".to_string()),
         is_variadic: false,
         visibility: Visibility::Public,
+        span: Span::single(Position::start()),
     };
 
     // Convert to FFI representation
@@ -10,7 +10,7 @@ use std::path::PathBuf;
 use std::time::Instant;
 
 use crate::error::FluxResult;
-use crate::package::{ProjectInstance, BuildConfig, OptimizationLevel};
+use crate::package::{AnalyzedProject, ProjectInstance, BuildConfig, OptimizationLevel};
 
 /// Flux Programming Language Compiler
 #[derive(Parser)]
@@ -58,6 +58,10 @@ pub enum Commands {
         #[arg(long)]
         check: bool,
 
+        /// Print an intermediate representation instead of only building
+        #[arg(long)]
+        emit: Option<EmitKind>,
+
         /// Show compilation progress
         #[arg(long)]
         progress: bool,
@@ -73,6 +77,10 @@ pub enum Commands {
         #[arg(short, long, default_value = "debug")]
         mode: BuildMode,
 
+        /// Execute on the bytecode VM instead of the interpreter
+        #[arg(long)]
+        bytecode: bool,
+
         /// Arguments to pass to the program
         #[arg(last = true)]
         args: Vec<String>,
@@ -186,6 +194,12 @@ pub enum Commands {
         #[arg(default_value = ".")]
         path: PathBuf,
     },
+
+    /// Start an interactive session
+    Repl {
+        /// Source files to load before the first prompt
+        files: Vec<PathBuf>,
+    },
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -216,6 +230,14 @@ pub enum CompilationTarget {
     Wasm,
     /// WebAssembly with JavaScript bindings
     WasmJs,
+    /// Portable bytecode for the Flux VM
+    Bytecode,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum EmitKind {
+    /// Bytecode disassembly
+    Bytecode,
 }
 
 /// CLI execution context
@@ -303,7 +325,7 @@ impl CompilerDriver {
 
     /// Execute build command
     pub fn build(&self, command: &Commands) -> FluxResult<()> {
-        if let Commands::Build { mode, target, output, optimize, check, progress, path } = command {
+        if let Commands::Build { mode, target, output, optimize, check, emit, progress, path } = command {
             self.context.verbose(&format!("Building project at {:?}", path));
             self.context.verbose(&format!("Build mode: {:?}", mode));
 
@@ -311,6 +333,36 @@ impl CompilerDriver {
             let project = ProjectInstance::load(path)?;
             self.context.verbose(&format!("Loaded project: {}", project.name()));
 
+            // Report every syntax error in the project before building anything
+            let syntax_errors = project.syntax_errors()?;
+            if !syntax_errors.is_empty() {
+                for (file, error) in &syntax_errors {
+                    self.context.error(&format!(
+                        "{}:{}:{}: {}",
+                        file.display(),
+                        error.span.start.line,
+                        error.span.start.column,
+                        error.kind
+                    ));
+                }
+                return Err(crate::error::FluxError::Cli(format!(
+                    "Could not build {} due to {} syntax error{}",
+                    project.name(),
+                    syntax_errors.len(),
+                    if syntax_errors.len() == 1 { "" } else { "s" }
+                )));
+            }
+
+            // Libraries without a main file have no program to analyze
+            let analyzed = if project.metadata.main_file().is_file() {
+                Some(project.analyze()?)
+            } else {
+                None
+            };
+            if let Some(analyzed) = &analyzed {
+                self.report_warnings(analyzed);
+            }
+
             // Create build configuration
             let mut build_config = match mode {
                 BuildMode::Debug => BuildConfig::debug(),
@@ -327,6 +379,7 @@ impl CompilerDriver {
                     CompilationTarget::Native => "native".to_string(),
                     CompilationTarget::Wasm => "wasm32-unknown-unknown".to_string(),
                     CompilationTarget::WasmJs => "wasm32-unknown-unknown".to_string(),
+                    CompilationTarget::Bytecode => "bytecode".to_string(),
                 });
                 
                 // Set WebAssembly-specific configuration
@@ -334,6 +387,7 @@ impl CompilerDriver {
                     build_config.wasm_target = true;
                     build_config.generate_js_bindings = matches!(target, CompilationTarget::WasmJs);
                 }
+                build_config.bytecode_target = matches!(target, CompilationTarget::Bytecode);
             }
 
             if let Some(output) = output {
@@ -397,7 +451,15 @@ impl CompilerDriver {
             }
 
             // Build the project
-            project.build(&build_config)?;
+            project.build(&build_config, analyzed.as_ref())?;
+
+            if let Some(EmitKind::Bytecode) = emit {
+                let bytecode = match &analyzed {
+                    Some(analyzed) => analyzed.compile_bytecode()?,
+                    None => project.compile_bytecode()?,
+                };
+                print!("{}", bytecode);
+            }
 
             let elapsed = self.context.elapsed();
             if *check {
@@ -414,28 +476,19 @@ impl CompilerDriver {
 
     /// Execute run command
     pub fn run(&self, command: &Commands) -> FluxResult<()> {
-        if let Commands::Run { mode, args, path } = command {
+        if let Commands::Run { bytecode, args, path, .. } = command {
             self.context.verbose(&format!("Running project at {:?}", path));
 
-            // First build the project
-            let build_command = Commands::Build {
-                mode: mode.clone(),
-                target: None,
-                output: None,
-                optimize: false,
-                check: false,
-                progress: false,
-                path: path.clone(),
-            };
-
-            self.build(&build_command)?;
-
-            // Then execute it
-            self.context.info("Running executable...");
-            
-            // Load project to get executable path
+            // Programs are interpreted, so there is no executable to build first
             let project = ProjectInstance::load(path)?;
-            project.run(args)?;
+            let analyzed = project.analyze()?;
+            self.report_warnings(&analyzed);
+            self.context.info(&format!("Running {}...", project.name()));
+            if *bytecode {
+                analyzed.run_bytecode(args)?;
+            } else {
+                analyzed.run(args)?;
+            }
 
             Ok(())
         } else {
@@ -443,6 +496,19 @@ impl CompilerDriver {
         }
     }
 
+    /// Print the semantic warnings of a project, such as unreachable code
+    fn report_warnings(&self, analyzed: &AnalyzedProject) {
+        for (file, warning) in analyzed.warnings() {
+            self.context.warn(&format!(
+                "{}:{}:{}: {}",
+                file.display(),
+                warning.span.start.line,
+                warning.span.start.column,
+                warning.kind
+            ));
+        }
+    }
+
     /// Execute test command
     pub fn test(&self, command: &Commands) -> FluxResult<()> {
         if let Commands::Test { filter, nocapture, jobs, path } = command {
@@ -0,0 +1,807 @@
+//! Bytecode format for the Flux virtual machine
+//!
+//! A `BytecodeProgram` is a flat image of every module of a project: a
+//! constant pool, the compiled functions, and the tables of globals, types,
+//! enum variants and methods that instructions refer to by index. Programs
+//! serialize to a compact binary encoding with `encode` and `decode`, and
+//! their `Display` implementation is a disassembly listing.
+
+use crate::error::{CodeGenError, CodeGenErrorKind};
+use crate::position::{Position, Span};
+use std::fmt;
+
+/// Magic bytes at the start of an encoded program
+const MAGIC: &[u8; 4] = b"FLXB";
+
+/// Version of the encoding, bumped whenever the instruction set changes
+pub const BYTECODE_VERSION: u16 = 4;
+
+/// Entry of the constant pool
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+}
+
+/// Function implemented by the VM itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Builtin {
+    Print,
+    Println,
+    Some,
+    Ok,
+    Err,
+}
+
+impl Builtin {
+    const ALL: [Builtin; 5] = [Builtin::Print, Builtin::Println, Builtin::Some, Builtin::Ok, Builtin::Err];
+
+    /// Builtin a name refers to, if any
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|builtin| builtin.name() == name)
+    }
+
+    /// Name the builtin is called by in source
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+            Builtin::Println => "println",
+            Builtin::Some => "Some",
+            Builtin::Ok => "Ok",
+            Builtin::Err => "Err",
+        }
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Declares `Instruction` together with its opcodes, so that encoding,
+/// decoding and disassembly cannot disagree about an instruction's operand
+macro_rules! instructions {
+    (@pattern $name:ident $binding:tt) => { Instruction::$name };
+    (@pattern $name:ident $binding:tt ($operand:ty)) => { Instruction::$name($binding) };
+    ($($(#[doc = $doc:literal])* $name:ident $(($operand:ty))? = $opcode:literal,)*) => {
+        /// Stack machine instruction
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum Instruction {
+            $($(#[doc = $doc])* $name $(($operand))?,)*
+        }
+
+        impl Instruction {
+            /// Name of the instruction in disassembly
+            pub fn mnemonic(&self) -> &'static str {
+                match self {
+                    $(instructions!(@pattern $name _ $(($operand))?) => stringify!($name),)*
+                }
+            }
+
+            fn write_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match *self {
+                    $(instructions!(@pattern $name operand $(($operand))?) => {
+                        $(let operand: $operand = operand; write!(f, " {}", operand)?;)?
+                    })*
+                }
+                Ok(())
+            }
+
+            fn encode(&self, writer: &mut Writer) {
+                match *self {
+                    $(instructions!(@pattern $name operand $(($operand))?) => {
+                        writer.byte($opcode);
+                        $(<$operand as Operand>::write(operand, writer);)?
+                    })*
+                }
+            }
+
+            fn decode(reader: &mut Reader) -> Result<Self, CodeGenError> {
+                match reader.byte()? {
+                    $($opcode => Ok(Instruction::$name $((<$operand as Operand>::read(reader)?))?),)*
+                    opcode => Err(invalid(&format!("unknown opcode {}", opcode))),
+                }
+            }
+        }
+    };
+}
+
+instructions! {
+    /// Push a constant from the pool
+    Constant(u32) = 0,
+    Unit = 1,
+    Null = 2,
+    True = 3,
+    False = 4,
+    Pop = 5,
+    /// Push the top value again
+    Dup = 6,
+
+    /// Push a local, reading through its cell if it has one
+    LoadLocal(u16) = 10,
+    /// Pop into a local, writing through its cell if it has one
+    StoreLocal(u16) = 11,
+    /// Pop into a local, replacing whatever the slot held
+    DefineLocal(u16) = 12,
+    /// Move a local into a shared cell, so that closures capturing it by
+    /// reference see later assignments
+    BoxLocal(u16) = 13,
+    /// Push a local as stored, cell included, for a closure to capture
+    CaptureLocal(u16) = 14,
+    /// Push a value captured by the running closure
+    LoadCapture(u16) = 15,
+    /// Pop into a value captured by reference
+    StoreCapture(u16) = 16,
+    /// Push a captured value as stored, cell included, for a nested closure
+    CaptureCapture(u16) = 17,
+    LoadGlobal(u32) = 18,
+    StoreGlobal(u32) = 19,
+    /// Push a reference to a function
+    Function(u32) = 20,
+    Builtin(Builtin) = 21,
+    /// Push the constructor function of a variant with a payload
+    Constructor(u32) = 22,
+
+    Add = 30,
+    Subtract = 31,
+    Multiply = 32,
+    Divide = 33,
+    Modulo = 34,
+    BitAnd = 35,
+    BitOr = 36,
+    BitXor = 37,
+    ShiftLeft = 38,
+    ShiftRight = 39,
+    Equal = 40,
+    NotEqual = 41,
+    Less = 42,
+    LessEqual = 43,
+    Greater = 44,
+    GreaterEqual = 45,
+    Negate = 46,
+    Not = 47,
+    BitNot = 48,
+
+    Jump(u32) = 50,
+    /// Pop a bool and jump if it is false
+    JumpIfFalse(u32) = 51,
+    /// Pop a bool and jump if it is true
+    JumpIfTrue(u32) = 52,
+    /// Call the callee below the given number of arguments
+    Call(u8) = 53,
+    Return = 54,
+    /// Unwrap `Ok`, or return an `Err` from the running function
+    Try = 55,
+    /// Start a goroutine calling the callee below the given number of arguments
+    Go(u8) = 56,
+    /// Report that no match arm matched the value on top of the stack
+    MatchFailed = 57,
+    /// Fail on `null`, leaving any other value in place
+    Unwrap = 58,
+
+    /// Pop the given number of elements into an array
+    Array(u32) = 60,
+    /// Pop the given number of key and value pairs into a map
+    Map(u32) = 61,
+    Tuple(u32) = 62,
+    /// Pop the fields of a type from the type table into an instance
+    Struct(u32) = 63,
+    /// Pop the payload of a variant from the variant table into a value
+    Variant(u32) = 64,
+    /// Pop the captures of a function into a closure
+    Closure(u32) = 65,
+    /// Replace an object with its field or a bound method of the name in the given constant
+    GetField(u32) = 66,
+    /// Pop a value into the field of the object below it
+    SetField(u32) = 67,
+    GetIndex = 68,
+    /// Pop a value into the element of the collection and index below it
+    SetIndex = 69,
+    /// Replace a collection with an array of the elements a `for` loop visits
+    Iterate = 70,
+    /// Replace an array with its length
+    Length = 71,
+    /// Replace a tuple or variant with one of its elements
+    Element(u32) = 72,
+    /// Test whether a value is a tuple of the given length
+    IsTuple(u32) = 73,
+    /// Test whether a value is a variant named by the given constant
+    IsVariant(u32) = 74,
+    /// Test whether a value is an instance of the type or variant named by the given constant
+    IsInstance(u32) = 75,
+    /// Pop a start, end and step into a range that stops before the end
+    Range = 76,
+    /// Pop a start, end and step into a range that includes the end
+    RangeInclusive = 77,
+    /// Pop the given number of values into a string joining their text
+    Concat(u32) = 78,
+}
+
+impl Instruction {
+    /// Target of a jump instruction
+    pub fn jump_target(&self) -> Option<u32> {
+        match *self {
+            Instruction::Jump(target) | Instruction::JumpIfFalse(target) | Instruction::JumpIfTrue(target) => Some(target),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())?;
+        self.write_operand(f)
+    }
+}
+
+/// Compiled function, method or closure body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// Name shown in stack traces
+    pub name: String,
+    /// Number of parameters, including the receiver of a method
+    pub arity: u16,
+    /// Number of local slots, parameters included
+    pub locals: u16,
+    /// Number of values a closure of this function captures
+    pub captures: u16,
+    pub code: Vec<Instruction>,
+    /// Source span of the instructions from each offset on, for error reports
+    pub spans: Vec<(u32, Span)>,
+    /// Source file the function was compiled from
+    pub file: Option<String>,
+}
+
+impl Function {
+    /// Source span of the instruction at `offset`
+    pub fn span_at(&self, offset: usize) -> Option<Span> {
+        let index = self.spans.partition_point(|(start, _)| *start as usize <= offset);
+        index.checked_sub(1).map(|index| self.spans[index].1)
+    }
+}
+
+/// Module of layouts that no module declares, such as `Result`'s variants
+pub const BUILTIN_MODULE: u32 = u32::MAX;
+
+/// Field names of a struct or class, in declaration order. Types are told
+/// apart by the index of the module declaring them as well as by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeLayout {
+    pub module: u32,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+/// Enum variant and the names of its payload fields; tuple payload fields
+/// are named by position
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantLayout {
+    pub module: u32,
+    pub enum_name: String,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+/// Method looked up by the module and name of its receiver's runtime type
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodEntry {
+    pub module: u32,
+    pub type_name: String,
+    pub name: String,
+    pub function: u32,
+}
+
+/// Compiled program ready to be run by the VM
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BytecodeProgram {
+    pub constants: Vec<Constant>,
+    pub functions: Vec<Function>,
+    /// Names of the global slots holding module constants
+    pub globals: Vec<String>,
+    pub types: Vec<TypeLayout>,
+    pub variants: Vec<VariantLayout>,
+    pub methods: Vec<MethodEntry>,
+    /// Functions evaluating module constants, run in order before the entry point
+    pub initializers: Vec<u32>,
+    /// The `main` function
+    pub entry: Option<u32>,
+}
+
+impl BytecodeProgram {
+    /// Serialize the program
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer { bytes: MAGIC.to_vec() };
+        writer.unsigned(BYTECODE_VERSION as u64);
+
+        writer.unsigned(self.constants.len() as u64);
+        for constant in &self.constants {
+            match constant {
+                Constant::Int(value) => {
+                    writer.byte(0);
+                    writer.signed(*value);
+                }
+                Constant::Float(value) => {
+                    writer.byte(1);
+                    writer.bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                Constant::String(value) => {
+                    writer.byte(2);
+                    writer.string(value);
+                }
+                Constant::Char(value) => {
+                    writer.byte(3);
+                    writer.unsigned(*value as u64);
+                }
+            }
+        }
+
+        writer.unsigned(self.functions.len() as u64);
+        for function in &self.functions {
+            writer.string(&function.name);
+            writer.unsigned(function.arity as u64);
+            writer.unsigned(function.locals as u64);
+            writer.unsigned(function.captures as u64);
+            writer.optional_string(function.file.as_deref());
+            writer.unsigned(function.code.len() as u64);
+            for instruction in &function.code {
+                instruction.encode(&mut writer);
+            }
+            writer.unsigned(function.spans.len() as u64);
+            for (offset, span) in &function.spans {
+                writer.unsigned(*offset as u64);
+                writer.span(span);
+            }
+        }
+
+        writer.strings(&self.globals);
+        writer.unsigned(self.types.len() as u64);
+        for layout in &self.types {
+            writer.unsigned(layout.module as u64);
+            writer.string(&layout.name);
+            writer.strings(&layout.fields);
+        }
+        writer.unsigned(self.variants.len() as u64);
+        for layout in &self.variants {
+            writer.unsigned(layout.module as u64);
+            writer.string(&layout.enum_name);
+            writer.string(&layout.name);
+            writer.strings(&layout.fields);
+        }
+        writer.unsigned(self.methods.len() as u64);
+        for method in &self.methods {
+            writer.unsigned(method.module as u64);
+            writer.string(&method.type_name);
+            writer.string(&method.name);
+            writer.unsigned(method.function as u64);
+        }
+        writer.unsigned(self.initializers.len() as u64);
+        for initializer in &self.initializers {
+            writer.unsigned(*initializer as u64);
+        }
+        writer.unsigned(self.entry.map_or(0, |entry| entry as u64 + 1));
+        writer.bytes
+    }
+
+    /// Deserialize a program produced by `encode`
+    pub fn decode(bytes: &[u8]) -> Result<Self, CodeGenError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("missing bytecode header"));
+        }
+        let mut reader = Reader { bytes, position: MAGIC.len() };
+        let version = reader.unsigned()?;
+        if version != BYTECODE_VERSION as u64 {
+            return Err(invalid(&format!("unsupported bytecode version {}", version)));
+        }
+
+        let mut program = BytecodeProgram::default();
+        for _ in 0..reader.length()? {
+            let constant = match reader.byte()? {
+                0 => Constant::Int(reader.signed()?),
+                1 => {
+                    let bytes = reader.take(8)?;
+                    Constant::Float(f64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+                }
+                2 => Constant::String(reader.string()?),
+                3 => {
+                    let code = u32::try_from(reader.unsigned()?).ok().and_then(char::from_u32);
+                    Constant::Char(code.ok_or_else(|| invalid("invalid character constant"))?)
+                }
+                tag => return Err(invalid(&format!("unknown constant tag {}", tag))),
+            };
+            program.constants.push(constant);
+        }
+
+        for _ in 0..reader.length()? {
+            let name = reader.string()?;
+            let arity = u16::read(&mut reader)?;
+            let locals = u16::read(&mut reader)?;
+            let captures = u16::read(&mut reader)?;
+            let file = reader.optional_string()?;
+            let mut code = Vec::new();
+            for _ in 0..reader.length()? {
+                code.push(Instruction::decode(&mut reader)?);
+            }
+            let mut spans = Vec::new();
+            for _ in 0..reader.length()? {
+                spans.push((u32::read(&mut reader)?, reader.span()?));
+            }
+            program.functions.push(Function { name, arity, locals, captures, code, spans, file });
+        }
+
+        program.globals = reader.strings()?;
+        for _ in 0..reader.length()? {
+            program.types.push(TypeLayout {
+                module: u32::read(&mut reader)?,
+                name: reader.string()?,
+                fields: reader.strings()?,
+            });
+        }
+        for _ in 0..reader.length()? {
+            program.variants.push(VariantLayout {
+                module: u32::read(&mut reader)?,
+                enum_name: reader.string()?,
+                name: reader.string()?,
+                fields: reader.strings()?,
+            });
+        }
+        for _ in 0..reader.length()? {
+            program.methods.push(MethodEntry {
+                module: u32::read(&mut reader)?,
+                type_name: reader.string()?,
+                name: reader.string()?,
+                function: u32::read(&mut reader)?,
+            });
+        }
+        for _ in 0..reader.length()? {
+            program.initializers.push(u32::read(&mut reader)?);
+        }
+        program.entry = match u32::read(&mut reader)? {
+            0 => None,
+            entry => Some(entry - 1),
+        };
+
+        if reader.position != bytes.len() {
+            return Err(invalid("trailing bytes after program"));
+        }
+        Ok(program)
+    }
+
+    /// Annotation shown after an instruction's operand in disassembly
+    fn annotation(&self, instruction: &Instruction) -> Option<String> {
+        let constant = |index: u32| self.constants.get(index as usize).map(|constant| match constant {
+            Constant::Int(value) => value.to_string(),
+            Constant::Float(value) => format!("{:?}", value),
+            Constant::String(value) => format!("{:?}", value),
+            Constant::Char(value) => format!("{:?}", value),
+        });
+        let function = |index: u32| self.functions.get(index as usize).map(|function| function.name.clone());
+        let variant = |index: u32| {
+            self.variants.get(index as usize).map(|layout| format!("{}::{}", layout.enum_name, layout.name))
+        };
+
+        match *instruction {
+            Instruction::Constant(index)
+            | Instruction::GetField(index)
+            | Instruction::SetField(index)
+            | Instruction::IsVariant(index)
+            | Instruction::IsInstance(index) => constant(index),
+            Instruction::Function(index) | Instruction::Closure(index) => function(index),
+            Instruction::LoadGlobal(index) | Instruction::StoreGlobal(index) => self.globals.get(index as usize).cloned(),
+            Instruction::Struct(index) => self.types.get(index as usize).map(|layout| layout.name.clone()),
+            Instruction::Variant(index) | Instruction::Constructor(index) => variant(index),
+            _ => None,
+        }
+    }
+}
+
+/// Disassembly listing
+impl fmt::Display for BytecodeProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.globals.is_empty() {
+            writeln!(f, "globals:")?;
+            for (index, name) in self.globals.iter().enumerate() {
+                writeln!(f, "  {:>4}  {}", index, name)?;
+            }
+            writeln!(f)?;
+        }
+
+        for (index, function) in self.functions.iter().enumerate() {
+            let mut notes = vec![format!("arity {}", function.arity), format!("locals {}", function.locals)];
+            if function.captures > 0 {
+                notes.push(format!("captures {}", function.captures));
+            }
+            if self.entry == Some(index as u32) {
+                notes.push("entry".to_string());
+            }
+            if self.initializers.contains(&(index as u32)) {
+                notes.push("initializer".to_string());
+            }
+            write!(f, "fn {} {} ({})", index, function.name, notes.join(", "))?;
+            if let Some(file) = &function.file {
+                write!(f, " in {}", file)?;
+            }
+            writeln!(f)?;
+
+            let mut line = None;
+            for (offset, instruction) in function.code.iter().enumerate() {
+                let current = function.span_at(offset).map(|span| span.start.line);
+                let line_column = match current {
+                    Some(current) if current != line.unwrap_or(0) => format!("{:>4}", current),
+                    _ => "   |".to_string(),
+                };
+                line = current;
+
+                let text = instruction.to_string();
+                match self.annotation(instruction) {
+                    Some(annotation) => writeln!(f, "  {:04} {}  {:<20} ; {}", offset, line_column, text, annotation)?,
+                    None => writeln!(f, "  {:04} {}  {}", offset, line_column, text)?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for method in &self.methods {
+            writeln!(f, "method {}.{} -> fn {}", method.type_name, method.name, method.function)?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: &str) -> CodeGenError {
+    CodeGenError {
+        span: None,
+        kind: CodeGenErrorKind::InternalError { message: format!("invalid bytecode: {}", message) },
+    }
+}
+
+/// Appends LEB128-encoded values
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn byte(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    fn unsigned(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    fn signed(&mut self, value: i64) {
+        // Zigzag encoding keeps small negative numbers short
+        self.unsigned(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.unsigned(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn optional_string(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.byte(1);
+                self.string(value);
+            }
+            None => self.byte(0),
+        }
+    }
+
+    fn strings(&mut self, values: &[String]) {
+        self.unsigned(values.len() as u64);
+        for value in values {
+            self.string(value);
+        }
+    }
+
+    fn span(&mut self, span: &Span) {
+        for position in [span.start, span.end] {
+            self.unsigned(position.line as u64);
+            self.unsigned(position.column as u64);
+            self.unsigned(position.offset as u64);
+        }
+    }
+}
+
+/// Reads values written by `Writer`, failing on truncated input
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, CodeGenError> {
+        let byte = *self.bytes.get(self.position).ok_or_else(|| invalid("unexpected end of input"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, count: usize) -> Result<&[u8], CodeGenError> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid("unexpected end of input"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn unsigned(&mut self) -> Result<u64, CodeGenError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("integer too long"))
+    }
+
+    fn signed(&mut self) -> Result<i64, CodeGenError> {
+        let value = self.unsigned()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn length(&mut self) -> Result<usize, CodeGenError> {
+        let length = self.unsigned()?;
+        // Every element takes at least one byte, which bounds bogus lengths
+        match usize::try_from(length) {
+            Ok(length) if length <= self.bytes.len() - self.position => Ok(length),
+            _ => Err(invalid("length exceeds input")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, CodeGenError> {
+        let length = self.length()?;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    fn optional_string(&mut self) -> Result<Option<String>, CodeGenError> {
+        match self.byte()? {
+            0 => Ok(None),
+            _ => self.string().map(Some),
+        }
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, CodeGenError> {
+        (0..self.length()?).map(|_| self.string()).collect()
+    }
+
+    fn position(&mut self) -> Result<Position, CodeGenError> {
+        let line = self.unsigned()? as usize;
+        let column = self.unsigned()? as usize;
+        let offset = self.unsigned()? as usize;
+        Ok(Position::new(line, column, offset))
+    }
+
+    fn span(&mut self) -> Result<Span, CodeGenError> {
+        Ok(Span { start: self.position()?, end: self.position()? })
+    }
+}
+
+/// Instruction operand with a fixed encoding
+trait Operand: Sized {
+    fn write(self, writer: &mut Writer);
+    fn read(reader: &mut Reader) -> Result<Self, CodeGenError>;
+}
+
+macro_rules! integer_operand {
+    ($($type:ty),*) => {
+        $(impl Operand for $type {
+            fn write(self, writer: &mut Writer) {
+                writer.unsigned(self as u64);
+            }
+
+            fn read(reader: &mut Reader) -> Result<Self, CodeGenError> {
+                <$type>::try_from(reader.unsigned()?).map_err(|_| invalid("operand out of range"))
+            }
+        })*
+    };
+}
+
+integer_operand!(u8, u16, u32);
+
+impl Operand for Builtin {
+    fn write(self, writer: &mut Writer) {
+        let index = Builtin::ALL.iter().position(|builtin| *builtin == self).unwrap_or_default();
+        writer.byte(index as u8);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, CodeGenError> {
+        let index = reader.byte()?;
+        Builtin::ALL.get(index as usize).copied().ok_or_else(|| invalid(&format!("unknown builtin {}", index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::bytecode_compiler::BytecodeCompiler;
+    use crate::lexer::FluxLexer;
+    use crate::parser::{FluxParser, Parser};
+    use crate::semantic::{FluxSemanticAnalyzer, SemanticAnalyzer};
+    use std::path::Path;
+
+    fn compile(source: &str) -> BytecodeProgram {
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = parser.parse_program().unwrap();
+        let typed = FluxSemanticAnalyzer::new().analyze(program).unwrap();
+        BytecodeCompiler::compile(&typed, Some(Path::new("main.flux"))).unwrap()
+    }
+
+    const SOURCE: &str = r#"
+const GREETING: string = "hello"
+
+enum Shape { Circle(int), Empty }
+
+func area(s: Shape) -> int {
+    match s {
+        Shape::Circle(r) => { 3 * r * r }
+        Shape::Empty => { -1 }
+    }
+}
+
+func main() {
+    let total = area(Shape::Circle(2)) + area(Shape::Empty);
+    let f = |x: float| x * 2.5;
+    if total > 0 {
+        println(GREETING);
+    }
+}
+"#;
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let program = compile(SOURCE);
+        let bytes = program.encode();
+        assert!(bytes.starts_with(MAGIC));
+        assert_eq!(BytecodeProgram::decode(&bytes).unwrap(), program);
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        let bytes = compile(SOURCE).encode();
+        assert!(BytecodeProgram::decode(b"nope").is_err());
+        assert!(BytecodeProgram::decode(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(BytecodeProgram::decode(&trailing).is_err());
+
+        let mut version = bytes;
+        version[MAGIC.len()] = 99;
+        assert!(BytecodeProgram::decode(&version).is_err());
+    }
+
+    #[test]
+    fn test_disassembly() {
+        let listing = compile(SOURCE).to_string();
+        assert!(listing.starts_with("globals:\n     0  main::GREETING\n"));
+        assert!(listing.contains("area (arity 1, locals 4) in main.flux"));
+        assert!(listing.contains("main (arity 0, locals 2, entry) in main.flux"));
+        assert!(listing.contains("<init main> (arity 0, locals 0, initializer)"));
+        assert!(listing.contains("IsVariant 0          ; \"Circle\""));
+        assert!(listing.contains("Constant 5           ; 2.5"));
+        assert!(listing.contains("LoadGlobal 0         ; main::GREETING"));
+    }
+
+    #[test]
+    fn test_counted_range_loops() {
+        // Only the loop whose step is not a constant builds a range to iterate
+        let listing = compile("func main() {\n let n = 2;\n for i in 0..n { }\n for i in n..=0 step -1 { }\n for i in 0..4 step n { }\n}").to_string();
+        assert_eq!(listing.matches("Iterate").count(), 1);
+        assert_eq!(listing.matches("Range").count(), 1);
+    }
+}
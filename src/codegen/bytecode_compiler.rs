@@ -0,0 +1,1277 @@
+//! Compiles typed programs to bytecode
+//!
+//! Names are resolved at compile time: locals live in numbered slots of
+//! their function's frame, closures read their captures by position, and
+//! module constants get global slots filled by an initializer function.
+//! Locals that a closure captures by reference are boxed into cells shared
+//! by the function and its closures.
+
+use super::bytecode::{
+    BytecodeProgram, Builtin, Constant, Function, Instruction, MethodEntry, TypeLayout, VariantLayout,
+    BUILTIN_MODULE,
+};
+use super::{constant_step, last_before_overflow, CodeGenerator};
+use crate::error::{CodeGenError, CodeGenErrorKind};
+use crate::parser::ast::{BinaryOp, Literal, Pattern, ResultPattern, UnaryOp};
+use crate::position::Span;
+use crate::semantic::{
+    TypedBlock, TypedClosure, TypedExpression, TypedExpressionKind, TypedField, TypedItem, TypedMatchArm,
+    TypedMethod, TypedProgram, TypedStatement, TypedStatementKind,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Item visible by name at the top level of a module
+#[derive(Debug, Clone, Copy)]
+enum Global {
+    Function(u32),
+    /// Module constant in a global slot
+    Const(u32),
+    /// Declared without a body, so not callable from bytecode
+    Extern,
+}
+
+/// Compiled module: its globals and the modules its imports refer to
+struct Module {
+    globals: HashMap<String, Global>,
+    imports: HashMap<String, usize>,
+}
+
+/// Constant pool entry in a hashable form, for deduplication
+#[derive(PartialEq, Eq, Hash)]
+enum ConstantKey {
+    Int(i64),
+    Float(u64),
+    String(String),
+    Char(char),
+}
+
+/// Body of a function being compiled
+#[derive(Clone, Copy)]
+enum Body<'a> {
+    Block(&'a TypedBlock),
+    Expression(&'a TypedExpression),
+}
+
+/// Compiler from typed modules to a single `BytecodeProgram`
+pub struct BytecodeCompiler {
+    program: BytecodeProgram,
+    modules: Vec<Module>,
+    module_paths: HashMap<String, usize>,
+    constants: HashMap<ConstantKey, u32>,
+    /// Layouts by declaring module and name, as types of different modules
+    /// may share a name
+    types: HashMap<(u32, String), u32>,
+    variants: HashMap<(u32, String, String), u32>,
+}
+
+impl BytecodeCompiler {
+    /// Create a compiler with no modules added
+    pub fn new() -> Self {
+        Self {
+            program: BytecodeProgram::default(),
+            modules: Vec::new(),
+            module_paths: HashMap::new(),
+            constants: HashMap::new(),
+            types: HashMap::new(),
+            variants: HashMap::new(),
+        }
+    }
+
+    /// Compile a single-module program
+    pub fn compile(program: &TypedProgram, file: Option<&Path>) -> Result<BytecodeProgram, CodeGenError> {
+        let mut compiler = Self::new();
+        compiler.add_module(&program.package, program, file)?;
+        Ok(compiler.into_program())
+    }
+
+    /// Add a module to the program. `path` is the path other modules import
+    /// it by; the modules it imports must already be added. The `main`
+    /// function of the last module that has one is the entry point.
+    pub fn add_module(&mut self, path: &str, program: &TypedProgram, file: Option<&Path>) -> Result<(), CodeGenError> {
+        let index = self.modules.len();
+        let imports = program.imports.iter()
+            .filter_map(|import| {
+                self.module_paths.get(&import.path).map(|&module| (import.binding_name().to_string(), module))
+            })
+            .collect();
+        self.modules.push(Module { globals: HashMap::new(), imports });
+        self.module_paths.insert(path.to_string(), index);
+        let file = file.map(|file| file.to_string_lossy().to_string());
+
+        // Every item is declared before any body is compiled, so bodies can
+        // refer to items declared after them
+        let mut bodies = Vec::new();
+        let mut consts = Vec::new();
+        for item in &program.items {
+            match item {
+                TypedItem::Function(func) => {
+                    let id = self.reserve_function();
+                    self.modules[index].globals.insert(func.name.clone(), Global::Function(id));
+                    let parameters = func.parameters.iter().map(|param| param.name.clone()).collect();
+                    bodies.push((id, func.name.clone(), parameters, &func.body));
+                }
+                TypedItem::Struct(struct_def) => {
+                    self.type_layout(index as u32, &struct_def.name, field_names(&struct_def.fields));
+                }
+                TypedItem::Class(class) => {
+                    self.type_layout(index as u32, &class.name, field_names(&class.fields));
+                    for method in &class.methods {
+                        bodies.push(self.declare_method(index, &class.name, method));
+                    }
+                }
+                TypedItem::Impl(impl_block) => {
+                    for method in &impl_block.methods {
+                        bodies.push(self.declare_method(index, &impl_block.type_name, method));
+                    }
+                }
+                TypedItem::Enum(enum_def) => {
+                    for variant in &enum_def.variants {
+                        self.variant_layout(index as u32, &enum_def.name, &variant.name, field_names(&variant.fields));
+                    }
+                }
+                TypedItem::Const(constant) => {
+                    let slot = self.program.globals.len() as u32;
+                    self.program.globals.push(format!("{}::{}", path, constant.name));
+                    self.modules[index].globals.insert(constant.name.clone(), Global::Const(slot));
+                    consts.push((slot, &constant.value));
+                }
+                TypedItem::ExternFunction(func) => {
+                    self.modules[index].globals.insert(func.name.clone(), Global::Extern);
+                }
+                TypedItem::Interface(_) => {}
+            }
+        }
+
+        for (id, name, parameters, body) in bodies {
+            let function = FunctionCompiler::new(self, index, file.clone())
+                .compile(name, &parameters, Vec::new(), Body::Block(body))?;
+            self.program.functions[id as usize] = function;
+        }
+
+        // Constants are evaluated in declaration order, so each sees the ones before it
+        if !consts.is_empty() {
+            let id = self.reserve_function();
+            let mut compiler = FunctionCompiler::new(self, index, file.clone());
+            compiler.scopes.push(Vec::new());
+            for (slot, value) in consts {
+                compiler.compile_expression(value)?;
+                compiler.emit(Instruction::StoreGlobal(slot));
+            }
+            compiler.emit(Instruction::Unit);
+            compiler.emit(Instruction::Return);
+            let function = compiler.finish(format!("<init {}>", path), 0, 0);
+            self.program.functions[id as usize] = function;
+            self.program.initializers.push(id);
+        }
+
+        if let Some(Global::Function(main)) = self.modules[index].globals.get("main") {
+            self.program.entry = Some(*main);
+        }
+        Ok(())
+    }
+
+    /// The program compiled so far
+    pub fn program(&self) -> &BytecodeProgram {
+        &self.program
+    }
+
+    /// Take the compiled program
+    pub fn into_program(self) -> BytecodeProgram {
+        self.program
+    }
+
+    fn declare_method<'m>(&mut self, module: usize, type_name: &str, method: &'m TypedMethod) -> (u32, String, Vec<String>, &'m TypedBlock) {
+        let id = self.reserve_function();
+        let name = format!("{}::{}", type_name, method.name);
+        let mut parameters: Vec<String> = method.parameters.iter().map(|param| param.name.clone()).collect();
+        if method.is_static {
+            self.modules[module].globals.insert(name.clone(), Global::Function(id));
+        } else {
+            // The receiver is passed as the first argument
+            parameters.insert(0, "self".to_string());
+            let (owner, type_name) = self.type_key(module, type_name);
+            self.program.methods.push(MethodEntry {
+                module: owner,
+                type_name: type_name.to_string(),
+                name: method.name.clone(),
+                function: id,
+            });
+        }
+        (id, name, parameters, &method.body)
+    }
+
+    fn reserve_function(&mut self) -> u32 {
+        self.program.functions.push(Function {
+            name: String::new(),
+            arity: 0,
+            locals: 0,
+            captures: 0,
+            code: Vec::new(),
+            spans: Vec::new(),
+            file: None,
+        });
+        (self.program.functions.len() - 1) as u32
+    }
+
+    fn constant(&mut self, constant: Constant) -> u32 {
+        let key = match &constant {
+            Constant::Int(value) => ConstantKey::Int(*value),
+            Constant::Float(value) => ConstantKey::Float(value.to_bits()),
+            Constant::String(value) => ConstantKey::String(value.clone()),
+            Constant::Char(value) => ConstantKey::Char(*value),
+        };
+        let next = self.program.constants.len() as u32;
+        let index = *self.constants.entry(key).or_insert(next);
+        if index == next {
+            self.program.constants.push(constant);
+        }
+        index
+    }
+
+    fn type_layout(&mut self, module: u32, name: &str, fields: Vec<String>) -> u32 {
+        let key = (module, name.to_string());
+        if let Some(&index) = self.types.get(&key) {
+            return index;
+        }
+        let index = self.program.types.len() as u32;
+        self.program.types.push(TypeLayout { module, name: name.to_string(), fields });
+        self.types.insert(key, index);
+        index
+    }
+
+    fn variant_layout(&mut self, module: u32, enum_name: &str, name: &str, fields: Vec<String>) -> u32 {
+        let key = (module, enum_name.to_string(), name.to_string());
+        if let Some(&index) = self.variants.get(&key) {
+            return index;
+        }
+        let index = self.program.variants.len() as u32;
+        self.program.variants.push(VariantLayout {
+            module,
+            enum_name: enum_name.to_string(),
+            name: name.to_string(),
+            fields,
+        });
+        self.variants.insert(key, index);
+        index
+    }
+
+    /// Declared variant a name in `module` refers to, including
+    /// `binding::Enum::Variant` for enums of imported modules
+    fn declared_variant(&self, module: usize, enum_name: &str, name: &str) -> Option<u32> {
+        let (module, enum_name) = self.type_key(module, enum_name);
+        self.variants.get(&(module, enum_name.to_string(), name.to_string())).copied()
+    }
+
+    /// Declaring module and name of a type named in `module`; imported types
+    /// are named `binding::Type`
+    fn type_key<'n>(&self, module: usize, name: &'n str) -> (u32, &'n str) {
+        if let Some((binding, member)) = name.split_once("::") {
+            if let Some(&imported) = self.modules[module].imports.get(binding) {
+                return (imported as u32, member);
+            }
+        }
+        (module as u32, name)
+    }
+
+    /// Global a name refers to in `module`, including `binding::name` for
+    /// globals of imported modules
+    fn global(&self, module: usize, name: &str) -> Option<Global> {
+        let module = &self.modules[module];
+        module.globals.get(name).copied().or_else(|| {
+            let (binding, member) = name.split_once("::")?;
+            let imported = module.imports.get(binding)?;
+            self.modules[*imported].globals.get(member).copied()
+        })
+    }
+}
+
+impl Default for BytecodeCompiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates the disassembly of a single-module program
+impl CodeGenerator for BytecodeCompiler {
+    fn generate(&mut self, program: TypedProgram) -> Result<String, CodeGenError> {
+        self.add_module(&program.package, &program, None)?;
+        Ok(self.program.to_string())
+    }
+}
+
+/// Jumps out of a loop waiting for the loop's end and continue target
+#[derive(Default)]
+struct Loop {
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// Compiles one function body
+struct FunctionCompiler<'a> {
+    compiler: &'a mut BytecodeCompiler,
+    module: usize,
+    file: Option<String>,
+    code: Vec<Instruction>,
+    spans: Vec<(u32, Span)>,
+    scopes: Vec<Vec<(String, u16)>>,
+    next_local: u16,
+    max_locals: u16,
+    /// Names of the values a closure captures, by position
+    captures: Vec<String>,
+    /// Variables closures in the body capture by reference
+    boxed: HashSet<String>,
+    loops: Vec<Loop>,
+}
+
+impl<'a> FunctionCompiler<'a> {
+    fn new(compiler: &'a mut BytecodeCompiler, module: usize, file: Option<String>) -> Self {
+        Self {
+            compiler,
+            module,
+            file,
+            code: Vec::new(),
+            spans: Vec::new(),
+            scopes: Vec::new(),
+            next_local: 0,
+            max_locals: 0,
+            captures: Vec::new(),
+            boxed: HashSet::new(),
+            loops: Vec::new(),
+        }
+    }
+
+    fn compile(mut self, name: String, parameters: &[String], captures: Vec<String>, body: Body) -> Result<Function, CodeGenError> {
+        self.boxed = captured_by_reference(body);
+        let capture_count = captures.len();
+        self.captures = captures;
+
+        self.scopes.push(Vec::new());
+        for param in parameters {
+            let slot = self.define_local(param, None)?;
+            if self.boxed.contains(param) {
+                self.emit(Instruction::BoxLocal(slot));
+            }
+        }
+        match body {
+            Body::Block(block) => self.compile_block(block, true)?,
+            Body::Expression(expr) => self.compile_expression(expr)?,
+        }
+        self.emit(Instruction::Return);
+
+        Ok(self.finish(name, parameters.len(), capture_count))
+    }
+
+    fn finish(self, name: String, arity: usize, captures: usize) -> Function {
+        Function {
+            name,
+            arity: arity as u16,
+            locals: self.max_locals,
+            captures: captures as u16,
+            code: self.code,
+            spans: self.spans,
+            file: self.file,
+        }
+    }
+
+    // Emission
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.code.push(instruction);
+        self.code.len() - 1
+    }
+
+    /// Attribute the instructions emitted next to `span`
+    fn mark(&mut self, span: Option<Span>) {
+        let Some(span) = span else { return };
+        let offset = self.code.len() as u32;
+        match self.spans.last_mut() {
+            Some((_, last)) if *last == span => {}
+            Some((start, last)) if *start == offset => *last = span,
+            _ => self.spans.push((offset, span)),
+        }
+    }
+
+    /// Point the jump at `at` to the next instruction
+    fn patch(&mut self, at: usize) {
+        let target = self.code.len() as u32;
+        self.patch_to(at, target);
+    }
+
+    fn patch_to(&mut self, at: usize, target: u32) {
+        self.code[at] = match self.code[at] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            Instruction::JumpIfTrue(_) => Instruction::JumpIfTrue(target),
+            other => other,
+        };
+    }
+
+    fn constant(&mut self, constant: Constant) -> Instruction {
+        Instruction::Constant(self.compiler.constant(constant))
+    }
+
+    fn name_constant(&mut self, name: &str) -> u32 {
+        self.compiler.constant(Constant::String(name.to_string()))
+    }
+
+    // Variables
+
+    fn define_local(&mut self, name: &str, span: Option<Span>) -> Result<u16, CodeGenError> {
+        let slot = self.next_local;
+        self.next_local = self.next_local.checked_add(1).ok_or_else(|| {
+            error(CodeGenErrorKind::UnsupportedFeature { feature: "more than 65535 local variables".to_string() }, span)
+        })?;
+        self.max_locals = self.max_locals.max(self.next_local);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), slot));
+        }
+        Ok(slot)
+    }
+
+    /// Slot for an intermediate value no source name refers to
+    fn temporary(&mut self) -> Result<u16, CodeGenError> {
+        self.define_local("", None)
+    }
+
+    /// Bind the value on top of the stack to a new local
+    fn bind(&mut self, name: &str, span: Option<Span>) -> Result<(), CodeGenError> {
+        let slot = self.define_local(name, span)?;
+        self.emit(Instruction::DefineLocal(slot));
+        if self.boxed.contains(name) {
+            self.emit(Instruction::BoxLocal(slot));
+        }
+        Ok(())
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn exit_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            if let Some((_, first)) = scope.first() {
+                self.next_local = *first;
+            }
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<u16> {
+        self.scopes.iter().rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(local, _)| local == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    fn capture(&self, name: &str) -> Option<u16> {
+        self.captures.iter().position(|capture| capture == name).map(|index| index as u16)
+    }
+
+    fn load(&mut self, name: &str, span: Option<Span>) -> Result<(), CodeGenError> {
+        if let Some(slot) = self.local(name) {
+            self.emit(Instruction::LoadLocal(slot));
+            return Ok(());
+        }
+        if let Some(index) = self.capture(name) {
+            self.emit(Instruction::LoadCapture(index));
+            return Ok(());
+        }
+
+        match self.compiler.global(self.module, name) {
+            Some(Global::Function(id)) => {
+                self.emit(Instruction::Function(id));
+                return Ok(());
+            }
+            Some(Global::Const(slot)) => {
+                self.emit(Instruction::LoadGlobal(slot));
+                return Ok(());
+            }
+            Some(Global::Extern) => {
+                let feature = format!("calling extern function `{}` from bytecode", name);
+                return Err(error(CodeGenErrorKind::UnsupportedFeature { feature }, span));
+            }
+            None => {}
+        }
+
+        if let Some((enum_name, variant)) = name.rsplit_once("::") {
+            if let Some(index) = self.compiler.declared_variant(self.module, enum_name, variant) {
+                let layout = &self.compiler.program.variants[index as usize];
+                self.emit(if layout.fields.is_empty() {
+                    Instruction::Variant(index)
+                } else {
+                    Instruction::Constructor(index)
+                });
+                return Ok(());
+            }
+        }
+        if let Some(builtin) = Builtin::from_name(name) {
+            self.emit(Instruction::Builtin(builtin));
+            return Ok(());
+        }
+
+        let message = format!("undefined name `{}`", name);
+        Err(error(CodeGenErrorKind::InternalError { message }, span))
+    }
+
+    // Statements
+
+    /// Compile a block; with `keep`, its value is left on the stack
+    fn compile_block(&mut self, block: &TypedBlock, keep: bool) -> Result<(), CodeGenError> {
+        self.enter_scope();
+        let last = block.statements.len().checked_sub(1);
+        for (index, stmt) in block.statements.iter().enumerate() {
+            self.compile_statement(stmt, keep && Some(index) == last)?;
+        }
+        if keep && last.is_none() {
+            self.emit(Instruction::Unit);
+        }
+        self.exit_scope();
+        Ok(())
+    }
+
+    /// Compile a statement; with `keep`, its value is left on the stack
+    fn compile_statement(&mut self, stmt: &TypedStatement, keep: bool) -> Result<(), CodeGenError> {
+        self.mark(stmt.span);
+        match &stmt.kind {
+            TypedStatementKind::Expression(expr) => {
+                self.compile_expression(expr)?;
+                if !keep {
+                    self.emit(Instruction::Pop);
+                }
+                return Ok(());
+            }
+            TypedStatementKind::Let(name, _, init) => {
+                match init {
+                    Some(init) => self.compile_expression(init)?,
+                    None => {
+                        self.emit(Instruction::Unit);
+                    }
+                }
+                self.bind(name, stmt.span)?;
+            }
+            TypedStatementKind::Destructure(pattern, value) => {
+                self.compile_expression(value)?;
+                let slot = self.temporary()?;
+                self.emit(Instruction::DefineLocal(slot));
+                let mut failures = Vec::new();
+                self.compile_pattern(pattern, slot, &mut failures, stmt.span)?;
+                
+                // The type checker only accepts patterns that always match,
+                // but their shape is still tested
+                if !failures.is_empty() {
+                    let done = self.emit(Instruction::Jump(0));
+                    for failure in failures {
+                        self.patch(failure);
+                    }
+                    self.emit(Instruction::LoadLocal(slot));
+                    self.mark(stmt.span);
+                    self.emit(Instruction::MatchFailed);
+                    self.patch(done);
+                }
+            }
+            TypedStatementKind::Const(name, _, value) => {
+                self.compile_expression(value)?;
+                self.bind(name, stmt.span)?;
+            }
+            TypedStatementKind::Assignment(target, value) => self.compile_assignment(target, value)?,
+            TypedStatementKind::Return(expr) => {
+                match expr {
+                    Some(expr) => self.compile_expression(expr)?,
+                    None => {
+                        self.emit(Instruction::Unit);
+                    }
+                }
+                self.emit(Instruction::Return);
+                return Ok(());
+            }
+            TypedStatementKind::Break(expr) => {
+                if let Some(expr) = expr {
+                    self.compile_expression(expr)?;
+                    self.emit(Instruction::Pop);
+                }
+                let jump = self.emit(Instruction::Jump(0));
+                self.current_loop(stmt.span, "break")?.breaks.push(jump);
+                return Ok(());
+            }
+            TypedStatementKind::Continue => {
+                let jump = self.emit(Instruction::Jump(0));
+                self.current_loop(stmt.span, "continue")?.continues.push(jump);
+                return Ok(());
+            }
+            TypedStatementKind::Go(expr) => {
+                let TypedExpressionKind::Call(callee, args) = &expr.kind else {
+                    let feature = "`go` with an expression other than a call".to_string();
+                    return Err(error(CodeGenErrorKind::UnsupportedFeature { feature }, expr.span));
+                };
+                self.compile_expression(callee)?;
+                for arg in args {
+                    self.compile_expression(arg)?;
+                }
+                self.mark(expr.span);
+                let count = argument_count(args.len(), expr.span)?;
+                self.emit(Instruction::Go(count));
+            }
+            TypedStatementKind::If(cond, then_block, else_block) => {
+                return self.compile_if(cond, then_block, else_block.as_ref(), keep);
+            }
+            TypedStatementKind::While(cond, body) => {
+                let start = self.code.len() as u32;
+                self.compile_expression(cond)?;
+                self.mark(cond.span);
+                let exit = self.emit(Instruction::JumpIfFalse(0));
+                self.loops.push(Loop::default());
+                self.compile_block(body, false)?;
+                let labels = self.loops.pop().unwrap_or_default();
+                self.emit(Instruction::Jump(start));
+                self.patch(exit);
+                self.finish_loop(labels, start);
+            }
+            TypedStatementKind::For(var, iter, body) => self.compile_for(var, iter, body, stmt.span)?,
+            TypedStatementKind::Match(scrutinee, arms) => return self.compile_match(scrutinee, arms, keep, stmt.span),
+        }
+        if keep {
+            self.emit(Instruction::Unit);
+        }
+        Ok(())
+    }
+
+    fn current_loop(&mut self, span: Option<Span>, keyword: &str) -> Result<&mut Loop, CodeGenError> {
+        self.loops.last_mut().ok_or_else(|| {
+            let message = format!("`{}` outside of a loop", keyword);
+            error(CodeGenErrorKind::InternalError { message }, span)
+        })
+    }
+
+    /// Point the loop's breaks past its end and its continues at `next`
+    fn finish_loop(&mut self, labels: Loop, next: u32) {
+        for jump in labels.breaks {
+            self.patch(jump);
+        }
+        for jump in labels.continues {
+            self.patch_to(jump, next);
+        }
+    }
+
+    fn compile_if(&mut self, cond: &TypedExpression, then_block: &TypedBlock, else_block: Option<&TypedBlock>, keep: bool) -> Result<(), CodeGenError> {
+        self.compile_expression(cond)?;
+        self.mark(cond.span);
+        let skip_then = self.emit(Instruction::JumpIfFalse(0));
+        self.compile_block(then_block, keep)?;
+        if else_block.is_none() && !keep {
+            self.patch(skip_then);
+            return Ok(());
+        }
+
+        let skip_else = self.emit(Instruction::Jump(0));
+        self.patch(skip_then);
+        match else_block {
+            Some(else_block) => self.compile_block(else_block, keep)?,
+            None => {
+                self.emit(Instruction::Unit);
+            }
+        }
+        self.patch(skip_else);
+        Ok(())
+    }
+
+    /// `for` loops walk a snapshot of the collection's elements by index
+    fn compile_for(&mut self, var: &str, iter: &TypedExpression, body: &TypedBlock, span: Option<Span>) -> Result<(), CodeGenError> {
+        if let TypedExpressionKind::Range(_, _, step, _) = &iter.kind {
+            if let Some(step) = constant_step(step.as_deref()) {
+                return self.compile_counted_for(var, iter, step, body, span);
+            }
+        }
+
+        self.enter_scope();
+        self.compile_expression(iter)?;
+        self.mark(iter.span);
+        self.emit(Instruction::Iterate);
+        let elements = self.temporary()?;
+        self.emit(Instruction::DefineLocal(elements));
+        let zero = self.constant(Constant::Int(0));
+        self.emit(zero);
+        let index = self.temporary()?;
+        self.emit(Instruction::DefineLocal(index));
+
+        let start = self.code.len() as u32;
+        self.emit(Instruction::LoadLocal(index));
+        self.emit(Instruction::LoadLocal(elements));
+        self.emit(Instruction::Length);
+        self.emit(Instruction::Less);
+        let exit = self.emit(Instruction::JumpIfFalse(0));
+
+        self.enter_scope();
+        self.emit(Instruction::LoadLocal(elements));
+        self.emit(Instruction::LoadLocal(index));
+        self.emit(Instruction::GetIndex);
+        self.bind(var, span)?;
+        self.loops.push(Loop::default());
+        self.compile_block(body, false)?;
+        let labels = self.loops.pop().unwrap_or_default();
+        self.exit_scope();
+
+        let next = self.code.len() as u32;
+        self.emit(Instruction::LoadLocal(index));
+        let one = self.constant(Constant::Int(1));
+        self.emit(one);
+        self.emit(Instruction::Add);
+        self.emit(Instruction::DefineLocal(index));
+        self.emit(Instruction::Jump(start));
+        self.patch(exit);
+        self.finish_loop(labels, next);
+        self.exit_scope();
+        Ok(())
+    }
+
+    /// Loops over a range literal with a constant step count the loop
+    /// variable from the start to the end without building a range
+    fn compile_counted_for(&mut self, var: &str, range: &TypedExpression, step: i64, body: &TypedBlock, span: Option<Span>) -> Result<(), CodeGenError> {
+        let TypedExpressionKind::Range(start, end, _, inclusive) = &range.kind else {
+            let message = "counted loop over a value that is not a range".to_string();
+            return Err(error(CodeGenErrorKind::InternalError { message }, range.span));
+        };
+        self.enter_scope();
+        self.compile_expression(start)?;
+        let index = self.temporary()?;
+        self.emit(Instruction::DefineLocal(index));
+        self.compile_expression(end)?;
+        let limit = self.temporary()?;
+        self.emit(Instruction::DefineLocal(limit));
+
+        let top = self.code.len() as u32;
+        self.emit(Instruction::LoadLocal(index));
+        self.emit(Instruction::LoadLocal(limit));
+        self.emit(match (step > 0, *inclusive) {
+            (true, false) => Instruction::Less,
+            (true, true) => Instruction::LessEqual,
+            (false, false) => Instruction::Greater,
+            (false, true) => Instruction::GreaterEqual,
+        });
+        let exit = self.emit(Instruction::JumpIfFalse(0));
+
+        self.enter_scope();
+        self.emit(Instruction::LoadLocal(index));
+        self.bind(var, span)?;
+        self.loops.push(Loop::default());
+        self.compile_block(body, false)?;
+        let labels = self.loops.pop().unwrap_or_default();
+        self.exit_scope();
+
+        // Stop before a step that would overflow, as at an end of `i64::MAX`
+        let next = self.code.len() as u32;
+        self.emit(Instruction::LoadLocal(index));
+        let last = self.constant(Constant::Int(last_before_overflow(step)));
+        self.emit(last);
+        self.emit(if step > 0 { Instruction::LessEqual } else { Instruction::GreaterEqual });
+        let overflow = self.emit(Instruction::JumpIfFalse(0));
+        self.emit(Instruction::LoadLocal(index));
+        let step = self.constant(Constant::Int(step));
+        self.emit(step);
+        self.mark(range.span);
+        self.emit(Instruction::Add);
+        self.emit(Instruction::DefineLocal(index));
+        self.emit(Instruction::Jump(top));
+        self.patch(exit);
+        self.patch(overflow);
+        self.finish_loop(labels, next);
+        self.exit_scope();
+        Ok(())
+    }
+
+    fn compile_assignment(&mut self, target: &TypedExpression, value: &TypedExpression) -> Result<(), CodeGenError> {
+        match &target.kind {
+            TypedExpressionKind::Identifier(name) => {
+                self.compile_expression(value)?;
+                if let Some(slot) = self.local(name) {
+                    self.emit(Instruction::StoreLocal(slot));
+                } else if let Some(index) = self.capture(name) {
+                    self.emit(Instruction::StoreCapture(index));
+                } else {
+                    let message = format!("cannot assign to `{}`", name);
+                    return Err(error(CodeGenErrorKind::InternalError { message }, target.span));
+                }
+            }
+            TypedExpressionKind::Field(object, name) => {
+                self.compile_expression(object)?;
+                self.compile_expression(value)?;
+                let name = self.name_constant(name);
+                self.mark(target.span);
+                self.emit(Instruction::SetField(name));
+            }
+            TypedExpressionKind::Index(collection, index) => {
+                self.compile_expression(collection)?;
+                self.compile_expression(index)?;
+                self.compile_expression(value)?;
+                self.mark(target.span);
+                self.emit(Instruction::SetIndex);
+            }
+            _ => {
+                let message = "invalid assignment target".to_string();
+                return Err(error(CodeGenErrorKind::InternalError { message }, target.span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Arms test their pattern against the scrutinee in turn; a failed test
+    /// jumps to the next arm, and falling off the last arm is an error
+    fn compile_match(&mut self, scrutinee: &TypedExpression, arms: &[TypedMatchArm], keep: bool, span: Option<Span>) -> Result<(), CodeGenError> {
+        self.compile_expression(scrutinee)?;
+        self.enter_scope();
+        let value = self.temporary()?;
+        self.emit(Instruction::DefineLocal(value));
+
+        let mut ends = Vec::new();
+        for arm in arms {
+            self.enter_scope();
+            let mut failures = Vec::new();
+            self.compile_pattern(&arm.pattern, value, &mut failures, span)?;
+            if let Some(guard) = &arm.guard {
+                self.compile_expression(guard)?;
+                self.mark(guard.span);
+                failures.push(self.emit(Instruction::JumpIfFalse(0)));
+            }
+            self.compile_block(&arm.body, keep)?;
+            ends.push(self.emit(Instruction::Jump(0)));
+            self.exit_scope();
+            for failure in failures {
+                self.patch(failure);
+            }
+        }
+
+        self.emit(Instruction::LoadLocal(value));
+        self.mark(span.or(scrutinee.span));
+        self.emit(Instruction::MatchFailed);
+        for end in ends {
+            self.patch(end);
+        }
+        self.exit_scope();
+        Ok(())
+    }
+
+    /// Test the value in `slot` against `pattern`, binding its variables
+    fn compile_pattern(&mut self, pattern: &Pattern, slot: u16, failures: &mut Vec<usize>, span: Option<Span>) -> Result<(), CodeGenError> {
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Identifier(name) => {
+                self.emit(Instruction::LoadLocal(slot));
+                self.bind(name, span)?;
+            }
+            Pattern::Literal(literal, _) => {
+                self.emit(Instruction::LoadLocal(slot));
+                self.compile_literal(literal);
+                self.emit(Instruction::Equal);
+                failures.push(self.emit(Instruction::JumpIfFalse(0)));
+            }
+            Pattern::Tuple(patterns) => {
+                self.emit(Instruction::LoadLocal(slot));
+                self.emit(Instruction::IsTuple(patterns.len() as u32));
+                failures.push(self.emit(Instruction::JumpIfFalse(0)));
+                self.compile_elements(patterns, slot, failures, span)?;
+            }
+            Pattern::Variant(path, patterns) => {
+                let name = self.name_constant(path.last().map(String::as_str).unwrap_or_default());
+                self.emit(Instruction::LoadLocal(slot));
+                self.emit(Instruction::IsVariant(name));
+                failures.push(self.emit(Instruction::JumpIfFalse(0)));
+                self.compile_elements(patterns, slot, failures, span)?;
+            }
+            Pattern::Struct(name, fields) => {
+                let name = self.name_constant(name);
+                self.emit(Instruction::LoadLocal(slot));
+                self.emit(Instruction::IsInstance(name));
+                failures.push(self.emit(Instruction::JumpIfFalse(0)));
+                for (field, pattern) in fields {
+                    let field = self.name_constant(field);
+                    self.emit(Instruction::LoadLocal(slot));
+                    self.emit(Instruction::GetField(field));
+                    let element = self.temporary()?;
+                    self.emit(Instruction::DefineLocal(element));
+                    self.compile_pattern(pattern, element, failures, span)?;
+                }
+            }
+            Pattern::Result(result_pattern) => {
+                let (variant, inner) = match result_pattern {
+                    ResultPattern::Ok(inner) => ("Ok", inner),
+                    ResultPattern::Err(inner) => ("Err", inner),
+                };
+                let name = self.name_constant(variant);
+                self.emit(Instruction::LoadLocal(slot));
+                self.emit(Instruction::IsVariant(name));
+                failures.push(self.emit(Instruction::JumpIfFalse(0)));
+                self.compile_elements(std::slice::from_ref(inner), slot, failures, span)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_elements(&mut self, patterns: &[Pattern], slot: u16, failures: &mut Vec<usize>, span: Option<Span>) -> Result<(), CodeGenError> {
+        for (index, pattern) in patterns.iter().enumerate() {
+            if matches!(pattern, Pattern::Wildcard) {
+                continue;
+            }
+            self.emit(Instruction::LoadLocal(slot));
+            self.emit(Instruction::Element(index as u32));
+            let element = self.temporary()?;
+            self.emit(Instruction::DefineLocal(element));
+            self.compile_pattern(pattern, element, failures, span)?;
+        }
+        Ok(())
+    }
+
+    // Expressions
+
+    fn compile_literal(&mut self, literal: &Literal) {
+        let instruction = match literal {
+            Literal::Integer(value) => self.constant(Constant::Int(*value)),
+            Literal::Float(value) => self.constant(Constant::Float(*value)),
+            // Bytes are held as ints
+            Literal::Byte(value) => self.constant(Constant::Int(i64::from(*value))),
+            Literal::String(value) => self.constant(Constant::String(value.clone())),
+            Literal::Character(value) => self.constant(Constant::Char(*value)),
+            Literal::Boolean(true) => Instruction::True,
+            Literal::Boolean(false) => Instruction::False,
+            Literal::Null => Instruction::Null,
+        };
+        self.emit(instruction);
+    }
+
+    fn compile_expression(&mut self, expr: &TypedExpression) -> Result<(), CodeGenError> {
+        match &expr.kind {
+            TypedExpressionKind::Literal(literal) => self.compile_literal(literal),
+            TypedExpressionKind::Identifier(name) | TypedExpressionKind::Instantiate(name, _) => self.load(name, expr.span)?,
+            TypedExpressionKind::Binary(left, op, right) => self.compile_binary(left, op, right, expr.span)?,
+            TypedExpressionKind::Unary(op, operand) => {
+                self.compile_expression(operand)?;
+                let instruction = match op {
+                    UnaryOp::Plus => return Ok(()),
+                    UnaryOp::Minus => Instruction::Negate,
+                    UnaryOp::Not => Instruction::Not,
+                    UnaryOp::BitwiseNot => Instruction::BitNot,
+                    UnaryOp::Try => Instruction::Try,
+                    UnaryOp::Unwrap => Instruction::Unwrap,
+                };
+                self.mark(expr.span);
+                self.emit(instruction);
+            }
+            TypedExpressionKind::Call(callee, args) => {
+                self.compile_expression(callee)?;
+                for arg in args {
+                    self.compile_expression(arg)?;
+                }
+                self.mark(expr.span);
+                let count = argument_count(args.len(), expr.span)?;
+                self.emit(Instruction::Call(count));
+            }
+            TypedExpressionKind::Index(collection, index) => {
+                self.compile_expression(collection)?;
+                self.compile_expression(index)?;
+                self.mark(expr.span);
+                self.emit(Instruction::GetIndex);
+            }
+            TypedExpressionKind::Field(object, name) => {
+                self.compile_expression(object)?;
+                let name = self.name_constant(name);
+                self.mark(expr.span);
+                self.emit(Instruction::GetField(name));
+            }
+            TypedExpressionKind::Match(scrutinee, arms) => self.compile_match(scrutinee, arms, true, expr.span)?,
+            TypedExpressionKind::If(cond, then_block, else_block) => {
+                self.compile_if(cond, then_block, else_block.as_ref(), true)?;
+            }
+            TypedExpressionKind::Block(block) => self.compile_block(block, true)?,
+            TypedExpressionKind::Array(elements) => {
+                for element in elements {
+                    self.compile_expression(element)?;
+                }
+                self.emit(Instruction::Array(elements.len() as u32));
+            }
+            TypedExpressionKind::Map(pairs) => {
+                for (key, value) in pairs {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+                self.emit(Instruction::Map(pairs.len() as u32));
+            }
+            TypedExpressionKind::Tuple(elements) => {
+                for element in elements {
+                    self.compile_expression(element)?;
+                }
+                self.emit(Instruction::Tuple(elements.len() as u32));
+            }
+            TypedExpressionKind::Interpolated(parts) => {
+                for part in parts {
+                    self.compile_expression(part)?;
+                }
+                self.emit(Instruction::Concat(parts.len() as u32));
+            }
+            TypedExpressionKind::Range(start, end, step, inclusive) => {
+                self.compile_expression(start)?;
+                self.compile_expression(end)?;
+                match step {
+                    Some(step) => self.compile_expression(step)?,
+                    None => {
+                        let one = self.constant(Constant::Int(1));
+                        self.emit(one);
+                    }
+                }
+                self.mark(expr.span);
+                self.emit(if *inclusive { Instruction::RangeInclusive } else { Instruction::Range });
+            }
+            TypedExpressionKind::StructLiteral(name, fields) => self.compile_struct_literal(name, fields)?,
+            TypedExpressionKind::Variant(enum_name, variant, payload) => {
+                for field in payload {
+                    self.compile_expression(field)?;
+                }
+                // `Result` and other variants without a declaration are laid out by position
+                let layout = match self.compiler.declared_variant(self.module, enum_name, variant) {
+                    Some(layout) => layout,
+                    None => {
+                        let names = (0..payload.len()).map(|index| index.to_string()).collect();
+                        self.compiler.variant_layout(BUILTIN_MODULE, enum_name, variant, names)
+                    }
+                };
+                self.emit(Instruction::Variant(layout));
+            }
+            // Interface values dispatch on the runtime type of the value itself
+            TypedExpressionKind::Upcast(inner) => self.compile_expression(inner)?,
+            TypedExpressionKind::Closure(closure) => self.compile_closure(closure, expr.span)?,
+        }
+        Ok(())
+    }
+
+    fn compile_binary(&mut self, left: &TypedExpression, op: &BinaryOp, right: &TypedExpression, span: Option<Span>) -> Result<(), CodeGenError> {
+        // `&&` and `||` only evaluate their right operand when needed
+        if matches!(op, BinaryOp::And | BinaryOp::Or) {
+            self.compile_expression(left)?;
+            self.emit(Instruction::Dup);
+            self.mark(left.span);
+            let short_circuit = self.emit(if matches!(op, BinaryOp::And) {
+                Instruction::JumpIfFalse(0)
+            } else {
+                Instruction::JumpIfTrue(0)
+            });
+            self.emit(Instruction::Pop);
+            self.compile_expression(right)?;
+            self.patch(short_circuit);
+            return Ok(());
+        }
+
+        self.compile_expression(left)?;
+        self.compile_expression(right)?;
+        let instruction = match op {
+            BinaryOp::Add => Instruction::Add,
+            BinaryOp::Subtract => Instruction::Subtract,
+            BinaryOp::Multiply => Instruction::Multiply,
+            BinaryOp::Divide => Instruction::Divide,
+            BinaryOp::Modulo => Instruction::Modulo,
+            BinaryOp::Equal => Instruction::Equal,
+            BinaryOp::NotEqual => Instruction::NotEqual,
+            BinaryOp::Less => Instruction::Less,
+            BinaryOp::Greater => Instruction::Greater,
+            BinaryOp::LessEqual => Instruction::LessEqual,
+            BinaryOp::GreaterEqual => Instruction::GreaterEqual,
+            BinaryOp::BitwiseAnd => Instruction::BitAnd,
+            BinaryOp::BitwiseOr => Instruction::BitOr,
+            BinaryOp::BitwiseXor => Instruction::BitXor,
+            BinaryOp::LeftShift => Instruction::ShiftLeft,
+            BinaryOp::RightShift => Instruction::ShiftRight,
+            BinaryOp::And | BinaryOp::Or => unreachable!("handled above"),
+            BinaryOp::Coalesce => unreachable!("lowered to a match by the type checker"),
+        };
+        self.mark(span);
+        self.emit(instruction);
+        Ok(())
+    }
+
+    /// Fields are evaluated in the order they are written and pushed in the
+    /// order the type declares them
+    fn compile_struct_literal(&mut self, name: &str, fields: &[(String, TypedExpression)]) -> Result<(), CodeGenError> {
+        let names = fields.iter().map(|(field, _)| field.clone()).collect();
+        let (module, name) = self.compiler.type_key(self.module, name);
+        let layout = self.compiler.type_layout(module, name, names);
+        let declared = self.compiler.program.types[layout as usize].fields.clone();
+        if declared.iter().eq(fields.iter().map(|(field, _)| field)) {
+            for (_, value) in fields {
+                self.compile_expression(value)?;
+            }
+            self.emit(Instruction::Struct(layout));
+            return Ok(());
+        }
+
+        self.enter_scope();
+        let mut slots = HashMap::new();
+        for (field, value) in fields {
+            self.compile_expression(value)?;
+            let slot = self.temporary()?;
+            self.emit(Instruction::DefineLocal(slot));
+            slots.insert(field.as_str(), slot);
+        }
+        for field in &declared {
+            match slots.get(field.as_str()) {
+                Some(&slot) => self.emit(Instruction::LoadLocal(slot)),
+                None => self.emit(Instruction::Unit),
+            };
+        }
+        self.emit(Instruction::Struct(layout));
+        self.exit_scope();
+        Ok(())
+    }
+
+    /// Push the captures, then compile the body as a function of its own
+    fn compile_closure(&mut self, closure: &TypedClosure, span: Option<Span>) -> Result<(), CodeGenError> {
+        for capture in &closure.captures {
+            match (capture.by_reference, self.local(&capture.name), self.capture(&capture.name)) {
+                (true, Some(slot), _) => {
+                    self.emit(Instruction::CaptureLocal(slot));
+                }
+                (true, None, Some(index)) => {
+                    self.emit(Instruction::CaptureCapture(index));
+                }
+                _ => self.load(&capture.name, span)?,
+            }
+        }
+
+        let id = self.compiler.reserve_function();
+        let parameters: Vec<String> = closure.parameters.iter().map(|param| param.name.clone()).collect();
+        let captures = closure.captures.iter().map(|capture| capture.name.clone()).collect();
+        let function = FunctionCompiler::new(self.compiler, self.module, self.file.clone())
+            .compile("<closure>".to_string(), &parameters, captures, Body::Expression(&closure.body))?;
+        self.compiler.program.functions[id as usize] = function;
+        self.emit(Instruction::Closure(id));
+        Ok(())
+    }
+}
+
+fn error(kind: CodeGenErrorKind, span: Option<Span>) -> CodeGenError {
+    CodeGenError { span, kind }
+}
+
+fn argument_count(count: usize, span: Option<Span>) -> Result<u8, CodeGenError> {
+    u8::try_from(count).map_err(|_| {
+        error(CodeGenErrorKind::UnsupportedFeature { feature: "calls with more than 255 arguments".to_string() }, span)
+    })
+}
+
+fn field_names(fields: &[TypedField]) -> Vec<String> {
+    fields.iter().map(|field| field.name.clone()).collect()
+}
+
+/// Names of the variables closures in `body` capture by reference
+fn captured_by_reference(body: Body) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut collect = |expr: &TypedExpression| {
+        if let TypedExpressionKind::Closure(closure) = &expr.kind {
+            names.extend(closure.captures.iter().filter(|capture| capture.by_reference).map(|capture| capture.name.clone()));
+        }
+    };
+    match body {
+        Body::Block(block) => visit_block(block, &mut collect),
+        Body::Expression(expr) => visit_expression(expr, &mut collect),
+    }
+    names
+}
+
+fn visit_block(block: &TypedBlock, f: &mut impl FnMut(&TypedExpression)) {
+    for stmt in &block.statements {
+        visit_statement(stmt, f);
+    }
+}
+
+fn visit_statement(stmt: &TypedStatement, f: &mut impl FnMut(&TypedExpression)) {
+    match &stmt.kind {
+        TypedStatementKind::Expression(expr)
+        | TypedStatementKind::Const(_, _, expr)
+        | TypedStatementKind::Go(expr)
+        | TypedStatementKind::Let(_, _, Some(expr))
+        | TypedStatementKind::Destructure(_, expr)
+        | TypedStatementKind::Return(Some(expr))
+        | TypedStatementKind::Break(Some(expr)) => visit_expression(expr, f),
+        TypedStatementKind::Assignment(target, value) => {
+            visit_expression(target, f);
+            visit_expression(value, f);
+        }
+        TypedStatementKind::If(cond, then_block, else_block) => {
+            visit_expression(cond, f);
+            visit_block(then_block, f);
+            if let Some(else_block) = else_block {
+                visit_block(else_block, f);
+            }
+        }
+        TypedStatementKind::While(cond, body) | TypedStatementKind::For(_, cond, body) => {
+            visit_expression(cond, f);
+            visit_block(body, f);
+        }
+        TypedStatementKind::Match(scrutinee, arms) => visit_match(scrutinee, arms, f),
+        TypedStatementKind::Let(_, _, None)
+        | TypedStatementKind::Return(None)
+        | TypedStatementKind::Break(None)
+        | TypedStatementKind::Continue => {}
+    }
+}
+
+fn visit_match(scrutinee: &TypedExpression, arms: &[TypedMatchArm], f: &mut impl FnMut(&TypedExpression)) {
+    visit_expression(scrutinee, f);
+    for arm in arms {
+        if let Some(guard) = &arm.guard {
+            visit_expression(guard, f);
+        }
+        visit_block(&arm.body, f);
+    }
+}
+
+fn visit_expression(expr: &TypedExpression, f: &mut impl FnMut(&TypedExpression)) {
+    f(expr);
+    match &expr.kind {
+        TypedExpressionKind::Binary(left, _, right) | TypedExpressionKind::Index(left, right) => {
+            visit_expression(left, f);
+            visit_expression(right, f);
+        }
+        TypedExpressionKind::Unary(_, inner) | TypedExpressionKind::Field(inner, _) | TypedExpressionKind::Upcast(inner) => {
+            visit_expression(inner, f);
+        }
+        TypedExpressionKind::Call(callee, args) => {
+            visit_expression(callee, f);
+            for arg in args {
+                visit_expression(arg, f);
+            }
+        }
+        TypedExpressionKind::Match(scrutinee, arms) => visit_match(scrutinee, arms, f),
+        TypedExpressionKind::If(cond, then_block, else_block) => {
+            visit_expression(cond, f);
+            visit_block(then_block, f);
+            if let Some(else_block) = else_block {
+                visit_block(else_block, f);
+            }
+        }
+        TypedExpressionKind::Block(block) => visit_block(block, f),
+        TypedExpressionKind::Array(elements)
+        | TypedExpressionKind::Tuple(elements)
+        | TypedExpressionKind::Interpolated(elements)
+        | TypedExpressionKind::Variant(_, _, elements) => {
+            for element in elements {
+                visit_expression(element, f);
+            }
+        }
+        TypedExpressionKind::Map(pairs) => {
+            for (key, value) in pairs {
+                visit_expression(key, f);
+                visit_expression(value, f);
+            }
+        }
+        TypedExpressionKind::Range(start, end, step, _) => {
+            visit_expression(start, f);
+            visit_expression(end, f);
+            if let Some(step) = step {
+                visit_expression(step, f);
+            }
+        }
+        TypedExpressionKind::StructLiteral(_, fields) => {
+            for (_, value) in fields {
+                visit_expression(value, f);
+            }
+        }
+        TypedExpressionKind::Closure(closure) => visit_expression(&closure.body, f),
+        TypedExpressionKind::Literal(_) | TypedExpressionKind::Identifier(_) | TypedExpressionKind::Instantiate(_, _) => {}
+    }
+}
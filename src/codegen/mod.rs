@@ -10,6 +10,8 @@ use std::collections::HashMap;
 pub mod wasm;
 pub mod js_interop;
 pub mod wasm_optimizations;
+pub mod bytecode;
+pub mod bytecode_compiler;
 
 #[cfg(feature = "llvm")]
 use inkwell::context::Context;
@@ -18,9 +20,9 @@ use inkwell::module::Module;
 #[cfg(feature = "llvm")]
 use inkwell::builder::Builder;
 #[cfg(feature = "llvm")]
-use inkwell::values::{FunctionValue, BasicValueEnum, BasicValue, PointerValue, IntValue, FloatValue};
+use inkwell::values::{FunctionValue, BasicValueEnum, BasicValue, PointerValue, IntValue, FloatValue, GlobalValue};
 #[cfg(feature = "llvm")]
-use inkwell::types::{BasicTypeEnum, FunctionType, BasicType};
+use inkwell::types::{BasicTypeEnum, FunctionType, BasicType, StructType};
 #[cfg(feature = "llvm")]
 use inkwell::{IntPredicate, FloatPredicate, AddressSpace};
 
@@ -30,6 +32,29 @@ pub trait CodeGenerator {
     fn generate(&mut self, program: TypedProgram) -> Result<String, CodeGenError>;
 }
 
+/// Step of a range when it is a nonzero integer literal, or 1 when omitted.
+/// Loops over ranges with a constant step know which way they count.
+pub(crate) fn constant_step(step: Option<&TypedExpression>) -> Option<i64> {
+    let Some(step) = step else {
+        return Some(1);
+    };
+    let value = match &step.kind {
+        TypedExpressionKind::Literal(Literal::Integer(value)) => *value,
+        TypedExpressionKind::Unary(UnaryOp::Minus, operand) => match &operand.kind {
+            TypedExpressionKind::Literal(Literal::Integer(value)) => value.checked_neg()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    (value != 0).then_some(value)
+}
+
+/// The last loop counter value that `step` can be added to without
+/// overflowing
+pub(crate) fn last_before_overflow(step: i64) -> i64 {
+    if step > 0 { i64::MAX - step } else { i64::MIN - step }
+}
+
 /// LLVM-based code generator for Flux
 #[cfg(feature = "llvm")]
 pub struct LLVMCodeGenerator<'ctx> {
@@ -39,6 +64,9 @@ pub struct LLVMCodeGenerator<'ctx> {
     function_table: HashMap<String, FunctionValue<'ctx>>,
     current_function: Option<FunctionValue<'ctx>>,
     variable_table: HashMap<String, PointerValue<'ctx>>,
+    interfaces: HashMap<String, TypedInterface>,
+    vtables: HashMap<(String, String), GlobalValue<'ctx>>,
+    closure_counter: u32,
 }
 
 /// Stub code generator when LLVM is not available
@@ -59,6 +87,9 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
             function_table: HashMap::new(),
             current_function: None,
             variable_table: HashMap::new(),
+            interfaces: HashMap::new(),
+            vtables: HashMap::new(),
+            closure_counter: 0,
         }
     }
     
@@ -94,6 +125,16 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
                 // For now, use i1 as a placeholder when we need a BasicTypeEnum
                 Ok(self.context.bool_type().into())
             }
+            Type::Named(name) if self.interfaces.contains_key(name) => {
+                Ok(self.interface_value_type().into())
+            }
+            Type::Named(_) => {
+                // Objects are passed by reference
+                Ok(self.context.i8_type().ptr_type(AddressSpace::default()).into())
+            }
+            Type::Function(_, _) => {
+                Ok(self.closure_value_type().into())
+            }
             _ => Err(CodeGenError {
                 span: None,
                 kind: CodeGenErrorKind::UnsupportedFeature {
@@ -108,6 +149,35 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         matches!(flux_type, Type::Unit)
     }
     
+    /// Layout of an interface value: object pointer and vtable pointer
+    fn interface_value_type(&self) -> StructType<'ctx> {
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        self.context.struct_type(&[ptr_type.into(), ptr_type.into()], false)
+    }
+    
+    /// Layout of a closure value: lifted function pointer and environment pointer
+    fn closure_value_type(&self) -> StructType<'ctx> {
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        self.context.struct_type(&[ptr_type.into(), ptr_type.into()], false)
+    }
+    
+    /// Type of the function a lambda is lifted to: its parameters followed by
+    /// the environment pointer
+    fn closure_function_type(&self, params: &[Type], return_type: &Type) -> Result<FunctionType<'ctx>, CodeGenError> {
+        let mut param_types: Vec<BasicTypeEnum> = Vec::new();
+        for param in params {
+            param_types.push(self.flux_type_to_llvm(param)?);
+        }
+        param_types.push(self.context.i8_type().ptr_type(AddressSpace::default()).into());
+        let param_types: Vec<_> = param_types.into_iter().map(|t| t.into()).collect();
+        
+        if self.is_unit_type(return_type) {
+            Ok(self.context.void_type().fn_type(&param_types, false))
+        } else {
+            Ok(self.flux_type_to_llvm(return_type)?.fn_type(&param_types, false))
+        }
+    }
+    
     /// Create a function type from parameter and return types
     fn create_function_type(&self, params: &[Type], return_type: &Type) -> Result<FunctionType<'ctx>, CodeGenError> {
         let param_types: Result<Vec<BasicTypeEnum>, _> = params.iter()
@@ -135,9 +205,24 @@ impl<'ctx> CodeGenerator for LLVMCodeGenerator<'ctx> {
             }
         }
         
+        // Interfaces and vtables must exist before any function upcasts or dispatches
+        for item in &program.items {
+            if let TypedItem::Interface(interface) = item {
+                self.interfaces.insert(interface.name.clone(), interface.clone());
+            }
+        }
+        for item in &program.items {
+            if let TypedItem::Impl(impl_def) = item {
+                self.generate_impl(impl_def)?;
+            }
+        }
+        
         // Second pass: Generate all other items
         for item in &program.items {
             match item {
+                TypedItem::Function(func) if !func.type_params.is_empty() => {
+                    // Generic functions are monomorphized by the WebAssembly backend only
+                }
                 TypedItem::Function(func) => {
                     self.generate_function_impl(func)?;
                 }
@@ -153,6 +238,12 @@ impl<'ctx> CodeGenerator for LLVMCodeGenerator<'ctx> {
                 TypedItem::ExternFunction(extern_func) => {
                     self.generate_extern_function_impl(extern_func)?;
                 }
+                TypedItem::Enum(_) => {
+                    // Enums have no code of their own; variants are lowered where constructed
+                }
+                TypedItem::Interface(_) | TypedItem::Impl(_) => {
+                    // Generated before the other items
+                }
             }
         }
         
@@ -279,6 +370,15 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
             TypedExpressionKind::Field(obj, field_name) => {
                 self.generate_field_access(obj, field_name)
             }
+            TypedExpressionKind::Upcast(value) => {
+                self.generate_upcast(value, &expr.type_)
+            }
+            TypedExpressionKind::Closure(closure) => {
+                self.generate_closure(closure)
+            }
+            TypedExpressionKind::Interpolated(parts) => {
+                self.generate_interpolated(parts)
+            }
             TypedExpressionKind::Block(block) => {
                 if let Some(value) = self.generate_block(block)? {
                     Ok(value)
@@ -313,20 +413,23 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
             Literal::Float(f) => {
                 Ok(self.context.f64_type().const_float(*f).into())
             }
+            Literal::Byte(b) => {
+                Ok(self.context.i8_type().const_int(u64::from(*b), false).into())
+            }
             Literal::Boolean(b) => {
                 Ok(self.context.bool_type().const_int(if *b { 1 } else { 0 }, false).into())
             }
             Literal::Character(c) => {
                 Ok(self.context.i8_type().const_int(*c as u64, false).into())
             }
-            Literal::String(_s) => {
-                // String literals are more complex - for now, return a placeholder
-                Err(CodeGenError {
+            Literal::String(s) => {
+                let global = self.builder.build_global_string_ptr(s, "str").map_err(|e| CodeGenError {
                     span: None,
-                    kind: CodeGenErrorKind::UnsupportedFeature {
-                        feature: "String literals".to_string(),
+                    kind: CodeGenErrorKind::LlvmError {
+                        message: format!("Failed to build string literal: {:?}", e),
                     },
-                })
+                })?;
+                Ok(global.as_pointer_value().into())
             }
             Literal::Null => {
                 // Null pointer
@@ -786,7 +889,28 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
     
     /// Generate code for function calls
     fn generate_call(&mut self, func: &TypedExpression, args: &[TypedExpression]) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
-        // For now, only support direct function calls by name
+        // Calls through an interface value are dispatched via its vtable
+        if let TypedExpressionKind::Field(object, method) = &func.kind {
+            if let Type::Named(interface) = &object.type_ {
+                if self.interfaces.contains_key(interface) {
+                    return self.generate_dynamic_call(object, interface, method, args);
+                }
+            }
+        }
+        
+        // Function-typed values other than named functions and methods are closures
+        let is_closure = match &func.kind {
+            TypedExpressionKind::Identifier(name) => self.variable_table.contains_key(name),
+            TypedExpressionKind::Field(_, _) => false,
+            _ => true,
+        };
+        if is_closure {
+            if let Type::Function(params, return_type) = &func.type_ {
+                return self.generate_closure_call(func, params, return_type, args);
+            }
+        }
+        
+        // Otherwise, only support direct function calls by name
         if let TypedExpressionKind::Identifier(func_name) = &func.kind {
             if let Some(function) = self.function_table.get(func_name).copied() {
                 let mut arg_values = Vec::new();
@@ -1087,139 +1211,126 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         Ok(None)
     }
     
-    /// Generate code for for loops (simplified implementation)
+    /// Generate a counted loop over a range literal. The loop variable moves
+    /// from the start by the step until it passes the end; which way it
+    /// counts is known up front unless the step is only known at run time.
     fn generate_for_loop(&mut self, var: &str, iter: &TypedExpression, body: &TypedBlock) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
-        // For now, implement a simple for loop as a while loop
-        // In a complete implementation, this would handle iterators properly
-        
-        // This is a placeholder implementation that treats the iterator as a range
-        // For example: for i in 0..10 would be implemented as:
-        // let i = 0; while i < 10 { body; i = i + 1; }
-        
+        let TypedExpressionKind::Range(start, end, step, inclusive) = &iter.kind else {
+            return Err(CodeGenError {
+                span: iter.span,
+                kind: CodeGenErrorKind::UnsupportedFeature {
+                    feature: format!("Iterating over {}", iter.type_),
+                },
+            });
+        };
         let current_function = self.current_function.ok_or_else(|| CodeGenError {
             span: None,
             kind: CodeGenErrorKind::InternalError {
                 message: "No current function for for loop".to_string(),
             },
         })?;
+        let llvm_error = |e: inkwell::builder::BuilderError| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::LlvmError {
+                message: format!("Failed to build for loop: {:?}", e),
+            },
+        };
+        let loop_var_type = self.context.i64_type();
+        
+        let start_val = self.generate_expression(start)?.into_int_value();
+        let end_val = self.generate_expression(end)?.into_int_value();
+        let constant = constant_step(step.as_deref());
+        let step_val = match (constant, step) {
+            (Some(value), _) => loop_var_type.const_int(value as u64, true),
+            (None, Some(step)) => self.generate_expression(step)?.into_int_value(),
+            (None, None) => loop_var_type.const_int(1, false),
+        };
+        
+        // A zero step would never reach the end
+        if constant.is_none() {
+            let zero_step_bb = self.context.append_basic_block(current_function, "zerostep");
+            let checked_bb = self.context.append_basic_block(current_function, "forinit");
+            let is_zero = self.builder.build_int_compare(IntPredicate::EQ, step_val, loop_var_type.const_zero(), "iszero")
+                .map_err(llvm_error)?;
+            self.builder.build_conditional_branch(is_zero, zero_step_bb, checked_bb).map_err(llvm_error)?;
+            
+            self.builder.position_at_end(zero_step_bb);
+            let trap = self.module.get_function("llvm.trap").unwrap_or_else(|| {
+                self.module.add_function("llvm.trap", self.context.void_type().fn_type(&[], false), None)
+            });
+            self.builder.build_call(trap, &[], "").map_err(llvm_error)?;
+            self.builder.build_unreachable().map_err(llvm_error)?;
+            
+            self.builder.position_at_end(checked_bb);
+        }
         
-        // Create basic blocks
         let loop_cond_bb = self.context.append_basic_block(current_function, "forcond");
         let loop_body_bb = self.context.append_basic_block(current_function, "forbody");
         let loop_end_bb = self.context.append_basic_block(current_function, "forend");
         
-        // For simplicity, assume iter is a literal integer representing the upper bound
-        // and start from 0
-        let iter_val = self.generate_expression(iter)?;
-        
-        // Create loop variable
-        let loop_var_type = self.context.i64_type();
-        let loop_var_alloca = self.builder.build_alloca(loop_var_type, var)
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to build alloca for loop variable: {:?}", e),
-                },
-            })?;
-        
-        // Initialize loop variable to 0
-        let zero = self.context.i64_type().const_int(0, false);
-        self.builder.build_store(loop_var_alloca, zero)
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to initialize loop variable: {:?}", e),
-                },
-            })?;
-        
-        // Add to variable table
-        self.variable_table.insert(var.to_string(), loop_var_alloca);
+        let loop_var_alloca = self.builder.build_alloca(loop_var_type, var).map_err(llvm_error)?;
+        self.builder.build_store(loop_var_alloca, start_val).map_err(llvm_error)?;
+        let shadowed = self.variable_table.insert(var.to_string(), loop_var_alloca);
+        self.builder.build_unconditional_branch(loop_cond_bb).map_err(llvm_error)?;
         
-        // Branch to condition check
-        self.builder.build_unconditional_branch(loop_cond_bb)
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to build branch to for condition: {:?}", e),
-                },
-            })?;
-        
-        // Generate condition check (i < iter_val)
+        // Leave once the counter has passed the end
         self.builder.position_at_end(loop_cond_bb);
-        let current_val = self.builder.build_load(loop_var_type, loop_var_alloca, "loopvar")
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to load loop variable: {:?}", e),
-                },
-            })?;
-        
-        let cond_val = self.builder.build_int_compare(
-            IntPredicate::SLT,
-            current_val.into_int_value(),
-            iter_val.into_int_value(),
-            "forcond"
-        ).map_err(|e| CodeGenError {
-            span: None,
-            kind: CodeGenErrorKind::LlvmError {
-                message: format!("Failed to build for loop condition: {:?}", e),
-            },
-        })?;
-        
-        self.builder.build_conditional_branch(cond_val, loop_body_bb, loop_end_bb)
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to build conditional branch: {:?}", e),
-                },
-            })?;
+        let current_val = self.builder.build_load(loop_var_type, loop_var_alloca, "loopvar").map_err(llvm_error)?
+            .into_int_value();
+        let (up, down) = if *inclusive {
+            (IntPredicate::SLE, IntPredicate::SGE)
+        } else {
+            (IntPredicate::SLT, IntPredicate::SGT)
+        };
+        let cond_val = match constant {
+            Some(value) => {
+                let predicate = if value > 0 { up } else { down };
+                self.builder.build_int_compare(predicate, current_val, end_val, "forcond").map_err(llvm_error)?
+            }
+            None => {
+                let counting_up = self.builder.build_int_compare(up, current_val, end_val, "countup").map_err(llvm_error)?;
+                let counting_down = self.builder.build_int_compare(down, current_val, end_val, "countdown").map_err(llvm_error)?;
+                let positive = self.builder.build_int_compare(IntPredicate::SGT, step_val, loop_var_type.const_zero(), "positive")
+                    .map_err(llvm_error)?;
+                self.builder.build_select(positive, counting_up, counting_down, "forcond").map_err(llvm_error)?
+                    .into_int_value()
+            }
+        };
+        self.builder.build_conditional_branch(cond_val, loop_body_bb, loop_end_bb).map_err(llvm_error)?;
         
-        // Generate loop body
         self.builder.position_at_end(loop_body_bb);
         self.generate_block(body)?;
         
-        // Increment loop variable
-        let current_val = self.builder.build_load(loop_var_type, loop_var_alloca, "loopvar")
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to load loop variable for increment: {:?}", e),
-                },
-            })?;
-        
-        let one = self.context.i64_type().const_int(1, false);
-        let incremented = self.builder.build_int_add(current_val.into_int_value(), one, "inc")
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to increment loop variable: {:?}", e),
-                },
-            })?;
-        
-        self.builder.build_store(loop_var_alloca, incremented)
-            .map_err(|e| CodeGenError {
-                span: None,
-                kind: CodeGenErrorKind::LlvmError {
-                    message: format!("Failed to store incremented loop variable: {:?}", e),
-                },
-            })?;
-        
-        // Branch back to condition if no terminator
+        // Step the counter and branch back to the condition if no terminator
         if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
-            self.builder.build_unconditional_branch(loop_cond_bb)
-                .map_err(|e| CodeGenError {
-                    span: None,
-                    kind: CodeGenErrorKind::LlvmError {
-                        message: format!("Failed to build branch back to condition: {:?}", e),
-                    },
-                })?;
+            let current_val = self.builder.build_load(loop_var_type, loop_var_alloca, "loopvar").map_err(llvm_error)?
+                .into_int_value();
+            let stepped = self.builder.build_int_add(current_val, step_val, "step").map_err(llvm_error)?;
+            self.builder.build_store(loop_var_alloca, stepped).map_err(llvm_error)?;
+            
+            // Leave rather than wrap around when the step overflows, as at
+            // an end of `i64::MAX`: the sum then moves against the step
+            let wrapped_up = self.builder.build_int_compare(IntPredicate::SLT, stepped, current_val, "wrappedup").map_err(llvm_error)?;
+            let wrapped_down = self.builder.build_int_compare(IntPredicate::SGT, stepped, current_val, "wrappeddown").map_err(llvm_error)?;
+            let wrapped = match constant {
+                Some(value) => if value > 0 { wrapped_up } else { wrapped_down },
+                None => {
+                    let positive = self.builder.build_int_compare(IntPredicate::SGT, step_val, loop_var_type.const_zero(), "positive")
+                        .map_err(llvm_error)?;
+                    self.builder.build_select(positive, wrapped_up, wrapped_down, "wrapped").map_err(llvm_error)?
+                        .into_int_value()
+                }
+            };
+            self.builder.build_conditional_branch(wrapped, loop_end_bb, loop_cond_bb).map_err(llvm_error)?;
         }
         
-        // Position at loop end
         self.builder.position_at_end(loop_end_bb);
         
-        // Remove loop variable from table
-        self.variable_table.remove(var);
+        // The loop variable goes out of scope
+        match shadowed {
+            Some(outer) => self.variable_table.insert(var.to_string(), outer),
+            None => self.variable_table.remove(var),
+        };
         
         Ok(None)
     }
@@ -1542,9 +1653,6 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
     
     /// Generate class method
     fn generate_class_method(&mut self, class_def: &TypedClass, method: &TypedMethod) -> Result<(), CodeGenError> {
-        // Clear variable table for new method
-        self.variable_table.clear();
-        
         // Create class type for 'self' parameter
         let mut field_types = Vec::new();
         let vtable_ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
@@ -1558,38 +1666,23 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         let class_type = self.context.struct_type(&field_types, false);
         let class_ptr_type = class_type.ptr_type(AddressSpace::default());
         
-        // Extract parameter types
-        let mut param_types = Vec::new();
-        
-        // Add 'self' parameter if not static
-        if !method.is_static {
-            param_types.push(class_ptr_type.into());
-        }
-        
-        // Add method parameters
-        for param in &method.parameters {
-            let param_type = self.flux_type_to_llvm(&param.type_)?;
-            param_types.push(param_type);
-        }
-        
-        // Create method function type
         let method_name = format!("{}_{}", class_def.name, method.name);
-        let fn_type = self.create_function_type(
-            &method.parameters.iter().map(|p| p.type_.clone()).collect::<Vec<_>>(),
-            &method.return_type
-        )?;
-        
-        // For methods, we need to manually create the function type with self parameter
-        let actual_fn_type = if self.is_unit_type(&method.return_type) {
-            self.context.void_type().fn_type(&param_types, false)
-        } else {
-            let ret_type = self.flux_type_to_llvm(&method.return_type)?;
-            ret_type.fn_type(&param_types, false)
-        };
+        let receiver_type = if method.is_static { None } else { Some(class_ptr_type.into()) };
+        self.generate_method_function(&method_name, receiver_type, method)?;
+        Ok(())
+    }
+    
+    /// Generate the function for a method, taking the receiver (if any) as the
+    /// first parameter
+    fn generate_method_function(&mut self, method_name: &str, receiver_type: Option<BasicTypeEnum<'ctx>>, method: &TypedMethod) -> Result<FunctionValue<'ctx>, CodeGenError> {
+        // Clear variable table for new method
+        self.variable_table.clear();
+        
+        let fn_type = self.method_function_type(receiver_type, &method.parameters, &method.return_type)?;
         
         // Create method function
-        let function = self.module.add_function(&method_name, actual_fn_type, None);
-        self.function_table.insert(method_name.clone(), function);
+        let function = self.module.add_function(method_name, fn_type, None);
+        self.function_table.insert(method_name.to_string(), function);
         self.current_function = Some(function);
         
         // Create entry basic block
@@ -1599,8 +1692,8 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         let mut param_index = 0;
         
         // Create alloca for 'self' parameter if not static
-        if !method.is_static {
-            let self_alloca = self.builder.build_alloca(class_ptr_type, "self")
+        if let Some(receiver_type) = receiver_type {
+            let self_alloca = self.builder.build_alloca(receiver_type, "self")
                 .map_err(|e| CodeGenError {
                     span: None,
                     kind: CodeGenErrorKind::LlvmError {
@@ -1663,9 +1756,358 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         }
         
         self.current_function = None;
+        Ok(function)
+    }
+    
+    /// Function type of a method with an optional receiver parameter
+    fn method_function_type(&self, receiver_type: Option<BasicTypeEnum<'ctx>>, parameters: &[TypedParameter], return_type: &Type) -> Result<FunctionType<'ctx>, CodeGenError> {
+        let mut param_types: Vec<BasicTypeEnum<'ctx>> = receiver_type.into_iter().collect();
+        for param in parameters {
+            param_types.push(self.flux_type_to_llvm(&param.type_)?);
+        }
+        let param_types: Vec<_> = param_types.into_iter().map(|t| t.into()).collect();
+        
+        if self.is_unit_type(return_type) {
+            Ok(self.context.void_type().fn_type(&param_types, false))
+        } else {
+            let ret_type = self.flux_type_to_llvm(return_type)?;
+            Ok(ret_type.fn_type(&param_types, false))
+        }
+    }
+    
+    /// Generate the methods of an impl block and its vtable: a constant array
+    /// of method pointers in the order the interface declares them
+    fn generate_impl(&mut self, impl_def: &TypedImpl) -> Result<(), CodeGenError> {
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        
+        let mut slots = Vec::new();
+        for method in &impl_def.methods {
+            let method_name = format!("{}_{}_{}", impl_def.type_name, impl_def.interface, method.name);
+            let function = self.generate_method_function(&method_name, Some(ptr_type.into()), method)?;
+            slots.push(function.as_global_value().as_pointer_value());
+        }
+        
+        let vtable_name = format!("{}_{}_vtable", impl_def.type_name, impl_def.interface);
+        let vtable = self.module.add_global(ptr_type.array_type(slots.len() as u32), None, &vtable_name);
+        vtable.set_initializer(&ptr_type.const_array(&slots));
+        vtable.set_constant(true);
+        
+        self.vtables.insert((impl_def.type_name.clone(), impl_def.interface.clone()), vtable);
         Ok(())
     }
     
+    /// Pair an object with the vtable of its type for the interface it is used as
+    fn generate_upcast(&mut self, value: &TypedExpression, interface_type: &Type) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let vtable = match (&value.type_, interface_type) {
+            (Type::Named(type_name), Type::Named(interface)) => {
+                self.vtables.get(&(type_name.clone(), interface.clone())).copied()
+            }
+            _ => None,
+        }.ok_or_else(|| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::InternalError {
+                message: format!("No vtable for {} as {}", value.type_, interface_type),
+            },
+        })?;
+        
+        let object = self.generate_expression(value)?;
+        let interface_value = self.builder.build_insert_value(self.interface_value_type().get_undef(), object, 0, "object")
+            .map_err(|e| CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::LlvmError {
+                    message: format!("Failed to build interface value: {:?}", e),
+                },
+            })?;
+        let interface_value = self.builder.build_insert_value(interface_value, vtable.as_pointer_value(), 1, "interface")
+            .map_err(|e| CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::LlvmError {
+                    message: format!("Failed to build interface value: {:?}", e),
+                },
+            })?;
+        
+        Ok(interface_value.into_struct_value().into())
+    }
+    
+    /// Call an interface method by loading its pointer from the receiver's vtable
+    fn generate_dynamic_call(&mut self, object: &TypedExpression, interface: &str, method: &str, args: &[TypedExpression]) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let (slot, signature) = self.interfaces.get(interface)
+            .and_then(|i| i.methods.iter().enumerate().find(|(_, m)| m.name == method))
+            .map(|(slot, signature)| (slot, signature.clone()))
+            .ok_or_else(|| CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::InternalError {
+                    message: format!("Interface {} has no method {}", interface, method),
+                },
+            })?;
+        
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        let interface_value = self.generate_expression(object)?.into_struct_value();
+        let llvm_error = |e: inkwell::builder::BuilderError| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::LlvmError {
+                message: format!("Failed to build dynamic dispatch: {:?}", e),
+            },
+        };
+        
+        let receiver = self.builder.build_extract_value(interface_value, 0, "receiver").map_err(llvm_error)?;
+        let vtable = self.builder.build_extract_value(interface_value, 1, "vtable").map_err(llvm_error)?
+            .into_pointer_value();
+        let slot_index = self.context.i64_type().const_int(slot as u64, false);
+        let slot_ptr = unsafe { self.builder.build_in_bounds_gep(ptr_type, vtable, &[slot_index], "slot") }
+            .map_err(llvm_error)?;
+        let method_ptr = self.builder.build_load(ptr_type, slot_ptr, "method").map_err(llvm_error)?
+            .into_pointer_value();
+        
+        let mut arg_values = vec![receiver.into()];
+        for arg in args {
+            arg_values.push(self.generate_expression(arg)?.into());
+        }
+        
+        let fn_type = self.method_function_type(Some(ptr_type.into()), &signature.parameters, &signature.return_type)?;
+        let call = self.builder.build_indirect_call(fn_type, method_ptr, &arg_values, "dispatch").map_err(llvm_error)?;
+        
+        // Unit methods produce the unit placeholder
+        Ok(call.try_as_basic_value().left()
+            .unwrap_or_else(|| self.context.bool_type().const_int(0, false).into()))
+    }
+    
+    /// Format the parts of an interpolated string into a new buffer: `snprintf`
+    /// measures the text first, then writes it to a buffer from `malloc`
+    fn generate_interpolated(&mut self, parts: &[TypedExpression]) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let llvm_error = |e: inkwell::builder::BuilderError| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::LlvmError {
+                message: format!("Failed to build interpolated string: {:?}", e),
+            },
+        };
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        
+        let mut format = String::new();
+        let mut values: Vec<inkwell::values::BasicMetadataValueEnum<'ctx>> = Vec::new();
+        for part in parts {
+            if let TypedExpressionKind::Literal(Literal::String(text)) = &part.kind {
+                format.push_str(&text.replace('%', "%%"));
+                continue;
+            }
+            let value = self.generate_expression(part)?;
+            match &part.type_ {
+                Type::String => {
+                    format.push_str("%s");
+                    values.push(value.into());
+                }
+                Type::Int => {
+                    format.push_str("%lld");
+                    values.push(value.into());
+                }
+                Type::Float => {
+                    format.push_str("%g");
+                    values.push(value.into());
+                }
+                // Variadic arguments narrower than int are passed as int
+                Type::Byte | Type::Char => {
+                    format.push_str(if part.type_ == Type::Byte { "%u" } else { "%c" });
+                    let widened = self.builder.build_int_z_extend(value.into_int_value(), i32_type, "widened")
+                        .map_err(llvm_error)?;
+                    values.push(widened.into());
+                }
+                Type::Bool => {
+                    format.push_str("%s");
+                    let true_text = self.builder.build_global_string_ptr("true", "true").map_err(llvm_error)?;
+                    let false_text = self.builder.build_global_string_ptr("false", "false").map_err(llvm_error)?;
+                    let text = self.builder.build_select(
+                        value.into_int_value(),
+                        true_text.as_pointer_value(),
+                        false_text.as_pointer_value(),
+                        "booltext",
+                    ).map_err(llvm_error)?;
+                    values.push(text.into());
+                }
+                other => {
+                    return Err(CodeGenError {
+                        span: part.span,
+                        kind: CodeGenErrorKind::UnsupportedFeature {
+                            feature: format!("Interpolating a value of type {}", other),
+                        },
+                    });
+                }
+            }
+        }
+        
+        let snprintf = self.module.get_function("snprintf").unwrap_or_else(|| {
+            let fn_type = i32_type.fn_type(&[ptr_type.into(), i64_type.into(), ptr_type.into()], true);
+            self.module.add_function("snprintf", fn_type, None)
+        });
+        let malloc = self.module.get_function("malloc").unwrap_or_else(|| {
+            self.module.add_function("malloc", ptr_type.fn_type(&[i64_type.into()], false), None)
+        });
+        let format = self.builder.build_global_string_ptr(&format, "format").map_err(llvm_error)?;
+        
+        let mut args = vec![ptr_type.const_null().into(), i64_type.const_zero().into(), format.as_pointer_value().into()];
+        args.extend(values);
+        let length = self.builder.build_call(snprintf, &args, "length").map_err(llvm_error)?
+            .try_as_basic_value().left()
+            .map(|value| value.into_int_value())
+            .ok_or_else(|| CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::InternalError {
+                    message: "snprintf returned no value".to_string(),
+                },
+            })?;
+        let length = self.builder.build_int_s_extend(length, i64_type, "length").map_err(llvm_error)?;
+        let size = self.builder.build_int_add(length, i64_type.const_int(1, false), "size").map_err(llvm_error)?;
+        let buffer = self.builder.build_call(malloc, &[size.into()], "buffer").map_err(llvm_error)?
+            .try_as_basic_value().left()
+            .map(|value| value.into_pointer_value())
+            .ok_or_else(|| CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::InternalError {
+                    message: "malloc returned no value".to_string(),
+                },
+            })?;
+        
+        args[0] = buffer.into();
+        args[1] = size.into();
+        self.builder.build_call(snprintf, &args, "").map_err(llvm_error)?;
+        Ok(buffer.into())
+    }
+    
+    /// Lift a lambda to a function taking its environment as last parameter,
+    /// then build the environment and pair it with the function. By-value
+    /// captures are copied into the environment; by-reference captures store
+    /// the address of the enclosing function's variable.
+    fn generate_closure(&mut self, closure: &TypedClosure) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let llvm_error = |e: inkwell::builder::BuilderError| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::LlvmError {
+                message: format!("Failed to build closure: {:?}", e),
+            },
+        };
+        let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        
+        let mut env_fields = Vec::new();
+        for capture in &closure.captures {
+            let value_type = self.flux_type_to_llvm(&capture.type_)?;
+            env_fields.push(if capture.by_reference {
+                value_type.ptr_type(AddressSpace::default()).into()
+            } else {
+                value_type
+            });
+        }
+        let env_type = self.context.struct_type(&env_fields, false);
+        
+        let param_types: Vec<Type> = closure.parameters.iter().map(|p| p.type_.clone()).collect();
+        let fn_type = self.closure_function_type(&param_types, &closure.return_type)?;
+        let name = format!("closure.{}", self.closure_counter);
+        self.closure_counter += 1;
+        let lifted = self.module.add_function(&name, fn_type, None);
+        
+        // Generate the lifted function, then return to the enclosing one
+        let outer_block = self.builder.get_insert_block();
+        let outer_function = self.current_function.replace(lifted);
+        let outer_variables = std::mem::take(&mut self.variable_table);
+        
+        let entry_block = self.context.append_basic_block(lifted, "entry");
+        self.builder.position_at_end(entry_block);
+        for (i, param) in closure.parameters.iter().enumerate() {
+            let param_type = self.flux_type_to_llvm(&param.type_)?;
+            let alloca = self.builder.build_alloca(param_type, &param.name).map_err(llvm_error)?;
+            self.builder.build_store(alloca, lifted.get_nth_param(i as u32).unwrap()).map_err(llvm_error)?;
+            self.variable_table.insert(param.name.clone(), alloca);
+        }
+        let env_param = lifted.get_nth_param(closure.parameters.len() as u32).unwrap().into_pointer_value();
+        let env = self.builder.build_pointer_cast(env_param, env_type.ptr_type(AddressSpace::default()), "env")
+            .map_err(llvm_error)?;
+        for (i, capture) in closure.captures.iter().enumerate() {
+            let slot = self.builder.build_struct_gep(env_type, env, i as u32, &capture.name).map_err(llvm_error)?;
+            let loaded = self.builder.build_load(env_fields[i], slot, &capture.name).map_err(llvm_error)?;
+            let variable = if capture.by_reference {
+                loaded.into_pointer_value()
+            } else {
+                let alloca = self.builder.build_alloca(env_fields[i], &capture.name).map_err(llvm_error)?;
+                self.builder.build_store(alloca, loaded).map_err(llvm_error)?;
+                alloca
+            };
+            self.variable_table.insert(capture.name.clone(), variable);
+        }
+        
+        let body = self.generate_expression(&closure.body);
+        let body = body.and_then(|value| {
+            if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                if self.is_unit_type(&closure.return_type) {
+                    self.builder.build_return(None).map_err(llvm_error)?;
+                } else {
+                    self.builder.build_return(Some(&value)).map_err(llvm_error)?;
+                }
+            }
+            Ok(())
+        });
+        
+        self.variable_table = outer_variables;
+        self.current_function = outer_function;
+        if let Some(block) = outer_block {
+            self.builder.position_at_end(block);
+        }
+        body?;
+        
+        // Environment: one slot per capture
+        let env = self.builder.build_malloc(env_type, "env").map_err(llvm_error)?;
+        for (i, capture) in closure.captures.iter().enumerate() {
+            let variable = *self.variable_table.get(&capture.name).ok_or_else(|| CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::UnsupportedFeature {
+                    feature: format!("Undefined variable: {}", capture.name),
+                },
+            })?;
+            let value: BasicValueEnum = if capture.by_reference {
+                variable.into()
+            } else {
+                self.builder.build_load(env_fields[i], variable, &capture.name).map_err(llvm_error)?
+            };
+            let slot = self.builder.build_struct_gep(env_type, env, i as u32, &capture.name).map_err(llvm_error)?;
+            self.builder.build_store(slot, value).map_err(llvm_error)?;
+        }
+        
+        let function_ptr = self.builder.build_pointer_cast(lifted.as_global_value().as_pointer_value(), ptr_type, "function")
+            .map_err(llvm_error)?;
+        let env_ptr = self.builder.build_pointer_cast(env, ptr_type, "env").map_err(llvm_error)?;
+        let value = self.builder.build_insert_value(self.closure_value_type().get_undef(), function_ptr, 0, "closure")
+            .map_err(llvm_error)?;
+        let value = self.builder.build_insert_value(value, env_ptr, 1, "closure").map_err(llvm_error)?;
+        
+        Ok(value.into_struct_value().into())
+    }
+    
+    /// Call a closure value: its function receives the environment as last argument
+    fn generate_closure_call(&mut self, func: &TypedExpression, params: &[Type], return_type: &Type, args: &[TypedExpression]) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let llvm_error = |e: inkwell::builder::BuilderError| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::LlvmError {
+                message: format!("Failed to build closure call: {:?}", e),
+            },
+        };
+        
+        let closure = self.generate_expression(func)?.into_struct_value();
+        let function_ptr = self.builder.build_extract_value(closure, 0, "function").map_err(llvm_error)?
+            .into_pointer_value();
+        let env = self.builder.build_extract_value(closure, 1, "env").map_err(llvm_error)?;
+        
+        let mut arg_values = Vec::new();
+        for arg in args {
+            arg_values.push(self.generate_expression(arg)?.into());
+        }
+        arg_values.push(env.into());
+        
+        let fn_type = self.closure_function_type(params, return_type)?;
+        let call = self.builder.build_indirect_call(fn_type, function_ptr, &arg_values, "call").map_err(llvm_error)?;
+        
+        // Unit closures produce the unit placeholder
+        Ok(call.try_as_basic_value().left()
+            .unwrap_or_else(|| self.context.bool_type().const_int(0, false).into()))
+    }
+    
     /// Generate constant implementation
     fn generate_const_impl(&mut self, const_def: &TypedConst) -> Result<(), CodeGenError> {
         // Generate global constant
@@ -2,6 +2,8 @@
 //! 
 //! Provides WebAssembly code generation for Flux programs with JavaScript interop support.
 
+#[cfg(feature = "wasm")]
+use crate::codegen::constant_step;
 use crate::error::{CodeGenError, CodeGenErrorKind};
 use crate::semantic::*;
 use crate::parser::ast::{Type, Literal, BinaryOp, UnaryOp};
@@ -12,6 +14,7 @@ use wasm_encoder::{
     Module, CodeSection, DataSection, ExportSection, FunctionSection, ImportSection,
     MemorySection, MemoryType, TypeSection, ValType, Instruction,
     FuncType, EntityType, GlobalSection, GlobalType, ExportKind,
+    TableSection, TableType, ElementSection, Elements, RefType,
 };
 
 /// WebAssembly code generator for Flux
@@ -39,6 +42,92 @@ pub struct WasmCodeGenerator {
     // Memory management
     memory_offset: u32,
     string_literals: HashMap<String, u32>,
+    
+    // Interfaces: an interface value is an i64 holding the vtable base (a
+    // function table index) in the high 32 bits and the object pointer in the
+    // low 32 bits
+    interfaces: HashMap<String, TypedInterface>,
+    vtable_bases: HashMap<(String, String), u32>,
+    vtable_entries: Vec<u32>,
+    method_indices: HashMap<(String, String), u32>,
+    
+    // Generics: each instantiation of a generic function is compiled
+    // separately, with its type arguments bound while its body is generated
+    generic_functions: HashMap<String, TypedFunction>,
+    late_bodies: Vec<LateBody>,
+    type_args: HashMap<String, Type>,
+    
+    // Closures: a closure value is a pointer to its environment, which holds
+    // the table index of the lifted lambda at offset 0 followed by one 8-byte
+    // slot per capture. Every function has a scratch i32 local for building
+    // environments.
+    closure_counter: u32,
+    scratch_local: u32,
+    
+    // Results: a pointer to a tag, 0 for `Ok` and 1 for `Err`, followed by
+    // the payload at offset 8. `?` returns an `Err` as is, so it needs the
+    // return type of the function being generated.
+    return_type: Type,
+    
+    // Loops: `let` bindings and loop variables get their locals before a
+    // body is generated, along with an end and a step local for each level
+    // of nested range loops. A loop is a block (`break`) around a loop
+    // around a block (`continue`); the depths of those blocks give the
+    // labels of `break` and `continue` inside the body.
+    range_locals: Vec<(u32, u32)>,
+    range_depth: usize,
+    block_depth: u32,
+    loop_labels: Vec<(u32, u32)>,
+}
+
+/// Bytes before the first capture slot of a closure environment
+#[cfg(feature = "wasm")]
+const CLOSURE_HEADER_SIZE: u32 = 8;
+
+/// Bytes per capture slot of a closure environment
+#[cfg(feature = "wasm")]
+const CLOSURE_SLOT_SIZE: u32 = 8;
+
+/// Offset of the payload in a result, after its tag
+#[cfg(feature = "wasm")]
+const RESULT_PAYLOAD_OFFSET: u32 = 8;
+
+/// Bytes allocated for a result
+#[cfg(feature = "wasm")]
+const RESULT_SIZE: u32 = 16;
+
+/// Address of the first heap allocation. String literals are stored below
+/// it, after the null address 0, as bytes ending in a 0 byte; strings built
+/// at run time are allocated on the heap the same way.
+pub const WASM_HEAP_BASE: u32 = 64 * 1024;
+
+/// Pages of linear memory, 64KB each, which hold the literals and the heap
+#[cfg(feature = "wasm")]
+const MEMORY_PAGES: u64 = 16;
+
+/// Function body waiting to be generated once every function is declared
+#[cfg(feature = "wasm")]
+enum PendingBody<'a> {
+    Function(&'a TypedFunction),
+    Method(&'a TypedMethod),
+    Dispatch(&'a TypedInterface, usize),
+}
+
+/// Function declared while other bodies were being generated; its body is
+/// generated after theirs
+#[cfg(feature = "wasm")]
+enum LateBody {
+    /// Instance of a generic function with its type arguments
+    Instance(String, HashMap<String, Type>),
+    /// Lambda lifted to a function taking its environment as last parameter,
+    /// with the type arguments bound where it was created
+    Closure(TypedClosure, HashMap<String, Type>),
+    /// Calls the lambda stored in the environment passed as last parameter
+    ClosureCall(u32, usize),
+    /// Joins the two strings passed into a new one
+    Concat,
+    /// Formats the integer passed as a new string in decimal
+    IntToString,
 }
 
 /// Stub WebAssembly code generator when WASM feature is not available
@@ -68,17 +157,37 @@ impl WasmCodeGenerator {
             function_index_counter: 0,
             global_index_counter: 0,
             
-            memory_offset: 0,
+            memory_offset: 8,
             string_literals: HashMap::new(),
+            
+            interfaces: HashMap::new(),
+            vtable_bases: HashMap::new(),
+            vtable_entries: Vec::new(),
+            method_indices: HashMap::new(),
+            
+            generic_functions: HashMap::new(),
+            late_bodies: Vec::new(),
+            type_args: HashMap::new(),
+            
+            closure_counter: 0,
+            scratch_local: 0,
+            
+            return_type: Type::Unit,
+            
+            range_locals: Vec::new(),
+            range_depth: 0,
+            block_depth: 0,
+            loop_labels: Vec::new(),
         };
         
-        // Set up basic memory (1 page = 64KB)
+        // Literals and the heap share one fixed-size memory
         generator.memory.memory(MemoryType {
-            minimum: 1,
-            maximum: Some(16), // 1MB max
+            minimum: MEMORY_PAGES,
+            maximum: Some(MEMORY_PAGES),
             memory64: false,
             shared: false,
         });
+        generator.exports.export("memory", ExportKind::Memory, 0);
         
         // Add JavaScript interop imports
         generator.add_js_imports();
@@ -128,9 +237,14 @@ impl WasmCodeGenerator {
         index
     }
     
+    /// Substitute the type arguments of the generic instance being generated
+    fn resolve_type(&self, flux_type: &Type) -> Type {
+        flux_type.substitute(&self.type_args)
+    }
+    
     /// Convert a Flux type to a WebAssembly value type
     fn flux_type_to_wasm(&self, flux_type: &Type) -> Result<ValType, CodeGenError> {
-        match flux_type {
+        match &self.resolve_type(flux_type) {
             Type::Int => Ok(ValType::I64),
             Type::Float => Ok(ValType::F64),
             Type::Bool => Ok(ValType::I32),
@@ -139,6 +253,11 @@ impl WasmCodeGenerator {
             Type::String => Ok(ValType::I32), // Pointer to string data
             Type::Array(_) => Ok(ValType::I32), // Pointer to array data
             Type::Nullable(_) => Ok(ValType::I32), // Pointer (null = 0)
+            Type::Result(_, _) => Ok(ValType::I32), // Pointer to tag and payload
+            Type::Named(name) if self.interfaces.contains_key(name) => Ok(ValType::I64), // Vtable base and object pointer
+            Type::Named(_) => Ok(ValType::I32), // Pointer to object data
+            Type::Generic(_, _) => Ok(ValType::I32), // Pointer to object data
+            Type::Function(_, _) => Ok(ValType::I32), // Pointer to closure environment
             Type::Unit => Err(CodeGenError {
                 span: None,
                 kind: CodeGenErrorKind::UnsupportedFeature {
@@ -156,19 +275,63 @@ impl WasmCodeGenerator {
     
     /// Check if a type is the unit type (void)
     fn is_unit_type(&self, flux_type: &Type) -> bool {
-        matches!(flux_type, Type::Unit)
+        matches!(self.resolve_type(flux_type), Type::Unit)
     }
     
     /// Generate WebAssembly module from typed program
     pub fn generate(&mut self, program: TypedProgram) -> Result<Vec<u8>, CodeGenError> {
-        // Generate all functions
+        // Constants become globals that function bodies refer to
+        for item in &program.items {
+            if let TypedItem::Const(const_def) = item {
+                self.generate_const(const_def)?;
+            }
+        }
+        
+        // Interface declarations fix the vtable slot of each method
+        for item in &program.items {
+            if let TypedItem::Interface(interface) = item {
+                self.interfaces.insert(interface.name.clone(), interface.clone());
+            }
+        }
+        
+        // Declare every function before generating any body so calls can refer
+        // to functions defined later in the program
+        let mut bodies = Vec::new();
         for item in &program.items {
             match item {
+                TypedItem::Function(func) if !func.type_params.is_empty() => {
+                    // Generic functions are compiled once per instantiation
+                    self.generic_functions.insert(func.name.clone(), func.clone());
+                }
                 TypedItem::Function(func) => {
-                    self.generate_function(func)?;
+                    let func_index = self.declare_function(&func.name, None, &func.parameters, &func.return_type)?;
+                    
+                    // Export main function if it exists
+                    if func.name == "main" {
+                        self.exports.export(&func.name, ExportKind::Func, func_index);
+                    }
+                    bodies.push(PendingBody::Function(func));
+                }
+                TypedItem::Impl(impl_def) => {
+                    // The impl's methods occupy consecutive table slots: its vtable
+                    let base = self.vtable_entries.len() as u32;
+                    self.vtable_bases.insert((impl_def.type_name.clone(), impl_def.interface.clone()), base);
+                    
+                    for method in &impl_def.methods {
+                        let name = format!("{}::{}::{}", impl_def.type_name, impl_def.interface, method.name);
+                        let func_index = self.declare_function(&name, Some(ValType::I32), &method.parameters, &method.return_type)?;
+                        self.vtable_entries.push(func_index);
+                        self.method_indices.entry((impl_def.type_name.clone(), method.name.clone())).or_insert(func_index);
+                        bodies.push(PendingBody::Method(method));
+                    }
                 }
-                TypedItem::Const(const_def) => {
-                    self.generate_const(const_def)?;
+                TypedItem::Interface(interface) => {
+                    // One dispatcher per method, called with the interface value as receiver
+                    for (slot, method) in interface.methods.iter().enumerate() {
+                        let name = format!("{}::{}", interface.name, method.name);
+                        self.declare_function(&name, Some(ValType::I64), &method.parameters, &method.return_type)?;
+                        bodies.push(PendingBody::Dispatch(interface, slot));
+                    }
                 }
                 _ => {
                     // Skip other items for now
@@ -176,66 +339,391 @@ impl WasmCodeGenerator {
             }
         }
         
+        // Bodies go in the code section in declaration order
+        for body in bodies {
+            match body {
+                PendingBody::Function(func) => self.generate_function_body(false, &func.parameters, &func.return_type, &func.body)?,
+                PendingBody::Method(method) => self.generate_function_body(true, &method.parameters, &method.return_type, &method.body)?,
+                PendingBody::Dispatch(interface, slot) => self.generate_dispatch_thunk(interface, slot)?,
+            }
+        }
+        
+        // Instances and closures are declared as the code using them is
+        // generated, and may in turn declare further functions
+        let mut next = 0;
+        while next < self.late_bodies.len() {
+            match &self.late_bodies[next] {
+                LateBody::Instance(name, bindings) => {
+                    let func = self.generic_functions[name].clone();
+                    self.type_args = bindings.clone();
+                    self.generate_function_body(false, &func.parameters, &func.return_type, &func.body)?;
+                }
+                LateBody::Closure(closure, bindings) => {
+                    let closure = closure.clone();
+                    self.type_args = bindings.clone();
+                    self.generate_closure_body(&closure)?;
+                }
+                &LateBody::ClosureCall(type_index, param_count) => {
+                    self.generate_closure_call_thunk(type_index, param_count);
+                }
+                LateBody::Concat => self.generate_concat_body(),
+                LateBody::IntToString => self.generate_int_to_string_body(),
+            }
+            next += 1;
+        }
+        self.type_args.clear();
+        
         // Build the final module
         self.build_module()
     }
     
-    /// Generate a function
-    fn generate_function(&mut self, func: &TypedFunction) -> Result<(), CodeGenError> {
+    /// Add a function to the function section and return its index. Methods
+    /// take their receiver as an extra first parameter.
+    fn declare_function(&mut self, name: &str, receiver: Option<ValType>, parameters: &[TypedParameter], return_type: &Type) -> Result<u32, CodeGenError> {
+        let param_types: Vec<Type> = parameters.iter().map(|param| param.type_.clone()).collect();
+        let type_index = self.signature_type(receiver, &param_types, return_type)?;
+        Ok(self.declare_function_of_type(name, type_index))
+    }
+    
+    /// Add the WebAssembly function type for a signature and return its index
+    fn signature_type(&mut self, receiver: Option<ValType>, param_types: &[Type], return_type: &Type) -> Result<u32, CodeGenError> {
         // Convert parameter types
-        let param_types: Result<Vec<ValType>, _> = func.parameters.iter()
-            .map(|p| self.flux_type_to_wasm(&p.type_))
-            .collect();
-        let param_types = param_types?;
+        let mut params: Vec<ValType> = receiver.into_iter().collect();
+        for param_type in param_types {
+            params.push(self.flux_type_to_wasm(param_type)?);
+        }
         
         // Convert return type
-        let return_types = if self.is_unit_type(&func.return_type) {
+        let return_types = if self.is_unit_type(return_type) {
             vec![]
         } else {
-            vec![self.flux_type_to_wasm(&func.return_type)?]
+            vec![self.flux_type_to_wasm(return_type)?]
         };
         
-        // Add function type
-        let type_index = self.add_function_type(&param_types, &return_types);
-        self.function_types.insert(func.name.clone(), type_index);
+        Ok(self.add_function_type(&params, &return_types))
+    }
+    
+    /// Add a function with an existing type to the function section and
+    /// return its index
+    fn declare_function_of_type(&mut self, name: &str, type_index: u32) -> u32 {
+        self.function_types.insert(name.to_string(), type_index);
         
         // Add function to function section
         self.functions.function(type_index);
         let func_index = self.function_index_counter;
-        self.function_indices.insert(func.name.clone(), func_index);
+        self.function_indices.insert(name.to_string(), func_index);
         self.function_index_counter += 1;
         
-        // Export main function if it exists
-        if func.name == "main" {
-            self.exports.export(&func.name, ExportKind::Func, func_index);
+        func_index
+    }
+    
+    /// Generate the body of a declared function; a method's receiver is local 0
+    fn generate_function_body(&mut self, has_receiver: bool, parameters: &[TypedParameter], return_type: &Type, body: &TypedBlock) -> Result<(), CodeGenError> {
+        // Set up local variable mapping
+        self.local_indices.clear();
+        self.return_type = return_type.clone();
+        let first_param = if has_receiver {
+            self.local_indices.insert("self".to_string(), 0);
+            1
+        } else {
+            0
+        };
+        for (i, param) in parameters.iter().enumerate() {
+            self.local_indices.insert(param.name.clone(), first_param + i as u32);
         }
+        self.scratch_local = first_param + parameters.len() as u32;
+        let mut locals = vec![(1, ValType::I32)];
+        self.range_locals.clear();
+        self.declare_locals(body, 0, &mut locals)?;
+        let mut function_body = wasm_encoder::Function::new(locals);
         
-        // Generate function body
-        let mut function_body = wasm_encoder::Function::new(vec![]); // No additional locals for now
+        // Generate function body instructions
+        self.generate_block_instructions(body, &mut function_body)?;
+        function_body.instruction(&Instruction::End);
         
-        // Set up local variable mapping
+        self.code.function(&function_body);
+        
+        Ok(())
+    }
+    
+    /// Generate the dispatcher for one interface method: split the interface
+    /// value into object pointer and vtable base, then call the implementation
+    /// at `base + slot` in the function table
+    fn generate_dispatch_thunk(&mut self, interface: &TypedInterface, slot: usize) -> Result<(), CodeGenError> {
+        let method = &interface.methods[slot];
+        
+        // Implementations take the object pointer as their receiver
+        let mut param_types = vec![ValType::I32];
+        for param in &method.parameters {
+            param_types.push(self.flux_type_to_wasm(&param.type_)?);
+        }
+        let return_types = if self.is_unit_type(&method.return_type) {
+            vec![]
+        } else {
+            vec![self.flux_type_to_wasm(&method.return_type)?]
+        };
+        let impl_type = self.add_function_type(&param_types, &return_types);
+        
+        let mut function = wasm_encoder::Function::new(vec![]);
+        
+        // Receiver: the low 32 bits
+        function.instruction(&Instruction::LocalGet(0));
+        function.instruction(&Instruction::I32WrapI64);
+        for i in 0..method.parameters.len() {
+            function.instruction(&Instruction::LocalGet(i as u32 + 1));
+        }
+        
+        // Table index: vtable base from the high 32 bits plus the slot
+        function.instruction(&Instruction::LocalGet(0));
+        function.instruction(&Instruction::I64Const(32));
+        function.instruction(&Instruction::I64ShrU);
+        function.instruction(&Instruction::I32WrapI64);
+        function.instruction(&Instruction::I32Const(slot as i32));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::CallIndirect { ty: impl_type, table: 0 });
+        function.instruction(&Instruction::End);
+        
+        self.code.function(&function);
+        
+        Ok(())
+    }
+    
+    /// Generate the function a lambda is lifted to. Its parameters are the
+    /// lambda's followed by the environment, whose captures are copied into
+    /// locals before the body runs.
+    fn generate_closure_body(&mut self, closure: &TypedClosure) -> Result<(), CodeGenError> {
         self.local_indices.clear();
-        for (i, param) in func.parameters.iter().enumerate() {
+        self.return_type = closure.return_type.clone();
+        for (i, param) in closure.parameters.iter().enumerate() {
             self.local_indices.insert(param.name.clone(), i as u32);
         }
+        let env_local = closure.parameters.len() as u32;
+        self.scratch_local = env_local + 1;
         
-        // Generate function body instructions
-        self.generate_block_instructions(&func.body, &mut function_body)?;
+        let mut locals = vec![(1, ValType::I32)];
+        let mut prologue = Vec::new();
+        for (slot, capture) in closure.captures.iter().enumerate() {
+            let val_type = self.flux_type_to_wasm(&capture.type_)?;
+            let local_index = self.scratch_local + 1 + slot as u32;
+            locals.push((1, val_type));
+            self.local_indices.insert(capture.name.clone(), local_index);
+            prologue.push((local_index, val_type, CLOSURE_HEADER_SIZE + CLOSURE_SLOT_SIZE * slot as u32));
+        }
+        self.range_locals.clear();
+        if let TypedExpressionKind::Block(body) = &closure.body.kind {
+            self.declare_locals(body, 0, &mut locals)?;
+        }
         
-        // Add return instruction if needed
-        if self.is_unit_type(&func.return_type) {
-            // Void function - just return
-            // No explicit return needed for void functions in WASM
-        } else {
-            // Non-void function should have a return value on stack
-            // The block generation should handle this
+        let mut function = wasm_encoder::Function::new(locals);
+        for (local_index, val_type, offset) in prologue {
+            function.instruction(&Instruction::LocalGet(env_local));
+            function.instruction(&load_instruction(val_type, offset));
+            function.instruction(&Instruction::LocalSet(local_index));
         }
+        self.generate_expression_instructions(&closure.body, &mut function)?;
+        function.instruction(&Instruction::End);
         
-        self.code.function(&function_body);
+        self.code.function(&function);
+        
+        Ok(())
+    }
+    
+    /// Generate the caller for closures of one signature: forward the
+    /// arguments and environment to the lambda whose table index is stored at
+    /// the start of the environment
+    fn generate_closure_call_thunk(&mut self, type_index: u32, param_count: usize) {
+        let mut function = wasm_encoder::Function::new(vec![]);
         
+        let env_local = param_count as u32;
+        for i in 0..=env_local {
+            function.instruction(&Instruction::LocalGet(i));
+        }
+        function.instruction(&Instruction::LocalGet(env_local));
+        function.instruction(&load_instruction(ValType::I32, 0));
+        function.instruction(&Instruction::CallIndirect { ty: type_index, table: 0 });
+        function.instruction(&Instruction::End);
+        
+        self.code.function(&function);
+    }
+    
+    /// Generate instructions for an interpolated string: each part becomes
+    /// a string, and the strings are joined from left to right
+    fn generate_interpolated_instructions(&mut self, parts: &[TypedExpression], function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        let Some((first, rest)) = parts.split_first() else {
+            let offset = self.add_string_literal("");
+            function.instruction(&Instruction::I32Const(offset as i32));
+            return Ok(());
+        };
+        self.generate_string_instructions(first, function)?;
+        for part in rest {
+            self.generate_string_instructions(part, function)?;
+            let concat = self.helper_index("<concat>", &[ValType::I32, ValType::I32], &[ValType::I32], LateBody::Concat);
+            function.instruction(&Instruction::Call(concat));
+        }
         Ok(())
     }
     
+    /// Generate instructions for a value converted to a string
+    fn generate_string_instructions(&mut self, value: &TypedExpression, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        match self.resolve_type(&value.type_) {
+            Type::String => {
+                self.generate_expression_instructions(value, function)?;
+            }
+            Type::Int | Type::Byte => {
+                self.generate_expression_instructions(value, function)?;
+                if value.type_ == Type::Byte {
+                    function.instruction(&Instruction::I64ExtendI32U);
+                }
+                let int_to_string = self.helper_index("<int to string>", &[ValType::I64], &[ValType::I32], LateBody::IntToString);
+                function.instruction(&Instruction::Call(int_to_string));
+            }
+            Type::Bool => {
+                let true_offset = self.add_string_literal("true");
+                let false_offset = self.add_string_literal("false");
+                function.instruction(&Instruction::I32Const(true_offset as i32));
+                function.instruction(&Instruction::I32Const(false_offset as i32));
+                self.generate_expression_instructions(value, function)?;
+                function.instruction(&Instruction::Select);
+            }
+            other => {
+                return Err(CodeGenError {
+                    span: value.span,
+                    kind: CodeGenErrorKind::UnsupportedFeature {
+                        feature: format!("Interpolating a value of type {} in WebAssembly", other),
+                    },
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Index of a runtime helper function, declaring it on first use
+    fn helper_index(&mut self, name: &str, params: &[ValType], results: &[ValType], body: LateBody) -> u32 {
+        if let Some(&func_index) = self.function_indices.get(name) {
+            return func_index;
+        }
+        let type_index = self.add_function_type(params, results);
+        let func_index = self.declare_function_of_type(name, type_index);
+        self.late_bodies.push(body);
+        func_index
+    }
+    
+    /// Body of `<concat>(a, b)`: copy both strings, without the first one's
+    /// final 0 byte, to a new allocation
+    fn generate_concat_body(&mut self) {
+        // Locals after the parameters: the two lengths and the result
+        let (a, b, a_len, b_len, result) = (0, 1, 2, 3, 4);
+        let mut function = wasm_encoder::Function::new(vec![(3, ValType::I32)]);
+        generate_strlen(a, a_len, &mut function);
+        generate_strlen(b, b_len, &mut function);
+        
+        function.instruction(&Instruction::LocalGet(a_len));
+        function.instruction(&Instruction::LocalGet(b_len));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::I32Const(1));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::Call(self.function_indices["js.malloc"]));
+        function.instruction(&Instruction::LocalSet(result));
+        
+        function.instruction(&Instruction::LocalGet(result));
+        function.instruction(&Instruction::LocalGet(a));
+        function.instruction(&Instruction::LocalGet(a_len));
+        function.instruction(&Instruction::MemoryCopy { src_mem: 0, dst_mem: 0 });
+        
+        // The second string is copied with its final 0 byte
+        function.instruction(&Instruction::LocalGet(result));
+        function.instruction(&Instruction::LocalGet(a_len));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::LocalGet(b));
+        function.instruction(&Instruction::LocalGet(b_len));
+        function.instruction(&Instruction::I32Const(1));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::MemoryCopy { src_mem: 0, dst_mem: 0 });
+        
+        function.instruction(&Instruction::LocalGet(result));
+        function.instruction(&Instruction::End);
+        self.code.function(&function);
+    }
+    
+    /// Body of `<int to string>(n)`: write the digits from the end of a new
+    /// allocation backwards. The value is kept negative while dividing, so
+    /// `i64::MIN` needs no special case.
+    fn generate_int_to_string_body(&mut self) {
+        // Locals after the parameter: the buffer, the write position and
+        // whether the value is negative
+        let (n, buffer, position, negative) = (0, 1, 2, 3);
+        let mut function = wasm_encoder::Function::new(vec![(3, ValType::I32)]);
+        let byte = wasm_encoder::MemArg { offset: 0, align: 0, memory_index: 0 };
+        
+        // A sign, 19 digits and the final 0 byte
+        function.instruction(&Instruction::I32Const(24));
+        function.instruction(&Instruction::Call(self.function_indices["js.malloc"]));
+        function.instruction(&Instruction::LocalSet(buffer));
+        function.instruction(&Instruction::I32Const(23));
+        function.instruction(&Instruction::LocalSet(position));
+        function.instruction(&Instruction::LocalGet(buffer));
+        function.instruction(&Instruction::LocalGet(position));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::I32Const(0));
+        function.instruction(&Instruction::I32Store8(byte));
+        
+        function.instruction(&Instruction::LocalGet(n));
+        function.instruction(&Instruction::I64Const(0));
+        function.instruction(&Instruction::I64LtS);
+        function.instruction(&Instruction::LocalTee(negative));
+        function.instruction(&Instruction::I32Eqz);
+        function.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+        function.instruction(&Instruction::I64Const(0));
+        function.instruction(&Instruction::LocalGet(n));
+        function.instruction(&Instruction::I64Sub);
+        function.instruction(&Instruction::LocalSet(n));
+        function.instruction(&Instruction::End);
+        
+        // Each remainder is between -9 and 0
+        function.instruction(&Instruction::Loop(wasm_encoder::BlockType::Empty));
+        function.instruction(&Instruction::LocalGet(position));
+        function.instruction(&Instruction::I32Const(1));
+        function.instruction(&Instruction::I32Sub);
+        function.instruction(&Instruction::LocalSet(position));
+        function.instruction(&Instruction::LocalGet(buffer));
+        function.instruction(&Instruction::LocalGet(position));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::I32Const(i32::from(b'0')));
+        function.instruction(&Instruction::LocalGet(n));
+        function.instruction(&Instruction::I64Const(10));
+        function.instruction(&Instruction::I64RemS);
+        function.instruction(&Instruction::I32WrapI64);
+        function.instruction(&Instruction::I32Sub);
+        function.instruction(&Instruction::I32Store8(byte));
+        function.instruction(&Instruction::LocalGet(n));
+        function.instruction(&Instruction::I64Const(10));
+        function.instruction(&Instruction::I64DivS);
+        function.instruction(&Instruction::LocalTee(n));
+        function.instruction(&Instruction::I64Const(0));
+        function.instruction(&Instruction::I64Ne);
+        function.instruction(&Instruction::BrIf(0));
+        function.instruction(&Instruction::End);
+        
+        function.instruction(&Instruction::LocalGet(negative));
+        function.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+        function.instruction(&Instruction::LocalGet(position));
+        function.instruction(&Instruction::I32Const(1));
+        function.instruction(&Instruction::I32Sub);
+        function.instruction(&Instruction::LocalSet(position));
+        function.instruction(&Instruction::LocalGet(buffer));
+        function.instruction(&Instruction::LocalGet(position));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::I32Const(i32::from(b'-')));
+        function.instruction(&Instruction::I32Store8(byte));
+        function.instruction(&Instruction::End);
+        
+        function.instruction(&Instruction::LocalGet(buffer));
+        function.instruction(&Instruction::LocalGet(position));
+        function.instruction(&Instruction::I32Add);
+        function.instruction(&Instruction::End);
+        self.code.function(&function);
+    }
+    
     /// Generate a constant
     fn generate_const(&mut self, const_def: &TypedConst) -> Result<(), CodeGenError> {
         let wasm_type = self.flux_type_to_wasm(&const_def.type_)?;
@@ -256,6 +744,59 @@ impl WasmCodeGenerator {
         Ok(())
     }
     
+    /// Give each `let` binding and loop variable of a body a local, and each
+    /// level of nested range loops its end and step locals
+    fn declare_locals(&mut self, block: &TypedBlock, depth: usize, locals: &mut Vec<(u32, ValType)>) -> Result<(), CodeGenError> {
+        for stmt in &block.statements {
+            match &stmt.kind {
+                TypedStatementKind::Let(name, type_, _) => {
+                    let val_type = self.flux_type_to_wasm(type_)?;
+                    self.declare_local(name, val_type, locals, stmt.span)?;
+                }
+                TypedStatementKind::For(var, iter, body) if matches!(iter.kind, TypedExpressionKind::Range(..)) => {
+                    self.declare_local(var, ValType::I64, locals, stmt.span)?;
+                    if self.range_locals.len() == depth {
+                        let end = self.scratch_local + locals.len() as u32;
+                        locals.extend([(1, ValType::I64), (1, ValType::I64)]);
+                        self.range_locals.push((end, end + 1));
+                    }
+                    self.declare_locals(body, depth + 1, locals)?;
+                }
+                TypedStatementKind::If(_, then_block, else_block) => {
+                    self.declare_locals(then_block, depth, locals)?;
+                    if let Some(else_block) = else_block {
+                        self.declare_locals(else_block, depth, locals)?;
+                    }
+                }
+                TypedStatementKind::While(_, body) => self.declare_locals(body, depth, locals)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    
+    /// Give a variable a local; rebinding a name reuses its local, which
+    /// needs a value of the same type
+    fn declare_local(&mut self, name: &str, val_type: ValType, locals: &mut Vec<(u32, ValType)>, span: Option<crate::position::Span>) -> Result<(), CodeGenError> {
+        if let Some(&index) = self.local_indices.get(name) {
+            // Parameters come before the scratch local and keep their type
+            let declared = index.checked_sub(self.scratch_local).and_then(|slot| locals.get(slot as usize));
+            return match declared {
+                Some((_, existing)) if *existing != val_type => Err(CodeGenError {
+                    span,
+                    kind: CodeGenErrorKind::UnsupportedFeature {
+                        feature: format!("Rebinding '{}' to a value of another type", name),
+                    },
+                }),
+                _ => Ok(()),
+            };
+        }
+        let index = self.scratch_local + locals.len() as u32;
+        locals.push((1, val_type));
+        self.local_indices.insert(name.to_string(), index);
+        Ok(())
+    }
+    
     /// Generate instructions for a block
     fn generate_block_instructions(&mut self, block: &TypedBlock, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
         for stmt in &block.statements {
@@ -278,13 +819,48 @@ impl WasmCodeGenerator {
                 // Generate initialization expression
                 self.generate_expression_instructions(init_expr, function)?;
                 
-                // For now, we'll use locals for let bindings
-                // This is a simplified approach - a full implementation would need
-                // proper local variable allocation
+                // The binding's local was declared before the body
                 if let Some(&local_index) = self.local_indices.get(name) {
                     function.instruction(&Instruction::LocalSet(local_index));
                 }
             }
+            TypedStatementKind::Assignment(target, value) => {
+                // Only variables held in locals can be assigned so far
+                let local_index = match &target.kind {
+                    TypedExpressionKind::Identifier(name) => self.local_indices.get(name).copied(),
+                    _ => None,
+                };
+                let Some(local_index) = local_index else {
+                    return Err(CodeGenError {
+                        span: stmt.span,
+                        kind: CodeGenErrorKind::UnsupportedFeature {
+                            feature: format!("Assignment to: {:?}", target.kind),
+                        },
+                    });
+                };
+                self.generate_expression_instructions(value, function)?;
+                function.instruction(&Instruction::LocalSet(local_index));
+            }
+            TypedStatementKind::If(cond, then_block, else_block) => {
+                self.generate_expression_instructions(cond, function)?;
+                function.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+                self.block_depth += 1;
+                self.generate_block_instructions(then_block, function)?;
+                if let Some(else_block) = else_block {
+                    function.instruction(&Instruction::Else);
+                    self.generate_block_instructions(else_block, function)?;
+                }
+                function.instruction(&Instruction::End);
+                self.block_depth -= 1;
+            }
+            TypedStatementKind::For(var, iter, body) if matches!(iter.kind, TypedExpressionKind::Range(..)) => {
+                self.generate_range_loop(var, iter, body, function)?;
+            }
+            TypedStatementKind::Break(None) | TypedStatementKind::Continue if !self.loop_labels.is_empty() => {
+                let (break_depth, continue_depth) = self.loop_labels[self.loop_labels.len() - 1];
+                let target = if matches!(stmt.kind, TypedStatementKind::Continue) { continue_depth } else { break_depth };
+                function.instruction(&Instruction::Br(self.block_depth - target));
+            }
             TypedStatementKind::Return(Some(expr)) => {
                 self.generate_expression_instructions(expr, function)?;
                 function.instruction(&Instruction::Return);
@@ -304,6 +880,113 @@ impl WasmCodeGenerator {
         Ok(())
     }
     
+    /// Generate a counted loop over a range literal. The loop variable moves
+    /// from the start by the step until it passes the end; which way it
+    /// counts is known up front unless the step is only known at run time.
+    fn generate_range_loop(&mut self, var: &str, range: &TypedExpression, body: &TypedBlock, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        let internal = |message: &str| CodeGenError {
+            span: range.span,
+            kind: CodeGenErrorKind::InternalError { message: message.to_string() },
+        };
+        let TypedExpressionKind::Range(start, end, step, inclusive) = &range.kind else {
+            return Err(internal("Counted loop over a value that is not a range"));
+        };
+        let (Some(&counter), Some(&(end_local, step_local))) = (self.local_indices.get(var), self.range_locals.get(self.range_depth)) else {
+            return Err(internal("No locals declared for a range loop"));
+        };
+        
+        self.generate_expression_instructions(start, function)?;
+        function.instruction(&Instruction::LocalSet(counter));
+        self.generate_expression_instructions(end, function)?;
+        function.instruction(&Instruction::LocalSet(end_local));
+        let constant = constant_step(step.as_deref());
+        if let (None, Some(step)) = (constant, step) {
+            // A zero step would never reach the end
+            self.generate_expression_instructions(step, function)?;
+            function.instruction(&Instruction::LocalTee(step_local));
+            function.instruction(&Instruction::I64Eqz);
+            function.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+            function.instruction(&Instruction::Unreachable);
+            function.instruction(&Instruction::End);
+        }
+        
+        function.instruction(&Instruction::Block(wasm_encoder::BlockType::Empty));
+        self.block_depth += 1;
+        let break_depth = self.block_depth;
+        function.instruction(&Instruction::Loop(wasm_encoder::BlockType::Empty));
+        self.block_depth += 1;
+        
+        // Leave once the counter has passed the end
+        let (up, down) = if *inclusive {
+            (Instruction::I64LeS, Instruction::I64GeS)
+        } else {
+            (Instruction::I64LtS, Instruction::I64GtS)
+        };
+        let mut compare = |comparison: Instruction<'static>| {
+            function.instruction(&Instruction::LocalGet(counter));
+            function.instruction(&Instruction::LocalGet(end_local));
+            function.instruction(&comparison);
+        };
+        match constant {
+            Some(step) => compare(if step > 0 { up } else { down }),
+            None => {
+                compare(up);
+                compare(down);
+                function.instruction(&Instruction::LocalGet(step_local));
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64GtS);
+                function.instruction(&Instruction::Select);
+            }
+        }
+        function.instruction(&Instruction::I32Eqz);
+        function.instruction(&Instruction::BrIf(1));
+        
+        function.instruction(&Instruction::Block(wasm_encoder::BlockType::Empty));
+        self.block_depth += 1;
+        self.loop_labels.push((break_depth, self.block_depth));
+        self.range_depth += 1;
+        self.generate_block_instructions(body, function)?;
+        self.range_depth -= 1;
+        self.loop_labels.pop();
+        function.instruction(&Instruction::End);
+        self.block_depth -= 1;
+        
+        // Leave rather than wrap around when the step overflows, as at an
+        // end of `i64::MAX`: the sum then moves against the step
+        let step_instruction = match constant {
+            Some(step) => Instruction::I64Const(step),
+            None => Instruction::LocalGet(step_local),
+        };
+        function.instruction(&Instruction::LocalGet(counter));
+        function.instruction(&step_instruction);
+        function.instruction(&Instruction::I64Add);
+        function.instruction(&Instruction::LocalGet(counter));
+        function.instruction(&Instruction::I64LtS);
+        match constant {
+            Some(step) if step < 0 => {
+                function.instruction(&Instruction::I32Eqz);
+            }
+            Some(_) => {}
+            None => {
+                function.instruction(&Instruction::LocalGet(step_local));
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64LtS);
+                function.instruction(&Instruction::I32Xor);
+            }
+        }
+        function.instruction(&Instruction::BrIf(1));
+        
+        function.instruction(&Instruction::LocalGet(counter));
+        function.instruction(&step_instruction);
+        function.instruction(&Instruction::I64Add);
+        function.instruction(&Instruction::LocalSet(counter));
+        function.instruction(&Instruction::Br(0));
+        function.instruction(&Instruction::End);
+        function.instruction(&Instruction::End);
+        self.block_depth -= 2;
+        Ok(())
+    }
+    
     /// Generate instructions for an expression
     fn generate_expression_instructions(&mut self, expr: &TypedExpression, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
         match &expr.kind {
@@ -336,6 +1019,42 @@ impl WasmCodeGenerator {
             TypedExpressionKind::Block(block) => {
                 self.generate_block_instructions(block, function)?;
             }
+            TypedExpressionKind::Upcast(value) => {
+                let base = self.vtable_base(&value.type_, &expr.type_)?;
+                
+                // Combine the vtable base and object pointer into one i64
+                self.generate_expression_instructions(value, function)?;
+                function.instruction(&Instruction::I64ExtendI32U);
+                function.instruction(&Instruction::I64Const((base as i64) << 32));
+                function.instruction(&Instruction::I64Or);
+            }
+            TypedExpressionKind::Closure(closure) => {
+                self.generate_closure_instructions(closure, expr.span, function)?;
+            }
+            TypedExpressionKind::Variant(enum_name, variant, args) if enum_name == "Result" => {
+                self.generate_result_instructions(variant == "Err", args.first(), function)?;
+            }
+            TypedExpressionKind::Interpolated(parts) => {
+                self.generate_interpolated_instructions(parts, function)?;
+            }
+            // Objects can be passed in and dispatched on, but their fields
+            // have no memory layout yet
+            TypedExpressionKind::StructLiteral(name, _) => {
+                return Err(CodeGenError {
+                    span: expr.span,
+                    kind: CodeGenErrorKind::UnsupportedFeature {
+                        feature: format!("Struct literal '{}' in WebAssembly; structs have no memory layout yet", name),
+                    },
+                });
+            }
+            TypedExpressionKind::Field(_, field) => {
+                return Err(CodeGenError {
+                    span: expr.span,
+                    kind: CodeGenErrorKind::UnsupportedFeature {
+                        feature: format!("Field access '.{}' in WebAssembly; structs have no memory layout yet", field),
+                    },
+                });
+            }
             _ => {
                 return Err(CodeGenError {
                     span: None,
@@ -357,6 +1076,9 @@ impl WasmCodeGenerator {
             Literal::Float(f) => {
                 function.instruction(&Instruction::F64Const(*f));
             }
+            Literal::Byte(b) => {
+                function.instruction(&Instruction::I32Const(i32::from(*b)));
+            }
             Literal::Boolean(b) => {
                 function.instruction(&Instruction::I32Const(if *b { 1 } else { 0 }));
             }
@@ -377,6 +1099,9 @@ impl WasmCodeGenerator {
     
     /// Generate instructions for binary operations
     fn generate_binary_op_instructions(&mut self, left: &TypedExpression, op: &BinaryOp, right: &TypedExpression, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        // Operands of a generic instance have the instance's type arguments
+        let operand_type = self.resolve_type(&left.type_);
+        
         // Generate left operand
         self.generate_expression_instructions(left, function)?;
         
@@ -386,9 +1111,9 @@ impl WasmCodeGenerator {
         // Generate operation instruction
         match op {
             BinaryOp::Add => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Add);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Add);
                 } else {
                     return Err(CodeGenError {
@@ -400,9 +1125,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Subtract => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Sub);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Sub);
                 } else {
                     return Err(CodeGenError {
@@ -414,9 +1139,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Multiply => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Mul);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Mul);
                 } else {
                     return Err(CodeGenError {
@@ -428,9 +1153,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Divide => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64DivS);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Div);
                 } else {
                     return Err(CodeGenError {
@@ -442,9 +1167,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Equal => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Eq);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Eq);
                 } else {
                     return Err(CodeGenError {
@@ -456,9 +1181,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Less => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64LtS);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Lt);
                 } else {
                     return Err(CodeGenError {
@@ -470,9 +1195,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Greater => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64GtS);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Gt);
                 } else {
                     return Err(CodeGenError {
@@ -484,9 +1209,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::LessEqual => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64LeS);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Le);
                 } else {
                     return Err(CodeGenError {
@@ -498,9 +1223,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::GreaterEqual => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64GeS);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Ge);
                 } else {
                     return Err(CodeGenError {
@@ -512,9 +1237,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::NotEqual => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Ne);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Ne);
                 } else {
                     return Err(CodeGenError {
@@ -526,9 +1251,9 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Modulo => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64RemS);
-                } else if matches!(left.type_, Type::Float) {
+                } else if matches!(operand_type, Type::Float) {
                     // Float modulo is not directly supported in WASM, would need to implement
                     return Err(CodeGenError {
                         span: None,
@@ -546,7 +1271,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::And => {
-                if matches!(left.type_, Type::Bool) {
+                if matches!(operand_type, Type::Bool) {
                     function.instruction(&Instruction::I32And);
                 } else {
                     return Err(CodeGenError {
@@ -558,7 +1283,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::Or => {
-                if matches!(left.type_, Type::Bool) {
+                if matches!(operand_type, Type::Bool) {
                     function.instruction(&Instruction::I32Or);
                 } else {
                     return Err(CodeGenError {
@@ -570,7 +1295,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::BitwiseAnd => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64And);
                 } else {
                     return Err(CodeGenError {
@@ -582,7 +1307,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::BitwiseOr => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Or);
                 } else {
                     return Err(CodeGenError {
@@ -594,7 +1319,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::BitwiseXor => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Xor);
                 } else {
                     return Err(CodeGenError {
@@ -606,7 +1331,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::LeftShift => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64Shl);
                 } else {
                     return Err(CodeGenError {
@@ -618,7 +1343,7 @@ impl WasmCodeGenerator {
                 }
             }
             BinaryOp::RightShift => {
-                if matches!(left.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     function.instruction(&Instruction::I64ShrS);
                 } else {
                     return Err(CodeGenError {
@@ -629,6 +1354,15 @@ impl WasmCodeGenerator {
                     });
                 }
             }
+            BinaryOp::Coalesce => {
+                // The type checker lowers `??` to a match
+                return Err(CodeGenError {
+                    span: None,
+                    kind: CodeGenErrorKind::UnsupportedFeature {
+                        feature: "Null coalescing (??) outside a match".to_string(),
+                    },
+                });
+            }
         }
         
         Ok(())
@@ -636,12 +1370,13 @@ impl WasmCodeGenerator {
     
     /// Generate instructions for unary operations
     fn generate_unary_op_instructions(&mut self, op: &UnaryOp, operand: &TypedExpression, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        let operand_type = self.resolve_type(&operand.type_);
         self.generate_expression_instructions(operand, function)?;
         
         match op {
             UnaryOp::Plus => {
                 // Unary plus is a no-op for numeric types
-                if !matches!(operand.type_, Type::Int | Type::Float) {
+                if !matches!(operand_type, Type::Int | Type::Float) {
                     return Err(CodeGenError {
                         span: None,
                         kind: CodeGenErrorKind::UnsupportedFeature {
@@ -652,11 +1387,11 @@ impl WasmCodeGenerator {
                 // Value is already on stack, no additional instruction needed
             }
             UnaryOp::Minus => {
-                if matches!(operand.type_, Type::Int) {
-                    // Negate by subtracting from 0
-                    function.instruction(&Instruction::I64Const(0));
-                    function.instruction(&Instruction::I64Sub);
-                } else if matches!(operand.type_, Type::Float) {
+                if matches!(operand_type, Type::Int) {
+                    // Negate by multiplying by -1, as the operand is already on the stack
+                    function.instruction(&Instruction::I64Const(-1));
+                    function.instruction(&Instruction::I64Mul);
+                } else if matches!(operand_type, Type::Float) {
                     function.instruction(&Instruction::F64Neg);
                 } else {
                     return Err(CodeGenError {
@@ -668,7 +1403,7 @@ impl WasmCodeGenerator {
                 }
             }
             UnaryOp::Not => {
-                if matches!(operand.type_, Type::Bool) {
+                if matches!(operand_type, Type::Bool) {
                     // Logical not: XOR with 1
                     function.instruction(&Instruction::I32Const(1));
                     function.instruction(&Instruction::I32Xor);
@@ -682,7 +1417,7 @@ impl WasmCodeGenerator {
                 }
             }
             UnaryOp::BitwiseNot => {
-                if matches!(operand.type_, Type::Int) {
+                if matches!(operand_type, Type::Int) {
                     // Bitwise not: XOR with all 1s
                     function.instruction(&Instruction::I64Const(-1));
                     function.instruction(&Instruction::I64Xor);
@@ -696,12 +1431,58 @@ impl WasmCodeGenerator {
                 }
             }
             UnaryOp::Try => {
-                return Err(CodeGenError {
-                    span: None,
-                    kind: CodeGenErrorKind::UnsupportedFeature {
-                        feature: "Try operator (?) not yet implemented for WebAssembly".to_string(),
-                    },
-                });
+                // The `Err` is returned as is, so its payload must already have
+                // the function's error type
+                let (ok_type, err_type) = match &operand_type {
+                    Type::Result(ok, err) => (ok, err),
+                    _ => unreachable!("the type checker lowers `?` on nullable types"),
+                };
+                let converts = match self.resolve_type(&self.return_type) {
+                    Type::Result(_, expected) => **err_type != *expected,
+                    _ => true,
+                };
+                if converts {
+                    return Err(CodeGenError {
+                        span: None,
+                        kind: CodeGenErrorKind::UnsupportedFeature {
+                            feature: "Error type conversion in '?' for WebAssembly".to_string(),
+                        },
+                    });
+                }
+                
+                function.instruction(&Instruction::LocalTee(self.scratch_local));
+                function.instruction(&load_instruction(ValType::I32, 0));
+                function.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+                function.instruction(&Instruction::LocalGet(self.scratch_local));
+                function.instruction(&Instruction::Return);
+                function.instruction(&Instruction::End);
+                if !self.is_unit_type(ok_type) {
+                    let val_type = self.flux_type_to_wasm(ok_type)?;
+                    function.instruction(&Instruction::LocalGet(self.scratch_local));
+                    function.instruction(&load_instruction(val_type, RESULT_PAYLOAD_OFFSET));
+                }
+            }
+            UnaryOp::Unwrap => {
+                // Nullable values are pointers, so only pointer types unwrap
+                // to the same value; null is address 0 and traps
+                let inner = match &operand_type {
+                    Type::Nullable(inner) => self.resolve_type(inner),
+                    other => other.clone(),
+                };
+                if self.flux_type_to_wasm(&inner)? != ValType::I32 {
+                    return Err(CodeGenError {
+                        span: operand.span,
+                        kind: CodeGenErrorKind::UnsupportedFeature {
+                            feature: format!("Unwrapping a nullable {} in WebAssembly", inner),
+                        },
+                    });
+                }
+                function.instruction(&Instruction::LocalTee(self.scratch_local));
+                function.instruction(&Instruction::I32Eqz);
+                function.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+                function.instruction(&Instruction::Unreachable);
+                function.instruction(&Instruction::End);
+                function.instruction(&Instruction::LocalGet(self.scratch_local));
             }
         }
         
@@ -710,11 +1491,36 @@ impl WasmCodeGenerator {
     
     /// Generate instructions for function calls
     fn generate_call_instructions(&mut self, func_expr: &TypedExpression, args: &[TypedExpression], function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        if let TypedExpressionKind::Field(object, method) = &func_expr.kind {
+            return self.generate_method_call_instructions(object, method, args, function);
+        }
+        
         // Generate arguments
         for arg in args {
             self.generate_expression_instructions(arg, function)?;
         }
         
+        // Any other function-typed value is a closure
+        let is_named_function = match &func_expr.kind {
+            TypedExpressionKind::Identifier(name) => !self.local_indices.contains_key(name),
+            TypedExpressionKind::Instantiate(_, _) => true,
+            _ => false,
+        };
+        if !is_named_function {
+            if let Type::Function(param_types, return_type) = &func_expr.type_ {
+                let thunk_index = self.closure_call_index(param_types, return_type)?;
+                self.generate_expression_instructions(func_expr, function)?;
+                function.instruction(&Instruction::Call(thunk_index));
+                return Ok(());
+            }
+        }
+        
+        if let TypedExpressionKind::Instantiate(name, type_args) = &func_expr.kind {
+            let func_index = self.instance_index(name, type_args)?;
+            function.instruction(&Instruction::Call(func_index));
+            return Ok(());
+        }
+        
         // Get function name
         if let TypedExpressionKind::Identifier(func_name) = &func_expr.kind {
             if let Some(&func_index) = self.function_indices.get(func_name) {
@@ -739,6 +1545,181 @@ impl WasmCodeGenerator {
         Ok(())
     }
     
+    /// Index of a generic function's instance for the given type arguments,
+    /// declaring the instance on first use. Instances are named like
+    /// `identity<int>`.
+    fn instance_index(&mut self, name: &str, type_args: &[Type]) -> Result<u32, CodeGenError> {
+        let type_args: Vec<Type> = type_args.iter().map(|t| self.resolve_type(t)).collect();
+        let instance_name = format!(
+            "{}<{}>",
+            name,
+            type_args.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+        );
+        if let Some(&func_index) = self.function_indices.get(&instance_name) {
+            return Ok(func_index);
+        }
+        
+        let func = self.generic_functions.get(name).cloned().ok_or_else(|| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::UnsupportedFeature {
+                feature: format!("Undefined function: {}", name),
+            },
+        })?;
+        let bindings: HashMap<String, Type> = func.type_params.iter().cloned().zip(type_args).collect();
+        let parameters: Vec<TypedParameter> = func.parameters.iter()
+            .map(|param| TypedParameter { type_: param.type_.substitute(&bindings), ..param.clone() })
+            .collect();
+        let return_type = func.return_type.substitute(&bindings);
+        
+        let func_index = self.declare_function(&instance_name, None, &parameters, &return_type)?;
+        self.late_bodies.push(LateBody::Instance(name.to_string(), bindings));
+        Ok(func_index)
+    }
+    
+    /// Generate instructions creating a closure: lift its lambda to a function
+    /// in the table, then allocate the environment and copy each capture
+    /// into it
+    fn generate_closure_instructions(&mut self, closure: &TypedClosure, span: Option<crate::position::Span>, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        if let Some(capture) = closure.captures.iter().find(|capture| capture.by_reference) {
+            return Err(CodeGenError {
+                span,
+                kind: CodeGenErrorKind::UnsupportedFeature {
+                    feature: format!("Closure capturing assigned variable '{}'", capture.name),
+                },
+            });
+        }
+        
+        let param_types: Vec<Type> = closure.parameters.iter().map(|param| param.type_.clone()).collect();
+        let type_index = self.closure_type(&param_types, &closure.return_type)?;
+        let name = format!("<closure#{}>", self.closure_counter);
+        self.closure_counter += 1;
+        let func_index = self.declare_function_of_type(&name, type_index);
+        let table_index = self.vtable_entries.len() as i32;
+        self.vtable_entries.push(func_index);
+        self.late_bodies.push(LateBody::Closure(closure.clone(), self.type_args.clone()));
+        
+        let env_size = CLOSURE_HEADER_SIZE + CLOSURE_SLOT_SIZE * closure.captures.len() as u32;
+        function.instruction(&Instruction::I32Const(env_size as i32));
+        function.instruction(&Instruction::Call(self.function_indices["js.malloc"]));
+        function.instruction(&Instruction::LocalSet(self.scratch_local));
+        
+        function.instruction(&Instruction::LocalGet(self.scratch_local));
+        function.instruction(&Instruction::I32Const(table_index));
+        function.instruction(&store_instruction(ValType::I32, 0));
+        for (slot, capture) in closure.captures.iter().enumerate() {
+            let val_type = self.flux_type_to_wasm(&capture.type_)?;
+            let value = TypedExpression {
+                kind: TypedExpressionKind::Identifier(capture.name.clone()),
+                type_: capture.type_.clone(),
+                span,
+            };
+            function.instruction(&Instruction::LocalGet(self.scratch_local));
+            self.generate_expression_instructions(&value, function)?;
+            function.instruction(&store_instruction(val_type, CLOSURE_HEADER_SIZE + CLOSURE_SLOT_SIZE * slot as u32));
+        }
+        function.instruction(&Instruction::LocalGet(self.scratch_local));
+        
+        Ok(())
+    }
+    
+    /// Generate an `Ok` or `Err` result holding `payload`
+    fn generate_result_instructions(&mut self, is_err: bool, payload: Option<&TypedExpression>, function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        function.instruction(&Instruction::I32Const(RESULT_SIZE as i32));
+        function.instruction(&Instruction::Call(self.function_indices["js.malloc"]));
+        function.instruction(&Instruction::LocalTee(self.scratch_local));
+        function.instruction(&Instruction::I32Const(is_err as i32));
+        function.instruction(&store_instruction(ValType::I32, 0));
+        
+        // The payload may build values of its own, so the pointer is kept on
+        // the stack rather than in the scratch local
+        function.instruction(&Instruction::LocalGet(self.scratch_local));
+        if let Some(payload) = payload.filter(|payload| !self.is_unit_type(&payload.type_)) {
+            let val_type = self.flux_type_to_wasm(&payload.type_)?;
+            function.instruction(&Instruction::LocalGet(self.scratch_local));
+            self.generate_expression_instructions(payload, function)?;
+            function.instruction(&store_instruction(val_type, RESULT_PAYLOAD_OFFSET));
+        }
+        
+        Ok(())
+    }
+    
+    /// Function type of a lifted lambda: its parameters followed by the
+    /// environment pointer
+    fn closure_type(&mut self, param_types: &[Type], return_type: &Type) -> Result<u32, CodeGenError> {
+        let mut params = Vec::new();
+        for param_type in param_types {
+            params.push(self.flux_type_to_wasm(param_type)?);
+        }
+        params.push(ValType::I32);
+        let results = if self.is_unit_type(return_type) {
+            vec![]
+        } else {
+            vec![self.flux_type_to_wasm(return_type)?]
+        };
+        Ok(self.add_function_type(&params, &results))
+    }
+    
+    /// Index of the caller for closures of a signature, declaring it on
+    /// first use
+    fn closure_call_index(&mut self, param_types: &[Type], return_type: &Type) -> Result<u32, CodeGenError> {
+        let signature = Type::Function(
+            param_types.iter().map(|t| self.resolve_type(t)).collect(),
+            Box::new(self.resolve_type(return_type)),
+        );
+        let name = format!("<call {}>", signature);
+        if let Some(&func_index) = self.function_indices.get(&name) {
+            return Ok(func_index);
+        }
+        
+        let type_index = self.closure_type(param_types, return_type)?;
+        let func_index = self.declare_function_of_type(&name, type_index);
+        self.late_bodies.push(LateBody::ClosureCall(type_index, param_types.len()));
+        Ok(func_index)
+    }
+    
+    /// Generate instructions for a method call: the receiver is passed first.
+    /// Calls through an interface go to its dispatcher, other calls directly
+    /// to the implementation.
+    fn generate_method_call_instructions(&mut self, object: &TypedExpression, method: &str, args: &[TypedExpression], function: &mut wasm_encoder::Function) -> Result<(), CodeGenError> {
+        let func_index = match &object.type_ {
+            Type::Named(name) if self.interfaces.contains_key(name) => {
+                self.function_indices.get(&format!("{}::{}", name, method)).copied()
+            }
+            Type::Named(name) => self.method_indices.get(&(name.clone(), method.to_string())).copied(),
+            _ => None,
+        };
+        let func_index = func_index.ok_or_else(|| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::UnsupportedFeature {
+                feature: format!("Method call: {}.{}", object.type_, method),
+            },
+        })?;
+        
+        self.generate_expression_instructions(object, function)?;
+        for arg in args {
+            self.generate_expression_instructions(arg, function)?;
+        }
+        function.instruction(&Instruction::Call(func_index));
+        
+        Ok(())
+    }
+    
+    /// First function table slot of the vtable for `type_` as `interface`
+    fn vtable_base(&self, type_: &Type, interface: &Type) -> Result<u32, CodeGenError> {
+        let base = match (type_, interface) {
+            (Type::Named(type_name), Type::Named(interface_name)) => {
+                self.vtable_bases.get(&(type_name.clone(), interface_name.clone())).copied()
+            }
+            _ => None,
+        };
+        base.ok_or_else(|| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::InternalError {
+                message: format!("No vtable for {} as {}", type_, interface),
+            },
+        })
+    }
+    
     /// Generate constant expression for globals
     fn generate_const_expression(&mut self, expr: &TypedExpression) -> Result<wasm_encoder::ConstExpr, CodeGenError> {
         match &expr.kind {
@@ -746,6 +1727,7 @@ impl WasmCodeGenerator {
                 match lit {
                     Literal::Integer(n) => Ok(wasm_encoder::ConstExpr::i64_const(*n)),
                     Literal::Float(f) => Ok(wasm_encoder::ConstExpr::f64_const(*f)),
+                    Literal::Byte(b) => Ok(wasm_encoder::ConstExpr::i32_const(i32::from(*b))),
                     Literal::Boolean(b) => Ok(wasm_encoder::ConstExpr::i32_const(if *b { 1 } else { 0 })),
                     Literal::Character(c) => Ok(wasm_encoder::ConstExpr::i32_const(*c as i32)),
                     Literal::Null => Ok(wasm_encoder::ConstExpr::i32_const(0)),
@@ -773,12 +1755,12 @@ impl WasmCodeGenerator {
         }
         
         let offset = self.memory_offset;
-        let bytes = s.as_bytes();
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
         
         // Add string data
-        self.data.active(0, &wasm_encoder::ConstExpr::i32_const(offset as i32), bytes.to_vec());
-        
         self.memory_offset += bytes.len() as u32;
+        self.data.active(0, &wasm_encoder::ConstExpr::i32_const(offset as i32), bytes);
         self.string_literals.insert(s.to_string(), offset);
         
         offset
@@ -786,16 +1768,44 @@ impl WasmCodeGenerator {
     
     /// Build the final WebAssembly module
     fn build_module(&mut self) -> Result<Vec<u8>, CodeGenError> {
+        if self.memory_offset > WASM_HEAP_BASE {
+            return Err(CodeGenError {
+                span: None,
+                kind: CodeGenErrorKind::UnsupportedFeature {
+                    feature: format!("More than {} bytes of string literals", WASM_HEAP_BASE),
+                },
+            });
+        }
+        
         // Create a new module for building
         let mut module = Module::new();
         
         // Add all sections to the module
+        // Vtables and lifted lambdas live in a single function table
+        let table_size = self.vtable_entries.len() as u32;
+        let mut tables = TableSection::new();
+        let mut elements = ElementSection::new();
+        if table_size > 0 {
+            tables.table(TableType {
+                element_type: RefType::FUNCREF,
+                minimum: table_size,
+                maximum: Some(table_size),
+            });
+            elements.active(None, &wasm_encoder::ConstExpr::i32_const(0), Elements::Functions(&self.vtable_entries));
+        }
+        
         module.section(&self.types);
         module.section(&self.imports);
         module.section(&self.functions);
+        if table_size > 0 {
+            module.section(&tables);
+        }
         module.section(&self.memory);
         module.section(&self.globals);
         module.section(&self.exports);
+        if table_size > 0 {
+            module.section(&elements);
+        }
         module.section(&self.code);
         module.section(&self.data);
         
@@ -803,6 +1813,53 @@ impl WasmCodeGenerator {
     }
 }
 
+/// Count the bytes of the string at local `string` before its 0 byte into
+/// local `length`
+#[cfg(feature = "wasm")]
+fn generate_strlen(string: u32, length: u32, function: &mut wasm_encoder::Function) {
+    function.instruction(&Instruction::I32Const(0));
+    function.instruction(&Instruction::LocalSet(length));
+    function.instruction(&Instruction::Block(wasm_encoder::BlockType::Empty));
+    function.instruction(&Instruction::Loop(wasm_encoder::BlockType::Empty));
+    function.instruction(&Instruction::LocalGet(string));
+    function.instruction(&Instruction::LocalGet(length));
+    function.instruction(&Instruction::I32Add);
+    function.instruction(&Instruction::I32Load8U(wasm_encoder::MemArg { offset: 0, align: 0, memory_index: 0 }));
+    function.instruction(&Instruction::I32Eqz);
+    function.instruction(&Instruction::BrIf(1));
+    function.instruction(&Instruction::LocalGet(length));
+    function.instruction(&Instruction::I32Const(1));
+    function.instruction(&Instruction::I32Add);
+    function.instruction(&Instruction::LocalSet(length));
+    function.instruction(&Instruction::Br(0));
+    function.instruction(&Instruction::End);
+    function.instruction(&Instruction::End);
+}
+
+/// Load a value of `val_type` stored `offset` bytes past the address on the stack
+#[cfg(feature = "wasm")]
+fn load_instruction(val_type: ValType, offset: u32) -> Instruction<'static> {
+    let memarg = |align| wasm_encoder::MemArg { offset: offset as u64, align, memory_index: 0 };
+    match val_type {
+        ValType::I64 => Instruction::I64Load(memarg(3)),
+        ValType::F64 => Instruction::F64Load(memarg(3)),
+        ValType::F32 => Instruction::F32Load(memarg(2)),
+        _ => Instruction::I32Load(memarg(2)),
+    }
+}
+
+/// Store the value on top of the stack `offset` bytes past the address below it
+#[cfg(feature = "wasm")]
+fn store_instruction(val_type: ValType, offset: u32) -> Instruction<'static> {
+    let memarg = |align| wasm_encoder::MemArg { offset: offset as u64, align, memory_index: 0 };
+    match val_type {
+        ValType::I64 => Instruction::I64Store(memarg(3)),
+        ValType::F64 => Instruction::F64Store(memarg(3)),
+        ValType::F32 => Instruction::F32Store(memarg(2)),
+        _ => Instruction::I32Store(memarg(2)),
+    }
+}
+
 #[cfg(not(feature = "wasm"))]
 impl WasmCodeGenerator {
     pub fn new() -> Self {
@@ -851,10 +1908,12 @@ impl WasmRuntime {
             println!("WASM console.log: {}", ptr);
         });
         
-        let malloc = wasmtime::Func::wrap(&mut self.store, |size: i32| -> i32 {
-            // Simple malloc implementation - in a real implementation,
-            // this would manage a heap
-            size // Just return the size as a fake pointer
+        // Allocations are never freed; each one starts 8-byte aligned above
+        // the module's string literals
+        let heap_top = std::sync::Arc::new(std::sync::atomic::AtomicI32::new(WASM_HEAP_BASE as i32));
+        let malloc = wasmtime::Func::wrap(&mut self.store, move |size: i32| -> i32 {
+            let size = (size + 7) & !7;
+            heap_top.fetch_add(size, std::sync::atomic::Ordering::Relaxed)
         });
         
         let free = wasmtime::Func::wrap(&mut self.store, |_ptr: i32| {
@@ -878,6 +1937,19 @@ impl WasmRuntime {
         Ok(instance)
     }
     
+    /// Read the string a Flux string value points to in the module's memory
+    pub fn read_string(&mut self, instance: &wasmtime::Instance, pointer: i32) -> Result<String, CodeGenError> {
+        let memory = instance.get_memory(&mut self.store, "memory").ok_or_else(|| CodeGenError {
+            span: None,
+            kind: CodeGenErrorKind::RuntimeError {
+                message: "Memory not exported by WASM module".to_string(),
+            },
+        })?;
+        let bytes = memory.data(&self.store).get(pointer as usize..).unwrap_or_default();
+        let length = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..length]).into_owned())
+    }
+    
     /// Call a function in the WebAssembly module
     pub fn call_function(&mut self, instance: &wasmtime::Instance, name: &str, args: &[wasmtime::Val]) -> Result<Vec<wasmtime::Val>, CodeGenError> {
         let func = instance.get_func(&mut self.store, name)
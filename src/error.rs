@@ -19,10 +19,16 @@ pub enum FluxError {
     #[error("Semantic error: {0}")]
     Semantic(#[from] SemanticError),
     
+    #[error("{}", .0.iter()
+        .map(|(file, error)| format!("Semantic error: {}:{}", file.display(), error))
+        .collect::<Vec<_>>()
+        .join("\n"))]
+    SemanticErrors(Vec<(std::path::PathBuf, SemanticError)>),
+    
     #[error("Code generation error: {0}")]
     CodeGen(#[from] CodeGenError),
     
-    #[error("Runtime error: {0}")]
+    #[error("{0}")]
     Runtime(#[from] RuntimeError),
     
     #[error("Package error: {0}")]
@@ -62,6 +68,12 @@ pub enum LexErrorKind {
     #[error("Invalid number format")]
     InvalidNumber,
     
+    #[error("Number {literal} is out of range for {type_name}")]
+    NumberOutOfRange { literal: String, type_name: String },
+    
+    #[error("Invalid suffix '{0}' on number")]
+    InvalidSuffix(String),
+    
     #[error("Invalid escape sequence")]
     InvalidEscape,
     
@@ -73,7 +85,7 @@ pub enum LexErrorKind {
 }
 
 /// Parse errors
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub struct ParseError {
     pub span: Span,
     pub kind: ParseErrorKind,
@@ -85,7 +97,7 @@ impl fmt::Display for ParseError {
     }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ParseErrorKind {
     #[error("Expected {expected}, found {found}")]
     UnexpectedToken { expected: String, found: String },
@@ -107,7 +119,7 @@ pub enum ParseErrorKind {
 }
 
 /// Semantic analysis errors
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub struct SemanticError {
     pub span: Span,
     pub kind: SemanticErrorKind,
@@ -119,7 +131,14 @@ impl fmt::Display for SemanticError {
     }
 }
 
-#[derive(Debug, Error)]
+impl SemanticError {
+    /// Point the error at the given source span
+    pub fn with_span(self, span: Span) -> Self {
+        Self { span, ..self }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
 pub enum SemanticErrorKind {
     #[error("Undefined variable: '{name}'")]
     UndefinedVariable { name: String },
@@ -147,6 +166,60 @@ pub enum SemanticErrorKind {
     
     #[error("Unsupported feature: {feature}")]
     UnsupportedFeature { feature: String },
+
+    #[error("Unresolved import: \"{path}\"")]
+    UnresolvedImport { path: String },
+
+    #[error("Import cycle detected: {}", .cycle.join(" -> "))]
+    ImportCycle { cycle: Vec<String> },
+
+    #[error("'{name}' is private to module '{module}'")]
+    PrivateItem { name: String, module: String },
+
+    #[error("Non-exhaustive match: missing {}", .missing.join(", "))]
+    NonExhaustiveMatch { missing: Vec<String> },
+
+    #[error("Pattern does not match every value: missing {}; use 'match' instead", .missing.join(", "))]
+    RefutablePattern { missing: Vec<String> },
+
+    #[error("'{type_name}' does not implement interface '{interface}': {reason}")]
+    InterfaceNotImplemented { type_name: String, interface: String, reason: String },
+
+    #[error("'{function}' does not return a value on every path")]
+    MissingReturn { function: String },
+
+    #[error("Unreachable statement")]
+    UnreachableCode,
+
+    #[error("'break' outside of a loop")]
+    BreakOutsideLoop,
+
+    #[error("'continue' outside of a loop")]
+    ContinueOutsideLoop,
+
+    #[error("Unused Result value; its error is silently dropped")]
+    UnusedResult,
+
+    #[error("Variable '{name}' is used before it is assigned on every path")]
+    UninitializedVariable { name: String },
+
+    #[error("Cannot assign to immutable binding '{name}'; {help}")]
+    AssignToImmutable { name: String, help: String },
+
+    #[error("Value of nullable type '{type_name}' may be null; compare it with null first, or use '?.', '??' or '!'")]
+    NullableDereference { type_name: String },
+
+    #[error("Cannot use '?' on '{operand}' in a function returning '{return_type}'")]
+    InvalidTry { operand: String, return_type: String },
+
+    #[error("Error type '{from}' cannot be converted to '{to}', the error type the function returns")]
+    ErrorConversion { from: String, to: String },
+
+    #[error("Value of type '{type_name}' cannot be interpolated into a string")]
+    NotPrintable { type_name: String },
+
+    #[error("Pattern has {elements} elements but value is {found}")]
+    TuplePatternMismatch { elements: usize, found: String },
 }
 
 /// Code generation errors
@@ -191,7 +264,11 @@ pub struct RuntimeError {
 
 impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.kind)
+        match &self.kind {
+            // A report already names the error it describes
+            RuntimeErrorKind::Reported(report) => write!(f, "{}", report),
+            kind => write!(f, "Runtime error: {}", kind),
+        }
     }
 }
 
@@ -220,6 +297,10 @@ pub enum RuntimeErrorKind {
     
     #[error("Panic: {message}")]
     Panic { message: String },
+    
+    /// Failure of an interpreted program, with its location and stack trace
+    #[error("{0}")]
+    Reported(Box<crate::runtime::ErrorReport>),
 }
 
 /// Package management errors
@@ -258,4 +339,19 @@ impl FluxError {
             error: message,
         })
     }
-}
\ No newline at end of file
+
+    /// The report of a failed program run, which carries its own header
+    pub fn report(&self) -> Option<&crate::runtime::ErrorReport> {
+        match self {
+            Self::Runtime(RuntimeError { kind: RuntimeErrorKind::Reported(report) }) => Some(report),
+            _ => None,
+        }
+    }
+}
+
+/// Runtime failure of an interpreted program
+impl From<Box<crate::runtime::ErrorReport>> for FluxError {
+    fn from(report: Box<crate::runtime::ErrorReport>) -> Self {
+        Self::Runtime(RuntimeError { kind: RuntimeErrorKind::Reported(report) })
+    }
+}
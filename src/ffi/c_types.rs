@@ -41,6 +41,8 @@ pub fn flux_to_c_type(flux_type: &FluxType) -> FFIResult<CType> {
         FluxType::List(_) | 
         FluxType::Map(_, _) | 
         FluxType::Set(_) |
+        FluxType::Tuple(_) |
+        FluxType::Range |
         FluxType::Named(_) => {
             Err(FFIError::type_conversion(
                 &format!("{:?}", flux_type),
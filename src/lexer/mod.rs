@@ -3,17 +3,24 @@
 //! Provides tokenization of Flux source code into a stream of tokens.
 
 use crate::error::{LexError, LexErrorKind};
-use crate::position::Position;
+use crate::position::{Position, Span};
 
 pub mod token;
+pub mod trivia;
 
 pub use token::Token;
 
 /// Default implementation of the Flux lexer
 pub struct FluxLexer {
+    /// Source text, for the spelling of tokens
+    source: String,
     input: Vec<char>,
     position: usize,
     current_pos: Position,
+    token_start: Position,
+    /// Open braces inside each interpolated expression of the strings
+    /// being read, innermost last
+    interpolations: Vec<usize>,
 }
 
 impl FluxLexer {
@@ -21,8 +28,11 @@ impl FluxLexer {
     pub fn new(input: String) -> Self {
         Self {
             input: input.chars().collect(),
+            source: input,
             position: 0,
             current_pos: Position::start(),
+            token_start: Position::start(),
+            interpolations: Vec::new(),
         }
     }
     
@@ -84,6 +94,49 @@ impl FluxLexer {
         })
     }
     
+    /// Read the text of a `///` comment after its slashes, without the
+    /// space that usually follows them
+    fn read_line_doc_comment(&mut self) -> Token {
+        let start = self.position;
+        self.skip_line_comment();
+        let text: String = self.input[start..self.position].iter().collect();
+        let text = text.trim_end();
+        Token::DocComment(text.strip_prefix(' ').unwrap_or(text).to_string())
+    }
+    
+    /// Read the text of a `/** */` comment after its opening. Blank first
+    /// and last lines are dropped, each line loses any leading `*`
+    /// decoration, and the lines after the first lose the indentation they
+    /// all share.
+    fn read_block_doc_comment(&mut self) -> Result<Token, LexError> {
+        let start = self.position;
+        self.skip_block_comment()?;
+        let text: String = self.input[start..self.position - 2].iter().collect();
+        
+        let mut lines: Vec<&str> = text
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                trimmed.strip_prefix('*').unwrap_or(line).trim_end()
+            })
+            .collect();
+        let indentation = lines.iter().skip(1)
+            .filter(|line| !line.is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        for (index, line) in lines.iter_mut().enumerate() {
+            *line = if index == 0 { line.trim_start() } else { line.get(indentation..).unwrap_or("") };
+        }
+        if lines.first().is_some_and(|line| line.is_empty()) {
+            lines.remove(0);
+        }
+        if lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        Ok(Token::DocComment(lines.join("\n")))
+    }
+    
     /// Read an identifier or keyword
     fn read_identifier(&mut self) -> String {
         let mut result = String::new();
@@ -100,22 +153,36 @@ impl FluxLexer {
         result
     }
     
-    /// Read a number (integer or float)
+    /// Read a number: a decimal integer or float, or an integer in hex
+    /// (`0xFF`), octal (`0o755`) or binary (`0b1010`). Digits may be
+    /// separated by underscores, and a suffix picks the type: `i64` or `u8`
+    /// for integers, `f64` or `f32` for floats.
     fn read_number(&mut self) -> Result<Token, LexError> {
         let start_pos = self.current_pos;
-        let mut result = String::new();
-        let mut is_float = false;
+        let start = self.position;
+        let invalid = || LexError {
+            position: start_pos,
+            kind: LexErrorKind::InvalidNumber,
+        };
         
-        // Read integer part
-        while let Some(ch) = self.current_char() {
-            if ch.is_ascii_digit() {
-                result.push(ch);
-                self.advance();
-            } else {
-                break;
-            }
+        let radix = match (self.current_char(), self.peek_char()) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('o' | 'O')) => 8,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.advance(); // consume '0'
+            self.advance(); // consume the radix letter
+            let digits = self.read_digits(radix).ok_or_else(invalid)?;
+            let suffix = self.read_suffix();
+            return self.integer_token(&digits, radix, &suffix, start, start_pos);
         }
         
+        // Read integer part
+        let mut result = self.read_digits(10).ok_or_else(invalid)?;
+        let mut is_float = false;
+        
         // Check for decimal point
         if self.current_char() == Some('.') && self.peek_char().map_or(false, |c| c.is_ascii_digit()) {
             is_float = true;
@@ -123,14 +190,7 @@ impl FluxLexer {
             self.advance(); // consume '.'
             
             // Read fractional part
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_digit() {
-                    result.push(ch);
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
+            result.push_str(&self.read_digits(10).ok_or_else(invalid)?);
         }
         
         // Check for scientific notation
@@ -149,82 +209,127 @@ impl FluxLexer {
                 }
                 
                 // Exponent digits
-                let mut has_exponent_digits = false;
-                while let Some(ch) = self.current_char() {
-                    if ch.is_ascii_digit() {
-                        result.push(ch);
-                        self.advance();
-                        has_exponent_digits = true;
-                    } else {
-                        break;
-                    }
-                }
-                
-                if !has_exponent_digits {
-                    return Err(LexError {
-                        position: start_pos,
-                        kind: LexErrorKind::InvalidNumber,
-                    });
+                if !self.current_char().is_some_and(|c| c.is_ascii_digit()) {
+                    return Err(invalid());
                 }
+                result.push_str(&self.read_digits(10).ok_or_else(invalid)?);
             }
         }
         
-        if is_float {
-            match result.parse::<f64>() {
-                Ok(num) => Ok(Token::Float(num)),
-                Err(_) => Err(LexError {
-                    position: start_pos,
-                    kind: LexErrorKind::InvalidNumber,
-                }),
-            }
+        let suffix = self.read_suffix();
+        if is_float || matches!(suffix.as_str(), "f64" | "f32") {
+            self.float_token(&result, &suffix, start, start_pos)
         } else {
-            match result.parse::<i64>() {
-                Ok(num) => Ok(Token::Integer(num)),
-                Err(_) => Err(LexError {
-                    position: start_pos,
-                    kind: LexErrorKind::InvalidNumber,
-                }),
+            self.integer_token(&result, 10, &suffix, start, start_pos)
+        }
+    }
+    
+    /// Read the digits of a number in the given radix without the
+    /// underscores between them, or `None` when there are no digits or a
+    /// separator is not followed by one
+    fn read_digits(&mut self, radix: u32) -> Option<String> {
+        let mut digits = String::new();
+        let mut separated = false;
+        
+        // Letters are digits only in hex; in other radixes they start a suffix
+        while let Some(ch) = self.current_char() {
+            if ch == '_' {
+                separated = true;
+            } else if ch.is_ascii_digit() || (radix == 16 && ch.is_ascii_hexdigit()) {
+                digits.push(ch);
+                separated = false;
+            } else {
+                break;
             }
+            self.advance();
+        }
+        
+        (!digits.is_empty() && !separated).then_some(digits)
+    }
+    
+    /// Read the letters and digits directly following a number
+    fn read_suffix(&mut self) -> String {
+        match self.current_char() {
+            Some(ch) if ch.is_alphabetic() => self.read_identifier(),
+            _ => String::new(),
         }
     }
     
-    /// Read a string literal with escape sequences
+    /// Source text of a number starting at `start`, for error messages
+    fn literal_text(&self, start: usize) -> String {
+        self.input[start..self.position].iter().collect()
+    }
+    
+    fn integer_token(&self, digits: &str, radix: u32, suffix: &str, start: usize, start_pos: Position) -> Result<Token, LexError> {
+        let error = |kind| LexError { position: start_pos, kind };
+        let out_of_range = |type_name: &str| error(LexErrorKind::NumberOutOfRange {
+            literal: self.literal_text(start),
+            type_name: type_name.to_string(),
+        });
+        
+        if digits.chars().any(|c| !c.is_digit(radix)) {
+            return Err(error(LexErrorKind::InvalidNumber));
+        }
+        match suffix {
+            // 2^63 only fits negated, as `i64::MIN`; the parser checks for the minus
+            "" | "i64" if u64::from_str_radix(digits, radix) == Ok(1 << 63) => Ok(Token::Integer(i64::MIN)),
+            "" | "i64" => i64::from_str_radix(digits, radix).map(Token::Integer).map_err(|_| out_of_range("int")),
+            "u8" => u8::from_str_radix(digits, radix).map(Token::Byte).map_err(|_| out_of_range("byte")),
+            _ => Err(error(LexErrorKind::InvalidSuffix(suffix.to_string()))),
+        }
+    }
+    
+    fn float_token(&self, text: &str, suffix: &str, start: usize, start_pos: Position) -> Result<Token, LexError> {
+        let error = |kind| LexError { position: start_pos, kind };
+        let out_of_range = |type_name: &str| error(LexErrorKind::NumberOutOfRange {
+            literal: self.literal_text(start),
+            type_name: type_name.to_string(),
+        });
+        
+        let value = text.parse::<f64>().map_err(|_| error(LexErrorKind::InvalidNumber))?;
+        match suffix {
+            "" | "f64" if value.is_finite() => Ok(Token::Float(value)),
+            "" | "f64" => Err(out_of_range("float")),
+            // Floats are 64-bit; an `f32` literal has the value it would
+            // have with 32 bits
+            "f32" if (value as f32).is_finite() => Ok(Token::Float(value as f32 as f64)),
+            "f32" => Err(out_of_range("f32")),
+            _ => Err(error(LexErrorKind::InvalidSuffix(suffix.to_string()))),
+        }
+    }
+    
+    /// Read a string literal with escape sequences, up to its closing quote
+    /// or the `{` opening its first interpolated expression
     fn read_string(&mut self) -> Result<Token, LexError> {
-        let start_pos = self.current_pos;
-        let mut result = String::new();
-        
         self.advance(); // consume opening quote
+        self.read_string_segment(true)
+    }
+    
+    /// Read string text after an opening quote or the `}` closing an
+    /// interpolated expression. A `{` opens an expression whose tokens
+    /// follow, until the `}` that balances it continues the string. An
+    /// empty `{}` holds no expression and stays in the text.
+    fn read_string_segment(&mut self, opening: bool) -> Result<Token, LexError> {
+        let start_pos = self.token_start;
+        let mut result = String::new();
         
         while let Some(ch) = self.current_char() {
             match ch {
                 '"' => {
                     self.advance(); // consume closing quote
-                    return Ok(Token::String(result));
+                    return Ok(if opening { Token::String(result) } else { Token::StringEnd(result) });
                 }
-                '\\' => {
-                    self.advance(); // consume backslash
-                    match self.current_char() {
-                        Some('n') => { result.push('\n'); self.advance(); }
-                        Some('t') => { result.push('\t'); self.advance(); }
-                        Some('r') => { result.push('\r'); self.advance(); }
-                        Some('\\') => { result.push('\\'); self.advance(); }
-                        Some('"') => { result.push('"'); self.advance(); }
-                        Some('\'') => { result.push('\''); self.advance(); }
-                        Some('0') => { result.push('\0'); self.advance(); }
-                        Some(_c) => {
-                            return Err(LexError {
-                                position: start_pos,
-                                kind: LexErrorKind::InvalidEscape,
-                            });
-                        }
-                        None => {
-                            return Err(LexError {
-                                position: start_pos,
-                                kind: LexErrorKind::UnterminatedString,
-                            });
-                        }
-                    }
+                '{' if self.peek_char() == Some('}') => {
+                    result.push_str("{}");
+                    self.advance();
+                    self.advance();
+                }
+                '{' => {
+                    self.advance(); // consume '{'
+                    self.interpolations.push(0);
+                    return Ok(if opening { Token::StringStart(result) } else { Token::StringMiddle(result) });
                 }
+                '\\' => result.push(self.read_escape(start_pos)?),
                 '\n' => {
                     return Err(LexError {
                         position: start_pos,
@@ -244,6 +349,179 @@ impl FluxLexer {
         })
     }
     
+    /// Read an escape sequence in a string, starting at its backslash
+    fn read_escape(&mut self, start_pos: Position) -> Result<char, LexError> {
+        self.advance(); // consume backslash
+        let ch = match self.current_char() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('0') => '\0',
+            Some('{') => '{',
+            Some('}') => '}',
+            Some(_) => {
+                return Err(LexError {
+                    position: start_pos,
+                    kind: LexErrorKind::InvalidEscape,
+                });
+            }
+            None => {
+                return Err(LexError {
+                    position: start_pos,
+                    kind: LexErrorKind::UnterminatedString,
+                });
+            }
+        };
+        self.advance();
+        Ok(ch)
+    }
+    
+    /// Whether the characters from `index` are `"""`
+    fn is_triple_quote(&self, index: usize) -> bool {
+        self.input.get(index..index + 3) == Some(&['"'; 3][..])
+    }
+    
+    /// Read a `"""` string, which may span lines. A line break straight
+    /// after the opening quotes is not part of the text, nor is a line
+    /// holding only the closing quotes, and the indentation the remaining
+    /// lines share is removed. Escapes work as in other strings; braces
+    /// are plain characters.
+    fn read_multiline_string(&mut self) -> Result<Token, LexError> {
+        let start_pos = self.token_start;
+        let unterminated = || LexError {
+            position: start_pos,
+            kind: LexErrorKind::UnterminatedString,
+        };
+        
+        for _ in 0..3 {
+            self.advance(); // consume opening quotes
+        }
+        let mut end = self.position;
+        while matches!(self.input.get(end), Some(' ' | '\t' | '\r')) {
+            end += 1;
+        }
+        let at_line_start = self.input.get(end) == Some(&'\n');
+        if at_line_start {
+            while self.position <= end {
+                self.advance();
+            }
+        }
+        let (indent, closing_alone) = self.multiline_layout(at_line_start).ok_or_else(unterminated)?;
+        
+        let mut result = String::new();
+        let mut line_start = at_line_start;
+        loop {
+            if line_start {
+                for _ in 0..indent {
+                    if !matches!(self.current_char(), Some(' ' | '\t')) {
+                        break;
+                    }
+                    self.advance();
+                }
+                line_start = false;
+            }
+            match self.current_char() {
+                Some('"') if self.is_triple_quote(self.position) => break,
+                Some('\\') => result.push(self.read_escape(start_pos)?),
+                Some(ch) => {
+                    result.push(ch);
+                    self.advance();
+                    line_start = ch == '\n';
+                }
+                None => return Err(unterminated()),
+            }
+        }
+        for _ in 0..3 {
+            self.advance(); // consume closing quotes
+        }
+        
+        if closing_alone {
+            if let Some(newline) = result.rfind('\n') {
+                result.truncate(newline);
+            }
+        }
+        Ok(Token::String(result))
+    }
+    
+    /// Scan a `"""` string's text without consuming it, giving the
+    /// indentation its lines share and whether its closing quotes are alone
+    /// on their line, or `None` when it is not closed. Lines holding only
+    /// whitespace do not count towards the indentation, except the closing
+    /// one.
+    fn multiline_layout(&self, at_line_start: bool) -> Option<(usize, bool)> {
+        let mut indent = usize::MAX;
+        // Start of the current line while it has held only whitespace
+        let mut line_start = at_line_start.then_some(self.position);
+        let mut index = self.position;
+        
+        loop {
+            match self.input.get(index) {
+                Some('"') if self.is_triple_quote(index) => {
+                    if let Some(start) = line_start {
+                        indent = indent.min(index - start);
+                    }
+                    return Some((if indent == usize::MAX { 0 } else { indent }, line_start.is_some()));
+                }
+                Some('\n') => line_start = Some(index + 1),
+                Some(' ' | '\t' | '\r') => {}
+                Some(ch) => {
+                    if let Some(start) = line_start.take() {
+                        indent = indent.min(index - start);
+                    }
+                    // An escaped character cannot end the string
+                    if *ch == '\\' {
+                        index += 1;
+                    }
+                }
+                None => return None,
+            }
+            index += 1;
+        }
+    }
+    
+    /// Whether a raw string starts at the current `r`: it is followed by
+    /// a quote, possibly after some `#`
+    fn at_raw_string(&self) -> bool {
+        let hashes = self.input[self.position + 1..].iter().take_while(|&&c| c == '#').count();
+        self.input.get(self.position + 1 + hashes) == Some(&'"')
+    }
+    
+    /// Read a raw string, `r"..."` or `r#"..."#`. Its text is taken as
+    /// written, so backslashes and braces are plain characters and it may
+    /// span lines. It ends at a quote followed by as many `#` as precede
+    /// the opening one.
+    fn read_raw_string(&mut self) -> Result<Token, LexError> {
+        let start_pos = self.token_start;
+        self.advance(); // consume 'r'
+        let mut hashes = 0;
+        while self.current_char() == Some('#') {
+            hashes += 1;
+            self.advance();
+        }
+        self.advance(); // consume opening quote
+        
+        let mut result = String::new();
+        while let Some(ch) = self.current_char() {
+            let closing_hashes = self.input.get(self.position + 1..self.position + 1 + hashes);
+            if ch == '"' && closing_hashes.is_some_and(|tail| tail.iter().all(|&c| c == '#')) {
+                for _ in 0..=hashes {
+                    self.advance(); // consume closing quote and hashes
+                }
+                return Ok(Token::String(result));
+            }
+            result.push(ch);
+            self.advance();
+        }
+        
+        Err(LexError {
+            position: start_pos,
+            kind: LexErrorKind::UnterminatedString,
+        })
+    }
+    
     /// Read a character literal
     fn read_character(&mut self) -> Result<Token, LexError> {
         let start_pos = self.current_pos;
@@ -306,6 +584,9 @@ impl FluxLexer {
             "func" => Token::Func,
             "struct" => Token::Struct,
             "class" => Token::Class,
+            "enum" => Token::Enum,
+            "interface" => Token::Interface,
+            "impl" => Token::Impl,
             "if" => Token::If,
             "else" => Token::Else,
             "match" => Token::Match,
@@ -326,6 +607,7 @@ impl FluxLexer {
             "extern" => Token::Extern,
             "true" => Token::Boolean(true),
             "false" => Token::Boolean(false),
+            "null" => Token::Null,
             _ => Token::Identifier(ident),
         }
     }
@@ -333,6 +615,7 @@ impl FluxLexer {
     /// Get the next token from the input stream
     pub fn next_token(&mut self) -> Result<Token, LexError> {
         self.skip_whitespace();
+        self.token_start = self.current_pos;
         
         match self.current_char() {
             None => Ok(Token::Eof),
@@ -349,14 +632,53 @@ impl FluxLexer {
                     // Single-character tokens
                     '(' => { self.advance(); Ok(Token::LeftParen) }
                     ')' => { self.advance(); Ok(Token::RightParen) }
-                    '{' => { self.advance(); Ok(Token::LeftBrace) }
-                    '}' => { self.advance(); Ok(Token::RightBrace) }
+                    '{' => {
+                        if let Some(depth) = self.interpolations.last_mut() {
+                            *depth += 1;
+                        }
+                        self.advance();
+                        Ok(Token::LeftBrace)
+                    }
+                    '}' => {
+                        self.advance();
+                        match self.interpolations.last_mut() {
+                            // The brace closes an interpolated expression
+                            Some(0) => {
+                                self.interpolations.pop();
+                                self.read_string_segment(false)
+                            }
+                            Some(depth) => {
+                                *depth -= 1;
+                                Ok(Token::RightBrace)
+                            }
+                            None => Ok(Token::RightBrace),
+                        }
+                    }
                     '[' => { self.advance(); Ok(Token::LeftBracket) }
                     ']' => { self.advance(); Ok(Token::RightBracket) }
                     ',' => { self.advance(); Ok(Token::Comma) }
                     ';' => { self.advance(); Ok(Token::Semicolon) }
-                    '.' => { self.advance(); Ok(Token::Dot) }
-                    '?' => { self.advance(); Ok(Token::Question) }
+                    '.' => {
+                        self.advance();
+                        if self.current_char() != Some('.') {
+                            return Ok(Token::Dot);
+                        }
+                        self.advance();
+                        if self.current_char() == Some('=') {
+                            self.advance();
+                            Ok(Token::DotDotEq)
+                        } else {
+                            Ok(Token::DotDot)
+                        }
+                    }
+                    '?' => {
+                        self.advance();
+                        match self.current_char() {
+                            Some('.') => { self.advance(); Ok(Token::QuestionDot) }
+                            Some('?') => { self.advance(); Ok(Token::DoubleQuestion) }
+                            _ => Ok(Token::Question)
+                        }
+                    }
                     '~' => { self.advance(); Ok(Token::BitwiseNot) }
                     
                     // Operators that might be multi-character
@@ -396,11 +718,23 @@ impl FluxLexer {
                                 self.advance(); 
                                 Ok(Token::DivideAssign) 
                             }
+                            // `///` starts a doc comment, but `////` does not
+                            Some('/') if self.peek_char() == Some('/') && self.input.get(self.position + 2) != Some(&'/') => {
+                                self.advance(); // consume second '/'
+                                self.advance(); // consume third '/'
+                                Ok(self.read_line_doc_comment())
+                            }
                             Some('/') => {
                                 self.advance(); // consume second '/'
                                 self.skip_line_comment();
                                 self.next_token() // recursively get next token after comment
                             }
+                            // `/**` starts a doc comment, but `/**/` and `/***` do not
+                            Some('*') if self.peek_char() == Some('*') && !matches!(self.input.get(self.position + 2), Some('*' | '/')) => {
+                                self.advance(); // consume first '*'
+                                self.advance(); // consume second '*'
+                                self.read_block_doc_comment()
+                            }
                             Some('*') => {
                                 self.advance(); // consume '*'
                                 self.skip_block_comment()?;
@@ -489,6 +823,11 @@ impl FluxLexer {
                         }
                     }
                     
+                    // Raw strings
+                    'r' if self.at_raw_string() => {
+                        self.read_raw_string()
+                    }
+                    
                     // Identifiers and keywords
                     c if c.is_alphabetic() || c == '_' => {
                         let ident = self.read_identifier();
@@ -501,6 +840,9 @@ impl FluxLexer {
                     }
                     
                     // String literals
+                    '"' if self.is_triple_quote(self.position) => {
+                        self.read_multiline_string()
+                    }
                     '"' => {
                         self.read_string()
                     }
@@ -531,6 +873,16 @@ impl FluxLexer {
         self.current_pos
     }
     
+    /// Get the start position of the most recently returned token
+    pub fn token_start(&self) -> Position {
+        self.token_start
+    }
+    
+    /// Source text a span covers
+    pub fn text(&self, span: Span) -> &str {
+        &self.source[span.start.offset..span.end.offset]
+    }
+    
     /// Check if we've reached the end of input
     pub fn is_at_end(&self) -> bool {
         self.current_char().is_none()
@@ -540,9 +892,12 @@ impl FluxLexer {
 impl Clone for FluxLexer {
     fn clone(&self) -> Self {
         Self {
+            source: self.source.clone(),
             input: self.input.clone(),
             position: self.position,
             current_pos: self.current_pos,
+            token_start: self.token_start,
+            interpolations: self.interpolations.clone(),
         }
     }
 }
@@ -741,6 +1096,35 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), Token::Eof);
     }
 
+    #[test]
+    fn test_radix_and_separated_literals() {
+        let mut lexer = FluxLexer::new("0xFF 0Xdead_BEEF 0o755 0b1010 1_000_000 1_0.2_5e1_0 0x7FFF_FFFF_FFFF_FFFF".to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(255));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(0xdead_beef));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(0o755));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(10));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(1_000_000));
+        assert_eq!(lexer.next_token().unwrap(), Token::Float(10.25e10));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(i64::MAX));
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+    }
+
+    #[test]
+    fn test_number_suffixes() {
+        let mut lexer = FluxLexer::new("10u8 0xFFu8 0b1u8 7i64 3.0f32 0.1f32 2f64 1e3f64".to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::Byte(10));
+        assert_eq!(lexer.next_token().unwrap(), Token::Byte(255));
+        assert_eq!(lexer.next_token().unwrap(), Token::Byte(1));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(7));
+        assert_eq!(lexer.next_token().unwrap(), Token::Float(3.0));
+        assert_eq!(lexer.next_token().unwrap(), Token::Float(0.1f32 as f64));
+        assert_eq!(lexer.next_token().unwrap(), Token::Float(2.0));
+        assert_eq!(lexer.next_token().unwrap(), Token::Float(1000.0));
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+    }
+
     #[test]
     fn test_scientific_notation() {
         let mut lexer = FluxLexer::new("1e5 2.5e-3 1.23E+10 5E0".to_string());
@@ -773,6 +1157,100 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), Token::Eof);
     }
 
+    #[test]
+    fn test_interpolated_strings() {
+        let mut lexer = FluxLexer::new(r#""Hello, {name}! You have {count + 1} items" "{m[{k}]} {"x{y}"}" "\{literal\}""#.to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::StringStart("Hello, ".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("name".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::StringMiddle("! You have ".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("count".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Plus);
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(1));
+        assert_eq!(lexer.next_token().unwrap(), Token::StringEnd(" items".to_string()));
+        
+        // Braces inside an expression balance, and strings nest
+        assert_eq!(lexer.next_token().unwrap(), Token::StringStart("".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("m".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::LeftBracket);
+        assert_eq!(lexer.next_token().unwrap(), Token::LeftBrace);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("k".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::RightBrace);
+        assert_eq!(lexer.next_token().unwrap(), Token::RightBracket);
+        assert_eq!(lexer.next_token().unwrap(), Token::StringMiddle(" ".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::StringStart("x".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("y".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::StringEnd("".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::StringEnd("".to_string()));
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::String("{literal}".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+        
+        // Empty braces are text
+        let mut lexer = FluxLexer::new(r#""{} and {x}{}""#.to_string());
+        assert_eq!(lexer.next_token().unwrap(), Token::StringStart("{} and ".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("x".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::StringEnd("{}".to_string()));
+        
+        // Text after an expression reports errors from its closing brace
+        let mut lexer = FluxLexer::new("\"{a} tail".to_string());
+        lexer.next_token().unwrap();
+        lexer.next_token().unwrap();
+        match lexer.next_token() {
+            Err(LexError { kind: LexErrorKind::UnterminatedString, position }) => assert_eq!(position.column, 4),
+            other => panic!("Expected UnterminatedString error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        let mut lexer = FluxLexer::new(r##"r"\d+{2}" r#"say "hi""# r"line
+next" raw"##.to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::String(r"\d+{2}".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::String(r#"say "hi""#.to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::String("line\nnext".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("raw".to_string()));
+        assert_eq!(lexer.token_start().line, 2);
+        assert_eq!(lexer.token_start().column, 7);
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+        
+        let mut lexer = FluxLexer::new(r##"r#"open" "##.to_string());
+        match lexer.next_token() {
+            Err(LexError { kind: LexErrorKind::UnterminatedString, .. }) => {},
+            other => panic!("Expected UnterminatedString error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_multiline_strings() {
+        let source = "let q = \"\"\"\n    SELECT *\n      FROM t\\twhere {x}\n\n    \"\"\"\nq";
+        let mut lexer = FluxLexer::new(source.to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::Let);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("q".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Assign);
+        assert_eq!(lexer.next_token().unwrap(), Token::String("SELECT *\n  FROM t\twhere {x}\n".to_string()));
+        assert_eq!(lexer.position().line, 5);
+        assert_eq!(lexer.position().column, 8);
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("q".to_string()));
+        assert_eq!(lexer.token_start().line, 6);
+        
+        // Text may start on the opening line, and the closing quotes may
+        // end the last line
+        let mut lexer = FluxLexer::new("\"\"\"a \"quoted\" word\n  b\"\"\" \"\"\"\n\"\"\"".to_string());
+        assert_eq!(lexer.next_token().unwrap(), Token::String("a \"quoted\" word\nb".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::String("".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+        
+        let mut lexer = FluxLexer::new("\"\"\"\n  never closed\n\"\"".to_string());
+        match lexer.next_token() {
+            Err(LexError { kind: LexErrorKind::UnterminatedString, position }) => assert_eq!(position.line, 1),
+            other => panic!("Expected UnterminatedString error, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_character_literals() {
         let mut lexer = FluxLexer::new("'a' 'Z' '5' ' '".to_string());
@@ -907,6 +1385,28 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), Token::Eof);
     }
 
+    #[test]
+    fn test_doc_comments() {
+        let source = "/// Adds two numbers\n///\n////not docs\nfunc /**/ f /** Inline */\n/**\n * Block\n *   indented\n */\n/** Block doc\n *  for Point. */\n/**\n    Plain\n      text\n*/";
+        let mut lexer = FluxLexer::new(source.to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::DocComment("Adds two numbers".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::DocComment("".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::Func);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("f".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::DocComment("Inline".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::DocComment("Block\n  indented".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::DocComment("Block doc\nfor Point.".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Newline);
+        assert_eq!(lexer.next_token().unwrap(), Token::DocComment("Plain\n  text".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+    }
+
     #[test]
     fn test_unterminated_block_comment_error() {
         let mut lexer = FluxLexer::new("hello /* unterminated comment".to_string());
@@ -927,6 +1427,9 @@ mod tests {
             ("func", Token::Func),
             ("struct", Token::Struct),
             ("class", Token::Class),
+            ("enum", Token::Enum),
+            ("interface", Token::Interface),
+            ("impl", Token::Impl),
             ("if", Token::If),
             ("else", Token::Else),
             ("match", Token::Match),
@@ -946,6 +1449,7 @@ mod tests {
             ("mut", Token::Mut),
             ("true", Token::Boolean(true)),
             ("false", Token::Boolean(false)),
+            ("null", Token::Null),
         ];
 
         for (keyword, expected_token) in keywords {
@@ -1009,6 +1513,8 @@ mod tests {
             (",", Token::Comma),
             (";", Token::Semicolon),
             (".", Token::Dot),
+            ("..", Token::DotDot),
+            ("..=", Token::DotDotEq),
             ("?", Token::Question),
         ];
 
@@ -1079,6 +1585,39 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), Token::Eof);
     }
 
+    #[test]
+    fn test_null_safety_operators() {
+        let mut lexer = FluxLexer::new("user?.name ?? \"anon\" x? y!".to_string());
+        
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("user".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::QuestionDot);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("name".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::DoubleQuestion);
+        assert_eq!(lexer.next_token().unwrap(), Token::String("anon".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("x".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Question);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("y".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Not);
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+    }
+
+    #[test]
+    fn test_range_operators() {
+        // An integer followed by `..` is not the start of a float
+        let mut lexer = FluxLexer::new("0..n 1..=10 x.y".to_string());
+
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(0));
+        assert_eq!(lexer.next_token().unwrap(), Token::DotDot);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("n".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(1));
+        assert_eq!(lexer.next_token().unwrap(), Token::DotDotEq);
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(10));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("x".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Dot);
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("y".to_string()));
+        assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+    }
+
     #[test]
     fn test_newlines() {
         let mut lexer = FluxLexer::new("hello\nworld\n".to_string());
@@ -1178,10 +1717,48 @@ mod tests {
 
     #[test]
     fn test_invalid_number_error() {
-        // This test is for future expansion when we add more complex number parsing
-        // For now, our simple parser shouldn't produce invalid numbers
         let mut lexer = FluxLexer::new("123".to_string());
         assert_eq!(lexer.next_token().unwrap(), Token::Integer(123));
+        
+        for source in ["0x", "0b102", "0o8", "1_", "1__", "0x_", "1_.5", "2.5_e3"] {
+            match FluxLexer::new(source.to_string()).next_token() {
+                Err(LexError { kind: LexErrorKind::InvalidNumber, .. }) => {},
+                other => panic!("Expected InvalidNumber error for {}, got {:?}", source, other),
+            }
+        }
+        
+        for source in ["10abc", "0xFFu16", "1.5u8", "0b1f32", "3i32"] {
+            match FluxLexer::new(source.to_string()).next_token() {
+                Err(LexError { kind: LexErrorKind::InvalidSuffix(_), .. }) => {},
+                other => panic!("Expected InvalidSuffix error for {}, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_number_out_of_range_error() {
+        let cases = [
+            ("9223372036854775809", "int"),
+            ("0x8000_0000_0000_0001", "int"),
+            ("256u8", "byte"),
+            ("0b1_0000_0000u8", "byte"),
+            ("1e400", "float"),
+            ("1e39f32", "f32"),
+        ];
+        for (source, type_) in cases {
+            match FluxLexer::new(format!("  {}", source)).next_token() {
+                Err(LexError { kind: LexErrorKind::NumberOutOfRange { literal, type_name }, position }) => {
+                    assert_eq!((literal.as_str(), type_name.as_str()), (source, type_));
+                    assert_eq!(position.column, 3);
+                }
+                other => panic!("Expected NumberOutOfRange error for {}, got {:?}", source, other),
+            }
+        }
+
+        // 2^63 is `i64::MIN` once negated, which the parser checks
+        let mut lexer = FluxLexer::new("9223372036854775808 0x8000_0000_0000_0000".to_string());
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(i64::MIN));
+        assert_eq!(lexer.next_token().unwrap(), Token::Integer(i64::MIN));
     }
 
     #[test]
@@ -10,9 +10,18 @@ pub enum Token {
     // Literals
     Integer(i64),
     Float(f64),
+    /// Integer with the `u8` suffix
+    Byte(u8),
     String(String),
+    /// Text of an interpolated string up to its first `{`: `"Hello, {`
+    StringStart(String),
+    /// Text between two interpolated expressions: `} and {`
+    StringMiddle(String),
+    /// Text after the last interpolated expression: `}!"`
+    StringEnd(String),
     Boolean(bool),
     Character(char),
+    Null,
     
     // Keywords
     Let,
@@ -20,6 +29,9 @@ pub enum Token {
     Func,
     Struct,
     Class,
+    Enum,
+    Interface,
+    Impl,
     If,
     Else,
     Match,
@@ -83,12 +95,17 @@ pub enum Token {
     Colon,          // :
     DoubleColon,    // ::
     Dot,            // .
+    DotDot,         // ..
+    DotDotEq,       // ..=
     Arrow,          // ->
     FatArrow,       // =>
     Question,       // ?
+    QuestionDot,    // ?.
+    DoubleQuestion, // ??
     
     // Special
     Identifier(String),
+    DocComment(String), // `///` or `/** */`, with the comment markers removed
     Newline,
     Eof,
 }
@@ -98,15 +115,23 @@ impl fmt::Display for Token {
         match self {
             Token::Integer(n) => write!(f, "{}", n),
             Token::Float(n) => write!(f, "{}", n),
+            Token::Byte(n) => write!(f, "{}u8", n),
             Token::String(s) => write!(f, "\"{}\"", s),
+            Token::StringStart(s) => write!(f, "\"{}{{", s),
+            Token::StringMiddle(s) => write!(f, "}}{}{{", s),
+            Token::StringEnd(s) => write!(f, "}}{}\"", s),
             Token::Boolean(b) => write!(f, "{}", b),
             Token::Character(c) => write!(f, "'{}'", c),
+            Token::Null => write!(f, "null"),
             
             Token::Let => write!(f, "let"),
             Token::Const => write!(f, "const"),
             Token::Func => write!(f, "func"),
             Token::Struct => write!(f, "struct"),
             Token::Class => write!(f, "class"),
+            Token::Enum => write!(f, "enum"),
+            Token::Interface => write!(f, "interface"),
+            Token::Impl => write!(f, "impl"),
             Token::If => write!(f, "if"),
             Token::Else => write!(f, "else"),
             Token::Match => write!(f, "match"),
@@ -166,11 +191,16 @@ impl fmt::Display for Token {
             Token::Colon => write!(f, ":"),
             Token::DoubleColon => write!(f, "::"),
             Token::Dot => write!(f, "."),
+            Token::DotDot => write!(f, ".."),
+            Token::DotDotEq => write!(f, "..="),
             Token::Arrow => write!(f, "->"),
             Token::FatArrow => write!(f, "=>"),
             Token::Question => write!(f, "?"),
+            Token::QuestionDot => write!(f, "?."),
+            Token::DoubleQuestion => write!(f, "??"),
             
             Token::Identifier(name) => write!(f, "{}", name),
+            Token::DocComment(text) => write!(f, "/**{}*/", text),
             Token::Newline => write!(f, "\\n"),
             Token::Eof => write!(f, "EOF"),
         }
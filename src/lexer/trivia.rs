@@ -0,0 +1,150 @@
+//! Lossless token stream
+//!
+//! The parser reads tokens without the whitespace and comments between them.
+//! Tools that rewrite source, such as the formatter, need those as well, so
+//! here every token keeps its source text and the trivia before it.
+
+use crate::error::LexError;
+use crate::position::{Position, Span};
+
+use super::{FluxLexer, Token};
+
+/// Kind of source text between tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and carriage returns
+    Whitespace,
+    Newline,
+    /// `// ...`, without the line break ending it
+    LineComment,
+    /// `/* ... */`
+    BlockComment,
+}
+
+/// Whitespace or a comment before a token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+impl Trivia {
+    /// Whether this is a comment. Doc comments are tokens rather than trivia.
+    pub fn is_comment(&self) -> bool {
+        matches!(self.kind, TriviaKind::LineComment | TriviaKind::BlockComment)
+    }
+}
+
+/// Token with its source text and the trivia before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub token: Token,
+    pub text: String,
+    pub span: Span,
+    pub leading_trivia: Vec<Trivia>,
+}
+
+/// Split `source` into tokens that keep their trivia, ending with `Eof`,
+/// whose trivia is what follows the last token. Writing out the trivia and
+/// text of every token gives back `source` exactly.
+pub fn tokenize(source: &str) -> Result<Vec<SyntaxToken>, LexError> {
+    let mut lexer = FluxLexer::new(source.to_string());
+    let mut tokens = Vec::new();
+    let mut trivia = Vec::new();
+    let mut end = Position::start();
+
+    loop {
+        let token = lexer.next_token()?;
+        let start = lexer.token_start();
+        split_trivia(source, end, start, &mut trivia);
+        end = lexer.position();
+
+        let span = Span::new(start, end);
+        let text = source[start.offset..end.offset].to_string();
+        match token {
+            // Line breaks are tokens only to the parser
+            Token::Newline => trivia.push(Trivia { kind: TriviaKind::Newline, text, span }),
+            Token::Eof => {
+                tokens.push(SyntaxToken { token, text, span, leading_trivia: trivia });
+                return Ok(tokens);
+            }
+            token => tokens.push(SyntaxToken { token, text, span, leading_trivia: std::mem::take(&mut trivia) }),
+        }
+    }
+}
+
+/// Split the source between two tokens, which the lexer skipped, into trivia
+fn split_trivia(source: &str, start: Position, end: Position, trivia: &mut Vec<Trivia>) {
+    let mut position = start;
+
+    while position.offset < end.offset {
+        let rest = &source[position.offset..end.offset];
+        let (kind, len) = if rest.starts_with("//") {
+            (TriviaKind::LineComment, rest.find('\n').unwrap_or(rest.len()))
+        } else if rest.starts_with("/*") {
+            (TriviaKind::BlockComment, rest.find("*/").map_or(rest.len(), |i| i + 2))
+        } else if rest.starts_with('\n') {
+            (TriviaKind::Newline, 1)
+        } else {
+            let len = rest.find(|c: char| !c.is_whitespace() || c == '\n').unwrap_or(rest.len());
+            (TriviaKind::Whitespace, len)
+        };
+
+        let text = &rest[..len];
+        let trivia_start = position;
+        for ch in text.chars() {
+            position.advance(ch);
+        }
+        trivia.push(Trivia { kind, text: text.to_string(), span: Span::new(trivia_start, position) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_out(tokens: &[SyntaxToken]) -> String {
+        let mut source = String::new();
+        for token in tokens {
+            for trivia in &token.leading_trivia {
+                source.push_str(&trivia.text);
+            }
+            source.push_str(&token.text);
+        }
+        source
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "// header\r\n\nfunc main() {  /* note\n  more */\n    let s = \"a{x + 1}b\";\t// tail\n\n    /// doc\n}\n";
+        let tokens = tokenize(source).unwrap();
+        assert_eq!(write_out(&tokens), source);
+        assert_eq!(tokens.last().unwrap().token, Token::Eof);
+    }
+
+    #[test]
+    fn test_trivia_attachment() {
+        let tokens = tokenize("a // one\n  /* two */ b").unwrap();
+
+        assert_eq!(tokens[0].token, Token::Identifier("a".to_string()));
+        assert!(tokens[0].leading_trivia.is_empty());
+
+        let b = &tokens[1];
+        assert_eq!(b.text, "b");
+        let kinds: Vec<_> = b.leading_trivia.iter().map(|trivia| trivia.kind).collect();
+        assert_eq!(kinds, vec![
+            TriviaKind::Whitespace,
+            TriviaKind::LineComment,
+            TriviaKind::Newline,
+            TriviaKind::Whitespace,
+            TriviaKind::BlockComment,
+            TriviaKind::Whitespace,
+        ]);
+        assert_eq!(b.leading_trivia[1].text, "// one");
+        assert_eq!(b.leading_trivia[4].span.start.line, 2);
+        assert_eq!(b.leading_trivia[4].span.start.column, 3);
+        assert!(b.leading_trivia[4].is_comment());
+        assert_eq!(b.span.start.column, 13);
+    }
+}
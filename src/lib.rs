@@ -13,6 +13,7 @@ pub mod runtime;
 pub mod std;
 pub mod package;
 pub mod cli;
+pub mod repl;
 pub mod tools;
 pub mod ffi;
 
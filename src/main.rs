@@ -7,8 +7,9 @@ use std::process;
 use std::path::Path;
 
 use flux_compiler::cli::{Cli, Commands, CliContext, CompilerDriver};
-use flux_compiler::tools::{Formatter, Linter, TestRunner, LintSeverity};
+use flux_compiler::tools::{DocGenerator, Formatter, Linter, TestRunner, LintSeverity};
 use flux_compiler::error::FluxResult;
+use flux_compiler::repl::Repl;
 
 fn main() {
     let cli = Cli::parse();
@@ -24,7 +25,7 @@ fn main() {
     let context = CliContext::new(cli.verbose, cli.quiet);
     
     let result = match &cli.command {
-        Commands::Build { mode, target, output, optimize, check, progress, path } => {
+        Commands::Build { mode, target, output, optimize, check, emit, progress, path } => {
             let driver = CompilerDriver::new(context.clone());
             // Create a temporary build args structure
             let build_command = Commands::Build {
@@ -33,16 +34,18 @@ fn main() {
                 output: output.clone(),
                 optimize: *optimize,
                 check: *check,
+                emit: emit.clone(),
                 progress: *progress,
                 path: path.clone(),
             };
             driver.build(&build_command)
         }
         
-        Commands::Run { mode, args, path } => {
+        Commands::Run { mode, bytecode, args, path } => {
             let driver = CompilerDriver::new(context.clone());
             let run_command = Commands::Run {
                 mode: mode.clone(),
+                bytecode: *bytecode,
                 args: args.clone(),
                 path: path.clone(),
             };
@@ -83,10 +86,17 @@ fn main() {
         Commands::Init { name, template, path } => {
             handle_init_command(&context, name.as_deref(), template, path)
         }
+        
+        Commands::Repl { files } => {
+            handle_repl_command(&context, files)
+        }
     };
 
     if let Err(e) = result {
-        context.error(&format!("{}", e));
+        match e.report() {
+            Some(report) => eprint!("{}", report),
+            None => context.error(&format!("{}", e)),
+        }
         process::exit(1);
     }
 }
@@ -232,8 +242,27 @@ fn handle_bench_command(context: &CliContext, filter: Option<&str>, iterations:
 fn handle_doc_command(context: &CliContext, open: bool, private: bool, output: Option<&Path>, path: &Path) -> FluxResult<()> {
     context.info("Generating documentation...");
     
-    // TODO: Implement documentation generator
-    context.info("Documentation generator not yet implemented");
+    let generator = DocGenerator::new(context.clone(), private);
+    let files = if path.is_dir() {
+        find_flux_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let output_dir = output.unwrap_or(Path::new("target/doc"));
+    std::fs::create_dir_all(output_dir).map_err(|e| flux_compiler::FluxError::Io(e.to_string()))?;
+    
+    for file in &files {
+        let markdown = generator.document_file(file)?;
+        let name = file.file_stem().and_then(|s| s.to_str()).unwrap_or("module");
+        let doc_path = output_dir.join(format!("{}.md", name));
+        std::fs::write(&doc_path, markdown).map_err(|e| flux_compiler::FluxError::Io(e.to_string()))?;
+        context.verbose(&format!("Wrote {:?}", doc_path));
+    }
+    
+    context.success(&format!("Documented {} files in {:?}", files.len(), output_dir));
+    if open {
+        context.info("Markdown documentation cannot be opened in a browser; read it from the output directory");
+    }
     
     Ok(())
 }
@@ -262,6 +291,18 @@ fn handle_init_command(context: &CliContext, name: Option<&str>, template: &flux
     Ok(())
 }
 
+fn handle_repl_command(context: &CliContext, files: &[std::path::PathBuf]) -> FluxResult<()> {
+    let mut repl = Repl::new();
+    for file in files {
+        context.info(&repl.eval(&format!(":load {}", file.display()))?);
+    }
+    
+    context.info("Flux REPL. Type :help for commands, :quit to exit.");
+    let stdin = std::io::stdin();
+    repl.run(stdin.lock(), std::io::stdout())
+        .map_err(|e| flux_compiler::FluxError::Io(e.to_string()))
+}
+
 fn find_flux_files(dir: &Path) -> FluxResult<Vec<std::path::PathBuf>> {
     let mut files = Vec::new();
     
@@ -33,6 +33,7 @@ pub struct BuildConfig {
     pub verbose: bool,
     pub wasm_target: bool,
     pub generate_js_bindings: bool,
+    pub bytecode_target: bool,
 }
 
 /// Optimization levels for compilation
@@ -480,6 +481,7 @@ impl BuildConfig {
             verbose: false,
             wasm_target: false,
             generate_js_bindings: false,
+            bytecode_target: false,
         })
     }
     
@@ -495,6 +497,7 @@ impl BuildConfig {
             verbose: false,
             wasm_target: false,
             generate_js_bindings: false,
+            bytecode_target: false,
         }
     }
     
@@ -510,6 +513,7 @@ impl BuildConfig {
             verbose: false,
             wasm_target: false,
             generate_js_bindings: false,
+            bytecode_target: false,
         }
     }
 }
@@ -570,8 +574,8 @@ impl DependencyGraph {
             if line.starts_with("import ") {
                 // Extract module name from import statement
                 if let Some(module_name) = line.strip_prefix("import ").and_then(|s| s.split_whitespace().next()) {
-                    // Remove semicolon if present
-                    let module_name = module_name.trim_end_matches(';');
+                    // Remove semicolon and quotes around the import path
+                    let module_name = module_name.trim_end_matches(';').trim_matches('"');
                     
                     // Convert module name to file path
                     let module_path = self.module_name_to_path(module_name, file)?;
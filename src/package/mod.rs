@@ -13,7 +13,7 @@ pub mod metadata;
 pub mod build;
 
 pub use config::{ProjectConfig, PackageInfo, DependencySpec, BuildConfig as ConfigBuildConfig, OptimizationLevel as ConfigOptimizationLevel};
-pub use project::{Project, ProjectInstance, ProjectMetadata, TestResults};
+pub use project::{AnalyzedProject, Project, ProjectInstance, ProjectMetadata, TestResults};
 pub use dependency::{ResolvedDependency, DependencySource, DependencyResolver, VersionReq, RegistryPackage};
 pub use metadata::{PackageMetadata, BuildMetadata, BuildArtifact, ArtifactType, CompilationUnit, LockFile, LockedDependency};
 pub use build::{BuildSystem, BuildConfig, OptimizationLevel, DependencyGraph, DependencyNode, BuildResult, CompilationTarget};
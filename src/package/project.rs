@@ -1,14 +1,32 @@
 //! Project directory structure creation and management
 
-use crate::error::{FluxError, PackageError};
+use crate::error::{FluxError, PackageError, ParseError, RuntimeError, RuntimeErrorKind, SemanticError};
+use crate::lexer::FluxLexer;
+use crate::parser::FluxParser;
+use crate::codegen::bytecode::BytecodeProgram;
+use crate::codegen::bytecode_compiler::BytecodeCompiler;
+use crate::runtime::{Interpreter, Vm};
+use crate::semantic::modules::ModuleGraph;
+use crate::semantic::TypedProgram;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Stack size of the thread `flux run` interprets the program on
+const INTERPRETER_STACK_SIZE: usize = 512 * 1024 * 1024;
 
 /// Project structure manager
 pub struct Project {
     metadata: ProjectMetadata,
 }
 
+/// `src/main.flux` and the modules it imports, analyzed once so that
+/// reporting warnings and running or compiling the program share the result
+pub struct AnalyzedProject {
+    graph: ModuleGraph,
+    programs: Vec<TypedProgram>,
+    warnings: Vec<(PathBuf, SemanticError)>,
+}
+
 /// Project instance for build operations
 #[derive(Debug, Clone)]
 pub struct ProjectInstance {
@@ -40,8 +58,9 @@ impl ProjectInstance {
         &self.metadata.name
     }
 
-    /// Build the project
-    pub fn build(&self, build_config: &super::BuildConfig) -> Result<(), FluxError> {
+    /// Build the project; a bytecode target is compiled from `analyzed`
+    /// when the project has already been analyzed
+    pub fn build(&self, build_config: &super::BuildConfig, analyzed: Option<&AnalyzedProject>) -> Result<(), FluxError> {
         use super::{BuildSystem, PackageMetadata};
 
         // Create package metadata from project metadata
@@ -65,44 +84,73 @@ impl ProjectInstance {
             )));
         }
 
+        if build_config.bytecode_target {
+            let output_dir = self.metadata.root_path.join(&build_config.output_dir);
+            fs::create_dir_all(&output_dir)
+                .map_err(|e| FluxError::Package(PackageError::IoError(e.to_string())))?;
+            let bytecode = match analyzed {
+                Some(analyzed) => analyzed.compile_bytecode()?,
+                None => self.compile_bytecode()?,
+            };
+            fs::write(output_dir.join(format!("{}.fbc", self.name())), bytecode.encode())
+                .map_err(|e| FluxError::Package(PackageError::IoError(e.to_string())))?;
+        }
+
         Ok(())
     }
 
-    /// Run the project executable
-    pub fn run(&self, args: &[String]) -> Result<(), FluxError> {
-        use std::process::Command;
-
-        // Find the executable
-        let target_dir = self.metadata.root_path.join("target").join("debug");
-        let executable_name = if cfg!(windows) {
-            format!("{}.exe", self.metadata.name)
-        } else {
-            self.metadata.name.clone()
-        };
-        let executable_path = target_dir.join(&executable_name);
-
-        if !executable_path.exists() {
-            return Err(FluxError::Package(PackageError::ProjectCreationFailed(
-                format!("Executable not found: {}", executable_path.display())
-            )));
+    /// Parse every source file of the project and collect all syntax errors,
+    /// rather than stopping at the first one
+    pub fn syntax_errors(&self) -> Result<Vec<(PathBuf, ParseError)>, FluxError> {
+        let mut errors = Vec::new();
+        for file in &self.metadata.source_files {
+            let source = fs::read_to_string(file).map_err(|e| FluxError::Io(e.to_string()))?;
+            match FluxParser::new(FluxLexer::new(source)) {
+                Ok(mut parser) => {
+                    let (_, file_errors) = parser.parse_program_with_errors();
+                    errors.extend(file_errors.into_iter().map(|error| (file.clone(), error)));
+                }
+                Err(error) => errors.push((file.clone(), error)),
+            }
         }
+        Ok(errors)
+    }
 
-        // Run the executable
-        let mut cmd = Command::new(&executable_path);
-        cmd.args(args);
+    /// Analyze `src/main.flux` and the modules it imports
+    pub fn analyze(&self) -> Result<AnalyzedProject, FluxError> {
+        let graph = ModuleGraph::load(self.metadata.main_file())?;
+        let module_file = |path: &str| graph.get(path)
+            .and_then(|module| module.files.first().cloned())
+            .unwrap_or_default();
+        let analyzed = graph.analyze_with_warnings().map_err(|errors| FluxError::SemanticErrors(
+            errors.into_iter().map(|(module, error)| (module_file(&module), error)).collect()
+        ))?;
+        let modules = graph.topological_order()?;
+
+        // Warnings are paired with the file of the module each one is in
+        let mut programs = Vec::new();
+        let mut warnings = Vec::new();
+        for (module, (program, module_warnings)) in modules.iter().zip(analyzed) {
+            let file = module.files.first().cloned().unwrap_or_default();
+            warnings.extend(module_warnings.into_iter().map(|warning| (file.clone(), warning)));
+            programs.push(program);
+        }
+        Ok(AnalyzedProject { graph, programs, warnings })
+    }
 
-        let status = cmd.status()
-            .map_err(|e| FluxError::Package(PackageError::IoError(
-                format!("Failed to run executable: {}", e)
-            )))?;
+    /// Compile `src/main.flux` and the modules it imports to bytecode
+    pub fn compile_bytecode(&self) -> Result<BytecodeProgram, FluxError> {
+        self.analyze()?.compile_bytecode()
+    }
 
-        if !status.success() {
-            return Err(FluxError::Package(PackageError::ProjectCreationFailed(
-                format!("Executable exited with code: {:?}", status.code())
-            )));
-        }
+    /// Run the project by interpreting `src/main.flux` and the modules it imports
+    pub fn run(&self, args: &[String]) -> Result<(), FluxError> {
+        self.analyze()?.run(args)
+    }
 
-        Ok(())
+    /// Run the project on the bytecode VM instead of the tree-walking interpreter
+    pub fn run_bytecode(&self, args: &[String]) -> Result<(), FluxError> {
+        self.analyze()?.run_bytecode(args)
     }
 
     /// Run tests for the project
@@ -127,6 +175,81 @@ impl ProjectInstance {
     }
 }
 
+impl AnalyzedProject {
+    /// The warnings found, with the file of the module each one is in
+    pub fn warnings(&self) -> &[(PathBuf, SemanticError)] {
+        &self.warnings
+    }
+
+    /// Compile the analyzed modules to bytecode
+    pub fn compile_bytecode(&self) -> Result<BytecodeProgram, FluxError> {
+        let modules = self.graph.topological_order()?;
+
+        let mut compiler = BytecodeCompiler::new();
+        for (module, program) in modules.iter().zip(&self.programs) {
+            compiler.add_module(&module.path, program, module.files.first().map(|file| file.as_path()))?;
+        }
+        Ok(compiler.into_program())
+    }
+
+    /// Run the program with the tree-walking interpreter
+    pub fn run(&self, args: &[String]) -> Result<(), FluxError> {
+        Self::run_on_large_stack(|| self.interpret(args))
+    }
+
+    /// Run the program on the bytecode VM
+    pub fn run_bytecode(&self, args: &[String]) -> Result<(), FluxError> {
+        Self::run_on_large_stack(|| self.execute_bytecode(args))
+    }
+
+    fn run_on_large_stack<F>(run: F) -> Result<(), FluxError>
+    where
+        F: FnOnce() -> Result<(), FluxError> + Send,
+    {
+        // Interpreted recursion is much deeper on the Rust stack than in
+        // compiled code, so the program gets a thread with a large stack
+        std::thread::scope(|scope| {
+            std::thread::Builder::new()
+                .stack_size(INTERPRETER_STACK_SIZE)
+                .spawn_scoped(scope, run)
+                .map_err(|e| FluxError::Io(e.to_string()))?
+                .join()
+                .unwrap_or_else(|_| Err(FluxError::Runtime(RuntimeError {
+                    kind: RuntimeErrorKind::Panic { message: "interpreter thread panicked".to_string() },
+                })))
+        })
+    }
+
+    fn interpret(&self, args: &[String]) -> Result<(), FluxError> {
+        let modules = self.graph.topological_order()?;
+
+        let mut interpreter = Interpreter::new();
+        for (module, program) in modules.iter().zip(&self.programs) {
+            for file in &module.files {
+                let source = fs::read_to_string(file).map_err(|e| FluxError::Io(e.to_string()))?;
+                interpreter.add_source_file(file.clone(), source);
+            }
+            interpreter.load(&module.path, program, module.files.first().map(|file| file.as_path()))?;
+        }
+
+        interpreter.run_main(args)?;
+        Ok(())
+    }
+
+    fn execute_bytecode(&self, args: &[String]) -> Result<(), FluxError> {
+        let mut vm = Vm::new(self.compile_bytecode()?);
+        for module in self.graph.topological_order()? {
+            for file in &module.files {
+                let source = fs::read_to_string(file).map_err(|e| FluxError::Io(e.to_string()))?;
+                vm.add_source_file(file.clone(), source);
+            }
+        }
+
+        vm.run_main(args)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct TestResults {
     pub passed: usize,
@@ -167,23 +290,23 @@ pub func hello() -> string {
         
         // Create a basic test file
         let test_content = r#"// Tests for the main library
-import std/test;
-import lib;
+import "std/test"
+import "lib"
 
 #[test]
 func test_hello() {
-    let result = lib.hello();
-    test.assert_eq(result, "Hello from lib!");
+    let result = lib::hello();
+    test::assert_eq(result, "Hello from lib!");
 }
 "#;
         Self::create_file(&root.join("tests").join("lib_test.flux"), test_content)?;
         
         // Create an example file
         let example_content = r#"// Example usage of the library
-import lib;
+import "lib"
 
 func main() {
-    let message = lib.hello();
+    let message = lib::hello();
     println(message);
 }
 "#;
@@ -2,8 +2,25 @@
 //! 
 //! Defines all AST node types that represent the structure of Flux programs.
 
+use crate::position::{Position, Span};
+use std::collections::HashMap;
 use std::fmt;
 
+/// Implement `PartialEq` for a node by comparing every field except its span,
+/// so trees parsed from differently formatted sources compare equal
+macro_rules! impl_eq_ignoring_span {
+    ($($node:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl PartialEq for $node {
+                fn eq(&self, other: &Self) -> bool {
+                    let $node { $($field,)* span: _ } = self;
+                    true $(&& *$field == other.$field)*
+                }
+            }
+        )*
+    };
+}
+
 /// Root node representing a complete Flux program
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
@@ -13,10 +30,22 @@ pub struct Program {
 }
 
 /// Import declaration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Import {
     pub path: String,
     pub alias: Option<String>,
+    pub span: Span,
+}
+
+impl Import {
+    /// Name the import is bound to in the importing file: the alias if
+    /// present, otherwise the last segment of the path
+    pub fn binding_name(&self) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None => self.path.rsplit('/').next().unwrap_or(&self.path),
+        }
+    }
 }
 
 /// Top-level item in a program
@@ -25,12 +54,61 @@ pub enum Item {
     Function(Function),
     Struct(Struct),
     Class(Class),
+    Enum(Enum),
+    Interface(Interface),
+    Impl(Impl),
     Const(Const),
     ExternFunction(ExternFunction),
 }
 
+impl Item {
+    /// Name the item is declared under. An impl block declares no name of its
+    /// own and reports the type it implements the interface for.
+    pub fn name(&self) -> &str {
+        match self {
+            Item::Function(func) => &func.name,
+            Item::Struct(struct_def) => &struct_def.name,
+            Item::Class(class_def) => &class_def.name,
+            Item::Enum(enum_def) => &enum_def.name,
+            Item::Interface(interface) => &interface.name,
+            Item::Impl(impl_block) => &impl_block.type_name,
+            Item::Const(const_def) => &const_def.name,
+            Item::ExternFunction(extern_func) => &extern_func.name,
+        }
+    }
+    
+    /// Source span of the whole declaration
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Function(func) => func.span,
+            Item::Struct(struct_def) => struct_def.span,
+            Item::Class(class_def) => class_def.span,
+            Item::Enum(enum_def) => enum_def.span,
+            Item::Interface(interface) => interface.span,
+            Item::Impl(impl_block) => impl_block.span,
+            Item::Const(const_def) => const_def.span,
+            Item::ExternFunction(extern_func) => extern_func.span,
+        }
+    }
+    
+    /// Visibility of the item outside its module
+    pub fn visibility(&self) -> &Visibility {
+        match self {
+            Item::Function(func) => &func.visibility,
+            Item::Struct(struct_def) => &struct_def.visibility,
+            Item::Class(class_def) => &class_def.visibility,
+            Item::Enum(enum_def) => &enum_def.visibility,
+            Item::Interface(interface) => &interface.visibility,
+            // Implementations are as visible as the type and interface they join
+            Item::Impl(_) => &Visibility::Public,
+            Item::Const(const_def) => &const_def.visibility,
+            Item::ExternFunction(extern_func) => &extern_func.visibility,
+        }
+    }
+}
+
 /// External function declaration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct ExternFunction {
     pub name: String,
     pub parameters: Vec<Parameter>,
@@ -38,55 +116,160 @@ pub struct ExternFunction {
     pub library: Option<String>,
     pub is_variadic: bool,
     pub visibility: Visibility,
+    pub span: Span,
 }
 
 /// Function declaration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Function {
     pub name: String,
+    /// Type parameters of a generic function, such as `T` in `func id<T>`
+    pub type_params: Vec<String>,
     pub parameters: Vec<Parameter>,
     pub return_type: Option<Type>,
     pub body: Block,
     pub is_async: bool,
     pub visibility: Visibility,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 /// Function parameter
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Parameter {
     pub name: String,
     pub type_: Type,
     pub is_mutable: bool,
+    pub span: Span,
+}
+
+/// Lambda parameter; without an annotation its type is inferred from how
+/// the lambda is used
+#[derive(Debug, Clone)]
+pub struct LambdaParameter {
+    pub name: String,
+    pub type_: Option<Type>,
+    pub is_mutable: bool,
+    pub span: Span,
 }
 
 /// Struct declaration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Struct {
     pub name: String,
+    pub type_params: Vec<String>,
     pub fields: Vec<Field>,
     pub visibility: Visibility,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 /// Class declaration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Class {
     pub name: String,
+    pub type_params: Vec<String>,
     pub fields: Vec<Field>,
     pub methods: Vec<Method>,
     pub visibility: Visibility,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
-/// Struct or class field
+/// Enum declaration: a tagged union of variants
+#[derive(Debug, Clone)]
+pub struct Enum {
+    pub name: String,
+    pub variants: Vec<Variant>,
+    pub visibility: Visibility,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl Enum {
+    /// Look up a variant by name
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+}
+
+/// Enum variant
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: String,
+    pub payload: VariantPayload,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+/// Data carried by an enum variant
 #[derive(Debug, Clone, PartialEq)]
+pub enum VariantPayload {
+    /// `Empty`
+    Unit,
+    /// `Circle(float)`
+    Tuple(Vec<Type>),
+    /// `Rect { w: float, h: float }`
+    Struct(Vec<Field>),
+}
+
+/// Interface declaration: a set of method signatures
+#[derive(Debug, Clone)]
+pub struct Interface {
+    pub name: String,
+    pub methods: Vec<MethodSignature>,
+    pub visibility: Visibility,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl Interface {
+    /// Look up a required method by name
+    pub fn method(&self, name: &str) -> Option<&MethodSignature> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+/// Method required by an interface; the receiver is implicit
+#[derive(Debug, Clone)]
+pub struct MethodSignature {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<Type>,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+/// `impl Interface for Type { ... }` block
+#[derive(Debug, Clone)]
+pub struct Impl {
+    pub interface: String,
+    pub type_name: String,
+    pub methods: Vec<Method>,
+    pub span: Span,
+}
+
+/// Struct or class field
+#[derive(Debug, Clone)]
 pub struct Field {
     pub name: String,
     pub type_: Type,
     pub visibility: Visibility,
     pub is_mutable: bool,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 /// Class method
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Method {
     pub name: String,
     pub parameters: Vec<Parameter>,
@@ -94,15 +277,21 @@ pub struct Method {
     pub body: Block,
     pub visibility: Visibility,
     pub is_static: bool,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 /// Constant declaration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Const {
     pub name: String,
     pub type_: Type,
     pub value: Expression,
     pub visibility: Visibility,
+    /// Text of the doc comment before the declaration
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 /// Visibility modifier
@@ -128,6 +317,9 @@ pub enum Type {
     List(Box<Type>),
     Map(Box<Type>, Box<Type>),
     Set(Box<Type>),
+    Tuple(Vec<Type>),
+    /// Integer range such as `0..n`
+    Range,
     
     // User-defined types
     Named(String),
@@ -145,17 +337,53 @@ pub enum Type {
     Never,
 }
 
+impl Type {
+    /// Replace type parameters, which appear as named types, by the types
+    /// bound to them
+    pub fn substitute(&self, bindings: &HashMap<String, Type>) -> Type {
+        let sub = |t: &Type| Box::new(t.substitute(bindings));
+        match self {
+            Type::Named(name) => bindings.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Type::Array(t) => Type::Array(sub(t)),
+            Type::List(t) => Type::List(sub(t)),
+            Type::Set(t) => Type::Set(sub(t)),
+            Type::Map(k, v) => Type::Map(sub(k), sub(v)),
+            Type::Tuple(elements) => Type::Tuple(elements.iter().map(|e| e.substitute(bindings)).collect()),
+            Type::Nullable(t) => Type::Nullable(sub(t)),
+            Type::Result(ok, err) => Type::Result(sub(ok), sub(err)),
+            Type::Function(params, ret) => Type::Function(
+                params.iter().map(|p| p.substitute(bindings)).collect(),
+                sub(ret),
+            ),
+            Type::Generic(name, args) => Type::Generic(
+                name.clone(),
+                args.iter().map(|a| a.substitute(bindings)).collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+}
+
 /// Block of statements
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Block {
     pub statements: Vec<Statement>,
+    pub span: Span,
 }
 
-/// Statement
+/// Statement with the source span it was parsed from
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+/// Kind of statement
 #[derive(Debug, Clone, PartialEq)]
-pub enum Statement {
+pub enum StatementKind {
     Expression(Expression),
-    Let(String, Option<Type>, Option<Expression>),
+    /// `let x = v`, or a destructuring `let (a, b) = v`, which needs a value
+    Let(Pattern, Option<Type>, Option<Expression>),
     Const(String, Type, Expression),
     Assignment(Expression, Expression),
     Return(Option<Expression>),
@@ -164,26 +392,29 @@ pub enum Statement {
     Go(Expression),
     If(Expression, Block, Option<Block>),
     While(Expression, Block),
-    For(String, Expression, Block),
+    For(Pattern, Expression, Block),
     Match(Expression, Vec<MatchArm>),
 }
 
 /// Match arm
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct MatchArm {
     pub pattern: Pattern,
     pub guard: Option<Expression>,
     pub body: Block,
+    pub span: Span,
 }
 
 /// Pattern for match expressions
 #[derive(Debug, Clone, PartialEq)]
 pub enum Pattern {
-    Literal(Literal),
+    Literal(Literal, Spelling),
     Identifier(String),
     Wildcard,
     Tuple(Vec<Pattern>),
     Struct(String, Vec<(String, Pattern)>),
+    /// Enum variant such as `Shape::Circle(r)` or `Shape::Empty`
+    Variant(Vec<String>, Vec<Pattern>),
     Result(ResultPattern),
 }
 
@@ -194,11 +425,50 @@ pub enum ResultPattern {
     Err(Box<Pattern>),
 }
 
-/// Expression
+/// Expression with the source span it was parsed from
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+    /// How a literal was written
+    pub spelling: Spelling,
+}
+
+/// How a literal was written in the source, such as `0xFF`, `1_000` or a
+/// raw or `"""` string, so it prints back that way rather than respelled
+/// from its value. Literals the compiler builds have none. Like spans,
+/// spellings are left out when nodes are compared.
+#[derive(Debug, Clone, Default)]
+pub struct Spelling(pub Option<String>);
+
+impl Spelling {
+    /// Spelling of a literal written as `text`
+    pub fn new(text: &str) -> Self {
+        Self(Some(text.to_string()))
+    }
+    
+    /// Write the spelling, or `value` if there is none
+    fn write(&self, f: &mut fmt::Formatter<'_>, value: &dyn fmt::Display) -> fmt::Result {
+        match &self.0 {
+            Some(text) => f.write_str(text),
+            None => write!(f, "{}", value),
+        }
+    }
+}
+
+impl PartialEq for Spelling {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Kind of expression
 #[derive(Debug, Clone, PartialEq)]
-pub enum Expression {
+pub enum ExpressionKind {
     Literal(Literal),
     Identifier(String),
+    /// Qualified name such as `math::sqrt`
+    Path(Vec<String>),
     Binary(Box<Expression>, BinaryOp, Box<Expression>),
     Unary(UnaryOp, Box<Expression>),
     Call(Box<Expression>, Vec<Expression>),
@@ -210,6 +480,24 @@ pub enum Expression {
     Array(Vec<Expression>),
     Map(Vec<(Expression, Expression)>),
     Tuple(Vec<Expression>),
+    /// Integer range `start..end`, or `start..=end` when inclusive, with an
+    /// optional step: `0..n step 2`
+    Range(Box<Expression>, Box<Expression>, Option<Box<Expression>>, bool),
+    /// String with interpolated expressions: `"Hello, {name}!"`
+    Interpolated(Vec<StringPart>),
+    /// Struct or struct-like variant construction: `Shape::Rect { w: 1.0, h: 2.0 }`
+    StructLiteral(String, Vec<(String, Expression)>),
+    /// Anonymous function such as `|x: int| x + 1` or `|x| x + 1`; a
+    /// declared return type is followed by a block body:
+    /// `|x: int| -> int { return x + 1; }`
+    Lambda(Vec<LambdaParameter>, Option<Type>, Box<Expression>),
+}
+
+/// Piece of an interpolated string
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Text(String),
+    Expression(Expression),
 }
 
 /// Literal value
@@ -217,6 +505,7 @@ pub enum Expression {
 pub enum Literal {
     Integer(i64),
     Float(f64),
+    Byte(u8),
     String(String),
     Boolean(bool),
     Character(char),
@@ -251,6 +540,9 @@ pub enum BinaryOp {
     BitwiseXor,
     LeftShift,
     RightShift,
+    
+    // Null safety
+    Coalesce, // The ?? operator, the right operand when the left is null
 }
 
 /// Unary operator
@@ -261,6 +553,72 @@ pub enum UnaryOp {
     Not,
     BitwiseNot,
     Try, // The ? operator for error propagation
+    Unwrap, // The postfix ! operator, asserting a nullable value is not null
+}
+
+impl_eq_ignoring_span! {
+    Import { path, alias }
+    ExternFunction { name, parameters, return_type, library, is_variadic, visibility }
+    Function { name, type_params, parameters, return_type, body, is_async, visibility, doc }
+    Parameter { name, type_, is_mutable }
+    LambdaParameter { name, type_, is_mutable }
+    Struct { name, type_params, fields, visibility, doc }
+    Class { name, type_params, fields, methods, visibility, doc }
+    Enum { name, variants, visibility, doc }
+    Variant { name, payload, doc }
+    Interface { name, methods, visibility, doc }
+    MethodSignature { name, parameters, return_type, doc }
+    Impl { interface, type_name, methods }
+    Field { name, type_, visibility, is_mutable, doc }
+    Method { name, parameters, return_type, body, visibility, is_static, doc }
+    Const { name, type_, value, visibility, doc }
+    Block { statements }
+    MatchArm { pattern, guard, body }
+}
+
+impl Statement {
+    /// Create a statement covering the given span
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl From<StatementKind> for Statement {
+    /// Wrap a statement synthesized by the compiler, which has no source location
+    fn from(kind: StatementKind) -> Self {
+        Self::new(kind, Span::single(Position::start()))
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Expression {
+    /// Create an expression covering the given span
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span, spelling: Spelling::default() }
+    }
+    
+    /// Create a literal written as `spelling`
+    pub fn literal(literal: Literal, spelling: Spelling, span: Span) -> Self {
+        Self { kind: ExpressionKind::Literal(literal), span, spelling }
+    }
+}
+
+impl From<ExpressionKind> for Expression {
+    /// Wrap an expression synthesized by the compiler, which has no source location
+    fn from(kind: ExpressionKind) -> Self {
+        Self::new(kind, Span::single(Position::start()))
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
 }
 
 impl fmt::Display for Program {
@@ -300,6 +658,9 @@ impl fmt::Display for Item {
             Item::Function(func) => write!(f, "{}", func),
             Item::Struct(struct_) => write!(f, "{}", struct_),
             Item::Class(class) => write!(f, "{}", class),
+            Item::Enum(enum_def) => write!(f, "{}", enum_def),
+            Item::Interface(interface) => write!(f, "{}", interface),
+            Item::Impl(impl_block) => write!(f, "{}", impl_block),
             Item::Const(const_) => write!(f, "{}", const_),
             Item::ExternFunction(extern_func) => write!(f, "{}", extern_func),
         }
@@ -312,7 +673,7 @@ impl fmt::Display for Function {
         if self.is_async {
             write!(f, "async ")?;
         }
-        write!(f, "func {}(", self.name)?;
+        write!(f, "func {}{}(", self.name, type_params(&self.type_params))?;
         
         for (i, param) in self.parameters.iter().enumerate() {
             if i > 0 { write!(f, ", ")?; }
@@ -329,6 +690,15 @@ impl fmt::Display for Function {
     }
 }
 
+/// Render a type parameter list such as `<T, U>`, or nothing when empty
+pub fn type_params(params: &[String]) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    }
+}
+
 impl fmt::Display for Parameter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.is_mutable {
@@ -338,9 +708,22 @@ impl fmt::Display for Parameter {
     }
 }
 
+impl fmt::Display for LambdaParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_mutable {
+            write!(f, "mut ")?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(type_) = &self.type_ {
+            write!(f, ": {}", type_)?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for Struct {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}struct {} {{\n", self.visibility, self.name)?;
+        write!(f, "{}struct {}{} {{\n", self.visibility, self.name, type_params(&self.type_params))?;
         
         for field in &self.fields {
             writeln!(f, "    {}", field)?;
@@ -352,7 +735,7 @@ impl fmt::Display for Struct {
 
 impl fmt::Display for Class {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}class {} {{\n", self.visibility, self.name)?;
+        write!(f, "{}class {}{} {{\n", self.visibility, self.name, type_params(&self.type_params))?;
         
         for field in &self.fields {
             writeln!(f, "    {}", field)?;
@@ -370,6 +753,86 @@ impl fmt::Display for Class {
     }
 }
 
+impl fmt::Display for Enum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}enum {} {{", self.visibility, self.name)?;
+        
+        for variant in &self.variants {
+            writeln!(f, "    {},", variant)?;
+        }
+        
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        match &self.payload {
+            VariantPayload::Unit => Ok(()),
+            VariantPayload::Tuple(types) => {
+                write!(f, "(")?;
+                for (i, type_) in types.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", type_)?;
+                }
+                write!(f, ")")
+            }
+            VariantPayload::Struct(fields) => {
+                write!(f, " {{ ")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", field)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}interface {} {{", self.visibility, self.name)?;
+        
+        for method in &self.methods {
+            writeln!(f, "    {};", method)?;
+        }
+        
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for MethodSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "func {}(", self.name)?;
+        
+        for (i, param) in self.parameters.iter().enumerate() {
+            if i > 0 { write!(f, ", ")?; }
+            write!(f, "{}", param)?;
+        }
+        
+        write!(f, ")")?;
+        
+        if let Some(ret_type) = &self.return_type {
+            write!(f, " -> {}", ret_type)?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl fmt::Display for Impl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "impl {} for {} {{", self.interface, self.type_name)?;
+        
+        for method in &self.methods {
+            writeln!(f, "    {}", method)?;
+        }
+        
+        write!(f, "}}")
+    }
+}
+
 impl fmt::Display for Field {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.visibility)?;
@@ -465,9 +928,9 @@ impl fmt::Display for Block {
 
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Statement::Expression(expr) => write!(f, "{}", expr),
-            Statement::Let(name, type_, value) => {
+        match &self.kind {
+            StatementKind::Expression(expr) => write!(f, "{}", expr),
+            StatementKind::Let(name, type_, value) => {
                 write!(f, "let {}", name)?;
                 if let Some(t) = type_ {
                     write!(f, ": {}", t)?;
@@ -477,42 +940,42 @@ impl fmt::Display for Statement {
                 }
                 Ok(())
             }
-            Statement::Const(name, type_, value) => {
+            StatementKind::Const(name, type_, value) => {
                 write!(f, "const {}: {} = {}", name, type_, value)
             }
-            Statement::Assignment(target, value) => {
+            StatementKind::Assignment(target, value) => {
                 write!(f, "{} = {}", target, value)
             }
-            Statement::Return(value) => {
+            StatementKind::Return(value) => {
                 write!(f, "return")?;
                 if let Some(v) = value {
                     write!(f, " {}", v)?;
                 }
                 Ok(())
             }
-            Statement::Break(value) => {
+            StatementKind::Break(value) => {
                 write!(f, "break")?;
                 if let Some(v) = value {
                     write!(f, " {}", v)?;
                 }
                 Ok(())
             }
-            Statement::Continue => write!(f, "continue"),
-            Statement::Go(expr) => write!(f, "go {}", expr),
-            Statement::If(cond, then_block, else_block) => {
+            StatementKind::Continue => write!(f, "continue"),
+            StatementKind::Go(expr) => write!(f, "go {}", expr),
+            StatementKind::If(cond, then_block, else_block) => {
                 write!(f, "if {} {}", cond, then_block)?;
                 if let Some(else_b) = else_block {
                     write!(f, " else {}", else_b)?;
                 }
                 Ok(())
             }
-            Statement::While(cond, body) => {
+            StatementKind::While(cond, body) => {
                 write!(f, "while {} {}", cond, body)
             }
-            Statement::For(var, iter, body) => {
+            StatementKind::For(var, iter, body) => {
                 write!(f, "for {} in {} {}", var, iter, body)
             }
-            Statement::Match(expr, arms) => {
+            StatementKind::Match(expr, arms) => {
                 writeln!(f, "match {} {{", expr)?;
                 for arm in arms {
                     writeln!(f, "    {}", arm)?;
@@ -536,7 +999,7 @@ impl fmt::Display for MatchArm {
 impl fmt::Display for Pattern {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Pattern::Literal(lit) => write!(f, "{}", lit),
+            Pattern::Literal(lit, spelling) => spelling.write(f, lit),
             Pattern::Identifier(name) => write!(f, "{}", name),
             Pattern::Wildcard => write!(f, "_"),
             Pattern::Tuple(patterns) => {
@@ -551,10 +1014,26 @@ impl fmt::Display for Pattern {
                 write!(f, "{} {{ ", name)?;
                 for (i, (field_name, pattern)) in fields.iter().enumerate() {
                     if i > 0 { write!(f, ", ")?; }
-                    write!(f, "{}: {}", field_name, pattern)?;
+                    // `x` is short for `x: x`
+                    match pattern {
+                        Pattern::Identifier(name) if name == field_name => write!(f, "{}", name)?,
+                        _ => write!(f, "{}: {}", field_name, pattern)?,
+                    }
                 }
                 write!(f, " }}")
             }
+            Pattern::Variant(path, patterns) => {
+                write!(f, "{}", path.join("::"))?;
+                if !patterns.is_empty() {
+                    write!(f, "(")?;
+                    for (i, pattern) in patterns.iter().enumerate() {
+                        if i > 0 { write!(f, ", ")?; }
+                        write!(f, "{}", pattern)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
             Pattern::Result(result_pattern) => write!(f, "{}", result_pattern),
         }
     }
@@ -562,16 +1041,23 @@ impl fmt::Display for Pattern {
 
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Expression::Literal(lit) => write!(f, "{}", lit),
-            Expression::Identifier(name) => write!(f, "{}", name),
-            Expression::Binary(left, op, right) => {
+        match &self.kind {
+            ExpressionKind::Literal(lit) => self.spelling.write(f, lit),
+            ExpressionKind::Identifier(name) => write!(f, "{}", name),
+            ExpressionKind::Path(segments) => write!(f, "{}", segments.join("::")),
+            ExpressionKind::Binary(left, op, right) => {
                 write!(f, "({} {} {})", left, op, right)
             }
-            Expression::Unary(op, expr) => {
+            ExpressionKind::Unary(UnaryOp::Try, expr) => {
+                write!(f, "{}?", expr)
+            }
+            ExpressionKind::Unary(UnaryOp::Unwrap, expr) => {
+                write!(f, "{}!", expr)
+            }
+            ExpressionKind::Unary(op, expr) => {
                 write!(f, "{}{}", op, expr)
             }
-            Expression::Call(func, args) => {
+            ExpressionKind::Call(func, args) => {
                 write!(f, "{}(", func)?;
                 for (i, arg) in args.iter().enumerate() {
                     if i > 0 { write!(f, ", ")?; }
@@ -579,28 +1065,28 @@ impl fmt::Display for Expression {
                 }
                 write!(f, ")")
             }
-            Expression::Index(expr, index) => {
+            ExpressionKind::Index(expr, index) => {
                 write!(f, "{}[{}]", expr, index)
             }
-            Expression::Field(expr, field) => {
+            ExpressionKind::Field(expr, field) => {
                 write!(f, "{}.{}", expr, field)
             }
-            Expression::Match(expr, arms) => {
+            ExpressionKind::Match(expr, arms) => {
                 writeln!(f, "match {} {{", expr)?;
                 for arm in arms {
                     writeln!(f, "    {}", arm)?;
                 }
                 write!(f, "}}")
             }
-            Expression::If(cond, then_block, else_block) => {
+            ExpressionKind::If(cond, then_block, else_block) => {
                 write!(f, "if {} {}", cond, then_block)?;
                 if let Some(else_b) = else_block {
                     write!(f, " else {}", else_b)?;
                 }
                 Ok(())
             }
-            Expression::Block(block) => write!(f, "{}", block),
-            Expression::Array(elements) => {
+            ExpressionKind::Block(block) => write!(f, "{}", block),
+            ExpressionKind::Array(elements) => {
                 write!(f, "[")?;
                 for (i, elem) in elements.iter().enumerate() {
                     if i > 0 { write!(f, ", ")?; }
@@ -608,7 +1094,7 @@ impl fmt::Display for Expression {
                 }
                 write!(f, "]")
             }
-            Expression::Map(pairs) => {
+            ExpressionKind::Map(pairs) => {
                 write!(f, "{{")?;
                 for (i, (key, value)) in pairs.iter().enumerate() {
                     if i > 0 { write!(f, ", ")?; }
@@ -616,7 +1102,7 @@ impl fmt::Display for Expression {
                 }
                 write!(f, "}}")
             }
-            Expression::Tuple(elements) => {
+            ExpressionKind::Tuple(elements) => {
                 write!(f, "(")?;
                 for (i, elem) in elements.iter().enumerate() {
                     if i > 0 { write!(f, ", ")?; }
@@ -624,6 +1110,43 @@ impl fmt::Display for Expression {
                 }
                 write!(f, ")")
             }
+            ExpressionKind::Interpolated(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => write!(f, "{}", escape(text, '"'))?,
+                        StringPart::Expression(expr) => write!(f, "{{{}}}", expr)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            ExpressionKind::Range(start, end, step, inclusive) => {
+                write!(f, "{}{}{}", start, if *inclusive { "..=" } else { ".." }, end)?;
+                if let Some(step) = step {
+                    write!(f, " step {}", step)?;
+                }
+                Ok(())
+            }
+            ExpressionKind::Lambda(params, return_type, body) => {
+                write!(f, "|")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, "| ")?;
+                if let Some(return_type) = return_type {
+                    write!(f, "-> {} ", return_type)?;
+                }
+                write!(f, "{}", body)
+            }
+            ExpressionKind::StructLiteral(name, fields) => {
+                write!(f, "{} {{ ", name)?;
+                for (i, (field_name, value)) in fields.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}: {}", field_name, value)?;
+                }
+                write!(f, " }}")
+            }
         }
     }
 }
@@ -632,15 +1155,41 @@ impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Literal::Integer(n) => write!(f, "{}", n),
-            Literal::Float(n) => write!(f, "{}", n),
-            Literal::String(s) => write!(f, "\"{}\"", s),
+            // Debug keeps the fractional part (`2.0`) so the literal re-lexes as a float
+            Literal::Float(n) => write!(f, "{:?}", n),
+            Literal::Byte(n) => write!(f, "{}u8", n),
+            Literal::String(s) => write!(f, "\"{}\"", escape(s, '"')),
             Literal::Boolean(b) => write!(f, "{}", b),
-            Literal::Character(c) => write!(f, "'{}'", c),
+            Literal::Character(c) => write!(f, "'{}'", escape(&c.to_string(), '\'')),
             Literal::Null => write!(f, "null"),
         }
     }
 }
 
+/// Escape a literal's contents using the escapes the lexer understands
+fn escape(value: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            '\\' => escaped.push_str("\\\\"),
+            // A brace in a string would open an interpolated expression,
+            // unless it is an empty `{}`, which the lexer keeps as text
+            '{' if quote == '"' && chars.peek() != Some(&'}') => escaped.push_str("\\{"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl fmt::Display for BinaryOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -662,6 +1211,7 @@ impl fmt::Display for BinaryOp {
             BinaryOp::BitwiseXor => write!(f, "^"),
             BinaryOp::LeftShift => write!(f, "<<"),
             BinaryOp::RightShift => write!(f, ">>"),
+            BinaryOp::Coalesce => write!(f, "??"),
         }
     }
 }
@@ -674,6 +1224,7 @@ impl fmt::Display for UnaryOp {
             UnaryOp::Not => write!(f, "!"),
             UnaryOp::BitwiseNot => write!(f, "~"),
             UnaryOp::Try => write!(f, "?"),
+            UnaryOp::Unwrap => write!(f, "!"),
         }
     }
 }
@@ -700,6 +1251,15 @@ impl fmt::Display for Type {
             Type::List(t) => write!(f, "List<{}>", t),
             Type::Map(k, v) => write!(f, "Map<{}, {}>", k, v),
             Type::Set(t) => write!(f, "Set<{}>", t),
+            Type::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, ")")
+            }
+            Type::Range => write!(f, "Range"),
             Type::Named(name) => write!(f, "{}", name),
             Type::Function(params, ret) => {
                 write!(f, "(")?;
@@ -721,6 +1281,8 @@ impl fmt::Display for Type {
                 }
                 Ok(())
             }
+            // The type of the `null` literal
+            Type::Nullable(t) if **t == Type::Never => write!(f, "null"),
             Type::Nullable(t) => write!(f, "{}?", t),
             Type::Result(ok, err) => write!(f, "Result<{}, {}>", ok, err),
             Type::Unit => write!(f, "()"),
@@ -728,8 +1290,7 @@ impl fmt::Display for Type {
         }
     }
 }
-#[cfg(test)
-]
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -737,6 +1298,7 @@ mod tests {
     fn test_literal_display() {
         assert_eq!(format!("{}", Literal::Integer(42)), "42");
         assert_eq!(format!("{}", Literal::Float(3.14)), "3.14");
+        assert_eq!(format!("{}", Literal::Byte(255)), "255u8");
         assert_eq!(format!("{}", Literal::String("hello".to_string())), "\"hello\"");
         assert_eq!(format!("{}", Literal::Boolean(true)), "true");
         assert_eq!(format!("{}", Literal::Character('a')), "'a'");
@@ -767,47 +1329,54 @@ mod tests {
         assert_eq!(format!("{}", Type::List(Box::new(Type::String))), "List<string>");
         assert_eq!(format!("{}", Type::Map(Box::new(Type::String), Box::new(Type::Int))), "Map<string, int>");
         assert_eq!(format!("{}", Type::Nullable(Box::new(Type::Int))), "int?");
+        assert_eq!(format!("{}", Type::Nullable(Box::new(Type::Never))), "null");
         assert_eq!(format!("{}", Type::Result(Box::new(Type::Int), Box::new(Type::String))), "Result<int, string>");
+        assert_eq!(format!("{}", Type::Tuple(vec![Type::Int, Type::String])), "(int, string)");
+        assert_eq!(format!("{}", Type::Range), "Range");
     }
 
     #[test]
     fn test_expression_display() {
-        let expr = Expression::Binary(
-            Box::new(Expression::Literal(Literal::Integer(1))),
+        let expr = Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(1)))),
             BinaryOp::Add,
-            Box::new(Expression::Literal(Literal::Integer(2)))
-        );
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(2))))
+        ));
         assert_eq!(format!("{}", expr), "(1 + 2)");
 
-        let call_expr = Expression::Call(
-            Box::new(Expression::Identifier("print".to_string())),
-            vec![Expression::Literal(Literal::String("hello".to_string()))]
-        );
+        let call_expr = Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Identifier("print".to_string()))),
+            vec![Expression::from(ExpressionKind::Literal(Literal::String("hello".to_string())))]
+        ));
         assert_eq!(format!("{}", call_expr), "print(\"hello\")");
 
-        let field_expr = Expression::Field(
-            Box::new(Expression::Identifier("obj".to_string())),
+        let field_expr = Expression::from(ExpressionKind::Field(
+            Box::new(Expression::from(ExpressionKind::Identifier("obj".to_string()))),
             "field".to_string()
-        );
+        ));
         assert_eq!(format!("{}", field_expr), "obj.field");
+
+        let integer = |n| Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(n))));
+        let range = Expression::from(ExpressionKind::Range(integer(0), integer(10), Some(integer(2)), true));
+        assert_eq!(format!("{}", range), "0..=10 step 2");
     }
 
     #[test]
     fn test_statement_display() {
-        let let_stmt = Statement::Let(
-            "x".to_string(),
+        let let_stmt = Statement::from(StatementKind::Let(
+            Pattern::Identifier("x".to_string()),
             Some(Type::Int),
-            Some(Expression::Literal(Literal::Integer(42)))
-        );
+            Some(Expression::from(ExpressionKind::Literal(Literal::Integer(42))))
+        ));
         assert_eq!(format!("{}", let_stmt), "let x: int = 42");
 
-        let return_stmt = Statement::Return(Some(Expression::Identifier("x".to_string())));
+        let return_stmt = Statement::from(StatementKind::Return(Some(Expression::from(ExpressionKind::Identifier("x".to_string())))));
         assert_eq!(format!("{}", return_stmt), "return x");
 
-        let assignment = Statement::Assignment(
-            Expression::Identifier("x".to_string()),
-            Expression::Literal(Literal::Integer(10))
-        );
+        let assignment = Statement::from(StatementKind::Assignment(
+            Expression::from(ExpressionKind::Identifier("x".to_string())),
+            Expression::from(ExpressionKind::Literal(Literal::Integer(10)))
+        ));
         assert_eq!(format!("{}", assignment), "x = 10");
     }
 
@@ -815,30 +1384,36 @@ mod tests {
     fn test_function_display() {
         let func = Function {
             name: "add".to_string(),
+            type_params: vec![],
             parameters: vec![
                 Parameter {
                     name: "a".to_string(),
                     type_: Type::Int,
                     is_mutable: false,
+                    span: Span::single(Position::start()),
                 },
                 Parameter {
                     name: "b".to_string(),
                     type_: Type::Int,
                     is_mutable: false,
+                    span: Span::single(Position::start()),
                 }
             ],
             return_type: Some(Type::Int),
             body: Block {
                 statements: vec![
-                    Statement::Return(Some(Expression::Binary(
-                        Box::new(Expression::Identifier("a".to_string())),
+                    Statement::from(StatementKind::Return(Some(Expression::from(ExpressionKind::Binary(
+                        Box::new(Expression::from(ExpressionKind::Identifier("a".to_string()))),
                         BinaryOp::Add,
-                        Box::new(Expression::Identifier("b".to_string()))
-                    )))
-                ]
+                        Box::new(Expression::from(ExpressionKind::Identifier("b".to_string())))
+                    )))))
+                ],
+                span: Span::single(Position::start()),
             },
             is_async: false,
             visibility: Visibility::Public,
+            doc: None,
+            span: Span::single(Position::start()),
         };
 
         let display = format!("{}", func);
@@ -850,21 +1425,28 @@ mod tests {
     fn test_struct_display() {
         let struct_ = Struct {
             name: "Point".to_string(),
+            type_params: vec![],
             fields: vec![
                 Field {
                     name: "x".to_string(),
                     type_: Type::Int,
                     visibility: Visibility::Public,
                     is_mutable: false,
+                    doc: None,
+                    span: Span::single(Position::start()),
                 },
                 Field {
                     name: "y".to_string(),
                     type_: Type::Int,
                     visibility: Visibility::Public,
                     is_mutable: false,
+                    doc: None,
+                    span: Span::single(Position::start()),
                 }
             ],
             visibility: Visibility::Public,
+            doc: None,
+            span: Span::single(Position::start()),
         };
 
         let display = format!("{}", struct_);
@@ -881,23 +1463,28 @@ mod tests {
                 Import {
                     path: "std/io".to_string(),
                     alias: None,
+                    span: Span::single(Position::start()),
                 }
             ],
             items: vec![
                 Item::Function(Function {
                     name: "main".to_string(),
+                    type_params: vec![],
                     parameters: vec![],
                     return_type: None,
                     body: Block {
                         statements: vec![
-                            Statement::Expression(Expression::Call(
-                                Box::new(Expression::Identifier("println".to_string())),
-                                vec![Expression::Literal(Literal::String("Hello, World!".to_string()))]
-                            ))
-                        ]
+                            Statement::from(StatementKind::Expression(Expression::from(ExpressionKind::Call(
+                                Box::new(Expression::from(ExpressionKind::Identifier("println".to_string()))),
+                                vec![Expression::from(ExpressionKind::Literal(Literal::String("Hello, World!".to_string())))]
+                            ))))
+                        ],
+                        span: Span::single(Position::start()),
                     },
                     is_async: false,
                     visibility: Visibility::Private,
+                    doc: None,
+                    span: Span::single(Position::start()),
                 })
             ],
         };
@@ -911,7 +1498,7 @@ mod tests {
 
     #[test]
     fn test_pattern_display() {
-        let literal_pattern = Pattern::Literal(Literal::Integer(42));
+        let literal_pattern = Pattern::Literal(Literal::Integer(42), Spelling::default());
         assert_eq!(format!("{}", literal_pattern), "42");
 
         let wildcard_pattern = Pattern::Wildcard;
@@ -931,22 +1518,30 @@ mod tests {
             ]
         );
         assert_eq!(format!("{}", struct_pattern), "Point { x: px, y: _ }");
+
+        let shorthand_pattern = Pattern::Struct(
+            "Point".to_string(),
+            vec![("x".to_string(), Pattern::Identifier("x".to_string()))]
+        );
+        assert_eq!(format!("{}", shorthand_pattern), "Point { x }");
     }
 
     #[test]
     fn test_match_arm_display() {
         let arm = MatchArm {
-            pattern: Pattern::Literal(Literal::Integer(1)),
-            guard: Some(Expression::Binary(
-                Box::new(Expression::Identifier("x".to_string())),
+            pattern: Pattern::Literal(Literal::Integer(1), Spelling::default()),
+            guard: Some(Expression::from(ExpressionKind::Binary(
+                Box::new(Expression::from(ExpressionKind::Identifier("x".to_string()))),
                 BinaryOp::Greater,
-                Box::new(Expression::Literal(Literal::Integer(0)))
-            )),
+                Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(0))))
+            ))),
             body: Block {
                 statements: vec![
-                    Statement::Expression(Expression::Literal(Literal::String("positive".to_string())))
-                ]
-            }
+                    Statement::from(StatementKind::Expression(Expression::from(ExpressionKind::Literal(Literal::String("positive".to_string())))))
+                ],
+                span: Span::single(Position::start()),
+            },
+            span: Span::single(Position::start()),
         };
 
         let display = format!("{}", arm);
@@ -4,7 +4,7 @@
 
 use crate::error::{ParseError, ParseErrorKind};
 use crate::lexer::{FluxLexer, Token};
-use crate::position::Span;
+use crate::position::{Position, Span};
 
 pub mod ast;
 
@@ -36,41 +36,198 @@ pub struct FluxParser {
     lexer: FluxLexer,
     current_token: Token,
     peek_token: Token,
+    current_span: Span,
+    peek_span: Span,
+    /// Doc comments directly before the current and peek tokens
+    current_doc: Option<String>,
+    peek_doc: Option<String>,
+    previous_end: Position,
+    /// Set while parsing `if`/`while`/`for`/`match` heads, where `Name {`
+    /// opens the body rather than a struct literal
+    no_struct_literal: bool,
+    /// Set inside `(...)` and `[...]`, where a line break does not end the
+    /// expression; blocks clear it again
+    grouped: bool,
+    /// Errors recovered from so far
+    errors: Vec<ParseError>,
+    /// Error the lexer stopped at; the input ends there, and no errors are
+    /// recorded after it
+    lexical_error: Option<ParseError>,
+}
+
+/// Where parsing resumes after a syntax error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recovery {
+    /// The next top-level declaration
+    Item,
+    /// The next statement of the enclosing block
+    Statement,
 }
 
 impl FluxParser {
     /// Create a new parser with the given lexer
     pub fn new(mut lexer: FluxLexer) -> Result<Self, ParseError> {
-        let current_token = lexer.next_token().map_err(|e| ParseError {
-            span: Span::single(e.position),
-            kind: ParseErrorKind::InvalidSyntax { 
-                message: format!("Lexical error: {}", e) 
-            },
-        })?;
-        
-        let peek_token = lexer.next_token().map_err(|e| ParseError {
-            span: Span::single(e.position),
-            kind: ParseErrorKind::InvalidSyntax { 
-                message: format!("Lexical error: {}", e) 
-            },
-        })?;
+        let (current_token, current_span, current_doc) = Self::next_significant_token(&mut lexer)?;
+        let (peek_token, peek_span, peek_doc) = Self::next_significant_token(&mut lexer)?;
         
         Ok(Self {
             lexer,
             current_token,
             peek_token,
+            current_span,
+            peek_span,
+            current_doc,
+            peek_doc,
+            previous_end: current_span.start,
+            no_struct_literal: false,
+            grouped: false,
+            errors: Vec::new(),
+            lexical_error: None,
         })
     }
     
+    /// Parse statements separated by optional semicolons up to the end of
+    /// input, as entered at an interactive prompt
+    pub fn parse_statements(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        self.skip_semicolons()?;
+        while !self.is_at_end() {
+            let statement = self.parse_statement_impl();
+            statements.push(self.first_error(statement)?);
+            self.skip_semicolons()?;
+        }
+        Ok(statements)
+    }
+    
+    /// Read the next token and its span from the lexer, skipping newlines,
+    /// along with the lines of the doc comments before it
+    fn next_significant_token(lexer: &mut FluxLexer) -> Result<(Token, Span, Option<String>), ParseError> {
+        let mut doc: Option<String> = None;
+        loop {
+            let token = lexer.next_token().map_err(|e| ParseError {
+                span: Span::single(e.position),
+                kind: ParseErrorKind::InvalidSyntax { 
+                    message: format!("Lexical error: {}", e) 
+                },
+            })?;
+            
+            match token {
+                Token::Newline => {}
+                Token::DocComment(text) => match &mut doc {
+                    Some(doc) => {
+                        doc.push('\n');
+                        doc.push_str(&text);
+                    }
+                    None => doc = Some(text),
+                },
+                token => return Ok((token, Span::new(lexer.token_start(), lexer.position()), doc)),
+            }
+        }
+    }
+    
     /// Advance to the next token
     fn advance(&mut self) -> Result<(), ParseError> {
+        self.previous_end = self.current_span.end;
         self.current_token = std::mem::replace(&mut self.peek_token, Token::Eof);
-        self.peek_token = self.lexer.next_token().map_err(|e| ParseError {
-            span: Span::single(e.position),
-            kind: ParseErrorKind::InvalidSyntax { 
-                message: format!("Lexical error: {}", e) 
+        self.current_span = self.peek_span;
+        self.current_doc = self.peek_doc.take();
+        if self.lexical_error.is_some() {
+            return Ok(());
+        }
+        match Self::next_significant_token(&mut self.lexer) {
+            Ok((peek_token, peek_span, peek_doc)) => {
+                self.peek_token = peek_token;
+                self.peek_span = peek_span;
+                self.peek_doc = peek_doc;
+                Ok(())
+            }
+            Err(error) => {
+                self.peek_span = error.span;
+                self.lexical_error = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+    
+    /// Take the doc comment before the current token, which starts a
+    /// declaration
+    fn take_doc(&mut self) -> Option<String> {
+        self.current_doc.take()
+    }
+    
+    /// Start position of the current token
+    fn start(&self) -> Position {
+        self.current_span.start
+    }
+    
+    /// Span from `start` to the end of the last consumed token
+    fn span_from(&self, start: Position) -> Span {
+        Span::new(start, self.previous_end)
+    }
+    
+    /// Whether a line break separates the current token from the one before it
+    fn on_new_line(&self) -> bool {
+        self.current_span.start.line > self.previous_end.line
+    }
+    
+    /// Whether the current token continues the expression before it. Outside
+    /// parentheses and brackets a line break ends the expression before a
+    /// token that could also start the next statement: `-`, `+`, `(` or `[`.
+    fn continues_line(&self) -> bool {
+        self.grouped || !self.on_new_line()
+    }
+    
+    /// Run `parse` with line breaks inside or outside a group
+    fn with_grouped<T>(&mut self, grouped: bool, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        let outer = std::mem::replace(&mut self.grouped, grouped);
+        let result = parse(self);
+        self.grouped = outer;
+        result
+    }
+    
+    /// Number literal at the current token, with how it is written
+    fn parse_number(&mut self) -> Result<(Literal, Spelling), ParseError> {
+        let literal = match self.current_token {
+            Token::Integer(n) => Literal::Integer(self.check_integer(n)?),
+            Token::Float(f) => Literal::Float(f),
+            Token::Byte(b) => Literal::Byte(b),
+            _ => return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "number".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            }),
+        };
+        let spelling = self.spelling(self.current_span);
+        self.advance()?;
+        Ok((literal, spelling))
+    }
+    
+    /// Spelling of the literal the source has at `span`
+    fn spelling(&self, span: Span) -> Spelling {
+        Spelling::new(self.lexer.text(span))
+    }
+    
+    /// Value of an integer literal. The lexer reads 2^63 as `i64::MIN`,
+    /// which it is only right after a minus sign.
+    fn check_integer(&self, value: i64) -> Result<i64, ParseError> {
+        if value != i64::MIN {
+            return Ok(value);
+        }
+        Err(ParseError {
+            span: self.current_span,
+            kind: ParseErrorKind::InvalidSyntax {
+                message: format!("Number {} is out of range for int", 1u64 << 63),
             },
-        })?;
+        })
+    }
+    
+    /// Skip optional statement terminators
+    fn skip_semicolons(&mut self) -> Result<(), ParseError> {
+        while matches!(self.current_token, Token::Semicolon) {
+            self.advance()?;
+        }
         Ok(())
     }
     
@@ -86,7 +243,7 @@ impl FluxParser {
             Ok(())
         } else {
             Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: format!("{} ({})", expected, message),
                     found: format!("{}", self.current_token),
@@ -95,55 +252,83 @@ impl FluxParser {
         }
     }
 
-    /// Synchronize the parser after an error by skipping tokens until we reach a statement boundary
-    fn synchronize(&mut self) -> Result<(), ParseError> {
-        self.advance()?;
+    /// Whether the token can only begin a top-level declaration
+    fn starts_item(token: &Token) -> bool {
+        matches!(
+            token,
+            Token::Func | Token::Struct | Token::Class | Token::Enum | Token::Interface |
+            Token::Impl | Token::Extern | Token::Pub | Token::Import | Token::Package
+        )
+    }
 
-        while !self.is_at_end() {
-            // If we see a semicolon, we're at the end of a statement
-            if matches!(self.current_token, Token::Semicolon) {
-                self.advance()?;
-                return Ok(());
-            }
+    /// Whether the token begins a statement that is not an expression
+    fn starts_statement(token: &Token) -> bool {
+        matches!(
+            token,
+            Token::Let | Token::Const | Token::If | Token::While | Token::For | Token::Match |
+            Token::Return | Token::Break | Token::Continue | Token::Go
+        )
+    }
 
-            // Look for statement keywords that indicate the start of a new statement
-            match &self.current_token {
-                Token::Let | Token::Const | Token::Func | Token::Struct | Token::Class |
-                Token::If | Token::While | Token::For | Token::Match | Token::Return |
-                Token::Break | Token::Continue | Token::Go => {
+    /// Synchronize the parser after an error by skipping tokens until we reach
+    /// the boundary `recovery` resumes at. At least one token is skipped when
+    /// the failed parse did not consume any, so recovery always makes progress
+    fn synchronize(&mut self, recovery: Recovery, start: Position) -> Result<(), ParseError> {
+        if self.current_span.start == start && !self.is_at_end() {
+            self.advance()?;
+        }
+
+        // Braces opened while skipping are skipped as a whole, so a block
+        // inside the broken code cannot end the recovery early
+        let mut depth = 0usize;
+        while !self.is_at_end() {
+            match (&self.current_token, recovery) {
+                (Token::LeftBrace, _) => depth += 1,
+                (Token::RightBrace, Recovery::Item) => depth = depth.saturating_sub(1),
+                // The closing brace of the enclosing block ends the statement
+                (Token::RightBrace, Recovery::Statement) if depth == 0 => return Ok(()),
+                (Token::RightBrace, Recovery::Statement) => depth -= 1,
+                (Token::Semicolon, Recovery::Statement) if depth == 0 => {
+                    self.advance()?;
                     return Ok(());
                 }
-                _ => {
-                    self.advance()?;
+                (token, Recovery::Item) if depth == 0 && (Self::starts_item(token) || matches!(token, Token::Const)) => {
+                    return Ok(());
+                }
+                (token, Recovery::Statement) if depth == 0 && (Self::starts_item(token) || Self::starts_statement(token)) => {
+                    return Ok(());
                 }
+                _ => {}
             }
+            self.advance()?;
         }
 
         Ok(())
     }
 
-    /// Try to recover from a parsing error and continue parsing
-    fn recover_from_error(&mut self, error: ParseError) -> ParseError {
-        // Log the error (in a real implementation, you might want to collect multiple errors)
-        eprintln!("Parse error: {:?}", error);
-        
-        // Try to synchronize to the next statement
-        if let Err(sync_error) = self.synchronize() {
-            // If synchronization fails, return the original error
-            error
-        } else {
-            // Return the original error but continue parsing
-            error
+    /// Record a parsing error and skip to where parsing can resume
+    fn recover_from_error(&mut self, error: ParseError, recovery: Recovery, start: Position) {
+        // Errors after a lexical error come from the input ending early
+        if self.lexical_error.is_none() {
+            self.errors.push(error);
+        }
+
+        // A lexical error while skipping ends the recovery; the parser then
+        // runs into the end of input, so it is not reported a second time
+        if self.synchronize(recovery, start).is_err() {
+            self.current_token = Token::Eof;
         }
     }
 
-    /// Parse with error recovery - continues parsing even after errors
-    fn parse_with_recovery<T, F>(&mut self, parse_fn: F, context: &str) -> Result<T, ParseError>
+    /// Parse with error recovery - records the error and resynchronizes so
+    /// the caller can continue with the next item or statement
+    fn parse_with_recovery<T, F>(&mut self, parse_fn: F, context: &str, recovery: Recovery) -> Option<T>
     where
         F: FnOnce(&mut Self) -> Result<T, ParseError>,
     {
+        let start = self.start();
         match parse_fn(self) {
-            Ok(result) => Ok(result),
+            Ok(result) => Some(result),
             Err(error) => {
                 // Enhance error message with context
                 let enhanced_error = ParseError {
@@ -169,119 +354,267 @@ impl FluxParser {
                     },
                 };
                 
-                Err(self.recover_from_error(enhanced_error))
+                self.recover_from_error(enhanced_error, recovery, start);
+                None
+            }
+        }
+    }
+
+    /// Parse a complete program, recovering from syntax errors at item and
+    /// statement boundaries. Returns whatever could be parsed along with every
+    /// error found, in source order
+    pub fn parse_program_with_errors(&mut self) -> (Program, Vec<ParseError>) {
+        let program = self.parse_program_impl();
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.extend(self.lexical_error.take());
+        errors.sort_by_key(|error| error.span.start.offset);
+        (program, errors)
+    }
+
+    /// Fail with the earliest error, including those recovered from, if any
+    fn first_error<T>(&mut self, result: Result<T, ParseError>) -> Result<T, ParseError> {
+        let mut errors = std::mem::take(&mut self.errors);
+        let result = match self.lexical_error.take() {
+            Some(error) => Err(error),
+            None => result,
+        };
+        let value = match result {
+            Ok(value) => value,
+            Err(error) => {
+                errors.push(error);
+                errors.sort_by_key(|error| error.span.start.offset);
+                return Err(errors.remove(0));
             }
+        };
+        errors.sort_by_key(|error| error.span.start.offset);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(value),
         }
     }
 
     // Implementation methods (not part of trait)
-    fn parse_program_impl(&mut self) -> Result<Program, ParseError> {
+    fn parse_program_impl(&mut self) -> Program {
         let mut items = Vec::new();
-        let mut errors = Vec::new();
+        
+        // Optional package declaration, defaulting to `main`
+        let package = if matches!(self.current_token, Token::Package) {
+            self.parse_with_recovery(|p| p.parse_package_declaration(), "package declaration", Recovery::Item)
+                .unwrap_or_else(|| "main".to_string())
+        } else {
+            "main".to_string()
+        };
+        
+        // Imports must precede all other items
+        let mut imports: Vec<Import> = Vec::new();
+        while matches!(self.current_token, Token::Import) {
+            let Some(import) = self.parse_with_recovery(|p| p.parse_import_declaration(), "import declaration", Recovery::Item) else {
+                continue;
+            };
+            
+            if imports.iter().any(|existing| existing.path == import.path) {
+                self.errors.push(ParseError {
+                    span: import.span,
+                    kind: ParseErrorKind::InvalidSyntax {
+                        message: format!("Duplicate import of \"{}\"", import.path),
+                    },
+                });
+                continue;
+            }
+            
+            if imports.iter().any(|existing| existing.binding_name() == import.binding_name()) {
+                self.errors.push(ParseError {
+                    span: import.span,
+                    kind: ParseErrorKind::InvalidSyntax {
+                        message: format!(
+                            "Import name '{}' is already in use; add an alias with 'as'",
+                            import.binding_name()
+                        ),
+                    },
+                });
+                continue;
+            }
+            
+            imports.push(import);
+        }
         
         while !self.is_at_end() {
-            match &self.current_token {
+            let start = self.start();
+            let item = match &self.current_token {
+                Token::Package => {
+                    let error = ParseError {
+                        span: self.current_span,
+                        kind: ParseErrorKind::InvalidSyntax {
+                            message: "Package declaration must be the first item in the file".to_string(),
+                        },
+                    };
+                    self.recover_from_error(error, Recovery::Item, start);
+                    None
+                }
+                Token::Import => {
+                    let error = ParseError {
+                        span: self.current_span,
+                        kind: ParseErrorKind::InvalidSyntax {
+                            message: "Import declarations must come before all other items".to_string(),
+                        },
+                    };
+                    self.recover_from_error(error, Recovery::Item, start);
+                    None
+                }
                 Token::Pub => {
                     // Look ahead to see what kind of declaration this is
                     match &self.peek_token {
-                        Token::Func => {
-                            match self.parse_with_recovery(|p| p.parse_function_impl(), "function declaration") {
-                                Ok(func) => items.push(Item::Function(func)),
-                                Err(error) => {
-                                    errors.push(error);
-                                    // Continue parsing after error recovery
-                                }
-                            }
-                        }
-                        Token::Struct => {
-                            match self.parse_with_recovery(|p| p.parse_struct_impl(), "struct declaration") {
-                                Ok(struct_) => items.push(Item::Struct(struct_)),
-                                Err(error) => {
-                                    errors.push(error);
-                                }
-                            }
-                        }
-                        Token::Extern => {
-                            match self.parse_with_recovery(|p| p.parse_extern_function_impl(), "extern function declaration") {
-                                Ok(extern_func) => items.push(Item::ExternFunction(extern_func)),
-                                Err(error) => {
-                                    errors.push(error);
-                                }
-                            }
-                        }
+                        Token::Func => self.parse_with_recovery(|p| p.parse_function_impl(), "function declaration", Recovery::Item)
+                            .map(Item::Function),
+                        Token::Struct => self.parse_with_recovery(|p| p.parse_struct_impl(), "struct declaration", Recovery::Item)
+                            .map(Item::Struct),
+                        Token::Class => self.parse_with_recovery(|p| p.parse_class_impl(), "class declaration", Recovery::Item)
+                            .map(Item::Class),
+                        Token::Enum => self.parse_with_recovery(|p| p.parse_enum_impl(), "enum declaration", Recovery::Item)
+                            .map(Item::Enum),
+                        Token::Interface => self.parse_with_recovery(|p| p.parse_interface_impl(), "interface declaration", Recovery::Item)
+                            .map(Item::Interface),
+                        Token::Extern => self.parse_with_recovery(|p| p.parse_extern_function_impl(), "extern function declaration", Recovery::Item)
+                            .map(Item::ExternFunction),
+                        Token::Const => self.parse_with_recovery(|p| p.parse_const_declaration(), "const declaration", Recovery::Item)
+                            .map(Item::Const),
                         _ => {
                             let error = ParseError {
-                                span: Span::single(self.lexer.position()),
+                                span: self.peek_span,
                                 kind: ParseErrorKind::UnexpectedToken {
-                                    expected: "function or struct declaration after 'pub'".to_string(),
+                                    expected: "function, struct, class, enum, interface, or const declaration after 'pub'".to_string(),
                                     found: format!("{}", self.peek_token),
                                 },
                             };
-                            errors.push(self.recover_from_error(error));
-                        }
-                    }
-                }
-                Token::Func => {
-                    match self.parse_with_recovery(|p| p.parse_function_impl(), "function declaration") {
-                        Ok(func) => items.push(Item::Function(func)),
-                        Err(error) => {
-                            errors.push(error);
-                        }
-                    }
-                }
-                Token::Struct => {
-                    match self.parse_with_recovery(|p| p.parse_struct_impl(), "struct declaration") {
-                        Ok(struct_) => items.push(Item::Struct(struct_)),
-                        Err(error) => {
-                            errors.push(error);
-                        }
-                    }
-                }
-                Token::Const => {
-                    // Parse top-level const declaration
-                    match self.parse_with_recovery(|p| p.parse_const_declaration(), "const declaration") {
-                        Ok(const_) => items.push(Item::Const(const_)),
-                        Err(error) => {
-                            errors.push(error);
-                        }
-                    }
-                }
-                Token::Extern => {
-                    match self.parse_with_recovery(|p| p.parse_extern_function_impl(), "extern function declaration") {
-                        Ok(extern_func) => items.push(Item::ExternFunction(extern_func)),
-                        Err(error) => {
-                            errors.push(error);
+                            self.recover_from_error(error, Recovery::Item, start);
+                            None
                         }
                     }
                 }
+                Token::Func => self.parse_with_recovery(|p| p.parse_function_impl(), "function declaration", Recovery::Item)
+                    .map(Item::Function),
+                Token::Struct => self.parse_with_recovery(|p| p.parse_struct_impl(), "struct declaration", Recovery::Item)
+                    .map(Item::Struct),
+                Token::Class => self.parse_with_recovery(|p| p.parse_class_impl(), "class declaration", Recovery::Item)
+                    .map(Item::Class),
+                Token::Enum => self.parse_with_recovery(|p| p.parse_enum_impl(), "enum declaration", Recovery::Item)
+                    .map(Item::Enum),
+                Token::Interface => self.parse_with_recovery(|p| p.parse_interface_impl(), "interface declaration", Recovery::Item)
+                    .map(Item::Interface),
+                Token::Impl => self.parse_with_recovery(|p| p.parse_impl_block(), "impl block", Recovery::Item)
+                    .map(Item::Impl),
+                // Parse top-level const declaration
+                Token::Const => self.parse_with_recovery(|p| p.parse_const_declaration(), "const declaration", Recovery::Item)
+                    .map(Item::Const),
+                Token::Extern => self.parse_with_recovery(|p| p.parse_extern_function_impl(), "extern function declaration", Recovery::Item)
+                    .map(Item::ExternFunction),
                 Token::Eof => break,
                 _ => {
                     let error = ParseError {
-                        span: Span::single(self.lexer.position()),
+                        span: self.current_span,
                         kind: ParseErrorKind::UnexpectedToken {
-                            expected: "function, struct, or const declaration".to_string(),
+                            expected: "function, struct, class, enum, interface, impl, or const declaration".to_string(),
                             found: format!("{}", self.current_token),
                         },
                     };
-                    errors.push(self.recover_from_error(error));
+                    self.recover_from_error(error, Recovery::Item, start);
+                    None
                 }
-            }
+            };
+            items.extend(item);
         }
         
-        // For now, return the first error if any occurred
-        // In a more sophisticated implementation, you might collect all errors
-        if let Some(first_error) = errors.into_iter().next() {
-            return Err(first_error);
+        Program {
+            package,
+            imports,
+            items,
         }
+    }
+
+    fn parse_package_declaration(&mut self) -> Result<String, ParseError> {
+        self.consume(Token::Package, "Expected 'package'")?;
         
-        Ok(Program {
-            package: "main".to_string(), // Default package
-            imports: Vec::new(),         // No imports for now
-            items,
-        })
+        let name = if let Token::Identifier(name) = &self.current_token {
+            let name = name.clone();
+            self.advance()?;
+            name
+        } else {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "package name".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        };
+        
+        // Optional trailing semicolon
+        if matches!(self.current_token, Token::Semicolon) {
+            self.advance()?;
+        }
+        
+        Ok(name)
+    }
+
+    fn parse_import_declaration(&mut self) -> Result<Import, ParseError> {
+        let start = self.start();
+        self.consume(Token::Import, "Expected 'import'")?;
+        
+        let path = if let Token::String(path) = &self.current_token {
+            let path = path.clone();
+            self.advance()?;
+            path
+        } else {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "import path string".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        };
+        
+        if path.is_empty() || path.split('/').any(|segment| segment.is_empty()) {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::InvalidSyntax {
+                    message: format!("Invalid import path \"{}\"", path),
+                },
+            });
+        }
+        
+        // Optional alias: import "path" as name
+        let alias = if matches!(&self.current_token, Token::Identifier(word) if word == "as") {
+            self.advance()?; // consume 'as'
+            if let Token::Identifier(alias) = &self.current_token {
+                let alias = alias.clone();
+                self.advance()?;
+                Some(alias)
+            } else {
+                return Err(ParseError {
+                    span: self.current_span,
+                    kind: ParseErrorKind::UnexpectedToken {
+                        expected: "import alias after 'as'".to_string(),
+                        found: format!("{}", self.current_token),
+                    },
+                });
+            }
+        } else {
+            None
+        };
+        
+        // Optional trailing semicolon
+        if matches!(self.current_token, Token::Semicolon) {
+            self.advance()?;
+        }
+        
+        Ok(Import { path, alias, span: self.span_from(start) })
     }
 
     fn parse_const_declaration(&mut self) -> Result<Const, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
         // Check for visibility modifier
         let visibility = if matches!(self.current_token, Token::Pub) {
             self.advance()?; // consume 'pub'
@@ -302,15 +635,22 @@ impl FluxParser {
             self.consume(Token::Assign, "Expected '=' after const type")?;
             let value = self.parse_expression_impl()?;
             
+            // Optional trailing semicolon
+            if matches!(self.current_token, Token::Semicolon) {
+                self.advance()?;
+            }
+            
             Ok(Const {
                 name,
                 type_: type_annotation,
                 value,
                 visibility,
+                doc,
+                span: self.span_from(start),
             })
         } else {
             Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "identifier".to_string(),
                     found: format!("{}", self.current_token),
@@ -323,6 +663,12 @@ impl FluxParser {
         self.parse_logical_or()
     }
 
+    // Build a binary expression spanning both operands
+    fn binary(&self, left: Expression, op: BinaryOp, right: Expression) -> Expression {
+        let span = Span::new(left.span.start, right.span.end);
+        Expression::new(ExpressionKind::Binary(Box::new(left), op, Box::new(right)), span)
+    }
+
     // Logical OR (lowest precedence)
     fn parse_logical_or(&mut self) -> Result<Expression, ParseError> {
         let mut expr = self.parse_logical_and()?;
@@ -331,7 +677,7 @@ impl FluxParser {
             let op = BinaryOp::Or;
             self.advance()?;
             let right = self.parse_logical_and()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -345,7 +691,7 @@ impl FluxParser {
             let op = BinaryOp::And;
             self.advance()?;
             let right = self.parse_equality()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -362,7 +708,7 @@ impl FluxParser {
         } {
             self.advance()?;
             let right = self.parse_comparison()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -370,7 +716,7 @@ impl FluxParser {
 
     // Comparison operators
     fn parse_comparison(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = self.parse_bitwise_or()?;
+        let mut expr = self.parse_range()?;
 
         while let Some(op) = match &self.current_token {
             Token::Greater => Some(BinaryOp::Greater),
@@ -380,8 +726,44 @@ impl FluxParser {
             _ => None,
         } {
             self.advance()?;
-            let right = self.parse_bitwise_or()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            let right = self.parse_range()?;
+            expr = self.binary(expr, op, right);
+        }
+
+        Ok(expr)
+    }
+
+    // Ranges `a..b` and `a..=b`, which don't chain, with an optional `step` (a contextual keyword)
+    fn parse_range(&mut self) -> Result<Expression, ParseError> {
+        let start = self.parse_coalesce()?;
+
+        let inclusive = match self.current_token {
+            Token::DotDot => false,
+            Token::DotDotEq => true,
+            _ => return Ok(start),
+        };
+        self.advance()?;
+        let end = self.parse_coalesce()?;
+        let step = if matches!(&self.current_token, Token::Identifier(keyword) if keyword == "step") {
+            self.advance()?;
+            Some(Box::new(self.parse_coalesce()?))
+        } else {
+            None
+        };
+
+        let last = step.as_deref().unwrap_or(&end);
+        let span = Span::new(start.span.start, last.span.end);
+        Ok(Expression::new(ExpressionKind::Range(Box::new(start), Box::new(end), step, inclusive), span))
+    }
+
+    // Null coalescing, which is right-associative: `a ?? b ?? c` tries `a`, then `b`
+    fn parse_coalesce(&mut self) -> Result<Expression, ParseError> {
+        let expr = self.parse_bitwise_or()?;
+
+        if matches!(self.current_token, Token::DoubleQuestion) {
+            self.advance()?;
+            let right = self.parse_coalesce()?;
+            return Ok(self.binary(expr, BinaryOp::Coalesce, right));
         }
 
         Ok(expr)
@@ -395,7 +777,7 @@ impl FluxParser {
             let op = BinaryOp::BitwiseOr;
             self.advance()?;
             let right = self.parse_bitwise_xor()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -409,7 +791,7 @@ impl FluxParser {
             let op = BinaryOp::BitwiseXor;
             self.advance()?;
             let right = self.parse_bitwise_and()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -423,7 +805,7 @@ impl FluxParser {
             let op = BinaryOp::BitwiseAnd;
             self.advance()?;
             let right = self.parse_shift()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -440,7 +822,7 @@ impl FluxParser {
         } {
             self.advance()?;
             let right = self.parse_term()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -451,13 +833,13 @@ impl FluxParser {
         let mut expr = self.parse_factor()?;
 
         while let Some(op) = match &self.current_token {
-            Token::Plus => Some(BinaryOp::Add),
-            Token::Minus => Some(BinaryOp::Subtract),
+            Token::Plus if self.continues_line() => Some(BinaryOp::Add),
+            Token::Minus if self.continues_line() => Some(BinaryOp::Subtract),
             _ => None,
         } {
             self.advance()?;
             let right = self.parse_factor()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -475,7 +857,7 @@ impl FluxParser {
         } {
             self.advance()?;
             let right = self.parse_unary()?;
-            expr = Expression::Binary(Box::new(expr), op, Box::new(right));
+            expr = self.binary(expr, op, right);
         }
 
         Ok(expr)
@@ -483,6 +865,7 @@ impl FluxParser {
 
     // Unary operators
     fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
         if let Some(op) = match &self.current_token {
             Token::Not => Some(UnaryOp::Not),
             Token::Minus => Some(UnaryOp::Minus),
@@ -491,55 +874,68 @@ impl FluxParser {
             _ => None,
         } {
             self.advance()?;
+            if op == UnaryOp::Minus && self.current_token == Token::Integer(i64::MIN) {
+                self.advance()?;
+                let span = self.span_from(start);
+                return Ok(Expression::literal(Literal::Integer(i64::MIN), self.spelling(span), span));
+            }
             let expr = self.parse_unary()?;
-            Ok(Expression::Unary(op, Box::new(expr)))
+            Ok(Expression::new(ExpressionKind::Unary(op, Box::new(expr)), self.span_from(start)))
         } else {
             self.parse_postfix()
         }
     }
 
-    // Postfix expressions (calls, indexing, field access)
+    // Postfix expressions (calls, indexing, field access, `?` and `!`)
     fn parse_postfix(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
         let mut expr = self.parse_primary()?;
 
         loop {
             match &self.current_token {
-                Token::LeftParen => {
+                Token::LeftParen if self.continues_line() => {
                     // Function call
                     self.advance()?; // consume '('
-                    let mut args = Vec::new();
-                    
-                    if !matches!(self.current_token, Token::RightParen) {
-                        loop {
-                            args.push(self.parse_expression_impl()?);
-                            if matches!(self.current_token, Token::Comma) {
-                                self.advance()?; // consume ','
-                            } else {
-                                break;
+                    let args = self.with_grouped(true, |p| {
+                        let mut args = Vec::new();
+                        if !matches!(p.current_token, Token::RightParen) {
+                            loop {
+                                args.push(p.parse_expression_impl()?);
+                                if matches!(p.current_token, Token::Comma) {
+                                    p.advance()?; // consume ','
+                                } else {
+                                    break;
+                                }
                             }
                         }
-                    }
+                        Ok(args)
+                    })?;
                     
                     self.consume(Token::RightParen, "Expected ')' after function arguments")?;
-                    expr = Expression::Call(Box::new(expr), args);
+                    expr = Expression::new(ExpressionKind::Call(Box::new(expr), args), self.span_from(start));
                 }
-                Token::LeftBracket => {
+                Token::LeftBracket if self.continues_line() => {
                     // Array/map indexing
                     self.advance()?; // consume '['
-                    let index = self.parse_expression_impl()?;
+                    let index = self.with_grouped(true, |p| p.parse_expression_impl())?;
                     self.consume(Token::RightBracket, "Expected ']' after index")?;
-                    expr = Expression::Index(Box::new(expr), Box::new(index));
+                    expr = Expression::new(ExpressionKind::Index(Box::new(expr), Box::new(index)), self.span_from(start));
                 }
-                Token::Dot => {
-                    // Field access
-                    self.advance()?; // consume '.'
+                Token::Dot | Token::QuestionDot => {
+                    // Field access; `expr?.field` is `expr?` followed by `.field`,
+                    // which the type checker reads as safe navigation on a
+                    // nullable `expr`
+                    if matches!(self.current_token, Token::QuestionDot) {
+                        expr = Expression::new(ExpressionKind::Unary(UnaryOp::Try, Box::new(expr)), self.span_from(start));
+                    }
+                    self.advance()?; // consume '.' or '?.'
                     if let Token::Identifier(field_name) = &self.current_token {
                         let field_name = field_name.clone();
                         self.advance()?;
-                        expr = Expression::Field(Box::new(expr), field_name);
+                        expr = Expression::new(ExpressionKind::Field(Box::new(expr), field_name), self.span_from(start));
                     } else {
                         return Err(ParseError {
-                            span: Span::single(self.lexer.position()),
+                            span: self.current_span,
                             kind: ParseErrorKind::UnexpectedToken {
                                 expected: "field name".to_string(),
                                 found: format!("{}", self.current_token),
@@ -547,6 +943,16 @@ impl FluxParser {
                         });
                     }
                 }
+                Token::Question => {
+                    // Error propagation: `expr?`
+                    self.advance()?; // consume '?'
+                    expr = Expression::new(ExpressionKind::Unary(UnaryOp::Try, Box::new(expr)), self.span_from(start));
+                }
+                // Unwrap: `expr!`, written with no space before the `!`
+                Token::Not if self.current_span.start.offset == self.previous_end.offset => {
+                    self.advance()?; // consume '!'
+                    expr = Expression::new(ExpressionKind::Unary(UnaryOp::Unwrap, Box::new(expr)), self.span_from(start));
+                }
                 _ => break,
             }
         }
@@ -556,95 +962,390 @@ impl FluxParser {
 
     // Primary expressions (literals, identifiers, parenthesized expressions, etc.)
     fn parse_primary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
+        
         match &self.current_token {
             // Literals
-            Token::Integer(n) => {
-                let value = *n;
-                self.advance()?;
-                Ok(Expression::Literal(Literal::Integer(value)))
-            }
-            Token::Float(f) => {
-                let value = *f;
-                self.advance()?;
-                Ok(Expression::Literal(Literal::Float(value)))
+            Token::Integer(_) | Token::Float(_) | Token::Byte(_) => {
+                let (literal, spelling) = self.parse_number()?;
+                Ok(Expression::literal(literal, spelling, self.span_from(start)))
             }
             Token::String(s) => {
-                let value = s.clone();
+                let literal = Literal::String(s.clone());
+                let spelling = self.spelling(self.current_span);
                 self.advance()?;
-                Ok(Expression::Literal(Literal::String(value)))
+                Ok(Expression::literal(literal, spelling, self.span_from(start)))
             }
+            Token::StringStart(_) => self.parse_interpolated_string(),
             Token::Boolean(b) => {
                 let value = *b;
                 self.advance()?;
-                Ok(Expression::Literal(Literal::Boolean(value)))
+                Ok(Expression::new(ExpressionKind::Literal(Literal::Boolean(value)), self.span_from(start)))
             }
             Token::Character(c) => {
                 let value = *c;
                 self.advance()?;
-                Ok(Expression::Literal(Literal::Character(value)))
+                Ok(Expression::new(ExpressionKind::Literal(Literal::Character(value)), self.span_from(start)))
+            }
+            Token::Null => {
+                self.advance()?;
+                Ok(Expression::new(ExpressionKind::Literal(Literal::Null), self.span_from(start)))
             }
             
-            // Identifier
+            // Identifier or qualified path (module::name)
             Token::Identifier(name) => {
                 let name = name.clone();
                 self.advance()?;
-                Ok(Expression::Identifier(name))
+                
+                let segments = if matches!(self.current_token, Token::DoubleColon) {
+                    self.parse_path_segments(name)?
+                } else {
+                    vec![name]
+                };
+                
+                if self.at_struct_literal() {
+                    return self.parse_struct_literal(segments.join("::"), start);
+                }
+                
+                let kind = if segments.len() == 1 {
+                    ExpressionKind::Identifier(segments.into_iter().next().unwrap_or_default())
+                } else {
+                    ExpressionKind::Path(segments)
+                };
+                Ok(Expression::new(kind, self.span_from(start)))
             }
             
-            // Parenthesized expression
+            // Parenthesized expression, or a tuple `(a, b)`
             Token::LeftParen => {
                 self.advance()?; // consume '('
-                let expr = self.parse_expression_impl()?;
+                let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, false);
+                let elements = self.with_grouped(true, |p| p.parse_parenthesized_elements());
+                self.no_struct_literal = no_struct_literal;
+                let mut elements = elements?;
                 self.consume(Token::RightParen, "Expected ')' after expression")?;
-                Ok(expr)
+                
+                // The parentheses belong to the expression's span
+                if elements.len() == 1 {
+                    let mut expr = elements.remove(0);
+                    expr.span = self.span_from(start);
+                    Ok(expr)
+                } else {
+                    Ok(Expression::new(ExpressionKind::Tuple(elements), self.span_from(start)))
+                }
             }
             
             // Array literal
             Token::LeftBracket => {
                 self.advance()?; // consume '['
-                let mut elements = Vec::new();
-                
-                if !matches!(self.current_token, Token::RightBracket) {
-                    loop {
-                        elements.push(self.parse_expression_impl()?);
-                        if matches!(self.current_token, Token::Comma) {
-                            self.advance()?; // consume ','
-                        } else {
-                            break;
+                let elements = self.with_grouped(true, |p| {
+                    let mut elements = Vec::new();
+                    if !matches!(p.current_token, Token::RightBracket) {
+                        loop {
+                            elements.push(p.parse_expression_impl()?);
+                            if matches!(p.current_token, Token::Comma) {
+                                p.advance()?; // consume ','
+                            } else {
+                                break;
+                            }
                         }
                     }
-                }
+                    Ok(elements)
+                })?;
                 
                 self.consume(Token::RightBracket, "Expected ']' after array elements")?;
-                Ok(Expression::Array(elements))
+                Ok(Expression::new(ExpressionKind::Array(elements), self.span_from(start)))
             }
             
-            // Block expression (simplified - no map literals for now)
+            // Block expression or map literal
             Token::LeftBrace => {
-                self.parse_block_expression()
+                self.parse_brace_expression()
             }
             
+            // Lambda: `|x: int| x + 1`, or `|| ...` without parameters
+            Token::BitwiseOr | Token::Or => self.parse_lambda(),
+            
             _ => Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::InvalidExpression,
             }),
         }
     }
-
-    // Parse a block expression
-    fn parse_block_expression(&mut self) -> Result<Expression, ParseError> {
-        self.advance()?; // consume '{'
+    
+    /// Parse a string with interpolated expressions; the lexer hands over
+    /// its text in pieces with the tokens of each expression in between
+    fn parse_interpolated_string(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
+        let mut parts = Vec::new();
+        let mut text = match &self.current_token {
+            Token::StringStart(text) => text.clone(),
+            _ => String::new(),
+        };
         
-        let mut statements = Vec::new();
+        loop {
+            if !text.is_empty() {
+                parts.push(StringPart::Text(std::mem::take(&mut text)));
+            }
+            self.advance()?; // consume the text up to '{'
+            
+            let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, false);
+            let expr = self.parse_expression_impl();
+            self.no_struct_literal = no_struct_literal;
+            parts.push(StringPart::Expression(expr?));
+            
+            match &self.current_token {
+                Token::StringMiddle(middle) => text = middle.clone(),
+                Token::StringEnd(end) => {
+                    if !end.is_empty() {
+                        parts.push(StringPart::Text(end.clone()));
+                    }
+                    self.advance()?;
+                    return Ok(Expression::new(ExpressionKind::Interpolated(parts), self.span_from(start)));
+                }
+                _ => {
+                    return Err(ParseError {
+                        span: self.current_span,
+                        kind: ParseErrorKind::UnexpectedToken {
+                            expected: "'}' after interpolated expression".to_string(),
+                            found: format!("{}", self.current_token),
+                        },
+                    });
+                }
+            }
+        }
+    }
+    
+    /// Parse the comma-separated expressions inside parentheses; there is
+    /// more than one in a tuple
+    fn parse_parenthesized_elements(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut elements = vec![self.parse_expression_impl()?];
+        while matches!(self.current_token, Token::Comma) {
+            self.advance()?; // consume ','
+            elements.push(self.parse_expression_impl()?);
+        }
+        Ok(elements)
+    }
+    
+    fn parse_lambda(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
+        
+        let mut parameters = Vec::new();
+        if matches!(self.current_token, Token::Or) {
+            self.advance()?; // consume '||'
+        } else {
+            self.consume(Token::BitwiseOr, "Expected '|' to start lambda parameters")?;
+            while !matches!(self.current_token, Token::BitwiseOr) {
+                parameters.push(self.parse_lambda_parameter()?);
+                if matches!(self.current_token, Token::Comma) {
+                    self.advance()?;
+                } else {
+                    break;
+                }
+            }
+            self.consume(Token::BitwiseOr, "Expected '|' after lambda parameters")?;
+        }
+        
+        // A declared return type must be followed by a block
+        let (return_type, body) = if matches!(self.current_token, Token::Arrow) {
+            self.advance()?; // consume '->'
+            let return_type = self.parse_type()?;
+            if !matches!(self.current_token, Token::LeftBrace) {
+                return Err(ParseError {
+                    span: self.current_span,
+                    kind: ParseErrorKind::UnexpectedToken {
+                        expected: "'{' after lambda return type".to_string(),
+                        found: format!("{}", self.current_token),
+                    },
+                });
+            }
+            (Some(return_type), self.parse_block_expression()?)
+        } else if matches!(self.current_token, Token::LeftBrace) {
+            // A braced body is always a block, even when empty
+            (None, self.parse_block_expression()?)
+        } else {
+            (None, self.parse_expression_impl()?)
+        };
+        
+        Ok(Expression::new(
+            ExpressionKind::Lambda(parameters, return_type, Box::new(body)),
+            self.span_from(start),
+        ))
+    }
+    
+    fn parse_lambda_parameter(&mut self) -> Result<LambdaParameter, ParseError> {
+        let start = self.start();
+        
+        let is_mutable = if matches!(self.current_token, Token::Mut) {
+            self.advance()?; // consume 'mut'
+            true
+        } else {
+            false
+        };
+        
+        let name = match &self.current_token {
+            Token::Identifier(name) => name.clone(),
+            _ => return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "parameter name".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            }),
+        };
+        self.advance()?;
+        
+        // The type may be left to inference
+        let type_ = if matches!(self.current_token, Token::Colon) {
+            self.advance()?; // consume ':'
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        
+        Ok(LambdaParameter {
+            name,
+            type_,
+            is_mutable,
+            span: self.span_from(start),
+        })
+    }
+
+    // Parse the head of an `if`/`while`/`for`/`match`, where a struct literal is not allowed
+    fn parse_condition(&mut self) -> Result<Expression, ParseError> {
+        let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, true);
+        let expr = self.parse_expression_impl();
+        self.no_struct_literal = no_struct_literal;
+        expr
+    }
+    
+    // Whether a `{` after a name starts a struct literal: `Name { field: ... }` or `Name {}`
+    fn at_struct_literal(&self) -> bool {
+        !self.no_struct_literal
+            && matches!(self.current_token, Token::LeftBrace)
+            && matches!(self.peek_token, Token::Identifier(_) | Token::RightBrace)
+    }
+    
+    // Parse `{ field: value, ... }` after a struct or variant name
+    fn parse_struct_literal(&mut self, name: String, start: Position) -> Result<Expression, ParseError> {
+        self.consume(Token::LeftBrace, "Expected '{' to start struct literal")?;
+        
+        let mut fields = Vec::new();
         while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
-            statements.push(self.parse_statement_impl()?);
+            let field_start = self.start();
+            let field_name = if let Token::Identifier(field_name) = &self.current_token {
+                let field_name = field_name.clone();
+                self.advance()?;
+                field_name
+            } else {
+                return Err(ParseError {
+                    span: self.current_span,
+                    kind: ParseErrorKind::UnexpectedToken {
+                        expected: "field name".to_string(),
+                        found: format!("{}", self.current_token),
+                    },
+                });
+            };
+            
+            // `Point { x }` is shorthand for `Point { x: x }`
+            let value = if matches!(self.current_token, Token::Colon) {
+                self.advance()?; // consume ':'
+                self.parse_expression_impl()?
+            } else {
+                Expression::new(ExpressionKind::Identifier(field_name.clone()), self.span_from(field_start))
+            };
+            fields.push((field_name, value));
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance()?; // consume ','
+            } else {
+                break;
+            }
         }
         
+        self.consume(Token::RightBrace, "Expected '}' after struct literal fields")?;
+        Ok(Expression::new(ExpressionKind::StructLiteral(name, fields), self.span_from(start)))
+    }
+
+    // Parse the remaining `::name` segments of a qualified path
+    fn parse_path_segments(&mut self, first: String) -> Result<Vec<String>, ParseError> {
+        let mut segments = vec![first];
+        
+        while matches!(self.current_token, Token::DoubleColon) {
+            self.advance()?; // consume '::'
+            if let Token::Identifier(segment) = &self.current_token {
+                segments.push(segment.clone());
+                self.advance()?;
+            } else {
+                return Err(ParseError {
+                    span: self.current_span,
+                    kind: ParseErrorKind::UnexpectedToken {
+                        expected: "identifier after '::'".to_string(),
+                        found: format!("{}", self.current_token),
+                    },
+                });
+            }
+        }
+        
+        Ok(segments)
+    }
+
+    // Parse a block expression
+    /// Parse `{ ... }` in expression position: `{}` and `{key: value, ...}`
+    /// are map literals, anything else is a block. A map key is followed by
+    /// ':', which cannot start a statement.
+    fn parse_brace_expression(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
+        self.advance()?; // consume '{'
+        
+        if !matches!(self.current_token, Token::RightBrace) && !matches!(self.peek_token, Token::Colon) {
+            return self.parse_block_contents(start);
+        }
+        
+        let mut pairs = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) {
+            let key = self.parse_expression_impl()?;
+            self.consume(Token::Colon, "Expected ':' after map key")?;
+            let value = self.parse_expression_impl()?;
+            pairs.push((key, value));
+            if matches!(self.current_token, Token::Comma) {
+                self.advance()?; // consume ','
+            } else {
+                break;
+            }
+        }
+        
+        self.consume(Token::RightBrace, "Expected '}' after map entries")?;
+        Ok(Expression::new(ExpressionKind::Map(pairs), self.span_from(start)))
+    }
+    
+    fn parse_block_expression(&mut self) -> Result<Expression, ParseError> {
+        let start = self.start();
+        self.advance()?; // consume '{'
+        self.parse_block_contents(start)
+    }
+    
+    /// Parse the statements of a block whose '{' has been consumed
+    fn parse_block_contents(&mut self, start: Position) -> Result<Expression, ParseError> {
+        let statements = self.with_grouped(false, |p| {
+            let mut statements = Vec::new();
+            while !matches!(p.current_token, Token::RightBrace) && !p.is_at_end() {
+                statements.push(p.parse_statement_impl()?);
+                p.skip_semicolons()?;
+            }
+            Ok(statements)
+        })?;
+        
         self.consume(Token::RightBrace, "Expected '}' after block")?;
-        Ok(Expression::Block(Block { statements }))
+        let span = self.span_from(start);
+        Ok(Expression::new(ExpressionKind::Block(Block { statements, span }), span))
     }
     
     fn parse_statement_impl(&mut self) -> Result<Statement, ParseError> {
+        let start = self.start();
+        let kind = self.parse_statement_kind()?;
+        Ok(Statement::new(kind, self.span_from(start)))
+    }
+    
+    fn parse_statement_kind(&mut self) -> Result<StatementKind, ParseError> {
         match &self.current_token {
             Token::Let => {
                 self.parse_let_statement()
@@ -682,57 +1383,66 @@ impl FluxParser {
                     // Look ahead to see if this is an assignment
                     if matches!(self.peek_token, Token::Assign | Token::PlusAssign | Token::MinusAssign | 
                                Token::MultiplyAssign | Token::DivideAssign | Token::ModuloAssign) {
-                        self.parse_assignment_statement()
-                    } else {
-                        // Parse as expression statement
-                        let expr = self.parse_expression_impl()?;
-                        Ok(Statement::Expression(expr))
+                        return self.parse_assignment_statement();
                     }
-                } else {
-                    // Parse as expression statement
-                    let expr = self.parse_expression_impl()?;
-                    Ok(Statement::Expression(expr))
                 }
+                
+                // Parse as expression statement, unless it turns out to be a
+                // field or index assignment target
+                let expr = self.parse_expression_impl()?;
+                if matches!(self.current_token, Token::Assign)
+                    && matches!(expr.kind, ExpressionKind::Field(..) | ExpressionKind::Index(..)) {
+                    self.advance()?; // consume '='
+                    let value = self.parse_expression_impl()?;
+                    return Ok(StatementKind::Assignment(expr, value));
+                }
+                Ok(StatementKind::Expression(expr))
             }
         }
     }
 
-    fn parse_let_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_let_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'let'
         
-        if let Token::Identifier(name) = &self.current_token {
-            let name = name.clone();
-            self.advance()?;
-            
-            // Optional type annotation
-            let type_annotation = if matches!(self.current_token, Token::Colon) {
-                self.advance()?; // consume ':'
-                Some(self.parse_type()?)
-            } else {
-                None
-            };
-            
-            // Optional initialization
-            let value = if matches!(self.current_token, Token::Assign) {
-                self.advance()?; // consume '='
-                Some(self.parse_expression_impl()?)
-            } else {
-                None
-            };
-            
-            Ok(Statement::Let(name, type_annotation, value))
-        } else {
-            Err(ParseError {
-                span: Span::single(self.lexer.position()),
+        if !matches!(self.current_token, Token::Identifier(_) | Token::LeftParen) {
+            return Err(ParseError {
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "identifier".to_string(),
                     found: format!("{}", self.current_token),
                 },
-            })
+            });
         }
+        let pattern = self.parse_pattern()?;
+        
+        // Optional type annotation
+        let type_annotation = if matches!(self.current_token, Token::Colon) {
+            self.advance()?; // consume ':'
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        
+        // Optional initialization; a destructuring `let` needs a value
+        let value = if matches!(self.current_token, Token::Assign) {
+            self.advance()?; // consume '='
+            Some(self.parse_expression_impl()?)
+        } else if !matches!(pattern, Pattern::Identifier(_)) {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "'=' after destructuring pattern".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        } else {
+            None
+        };
+        
+        Ok(StatementKind::Let(pattern, type_annotation, value))
     }
 
-    fn parse_const_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_const_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'const'
         
         if let Token::Identifier(name) = &self.current_token {
@@ -745,10 +1455,10 @@ impl FluxParser {
             self.consume(Token::Assign, "Expected '=' after const type")?;
             let value = self.parse_expression_impl()?;
             
-            Ok(Statement::Const(name, type_annotation, value))
+            Ok(StatementKind::Const(name, type_annotation, value))
         } else {
             Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "identifier".to_string(),
                     found: format!("{}", self.current_token),
@@ -757,42 +1467,44 @@ impl FluxParser {
         }
     }
 
-    fn parse_return_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_return_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'return'
         
-        if self.check(&Token::Semicolon) || self.check(&Token::RightBrace) || self.is_at_end() {
-            Ok(Statement::Return(None))
+        // A value must start on the same line as the keyword
+        if self.check(&Token::Semicolon) || self.check(&Token::RightBrace) || self.is_at_end() || self.on_new_line() {
+            Ok(StatementKind::Return(None))
         } else {
             let expr = self.parse_expression_impl()?;
-            Ok(Statement::Return(Some(expr)))
+            Ok(StatementKind::Return(Some(expr)))
         }
     }
 
-    fn parse_break_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_break_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'break'
         
-        if self.check(&Token::Semicolon) || self.check(&Token::RightBrace) || self.is_at_end() {
-            Ok(Statement::Break(None))
+        // A value must start on the same line as the keyword
+        if self.check(&Token::Semicolon) || self.check(&Token::RightBrace) || self.is_at_end() || self.on_new_line() {
+            Ok(StatementKind::Break(None))
         } else {
             let expr = self.parse_expression_impl()?;
-            Ok(Statement::Break(Some(expr)))
+            Ok(StatementKind::Break(Some(expr)))
         }
     }
 
-    fn parse_continue_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_continue_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'continue'
-        Ok(Statement::Continue)
+        Ok(StatementKind::Continue)
     }
 
-    fn parse_go_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_go_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'go'
         let expr = self.parse_expression_impl()?;
-        Ok(Statement::Go(expr))
+        Ok(StatementKind::Go(expr))
     }
 
-    fn parse_if_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_if_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'if'
-        let condition = self.parse_expression_impl()?;
+        let condition = self.parse_condition()?;
         let then_block = self.parse_block()?;
         
         let else_block = if matches!(self.current_token, Token::Else) {
@@ -802,33 +1514,32 @@ impl FluxParser {
             None
         };
         
-        Ok(Statement::If(condition, then_block, else_block))
+        Ok(StatementKind::If(condition, then_block, else_block))
     }
 
-    fn parse_while_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_while_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'while'
-        let condition = self.parse_expression_impl()?;
+        let condition = self.parse_condition()?;
         let body = self.parse_block()?;
-        Ok(Statement::While(condition, body))
+        Ok(StatementKind::While(condition, body))
     }
 
-    fn parse_for_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_for_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'for'
         
-        if let Token::Identifier(var_name) = &self.current_token {
-            let var_name = var_name.clone();
-            self.advance()?;
+        if matches!(self.current_token, Token::Identifier(_) | Token::LeftParen) {
+            let pattern = self.parse_pattern()?;
             
             // Expect 'in' keyword (we'll use identifier for now)
             if let Token::Identifier(keyword) = &self.current_token {
                 if keyword == "in" {
                     self.advance()?;
-                    let iterable = self.parse_expression_impl()?;
+                    let iterable = self.parse_condition()?;
                     let body = self.parse_block()?;
-                    Ok(Statement::For(var_name, iterable, body))
+                    Ok(StatementKind::For(pattern, iterable, body))
                 } else {
                     Err(ParseError {
-                        span: Span::single(self.lexer.position()),
+                        span: self.current_span,
                         kind: ParseErrorKind::UnexpectedToken {
                             expected: "'in'".to_string(),
                             found: format!("{}", self.current_token),
@@ -837,7 +1548,7 @@ impl FluxParser {
                 }
             } else {
                 Err(ParseError {
-                    span: Span::single(self.lexer.position()),
+                    span: self.current_span,
                     kind: ParseErrorKind::UnexpectedToken {
                         expected: "'in'".to_string(),
                         found: format!("{}", self.current_token),
@@ -846,7 +1557,7 @@ impl FluxParser {
             }
         } else {
             Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "variable name".to_string(),
                     found: format!("{}", self.current_token),
@@ -855,9 +1566,9 @@ impl FluxParser {
         }
     }
 
-    fn parse_match_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_match_statement(&mut self) -> Result<StatementKind, ParseError> {
         self.advance()?; // consume 'match'
-        let expr = self.parse_expression_impl()?;
+        let expr = self.parse_condition()?;
         self.consume(Token::LeftBrace, "Expected '{' after match expression")?;
         
         let mut arms = Vec::new();
@@ -866,21 +1577,22 @@ impl FluxParser {
         }
         
         self.consume(Token::RightBrace, "Expected '}' after match arms")?;
-        Ok(Statement::Match(expr, arms))
+        Ok(StatementKind::Match(expr, arms))
     }
 
-    fn parse_assignment_statement(&mut self) -> Result<Statement, ParseError> {
+    fn parse_assignment_statement(&mut self) -> Result<StatementKind, ParseError> {
         let target = self.parse_expression_impl()?;
         
         // For now, only handle simple assignment
         self.consume(Token::Assign, "Expected '=' in assignment")?;
         let value = self.parse_expression_impl()?;
         
-        Ok(Statement::Assignment(target, value))
+        Ok(StatementKind::Assignment(target, value))
     }
 
     // Parse a match arm
     fn parse_match_arm(&mut self) -> Result<MatchArm, ParseError> {
+        let start = self.start();
         let pattern = self.parse_pattern()?;
         
         let guard = if matches!(self.current_token, Token::If) {
@@ -893,48 +1605,130 @@ impl FluxParser {
         self.consume(Token::FatArrow, "Expected '=>' after match pattern")?;
         let body = self.parse_block()?;
         
-        Ok(MatchArm { pattern, guard, body })
+        Ok(MatchArm { pattern, guard, body, span: self.span_from(start) })
     }
 
     // Parse a pattern
     fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
         match &self.current_token {
-            Token::Integer(n) => {
-                let value = *n;
-                self.advance()?;
-                Ok(Pattern::Literal(Literal::Integer(value)))
-            }
-            Token::Float(f) => {
-                let value = *f;
-                self.advance()?;
-                Ok(Pattern::Literal(Literal::Float(value)))
+            Token::Integer(_) | Token::Float(_) | Token::Byte(_) => {
+                let (literal, spelling) = self.parse_number()?;
+                Ok(Pattern::Literal(literal, spelling))
             }
             Token::String(s) => {
-                let value = s.clone();
+                let literal = Literal::String(s.clone());
+                let spelling = self.spelling(self.current_span);
                 self.advance()?;
-                Ok(Pattern::Literal(Literal::String(value)))
+                Ok(Pattern::Literal(literal, spelling))
             }
             Token::Boolean(b) => {
                 let value = *b;
                 self.advance()?;
-                Ok(Pattern::Literal(Literal::Boolean(value)))
+                Ok(Pattern::Literal(Literal::Boolean(value), Spelling::default()))
             }
             Token::Character(c) => {
                 let value = *c;
                 self.advance()?;
-                Ok(Pattern::Literal(Literal::Character(value)))
+                Ok(Pattern::Literal(Literal::Character(value), Spelling::default()))
+            }
+            Token::Null => {
+                self.advance()?;
+                Ok(Pattern::Literal(Literal::Null, Spelling::default()))
+            }
+            Token::LeftParen => {
+                self.advance()?; // consume '('
+                let mut patterns = vec![self.parse_pattern()?];
+                while matches!(self.current_token, Token::Comma) {
+                    self.advance()?; // consume ','
+                    patterns.push(self.parse_pattern()?);
+                }
+                self.consume(Token::RightParen, "Expected ')' after tuple patterns")?;
+                
+                // `(p)` is just `p`
+                if patterns.len() == 1 {
+                    Ok(patterns.remove(0))
+                } else {
+                    Ok(Pattern::Tuple(patterns))
+                }
             }
             Token::Identifier(name) => {
                 let name = name.clone();
                 self.advance()?;
-                if name == "_" {
-                    Ok(Pattern::Wildcard)
+                
+                let path = if matches!(self.current_token, Token::DoubleColon) {
+                    self.parse_path_segments(name)?
+                } else if name == "_" {
+                    return Ok(Pattern::Wildcard);
                 } else {
-                    Ok(Pattern::Identifier(name))
+                    vec![name]
+                };
+                
+                match &self.current_token {
+                    // Tuple variant: `Shape::Circle(r)` or `Circle(r)`
+                    Token::LeftParen => {
+                        self.advance()?; // consume '('
+                        let mut patterns = Vec::new();
+                        if !matches!(self.current_token, Token::RightParen) {
+                            loop {
+                                patterns.push(self.parse_pattern()?);
+                                if matches!(self.current_token, Token::Comma) {
+                                    self.advance()?; // consume ','
+                                } else {
+                                    break;
+                                }
+                            }
+                        }
+                        self.consume(Token::RightParen, "Expected ')' after variant patterns")?;
+                        
+                        // `Ok(p)` and `Err(p)` match the builtin `Result`
+                        match (path.as_slice(), patterns.len()) {
+                            ([name], 1) if name == "Ok" => Ok(Pattern::Result(ResultPattern::Ok(Box::new(patterns.remove(0))))),
+                            ([name], 1) if name == "Err" => Ok(Pattern::Result(ResultPattern::Err(Box::new(patterns.remove(0))))),
+                            _ => Ok(Pattern::Variant(path, patterns)),
+                        }
+                    }
+                    // Struct or struct-like variant: `Shape::Rect { w, h: height }`
+                    Token::LeftBrace => {
+                        self.advance()?; // consume '{'
+                        let mut fields = Vec::new();
+                        while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
+                            let field_name = if let Token::Identifier(field_name) = &self.current_token {
+                                let field_name = field_name.clone();
+                                self.advance()?;
+                                field_name
+                            } else {
+                                return Err(ParseError {
+                                    span: self.current_span,
+                                    kind: ParseErrorKind::UnexpectedToken {
+                                        expected: "field name".to_string(),
+                                        found: format!("{}", self.current_token),
+                                    },
+                                });
+                            };
+                            let pattern = if matches!(self.current_token, Token::Colon) {
+                                self.advance()?; // consume ':'
+                                self.parse_pattern()?
+                            } else {
+                                Pattern::Identifier(field_name.clone())
+                            };
+                            fields.push((field_name, pattern));
+                            
+                            if matches!(self.current_token, Token::Comma) {
+                                self.advance()?; // consume ','
+                            } else {
+                                break;
+                            }
+                        }
+                        self.consume(Token::RightBrace, "Expected '}' after field patterns")?;
+                        Ok(Pattern::Struct(path.join("::"), fields))
+                    }
+                    // Unit variant: `Shape::Empty`; a bare name is a binding
+                    _ if path.len() > 1 => Ok(Pattern::Variant(path, Vec::new())),
+                    _ => Ok(Pattern::Identifier(path.into_iter().next().unwrap_or_default())),
                 }
             }
             _ => Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "pattern".to_string(),
                     found: format!("{}", self.current_token),
@@ -943,40 +1737,57 @@ impl FluxParser {
         }
     }
 
-    // Parse a block of statements with error recovery
+    // Parse a block of statements with error recovery; broken statements are
+    // recorded and left out of the block
     fn parse_block(&mut self) -> Result<Block, ParseError> {
+        let start = self.start();
         self.consume(Token::LeftBrace, "Expected '{' to start block")?;
         
-        let mut statements = Vec::new();
-        let mut errors = Vec::new();
-        
-        while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
-            match self.parse_with_recovery(|p| p.parse_statement_impl(), "statement") {
-                Ok(stmt) => statements.push(stmt),
-                Err(error) => {
-                    errors.push(error);
-                    // Continue parsing after error recovery
-                }
+        let statements = self.with_grouped(false, |p| {
+            let mut statements = Vec::new();
+            // A declaration keyword means the block was never closed
+            while !matches!(p.current_token, Token::RightBrace) && !p.is_at_end() && !Self::starts_item(&p.current_token) {
+                statements.extend(p.parse_with_recovery(|p| p.parse_statement_impl(), "statement", Recovery::Statement));
+                p.skip_semicolons()?;
             }
-        }
+            Ok(statements)
+        })?;
         
         self.consume(Token::RightBrace, "Expected '}' to end block")?;
         
-        // For now, return the first error if any occurred
-        if let Some(first_error) = errors.into_iter().next() {
-            return Err(first_error);
-        }
-        
-        Ok(Block { statements })
+        Ok(Block { statements, span: self.span_from(start) })
     }
 
     // Parse a type annotation
     fn parse_type(&mut self) -> Result<Type, ParseError> {
+        let mut type_ = self.parse_base_type()?;
+        
+        // `T?` is a nullable `T`
+        while matches!(self.current_token, Token::Question) {
+            self.advance()?;
+            type_ = Type::Nullable(Box::new(type_));
+        }
+        
+        Ok(type_)
+    }
+    
+    fn parse_base_type(&mut self) -> Result<Type, ParseError> {
         match &self.current_token {
             Token::Identifier(name) => {
                 let name = name.clone();
                 self.advance()?;
                 
+                let name = if matches!(self.current_token, Token::DoubleColon) {
+                    self.parse_path_segments(name)?.join("::")
+                } else {
+                    name
+                };
+                
+                if matches!(self.current_token, Token::Less) {
+                    let args = self.parse_type_arguments()?;
+                    return self.generic_type(name, args);
+                }
+                
                 match name.as_str() {
                     "int" => Ok(Type::Int),
                     "float" => Ok(Type::Float),
@@ -984,6 +1795,7 @@ impl FluxParser {
                     "bool" => Ok(Type::Bool),
                     "char" => Ok(Type::Char),
                     "byte" => Ok(Type::Byte),
+                    "Range" => Ok(Type::Range),
                     _ => Ok(Type::Named(name)),
                 }
             }
@@ -993,8 +1805,43 @@ impl FluxParser {
                 self.consume(Token::RightBracket, "Expected ']' after array element type")?;
                 Ok(Type::Array(Box::new(element_type)))
             }
+            Token::LeftParen => {
+                // `()` is the unit type, `(A, B)` a tuple type and
+                // `(A, B) -> R` a function type
+                self.advance()?; // consume '('
+                let mut params = Vec::new();
+                if !matches!(self.current_token, Token::RightParen) {
+                    loop {
+                        params.push(self.parse_type()?);
+                        if matches!(self.current_token, Token::Comma) {
+                            self.advance()?;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.consume(Token::RightParen, "Expected ')' after parameter types")?;
+                
+                if matches!(self.current_token, Token::Arrow) {
+                    self.advance()?; // consume '->'
+                    let return_type = self.parse_type()?;
+                    Ok(Type::Function(params, Box::new(return_type)))
+                } else if params.is_empty() {
+                    Ok(Type::Unit)
+                } else if params.len() > 1 {
+                    Ok(Type::Tuple(params))
+                } else {
+                    Err(ParseError {
+                        span: self.current_span,
+                        kind: ParseErrorKind::UnexpectedToken {
+                            expected: "'->' after function parameter types".to_string(),
+                            found: format!("{}", self.current_token),
+                        },
+                    })
+                }
+            }
             _ => Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "type".to_string(),
                     found: format!("{}", self.current_token),
@@ -1003,7 +1850,86 @@ impl FluxParser {
         }
     }
     
+    /// Parse `<A, B>` after a generic type name
+    fn parse_type_arguments(&mut self) -> Result<Vec<Type>, ParseError> {
+        self.consume(Token::Less, "Expected '<' before type arguments")?;
+        let mut args = vec![self.parse_type()?];
+        while matches!(self.current_token, Token::Comma) {
+            self.advance()?;
+            args.push(self.parse_type()?);
+        }
+        
+        // `>>` closes two argument lists at once, as in `List<List<int>>`
+        if matches!(self.current_token, Token::RightShift) {
+            self.current_token = Token::Greater;
+            self.current_span.start = self.current_span.start.advanced('>');
+            return Ok(args);
+        }
+        self.consume(Token::Greater, "Expected '>' after type arguments")?;
+        Ok(args)
+    }
+    
+    /// Build the type for `name<args>`, mapping built-in collections to their
+    /// own variants
+    fn generic_type(&self, name: String, mut args: Vec<Type>) -> Result<Type, ParseError> {
+        let arity = match name.as_str() {
+            "List" | "Set" => 1,
+            "Map" | "Result" => 2,
+            _ => return Ok(Type::Generic(name, args)),
+        };
+        if args.len() != arity {
+            return Err(ParseError {
+                span: Span::single(self.previous_end),
+                kind: ParseErrorKind::InvalidSyntax {
+                    message: format!("'{}' takes {} type argument(s), found {}", name, arity, args.len()),
+                },
+            });
+        }
+        
+        let first = Box::new(args.remove(0));
+        Ok(match name.as_str() {
+            "List" => Type::List(first),
+            "Set" => Type::Set(first),
+            "Map" => Type::Map(first, Box::new(args.remove(0))),
+            _ => Type::Result(first, Box::new(args.remove(0))),
+        })
+    }
+    
+    /// Parse an optional type parameter list such as `<T, U>` after the name
+    /// of a function, struct or class
+    fn parse_type_params(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut params = Vec::new();
+        if !matches!(self.current_token, Token::Less) {
+            return Ok(params);
+        }
+        self.advance()?; // consume '<'
+        
+        loop {
+            let param = self.parse_identifier("type parameter name")?;
+            if params.contains(&param) {
+                return Err(ParseError {
+                    span: self.current_span,
+                    kind: ParseErrorKind::InvalidSyntax {
+                        message: format!("Duplicate type parameter '{}'", param),
+                    },
+                });
+            }
+            params.push(param);
+            if matches!(self.current_token, Token::Comma) {
+                self.advance()?;
+            } else {
+                break;
+            }
+        }
+        
+        self.consume(Token::Greater, "Expected '>' after type parameters")?;
+        Ok(params)
+    }
+    
     fn parse_function_impl(&mut self) -> Result<Function, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
         // Check for visibility modifier
         let visibility = if matches!(self.current_token, Token::Pub) {
             self.advance()?; // consume 'pub'
@@ -1028,13 +1954,14 @@ impl FluxParser {
             name
         } else {
             return Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "function name".to_string(),
                     found: format!("{}", self.current_token),
                 },
             });
         };
+        let type_params = self.parse_type_params()?;
         
         self.consume(Token::LeftParen, "Expected '(' after function name")?;
         
@@ -1065,15 +1992,20 @@ impl FluxParser {
         
         Ok(Function {
             name,
+            type_params,
             parameters,
             return_type,
             body,
             is_async,
             visibility,
+            doc,
+            span: self.span_from(start),
         })
     }
 
     fn parse_parameter(&mut self) -> Result<Parameter, ParseError> {
+        let start = self.start();
+        
         // Check for mutability modifier
         let is_mutable = if matches!(self.current_token, Token::Mut) {
             self.advance()?; // consume 'mut'
@@ -1093,10 +2025,11 @@ impl FluxParser {
                 name,
                 type_,
                 is_mutable,
+                span: self.span_from(start),
             })
         } else {
             Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "parameter name".to_string(),
                     found: format!("{}", self.current_token),
@@ -1106,6 +2039,9 @@ impl FluxParser {
     }
     
     fn parse_struct_impl(&mut self) -> Result<Struct, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
         // Check for visibility modifier
         let visibility = if matches!(self.current_token, Token::Pub) {
             self.advance()?; // consume 'pub'
@@ -1122,13 +2058,14 @@ impl FluxParser {
             name
         } else {
             return Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "struct name".to_string(),
                     found: format!("{}", self.current_token),
                 },
             });
         };
+        let type_params = self.parse_type_params()?;
         
         self.consume(Token::LeftBrace, "Expected '{' after struct name")?;
         
@@ -1147,12 +2084,18 @@ impl FluxParser {
         
         Ok(Struct {
             name,
+            type_params,
             fields,
             visibility,
+            doc,
+            span: self.span_from(start),
         })
     }
 
     fn parse_field(&mut self) -> Result<Field, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
         // Check for visibility modifier
         let visibility = if matches!(self.current_token, Token::Pub) {
             self.advance()?; // consume 'pub'
@@ -1181,10 +2124,12 @@ impl FluxParser {
                 type_,
                 visibility,
                 is_mutable,
+                doc,
+                span: self.span_from(start),
             })
         } else {
             Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "field name".to_string(),
                     found: format!("{}", self.current_token),
@@ -1193,7 +2138,388 @@ impl FluxParser {
         }
     }
     
+    fn parse_class_impl(&mut self) -> Result<Class, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
+        // Check for visibility modifier
+        let visibility = if matches!(self.current_token, Token::Pub) {
+            self.advance()?; // consume 'pub'
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+
+        self.consume(Token::Class, "Expected 'class'")?;
+        
+        let name = if let Token::Identifier(name) = &self.current_token {
+            let name = name.clone();
+            self.advance()?;
+            name
+        } else {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "class name".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        };
+        let type_params = self.parse_type_params()?;
+        
+        self.consume(Token::LeftBrace, "Expected '{' after class name")?;
+        
+        // Fields and methods may appear in any order
+        let mut fields = Vec::new();
+        let mut methods = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
+            if self.at_method_start() {
+                methods.push(self.parse_method()?);
+            } else {
+                fields.push(self.parse_field()?);
+                
+                // Optional separator after field
+                if matches!(self.current_token, Token::Comma | Token::Semicolon) {
+                    self.advance()?;
+                }
+            }
+        }
+        
+        self.consume(Token::RightBrace, "Expected '}' to end class")?;
+        
+        Ok(Class {
+            name,
+            type_params,
+            fields,
+            methods,
+            visibility,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+    
+    /// Whether the current tokens begin a method: `[pub] [static] func`
+    fn at_method_start(&self) -> bool {
+        let is_static = |token: &Token| matches!(token, Token::Identifier(word) if word == "static");
+        match &self.current_token {
+            Token::Func => true,
+            Token::Pub => matches!(self.peek_token, Token::Func) || is_static(&self.peek_token),
+            token => is_static(token) && matches!(self.peek_token, Token::Func),
+        }
+    }
+    
+    fn parse_method(&mut self) -> Result<Method, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
+        // Check for visibility modifier
+        let visibility = if matches!(self.current_token, Token::Pub) {
+            self.advance()?; // consume 'pub'
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+        
+        // `static` is contextual: static methods take no receiver
+        let is_static = if matches!(&self.current_token, Token::Identifier(word) if word == "static") {
+            self.advance()?; // consume 'static'
+            true
+        } else {
+            false
+        };
+        
+        self.consume(Token::Func, "Expected 'func'")?;
+        
+        let name = if let Token::Identifier(name) = &self.current_token {
+            let name = name.clone();
+            self.advance()?;
+            name
+        } else {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "method name".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        };
+        
+        let (parameters, return_type) = self.parse_method_signature_tail()?;
+        let body = self.parse_block()?;
+        
+        Ok(Method {
+            name,
+            parameters,
+            return_type,
+            body,
+            visibility,
+            is_static,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+    
+    /// Parse `(params) [-> type]` after a method name
+    fn parse_method_signature_tail(&mut self) -> Result<(Vec<Parameter>, Option<Type>), ParseError> {
+        self.consume(Token::LeftParen, "Expected '(' after method name")?;
+        
+        // Parse parameter list; instance methods receive `self` implicitly
+        let mut parameters = Vec::new();
+        if !matches!(self.current_token, Token::RightParen) {
+            loop {
+                let parameter = self.parse_parameter()?;
+                if parameter.name == "self" {
+                    return Err(ParseError {
+                        span: parameter.span,
+                        kind: ParseErrorKind::InvalidSyntax {
+                            message: "'self' is implicit in instance methods and cannot be declared".to_string(),
+                        },
+                    });
+                }
+                parameters.push(parameter);
+                if matches!(self.current_token, Token::Comma) {
+                    self.advance()?; // consume ','
+                } else {
+                    break;
+                }
+            }
+        }
+        
+        self.consume(Token::RightParen, "Expected ')' after parameters")?;
+        
+        // Optional return type
+        let return_type = if matches!(self.current_token, Token::Arrow) {
+            self.advance()?; // consume '->'
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        
+        Ok((parameters, return_type))
+    }
+    
+    fn parse_interface_impl(&mut self) -> Result<Interface, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
+        // Check for visibility modifier
+        let visibility = if matches!(self.current_token, Token::Pub) {
+            self.advance()?; // consume 'pub'
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+
+        self.consume(Token::Interface, "Expected 'interface'")?;
+        let name = self.parse_identifier("interface name")?;
+        self.consume(Token::LeftBrace, "Expected '{' after interface name")?;
+        
+        let mut methods: Vec<MethodSignature> = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
+            let method_doc = self.take_doc();
+            let method_start = self.start();
+            self.consume(Token::Func, "Expected 'func' in interface")?;
+            let method_name = self.parse_identifier("method name")?;
+            let (parameters, return_type) = self.parse_method_signature_tail()?;
+            
+            if methods.iter().any(|existing| existing.name == method_name) {
+                return Err(ParseError {
+                    span: self.span_from(method_start),
+                    kind: ParseErrorKind::InvalidSyntax {
+                        message: format!("Duplicate method '{}' in interface '{}'", method_name, name),
+                    },
+                });
+            }
+            methods.push(MethodSignature {
+                name: method_name,
+                parameters,
+                return_type,
+                doc: method_doc,
+                span: self.span_from(method_start),
+            });
+            
+            // Optional separator after signature
+            if matches!(self.current_token, Token::Semicolon | Token::Comma) {
+                self.advance()?;
+            }
+        }
+        
+        self.consume(Token::RightBrace, "Expected '}' to end interface")?;
+        
+        Ok(Interface {
+            name,
+            methods,
+            visibility,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+    
+    /// Parse `impl Interface for Type { methods }`
+    fn parse_impl_block(&mut self) -> Result<Impl, ParseError> {
+        let start = self.start();
+        
+        self.consume(Token::Impl, "Expected 'impl'")?;
+        let interface = self.parse_identifier("interface name")?;
+        self.consume(Token::For, "Expected 'for' after interface name")?;
+        let type_name = self.parse_identifier("type name")?;
+        self.consume(Token::LeftBrace, "Expected '{' after implemented type")?;
+        
+        let mut methods = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
+            methods.push(self.parse_method()?);
+        }
+        
+        self.consume(Token::RightBrace, "Expected '}' to end impl block")?;
+        
+        Ok(Impl {
+            interface,
+            type_name,
+            methods,
+            span: self.span_from(start),
+        })
+    }
+    
+    /// Consume an identifier, describing what was expected if it is missing
+    fn parse_identifier(&mut self, expected: &str) -> Result<String, ParseError> {
+        if let Token::Identifier(name) = &self.current_token {
+            let name = name.clone();
+            self.advance()?;
+            Ok(name)
+        } else {
+            Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: expected.to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            })
+        }
+    }
+    
+    fn parse_enum_impl(&mut self) -> Result<Enum, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
+        // Check for visibility modifier
+        let visibility = if matches!(self.current_token, Token::Pub) {
+            self.advance()?; // consume 'pub'
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+
+        self.consume(Token::Enum, "Expected 'enum'")?;
+        
+        let name = if let Token::Identifier(name) = &self.current_token {
+            let name = name.clone();
+            self.advance()?;
+            name
+        } else {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "enum name".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        };
+        
+        self.consume(Token::LeftBrace, "Expected '{' after enum name")?;
+        
+        let mut variants: Vec<Variant> = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
+            let variant = self.parse_variant()?;
+            if variants.iter().any(|existing| existing.name == variant.name) {
+                return Err(ParseError {
+                    span: variant.span,
+                    kind: ParseErrorKind::InvalidSyntax {
+                        message: format!("Duplicate variant '{}' in enum '{}'", variant.name, name),
+                    },
+                });
+            }
+            variants.push(variant);
+            
+            // Optional comma after variant
+            if matches!(self.current_token, Token::Comma) {
+                self.advance()?;
+            }
+        }
+        
+        self.consume(Token::RightBrace, "Expected '}' to end enum")?;
+        
+        Ok(Enum {
+            name,
+            variants,
+            visibility,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+    
+    fn parse_variant(&mut self) -> Result<Variant, ParseError> {
+        let doc = self.take_doc();
+        let start = self.start();
+        
+        let name = if let Token::Identifier(name) = &self.current_token {
+            let name = name.clone();
+            self.advance()?;
+            name
+        } else {
+            return Err(ParseError {
+                span: self.current_span,
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected: "variant name".to_string(),
+                    found: format!("{}", self.current_token),
+                },
+            });
+        };
+        
+        let payload = match &self.current_token {
+            Token::LeftParen => {
+                self.advance()?; // consume '('
+                let mut types = Vec::new();
+                if !matches!(self.current_token, Token::RightParen) {
+                    loop {
+                        types.push(self.parse_type()?);
+                        if matches!(self.current_token, Token::Comma) {
+                            self.advance()?; // consume ','
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.consume(Token::RightParen, "Expected ')' after variant types")?;
+                VariantPayload::Tuple(types)
+            }
+            Token::LeftBrace => {
+                self.advance()?; // consume '{'
+                let mut fields = Vec::new();
+                while !matches!(self.current_token, Token::RightBrace) && !self.is_at_end() {
+                    fields.push(self.parse_field()?);
+                    
+                    // Optional comma after field
+                    if matches!(self.current_token, Token::Comma) {
+                        self.advance()?;
+                    }
+                }
+                self.consume(Token::RightBrace, "Expected '}' after variant fields")?;
+                VariantPayload::Struct(fields)
+            }
+            _ => VariantPayload::Unit,
+        };
+        
+        Ok(Variant {
+            name,
+            payload,
+            doc,
+            span: self.span_from(start),
+        })
+    }
+    
     fn parse_extern_function_impl(&mut self) -> Result<ExternFunction, ParseError> {
+        let start = self.start();
+        
         // Check for visibility modifier
         let visibility = if matches!(self.current_token, Token::Pub) {
             self.advance()?; // consume 'pub'
@@ -1221,7 +2547,7 @@ impl FluxParser {
             name
         } else {
             return Err(ParseError {
-                span: Span::single(self.lexer.position()),
+                span: self.current_span,
                 kind: ParseErrorKind::UnexpectedToken {
                     expected: "function name".to_string(),
                     found: format!("{}", self.current_token),
@@ -1276,6 +2602,7 @@ impl FluxParser {
             library,
             is_variadic,
             visibility,
+            span: self.span_from(start),
         })
     }
     
@@ -1286,23 +2613,28 @@ impl FluxParser {
 
 impl Parser for FluxParser {
     fn parse_program(&mut self) -> Result<Program, ParseError> {
-        self.parse_program_impl()
+        let program = self.parse_program_impl();
+        self.first_error(Ok(program))
     }
     
     fn parse_expression(&mut self) -> Result<Expression, ParseError> {
-        self.parse_expression_impl()
+        let expression = self.parse_expression_impl();
+        self.first_error(expression)
     }
     
     fn parse_statement(&mut self) -> Result<Statement, ParseError> {
-        self.parse_statement_impl()
+        let statement = self.parse_statement_impl();
+        self.first_error(statement)
     }
     
     fn parse_function(&mut self) -> Result<Function, ParseError> {
-        self.parse_function_impl()
+        let function = self.parse_function_impl();
+        self.first_error(function)
     }
     
     fn parse_struct(&mut self) -> Result<Struct, ParseError> {
-        self.parse_struct_impl()
+        let struct_ = self.parse_struct_impl();
+        self.first_error(struct_)
     }
     
     fn is_at_end(&self) -> bool {
@@ -1325,205 +2657,219 @@ mod expression_tests {
     fn test_literal_expressions() {
         // Integer literal
         let expr = parse_expression_from_source("42").unwrap();
-        assert_eq!(expr, Expression::Literal(Literal::Integer(42)));
+        assert_eq!(expr, Expression::from(ExpressionKind::Literal(Literal::Integer(42))));
 
         // Float literal
         let expr = parse_expression_from_source("3.14").unwrap();
-        assert_eq!(expr, Expression::Literal(Literal::Float(3.14)));
+        assert_eq!(expr, Expression::from(ExpressionKind::Literal(Literal::Float(3.14))));
 
         // String literal
         let expr = parse_expression_from_source("\"hello\"").unwrap();
-        assert_eq!(expr, Expression::Literal(Literal::String("hello".to_string())));
+        assert_eq!(expr, Expression::from(ExpressionKind::Literal(Literal::String("hello".to_string()))));
 
         // Boolean literal
         let expr = parse_expression_from_source("true").unwrap();
-        assert_eq!(expr, Expression::Literal(Literal::Boolean(true)));
+        assert_eq!(expr, Expression::from(ExpressionKind::Literal(Literal::Boolean(true))));
 
         // Character literal
         let expr = parse_expression_from_source("'a'").unwrap();
-        assert_eq!(expr, Expression::Literal(Literal::Character('a')));
+        assert_eq!(expr, Expression::from(ExpressionKind::Literal(Literal::Character('a'))));
     }
 
     #[test]
     fn test_identifier_expressions() {
         let expr = parse_expression_from_source("variable").unwrap();
-        assert_eq!(expr, Expression::Identifier("variable".to_string()));
+        assert_eq!(expr, Expression::from(ExpressionKind::Identifier("variable".to_string())));
     }
 
     #[test]
     fn test_binary_expressions() {
         // Addition
         let expr = parse_expression_from_source("1 + 2").unwrap();
-        assert_eq!(expr, Expression::Binary(
-            Box::new(Expression::Literal(Literal::Integer(1))),
+        assert_eq!(expr, Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(1)))),
             BinaryOp::Add,
-            Box::new(Expression::Literal(Literal::Integer(2)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(2))))
+        )));
 
         // Multiplication with higher precedence
         let expr = parse_expression_from_source("1 + 2 * 3").unwrap();
-        assert_eq!(expr, Expression::Binary(
-            Box::new(Expression::Literal(Literal::Integer(1))),
+        assert_eq!(expr, Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(1)))),
             BinaryOp::Add,
-            Box::new(Expression::Binary(
-                Box::new(Expression::Literal(Literal::Integer(2))),
+            Box::new(Expression::from(ExpressionKind::Binary(
+                Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(2)))),
                 BinaryOp::Multiply,
-                Box::new(Expression::Literal(Literal::Integer(3)))
-            ))
-        ));
+                Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(3))))
+            )))
+        )));
 
         // Comparison
         let expr = parse_expression_from_source("x == 5").unwrap();
-        assert_eq!(expr, Expression::Binary(
-            Box::new(Expression::Identifier("x".to_string())),
+        assert_eq!(expr, Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Identifier("x".to_string()))),
             BinaryOp::Equal,
-            Box::new(Expression::Literal(Literal::Integer(5)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(5))))
+        )));
 
         // Logical AND
         let expr = parse_expression_from_source("true && false").unwrap();
-        assert_eq!(expr, Expression::Binary(
-            Box::new(Expression::Literal(Literal::Boolean(true))),
+        assert_eq!(expr, Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Boolean(true)))),
             BinaryOp::And,
-            Box::new(Expression::Literal(Literal::Boolean(false)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Boolean(false))))
+        )));
     }
 
     #[test]
     fn test_unary_expressions() {
         // Negation
         let expr = parse_expression_from_source("-42").unwrap();
-        assert_eq!(expr, Expression::Unary(
+        assert_eq!(expr, Expression::from(ExpressionKind::Unary(
             UnaryOp::Minus,
-            Box::new(Expression::Literal(Literal::Integer(42)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(42))))
+        )));
 
         // Logical NOT
         let expr = parse_expression_from_source("!true").unwrap();
-        assert_eq!(expr, Expression::Unary(
+        assert_eq!(expr, Expression::from(ExpressionKind::Unary(
             UnaryOp::Not,
-            Box::new(Expression::Literal(Literal::Boolean(true)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Boolean(true))))
+        )));
 
         // Bitwise NOT
         let expr = parse_expression_from_source("~42").unwrap();
-        assert_eq!(expr, Expression::Unary(
+        assert_eq!(expr, Expression::from(ExpressionKind::Unary(
             UnaryOp::BitwiseNot,
-            Box::new(Expression::Literal(Literal::Integer(42)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(42))))
+        )));
     }
 
     #[test]
     fn test_function_call_expressions() {
         // Simple function call
         let expr = parse_expression_from_source("print()").unwrap();
-        assert_eq!(expr, Expression::Call(
-            Box::new(Expression::Identifier("print".to_string())),
+        assert_eq!(expr, Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Identifier("print".to_string()))),
             vec![]
-        ));
+        )));
 
         // Function call with arguments
         let expr = parse_expression_from_source("add(1, 2)").unwrap();
-        assert_eq!(expr, Expression::Call(
-            Box::new(Expression::Identifier("add".to_string())),
+        assert_eq!(expr, Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Identifier("add".to_string()))),
             vec![
-                Expression::Literal(Literal::Integer(1)),
-                Expression::Literal(Literal::Integer(2))
+                Expression::from(ExpressionKind::Literal(Literal::Integer(1))),
+                Expression::from(ExpressionKind::Literal(Literal::Integer(2)))
             ]
-        ));
+        )));
 
         // Chained function calls
         let expr = parse_expression_from_source("obj.method()").unwrap();
-        assert_eq!(expr, Expression::Call(
-            Box::new(Expression::Field(
-                Box::new(Expression::Identifier("obj".to_string())),
+        assert_eq!(expr, Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Field(
+                Box::new(Expression::from(ExpressionKind::Identifier("obj".to_string()))),
                 "method".to_string()
-            )),
+            ))),
             vec![]
-        ));
+        )));
+    }
+
+    #[test]
+    fn test_qualified_path_expressions() {
+        let expr = parse_expression_from_source("math::sqrt(2.0)").unwrap();
+        assert_eq!(expr, Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Path(vec!["math".to_string(), "sqrt".to_string()]))),
+            vec![Expression::from(ExpressionKind::Literal(Literal::Float(2.0)))]
+        )));
+
+        let expr = parse_expression_from_source("geometry::PI").unwrap();
+        assert_eq!(expr, Expression::from(ExpressionKind::Path(vec!["geometry".to_string(), "PI".to_string()])));
+
+        assert!(parse_expression_from_source("math::").is_err());
     }
 
     #[test]
     fn test_field_access_expressions() {
         let expr = parse_expression_from_source("obj.field").unwrap();
-        assert_eq!(expr, Expression::Field(
-            Box::new(Expression::Identifier("obj".to_string())),
+        assert_eq!(expr, Expression::from(ExpressionKind::Field(
+            Box::new(Expression::from(ExpressionKind::Identifier("obj".to_string()))),
             "field".to_string()
-        ));
+        )));
 
         // Chained field access
         let expr = parse_expression_from_source("obj.field.subfield").unwrap();
-        assert_eq!(expr, Expression::Field(
-            Box::new(Expression::Field(
-                Box::new(Expression::Identifier("obj".to_string())),
+        assert_eq!(expr, Expression::from(ExpressionKind::Field(
+            Box::new(Expression::from(ExpressionKind::Field(
+                Box::new(Expression::from(ExpressionKind::Identifier("obj".to_string()))),
                 "field".to_string()
-            )),
+            ))),
             "subfield".to_string()
-        ));
+        )));
     }
 
     #[test]
     fn test_index_expressions() {
         // Array indexing
         let expr = parse_expression_from_source("arr[0]").unwrap();
-        assert_eq!(expr, Expression::Index(
-            Box::new(Expression::Identifier("arr".to_string())),
-            Box::new(Expression::Literal(Literal::Integer(0)))
-        ));
+        assert_eq!(expr, Expression::from(ExpressionKind::Index(
+            Box::new(Expression::from(ExpressionKind::Identifier("arr".to_string()))),
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(0))))
+        )));
 
         // Map indexing
         let expr = parse_expression_from_source("map[\"key\"]").unwrap();
-        assert_eq!(expr, Expression::Index(
-            Box::new(Expression::Identifier("map".to_string())),
-            Box::new(Expression::Literal(Literal::String("key".to_string())))
-        ));
+        assert_eq!(expr, Expression::from(ExpressionKind::Index(
+            Box::new(Expression::from(ExpressionKind::Identifier("map".to_string()))),
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::String("key".to_string()))))
+        )));
 
         // Chained indexing
         let expr = parse_expression_from_source("matrix[i][j]").unwrap();
-        assert_eq!(expr, Expression::Index(
-            Box::new(Expression::Index(
-                Box::new(Expression::Identifier("matrix".to_string())),
-                Box::new(Expression::Identifier("i".to_string()))
-            )),
-            Box::new(Expression::Identifier("j".to_string()))
-        ));
+        assert_eq!(expr, Expression::from(ExpressionKind::Index(
+            Box::new(Expression::from(ExpressionKind::Index(
+                Box::new(Expression::from(ExpressionKind::Identifier("matrix".to_string()))),
+                Box::new(Expression::from(ExpressionKind::Identifier("i".to_string())))
+            ))),
+            Box::new(Expression::from(ExpressionKind::Identifier("j".to_string())))
+        )));
     }
 
     #[test]
     fn test_array_literal_expressions() {
         // Empty array
         let expr = parse_expression_from_source("[]").unwrap();
-        assert_eq!(expr, Expression::Array(vec![]));
+        assert_eq!(expr, Expression::from(ExpressionKind::Array(vec![])));
 
         // Array with elements
         let expr = parse_expression_from_source("[1, 2, 3]").unwrap();
-        assert_eq!(expr, Expression::Array(vec![
-            Expression::Literal(Literal::Integer(1)),
-            Expression::Literal(Literal::Integer(2)),
-            Expression::Literal(Literal::Integer(3))
-        ]));
+        assert_eq!(expr, Expression::from(ExpressionKind::Array(vec![
+            Expression::from(ExpressionKind::Literal(Literal::Integer(1))),
+            Expression::from(ExpressionKind::Literal(Literal::Integer(2))),
+            Expression::from(ExpressionKind::Literal(Literal::Integer(3)))
+        ])));
     }
 
     #[test]
     fn test_parenthesized_expressions() {
         let expr = parse_expression_from_source("(1 + 2)").unwrap();
-        assert_eq!(expr, Expression::Binary(
-            Box::new(Expression::Literal(Literal::Integer(1))),
+        assert_eq!(expr, Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(1)))),
             BinaryOp::Add,
-            Box::new(Expression::Literal(Literal::Integer(2)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(2))))
+        )));
 
         // Parentheses changing precedence
         let expr = parse_expression_from_source("(1 + 2) * 3").unwrap();
-        assert_eq!(expr, Expression::Binary(
-            Box::new(Expression::Binary(
-                Box::new(Expression::Literal(Literal::Integer(1))),
+        assert_eq!(expr, Expression::from(ExpressionKind::Binary(
+            Box::new(Expression::from(ExpressionKind::Binary(
+                Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(1)))),
                 BinaryOp::Add,
-                Box::new(Expression::Literal(Literal::Integer(2)))
-            )),
+                Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(2))))
+            ))),
             BinaryOp::Multiply,
-            Box::new(Expression::Literal(Literal::Integer(3)))
-        ));
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Integer(3))))
+        )));
     }
 
     #[test]
@@ -1533,8 +2879,8 @@ mod expression_tests {
         
         // This should parse as: ((a + (b * c)) == d) && e) || f
         // Due to operator precedence: *, +, ==, &&, ||
-        match expr {
-            Expression::Binary(_, BinaryOp::Or, _) => {
+        match expr.kind {
+            ExpressionKind::Binary(_, BinaryOp::Or, _) => {
                 // Top level should be OR
             }
             _ => panic!("Expected OR at top level"),
@@ -1545,11 +2891,11 @@ mod expression_tests {
     fn test_operator_precedence() {
         // Test that multiplication has higher precedence than addition
         let expr = parse_expression_from_source("2 + 3 * 4").unwrap();
-        match expr {
-            Expression::Binary(left, BinaryOp::Add, right) => {
-                assert_eq!(*left, Expression::Literal(Literal::Integer(2)));
-                match *right {
-                    Expression::Binary(_, BinaryOp::Multiply, _) => {
+        match expr.kind {
+            ExpressionKind::Binary(left, BinaryOp::Add, right) => {
+                assert_eq!(*left, Expression::from(ExpressionKind::Literal(Literal::Integer(2))));
+                match right.kind {
+                    ExpressionKind::Binary(_, BinaryOp::Multiply, _) => {
                         // Correct: 3 * 4 is grouped together
                     }
                     _ => panic!("Expected multiplication to have higher precedence"),
@@ -1560,15 +2906,15 @@ mod expression_tests {
 
         // Test that comparison has lower precedence than arithmetic
         let expr = parse_expression_from_source("1 + 2 == 3").unwrap();
-        match expr {
-            Expression::Binary(left, BinaryOp::Equal, right) => {
-                match *left {
-                    Expression::Binary(_, BinaryOp::Add, _) => {
+        match expr.kind {
+            ExpressionKind::Binary(left, BinaryOp::Equal, right) => {
+                match left.kind {
+                    ExpressionKind::Binary(_, BinaryOp::Add, _) => {
                         // Correct: 1 + 2 is grouped together
                     }
                     _ => panic!("Expected addition to have higher precedence"),
                 }
-                assert_eq!(*right, Expression::Literal(Literal::Integer(3)));
+                assert_eq!(*right, Expression::from(ExpressionKind::Literal(Literal::Integer(3))));
             }
             _ => panic!("Expected equality at top level"),
         }
@@ -1578,20 +2924,186 @@ mod expression_tests {
     fn test_associativity() {
         // Test left associativity for same precedence operators
         let expr = parse_expression_from_source("1 - 2 - 3").unwrap();
-        match expr {
-            Expression::Binary(left, BinaryOp::Subtract, right) => {
-                match *left {
-                    Expression::Binary(_, BinaryOp::Subtract, _) => {
+        match expr.kind {
+            ExpressionKind::Binary(left, BinaryOp::Subtract, right) => {
+                match left.kind {
+                    ExpressionKind::Binary(_, BinaryOp::Subtract, _) => {
                         // Correct: (1 - 2) - 3
                     }
                     _ => panic!("Expected left associativity"),
                 }
-                assert_eq!(*right, Expression::Literal(Literal::Integer(3)));
+                assert_eq!(*right, Expression::from(ExpressionKind::Literal(Literal::Integer(3))));
             }
             _ => panic!("Expected subtraction at top level"),
         }
     }
 
+    #[test]
+    fn test_lambda_expressions() {
+        let expr = parse_expression_from_source("|x: int, mut y: int| x + y").unwrap();
+        match &expr.kind {
+            ExpressionKind::Lambda(params, None, body) => {
+                assert_eq!(params.len(), 2);
+                assert_eq!(params[0].type_, Some(Type::Int));
+                assert!(params[1].is_mutable);
+                assert!(matches!(body.kind, ExpressionKind::Binary(_, BinaryOp::Add, _)));
+            }
+            other => panic!("Expected lambda, got {:?}", other),
+        }
+        
+        // `||` opens a lambda without parameters, and a return type requires a block
+        let expr = parse_expression_from_source("|| -> int { return 1; }").unwrap();
+        match &expr.kind {
+            ExpressionKind::Lambda(params, Some(Type::Int), body) => {
+                assert!(params.is_empty());
+                assert!(matches!(body.kind, ExpressionKind::Block(_)));
+            }
+            other => panic!("Expected lambda, got {:?}", other),
+        }
+        assert!(parse_expression_from_source("|x: int| -> int x").is_err());
+        
+        // Lambdas are values that can be called immediately
+        let expr = parse_expression_from_source("(|x: int| x * 2)(3)").unwrap();
+        match &expr.kind {
+            ExpressionKind::Call(callee, args) => {
+                assert!(matches!(callee.kind, ExpressionKind::Lambda(..)));
+                assert_eq!(args.len(), 1);
+            }
+            other => panic!("Expected call, got {:?}", other),
+        }
+        assert_eq!(
+            parse_expression_from_source("|a: int| |b: int| a + b").unwrap().to_string(),
+            "|a: int| |b: int| (a + b)"
+        );
+    }
+
+    #[test]
+    fn test_unannotated_lambdas_and_map_literals() {
+        let expr = parse_expression_from_source("|x, y: int| x").unwrap();
+        match &expr.kind {
+            ExpressionKind::Lambda(params, None, _) => {
+                assert_eq!(params[0].type_, None);
+                assert_eq!(params[1].type_, Some(Type::Int));
+            }
+            other => panic!("Expected lambda, got {:?}", other),
+        }
+        assert_eq!(expr.to_string(), "|x, y: int| x");
+        
+        // `{}` and `{key: value}` are map literals, other braces open a block
+        let expr = parse_expression_from_source("{}").unwrap();
+        assert!(matches!(&expr.kind, ExpressionKind::Map(pairs) if pairs.is_empty()));
+        let expr = parse_expression_from_source("{\"a\": 1, \"b\": 2}").unwrap();
+        assert!(matches!(&expr.kind, ExpressionKind::Map(pairs) if pairs.len() == 2));
+        let expr = parse_expression_from_source("{ 1 }").unwrap();
+        assert!(matches!(expr.kind, ExpressionKind::Block(_)));
+    }
+
+    #[test]
+    fn test_try_operator() {
+        let expr = parse_expression_from_source("parse(s)?.value").unwrap();
+        match &expr.kind {
+            ExpressionKind::Field(object, _) => assert!(matches!(object.kind, ExpressionKind::Unary(UnaryOp::Try, _))),
+            other => panic!("Expected field access, got {:?}", other),
+        }
+        assert_eq!(expr.to_string(), "parse(s)?.value");
+    }
+
+    #[test]
+    fn test_null_safety_operators() {
+        // `??` binds looser than arithmetic and tighter than comparison, to the right
+        let expr = parse_expression_from_source("a ?? b ?? c + 1 > 0").unwrap();
+        assert_eq!(expr.to_string(), "((a ?? (b ?? (c + 1))) > 0)");
+        
+        let expr = parse_expression_from_source("user?.address?.city ?? null").unwrap();
+        assert_eq!(expr.to_string(), "(user?.address?.city ?? null)");
+        
+        // Postfix `!` unwraps only when written against its operand
+        let expr = parse_expression_from_source("find(1)!.name").unwrap();
+        match &expr.kind {
+            ExpressionKind::Field(object, _) => assert!(matches!(object.kind, ExpressionKind::Unary(UnaryOp::Unwrap, _))),
+            other => panic!("Expected field access, got {:?}", other),
+        }
+        assert_eq!(parse_expression_from_source("!x!").unwrap().to_string(), "!x!");
+        assert_eq!(parse_expression_from_source("x !y").unwrap().to_string(), "x");
+    }
+
+    #[test]
+    fn test_interpolated_strings() {
+        let expr = parse_expression_from_source(r#""Hello, {name}! You have {count + 1} items""#).unwrap();
+        match &expr.kind {
+            ExpressionKind::Interpolated(parts) => {
+                assert_eq!(parts.len(), 5);
+                assert_eq!(parts[0], StringPart::Text("Hello, ".to_string()));
+                assert!(matches!(&parts[3], StringPart::Expression(Expression { kind: ExpressionKind::Binary(..), .. })));
+                assert_eq!(parts[4], StringPart::Text(" items".to_string()));
+            }
+            other => panic!("Expected interpolated string, got {:?}", other),
+        }
+        assert_eq!(expr.span.end.column, 44);
+        
+        // Holes take any expression, including struct literals and strings;
+        // escaped braces print back escaped
+        let source = r#""{Point { x: 1 }.x}{"{a}"} \{b}""#;
+        assert_eq!(parse_expression_from_source(source).unwrap().to_string(), source);
+        
+        assert!(parse_expression_from_source(r#""{a b}""#).is_err());
+        
+        // Empty braces hold no expression and stay in the text
+        let expr = parse_expression_from_source(r#""{}""#).unwrap();
+        assert!(matches!(&expr.kind, ExpressionKind::Literal(Literal::String(text)) if text == "{}"));
+        let expr = parse_expression_from_source(r#""{} is {x}""#).unwrap();
+        let ExpressionKind::Interpolated(parts) = &expr.kind else { panic!("expected interpolated string, got {:?}", expr.kind) };
+        assert_eq!(parts[0], StringPart::Text("{} is ".to_string()));
+    }
+    
+    #[test]
+    fn test_nested_expression_spans() {
+        let source = "f(a.b[i + 1], -(x * 2))";
+        let expr = parse_expression_from_source(source).unwrap();
+        let text = |expr: &Expression| &source[expr.span.start.offset..expr.span.end.offset];
+        assert_eq!(text(&expr), source);
+        
+        let ExpressionKind::Call(callee, args) = &expr.kind else { panic!("expected call, got {:?}", expr.kind) };
+        assert_eq!(text(callee), "f");
+        assert_eq!(text(&args[0]), "a.b[i + 1]");
+        assert_eq!(text(&args[1]), "-(x * 2)");
+        
+        let ExpressionKind::Index(object, index) = &args[0].kind else { panic!("expected index") };
+        assert_eq!(text(object), "a.b");
+        assert_eq!(text(index), "i + 1");
+        let ExpressionKind::Field(base, _) = &object.kind else { panic!("expected field access") };
+        assert_eq!(text(base), "a");
+        let ExpressionKind::Binary(left, _, right) = &index.kind else { panic!("expected binary") };
+        assert_eq!((text(left), text(right)), ("i", "1"));
+        assert_eq!(right.span.start.column, 11);
+        
+        let ExpressionKind::Unary(_, operand) = &args[1].kind else { panic!("expected unary") };
+        let ExpressionKind::Binary(left, _, right) = &operand.kind else { panic!("expected binary") };
+        assert_eq!((text(left), text(right)), ("x", "2"));
+    }
+    
+    #[test]
+    fn test_range_expressions() {
+        // Arithmetic binds tighter than `..`, comparison looser
+        let expr = parse_expression_from_source("0..n + 1").unwrap();
+        assert_eq!(expr.to_string(), "0..(n + 1)");
+        assert_eq!(parse_expression_from_source("i < 0..2").unwrap().to_string(), "(i < 0..2)");
+
+        let expr = parse_expression_from_source("10..=0 step -2").unwrap();
+        match &expr.kind {
+            ExpressionKind::Range(start, end, Some(step), true) => {
+                assert_eq!(start.to_string(), "10");
+                assert_eq!(end.to_string(), "0");
+                assert_eq!(step.to_string(), "-2");
+            }
+            other => panic!("Expected inclusive range, got {:?}", other),
+        }
+        assert_eq!(expr.span.end.column, 15);
+
+        // Ranges don't chain
+        assert_eq!(parse_expression_from_source("a..b..c").unwrap().to_string(), "a..b");
+    }
+
     #[test]
     fn test_error_cases() {
         // Invalid expression
@@ -1631,83 +3143,118 @@ mod statement_tests {
     fn test_let_statements() {
         // Simple let with initialization
         let stmt = parse_statement_from_source("let x = 42").unwrap();
-        assert_eq!(stmt, Statement::Let(
-            "x".to_string(),
+        assert_eq!(stmt, Statement::from(StatementKind::Let(
+            Pattern::Identifier("x".to_string()),
             None,
-            Some(Expression::Literal(Literal::Integer(42)))
-        ));
+            Some(Expression::from(ExpressionKind::Literal(Literal::Integer(42))))
+        )));
 
         // Let with type annotation
         let stmt = parse_statement_from_source("let x: int = 42").unwrap();
-        assert_eq!(stmt, Statement::Let(
-            "x".to_string(),
+        assert_eq!(stmt, Statement::from(StatementKind::Let(
+            Pattern::Identifier("x".to_string()),
             Some(Type::Int),
-            Some(Expression::Literal(Literal::Integer(42)))
-        ));
+            Some(Expression::from(ExpressionKind::Literal(Literal::Integer(42))))
+        )));
 
         // Let without initialization
         let stmt = parse_statement_from_source("let x: int").unwrap();
-        assert_eq!(stmt, Statement::Let(
-            "x".to_string(),
+        assert_eq!(stmt, Statement::from(StatementKind::Let(
+            Pattern::Identifier("x".to_string()),
             Some(Type::Int),
             None
-        ));
+        )));
     }
 
     #[test]
     fn test_const_statements() {
         let stmt = parse_statement_from_source("const PI: float = 3.14").unwrap();
-        assert_eq!(stmt, Statement::Const(
+        assert_eq!(stmt, Statement::from(StatementKind::Const(
             "PI".to_string(),
             Type::Float,
-            Expression::Literal(Literal::Float(3.14))
-        ));
+            Expression::from(ExpressionKind::Literal(Literal::Float(3.14)))
+        )));
     }
 
     #[test]
     fn test_return_statements() {
         // Return with value
         let stmt = parse_statement_from_source("return 42").unwrap();
-        assert_eq!(stmt, Statement::Return(Some(Expression::Literal(Literal::Integer(42)))));
+        assert_eq!(stmt, Statement::from(StatementKind::Return(Some(Expression::from(ExpressionKind::Literal(Literal::Integer(42)))))));
 
         // Return without value
         let stmt = parse_statement_from_source("return").unwrap();
-        assert_eq!(stmt, Statement::Return(None));
+        assert_eq!(stmt, Statement::from(StatementKind::Return(None)));
+
+        // A value on the next line is a statement of its own
+        let stmt = parse_statement_from_source("return\n g()").unwrap();
+        assert_eq!(stmt, Statement::from(StatementKind::Return(None)));
+        let function = parse_function_from_source("func f() {\n return\n let x = 1\n}").unwrap();
+        assert_eq!(function.body.statements.len(), 2);
+        assert!(matches!(function.body.statements[0].kind, StatementKind::Return(None)));
+        assert!(matches!(function.body.statements[1].kind, StatementKind::Let(..)));
     }
 
     #[test]
     fn test_assignment_statements() {
         let stmt = parse_statement_from_source("x = 42").unwrap();
-        assert_eq!(stmt, Statement::Assignment(
-            Expression::Identifier("x".to_string()),
-            Expression::Literal(Literal::Integer(42))
-        ));
+        assert_eq!(stmt, Statement::from(StatementKind::Assignment(
+            Expression::from(ExpressionKind::Identifier("x".to_string())),
+            Expression::from(ExpressionKind::Literal(Literal::Integer(42)))
+        )));
+        
+        // Field and index targets
+        let stmt = parse_statement_from_source("p.x = 1").unwrap();
+        assert!(matches!(&stmt.kind, StatementKind::Assignment(target, _) if matches!(target.kind, ExpressionKind::Field(..))));
+        let stmt = parse_statement_from_source("a[0] = 1").unwrap();
+        assert!(matches!(&stmt.kind, StatementKind::Assignment(target, _) if matches!(target.kind, ExpressionKind::Index(..))));
+    }
+
+    #[test]
+    fn test_result_patterns() {
+        let stmt = parse_statement_from_source("match r { Ok(v) => { v } Err(_) => { 0 } }").unwrap();
+        match &stmt.kind {
+            StatementKind::Match(_, arms) => {
+                assert!(matches!(&arms[0].pattern, Pattern::Result(ResultPattern::Ok(inner)) if **inner == Pattern::Identifier("v".to_string())));
+                assert!(matches!(&arms[1].pattern, Pattern::Result(ResultPattern::Err(inner)) if **inner == Pattern::Wildcard));
+            }
+            other => panic!("Expected match, got {:?}", other),
+        }
     }
 
     #[test]
     fn test_control_flow_statements() {
         // Break statement
         let stmt = parse_statement_from_source("break").unwrap();
-        assert_eq!(stmt, Statement::Break(None));
+        assert_eq!(stmt, Statement::from(StatementKind::Break(None)));
+        let stmt = parse_statement_from_source("while true { break\n let y = 2 }").unwrap();
+        match &stmt.kind {
+            StatementKind::While(_, body) => {
+                assert_eq!(body.statements.len(), 2);
+                assert!(matches!(body.statements[0].kind, StatementKind::Break(None)));
+                assert!(matches!(body.statements[1].kind, StatementKind::Let(..)));
+            }
+            other => panic!("Expected while, got {:?}", other),
+        }
 
         // Continue statement
         let stmt = parse_statement_from_source("continue").unwrap();
-        assert_eq!(stmt, Statement::Continue);
+        assert_eq!(stmt, Statement::from(StatementKind::Continue));
 
         // Go statement
         let stmt = parse_statement_from_source("go print()").unwrap();
-        assert_eq!(stmt, Statement::Go(Expression::Call(
-            Box::new(Expression::Identifier("print".to_string())),
+        assert_eq!(stmt, Statement::from(StatementKind::Go(Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Identifier("print".to_string()))),
             vec![]
-        )));
+        )))));
     }
 
     #[test]
     fn test_if_statements() {
         let stmt = parse_statement_from_source("if x > 0 { return x }").unwrap();
-        match stmt {
-            Statement::If(condition, then_block, else_block) => {
-                assert!(matches!(condition, Expression::Binary(_, BinaryOp::Greater, _)));
+        match stmt.kind {
+            StatementKind::If(condition, then_block, else_block) => {
+                assert!(matches!(condition.kind, ExpressionKind::Binary(_, BinaryOp::Greater, _)));
                 assert_eq!(then_block.statements.len(), 1);
                 assert!(else_block.is_none());
             }
@@ -1716,8 +3263,8 @@ mod statement_tests {
 
         // If-else statement
         let stmt = parse_statement_from_source("if x > 0 { return x } else { return 0 }").unwrap();
-        match stmt {
-            Statement::If(_, _, else_block) => {
+        match stmt.kind {
+            StatementKind::If(_, _, else_block) => {
                 assert!(else_block.is_some());
             }
             _ => panic!("Expected if statement"),
@@ -1727,9 +3274,9 @@ mod statement_tests {
     #[test]
     fn test_while_statements() {
         let stmt = parse_statement_from_source("while x > 0 { x = x - 1 }").unwrap();
-        match stmt {
-            Statement::While(condition, body) => {
-                assert!(matches!(condition, Expression::Binary(_, BinaryOp::Greater, _)));
+        match stmt.kind {
+            StatementKind::While(condition, body) => {
+                assert!(matches!(condition.kind, ExpressionKind::Binary(_, BinaryOp::Greater, _)));
                 assert_eq!(body.statements.len(), 1);
             }
             _ => panic!("Expected while statement"),
@@ -1739,23 +3286,54 @@ mod statement_tests {
     #[test]
     fn test_for_statements() {
         let stmt = parse_statement_from_source("for i in range { print(i) }").unwrap();
-        match stmt {
-            Statement::For(var, iterable, body) => {
-                assert_eq!(var, "i");
-                assert_eq!(iterable, Expression::Identifier("range".to_string()));
+        match stmt.kind {
+            StatementKind::For(var, iterable, body) => {
+                assert_eq!(var, Pattern::Identifier("i".to_string()));
+                assert_eq!(iterable, Expression::from(ExpressionKind::Identifier("range".to_string())));
                 assert_eq!(body.statements.len(), 1);
             }
             _ => panic!("Expected for statement"),
         }
     }
 
+    #[test]
+    fn test_tuples_and_destructuring() {
+        let identifier = |name: &str| Pattern::Identifier(name.to_string());
+        
+        let stmt = parse_statement_from_source("let (a, b): (int, string) = (1, \"one\")").unwrap();
+        match stmt.kind {
+            StatementKind::Let(pattern, type_, Some(value)) => {
+                assert_eq!(pattern, Pattern::Tuple(vec![identifier("a"), identifier("b")]));
+                assert_eq!(type_, Some(Type::Tuple(vec![Type::Int, Type::String])));
+                assert_eq!(value.to_string(), "(1, \"one\")");
+            }
+            other => panic!("Expected let statement, found {:?}", other),
+        }
+        
+        let stmt = parse_statement_from_source("let Point { x, y: (p, _) } = q").unwrap();
+        assert_eq!(stmt.to_string(), "let Point { x, y: (p, _) } = q");
+        
+        let stmt = parse_statement_from_source("for (key, value) in scores { print(key) }").unwrap();
+        assert!(matches!(stmt.kind, StatementKind::For(Pattern::Tuple(_), _, _)));
+        
+        let stmt = parse_statement_from_source("for i in 0..n step k { print(i) }").unwrap();
+        assert!(matches!(stmt.kind, StatementKind::For(_, Expression { kind: ExpressionKind::Range(_, _, Some(_), false), .. }, _)));
+        
+        // Parentheses around one expression do not make a tuple
+        let stmt = parse_statement_from_source("let n = (1 + 2)").unwrap();
+        assert!(matches!(stmt.kind, StatementKind::Let(_, _, Some(Expression { kind: ExpressionKind::Binary(..), .. }))));
+        
+        // A destructuring `let` needs a value
+        assert!(parse_statement_from_source("let (a, b): (int, int)").is_err());
+    }
+
     #[test]
     fn test_expression_statements() {
         let stmt = parse_statement_from_source("print(42)").unwrap();
-        assert_eq!(stmt, Statement::Expression(Expression::Call(
-            Box::new(Expression::Identifier("print".to_string())),
-            vec![Expression::Literal(Literal::Integer(42))]
-        )));
+        assert_eq!(stmt, Statement::from(StatementKind::Expression(Expression::from(ExpressionKind::Call(
+            Box::new(Expression::from(ExpressionKind::Identifier("print".to_string()))),
+            vec![Expression::from(ExpressionKind::Literal(Literal::Integer(42)))]
+        )))));
     }
 
     #[test]
@@ -1813,37 +3391,40 @@ mod statement_tests {
     fn test_type_parsing() {
         // Test various type parsing through let statements
         let stmt = parse_statement_from_source("let arr: [int]").unwrap();
-        match stmt {
-            Statement::Let(_, Some(Type::Array(element_type)), _) => {
+        match stmt.kind {
+            StatementKind::Let(_, Some(Type::Array(element_type)), _) => {
                 assert_eq!(*element_type, Type::Int);
             }
             _ => panic!("Expected let statement with array type"),
         }
 
         let stmt = parse_statement_from_source("let name: string").unwrap();
-        match stmt {
-            Statement::Let(_, Some(Type::String), _) => {}
+        match stmt.kind {
+            StatementKind::Let(_, Some(Type::String), _) => {}
             _ => panic!("Expected let statement with string type"),
         }
 
         let stmt = parse_statement_from_source("let custom: MyType").unwrap();
-        match stmt {
-            Statement::Let(_, Some(Type::Named(name)), _) => {
+        match stmt.kind {
+            StatementKind::Let(_, Some(Type::Named(name)), _) => {
                 assert_eq!(name, "MyType");
             }
             _ => panic!("Expected let statement with named type"),
         }
+
+        let stmt = parse_statement_from_source("let evens: Range").unwrap();
+        assert!(matches!(stmt.kind, StatementKind::Let(_, Some(Type::Range), _)));
     }
 
     #[test]
     fn test_complex_statements() {
         // Nested if statements
         let stmt = parse_statement_from_source("if x > 0 { if y > 0 { return x + y } }").unwrap();
-        match stmt {
-            Statement::If(_, then_block, _) => {
+        match stmt.kind {
+            StatementKind::If(_, then_block, _) => {
                 assert_eq!(then_block.statements.len(), 1);
-                match &then_block.statements[0] {
-                    Statement::If(_, _, _) => {} // Nested if
+                match &then_block.statements[0].kind {
+                    StatementKind::If(_, _, _) => {} // Nested if
                     _ => panic!("Expected nested if statement"),
                 }
             }
@@ -2039,8 +3620,99 @@ mod error_recovery_tests {
         
         let result = parse_program_from_source(source);
         assert!(result.is_err());
-        // In a more sophisticated implementation, we would collect all errors
-        // and still parse the valid function
+    }
+
+    fn parse_with_errors(source: &str) -> (Program, Vec<ParseError>) {
+        let lexer = FluxLexer::new(source.to_string());
+        let mut parser = FluxParser::new(lexer).unwrap();
+        parser.parse_program_with_errors()
+    }
+
+    fn function_names(program: &Program) -> Vec<&str> {
+        program.items.iter().filter_map(|item| match item {
+            Item::Function(func) => Some(func.name.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_recovery_at_item_boundaries() {
+        let source = r#"
+            func invalid1(
+            struct invalid2 {
+            func valid() { let x = 42 }
+            const invalid3
+        "#;
+        let (program, errors) = parse_with_errors(source);
+        assert_eq!(function_names(&program), vec!["valid"]);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors.iter().map(|e| e.span.start.line).collect::<Vec<_>>(), vec![3, 4, 6]);
+
+        let source = r#"
+            func valid_func() {
+                let x = 42
+            }
+            
+            func invalid_func(
+            
+            func another_valid_func() {
+                let y = 24
+            }
+        "#;
+        let (program, errors) = parse_with_errors(source);
+        assert_eq!(function_names(&program), vec!["valid_func", "another_valid_func"]);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_recovery_at_statement_boundaries() {
+        let source = r#"
+            func main() {
+                let a = ;
+                let b = 2
+                if b > { return }
+                while true { let c = (1 + }
+                return b
+            }
+            func after() { }
+        "#;
+        let (program, errors) = parse_with_errors(source);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert_eq!(function_names(&program), vec!["main", "after"]);
+
+        let main = match &program.items[0] {
+            Item::Function(func) => func,
+            other => panic!("expected function, got {:?}", other),
+        };
+        assert_eq!(main.body.statements.len(), 3);
+        assert!(matches!(main.body.statements[0].kind, StatementKind::Let(..)));
+        assert!(matches!(main.body.statements[1].kind, StatementKind::While(..)));
+        assert!(matches!(main.body.statements[2].kind, StatementKind::Return(..)));
+    }
+
+    #[test]
+    fn test_unclosed_block_recovery() {
+        let (program, errors) = parse_with_errors("func broken() { let x = 1\nfunc fine() { }");
+        assert_eq!(function_names(&program), vec!["fine"]);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0].kind, ParseErrorKind::UnexpectedToken { expected, .. } if expected.contains("'}'")));
+
+        // The first recovered error is still what `parse_program` reports
+        let error = parse_program_from_source("func a() { let = 1 }\nfunc b(").unwrap_err();
+        assert_eq!(error.span.start.line, 1);
+    }
+
+    #[test]
+    fn test_lexical_error_ends_parsing() {
+        // Errors before the lexical error are kept; the missing '}' after it is not reported
+        let (_, errors) = parse_with_errors("func a() { let = 1 }\nfunc main() {\n    let v = 1 $ 2\n    return v\n}");
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert_eq!(errors[0].span.start.line, 1);
+        assert!(errors[1].to_string().contains("Lexical error"), "{}", errors[1]);
+        assert_eq!((errors[1].span.start.line, errors[1].span.start.column), (3, 15));
+
+        let error = parse_program_from_source("func main() {\n    let v = 1 $ 2\n}").unwrap_err();
+        assert!(error.to_string().contains("Lexical error"), "{}", error);
     }
 
     #[test]
@@ -2052,4 +3724,377 @@ mod error_recovery_tests {
         // The error should have position information
         assert!(error.span.start.line > 0 || error.span.start.column > 0);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod program_tests {
+    use super::*;
+    use crate::lexer::FluxLexer;
+
+    fn parse_program_from_source(source: &str) -> Result<Program, ParseError> {
+        let lexer = FluxLexer::new(source.to_string());
+        let mut parser = FluxParser::new(lexer)?;
+        parser.parse_program()
+    }
+
+    #[test]
+    fn test_package_declaration() {
+        let program = parse_program_from_source("package geometry\n\nfunc area() { }").unwrap();
+        assert_eq!(program.package, "geometry");
+        assert_eq!(program.items.len(), 1);
+
+        // Package defaults to main
+        let program = parse_program_from_source("func main() { }").unwrap();
+        assert_eq!(program.package, "main");
+    }
+
+    #[test]
+    fn test_import_declarations() {
+        let source = r#"
+            package main;
+
+            import "std/io";
+            import "math" as m
+
+            func main() {
+                println("Hello, World!");
+            }
+        "#;
+        let program = parse_program_from_source(source).unwrap();
+        assert_eq!(program.imports, vec![
+            Import { path: "std/io".to_string(), alias: None, span: Span::single(Position::start()) },
+            Import { path: "math".to_string(), alias: Some("m".to_string()), span: Span::single(Position::start()) },
+        ]);
+        assert_eq!(program.imports[0].binding_name(), "io");
+        assert_eq!(program.imports[1].binding_name(), "m");
+        assert_eq!(program.items.len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_imports() {
+        // Same path twice
+        let error = parse_program_from_source("import \"math\"\nimport \"math\"").unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::InvalidSyntax { ref message } if message.contains("Duplicate import")));
+
+        // Different paths bound to the same name
+        let error = parse_program_from_source("import \"std/math\"\nimport \"math\"").unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::InvalidSyntax { ref message } if message.contains("already in use")));
+
+        // An alias resolves the clash
+        assert!(parse_program_from_source("import \"std/math\"\nimport \"math\" as m2").is_ok());
+    }
+
+    #[test]
+    fn test_malformed_imports() {
+        // Missing path
+        let error = parse_program_from_source("import").unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::UnexpectedToken { .. }));
+
+        // Unquoted path
+        let error = parse_program_from_source("import math").unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::UnexpectedToken { .. }));
+
+        // Missing alias
+        let error = parse_program_from_source("import \"math\" as").unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::UnexpectedToken { .. }));
+
+        // Empty path segment
+        let error = parse_program_from_source("import \"std//io\"").unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::InvalidSyntax { .. }));
+    }
+
+    #[test]
+    fn test_class_declarations() {
+        let source = r#"
+            pub class Point {
+                pub x: float,
+                mut y: float
+
+                static func origin() -> Point { }
+                pub func norm(scale: float) -> float {
+                    return self.x * scale;
+                }
+            }
+        "#;
+        let program = parse_program_from_source(source).unwrap();
+        let class = match &program.items[0] {
+            Item::Class(class) => class,
+            other => panic!("expected class, got {:?}", other),
+        };
+        assert_eq!(class.name, "Point");
+        assert_eq!(class.visibility, Visibility::Public);
+        assert_eq!(class.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), vec!["x", "y"]);
+        assert!(class.fields[1].is_mutable);
+
+        assert_eq!(class.methods.len(), 2);
+        assert!(class.methods[0].is_static);
+        assert_eq!(class.methods[0].return_type, Some(Type::Named("Point".to_string())));
+        assert!(!class.methods[1].is_static);
+        assert_eq!(class.methods[1].visibility, Visibility::Public);
+        assert_eq!(class.methods[1].parameters.len(), 1);
+
+        // The receiver is implicit
+        assert!(parse_program_from_source("class A { func f(self: A) { } }").is_err());
+        // Missing closing brace
+        assert!(parse_program_from_source("class A { x: int").is_err());
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let source = r#"
+            /// A point
+            /// on the plane
+            pub struct Point {
+                /// Horizontal
+                pub x: float,
+                y: float,
+            }
+
+            /** A counter */
+            class Counter {
+                /// Current count
+                count: int
+
+                /// Next count
+                func next() -> int {
+                    /// Not attached to anything
+                    return self.count + 1;
+                }
+            }
+
+            /// Limit
+            pub const MAX: int = 10
+
+            /// Entry point
+            pub func main() { }
+            func plain() { }
+        "#;
+        let program = parse_program_from_source(source).unwrap();
+        let Item::Struct(point) = &program.items[0] else { panic!("expected struct") };
+        assert_eq!(point.doc.as_deref(), Some("A point\non the plane"));
+        assert_eq!(point.fields[0].doc.as_deref(), Some("Horizontal"));
+        assert_eq!(point.fields[1].doc, None);
+        
+        let Item::Class(counter) = &program.items[1] else { panic!("expected class") };
+        assert_eq!(counter.doc.as_deref(), Some("A counter"));
+        assert_eq!(counter.fields[0].doc.as_deref(), Some("Current count"));
+        assert_eq!(counter.methods[0].doc.as_deref(), Some("Next count"));
+        
+        let Item::Const(max) = &program.items[2] else { panic!("expected const") };
+        assert_eq!(max.doc.as_deref(), Some("Limit"));
+        assert_eq!(max.visibility, Visibility::Public);
+        
+        let Item::Function(main) = &program.items[3] else { panic!("expected function") };
+        assert_eq!(main.doc.as_deref(), Some("Entry point"));
+        let Item::Function(plain) = &program.items[4] else { panic!("expected function") };
+        assert_eq!(plain.doc, None);
+    }
+
+    #[test]
+    fn test_enum_declarations_and_patterns() {
+        let source = r#"
+            pub enum Shape {
+                Circle(float),
+                Rect { w: float, h: float },
+                Empty,
+            }
+
+            func area(s: Shape) -> float {
+                let r = Shape::Rect { w: 1.0, h };
+                match s {
+                    Shape::Circle(r) => { return r; }
+                    Shape::Rect { w, h: height } => { return w * height; }
+                    Shape::Empty => { return 0.0; }
+                }
+                if ready { }
+            }
+        "#;
+        let program = parse_program_from_source(source).unwrap();
+        let shape = match &program.items[0] {
+            Item::Enum(shape) => shape,
+            other => panic!("expected enum, got {:?}", other),
+        };
+        assert_eq!(shape.visibility, Visibility::Public);
+        assert_eq!(shape.variants.len(), 3);
+        assert_eq!(shape.variants[0].payload, VariantPayload::Tuple(vec![Type::Float]));
+        assert!(matches!(&shape.variants[1].payload, VariantPayload::Struct(fields) if fields.len() == 2));
+        assert_eq!(shape.variants[2].payload, VariantPayload::Unit);
+
+        let body = match &program.items[1] {
+            Item::Function(func) => &func.body,
+            other => panic!("expected function, got {:?}", other),
+        };
+        match &body.statements[0].kind {
+            StatementKind::Let(_, _, Some(value)) => match &value.kind {
+                ExpressionKind::StructLiteral(name, fields) => {
+                    assert_eq!(name, "Shape::Rect");
+                    // `h` is shorthand for `h: h`
+                    assert_eq!(fields[1].1, Expression::from(ExpressionKind::Identifier("h".to_string())));
+                }
+                other => panic!("expected struct literal, got {:?}", other),
+            },
+            other => panic!("expected let, got {:?}", other),
+        }
+
+        let arms = match &body.statements[1].kind {
+            StatementKind::Match(_, arms) => arms,
+            other => panic!("expected match, got {:?}", other),
+        };
+        let path = vec!["Shape".to_string(), "Circle".to_string()];
+        assert_eq!(arms[0].pattern, Pattern::Variant(path, vec![Pattern::Identifier("r".to_string())]));
+        assert_eq!(arms[1].pattern, Pattern::Struct("Shape::Rect".to_string(), vec![
+            ("w".to_string(), Pattern::Identifier("w".to_string())),
+            ("h".to_string(), Pattern::Identifier("height".to_string())),
+        ]));
+        assert_eq!(arms[2].pattern, Pattern::Variant(vec!["Shape".to_string(), "Empty".to_string()], vec![]));
+
+        // A block after a condition is not a struct literal
+        assert!(matches!(&body.statements[2].kind, StatementKind::If(cond, _, _)
+            if cond.kind == ExpressionKind::Identifier("ready".to_string())));
+
+        // Duplicate variants are rejected
+        assert!(parse_program_from_source("enum E { A, A }").is_err());
+    }
+
+    #[test]
+    fn test_interface_and_impl_blocks() {
+        let source = r#"
+            pub interface Shape {
+                func area() -> float;
+                func scale(factor: float)
+            }
+
+            impl Shape for Square {
+                func area() -> float { return self.side * self.side; }
+                func scale(factor: float) { }
+            }
+        "#;
+        let program = parse_program_from_source(source).unwrap();
+        let interface = match &program.items[0] {
+            Item::Interface(interface) => interface,
+            other => panic!("expected interface, got {:?}", other),
+        };
+        assert_eq!(interface.visibility, Visibility::Public);
+        assert_eq!(interface.methods.len(), 2);
+        assert_eq!(interface.methods[0].return_type, Some(Type::Float));
+        assert_eq!(interface.methods[1].parameters[0].name, "factor");
+        assert_eq!(interface.methods[1].return_type, None);
+
+        let impl_block = match &program.items[1] {
+            Item::Impl(impl_block) => impl_block,
+            other => panic!("expected impl block, got {:?}", other),
+        };
+        assert_eq!((impl_block.interface.as_str(), impl_block.type_name.as_str()), ("Shape", "Square"));
+        assert_eq!(impl_block.methods.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["area", "scale"]);
+
+        // Interface methods have no body, and `for` is required
+        assert!(parse_program_from_source("interface A { func f() { } }").is_err());
+        assert!(parse_program_from_source("impl A B { }").is_err());
+        assert!(parse_program_from_source("interface A { func f(); func f(); }").is_err());
+    }
+
+    #[test]
+    fn test_generic_declarations_and_types() {
+        let source = r#"
+            func map<T, U>(xs: List<T>, f: (T) -> U) -> List<U> { return xs; }
+            struct Pair<A, B> { first: A, second: B }
+            class Stack<T> { items: [T] }
+            func nested(m: Map<string, List<List<int>>>, p: Pair<int, string>?, done: () -> ()) { }
+        "#;
+        let program = parse_program_from_source(source).unwrap();
+        let map = match &program.items[0] {
+            Item::Function(func) => func,
+            other => panic!("expected function, got {:?}", other),
+        };
+        assert_eq!(map.type_params, vec!["T", "U"]);
+        assert_eq!(map.parameters[0].type_, Type::List(Box::new(Type::Named("T".to_string()))));
+        assert_eq!(
+            map.parameters[1].type_,
+            Type::Function(vec![Type::Named("T".to_string())], Box::new(Type::Named("U".to_string())))
+        );
+        assert_eq!(map.return_type, Some(Type::List(Box::new(Type::Named("U".to_string())))));
+        assert!(matches!(&program.items[1], Item::Struct(s) if s.type_params == vec!["A", "B"]));
+        assert!(matches!(&program.items[2], Item::Class(c) if c.type_params == vec!["T"]));
+
+        // `>>` closes two argument lists
+        let nested = match &program.items[3] {
+            Item::Function(func) => func,
+            other => panic!("expected function, got {:?}", other),
+        };
+        let types: Vec<String> = nested.parameters.iter().map(|p| p.type_.to_string()).collect();
+        assert_eq!(types, vec!["Map<string, List<List<int>>>", "Pair<int, string>?", "() -> ()"]);
+
+        assert!(parse_program_from_source("func f<T, T>(x: T) { }").is_err());
+        assert!(parse_program_from_source("func f(xs: List<int, int>) { }").is_err());
+        assert!(parse_program_from_source("func f(g: (int)) { }").is_err());
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let source = "func main() {\n    let total = a + b * 2;\n    return total;\n}";
+        let program = parse_program_from_source(source).unwrap();
+        let func = match &program.items[0] {
+            Item::Function(func) => func,
+            other => panic!("expected function, got {:?}", other),
+        };
+        assert_eq!((func.span.start.line, func.span.start.column), (1, 1));
+        assert_eq!((func.span.end.line, func.span.end.column), (4, 2));
+
+        let let_stmt = &func.body.statements[0];
+        assert_eq!((let_stmt.span.start.line, let_stmt.span.start.column), (2, 5));
+        let value = match &let_stmt.kind {
+            StatementKind::Let(_, _, Some(value)) => value,
+            other => panic!("expected let with value, got {:?}", other),
+        };
+        // `a + b * 2` covers columns 17..26
+        assert_eq!(value.span.start.column, 17);
+        assert_eq!(value.span.end.column, 26);
+        assert_eq!(&source[value.span.start.offset..value.span.end.offset], "a + b * 2");
+        if let ExpressionKind::Binary(_, _, right) = &value.kind {
+            assert_eq!(&source[right.span.start.offset..right.span.end.offset], "b * 2");
+        } else {
+            panic!("expected binary expression");
+        }
+
+        let return_stmt = &func.body.statements[1];
+        assert_eq!(return_stmt.span.start.line, 3);
+    }
+
+    #[test]
+    fn test_line_breaks_end_statements() {
+        let body = |source: &str| match parse_program_from_source(source).unwrap().items.remove(0) {
+            Item::Function(func) => func.body.statements,
+            other => panic!("expected function, got {:?}", other),
+        };
+
+        // `-`, `(` and `[` on a new line start the next statement
+        let statements = body("func main() {\n    let b = a\n    -2\n    f\n    (1)\n    g\n    [1]\n}");
+        assert_eq!(statements.len(), 6);
+        assert!(matches!(&statements[0].kind, StatementKind::Let(_, _, Some(value)) if matches!(value.kind, ExpressionKind::Identifier(_))));
+        assert!(matches!(&statements[1].kind, StatementKind::Expression(e) if matches!(e.kind, ExpressionKind::Unary(UnaryOp::Minus, _))));
+
+        // Inside parentheses and brackets the expression goes on
+        let statements = body("func main() {\n    let b = (a\n        - 2)\n    let c = [f\n        (1)]\n    h(a\n        + b)\n}");
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(&statements[0].kind, StatementKind::Let(_, _, Some(value)) if matches!(value.kind, ExpressionKind::Binary(..))));
+
+        // ...but not in a block nested inside them
+        let statements = body("func main() {\n    run(|| {\n        a\n        -2\n    })\n}");
+        match &statements[0].kind {
+            StatementKind::Expression(Expression { kind: ExpressionKind::Call(_, args), .. }) => match &args[0].kind {
+                ExpressionKind::Lambda(_, _, body) => assert!(matches!(&body.kind, ExpressionKind::Block(block) if block.statements.len() == 2)),
+                other => panic!("expected lambda, got {:?}", other),
+            },
+            other => panic!("expected call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_misplaced_declarations() {
+        // Imports after items
+        assert!(parse_program_from_source("func main() { }\nimport \"math\"").is_err());
+
+        // Second package declaration
+        assert!(parse_program_from_source("package a\npackage b").is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Interactive read-eval-print loop
+//!
+//! Each input is parsed as items or statements, analyzed against everything
+//! entered before it, and run by the interpreter. Expressions print their
+//! value and type; input with unbalanced braces continues on the next line.
+
+use crate::error::{FluxError, FluxResult};
+use crate::lexer::{FluxLexer, Token};
+use crate::parser::ast::{Item, Program, Statement, StatementKind, Type};
+use crate::parser::{FluxParser, Parser};
+use crate::runtime::{Interpreter, Value};
+use crate::semantic::{FluxSemanticAnalyzer, SemanticAnalyzer, TypedProgram, TypedStatementKind};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// File name runtime errors in typed input are reported against
+const INPUT_FILE: &str = "<repl>";
+
+const HELP: &str = "\
+Enter statements, expressions or declarations. Commands:
+  :type <expr>   show the type of an expression without evaluating it
+  :ast <input>   show the syntax tree of an input
+  :load <file>   load the declarations of a source file
+  :help          show this message
+  :quit          leave the REPL";
+
+/// Interactive session with persistent definitions
+pub struct Repl {
+    analyzer: FluxSemanticAnalyzer,
+    interpreter: Interpreter,
+    /// Lines of an input whose braces are not balanced yet
+    pending: String,
+}
+
+impl Repl {
+    /// Create a session with nothing defined
+    pub fn new() -> Self {
+        let mut interpreter = Interpreter::new();
+        interpreter.capture_output();
+        Self {
+            analyzer: FluxSemanticAnalyzer::new(),
+            interpreter,
+            pending: String::new(),
+        }
+    }
+
+    /// Prompt to show before reading the next line
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() { "flux> " } else { "  ... " }
+    }
+
+    /// Feed one line of input. Returns `None` while the input continues on
+    /// the next line, and the outcome of evaluating it once it is complete.
+    pub fn feed_line(&mut self, line: &str) -> Option<FluxResult<String>> {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        if !is_complete(&self.pending) {
+            return None;
+        }
+
+        let input = std::mem::take(&mut self.pending);
+        Some(self.eval(input.trim()))
+    }
+
+    /// Output printed by the program since the last call
+    pub fn take_output(&mut self) -> String {
+        self.interpreter.take_output()
+    }
+
+    /// Evaluate a complete input: a `:` command, declarations or statements
+    pub fn eval(&mut self, input: &str) -> FluxResult<String> {
+        if let Some(command) = input.strip_prefix(':') {
+            let (name, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+            let arg = arg.trim();
+            return match name {
+                "type" | "t" => self.type_of(arg),
+                "ast" => self.ast(arg),
+                "load" | "l" => self.load(Path::new(arg)),
+                "help" | "h" => Ok(HELP.to_string()),
+                _ => Err(FluxError::Cli(format!("Unknown command ':{}', try :help", name))),
+            };
+        }
+
+        if input.is_empty() {
+            Ok(String::new())
+        } else if starts_with_item(input) {
+            self.define(input)
+        } else {
+            self.run_statements(input)
+        }
+    }
+
+    /// Read lines from `input` until it ends or `:quit` is entered
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "{}", self.prompt())?;
+        output.flush()?;
+
+        for line in input.lines() {
+            let line = line?;
+            if self.pending.is_empty() && matches!(line.trim(), ":quit" | ":q") {
+                break;
+            }
+
+            let result = self.feed_line(&line);
+            write!(output, "{}", self.take_output())?;
+            match result {
+                Some(Ok(text)) if !text.is_empty() => writeln!(output, "{}", text)?,
+                Some(Err(error)) => match error.report() {
+                    Some(report) => write!(output, "{}", report)?,
+                    None => writeln!(output, "error: {}", error)?,
+                },
+                _ => {}
+            }
+
+            write!(output, "{}", self.prompt())?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+
+    fn run_statements(&mut self, input: &str) -> FluxResult<String> {
+        let statements = parser(input)?.parse_statements()?;
+        let typed = self.analyzer.analyze_statements(&statements)?;
+
+        self.interpreter.add_source_file(PathBuf::from(INPUT_FILE), input.to_string());
+        let value = self.interpreter.execute(&typed, Some(Path::new(INPUT_FILE)))?;
+
+        Ok(match typed.last().map(|stmt| &stmt.kind) {
+            Some(TypedStatementKind::Expression(expr)) if expr.type_ != Type::Unit => {
+                format!("{}: {}", show(&value), expr.type_)
+            }
+            Some(TypedStatementKind::Let(name, type_, _)) | Some(TypedStatementKind::Const(name, type_, _)) => {
+                format!("{}: {}", name, type_)
+            }
+            _ => String::new(),
+        })
+    }
+
+    fn define(&mut self, input: &str) -> FluxResult<String> {
+        let program = parser(input)?.parse_program()?;
+        let typed = self.analyze(program)?;
+
+        self.interpreter.add_source_file(PathBuf::from(INPUT_FILE), input.to_string());
+        self.interpreter.extend(&typed, Some(Path::new(INPUT_FILE)))?;
+        Ok(String::new())
+    }
+
+    fn load(&mut self, path: &Path) -> FluxResult<String> {
+        if path.as_os_str().is_empty() {
+            return Err(FluxError::Cli("Usage: :load <file>".to_string()));
+        }
+        let source = fs::read_to_string(path)
+            .map_err(|e| FluxError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+        let program = parser(&source)?.parse_program()?;
+        let names: Vec<String> = program.items.iter().map(item_name).collect();
+        let typed = self.analyze(program)?;
+
+        self.interpreter.add_source_file(path.to_path_buf(), source);
+        self.interpreter.extend(&typed, Some(path))?;
+        Ok(format!("Loaded {}: {}", path.display(), names.join(", ")))
+    }
+
+    fn analyze(&mut self, program: Program) -> FluxResult<TypedProgram> {
+        if !program.imports.is_empty() {
+            return Err(FluxError::Cli("Imports are not supported in the REPL".to_string()));
+        }
+        Ok(self.analyzer.analyze(program)?)
+    }
+
+    fn type_of(&mut self, input: &str) -> FluxResult<String> {
+        let mut parser = parser(input)?;
+        let expr = parser.parse_expression()?;
+        if !parser.is_at_end() {
+            return Err(FluxError::Cli(format!("Expected a single expression: {}", input)));
+        }
+
+        let span = expr.span;
+        let typed = self.analyzer.analyze_statements(&[Statement::new(StatementKind::Expression(expr), span)])?;
+        match typed.first().map(|stmt| &stmt.kind) {
+            Some(TypedStatementKind::Expression(expr)) => Ok(expr.type_.to_string()),
+            _ => Ok(Type::Unit.to_string()),
+        }
+    }
+
+    fn ast(&self, input: &str) -> FluxResult<String> {
+        if starts_with_item(input) {
+            Ok(format!("{:#?}", parser(input)?.parse_program()?.items))
+        } else {
+            Ok(format!("{:#?}", parser(input)?.parse_statements()?))
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parser(input: &str) -> FluxResult<FluxParser> {
+    Ok(FluxParser::new(FluxLexer::new(input.to_string()))?)
+}
+
+/// Whether every `{` in `input` has been closed. Input that does not lex is
+/// complete, so the parser can report the problem.
+fn is_complete(input: &str) -> bool {
+    let mut lexer = FluxLexer::new(input.to_string());
+    let mut depth = 0i32;
+    loop {
+        match lexer.next_token() {
+            Ok(Token::LeftBrace) => depth += 1,
+            Ok(Token::RightBrace) => depth -= 1,
+            Ok(Token::Eof) | Err(_) => return depth <= 0,
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Whether `input` starts with a declaration rather than a statement
+fn starts_with_item(input: &str) -> bool {
+    let mut lexer = FluxLexer::new(input.to_string());
+    loop {
+        match lexer.next_token() {
+            Ok(Token::Newline | Token::DocComment(_)) => continue,
+            Ok(token) => return matches!(token,
+                Token::Func | Token::Struct | Token::Class | Token::Enum | Token::Interface
+                | Token::Impl | Token::Extern | Token::Pub | Token::Package | Token::Import),
+            Err(_) => return false,
+        }
+    }
+}
+
+fn item_name(item: &Item) -> String {
+    match item {
+        Item::Function(func) => func.name.clone(),
+        Item::Struct(struct_def) => struct_def.name.clone(),
+        Item::Class(class_def) => class_def.name.clone(),
+        Item::Enum(enum_def) => enum_def.name.clone(),
+        Item::Interface(interface) => interface.name.clone(),
+        Item::Impl(impl_block) => format!("impl {} for {}", impl_block.interface, impl_block.type_name),
+        Item::Const(const_def) => const_def.name.clone(),
+        Item::ExternFunction(extern_func) => extern_func.name.clone(),
+    }
+}
+
+/// Display a value the way it would be written in source
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("{:?}", text),
+        Value::Char(c) => format!("{:?}", c),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(repl: &mut Repl, input: &str) -> String {
+        repl.eval(input).unwrap_or_else(|e| panic!("{}: {}", input, e))
+    }
+
+    #[test]
+    fn test_bindings_persist_between_inputs() {
+        let mut repl = Repl::new();
+        assert_eq!(eval(&mut repl, "let x = 40"), "x: int");
+        assert_eq!(eval(&mut repl, "x + 2"), "42: int");
+        assert_eq!(eval(&mut repl, "let x = \"shadowed\""), "x: string");
+        assert_eq!(eval(&mut repl, "x"), "\"shadowed\": string");
+    }
+
+    #[test]
+    fn test_multi_line_definitions() {
+        let mut repl = Repl::new();
+        assert!(repl.feed_line("func double(n: int) -> int {").is_none());
+        assert_eq!(repl.prompt(), "  ... ");
+        assert!(repl.feed_line("    return n * 2;").is_none());
+        assert_eq!(repl.feed_line("}").unwrap().unwrap(), "");
+        assert_eq!(repl.prompt(), "flux> ");
+
+        assert_eq!(repl.feed_line("double(21)").unwrap().unwrap(), "42: int");
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut repl = Repl::new();
+        eval(&mut repl, "let names = [\"a\", \"b\"]");
+        assert_eq!(eval(&mut repl, ":type names[0]"), "string");
+        assert!(eval(&mut repl, ":ast 1 + 2").contains("Binary"));
+        assert!(repl.eval(":nope").is_err());
+
+        let path = std::env::temp_dir().join(format!("flux_repl_{}.flux", std::process::id()));
+        fs::write(&path, "func square(n: int) -> int {\n    return n * n;\n}\n").unwrap();
+        let loaded = eval(&mut repl, &format!(":load {}", path.display()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.ends_with("square"));
+        assert_eq!(eval(&mut repl, "square(7)"), "49: int");
+    }
+
+    #[test]
+    fn test_output_and_errors() {
+        let mut repl = Repl::new();
+        assert_eq!(eval(&mut repl, "println(\"hello\")"), "");
+        assert_eq!(repl.take_output(), "hello\n");
+
+        assert!(repl.eval("undefined_name").is_err());
+        assert!(repl.eval("1 / 0").is_err());
+        assert_eq!(eval(&mut repl, "1 + 1"), "2: int");
+    }
+
+    #[test]
+    fn test_run_session() {
+        let mut repl = Repl::new();
+        let mut output = Vec::new();
+        repl.run("let x = 2\nprintln(\"hi\")\nx * 3\n:quit\nx\n".as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "flux> x: int\nflux> hi\nflux> 6: int\nflux> \n");
+    }
+}
@@ -91,6 +91,18 @@ impl Goroutine {
         }
     }
     
+    /// Create a goroutine whose code runs outside the scheduler, which only
+    /// decides when it gets a turn
+    pub fn hosted(id: GoroutineId) -> Self {
+        Self {
+            id,
+            stack: Stack::new(0),
+            state: GoroutineState::Ready,
+            context: Context::new(),
+            function: None,
+        }
+    }
+    
     /// Run the goroutine function
     pub fn run(&mut self) {
         if let Some(func) = self.function {
@@ -227,6 +239,17 @@ impl Scheduler {
     
     /// Add a goroutine to the scheduler
     pub fn add_goroutine(&self, func: fn()) -> GoroutineId {
+        self.enqueue(|id| Goroutine::new(id, func))
+    }
+    
+    /// Add a hosted goroutine, whose code the caller runs. Schedulers with
+    /// hosted goroutines are driven with `next_ready` rather than `start`.
+    pub fn add_hosted(&self) -> GoroutineId {
+        self.enqueue(Goroutine::hosted)
+    }
+    
+    /// Register a goroutine under the next id and queue it
+    fn enqueue(&self, goroutine: impl FnOnce(GoroutineId) -> Goroutine) -> GoroutineId {
         let id = {
             let mut next_id_guard = self.next_id.lock().unwrap();
             let id = *next_id_guard;
@@ -234,7 +257,7 @@ impl Scheduler {
             id
         };
         
-        let goroutine = Goroutine::new(id, func);
+        let goroutine = goroutine(id);
         
         {
             let mut goroutines_guard = self.goroutines.lock().unwrap();
@@ -310,6 +333,54 @@ impl Scheduler {
         }
     }
     
+    /// Take the next ready goroutine off the queue and make it the running
+    /// one, for callers that run hosted goroutines themselves
+    pub fn next_ready(&self) -> Option<GoroutineId> {
+        let id = self.ready_queue.lock().unwrap().pop_front()?;
+        
+        if let Some(goroutine) = self.goroutines.lock().unwrap().get_mut(&id) {
+            goroutine.state = GoroutineState::Running;
+        }
+        *self.current.lock().unwrap() = Some(id);
+        
+        let mut stats_guard = self.stats.lock().unwrap();
+        stats_guard.ready_count = stats_guard.ready_count.saturating_sub(1);
+        stats_guard.running_count += 1;
+        
+        Some(id)
+    }
+    
+    /// Record time the running goroutine spent on its turn
+    pub fn record_execution_time(&self, elapsed: Duration) {
+        self.stats.lock().unwrap().total_execution_time += elapsed;
+    }
+    
+    /// Mark the running goroutine finished and remove it
+    pub fn finish_current(&self) {
+        let Some(current_id) = self.current.lock().unwrap().take() else {
+            return;
+        };
+        
+        if self.goroutines.lock().unwrap().remove(&current_id).is_some() {
+            let mut stats_guard = self.stats.lock().unwrap();
+            stats_guard.running_count = stats_guard.running_count.saturating_sub(1);
+            stats_guard.finished_count += 1;
+            stats_guard.goroutines_executed += 1;
+        }
+    }
+    
+    /// Drop every goroutine that has not finished
+    pub fn clear(&self) {
+        self.goroutines.lock().unwrap().clear();
+        self.ready_queue.lock().unwrap().clear();
+        *self.current.lock().unwrap() = None;
+        
+        let mut stats_guard = self.stats.lock().unwrap();
+        stats_guard.ready_count = 0;
+        stats_guard.running_count = 0;
+        stats_guard.blocked_count = 0;
+    }
+    
     /// Stop the scheduler
     pub fn shutdown(&self) {
         {
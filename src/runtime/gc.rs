@@ -38,6 +38,39 @@ pub struct ObjectHeader {
     pub ref_count: u32,
     /// Allocation timestamp for debugging
     pub allocated_at: u64,
+    /// Reports the objects this one references, for traced allocations
+    pub trace: Option<unsafe fn(*mut u8, &mut Tracer)>,
+    /// Drops the object's value before its memory is freed, for traced allocations
+    pub drop: Option<unsafe fn(*mut u8)>,
+}
+
+/// Object that references other objects allocated by the same collector
+pub trait Trace {
+    /// Report every collected object this one references
+    fn trace(&self, tracer: &mut Tracer);
+}
+
+/// Receives the references of traced objects during the mark phase
+pub struct Tracer<'a> {
+    mark_stack: &'a mut Vec<NonNull<ObjectHeader>>,
+}
+
+impl Tracer<'_> {
+    /// Mark an object allocated by the collector as reachable
+    pub fn mark<T: 'static>(&mut self, ptr: NonNull<T>) {
+        unsafe {
+            let header = ObjectHeader::from_object_ptr(ptr.as_ptr());
+            self.mark_stack.push(NonNull::new_unchecked(header));
+        }
+    }
+}
+
+unsafe fn trace_object<T: Trace>(data: *mut u8, tracer: &mut Tracer) {
+    (*(data as *const T)).trace(tracer);
+}
+
+unsafe fn drop_object<T>(data: *mut u8) {
+    std::ptr::drop_in_place(data as *mut T);
 }
 
 impl ObjectHeader {
@@ -52,6 +85,8 @@ impl ObjectHeader {
             generation: 0,
             ref_count: 0,
             allocated_at: ALLOCATION_COUNTER.fetch_add(1, Ordering::SeqCst) as u64,
+            trace: None,
+            drop: None,
         }
     }
     
@@ -203,6 +238,20 @@ impl GarbageCollector {
         }
     }
     
+    /// Allocate an object whose references are followed during marking and
+    /// whose value is dropped when it is swept. Only the registered roots and
+    /// those passed to `collect_from` keep objects alive, so automatic
+    /// collection should be disabled when roots live elsewhere.
+    pub fn allocate_traced<T: Trace + 'static>(&mut self, value: T) -> Result<NonNull<T>, RuntimeError> {
+        let ptr = self.allocate(value)?;
+        unsafe {
+            let header = &mut *ObjectHeader::from_object_ptr(ptr.as_ptr());
+            header.trace = Some(trace_object::<T>);
+            header.drop = Some(drop_object::<T>);
+        }
+        Ok(ptr)
+    }
+    
     /// Deallocate memory for an object
     pub unsafe fn deallocate<T: 'static>(&mut self, ptr: NonNull<T>) -> Result<(), RuntimeError> {
         let header_ptr = ObjectHeader::from_object_ptr(ptr.as_ptr());
@@ -295,11 +344,33 @@ impl GarbageCollector {
     
     /// Perform garbage collection
     pub fn collect(&mut self) {
+        let collected = self.run_collection(Vec::new());
+        
+        #[cfg(debug_assertions)]
+        {
+            let (objects_collected, bytes_collected, collection_time) = collected;
+            println!("GC: Collected {} objects ({} bytes) in {:?}", 
+                     objects_collected, bytes_collected, collection_time);
+        }
+        let _ = collected;
+    }
+    
+    /// Collect everything not reachable from the registered roots or from
+    /// `roots`, for runtimes whose roots change too often to register
+    pub fn collect_from<T: 'static>(&mut self, roots: impl IntoIterator<Item = NonNull<T>>) {
+        let roots = roots.into_iter()
+            .map(|ptr| unsafe { NonNull::new_unchecked(ObjectHeader::from_object_ptr(ptr.as_ptr())) })
+            .collect();
+        self.run_collection(roots);
+    }
+    
+    /// Mark and sweep, returning the objects and bytes freed and the time taken
+    fn run_collection(&mut self, extra_roots: Vec<NonNull<ObjectHeader>>) -> (usize, usize, Duration) {
         let start_time = Instant::now();
         let initial_memory = self.stats.current_memory_usage;
         let initial_objects = self.heap.stats().object_count;
         
-        self.mark_phase();
+        self.mark_phase(extra_roots);
         self.sweep_phase();
         
         // Update statistics
@@ -314,11 +385,7 @@ impl GarbageCollector {
         self.stats.objects_collected += objects_collected;
         self.stats.bytes_collected += bytes_collected;
         
-        #[cfg(debug_assertions)]
-        {
-            println!("GC: Collected {} objects ({} bytes) in {:?}", 
-                     objects_collected, bytes_collected, collection_time);
-        }
+        (objects_collected, bytes_collected, collection_time)
     }
     
     /// Force garbage collection (for testing and debugging)
@@ -327,12 +394,13 @@ impl GarbageCollector {
     }
     
     /// Mark phase: mark all reachable objects
-    fn mark_phase(&mut self) {
+    fn mark_phase(&mut self, extra_roots: Vec<NonNull<ObjectHeader>>) {
         // Clear previous marks
         self.heap.clear_marks();
         
         // Mark all root objects
-        let roots: Vec<_> = self.roots.iter().copied().collect();
+        let mut roots: Vec<_> = self.roots.iter().copied().collect();
+        roots.extend(extra_roots);
         for root in roots {
             self.mark_object(root);
         }
@@ -352,7 +420,10 @@ impl GarbageCollector {
                 (obj.as_ptr() as *mut ObjectHeader).as_mut().unwrap().marked = true;
                 
                 // Add referenced objects to mark stack
-                // This is a placeholder - real implementation would traverse object references
+                if let Some(trace) = header.trace {
+                    let mut tracer = Tracer { mark_stack: &mut self.mark_stack };
+                    trace(header.data_ptr(), &mut tracer);
+                }
             }
         }
     }
@@ -360,6 +431,7 @@ impl GarbageCollector {
     /// Sweep phase: deallocate unmarked objects
     fn sweep_phase(&mut self) {
         self.heap.sweep();
+        self.stats.current_memory_usage = self.heap.stats().current_allocated;
     }
     
     /// Get heap statistics
@@ -499,6 +571,9 @@ impl Heap {
                 let header = obj.as_ref();
                 if !header.marked {
                     // Deallocate unmarked object
+                    if let Some(drop) = header.drop {
+                        drop(header.data_ptr());
+                    }
                     let size = header.size;
                     let layout = std::alloc::Layout::from_size_align_unchecked(
                         size,
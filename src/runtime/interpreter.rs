@@ -0,0 +1,1638 @@
+//! Tree-walking interpreter for typed Flux programs
+//!
+//! Executes the `TypedProgram`s produced by semantic analysis directly, so
+//! `flux run` works on machines without a native code generator. Runtime
+//! failures are reported through an `ErrorReporter`, together with a stack
+//! trace of the interpreted calls that led to them.
+
+use crate::parser::ast::{BinaryOp, Literal, Pattern, ResultPattern, UnaryOp};
+use crate::position::Span;
+use crate::runtime::error_reporting::{ErrorReport, ErrorReporter, StackFrame};
+use crate::runtime::range::IntRange;
+use crate::runtime::result::{FluxError, IndexError, RuntimeError, RuntimeErrorKind};
+use crate::semantic::{
+    TypedBlock, TypedClosure, TypedExpression, TypedExpressionKind, TypedItem, TypedMatchArm,
+    TypedMethod, TypedProgram, TypedStatement, TypedStatementKind,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Call depth at which a stack overflow is reported by default
+const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+/// Runtime value of a Flux expression
+#[derive(Debug, Clone)]
+pub enum Value {
+    Unit,
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(Rc<str>),
+    Array(Rc<RefCell<Vec<Value>>>),
+    /// Map entries in insertion order
+    Map(Rc<RefCell<Vec<(Value, Value)>>>),
+    Tuple(Rc<Vec<Value>>),
+    Range(IntRange),
+    /// Struct or class instance; fields are shared between copies of the value
+    Object(Rc<Object>),
+    /// Enum variant, including `Result`'s `Ok` and `Err`
+    Variant(Rc<VariantValue>),
+    Function(Rc<Callable>),
+}
+
+/// Struct or class instance
+#[derive(Debug)]
+pub struct Object {
+    pub type_name: String,
+    pub fields: RefCell<Vec<(String, Value)>>,
+}
+
+/// Enum variant with its payload in declaration order
+#[derive(Debug)]
+pub struct VariantValue {
+    pub enum_name: String,
+    pub variant: String,
+    pub fields: Vec<Value>,
+}
+
+/// Anything a call expression can invoke
+#[derive(Debug)]
+pub struct Callable {
+    kind: CallableKind,
+}
+
+#[derive(Debug)]
+enum CallableKind {
+    Function(Rc<FunctionDef>),
+    /// Method together with the receiver it was read from
+    Method(Value, Rc<FunctionDef>),
+    Closure(Rc<TypedClosure>, Vec<(String, Cell)>),
+    Variant(String, String),
+    Builtin(String),
+}
+
+/// Function, method or static method body ready to be called
+#[derive(Debug)]
+struct FunctionDef {
+    /// Name shown in stack traces
+    name: String,
+    parameters: Vec<String>,
+    body: TypedBlock,
+    /// Module whose globals the body sees
+    module: usize,
+    /// Source file the body was loaded from
+    file: Option<String>,
+}
+
+type Cell = Rc<RefCell<Value>>;
+
+/// Why evaluation stopped before producing a value
+enum Unwind {
+    Return(Value),
+    Break,
+    Continue,
+    Error(Box<ErrorReport>),
+}
+
+type Exec<T> = Result<T, Unwind>;
+
+/// Item visible by name at the top level of a module
+#[derive(Debug, Clone)]
+enum Global {
+    Function(Rc<FunctionDef>),
+    Const(Value),
+    /// Declared without a body; only callable through compiled code
+    Extern,
+}
+
+/// Loaded module: its globals and the modules its imports refer to
+struct Module {
+    globals: HashMap<String, Global>,
+    imports: HashMap<String, usize>,
+}
+
+/// Local variables of one active call. A frame without scopes runs at the
+/// top level of an interactive session, whose variables live in `session`.
+struct Frame {
+    scopes: Vec<HashMap<String, Cell>>,
+    module: usize,
+    file: Option<String>,
+    /// Whether the frame has an entry in the reporter's stack trace
+    traced: bool,
+}
+
+/// Where `print` and `println` write
+enum Output {
+    Stdout,
+    Captured(String),
+}
+
+/// Interpreter for typed Flux programs
+pub struct Interpreter {
+    modules: Vec<Module>,
+    module_paths: HashMap<String, usize>,
+    /// Methods by receiver type name and method name
+    methods: HashMap<(String, String), Rc<FunctionDef>>,
+    /// Field names of each enum variant, for struct patterns
+    variant_fields: HashMap<(String, String), Vec<String>>,
+    frames: Vec<Frame>,
+    /// Variables bound at the top level of an interactive session, one
+    /// scope per input so later inputs can shadow earlier bindings
+    session: Vec<HashMap<String, Cell>>,
+    reporter: ErrorReporter,
+    output: Output,
+    max_call_depth: usize,
+}
+
+impl Interpreter {
+    /// Create an interpreter with no modules loaded
+    pub fn new() -> Self {
+        Self {
+            modules: Vec::new(),
+            module_paths: HashMap::new(),
+            methods: HashMap::new(),
+            variant_fields: HashMap::new(),
+            frames: Vec::new(),
+            session: Vec::new(),
+            reporter: ErrorReporter::new(),
+            output: Output::Stdout,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+
+    /// Collect `print` output instead of writing it to stdout
+    pub fn capture_output(&mut self) {
+        self.output = Output::Captured(String::new());
+    }
+
+    /// Output collected since `capture_output` was called
+    pub fn captured_output(&self) -> Option<&str> {
+        match &self.output {
+            Output::Captured(output) => Some(output),
+            Output::Stdout => None,
+        }
+    }
+
+    /// Take the output captured so far, leaving the buffer empty
+    pub fn take_output(&mut self) -> String {
+        match &mut self.output {
+            Output::Captured(output) => std::mem::take(output),
+            Output::Stdout => String::new(),
+        }
+    }
+
+    /// Set the call depth at which a stack overflow is reported
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Register a source file so runtime errors can show the failing line
+    pub fn add_source_file(&mut self, path: PathBuf, content: String) {
+        self.reporter.add_source_file(path, content);
+    }
+
+    /// Load a module, evaluating its constants. `path` is the path other
+    /// modules import it by; the modules it imports must already be loaded.
+    pub fn load(&mut self, path: &str, program: &TypedProgram, file: Option<&Path>) -> Result<(), Box<ErrorReport>> {
+        let index = self.modules.len();
+        let imports = program.imports.iter()
+            .filter_map(|import| {
+                self.module_paths.get(&import.path).map(|&module| (import.binding_name().to_string(), module))
+            })
+            .collect();
+        self.modules.push(Module { globals: HashMap::new(), imports });
+        self.module_paths.insert(path.to_string(), index);
+        self.define_items(index, program, file)
+    }
+
+    /// Add the items of `program` to the last module loaded, as an
+    /// interactive session defines them one input at a time
+    pub fn extend(&mut self, program: &TypedProgram, file: Option<&Path>) -> Result<(), Box<ErrorReport>> {
+        let module = self.last_module();
+        self.define_items(module, program, file)
+    }
+
+    /// Index of the last module loaded, creating an empty one if there is none
+    fn last_module(&mut self) -> usize {
+        if self.modules.is_empty() {
+            self.modules.push(Module { globals: HashMap::new(), imports: HashMap::new() });
+        }
+        self.modules.len() - 1
+    }
+
+    fn define_items(&mut self, index: usize, program: &TypedProgram, file: Option<&Path>) -> Result<(), Box<ErrorReport>> {
+        let file = file.map(|file| file.to_string_lossy().to_string());
+        let mut consts = Vec::new();
+        for item in &program.items {
+            match item {
+                TypedItem::Function(func) => {
+                    let def = FunctionDef {
+                        name: func.name.clone(),
+                        parameters: func.parameters.iter().map(|param| param.name.clone()).collect(),
+                        body: func.body.clone(),
+                        module: index,
+                        file: file.clone(),
+                    };
+                    self.modules[index].globals.insert(func.name.clone(), Global::Function(Rc::new(def)));
+                }
+                TypedItem::Class(class) => {
+                    for method in &class.methods {
+                        self.define_method(index, file.clone(), &class.name, method);
+                    }
+                }
+                TypedItem::Impl(impl_block) => {
+                    for method in &impl_block.methods {
+                        self.define_method(index, file.clone(), &impl_block.type_name, method);
+                    }
+                }
+                TypedItem::Enum(enum_def) => {
+                    for variant in &enum_def.variants {
+                        let fields = variant.fields.iter().map(|field| field.name.clone()).collect();
+                        self.variant_fields.insert((enum_def.name.clone(), variant.name.clone()), fields);
+                    }
+                }
+                TypedItem::ExternFunction(func) => {
+                    self.modules[index].globals.insert(func.name.clone(), Global::Extern);
+                }
+                TypedItem::Const(constant) => consts.push(constant),
+                TypedItem::Struct(_) | TypedItem::Interface(_) => {}
+            }
+        }
+
+        // Constants are evaluated in declaration order, so each sees the ones before it
+        self.frames.push(Frame { scopes: vec![HashMap::new()], module: index, file, traced: false });
+        for constant in consts {
+            let value = self.eval(&constant.value);
+            let value = self.finish(value);
+            let value = match value {
+                Ok(value) => value,
+                Err(report) => {
+                    self.frames.pop();
+                    return Err(report);
+                }
+            };
+            self.modules[index].globals.insert(constant.name.clone(), Global::Const(value));
+        }
+        self.frames.pop();
+        Ok(())
+    }
+
+    fn define_method(&mut self, module: usize, file: Option<String>, type_name: &str, method: &TypedMethod) {
+        let def = Rc::new(FunctionDef {
+            name: format!("{}::{}", type_name, method.name),
+            parameters: method.parameters.iter().map(|param| param.name.clone()).collect(),
+            body: method.body.clone(),
+            module,
+            file,
+        });
+        if method.is_static {
+            self.modules[module].globals.insert(def.name.clone(), Global::Function(def));
+        } else {
+            self.methods.insert((type_name.to_string(), method.name.clone()), def);
+        }
+    }
+
+    /// Run the `main` function of the last module loaded. `args` are passed
+    /// as a `[string]` if `main` takes a parameter.
+    pub fn run_main(&mut self, args: &[String]) -> Result<Value, Box<ErrorReport>> {
+        let module = self.modules.len().saturating_sub(1);
+        let main = match self.modules.get(module).and_then(|module| module.globals.get("main")) {
+            Some(Global::Function(main)) => main.clone(),
+            _ => {
+                let error = runtime_error(RuntimeErrorKind::Panic, "program has no `main` function");
+                return Err(Box::new(self.reporter.generate_report(error, None, None)));
+            }
+        };
+
+        let args = if main.parameters.is_empty() {
+            Vec::new()
+        } else {
+            let args = args.iter().map(|arg| Value::String(arg.as_str().into())).collect();
+            vec![Value::Array(Rc::new(RefCell::new(args)))]
+        };
+        let result = self.call_function(&main, None, args, Vec::new(), None);
+        self.finish(result)
+    }
+
+    /// Call a top-level function of the last module loaded by name
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Box<ErrorReport>> {
+        let module = self.modules.len().saturating_sub(1);
+        let func = match self.modules.get(module).and_then(|module| module.globals.get(name)) {
+            Some(Global::Function(func)) => func.clone(),
+            _ => {
+                let error = runtime_error(RuntimeErrorKind::Panic, &format!("no function named `{}`", name));
+                return Err(Box::new(self.reporter.generate_report(error, None, None)));
+            }
+        };
+        let result = self.call_function(&func, None, args, Vec::new(), None);
+        self.finish(result)
+    }
+
+    /// Execute statements at the top level of an interactive session, in the
+    /// last module loaded. The variables they bind stay visible to later
+    /// calls, and the value of the last statement is returned.
+    pub fn execute(&mut self, statements: &[TypedStatement], file: Option<&Path>) -> Result<Value, Box<ErrorReport>> {
+        self.session.push(HashMap::new());
+        let module = self.last_module();
+        self.frames.push(Frame {
+            scopes: Vec::new(),
+            module,
+            file: file.map(|file| file.to_string_lossy().to_string()),
+            traced: false,
+        });
+        let result = self.exec_statements(statements);
+        self.frames.pop();
+        self.finish(result)
+    }
+
+    /// Turn an unwind that escaped to the top level into its outcome
+    fn finish(&mut self, result: Exec<Value>) -> Result<Value, Box<ErrorReport>> {
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(report)) => Err(report),
+            Err(Unwind::Break) | Err(Unwind::Continue) => Ok(Value::Unit),
+        }
+    }
+
+    // Calls
+
+    fn call_function(&mut self, def: &FunctionDef, receiver: Option<Value>, args: Vec<Value>, captures: Vec<(String, Cell)>, span: Option<Span>) -> Exec<Value> {
+        if self.frames.len() >= self.max_call_depth {
+            let message = format!("maximum call depth of {} exceeded in `{}`", self.max_call_depth, def.name);
+            return Err(self.fail(runtime_error(RuntimeErrorKind::StackOverflow, &message), span));
+        }
+
+        let mut scope: HashMap<String, Cell> = captures.into_iter().collect();
+        if let Some(receiver) = receiver {
+            scope.insert("self".to_string(), new_cell(receiver));
+        }
+        for (name, value) in def.parameters.iter().zip(args) {
+            scope.insert(name.clone(), new_cell(value));
+        }
+
+        self.enter(&def.name, def.module, def.file.clone(), scope);
+        let result = self.exec_block(&def.body);
+        self.leave();
+
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Break) | Err(Unwind::Continue) => Ok(Value::Unit),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn call_closure(&mut self, closure: &TypedClosure, captures: &[(String, Cell)], args: Vec<Value>, span: Option<Span>) -> Exec<Value> {
+        if self.frames.len() >= self.max_call_depth {
+            let message = format!("maximum call depth of {} exceeded in a closure", self.max_call_depth);
+            return Err(self.fail(runtime_error(RuntimeErrorKind::StackOverflow, &message), span));
+        }
+
+        let mut scope: HashMap<String, Cell> = captures.iter().cloned().collect();
+        for (param, value) in closure.parameters.iter().zip(args) {
+            scope.insert(param.name.clone(), new_cell(value));
+        }
+
+        let module = self.current_module();
+        let file = self.current_file();
+        self.enter("<closure>", module, file, scope);
+        let result = self.eval(&closure.body);
+        self.leave();
+
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Break) | Err(Unwind::Continue) => Ok(Value::Unit),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn call_value(&mut self, callee: &Value, args: Vec<Value>, span: Option<Span>) -> Exec<Value> {
+        let callable = match callee {
+            Value::Function(callable) => callable.clone(),
+            other => {
+                let message = format!("value `{}` is not callable", other);
+                return Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span));
+            }
+        };
+
+        match &callable.kind {
+            CallableKind::Function(def) => self.call_function(def, None, args, Vec::new(), span),
+            CallableKind::Method(receiver, def) => self.call_function(def, Some(receiver.clone()), args, Vec::new(), span),
+            CallableKind::Closure(closure, captures) => self.call_closure(closure, captures, args, span),
+            CallableKind::Variant(enum_name, variant) => Ok(variant_value(enum_name, variant, args)),
+            CallableKind::Builtin(name) => self.call_builtin(name, args, span),
+        }
+    }
+
+    fn call_builtin(&mut self, name: &str, mut args: Vec<Value>, span: Option<Span>) -> Exec<Value> {
+        match name {
+            "print" | "println" => {
+                let mut text: String = args.iter().map(|arg| arg.to_string()).collect();
+                if name == "println" {
+                    text.push('\n');
+                }
+                match &mut self.output {
+                    Output::Captured(output) => output.push_str(&text),
+                    Output::Stdout => {
+                        let mut stdout = std::io::stdout();
+                        if stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush()).is_err() {
+                            let error = runtime_error(RuntimeErrorKind::Panic, "failed to write to stdout");
+                            return Err(self.fail(error, span));
+                        }
+                    }
+                }
+                Ok(Value::Unit)
+            }
+            // `T?` values are represented by the value itself or `null`
+            "Some" => Ok(args.pop().unwrap_or(Value::Null)),
+            "Ok" | "Err" => Ok(variant_value("Result", name, args)),
+            _ => {
+                let message = format!("unknown builtin `{}`", name);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+            }
+        }
+    }
+
+    /// Push a call frame and its stack trace entry
+    fn enter(&mut self, name: &str, module: usize, file: Option<String>, scope: HashMap<String, Cell>) {
+        // The collector stops recording past its frame limit
+        let collector = &self.reporter.stack_trace_collector;
+        let traced = collector.frames.len() < collector.max_frames;
+        self.reporter.push_stack_frame(StackFrame {
+            function_name: name.to_string(),
+            file_path: file.clone().unwrap_or_else(|| "<unknown>".to_string()),
+            line: 0,
+            column: 0,
+            source_line: None,
+        });
+        self.frames.push(Frame { scopes: vec![scope], module, file, traced });
+    }
+
+    fn leave(&mut self) {
+        if self.frames.pop().is_some_and(|frame| frame.traced) {
+            self.reporter.pop_stack_frame();
+        }
+    }
+
+    /// Record the statement the current frame is executing
+    fn mark(&mut self, span: Option<Span>) {
+        let Some(span) = span else { return };
+        if !self.frames.last().is_some_and(|frame| frame.traced) {
+            return;
+        }
+        let source_line = self.current_file().and_then(|file| {
+            let source = self.reporter.source_files.get(&file)?;
+            source.lines.get(span.start.line.checked_sub(1)?).map(|line| line.trim().to_string())
+        });
+        if let Some(frame) = self.reporter.stack_trace_collector.frames.last_mut() {
+            frame.line = span.start.line;
+            frame.column = span.start.column;
+            frame.source_line = source_line;
+        }
+    }
+
+    /// Report a runtime error at `span` in the current module
+    fn fail(&self, error: FluxError, span: Option<Span>) -> Unwind {
+        let file = self.current_file();
+        Unwind::Error(Box::new(self.reporter.generate_report(error, span, file)))
+    }
+
+    fn current_module(&self) -> usize {
+        self.frames.last().map(|frame| frame.module).unwrap_or(0)
+    }
+
+    fn current_file(&self) -> Option<String> {
+        self.frames.last().and_then(|frame| frame.file.clone())
+    }
+
+    // Variables
+
+    fn define(&mut self, name: &str, value: Value) {
+        let scope = match self.frames.last_mut() {
+            Some(frame) if !frame.scopes.is_empty() => frame.scopes.last_mut(),
+            _ => self.session.last_mut(),
+        };
+        if let Some(scope) = scope {
+            scope.insert(name.to_string(), new_cell(value));
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<Cell> {
+        let frame = self.frames.last()?;
+        frame.scopes.iter().rev()
+            .chain(self.session.iter().rev())
+            .find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn lookup(&self, name: &str, span: Option<Span>) -> Exec<Value> {
+        if let Some(cell) = self.local(name) {
+            return Ok(cell.borrow().clone());
+        }
+
+        let module = &self.modules[self.current_module()];
+        let global = module.globals.get(name).or_else(|| {
+            // `binding::name` refers to a global of an imported module
+            let (binding, member) = name.split_once("::")?;
+            let imported = module.imports.get(binding)?;
+            self.modules[*imported].globals.get(member)
+        });
+        match global {
+            Some(Global::Function(def)) => return Ok(callable(CallableKind::Function(def.clone()))),
+            Some(Global::Const(value)) => return Ok(value.clone()),
+            Some(Global::Extern) => {
+                let message = format!("extern function `{}` cannot be called by the interpreter", name);
+                return Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span));
+            }
+            None => {}
+        }
+
+        if let Some((enum_name, variant)) = name.split_once("::") {
+            if self.variant_fields.contains_key(&(enum_name.to_string(), variant.to_string())) {
+                return Ok(callable(CallableKind::Variant(enum_name.to_string(), variant.to_string())));
+            }
+        }
+        if crate::semantic::type_checker::BUILTINS.contains(&name) {
+            return Ok(callable(CallableKind::Builtin(name.to_string())));
+        }
+
+        let message = format!("undefined name `{}`", name);
+        Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+    }
+
+    // Statements
+
+    fn exec_block(&mut self, block: &TypedBlock) -> Exec<Value> {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.push(HashMap::new());
+        }
+        let result = self.exec_statements(&block.statements);
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.pop();
+        }
+        result
+    }
+
+    /// Execute statements in order; their value is that of the last one, so
+    /// a trailing `match` or `if` gives a function its result
+    fn exec_statements(&mut self, statements: &[TypedStatement]) -> Exec<Value> {
+        let mut value = Value::Unit;
+        for stmt in statements {
+            value = self.exec_statement(stmt)?;
+        }
+        Ok(value)
+    }
+
+    fn exec_statement(&mut self, stmt: &TypedStatement) -> Exec<Value> {
+        self.mark(stmt.span);
+        match &stmt.kind {
+            TypedStatementKind::Expression(expr) => return self.eval(expr),
+            TypedStatementKind::Let(name, _, init) => {
+                let value = match init {
+                    Some(init) => self.eval(init)?,
+                    None => Value::Unit,
+                };
+                self.define(name, value);
+            }
+            TypedStatementKind::Destructure(pattern, value) => {
+                let value = self.eval(value)?;
+                let mut bindings = Vec::new();
+                if !self.match_pattern(pattern, &value, &mut bindings) {
+                    let message = format!("pattern `{}` did not match `{}`", pattern, value);
+                    return Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), stmt.span));
+                }
+                for (name, value) in bindings {
+                    self.define(&name, value);
+                }
+            }
+            TypedStatementKind::Const(name, _, value) => {
+                let value = self.eval(value)?;
+                self.define(name, value);
+            }
+            TypedStatementKind::Assignment(target, value) => {
+                let value = self.eval(value)?;
+                self.assign(target, value)?;
+            }
+            TypedStatementKind::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Unit,
+                };
+                return Err(Unwind::Return(value));
+            }
+            TypedStatementKind::Break(_) => return Err(Unwind::Break),
+            TypedStatementKind::Continue => return Err(Unwind::Continue),
+            // Goroutines run to completion when spawned; the interpreter is single-threaded
+            TypedStatementKind::Go(expr) => {
+                self.eval(expr)?;
+            }
+            TypedStatementKind::If(cond, then_block, else_block) => {
+                if self.eval_condition(cond)? {
+                    return self.exec_block(then_block);
+                } else if let Some(else_block) = else_block {
+                    return self.exec_block(else_block);
+                }
+            }
+            TypedStatementKind::While(cond, body) => {
+                while self.eval_condition(cond)? {
+                    match self.exec_block(body) {
+                        Ok(_) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break) => break,
+                        Err(other) => return Err(other),
+                    }
+                }
+            }
+            TypedStatementKind::For(var, iter, body) => {
+                // Ranges count without building the list of their values
+                let elements: Box<dyn Iterator<Item = Value>> = match self.eval(iter)? {
+                    Value::Array(elements) => Box::new(elements.borrow().clone().into_iter()),
+                    Value::Map(entries) => Box::new(entries.borrow().iter()
+                        .map(|(key, value)| Value::Tuple(Rc::new(vec![key.clone(), value.clone()])))
+                        .collect::<Vec<_>>()
+                        .into_iter()),
+                    Value::Range(range) => Box::new(range.iter().map(Value::Int)),
+                    other => {
+                        let message = format!("value `{}` is not iterable", other);
+                        return Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), iter.span));
+                    }
+                };
+                for element in elements {
+                    if let Some(frame) = self.frames.last_mut() {
+                        frame.scopes.push(HashMap::from([(var.clone(), new_cell(element))]));
+                    }
+                    let result = self.exec_block(body);
+                    if let Some(frame) = self.frames.last_mut() {
+                        frame.scopes.pop();
+                    }
+                    match result {
+                        Ok(_) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break) => break,
+                        Err(other) => return Err(other),
+                    }
+                }
+            }
+            TypedStatementKind::Match(scrutinee, arms) => return self.eval_match(scrutinee, arms, stmt.span),
+        }
+        Ok(Value::Unit)
+    }
+
+    fn assign(&mut self, target: &TypedExpression, value: Value) -> Exec<()> {
+        match &target.kind {
+            TypedExpressionKind::Identifier(name) => match self.local(name) {
+                Some(cell) => {
+                    *cell.borrow_mut() = value;
+                    Ok(())
+                }
+                None => {
+                    let message = format!("cannot assign to `{}`", name);
+                    Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), target.span))
+                }
+            },
+            TypedExpressionKind::Field(object, name) => match self.eval(object)? {
+                Value::Object(object) => {
+                    let mut fields = object.fields.borrow_mut();
+                    match fields.iter_mut().find(|(field, _)| field == name) {
+                        Some((_, slot)) => *slot = value,
+                        None => fields.push((name.clone(), value)),
+                    }
+                    Ok(())
+                }
+                other => {
+                    let message = format!("value `{}` has no field `{}`", other, name);
+                    Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), target.span))
+                }
+            },
+            TypedExpressionKind::Index(collection, index) => {
+                let collection_value = self.eval(collection)?;
+                let index_value = self.eval(index)?;
+                match collection_value {
+                    Value::Array(elements) => {
+                        let position = self.array_position(&index_value, elements.borrow().len(), target.span)?;
+                        elements.borrow_mut()[position] = value;
+                        Ok(())
+                    }
+                    Value::Map(entries) => {
+                        let mut entries = entries.borrow_mut();
+                        match entries.iter_mut().find(|(key, _)| values_equal(key, &index_value)) {
+                            Some((_, slot)) => *slot = value,
+                            None => entries.push((index_value, value)),
+                        }
+                        Ok(())
+                    }
+                    other => {
+                        let message = format!("value `{}` cannot be indexed", other);
+                        Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), target.span))
+                    }
+                }
+            }
+            _ => {
+                let error = runtime_error(RuntimeErrorKind::Panic, "invalid assignment target");
+                Err(self.fail(error, target.span))
+            }
+        }
+    }
+
+    fn eval_condition(&mut self, cond: &TypedExpression) -> Exec<bool> {
+        match self.eval(cond)? {
+            Value::Bool(value) => Ok(value),
+            other => {
+                let message = format!("condition evaluated to `{}` instead of a bool", other);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), cond.span))
+            }
+        }
+    }
+
+    fn eval_match(&mut self, scrutinee: &TypedExpression, arms: &[TypedMatchArm], span: Option<Span>) -> Exec<Value> {
+        let value = self.eval(scrutinee)?;
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if !self.match_pattern(&arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+
+            if let Some(frame) = self.frames.last_mut() {
+                frame.scopes.push(bindings.into_iter().map(|(name, value)| (name, new_cell(value))).collect());
+            }
+            let result = match &arm.guard {
+                Some(guard) => match self.eval_condition(guard) {
+                    Ok(true) => self.exec_block(&arm.body).map(Some),
+                    Ok(false) => Ok(None),
+                    Err(error) => Err(error),
+                },
+                None => self.exec_block(&arm.body).map(Some),
+            };
+            if let Some(frame) = self.frames.last_mut() {
+                frame.scopes.pop();
+            }
+            if let Some(value) = result? {
+                return Ok(value);
+            }
+        }
+
+        let message = format!("no match arm matched `{}`", value);
+        Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span.or(scrutinee.span)))
+    }
+
+    fn match_pattern(&self, pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+        match pattern {
+            Pattern::Wildcard => true,
+            Pattern::Identifier(name) => {
+                bindings.push((name.clone(), value.clone()));
+                true
+            }
+            Pattern::Literal(literal, _) => values_equal(&literal_value(literal), value),
+            Pattern::Tuple(patterns) => match value {
+                Value::Tuple(elements) => {
+                    patterns.len() == elements.len()
+                        && patterns.iter().zip(elements.iter()).all(|(pattern, element)| self.match_pattern(pattern, element, bindings))
+                }
+                _ => false,
+            },
+            Pattern::Variant(path, patterns) => match value {
+                Value::Variant(variant) => {
+                    path.last() == Some(&variant.variant)
+                        && patterns.len() == variant.fields.len()
+                        && patterns.iter().zip(&variant.fields).all(|(pattern, field)| self.match_pattern(pattern, field, bindings))
+                }
+                _ => false,
+            },
+            Pattern::Struct(name, field_patterns) => match value {
+                Value::Object(object) => {
+                    let fields = object.fields.borrow();
+                    object.type_name == *name && field_patterns.iter().all(|(field, pattern)| {
+                        fields.iter()
+                            .find(|(candidate, _)| candidate == field)
+                            .is_some_and(|(_, value)| self.match_pattern(pattern, value, bindings))
+                    })
+                }
+                Value::Variant(variant) => {
+                    let variant_name = name.rsplit("::").next().unwrap_or(name);
+                    let Some(names) = self.variant_fields.get(&(variant.enum_name.clone(), variant.variant.clone())) else {
+                        return false;
+                    };
+                    variant.variant == variant_name && field_patterns.iter().all(|(field, pattern)| {
+                        names.iter()
+                            .position(|candidate| candidate == field)
+                            .and_then(|position| variant.fields.get(position))
+                            .is_some_and(|value| self.match_pattern(pattern, value, bindings))
+                    })
+                }
+                _ => false,
+            },
+            Pattern::Result(result_pattern) => {
+                let (expected, inner) = match result_pattern {
+                    ResultPattern::Ok(inner) => ("Ok", inner),
+                    ResultPattern::Err(inner) => ("Err", inner),
+                };
+                match value {
+                    Value::Variant(variant) if variant.enum_name == "Result" && variant.variant == expected => {
+                        variant.fields.first().is_some_and(|payload| self.match_pattern(inner, payload, bindings))
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    // Expressions
+
+    fn eval(&mut self, expr: &TypedExpression) -> Exec<Value> {
+        match &expr.kind {
+            TypedExpressionKind::Literal(literal) => Ok(literal_value(literal)),
+            TypedExpressionKind::Identifier(name) | TypedExpressionKind::Instantiate(name, _) => self.lookup(name, expr.span),
+            TypedExpressionKind::Binary(left, op, right) => self.eval_binary(left, op, right, expr.span),
+            TypedExpressionKind::Unary(op, operand) => {
+                let value = self.eval(operand)?;
+                self.eval_unary(op, value, expr.span)
+            }
+            TypedExpressionKind::Call(callee, args) => self.eval_call(callee, args, expr.span),
+            TypedExpressionKind::Index(collection, index) => {
+                let collection = self.eval(collection)?;
+                let index = self.eval(index)?;
+                self.index(&collection, &index, expr.span)
+            }
+            TypedExpressionKind::Field(object, name) => {
+                let object = self.eval(object)?;
+                self.field(object, name, expr.span)
+            }
+            TypedExpressionKind::Match(scrutinee, arms) => self.eval_match(scrutinee, arms, expr.span),
+            TypedExpressionKind::If(cond, then_block, else_block) => {
+                if self.eval_condition(cond)? {
+                    self.exec_block(then_block)
+                } else if let Some(else_block) = else_block {
+                    self.exec_block(else_block)
+                } else {
+                    Ok(Value::Unit)
+                }
+            }
+            TypedExpressionKind::Block(block) => self.exec_block(block),
+            TypedExpressionKind::Array(elements) => {
+                let elements = elements.iter().map(|element| self.eval(element)).collect::<Exec<Vec<_>>>()?;
+                Ok(Value::Array(Rc::new(RefCell::new(elements))))
+            }
+            TypedExpressionKind::Map(pairs) => {
+                let mut entries: Vec<(Value, Value)> = Vec::new();
+                for (key, value) in pairs {
+                    let key = self.eval(key)?;
+                    let value = self.eval(value)?;
+                    match entries.iter_mut().find(|(existing, _)| values_equal(existing, &key)) {
+                        Some((_, slot)) => *slot = value,
+                        None => entries.push((key, value)),
+                    }
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(entries))))
+            }
+            TypedExpressionKind::Tuple(elements) => {
+                let elements = elements.iter().map(|element| self.eval(element)).collect::<Exec<Vec<_>>>()?;
+                Ok(Value::Tuple(Rc::new(elements)))
+            }
+            TypedExpressionKind::Interpolated(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.eval(part)?.to_string());
+                }
+                Ok(Value::String(text.into()))
+            }
+            TypedExpressionKind::Range(start, end, step, inclusive) => {
+                let start = self.eval_int(start)?;
+                let end = self.eval_int(end)?;
+                let step = match step {
+                    Some(step) => self.eval_int(step)?,
+                    None => 1,
+                };
+                match IntRange::new(start, end, step, *inclusive) {
+                    Some(range) => Ok(Value::Range(range)),
+                    None => Err(self.fail(runtime_error(RuntimeErrorKind::Panic, "range step cannot be zero"), expr.span)),
+                }
+            }
+            TypedExpressionKind::StructLiteral(name, fields) => {
+                let mut values = Vec::with_capacity(fields.len());
+                for (field, value) in fields {
+                    values.push((field.clone(), self.eval(value)?));
+                }
+                Ok(Value::Object(Rc::new(Object { type_name: name.clone(), fields: RefCell::new(values) })))
+            }
+            TypedExpressionKind::Variant(enum_name, variant, payload) => {
+                let fields = payload.iter().map(|field| self.eval(field)).collect::<Exec<Vec<_>>>()?;
+                Ok(variant_value(enum_name, variant, fields))
+            }
+            // Interface values dispatch on the runtime type of the value itself
+            TypedExpressionKind::Upcast(inner) => self.eval(inner),
+            TypedExpressionKind::Closure(closure) => {
+                let mut captures = Vec::with_capacity(closure.captures.len());
+                for capture in &closure.captures {
+                    let cell = match self.local(&capture.name) {
+                        Some(cell) if capture.by_reference => cell,
+                        Some(cell) => new_cell(cell.borrow().clone()),
+                        None => new_cell(self.lookup(&capture.name, expr.span)?),
+                    };
+                    captures.push((capture.name.clone(), cell));
+                }
+                Ok(callable(CallableKind::Closure(Rc::new(closure.clone()), captures)))
+            }
+        }
+    }
+
+    fn eval_call(&mut self, callee: &TypedExpression, args: &[TypedExpression], span: Option<Span>) -> Exec<Value> {
+        let callee = self.eval(callee)?;
+        let args = args.iter().map(|arg| self.eval(arg)).collect::<Exec<Vec<_>>>()?;
+        self.call_value(&callee, args, span)
+    }
+
+    fn field(&self, object: Value, name: &str, span: Option<Span>) -> Exec<Value> {
+        if let Value::Object(instance) = &object {
+            if let Some((_, value)) = instance.fields.borrow().iter().find(|(field, _)| field == name) {
+                return Ok(value.clone());
+            }
+        }
+
+        let type_name = object.type_name();
+        match self.methods.get(&(type_name.clone(), name.to_string())) {
+            Some(method) => Ok(callable(CallableKind::Method(object, method.clone()))),
+            None => {
+                let message = format!("`{}` has no field or method `{}`", type_name, name);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+            }
+        }
+    }
+
+    fn index(&self, collection: &Value, index: &Value, span: Option<Span>) -> Exec<Value> {
+        match collection {
+            Value::Array(elements) => {
+                let elements = elements.borrow();
+                let position = self.array_position(index, elements.len(), span)?;
+                Ok(elements[position].clone())
+            }
+            Value::Map(entries) => {
+                let entries = entries.borrow();
+                match entries.iter().find(|(key, _)| values_equal(key, index)) {
+                    Some((_, value)) => Ok(value.clone()),
+                    None => {
+                        let message = format!("key `{}` not found in map", index);
+                        Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+                    }
+                }
+            }
+            Value::String(text) => {
+                let position = self.array_position(index, text.chars().count(), span)?;
+                Ok(Value::Char(text.chars().nth(position).unwrap_or_default()))
+            }
+            other => {
+                let message = format!("value `{}` cannot be indexed", other);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+            }
+        }
+    }
+
+    /// Evaluate a range bound or step, which the type checker proved is an int
+    fn eval_int(&mut self, expr: &TypedExpression) -> Exec<i64> {
+        match self.eval(expr)? {
+            Value::Int(value) => Ok(value),
+            other => {
+                let message = format!("value `{}` is not an int", other);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), expr.span))
+            }
+        }
+    }
+
+    fn array_position(&self, index: &Value, length: usize, span: Option<Span>) -> Exec<usize> {
+        let index = match index {
+            Value::Int(index) => *index,
+            other => {
+                let message = format!("index `{}` is not an int", other);
+                return Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span));
+            }
+        };
+        match usize::try_from(index) {
+            Ok(position) if position < length => Ok(position),
+            _ => Err(self.fail(FluxError::IndexOutOfBounds(IndexError {
+                message: "array index out of range".to_string(),
+                index,
+                length,
+            }), span)),
+        }
+    }
+
+    fn eval_binary(&mut self, left: &TypedExpression, op: &BinaryOp, right: &TypedExpression, span: Option<Span>) -> Exec<Value> {
+        // `&&` and `||` only evaluate their right operand when needed
+        if matches!(op, BinaryOp::And | BinaryOp::Or) {
+            let left_value = self.eval_condition(left)?;
+            if left_value == matches!(op, BinaryOp::Or) {
+                return Ok(Value::Bool(left_value));
+            }
+            return self.eval_condition(right).map(Value::Bool);
+        }
+
+        let left = self.eval(left)?;
+        let right = self.eval(right)?;
+        match op {
+            BinaryOp::Equal => return Ok(Value::Bool(values_equal(&left, &right))),
+            BinaryOp::NotEqual => return Ok(Value::Bool(!values_equal(&left, &right))),
+            _ => {}
+        }
+
+        let result = match (&left, &right) {
+            (Value::Int(a), Value::Int(b)) => self.int_op(*a, op, *b, span)?,
+            (Value::Float(a), Value::Float(b)) => float_op(*a, op, *b),
+            (Value::String(a), Value::String(b)) => match op {
+                BinaryOp::Add => Some(Value::String(format!("{}{}", a, b).into())),
+                _ => compare(a.cmp(b), op),
+            },
+            (Value::Char(a), Value::Char(b)) => compare(a.cmp(b), op),
+            _ => None,
+        };
+        match result {
+            Some(value) => Ok(value),
+            None => {
+                let message = format!("unsupported operands `{}` and `{}` for {:?}", left, right, op);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+            }
+        }
+    }
+
+    fn int_op(&self, a: i64, op: &BinaryOp, b: i64, span: Option<Span>) -> Exec<Option<Value>> {
+        if matches!(op, BinaryOp::Divide | BinaryOp::Modulo) && b == 0 {
+            return Err(self.fail(runtime_error(RuntimeErrorKind::DivisionByZero, "division by zero"), span));
+        }
+
+        let checked = match op {
+            BinaryOp::Add => a.checked_add(b),
+            BinaryOp::Subtract => a.checked_sub(b),
+            BinaryOp::Multiply => a.checked_mul(b),
+            BinaryOp::Divide => a.checked_div(b),
+            BinaryOp::Modulo => a.checked_rem(b),
+            BinaryOp::BitwiseAnd => Some(a & b),
+            BinaryOp::BitwiseOr => Some(a | b),
+            BinaryOp::BitwiseXor => Some(a ^ b),
+            BinaryOp::LeftShift => u32::try_from(b).ok().and_then(|shift| a.checked_shl(shift)),
+            BinaryOp::RightShift => u32::try_from(b).ok().and_then(|shift| a.checked_shr(shift)),
+            _ => return Ok(compare(a.cmp(&b), op)),
+        };
+        match checked {
+            Some(value) => Ok(Some(Value::Int(value))),
+            None => {
+                let message = format!("integer overflow in {} {:?} {}", a, op, b);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+            }
+        }
+    }
+
+    fn eval_unary(&self, op: &UnaryOp, value: Value, span: Option<Span>) -> Exec<Value> {
+        match (op, value) {
+            (UnaryOp::Plus, value) => Ok(value),
+            (UnaryOp::Minus, Value::Int(value)) => match value.checked_neg() {
+                Some(negated) => Ok(Value::Int(negated)),
+                None => {
+                    let message = format!("integer overflow negating {}", value);
+                    Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+                }
+            },
+            (UnaryOp::Minus, Value::Float(value)) => Ok(Value::Float(-value)),
+            (UnaryOp::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+            (UnaryOp::BitwiseNot, Value::Int(value)) => Ok(Value::Int(!value)),
+            // `?` unwraps `Ok` and returns `Err` from the enclosing function
+            (UnaryOp::Try, Value::Variant(variant)) if variant.enum_name == "Result" => {
+                if variant.variant == "Ok" {
+                    Ok(variant.fields.first().cloned().unwrap_or(Value::Unit))
+                } else {
+                    Err(Unwind::Return(Value::Variant(variant)))
+                }
+            }
+            (UnaryOp::Unwrap, Value::Null) => {
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, "unwrapped a null value"), span))
+            }
+            (UnaryOp::Unwrap, value) => Ok(value),
+            (op, value) => {
+                let message = format!("unsupported operand `{}` for {:?}", value, op);
+                Err(self.fail(runtime_error(RuntimeErrorKind::Panic, &message), span))
+            }
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Value {
+    /// Name of the type methods are looked up on
+    fn type_name(&self) -> String {
+        match self {
+            Value::Unit => "()".to_string(),
+            Value::Null => "null".to_string(),
+            Value::Int(_) => "int".to_string(),
+            Value::Float(_) => "float".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Char(_) => "char".to_string(),
+            Value::String(_) => "string".to_string(),
+            Value::Array(_) => "array".to_string(),
+            Value::Map(_) => "map".to_string(),
+            Value::Tuple(_) => "tuple".to_string(),
+            Value::Range(_) => "Range".to_string(),
+            Value::Object(object) => object.type_name.clone(),
+            Value::Variant(variant) => variant.enum_name.clone(),
+            Value::Function(_) => "function".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Null => write!(f, "null"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, ")")
+            }
+            Value::Range(range) => write!(f, "{}", range),
+            Value::Object(object) => {
+                write!(f, "{} {{", object.type_name)?;
+                for (i, (name, value)) in object.fields.borrow().iter().enumerate() {
+                    write!(f, "{} {}: {}", if i > 0 { "," } else { "" }, name, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Variant(variant) => {
+                if variant.enum_name != "Result" {
+                    write!(f, "{}::", variant.enum_name)?;
+                }
+                write!(f, "{}", variant.variant)?;
+                if !variant.fields.is_empty() {
+                    write!(f, "(")?;
+                    for (i, field) in variant.fields.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", field)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            Value::Function(_) => write!(f, "<function>"),
+        }
+    }
+}
+
+fn new_cell(value: Value) -> Cell {
+    Rc::new(RefCell::new(value))
+}
+
+fn callable(kind: CallableKind) -> Value {
+    Value::Function(Rc::new(Callable { kind }))
+}
+
+fn variant_value(enum_name: &str, variant: &str, fields: Vec<Value>) -> Value {
+    Value::Variant(Rc::new(VariantValue {
+        enum_name: enum_name.to_string(),
+        variant: variant.to_string(),
+        fields,
+    }))
+}
+
+fn runtime_error(kind: RuntimeErrorKind, message: &str) -> FluxError {
+    FluxError::Runtime(RuntimeError { message: message.to_string(), kind })
+}
+
+fn literal_value(literal: &Literal) -> Value {
+    match literal {
+        Literal::Integer(value) => Value::Int(*value),
+        Literal::Float(value) => Value::Float(*value),
+        // Bytes are held as ints
+        Literal::Byte(value) => Value::Int(i64::from(*value)),
+        Literal::String(value) => Value::String(value.as_str().into()),
+        Literal::Boolean(value) => Value::Bool(*value),
+        Literal::Character(value) => Value::Char(*value),
+        Literal::Null => Value::Null,
+    }
+}
+
+fn float_op(a: f64, op: &BinaryOp, b: f64) -> Option<Value> {
+    match op {
+        BinaryOp::Add => Some(Value::Float(a + b)),
+        BinaryOp::Subtract => Some(Value::Float(a - b)),
+        BinaryOp::Multiply => Some(Value::Float(a * b)),
+        BinaryOp::Divide => Some(Value::Float(a / b)),
+        BinaryOp::Modulo => Some(Value::Float(a % b)),
+        _ => a.partial_cmp(&b).and_then(|ordering| compare(ordering, op)),
+    }
+}
+
+fn compare(ordering: std::cmp::Ordering, op: &BinaryOp) -> Option<Value> {
+    let result = match op {
+        BinaryOp::Less => ordering.is_lt(),
+        BinaryOp::LessEqual => ordering.is_le(),
+        BinaryOp::Greater => ordering.is_gt(),
+        BinaryOp::GreaterEqual => ordering.is_ge(),
+        _ => return None,
+    };
+    Some(Value::Bool(result))
+}
+
+/// Structural equality; objects and closures compare by identity
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Unit, Value::Unit) | (Value::Null, Value::Null) => true,
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Float(a), Value::Float(b)) => a == b,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Char(a), Value::Char(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::Array(a), Value::Array(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| values_equal(a, b))
+        }
+        (Value::Tuple(a), Value::Tuple(b)) => a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| values_equal(a, b)),
+        (Value::Range(a), Value::Range(b)) => a == b,
+        (Value::Variant(a), Value::Variant(b)) => {
+            a.enum_name == b.enum_name
+                && a.variant == b.variant
+                && a.fields.iter().zip(&b.fields).all(|(a, b)| values_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => Rc::ptr_eq(a, b),
+        (Value::Map(a), Value::Map(b)) => Rc::ptr_eq(a, b),
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::FluxLexer;
+    use crate::parser::{FluxParser, Parser};
+    use crate::semantic::{FluxSemanticAnalyzer, SemanticAnalyzer};
+
+    const FILE: &str = "main.flux";
+
+    fn interpreter(source: &str) -> Interpreter {
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = parser.parse_program().unwrap();
+        let typed = FluxSemanticAnalyzer::new().analyze(program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.capture_output();
+        interpreter.add_source_file(PathBuf::from(FILE), source.to_string());
+        interpreter.load("main", &typed, Some(Path::new(FILE))).unwrap();
+        interpreter
+    }
+
+    fn run(source: &str) -> String {
+        let mut interpreter = interpreter(source);
+        if let Err(report) = interpreter.run_main(&[]) {
+            panic!("{}", report);
+        }
+        interpreter.captured_output().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_arithmetic_and_loops() {
+        let output = run(r#"
+func main() {
+    let total = 0;
+    let i = 0;
+    while i < 10 {
+        i = i + 1;
+        if i % 2 == 0 { continue; }
+        if i > 7 { break; }
+        total = total + i;
+    }
+    for x in [10, 20] {
+        total = total + x;
+    }
+    if total == 46 && 7 / 2 == 3 && -(2 - 5) == 3 {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+    }
+
+    #[test]
+    fn test_structs_classes_and_methods() {
+        let output = run(r#"
+struct Point { mut x: int, y: int }
+
+class Counter {
+    mut count: int
+
+    static func start() -> Counter { return Counter { count: 10 } }
+    func bump() -> int {
+        self.count = self.count + 1;
+        return self.count;
+    }
+}
+
+func main() {
+    let p = Point { x: 1, y: 2 };
+    p.x = 5;
+    let c = Counter::start();
+    c.bump();
+    if p.x + p.y == 7 && c.bump() == 12 {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+    }
+
+    #[test]
+    fn test_match_on_enum_variants() {
+        let output = run(r#"
+enum Shape { Circle(int), Rect(int, int), Empty }
+
+func area(s: Shape) -> int {
+    match s {
+        Shape::Circle(r) => { 3 * r * r }
+        Shape::Rect(w, h) if w == h => { w * w }
+        Shape::Rect(w, h) => { w * h }
+        Shape::Empty => { 0 }
+    }
+}
+
+func main() {
+    if area(Shape::Circle(2)) == 12 && area(Shape::Rect(2, 3)) == 6 && area(Shape::Empty) == 0 {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+    }
+
+    #[test]
+    fn test_result_and_try_operator() {
+        let output = run(r#"
+func half(n: int) -> Result<int, string> {
+    if n % 2 != 0 {
+        return Err("odd");
+    }
+    return Ok(n / 2);
+}
+
+func quarter(n: int) -> Result<int, string> {
+    let h = half(n)?;
+    return half(h);
+}
+
+func main() {
+    match quarter(12) {
+        Ok(v) => { if v == 3 { println("three"); } }
+        Err(e) => { println(e); }
+    }
+    match quarter(6) {
+        Ok(v) => { println("unexpected"); }
+        Err(e) => { println("error: " + e); }
+    }
+}
+"#);
+        assert_eq!(output, "three\nerror: odd\n");
+    }
+
+    #[test]
+    fn test_null_safety_operators() {
+        let output = run(r#"
+struct User { name: string, boss: User? }
+
+func find(users: [User], name: string) -> User? {
+    for user in users {
+        if user.name == name { return user; }
+    }
+    return null;
+}
+
+func main() {
+    let ada = User { name: "ada", boss: null };
+    let users = [ada, User { name: "bob", boss: ada }];
+    println(find(users, "bob")?.boss?.name ?? "none");
+    println(find(users, "ada")?.boss?.name ?? "none");
+    let found = find(users, "eve");
+    if found == null {
+        println("no eve");
+    }
+    let bob = find(users, "bob");
+    if bob == null { return; }
+    println(bob.name + " " + bob.boss!.name);
+}
+"#);
+        assert_eq!(output, "ada\nnone\nno eve\nbob ada\n");
+    }
+
+    #[test]
+    fn test_try_on_nullable_values() {
+        let output = run(r#"
+struct User { name: string, boss: User? }
+
+func boss_name(user: User?) -> string? {
+    let found = user?;
+    let boss = found.boss?;
+    return boss.name;
+}
+
+func main() {
+    let ada = User { name: "ada", boss: null };
+    let bob = User { name: "bob", boss: ada };
+    println(boss_name(bob) ?? "none");
+    println(boss_name(ada) ?? "none");
+    println(boss_name(null) ?? "none");
+}
+"#);
+        assert_eq!(output, "ada\nnone\nnone\n");
+    }
+
+    #[test]
+    fn test_ranges() {
+        let output = run(r#"
+func main() {
+    let total = 0;
+    for i in 0..5 { total = total + i; }
+    for i in 1..=3 { total = total + i; }
+    for i in 10..0 step -3 { total = total + i; }
+    let n = 4;
+    let step = 2;
+    let evens = n..=0 step -step;
+    for i in evens { total = total + i; }
+    for i in 0..n step step { total = total + i; }
+    for i in n..0 { println("empty"); }
+    for i in 0..10 {
+        if i == 3 { break; }
+        if i == 1 { continue; }
+        total = total + 100;
+    }
+    if total == 246 && evens == (4..=0 step -2) {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+
+        // Counting stops at the end rather than overflowing past it
+        let output = run(r#"
+func main() {
+    let count = 0;
+    for i in 9223372036854775805..=9223372036854775807 { count = count + 1; }
+    for i in 9223372036854775800..=9223372036854775807 step 5 { count = count + 1; }
+    println("{count}");
+}
+"#);
+        assert_eq!(output, "5\n");
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let output = run(r#"
+struct Point { x: int, y: int }
+
+func main() {
+    let name = "Ada";
+    let count = 2;
+    let p = Point { x: 1, y: 2 };
+    let maybe: int? = null;
+    println("Hello, {name}! You have {count + 1} items");
+    println("{p} {[1.5, 2.0]} {maybe} {"{count}" + "!"} \{literal\}");
+}
+"#);
+        assert_eq!(output, "Hello, Ada! You have 3 items\nPoint { x: 1, y: 2 } [1.5, 2.0] null 2! {literal}\n");
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let output = run(r#"
+func main() {
+    let flags = 0b1000_0001u8;
+    match flags {
+        0x81u8 => { println("{0xFF + 0o10 + 1_000}"); }
+        _ => { println("no match"); }
+    }
+    println("{flags} {0.5f32}");
+}
+"#);
+        assert_eq!(output, "1263\n129 0.5\n");
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let output = run(r#"
+struct Point { x: int, y: int }
+
+func swap(pair: (int, string)) -> (string, int) {
+    let (n, s) = pair;
+    return (s, n);
+}
+
+func main() {
+    let (word, count) = swap((2, "two"));
+    println(word);
+    let Point { x, y: height } = Point { x: 1, y: 5 };
+    let ((a, _), b) = ((count, 0), x + height);
+    if a + b == 8 {
+        println("eight");
+    }
+    let totals = {"a": 1, "b": 2};
+    let sum = 0;
+    for (key, value) in totals {
+        println(key);
+        sum = sum + value;
+    }
+    if sum == 3 {
+        println("three");
+    }
+}
+"#);
+        assert_eq!(output, "two\neight\na\nb\nthree\n");
+    }
+
+    #[test]
+    fn test_closures_share_assigned_captures() {
+        let output = run(r#"
+func main() {
+    let count = 0;
+    let offset = 10;
+    let add = |n: int| { count = count + n; count + offset };
+    add(1);
+    if add(2) == 13 && count == 3 {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+    }
+
+    #[test]
+    fn test_runtime_error_has_stack_trace() {
+        let mut interpreter = interpreter(r#"func divide(a: int, b: int) -> int {
+    return a / b;
+}
+
+func main() {
+    println("before");
+    divide(1, 0);
+    println("after");
+}
+"#);
+        let report = interpreter.run_main(&[]).unwrap_err();
+        assert_eq!(interpreter.captured_output(), Some("before\n"));
+        assert!(matches!(&report.error, FluxError::Runtime(error) if error.kind == RuntimeErrorKind::DivisionByZero));
+        assert_eq!(report.location.unwrap().start.line, 2);
+        assert_eq!(report.file_path.as_deref(), Some(FILE));
+
+        let trace = report.stack_trace.as_ref().unwrap();
+        let frames: Vec<_> = trace.iter().map(|frame| (frame.function_name.as_str(), frame.line)).collect();
+        assert_eq!(frames, vec![("main", 7), ("divide", 2)]);
+        assert_eq!(trace[0].source_line.as_deref(), Some("divide(1, 0);"));
+    }
+
+    #[test]
+    fn test_index_out_of_bounds_and_stack_overflow() {
+        let mut indexing = interpreter("func main() { let a = [1, 2]; a[2]; }");
+        let report = indexing.run_main(&[]).unwrap_err();
+        assert!(matches!(&report.error, FluxError::IndexOutOfBounds(error) if error.index == 2 && error.length == 2));
+
+        let mut recursion = interpreter("func down(n: int) -> int { return down(n + 1); }\nfunc main() { down(0); }");
+        recursion.set_max_call_depth(50);
+        let report = recursion.run_main(&[]).unwrap_err();
+        assert!(matches!(&report.error, FluxError::Runtime(error) if error.kind == RuntimeErrorKind::StackOverflow));
+
+        let mut zero_step = interpreter("func main() {\n let step = 0;\n for i in 0..3 step step { }\n}");
+        let report = zero_step.run_main(&[]).unwrap_err();
+        assert!(matches!(&report.error, FluxError::Runtime(error) if error.message == "range step cannot be zero"));
+        assert_eq!(report.location.unwrap().start.line, 3);
+    }
+}
@@ -11,11 +11,17 @@ pub mod gc;
 pub mod concurrency;
 pub mod result;
 pub mod error_reporting;
+pub mod interpreter;
+pub mod vm;
+pub mod range;
 
 pub use gc::*;
 pub use concurrency::*;
 pub use result::*;
 pub use error_reporting::*;
+pub use interpreter::*;
+pub use vm::Vm;
+pub use range::IntRange;
 
 /// Core runtime trait
 pub trait Runtime {
@@ -0,0 +1,80 @@
+//! Integer ranges
+//!
+//! Range values as the interpreter and the VM hold them: `start..end` or
+//! `start..=end`, counting by a step that may be negative but never zero.
+
+use std::fmt;
+
+/// Integer range `start..end`, or `start..=end` when inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntRange {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub inclusive: bool,
+}
+
+impl IntRange {
+    /// Create a range, or `None` when the step is zero
+    pub fn new(start: i64, end: i64, step: i64, inclusive: bool) -> Option<Self> {
+        (step != 0).then_some(Self { start, end, step, inclusive })
+    }
+
+    /// Whether counting from the start has not yet passed the end
+    fn includes(&self, value: i64) -> bool {
+        match (self.step > 0, self.inclusive) {
+            (true, false) => value < self.end,
+            (true, true) => value <= self.end,
+            (false, false) => value > self.end,
+            (false, true) => value >= self.end,
+        }
+    }
+
+    /// The values of the range in order; counting stops rather than
+    /// overflowing past `i64::MAX` or `i64::MIN`
+    pub fn iter(&self) -> impl Iterator<Item = i64> {
+        let range = *self;
+        let mut next = Some(range.start);
+        std::iter::from_fn(move || {
+            let value = next.filter(|value| range.includes(*value))?;
+            next = value.checked_add(range.step);
+            Some(value)
+        })
+    }
+}
+
+impl fmt::Display for IntRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.start, if self.inclusive { "..=" } else { ".." }, self.end)?;
+        if self.step != 1 {
+            write!(f, " step {}", self.step)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(start: i64, end: i64, step: i64, inclusive: bool) -> Vec<i64> {
+        IntRange::new(start, end, step, inclusive).unwrap().iter().collect()
+    }
+
+    #[test]
+    fn test_range_values() {
+        assert_eq!(values(0, 4, 1, false), vec![0, 1, 2, 3]);
+        assert_eq!(values(0, 4, 1, true), vec![0, 1, 2, 3, 4]);
+        assert_eq!(values(0, 10, 3, false), vec![0, 3, 6, 9]);
+        assert_eq!(values(10, 0, -4, true), vec![10, 6, 2]);
+        assert!(values(5, 0, 1, false).is_empty());
+        assert_eq!(values(i64::MAX - 1, i64::MAX, 1, true), vec![i64::MAX - 1, i64::MAX]);
+        assert!(IntRange::new(0, 1, 0, false).is_none());
+    }
+
+    #[test]
+    fn test_range_display() {
+        assert_eq!(IntRange::new(0, 10, 1, false).unwrap().to_string(), "0..10");
+        assert_eq!(IntRange::new(10, 0, -2, true).unwrap().to_string(), "10..=0 step -2");
+    }
+}
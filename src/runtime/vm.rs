@@ -0,0 +1,1671 @@
+//! Stack virtual machine for Flux bytecode
+//!
+//! Runs the `BytecodeProgram`s produced by the bytecode compiler, as a
+//! portable alternative to native code. Heap values are allocated by the
+//! runtime's `GarbageCollector` and collected from the VM's own roots: the
+//! stacks of every goroutine, the module globals and the constant pool.
+//!
+//! Goroutines started with `go` are hosted by the runtime `Scheduler`:
+//! it queues them and decides whose turn it is, while the VM keeps each
+//! goroutine's stack and runs its instructions, switching after a fixed
+//! number of them. A program ends once every goroutine has finished.
+
+use crate::codegen::bytecode::{Builtin, BytecodeProgram, Constant, Instruction, VariantLayout, BUILTIN_MODULE};
+use crate::position::Span;
+use crate::runtime::concurrency::{Scheduler, SchedulerStats};
+use crate::runtime::error_reporting::{ErrorReport, ErrorReporter, StackFrame};
+use crate::runtime::gc::{GarbageCollector, GcConfig, MemorySummary, Trace, Tracer};
+use crate::runtime::range::IntRange;
+use crate::runtime::GoroutineId;
+use crate::runtime::result::{FluxError, IndexError, RuntimeError, RuntimeErrorKind};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::ptr::NonNull;
+use std::time::Instant;
+
+/// Call depth at which a stack overflow is reported by default
+const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+/// Heap size in bytes at which the first collection runs by default
+const DEFAULT_GC_THRESHOLD: usize = 1024 * 1024;
+
+/// Instructions a goroutine runs before the next one gets its turn
+const TIME_SLICE: usize = 1_000;
+
+/// Value on a VM stack, in a global or inside a heap object
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Unit,
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Function(u32),
+    Builtin(Builtin),
+    /// Constructor of a variant with a payload
+    Constructor(u32),
+    Object(Gc),
+}
+
+/// Pointer to an object owned by the garbage collector. Objects are only
+/// freed by collections, which run between instructions and keep everything
+/// reachable from the VM's roots, so a pointer read from a root stays valid
+/// until the instruction using it completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Gc(NonNull<HeapObject>);
+
+impl Gc {
+    fn get<'a>(self) -> &'a HeapObject {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+/// Heap-allocated value. Structs and variants refer to their layout in the
+/// program's type and variant tables.
+#[derive(Debug)]
+enum HeapObject {
+    String(Box<str>),
+    Array(RefCell<Vec<Value>>),
+    /// Map entries in insertion order
+    Map(RefCell<Vec<(Value, Value)>>),
+    Tuple(Box<[Value]>),
+    Range(IntRange),
+    Struct { layout: u32, fields: RefCell<Vec<Value>> },
+    Variant { layout: u32, fields: Box<[Value]> },
+    Closure { function: u32, captures: Box<[Value]> },
+    /// Method together with the receiver it was read from
+    BoundMethod { receiver: Value, function: u32 },
+    /// Local shared with the closures that capture it by reference
+    Cell(Cell<Value>),
+}
+
+impl Trace for HeapObject {
+    fn trace(&self, tracer: &mut Tracer) {
+        let mut mark = |value: &Value| {
+            if let Value::Object(object) = value {
+                tracer.mark(object.0);
+            }
+        };
+        match self {
+            HeapObject::String(_) | HeapObject::Range(_) => {}
+            HeapObject::Array(elements) => elements.borrow().iter().for_each(mark),
+            HeapObject::Map(entries) => {
+                for (key, value) in entries.borrow().iter() {
+                    mark(key);
+                    mark(value);
+                }
+            }
+            HeapObject::Struct { fields, .. } => fields.borrow().iter().for_each(mark),
+            HeapObject::Tuple(values)
+            | HeapObject::Variant { fields: values, .. }
+            | HeapObject::Closure { captures: values, .. } => values.iter().for_each(mark),
+            HeapObject::BoundMethod { receiver, .. } => mark(receiver),
+            HeapObject::Cell(cell) => mark(&cell.get()),
+        }
+    }
+}
+
+/// Active call of a function
+struct Frame {
+    function: u32,
+    /// Offset of the next instruction
+    pc: usize,
+    /// Stack index of local slot 0
+    base: usize,
+    /// Stack length to restore when the call returns, dropping the callee
+    /// and its arguments
+    return_to: usize,
+    /// Closure whose captures the function reads
+    closure: Option<Gc>,
+}
+
+/// Goroutine: its own operand stack and call frames
+struct Fiber {
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    /// Argument count of the call the goroutine starts with, made on its
+    /// first turn; the callee and arguments are already on its stack
+    start: Option<u8>,
+}
+
+impl Fiber {
+    fn new(stack: Vec<Value>, start: Option<u8>) -> Self {
+        Self { stack, frames: Vec::new(), start }
+    }
+}
+
+/// Where `print` and `println` write
+enum Output {
+    Stdout,
+    Captured(String),
+}
+
+type Step<T> = Result<T, FluxError>;
+
+/// Virtual machine running one bytecode program
+pub struct Vm {
+    program: BytecodeProgram,
+    /// Methods by the module and name of the receiver type and method name
+    methods: HashMap<(u32, String, String), u32>,
+    /// Layouts `Ok` and `Err` values are built with
+    ok_layout: u32,
+    err_layout: u32,
+    constants: Vec<Value>,
+    globals: Vec<Value>,
+    gc: GarbageCollector,
+    gc_threshold: usize,
+    /// Heap size at which the next collection runs
+    next_collection: usize,
+    collect_pending: bool,
+    /// Goroutine whose instructions are running
+    fiber: Fiber,
+    /// Goroutines waiting for their turn, by their id in the scheduler
+    waiting: HashMap<GoroutineId, Fiber>,
+    scheduler: Scheduler,
+    reporter: ErrorReporter,
+    output: Output,
+    max_call_depth: usize,
+}
+
+impl Vm {
+    /// Create a VM for `program`
+    pub fn new(mut program: BytecodeProgram) -> Self {
+        let methods = program.methods.iter()
+            .map(|method| ((method.module, method.type_name.clone(), method.name.clone()), method.function))
+            .collect();
+        let ok_layout = result_layout(&mut program, "Ok");
+        let err_layout = result_layout(&mut program, "Err");
+        let globals = vec![Value::Unit; program.globals.len()];
+
+        Self {
+            program,
+            methods,
+            ok_layout,
+            err_layout,
+            constants: Vec::new(),
+            globals,
+            gc: GarbageCollector::with_config(GcConfig { auto_gc_enabled: false, ..GcConfig::default() }),
+            gc_threshold: DEFAULT_GC_THRESHOLD,
+            next_collection: DEFAULT_GC_THRESHOLD,
+            collect_pending: false,
+            fiber: Fiber::new(Vec::new(), None),
+            waiting: HashMap::new(),
+            scheduler: Scheduler::with_worker_count(0),
+            reporter: ErrorReporter::new(),
+            output: Output::Stdout,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+
+    /// Collect `print` output instead of writing it to stdout
+    pub fn capture_output(&mut self) {
+        self.output = Output::Captured(String::new());
+    }
+
+    /// Output collected since `capture_output` was called
+    pub fn captured_output(&self) -> Option<&str> {
+        match &self.output {
+            Output::Captured(output) => Some(output),
+            Output::Stdout => None,
+        }
+    }
+
+    /// Take the output captured so far, leaving the buffer empty
+    pub fn take_output(&mut self) -> String {
+        match &mut self.output {
+            Output::Captured(output) => std::mem::take(output),
+            Output::Stdout => String::new(),
+        }
+    }
+
+    /// Set the call depth at which a stack overflow is reported
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Set the heap size in bytes at which garbage is collected; the
+    /// threshold grows with the heap that survives each collection
+    pub fn set_gc_threshold(&mut self, bytes: usize) {
+        self.gc_threshold = bytes;
+        self.next_collection = bytes;
+    }
+
+    /// Register a source file so runtime errors can show the failing line
+    pub fn add_source_file(&mut self, path: PathBuf, content: String) {
+        self.reporter.add_source_file(path, content);
+    }
+
+    /// Memory used by the program's heap
+    pub fn memory_summary(&self) -> MemorySummary {
+        self.gc.memory_summary()
+    }
+
+    /// Goroutines started and finished so far
+    pub fn scheduler_stats(&self) -> SchedulerStats {
+        self.scheduler.stats()
+    }
+
+    /// Evaluate the module constants, then run `main` and every goroutine it
+    /// starts to completion. `args` are passed as a `[string]` if `main`
+    /// takes a parameter.
+    pub fn run_main(&mut self, args: &[String]) -> Result<(), Box<ErrorReport>> {
+        let Some(entry) = self.program.entry else {
+            let error = runtime_error(RuntimeErrorKind::Panic, "program has no `main` function");
+            return Err(Box::new(self.reporter.generate_report(error, None, None)));
+        };
+        if let Err(error) = self.load_constants() {
+            return Err(self.report(error));
+        }
+
+        for initializer in self.program.initializers.clone() {
+            self.spawn(vec![Value::Function(initializer)], 0);
+            self.run_until_idle()?;
+        }
+
+        let mut stack = vec![Value::Function(entry)];
+        if self.program.functions.get(entry as usize).is_some_and(|main| main.arity > 0) {
+            match self.string_array(args) {
+                Ok(array) => stack.push(array),
+                Err(error) => return Err(self.report(error)),
+            }
+        }
+        let argc = (stack.len() - 1) as u8;
+        self.spawn(stack, argc);
+        self.run_until_idle()
+    }
+
+    fn load_constants(&mut self) -> Step<()> {
+        for index in self.constants.len()..self.program.constants.len() {
+            let value = match self.program.constants[index].clone() {
+                Constant::Int(value) => Value::Int(value),
+                Constant::Float(value) => Value::Float(value),
+                Constant::Char(value) => Value::Char(value),
+                Constant::String(value) => self.alloc(HeapObject::String(value.into()))?,
+            };
+            self.constants.push(value);
+        }
+        Ok(())
+    }
+
+    fn string_array(&mut self, strings: &[String]) -> Step<Value> {
+        let mut elements = Vec::with_capacity(strings.len());
+        for string in strings {
+            elements.push(self.alloc(HeapObject::String(string.as_str().into()))?);
+        }
+        self.alloc(HeapObject::Array(RefCell::new(elements)))
+    }
+
+    // Scheduling
+
+    /// Queue a goroutine that calls the callee at the bottom of `stack`
+    fn spawn(&mut self, stack: Vec<Value>, argc: u8) {
+        let id = self.scheduler.add_hosted();
+        self.waiting.insert(id, Fiber::new(stack, Some(argc)));
+    }
+
+    /// Give goroutines turns in order until all of them have finished
+    fn run_until_idle(&mut self) -> Result<(), Box<ErrorReport>> {
+        while let Some(id) = self.scheduler.next_ready() {
+            let Some(next) = self.waiting.remove(&id) else {
+                continue;
+            };
+            self.fiber = next;
+            let started = Instant::now();
+            let result = self.run_slice();
+            self.scheduler.record_execution_time(started.elapsed());
+
+            match result {
+                Ok(true) => self.scheduler.finish_current(),
+                Ok(false) => {
+                    let fiber = std::mem::replace(&mut self.fiber, Fiber::new(Vec::new(), None));
+                    self.waiting.insert(id, fiber);
+                    self.scheduler.yield_now();
+                }
+                Err(error) => {
+                    let report = self.report(error);
+                    self.waiting.clear();
+                    self.scheduler.clear();
+                    return Err(report);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the current goroutine for one time slice; returns whether it finished
+    fn run_slice(&mut self) -> Step<bool> {
+        if let Some(argc) = self.fiber.start.take() {
+            self.call(argc)?;
+        }
+
+        for _ in 0..TIME_SLICE {
+            if self.collect_pending {
+                self.collect_garbage();
+            }
+            let Some(frame) = self.fiber.frames.last_mut() else {
+                return Ok(true);
+            };
+            let code = &self.program.functions[frame.function as usize].code;
+            let Some(&instruction) = code.get(frame.pc) else {
+                return Err(internal("execution ran past the end of a function"));
+            };
+            frame.pc += 1;
+            self.execute(instruction)?;
+        }
+        Ok(self.fiber.frames.is_empty())
+    }
+
+    // Memory
+
+    fn alloc(&mut self, object: HeapObject) -> Step<Value> {
+        let object = self.gc.allocate_traced(object)
+            .map_err(|error| runtime_error(RuntimeErrorKind::OutOfMemory, &error.to_string()))?;
+        // Collecting here could free values the caller has taken off the
+        // stack, so collection waits for the next instruction boundary
+        if self.gc.heap_stats().current_allocated >= self.next_collection {
+            self.collect_pending = true;
+        }
+        Ok(Value::Object(Gc(object)))
+    }
+
+    fn collect_garbage(&mut self) {
+        let mut roots = Vec::new();
+        let mut add = |value: &Value| {
+            if let Value::Object(object) = value {
+                roots.push(object.0);
+            }
+        };
+        for fiber in std::iter::once(&self.fiber).chain(self.waiting.values()) {
+            fiber.stack.iter().for_each(&mut add);
+            for frame in &fiber.frames {
+                if let Some(closure) = frame.closure {
+                    add(&Value::Object(closure));
+                }
+            }
+        }
+        self.globals.iter().for_each(&mut add);
+        self.constants.iter().for_each(&mut add);
+
+        self.gc.collect_from(roots);
+        self.collect_pending = false;
+        self.next_collection = (self.gc.heap_stats().current_allocated * 2).max(self.gc_threshold);
+    }
+
+    // Stack
+
+    fn push(&mut self, value: Value) {
+        self.fiber.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.fiber.stack.pop().unwrap_or(Value::Unit)
+    }
+
+    /// Pop the top `count` values, in the order they were pushed
+    fn take(&mut self, count: usize) -> Step<Vec<Value>> {
+        let start = self.fiber.stack.len().checked_sub(count).ok_or_else(|| internal("stack underflow"))?;
+        Ok(self.fiber.stack.split_off(start))
+    }
+
+    fn frame(&self) -> &Frame {
+        &self.fiber.frames[self.fiber.frames.len() - 1]
+    }
+
+    fn slot(&self, index: u16) -> usize {
+        self.frame().base + index as usize
+    }
+
+    /// Value captured by the running closure, cell included
+    fn captured(&self, index: u16) -> Step<Value> {
+        if let Some(HeapObject::Closure { captures, .. }) = self.frame().closure.map(Gc::get) {
+            if let Some(value) = captures.get(index as usize) {
+                return Ok(*value);
+            }
+        }
+        Err(internal(&format!("capture {} out of range", index)))
+    }
+
+    fn constant_name(&self, index: u32) -> Step<String> {
+        match self.program.constants.get(index as usize) {
+            Some(Constant::String(name)) => Ok(name.clone()),
+            _ => Err(internal(&format!("constant {} is not a name", index))),
+        }
+    }
+
+    fn jump(&mut self, target: u32) {
+        if let Some(frame) = self.fiber.frames.last_mut() {
+            frame.pc = target as usize;
+        }
+    }
+
+    fn condition(&mut self) -> Step<bool> {
+        match self.pop() {
+            Value::Bool(value) => Ok(value),
+            other => Err(panic(&format!("condition evaluated to `{}` instead of a bool", self.display(other)))),
+        }
+    }
+
+    // Instructions
+
+    fn execute(&mut self, instruction: Instruction) -> Step<()> {
+        match instruction {
+            Instruction::Constant(index) => {
+                let value = *self.constants.get(index as usize).ok_or_else(|| internal("constant out of range"))?;
+                self.push(value);
+            }
+            Instruction::Unit => self.push(Value::Unit),
+            Instruction::Null => self.push(Value::Null),
+            Instruction::True => self.push(Value::Bool(true)),
+            Instruction::False => self.push(Value::Bool(false)),
+            Instruction::Pop => {
+                self.pop();
+            }
+            Instruction::Dup => {
+                let value = self.fiber.stack.last().copied().unwrap_or(Value::Unit);
+                self.push(value);
+            }
+
+            Instruction::LoadLocal(index) => {
+                let value = self.fiber.stack[self.slot(index)];
+                self.push(read_cell(value));
+            }
+            Instruction::StoreLocal(index) => {
+                let value = self.pop();
+                let slot = self.slot(index);
+                match cell(self.fiber.stack[slot]) {
+                    Some(cell) => cell.set(value),
+                    None => self.fiber.stack[slot] = value,
+                }
+            }
+            Instruction::DefineLocal(index) => {
+                let value = self.pop();
+                let slot = self.slot(index);
+                self.fiber.stack[slot] = value;
+            }
+            Instruction::BoxLocal(index) => {
+                let slot = self.slot(index);
+                let boxed = self.alloc(HeapObject::Cell(Cell::new(self.fiber.stack[slot])))?;
+                self.fiber.stack[slot] = boxed;
+            }
+            Instruction::CaptureLocal(index) => {
+                let value = self.fiber.stack[self.slot(index)];
+                self.push(value);
+            }
+            Instruction::LoadCapture(index) => {
+                let value = self.captured(index)?;
+                self.push(read_cell(value));
+            }
+            Instruction::StoreCapture(index) => {
+                let value = self.pop();
+                match cell(self.captured(index)?) {
+                    Some(cell) => cell.set(value),
+                    None => return Err(internal(&format!("capture {} is not captured by reference", index))),
+                }
+            }
+            Instruction::CaptureCapture(index) => {
+                let value = self.captured(index)?;
+                self.push(value);
+            }
+            Instruction::LoadGlobal(index) => {
+                let value = *self.globals.get(index as usize).ok_or_else(|| internal("global out of range"))?;
+                self.push(value);
+            }
+            Instruction::StoreGlobal(index) => {
+                let value = self.pop();
+                let global = self.globals.get_mut(index as usize).ok_or_else(|| internal("global out of range"))?;
+                *global = value;
+            }
+            Instruction::Function(index) => self.push(Value::Function(index)),
+            Instruction::Builtin(builtin) => self.push(Value::Builtin(builtin)),
+            Instruction::Constructor(index) => self.push(Value::Constructor(index)),
+
+            Instruction::Add
+            | Instruction::Subtract
+            | Instruction::Multiply
+            | Instruction::Divide
+            | Instruction::Modulo
+            | Instruction::BitAnd
+            | Instruction::BitOr
+            | Instruction::BitXor
+            | Instruction::ShiftLeft
+            | Instruction::ShiftRight
+            | Instruction::Equal
+            | Instruction::NotEqual
+            | Instruction::Less
+            | Instruction::LessEqual
+            | Instruction::Greater
+            | Instruction::GreaterEqual => {
+                let right = self.pop();
+                let left = self.pop();
+                let result = self.binary(instruction, left, right)?;
+                self.push(result);
+            }
+            Instruction::Negate | Instruction::Not | Instruction::BitNot => {
+                let operand = self.pop();
+                let result = match (instruction, operand) {
+                    (Instruction::Negate, Value::Int(value)) => match value.checked_neg() {
+                        Some(negated) => Value::Int(negated),
+                        None => return Err(panic(&format!("integer overflow negating {}", value))),
+                    },
+                    (Instruction::Negate, Value::Float(value)) => Value::Float(-value),
+                    (Instruction::Not, Value::Bool(value)) => Value::Bool(!value),
+                    (Instruction::BitNot, Value::Int(value)) => Value::Int(!value),
+                    _ => {
+                        let message = format!("unsupported operand `{}` for {}", self.display(operand), instruction.mnemonic());
+                        return Err(panic(&message));
+                    }
+                };
+                self.push(result);
+            }
+
+            Instruction::Jump(target) => self.jump(target),
+            Instruction::JumpIfFalse(target) => {
+                if !self.condition()? {
+                    self.jump(target);
+                }
+            }
+            Instruction::JumpIfTrue(target) => {
+                if self.condition()? {
+                    self.jump(target);
+                }
+            }
+            Instruction::Call(argc) => self.call(argc)?,
+            Instruction::Return => self.return_value(),
+            Instruction::Try => {
+                let value = self.pop();
+                // `?` unwraps `Ok` and returns `Err` from the enclosing function
+                let result = self.variant_layout(value).filter(|layout| layout.enum_name == "Result");
+                match result.map(|layout| layout.name == "Ok") {
+                    Some(true) => {
+                        let payload = elements(value).and_then(|fields| fields.first().copied());
+                        self.push(payload.unwrap_or(Value::Unit));
+                    }
+                    Some(false) => {
+                        self.push(value);
+                        self.return_value();
+                    }
+                    None => {
+                        let message = format!("unsupported operand `{}` for Try", self.display(value));
+                        return Err(panic(&message));
+                    }
+                }
+            }
+            Instruction::Go(argc) => {
+                let callee = self.fiber.stack.len().checked_sub(argc as usize + 1).ok_or_else(|| internal("stack underflow"))?;
+                let stack = self.fiber.stack.split_off(callee);
+                self.spawn(stack, argc);
+            }
+            Instruction::MatchFailed => {
+                let value = self.pop();
+                return Err(panic(&format!("no match arm matched `{}`", self.display(value))));
+            }
+            Instruction::Unwrap => {
+                if matches!(self.fiber.stack.last(), Some(Value::Null)) {
+                    return Err(panic("unwrapped a null value"));
+                }
+            }
+
+            Instruction::Array(count) => {
+                let elements = self.take(count as usize)?;
+                let array = self.alloc(HeapObject::Array(RefCell::new(elements)))?;
+                self.push(array);
+            }
+            Instruction::Map(count) => {
+                let values = self.take(count as usize * 2)?;
+                let mut entries: Vec<(Value, Value)> = Vec::with_capacity(count as usize);
+                for pair in values.chunks(2) {
+                    match entries.iter_mut().find(|(existing, _)| values_equal(*existing, pair[0])) {
+                        Some((_, slot)) => *slot = pair[1],
+                        None => entries.push((pair[0], pair[1])),
+                    }
+                }
+                let map = self.alloc(HeapObject::Map(RefCell::new(entries)))?;
+                self.push(map);
+            }
+            Instruction::Tuple(count) => {
+                let elements = self.take(count as usize)?;
+                let tuple = self.alloc(HeapObject::Tuple(elements.into()))?;
+                self.push(tuple);
+            }
+            Instruction::Concat(count) => {
+                let mut text = String::new();
+                for value in self.take(count as usize)? {
+                    self.write_value(value, &mut text);
+                }
+                let string = self.alloc(HeapObject::String(text.into()))?;
+                self.push(string);
+            }
+            Instruction::Range | Instruction::RangeInclusive => {
+                let bounds = self.take(3)?;
+                let [Value::Int(start), Value::Int(end), Value::Int(step)] = bounds[..] else {
+                    return Err(internal("range bounds that are not ints"));
+                };
+                let inclusive = instruction == Instruction::RangeInclusive;
+                let range = IntRange::new(start, end, step, inclusive).ok_or_else(|| panic("range step cannot be zero"))?;
+                let range = self.alloc(HeapObject::Range(range))?;
+                self.push(range);
+            }
+            Instruction::Struct(layout) => {
+                let count = self.program.types.get(layout as usize).ok_or_else(|| internal("type out of range"))?.fields.len();
+                let fields = self.take(count)?;
+                let instance = self.alloc(HeapObject::Struct { layout, fields: RefCell::new(fields) })?;
+                self.push(instance);
+            }
+            Instruction::Variant(layout) => {
+                let count = self.program.variants.get(layout as usize).ok_or_else(|| internal("variant out of range"))?.fields.len();
+                let fields = self.take(count)?;
+                let variant = self.alloc(HeapObject::Variant { layout, fields: fields.into() })?;
+                self.push(variant);
+            }
+            Instruction::Closure(function) => {
+                let count = self.program.functions.get(function as usize).ok_or_else(|| internal("function out of range"))?.captures;
+                let captures = self.take(count as usize)?;
+                let closure = self.alloc(HeapObject::Closure { function, captures: captures.into() })?;
+                self.push(closure);
+            }
+            Instruction::GetField(name) => {
+                let name = self.constant_name(name)?;
+                let object = self.pop();
+                let value = self.field(object, &name)?;
+                self.push(value);
+            }
+            Instruction::SetField(name) => {
+                let name = self.constant_name(name)?;
+                let value = self.pop();
+                let object = self.pop();
+                if let Value::Object(instance) = object {
+                    if let HeapObject::Struct { layout, fields } = instance.get() {
+                        if let Some(index) = self.program.types[*layout as usize].fields.iter().position(|field| *field == name) {
+                            fields.borrow_mut()[index] = value;
+                            return Ok(());
+                        }
+                    }
+                }
+                return Err(panic(&format!("value `{}` has no field `{}`", self.display(object), name)));
+            }
+            Instruction::GetIndex => {
+                let index = self.pop();
+                let collection = self.pop();
+                let value = self.index(collection, index)?;
+                self.push(value);
+            }
+            Instruction::SetIndex => {
+                let value = self.pop();
+                let index = self.pop();
+                let collection = self.pop();
+                self.set_index(collection, index, value)?;
+            }
+            Instruction::Iterate => {
+                let collection = self.pop();
+                let elements = match collection {
+                    Value::Object(object) => match object.get() {
+                        HeapObject::Array(elements) => Some(elements.borrow().clone()),
+                        HeapObject::Map(entries) => {
+                            // A map gives its entries as `(key, value)` tuples
+                            let entries = entries.borrow().clone();
+                            let mut pairs = Vec::with_capacity(entries.len());
+                            for (key, value) in entries {
+                                pairs.push(self.alloc(HeapObject::Tuple(Box::new([key, value])))?);
+                            }
+                            Some(pairs)
+                        }
+                        HeapObject::Range(range) => Some(range.iter().map(Value::Int).collect()),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let Some(elements) = elements else {
+                    return Err(panic(&format!("value `{}` is not iterable", self.display(collection))));
+                };
+                let array = self.alloc(HeapObject::Array(RefCell::new(elements)))?;
+                self.push(array);
+            }
+            Instruction::Length => {
+                let length = match self.pop() {
+                    Value::Object(object) => match object.get() {
+                        HeapObject::Array(elements) => elements.borrow().len(),
+                        _ => return Err(internal("length of a value that is not an array")),
+                    },
+                    _ => return Err(internal("length of a value that is not an array")),
+                };
+                self.push(Value::Int(length as i64));
+            }
+            Instruction::Element(index) => {
+                let element = elements(self.pop()).and_then(|values| values.get(index as usize).copied());
+                self.push(element.ok_or_else(|| internal(&format!("element {} out of range", index)))?);
+            }
+            Instruction::IsTuple(length) => {
+                let is_tuple = match self.pop() {
+                    Value::Object(object) => matches!(object.get(), HeapObject::Tuple(values) if values.len() == length as usize),
+                    _ => false,
+                };
+                self.push(Value::Bool(is_tuple));
+            }
+            Instruction::IsVariant(name) => {
+                let name = self.constant_name(name)?;
+                let value = self.pop();
+                let is_variant = self.variant_layout(value).is_some_and(|layout| layout.name == name);
+                self.push(Value::Bool(is_variant));
+            }
+            Instruction::IsInstance(name) => {
+                let name = self.constant_name(name)?;
+                let value = self.pop();
+                let variant_name = name.rsplit("::").next().unwrap_or(&name);
+                let is_instance = match value {
+                    Value::Object(object) => match object.get() {
+                        HeapObject::Struct { layout, .. } => self.program.types[*layout as usize].name == name,
+                        HeapObject::Variant { layout, .. } => self.program.variants[*layout as usize].name == variant_name,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                self.push(Value::Bool(is_instance));
+            }
+        }
+        Ok(())
+    }
+
+    /// Call the callee below the top `argc` values
+    fn call(&mut self, argc: u8) -> Step<()> {
+        let argc = argc as usize;
+        let callee_index = self.fiber.stack.len().checked_sub(argc + 1).ok_or_else(|| internal("stack underflow"))?;
+        let callee = self.fiber.stack[callee_index];
+        match callee {
+            Value::Function(function) => self.enter(function, callee_index + 1, callee_index, None, argc),
+            Value::Builtin(builtin) => {
+                let args = self.take(argc)?;
+                self.pop();
+                let result = self.call_builtin(builtin, &args)?;
+                self.push(result);
+                Ok(())
+            }
+            Value::Constructor(layout) => {
+                let fields = self.take(argc)?;
+                self.pop();
+                let variant = self.alloc(HeapObject::Variant { layout, fields: fields.into() })?;
+                self.push(variant);
+                Ok(())
+            }
+            Value::Object(object) => match object.get() {
+                HeapObject::Closure { function, .. } => self.enter(*function, callee_index + 1, callee_index, Some(object), argc),
+                HeapObject::BoundMethod { receiver, function } => {
+                    // The receiver takes the callee's place as the first argument
+                    self.fiber.stack[callee_index] = *receiver;
+                    self.enter(*function, callee_index, callee_index, None, argc + 1)
+                }
+                _ => Err(panic(&format!("value `{}` is not callable", self.display(callee)))),
+            },
+            _ => Err(panic(&format!("value `{}` is not callable", self.display(callee)))),
+        }
+    }
+
+    /// Push a frame for a function whose arguments start at `base`
+    fn enter(&mut self, function: u32, base: usize, return_to: usize, closure: Option<Gc>, argc: usize) -> Step<()> {
+        let definition = self.program.functions.get(function as usize).ok_or_else(|| internal("function out of range"))?;
+        if self.fiber.frames.len() >= self.max_call_depth {
+            let message = format!("maximum call depth of {} exceeded in `{}`", self.max_call_depth, definition.name);
+            return Err(runtime_error(RuntimeErrorKind::StackOverflow, &message));
+        }
+        if argc != definition.arity as usize {
+            let message = format!("`{}` expects {} arguments but got {}", definition.name, definition.arity, argc);
+            return Err(panic(&message));
+        }
+
+        let locals = (definition.locals as usize).max(argc);
+        self.fiber.stack.resize(base + locals, Value::Unit);
+        self.fiber.frames.push(Frame { function, pc: 0, base, return_to, closure });
+        Ok(())
+    }
+
+    /// Return the top value from the running function
+    fn return_value(&mut self) {
+        let result = self.pop();
+        if let Some(frame) = self.fiber.frames.pop() {
+            self.fiber.stack.truncate(frame.return_to);
+        }
+        self.push(result);
+    }
+
+    fn call_builtin(&mut self, builtin: Builtin, args: &[Value]) -> Step<Value> {
+        match builtin {
+            Builtin::Print | Builtin::Println => {
+                let mut text = String::new();
+                for arg in args {
+                    self.write_value(*arg, &mut text);
+                }
+                if builtin == Builtin::Println {
+                    text.push('\n');
+                }
+                match &mut self.output {
+                    Output::Captured(output) => output.push_str(&text),
+                    Output::Stdout => {
+                        let mut stdout = std::io::stdout();
+                        if stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush()).is_err() {
+                            return Err(panic("failed to write to stdout"));
+                        }
+                    }
+                }
+                Ok(Value::Unit)
+            }
+            // `T?` values are represented by the value itself or `null`
+            Builtin::Some => Ok(args.last().copied().unwrap_or(Value::Null)),
+            Builtin::Ok | Builtin::Err => {
+                let layout = if builtin == Builtin::Ok { self.ok_layout } else { self.err_layout };
+                self.alloc(HeapObject::Variant { layout, fields: args.into() })
+            }
+        }
+    }
+
+    fn binary(&mut self, op: Instruction, left: Value, right: Value) -> Step<Value> {
+        match op {
+            Instruction::Equal => return Ok(Value::Bool(values_equal(left, right))),
+            Instruction::NotEqual => return Ok(Value::Bool(!values_equal(left, right))),
+            _ => {}
+        }
+
+        let result = match (left, right) {
+            (Value::Int(a), Value::Int(b)) => int_op(a, op, b)?,
+            (Value::Float(a), Value::Float(b)) => float_op(a, op, b),
+            (Value::Char(a), Value::Char(b)) => compare(a.cmp(&b), op),
+            (Value::Object(a), Value::Object(b)) => match (a.get(), b.get()) {
+                (HeapObject::String(a), HeapObject::String(b)) => match op {
+                    Instruction::Add => Some(self.alloc(HeapObject::String(format!("{}{}", a, b).into()))?),
+                    _ => compare(a.cmp(b), op),
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        result.ok_or_else(|| {
+            panic(&format!("unsupported operands `{}` and `{}` for {}", self.display(left), self.display(right), op.mnemonic()))
+        })
+    }
+
+    fn field(&mut self, object: Value, name: &str) -> Step<Value> {
+        if let Value::Object(instance) = object {
+            let field = match instance.get() {
+                HeapObject::Struct { layout, fields } => {
+                    let index = self.program.types[*layout as usize].fields.iter().position(|field| field == name);
+                    index.map(|index| fields.borrow()[index])
+                }
+                HeapObject::Variant { layout, fields } => {
+                    let index = self.program.variants[*layout as usize].fields.iter().position(|field| field == name);
+                    index.and_then(|index| fields.get(index).copied())
+                }
+                _ => None,
+            };
+            if let Some(field) = field {
+                return Ok(field);
+            }
+        }
+
+        let method = self.method_owner(object)
+            .and_then(|(module, type_name)| self.methods.get(&(module, type_name.to_string(), name.to_string())));
+        match method {
+            Some(&function) => self.alloc(HeapObject::BoundMethod { receiver: object, function }),
+            None => Err(panic(&format!("`{}` has no field or method `{}`", self.type_name(object), name))),
+        }
+    }
+
+    /// Module and name of the declared type a value is an instance of
+    fn method_owner(&self, value: Value) -> Option<(u32, &str)> {
+        let Value::Object(object) = value else {
+            return None;
+        };
+        match object.get() {
+            HeapObject::Struct { layout, .. } => {
+                let layout = &self.program.types[*layout as usize];
+                Some((layout.module, &layout.name))
+            }
+            HeapObject::Variant { layout, .. } => {
+                let layout = &self.program.variants[*layout as usize];
+                Some((layout.module, &layout.enum_name))
+            }
+            HeapObject::Cell(cell) => self.method_owner(cell.get()),
+            _ => None,
+        }
+    }
+
+    fn index(&self, collection: Value, index: Value) -> Step<Value> {
+        if let Value::Object(object) = collection {
+            match object.get() {
+                HeapObject::Array(elements) => {
+                    let elements = elements.borrow();
+                    let position = self.array_position(index, elements.len())?;
+                    return Ok(elements[position]);
+                }
+                HeapObject::Map(entries) => {
+                    return match entries.borrow().iter().find(|(key, _)| values_equal(*key, index)) {
+                        Some((_, value)) => Ok(*value),
+                        None => Err(panic(&format!("key `{}` not found in map", self.display(index)))),
+                    };
+                }
+                HeapObject::String(text) => {
+                    let position = self.array_position(index, text.chars().count())?;
+                    return Ok(Value::Char(text.chars().nth(position).unwrap_or_default()));
+                }
+                _ => {}
+            }
+        }
+        Err(panic(&format!("value `{}` cannot be indexed", self.display(collection))))
+    }
+
+    fn set_index(&self, collection: Value, index: Value, value: Value) -> Step<()> {
+        if let Value::Object(object) = collection {
+            match object.get() {
+                HeapObject::Array(elements) => {
+                    let position = self.array_position(index, elements.borrow().len())?;
+                    elements.borrow_mut()[position] = value;
+                    return Ok(());
+                }
+                HeapObject::Map(entries) => {
+                    let mut entries = entries.borrow_mut();
+                    match entries.iter_mut().find(|(key, _)| values_equal(*key, index)) {
+                        Some((_, slot)) => *slot = value,
+                        None => entries.push((index, value)),
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err(panic(&format!("value `{}` cannot be indexed", self.display(collection))))
+    }
+
+    fn array_position(&self, index: Value, length: usize) -> Step<usize> {
+        let Value::Int(index) = index else {
+            return Err(panic(&format!("index `{}` is not an int", self.display(index))));
+        };
+        match usize::try_from(index) {
+            Ok(position) if position < length => Ok(position),
+            _ => Err(FluxError::IndexOutOfBounds(IndexError {
+                message: "array index out of range".to_string(),
+                index,
+                length,
+            })),
+        }
+    }
+
+    fn variant_layout(&self, value: Value) -> Option<&VariantLayout> {
+        match value {
+            Value::Object(object) => match object.get() {
+                HeapObject::Variant { layout, .. } => self.program.variants.get(*layout as usize),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Name of the type methods are looked up on
+    fn type_name(&self, value: Value) -> String {
+        let name = match value {
+            Value::Unit => "()",
+            Value::Null => "null",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::Function(_) | Value::Builtin(_) | Value::Constructor(_) => "function",
+            Value::Object(object) => match object.get() {
+                HeapObject::String(_) => "string",
+                HeapObject::Array(_) => "array",
+                HeapObject::Map(_) => "map",
+                HeapObject::Tuple(_) => "tuple",
+                HeapObject::Range(_) => "Range",
+                HeapObject::Struct { layout, .. } => &self.program.types[*layout as usize].name,
+                HeapObject::Variant { layout, .. } => &self.program.variants[*layout as usize].enum_name,
+                HeapObject::Closure { .. } | HeapObject::BoundMethod { .. } => "function",
+                HeapObject::Cell(cell) => return self.type_name(cell.get()),
+            },
+        };
+        name.to_string()
+    }
+
+    fn display(&self, value: Value) -> String {
+        let mut text = String::new();
+        self.write_value(value, &mut text);
+        text
+    }
+
+    /// Append a value's text as `print` shows it
+    fn write_value(&self, value: Value, out: &mut String) {
+        let object = match value {
+            Value::Unit => return out.push_str("()"),
+            Value::Null => return out.push_str("null"),
+            Value::Int(value) => return write!(out, "{}", value).unwrap_or_default(),
+            Value::Float(value) => return write!(out, "{:?}", value).unwrap_or_default(),
+            Value::Bool(value) => return write!(out, "{}", value).unwrap_or_default(),
+            Value::Char(value) => return out.push(value),
+            Value::Function(_) | Value::Builtin(_) | Value::Constructor(_) => return out.push_str("<function>"),
+            Value::Object(object) => object.get(),
+        };
+
+        match object {
+            HeapObject::String(text) => out.push_str(text),
+            HeapObject::Array(elements) => self.write_list("[", &elements.borrow(), "]", out),
+            HeapObject::Map(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.write_value(*key, out);
+                    out.push_str(": ");
+                    self.write_value(*value, out);
+                }
+                out.push('}');
+            }
+            HeapObject::Tuple(elements) => self.write_list("(", elements, ")", out),
+            HeapObject::Range(range) => write!(out, "{}", range).unwrap_or_default(),
+            HeapObject::Struct { layout, fields } => {
+                let layout = &self.program.types[*layout as usize];
+                write!(out, "{} {{", layout.name).unwrap_or_default();
+                for (i, (name, value)) in layout.fields.iter().zip(fields.borrow().iter()).enumerate() {
+                    write!(out, "{} {}: ", if i > 0 { "," } else { "" }, name).unwrap_or_default();
+                    self.write_value(*value, out);
+                }
+                out.push_str(" }");
+            }
+            HeapObject::Variant { layout, fields } => {
+                let layout = &self.program.variants[*layout as usize];
+                if layout.enum_name != "Result" {
+                    write!(out, "{}::", layout.enum_name).unwrap_or_default();
+                }
+                out.push_str(&layout.name);
+                if !fields.is_empty() {
+                    self.write_list("(", fields, ")", out);
+                }
+            }
+            HeapObject::Closure { .. } | HeapObject::BoundMethod { .. } => out.push_str("<function>"),
+            HeapObject::Cell(cell) => self.write_value(cell.get(), out),
+        }
+    }
+
+    fn write_list(&self, open: &str, values: &[Value], close: &str, out: &mut String) {
+        out.push_str(open);
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            self.write_value(*value, out);
+        }
+        out.push_str(close);
+    }
+
+    // Errors
+
+    /// Report a runtime error in the running goroutine, with a stack trace of its calls
+    fn report(&mut self, error: FluxError) -> Box<ErrorReport> {
+        let max_frames = self.reporter.stack_trace_collector.max_frames;
+        let frames: Vec<StackFrame> = self.fiber.frames.iter()
+            .take(max_frames)
+            .map(|frame| {
+                let function = &self.program.functions[frame.function as usize];
+                let span = function.span_at(frame.pc.saturating_sub(1));
+                let file_path = function.file.clone().unwrap_or_else(|| "<unknown>".to_string());
+                StackFrame {
+                    function_name: function.name.clone(),
+                    line: span.map_or(0, |span| span.start.line),
+                    column: span.map_or(0, |span| span.start.column),
+                    source_line: span.and_then(|span| self.source_line(&file_path, span)),
+                    file_path,
+                }
+            })
+            .collect();
+
+        let (span, file) = match self.fiber.frames.last() {
+            Some(frame) => {
+                let function = &self.program.functions[frame.function as usize];
+                (function.span_at(frame.pc.saturating_sub(1)), function.file.clone())
+            }
+            None => (None, None),
+        };
+
+        self.reporter.stack_trace_collector.frames = frames;
+        let report = self.reporter.generate_report(error, span, file);
+        self.reporter.stack_trace_collector.frames.clear();
+        Box::new(report)
+    }
+
+    fn source_line(&self, file: &str, span: Span) -> Option<String> {
+        let source = self.reporter.source_files.get(file)?;
+        source.lines.get(span.start.line.checked_sub(1)?).map(|line| line.trim().to_string())
+    }
+}
+
+impl Drop for Vm {
+    /// Free every object, since nothing outside the VM refers to them
+    fn drop(&mut self) {
+        self.gc.collect_from(std::iter::empty::<NonNull<HeapObject>>());
+    }
+}
+
+/// Index of the `Result` variant layout named `name`, added if the program lacks it
+fn result_layout(program: &mut BytecodeProgram, name: &str) -> u32 {
+    let existing = program.variants.iter().position(|layout| layout.module == BUILTIN_MODULE && layout.enum_name == "Result" && layout.name == name);
+    match existing {
+        Some(index) => index as u32,
+        None => {
+            program.variants.push(VariantLayout {
+                module: BUILTIN_MODULE,
+                enum_name: "Result".to_string(),
+                name: name.to_string(),
+                fields: vec!["0".to_string()],
+            });
+            (program.variants.len() - 1) as u32
+        }
+    }
+}
+
+/// Cell a local or capture is boxed in, if any
+fn cell<'a>(value: Value) -> Option<&'a Cell<Value>> {
+    match value {
+        Value::Object(object) => match object.get() {
+            HeapObject::Cell(cell) => Some(cell),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn read_cell(value: Value) -> Value {
+    cell(value).map_or(value, Cell::get)
+}
+
+/// Elements of a tuple or payload of a variant
+fn elements<'a>(value: Value) -> Option<&'a [Value]> {
+    match value {
+        Value::Object(object) => match object.get() {
+            HeapObject::Tuple(values) | HeapObject::Variant { fields: values, .. } => Some(values),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn runtime_error(kind: RuntimeErrorKind, message: &str) -> FluxError {
+    FluxError::Runtime(RuntimeError { message: message.to_string(), kind })
+}
+
+fn panic(message: &str) -> FluxError {
+    runtime_error(RuntimeErrorKind::Panic, message)
+}
+
+/// Error in the bytecode itself rather than in the program it encodes
+fn internal(message: &str) -> FluxError {
+    panic(&format!("invalid bytecode: {}", message))
+}
+
+fn int_op(a: i64, op: Instruction, b: i64) -> Step<Option<Value>> {
+    if matches!(op, Instruction::Divide | Instruction::Modulo) && b == 0 {
+        return Err(runtime_error(RuntimeErrorKind::DivisionByZero, "division by zero"));
+    }
+
+    let checked = match op {
+        Instruction::Add => a.checked_add(b),
+        Instruction::Subtract => a.checked_sub(b),
+        Instruction::Multiply => a.checked_mul(b),
+        Instruction::Divide => a.checked_div(b),
+        Instruction::Modulo => a.checked_rem(b),
+        Instruction::BitAnd => Some(a & b),
+        Instruction::BitOr => Some(a | b),
+        Instruction::BitXor => Some(a ^ b),
+        Instruction::ShiftLeft => u32::try_from(b).ok().and_then(|shift| a.checked_shl(shift)),
+        Instruction::ShiftRight => u32::try_from(b).ok().and_then(|shift| a.checked_shr(shift)),
+        _ => return Ok(compare(a.cmp(&b), op)),
+    };
+    match checked {
+        Some(value) => Ok(Some(Value::Int(value))),
+        None => Err(panic(&format!("integer overflow in {} {} {}", a, op.mnemonic(), b))),
+    }
+}
+
+fn float_op(a: f64, op: Instruction, b: f64) -> Option<Value> {
+    match op {
+        Instruction::Add => Some(Value::Float(a + b)),
+        Instruction::Subtract => Some(Value::Float(a - b)),
+        Instruction::Multiply => Some(Value::Float(a * b)),
+        Instruction::Divide => Some(Value::Float(a / b)),
+        Instruction::Modulo => Some(Value::Float(a % b)),
+        _ => a.partial_cmp(&b).and_then(|ordering| compare(ordering, op)),
+    }
+}
+
+fn compare(ordering: std::cmp::Ordering, op: Instruction) -> Option<Value> {
+    let result = match op {
+        Instruction::Less => ordering.is_lt(),
+        Instruction::LessEqual => ordering.is_le(),
+        Instruction::Greater => ordering.is_gt(),
+        Instruction::GreaterEqual => ordering.is_ge(),
+        _ => return None,
+    };
+    Some(Value::Bool(result))
+}
+
+/// Structural equality; instances, maps and functions compare by identity
+fn values_equal(a: Value, b: Value) -> bool {
+    match (a, b) {
+        (Value::Unit, Value::Unit) | (Value::Null, Value::Null) => true,
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Float(a), Value::Float(b)) => a == b,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Char(a), Value::Char(b)) => a == b,
+        (Value::Function(a), Value::Function(b)) | (Value::Constructor(a), Value::Constructor(b)) => a == b,
+        (Value::Builtin(a), Value::Builtin(b)) => a == b,
+        (Value::Object(a), Value::Object(b)) => {
+            a == b || match (a.get(), b.get()) {
+                (HeapObject::String(a), HeapObject::String(b)) => a == b,
+                (HeapObject::Array(a), HeapObject::Array(b)) => all_equal(&a.borrow(), &b.borrow()),
+                (HeapObject::Tuple(a), HeapObject::Tuple(b)) => all_equal(a, b),
+                (HeapObject::Range(a), HeapObject::Range(b)) => a == b,
+                (HeapObject::Variant { layout: a, fields: a_fields }, HeapObject::Variant { layout: b, fields: b_fields }) => {
+                    a == b && all_equal(a_fields, b_fields)
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn all_equal(a: &[Value], b: &[Value]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_equal(*a, *b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::bytecode_compiler::BytecodeCompiler;
+    use crate::lexer::FluxLexer;
+    use crate::parser::{FluxParser, Parser};
+    use crate::semantic::{FluxSemanticAnalyzer, SemanticAnalyzer};
+    use std::path::Path;
+
+    const FILE: &str = "main.flux";
+
+    fn vm(source: &str) -> Vm {
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = parser.parse_program().unwrap();
+        let typed = FluxSemanticAnalyzer::new().analyze(program).unwrap();
+        let bytecode = BytecodeCompiler::compile(&typed, Some(Path::new(FILE))).unwrap();
+
+        let mut vm = Vm::new(bytecode);
+        vm.capture_output();
+        vm.add_source_file(PathBuf::from(FILE), source.to_string());
+        vm
+    }
+
+    fn run(source: &str) -> String {
+        let mut vm = vm(source);
+        if let Err(report) = vm.run_main(&[]) {
+            panic!("{}", report);
+        }
+        vm.captured_output().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_arithmetic_loops_and_constants() {
+        let output = run(r#"
+const LIMIT: int = 7
+
+func main() {
+    let total = 0;
+    let i = 0;
+    while i < 10 {
+        i = i + 1;
+        if i % 2 == 0 { continue; }
+        if i > LIMIT { break; }
+        total = total + i;
+    }
+    for x in [10, 20] {
+        total = total + x;
+    }
+    let names = {"a": 1, "b": 2};
+    total = total + names["b"];
+    if total == 48 && 7 / 2 == 3 && 1.5 * 2.0 == 3.0 && -(2 - 5) == 3 {
+        println("ok " + "then");
+    }
+}
+"#);
+        assert_eq!(output, "ok then\n");
+    }
+
+    #[test]
+    fn test_structs_classes_and_methods() {
+        let output = run(r#"
+struct Point { mut x: int, y: int }
+
+class Counter {
+    mut count: int
+
+    static func start() -> Counter { return Counter { count: 10 } }
+    func bump() -> int {
+        self.count = self.count + 1;
+        return self.count;
+    }
+}
+
+func main() {
+    let p = Point { y: 2, x: 1 };
+    p.x = 5;
+    let c = Counter::start();
+    c.bump();
+    if p.x + p.y == 7 && c.bump() == 12 {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+    }
+
+    #[test]
+    fn test_match_result_and_try_operator() {
+        let output = run(r#"
+enum Shape { Circle(int), Rect(int, int), Empty }
+
+func area(s: Shape) -> int {
+    match s {
+        Shape::Circle(r) => { 3 * r * r }
+        Shape::Rect(w, h) if w == h => { w * w }
+        Shape::Rect(w, h) => { w * h }
+        Shape::Empty => { 0 }
+    }
+}
+
+func half(n: int) -> Result<int, string> {
+    if n % 2 != 0 {
+        return Err("odd");
+    }
+    return Ok(n / 2);
+}
+
+func quarter(n: int) -> Result<int, string> {
+    let h = half(n)?;
+    return half(h);
+}
+
+func main() {
+    if area(Shape::Circle(2)) == 12 && area(Shape::Rect(2, 2)) == 4 && area(Shape::Rect(2, 3)) == 6 && area(Shape::Empty) == 0 {
+        println("areas");
+    }
+    match quarter(12) {
+        Ok(v) => { if v == 3 { println("three"); } }
+        Err(e) => { println(e); }
+    }
+    match quarter(6) {
+        Ok(v) => { println("unexpected"); }
+        Err(e) => { println("error: " + e); }
+    }
+}
+"#);
+        assert_eq!(output, "areas\nthree\nerror: odd\n");
+    }
+
+    #[test]
+    fn test_null_safety_operators() {
+        let output = run(r#"
+struct User { name: string, boss: User? }
+
+func find(users: [User], name: string) -> User? {
+    for user in users {
+        if user.name == name { return user; }
+    }
+    return null;
+}
+
+func main() {
+    let ada = User { name: "ada", boss: null };
+    let users = [ada, User { name: "bob", boss: ada }];
+    println(find(users, "bob")?.boss?.name ?? "none");
+    println(find(users, "ada")?.boss?.name ?? "none");
+    let found = find(users, "eve");
+    if found == null {
+        println("no eve");
+    }
+    let bob = find(users, "bob");
+    if bob == null { return; }
+    println(bob.name + " " + bob.boss!.name);
+}
+"#);
+        assert_eq!(output, "ada\nnone\nno eve\nbob ada\n");
+    }
+
+    #[test]
+    fn test_try_on_nullable_values() {
+        let output = run(r#"
+struct User { name: string, boss: User? }
+
+func boss_name(user: User?) -> string? {
+    let found = user?;
+    let boss = found.boss?;
+    return boss.name;
+}
+
+func main() {
+    let ada = User { name: "ada", boss: null };
+    let bob = User { name: "bob", boss: ada };
+    println(boss_name(bob) ?? "none");
+    println(boss_name(ada) ?? "none");
+    println(boss_name(null) ?? "none");
+}
+"#);
+        assert_eq!(output, "ada\nnone\nnone\n");
+    }
+
+    #[test]
+    fn test_ranges() {
+        let output = run(r#"
+func main() {
+    let total = 0;
+    for i in 0..5 { total = total + i; }
+    for i in 1..=3 { total = total + i; }
+    for i in 10..0 step -3 { total = total + i; }
+    let n = 4;
+    let step = 2;
+    let evens = n..=0 step -step;
+    for i in evens { total = total + i; }
+    for i in 0..n step step { total = total + i; }
+    for i in n..0 { println("empty"); }
+    for i in 0..10 {
+        if i == 3 { break; }
+        if i == 1 { continue; }
+        total = total + 100;
+    }
+    if total == 246 && evens == (4..=0 step -2) {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+
+        // Counting stops at the end rather than overflowing past it
+        let output = run(r#"
+func main() {
+    let count = 0;
+    for i in 9223372036854775805..=9223372036854775807 { count = count + 1; }
+    for i in 9223372036854775800..=9223372036854775807 step 5 { count = count + 1; }
+    println("{count}");
+}
+"#);
+        assert_eq!(output, "5\n");
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let output = run(r#"
+struct Point { x: int, y: int }
+
+func main() {
+    let name = "Ada";
+    let count = 2;
+    let p = Point { x: 1, y: 2 };
+    let maybe: int? = null;
+    println("Hello, {name}! You have {count + 1} items");
+    println("{p} {[1.5, 2.0]} {maybe} {"{count}" + "!"} \{literal\}");
+}
+"#);
+        assert_eq!(output, "Hello, Ada! You have 3 items\nPoint { x: 1, y: 2 } [1.5, 2.0] null 2! {literal}\n");
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let output = run(r#"
+func main() {
+    let flags = 0b1000_0001u8;
+    match flags {
+        0x81u8 => { println("{0xFF + 0o10 + 1_000}"); }
+        _ => { println("no match"); }
+    }
+    println("{flags} {0.5f32}");
+}
+"#);
+        assert_eq!(output, "1263\n129 0.5\n");
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let output = run(r#"
+struct Point { x: int, y: int }
+
+func swap(pair: (int, string)) -> (string, int) {
+    let (n, s) = pair;
+    return (s, n);
+}
+
+func main() {
+    let (word, count) = swap((2, "two"));
+    println(word);
+    let Point { x, y: height } = Point { x: 1, y: 5 };
+    let ((a, _), b) = ((count, 0), x + height);
+    if a + b == 8 {
+        println("eight");
+    }
+    let totals = {"a": 1, "b": 2};
+    let sum = 0;
+    for (key, value) in totals {
+        println(key);
+        sum = sum + value;
+    }
+    if sum == 3 {
+        println("three");
+    }
+}
+"#);
+        assert_eq!(output, "two\neight\na\nb\nthree\n");
+    }
+
+    #[test]
+    fn test_closures_share_assigned_captures() {
+        let output = run(r#"
+func main() {
+    let count = 0;
+    let offset = 10;
+    let add = |n: int| { count = count + n; count + offset };
+    add(1);
+    if add(2) == 13 && count == 3 {
+        println("ok");
+    }
+}
+"#);
+        assert_eq!(output, "ok\n");
+    }
+
+    #[test]
+    fn test_goroutines_are_interleaved() {
+        let mut vm = vm(r#"
+func worker(name: string, steps: int) {
+    let i = 0;
+    while i < steps {
+        i = i + 1;
+    }
+    println(name);
+}
+
+func main() {
+    go worker("slow", 5000);
+    go worker("fast", 10);
+    println("main");
+}
+"#);
+        vm.run_main(&[]).unwrap();
+        assert_eq!(vm.captured_output(), Some("main\nfast\nslow\n"));
+
+        let stats = vm.scheduler_stats();
+        assert_eq!(stats.total_goroutines, 3);
+        assert_eq!(stats.finished_count, 3);
+        assert_eq!(stats.ready_count, 0);
+        assert_eq!(stats.running_count, 0);
+    }
+
+    #[test]
+    fn test_garbage_is_collected() {
+        let mut vm = vm(r#"
+func main() {
+    let kept = [[0], [0], [0], [0]];
+    let i = 0;
+    while i < 2000 {
+        let garbage = [i, i + 1, i + 2];
+        if i % 500 == 0 {
+            kept[i / 500] = garbage;
+        }
+        i = i + 1;
+    }
+    if kept[1][2] == 502 && kept[3][0] == 1500 {
+        println("ok");
+    }
+}
+"#);
+        vm.set_gc_threshold(4096);
+        vm.run_main(&[]).unwrap();
+        assert_eq!(vm.captured_output(), Some("ok\n"));
+
+        let memory = vm.memory_summary();
+        assert!(memory.collections_performed > 0);
+        assert!(memory.active_objects < 200, "{} objects survived", memory.active_objects);
+    }
+
+    #[test]
+    fn test_runtime_error_has_stack_trace() {
+        let mut vm = vm(r#"func divide(a: int, b: int) -> int {
+    return a / b;
+}
+
+func main() {
+    println("before");
+    divide(1, 0);
+    println("after");
+}
+"#);
+        let report = vm.run_main(&[]).unwrap_err();
+        assert_eq!(vm.captured_output(), Some("before\n"));
+        assert!(matches!(&report.error, FluxError::Runtime(error) if error.kind == RuntimeErrorKind::DivisionByZero));
+        assert_eq!(report.location.unwrap().start.line, 2);
+        assert_eq!(report.file_path.as_deref(), Some(FILE));
+
+        let trace = report.stack_trace.as_ref().unwrap();
+        let frames: Vec<_> = trace.iter().map(|frame| (frame.function_name.as_str(), frame.line)).collect();
+        assert_eq!(frames, vec![("main", 7), ("divide", 2)]);
+        assert_eq!(trace[0].source_line.as_deref(), Some("divide(1, 0);"));
+    }
+
+    #[test]
+    fn test_index_out_of_bounds_and_stack_overflow() {
+        let mut indexing = vm("func main() { let a = [1, 2]; a[2]; }");
+        let report = indexing.run_main(&[]).unwrap_err();
+        assert!(matches!(&report.error, FluxError::IndexOutOfBounds(error) if error.index == 2 && error.length == 2));
+
+        let mut recursion = vm("func down(n: int) -> int { return down(n + 1); }\nfunc main() { down(0); }");
+        recursion.set_max_call_depth(50);
+        let report = recursion.run_main(&[]).unwrap_err();
+        assert!(matches!(&report.error, FluxError::Runtime(error) if error.kind == RuntimeErrorKind::StackOverflow));
+
+        let mut zero_step = vm("func main() {\n let step = 0;\n for i in 0..3 step step { }\n}");
+        let report = zero_step.run_main(&[]).unwrap_err();
+        assert!(matches!(&report.error, FluxError::Runtime(error) if error.message == "range step cannot be zero"));
+        assert_eq!(report.location.unwrap().start.line, 3);
+    }
+}
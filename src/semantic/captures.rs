@@ -0,0 +1,259 @@
+//! Capture analysis for lambdas
+//!
+//! Finds the variables a lambda body uses without binding them itself, and the
+//! variables a function assigns to. A captured variable that is assigned
+//! anywhere in the enclosing function is shared with it by reference; any
+//! other capture is copied into the closure when it is created.
+
+use crate::parser::ast::{Block, Expression, ExpressionKind, LambdaParameter, Pattern, ResultPattern, Statement, StatementKind, StringPart};
+use std::collections::HashSet;
+
+/// Names used inside a lambda that are bound outside of it, in order of first use
+pub fn free_variables(parameters: &[LambdaParameter], body: &Expression) -> Vec<String> {
+    let mut walker = Walker::default();
+    walker.scopes.push(parameters.iter().map(|param| param.name.clone()).collect());
+    walker.expression(body);
+    walker.free
+}
+
+/// Names of the variables assigned anywhere in `block`, including inside
+/// nested lambdas
+pub fn assigned_variables(block: &Block) -> HashSet<String> {
+    let mut walker = Walker::default();
+    walker.block(block);
+    walker.assigned
+}
+
+/// Walks statements and expressions while tracking the names bound in each scope
+#[derive(Default)]
+struct Walker {
+    scopes: Vec<HashSet<String>>,
+    free: Vec<String>,
+    assigned: HashSet<String>,
+}
+
+impl Walker {
+    fn bind(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+    }
+
+    fn use_name(&mut self, name: &str) {
+        let bound = self.scopes.iter().any(|scope| scope.contains(name));
+        if !bound && !self.free.iter().any(|free| free == name) {
+            self.free.push(name.to_string());
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(HashSet::new());
+        for stmt in &block.statements {
+            self.statement(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match &stmt.kind {
+            StatementKind::Expression(expr) | StatementKind::Go(expr) => self.expression(expr),
+            StatementKind::Let(pattern, _, init) => {
+                if let Some(init) = init {
+                    self.expression(init);
+                }
+                self.pattern(pattern);
+            }
+            StatementKind::Const(name, _, value) => {
+                self.expression(value);
+                self.bind(name);
+            }
+            StatementKind::Assignment(target, value) => {
+                if let ExpressionKind::Identifier(name) = &target.kind {
+                    self.assigned.insert(name.clone());
+                }
+                self.expression(target);
+                self.expression(value);
+            }
+            StatementKind::Return(expr) | StatementKind::Break(expr) => {
+                if let Some(expr) = expr {
+                    self.expression(expr);
+                }
+            }
+            StatementKind::Continue => {}
+            StatementKind::If(cond, then_block, else_block) => {
+                self.expression(cond);
+                self.block(then_block);
+                if let Some(else_block) = else_block {
+                    self.block(else_block);
+                }
+            }
+            StatementKind::While(cond, body) => {
+                self.expression(cond);
+                self.block(body);
+            }
+            StatementKind::For(pattern, iter, body) => {
+                self.expression(iter);
+                self.scopes.push(HashSet::new());
+                self.pattern(pattern);
+                self.block(body);
+                self.scopes.pop();
+            }
+            StatementKind::Match(expr, arms) => {
+                self.expression(expr);
+                for arm in arms {
+                    self.scopes.push(HashSet::new());
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expression(guard);
+                    }
+                    self.block(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match &expr.kind {
+            ExpressionKind::Literal(_) | ExpressionKind::Path(_) => {}
+            ExpressionKind::Identifier(name) => self.use_name(name),
+            ExpressionKind::Binary(left, _, right) | ExpressionKind::Index(left, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            ExpressionKind::Unary(_, operand) | ExpressionKind::Field(operand, _) => self.expression(operand),
+            ExpressionKind::Call(func, args) => {
+                self.expression(func);
+                for arg in args {
+                    self.expression(arg);
+                }
+            }
+            ExpressionKind::Match(scrutinee, arms) => {
+                self.expression(scrutinee);
+                for arm in arms {
+                    self.scopes.push(HashSet::new());
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expression(guard);
+                    }
+                    self.block(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            ExpressionKind::If(cond, then_block, else_block) => {
+                self.expression(cond);
+                self.block(then_block);
+                if let Some(else_block) = else_block {
+                    self.block(else_block);
+                }
+            }
+            ExpressionKind::Block(block) => self.block(block),
+            ExpressionKind::Array(elements) | ExpressionKind::Tuple(elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+            }
+            ExpressionKind::Map(pairs) => {
+                for (key, value) in pairs {
+                    self.expression(key);
+                    self.expression(value);
+                }
+            }
+            ExpressionKind::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expression(expr) = part {
+                        self.expression(expr);
+                    }
+                }
+            }
+            ExpressionKind::Range(start, end, step, _) => {
+                self.expression(start);
+                self.expression(end);
+                if let Some(step) = step {
+                    self.expression(step);
+                }
+            }
+            ExpressionKind::StructLiteral(_, fields) => {
+                for (_, value) in fields {
+                    self.expression(value);
+                }
+            }
+            ExpressionKind::Lambda(parameters, _, body) => {
+                self.scopes.push(parameters.iter().map(|param| param.name.clone()).collect());
+                self.expression(body);
+                self.scopes.pop();
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name) => self.bind(name),
+            Pattern::Tuple(elements) | Pattern::Variant(_, elements) => {
+                for element in elements {
+                    self.pattern(element);
+                }
+            }
+            Pattern::Struct(_, fields) => {
+                for (_, field) in fields {
+                    self.pattern(field);
+                }
+            }
+            Pattern::Result(ResultPattern::Ok(inner)) | Pattern::Result(ResultPattern::Err(inner)) => self.pattern(inner),
+            Pattern::Literal(..) | Pattern::Wildcard => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::FluxLexer;
+    use crate::parser::{FluxParser, Parser};
+    use crate::parser::ast::{Item, Program};
+
+    fn parse(source: &str) -> Program {
+        let lexer = FluxLexer::new(source.to_string());
+        let mut parser = FluxParser::new(lexer).unwrap();
+        parser.parse_program().unwrap()
+    }
+
+    fn body(program: &Program) -> &Block {
+        match &program.items[0] {
+            Item::Function(func) => &func.body,
+            other => panic!("expected a function, found {:?}", other),
+        }
+    }
+
+    fn lambda(block: &Block) -> (&[LambdaParameter], &Expression) {
+        let init = block.statements.iter().find_map(|stmt| match &stmt.kind {
+            StatementKind::Let(_, _, Some(init)) => match &init.kind {
+                ExpressionKind::Lambda(parameters, _, body) => Some((parameters.as_slice(), &**body)),
+                _ => None,
+            },
+            _ => None,
+        });
+        init.expect("expected a lambda")
+    }
+
+    #[test]
+    fn test_free_variables_skip_parameters_and_locals() {
+        let program = parse("func f(a: int, b: int) { let g = |x: int| { let y = x + a; y + b + a }; }");
+        let (parameters, body) = lambda(body(&program));
+        assert_eq!(free_variables(parameters, body), vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_free_variables_of_nested_lambda() {
+        let program = parse("func f(a: int) { let g = |x: int| |y: int| x + y + a; }");
+        let (parameters, body) = lambda(body(&program));
+        assert_eq!(free_variables(parameters, body), vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn test_assigned_variables() {
+        let program = parse("func f(mut a: int) { let b = 1; a = 2; let g = || { b = 3; }; }");
+        let assigned = assigned_variables(body(&program));
+        assert_eq!(assigned, HashSet::from(["a".to_string(), "b".to_string()]));
+    }
+}
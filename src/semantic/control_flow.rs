@@ -0,0 +1,413 @@
+//! Control-flow validation
+//!
+//! Runs on the typed program once type checking is done. Reports non-unit
+//! functions with a path that ends without a value as errors, and statements
+//! that can never run or `Result` values that are computed and dropped as
+//! warnings. `break` and `continue` outside loops are rejected earlier, during
+//! name resolution.
+
+use crate::error::{SemanticError, SemanticErrorKind};
+use crate::parser::ast::{Literal, Type};
+use crate::position::{Position, Span};
+use crate::semantic::{
+    TypedBlock, TypedExpression, TypedExpressionKind, TypedItem, TypedProgram, TypedStatement,
+    TypedStatementKind,
+};
+
+/// Collects control-flow errors and warnings for a typed program
+#[derive(Debug, Default)]
+pub struct ControlFlowValidator {
+    errors: Vec<SemanticError>,
+    warnings: Vec<SemanticError>,
+}
+
+impl ControlFlowValidator {
+    /// Create a validator with no diagnostics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate every function and method body of a program
+    pub fn validate_program(&mut self, program: &TypedProgram) {
+        for item in &program.items {
+            match item {
+                TypedItem::Function(func) => {
+                    self.validate_body(&func.name, &func.return_type, &func.body, func.span);
+                }
+                TypedItem::Class(class_def) => {
+                    for method in &class_def.methods {
+                        self.validate_body(&method.name, &method.return_type, &method.body, method.span);
+                    }
+                }
+                TypedItem::Impl(impl_block) => {
+                    for method in &impl_block.methods {
+                        self.validate_body(&method.name, &method.return_type, &method.body, method.span);
+                    }
+                }
+                TypedItem::Const(const_def) => self.validate_expression(&const_def.value),
+                TypedItem::Struct(_) | TypedItem::Enum(_) | TypedItem::Interface(_) | TypedItem::ExternFunction(_) => {}
+            }
+        }
+    }
+
+    /// The errors and warnings found, in that order
+    pub fn into_diagnostics(self) -> (Vec<SemanticError>, Vec<SemanticError>) {
+        (self.errors, self.warnings)
+    }
+
+    fn validate_body(&mut self, name: &str, return_type: &Type, body: &TypedBlock, span: Option<Span>) {
+        let returns_value = *return_type != Type::Unit;
+        if returns_value && !produces_value(body) {
+            self.errors.push(SemanticError {
+                span: span.unwrap_or_else(|| Span::single(Position::start())),
+                kind: SemanticErrorKind::MissingReturn { function: name.to_string() },
+            });
+        }
+        self.validate_block(body, returns_value);
+    }
+
+    /// Check the statements of a block; `value_used` tells whether the value
+    /// of its last statement becomes the value of the block
+    fn validate_block(&mut self, block: &TypedBlock, value_used: bool) {
+        let mut diverged = false;
+        for (index, stmt) in block.statements.iter().enumerate() {
+            if diverged {
+                // Only the first dead statement is reported; the rest follow from it
+                self.warnings.push(SemanticError {
+                    span: statement_span(stmt),
+                    kind: SemanticErrorKind::UnreachableCode,
+                });
+                break;
+            }
+            let is_tail = value_used && index + 1 == block.statements.len();
+            self.validate_statement(stmt, is_tail);
+            diverged = diverges(stmt);
+        }
+    }
+
+    fn validate_statement(&mut self, stmt: &TypedStatement, is_tail: bool) {
+        match &stmt.kind {
+            TypedStatementKind::Expression(expr) => {
+                if matches!(expr.type_, Type::Result(_, _)) && !is_tail {
+                    self.warnings.push(SemanticError {
+                        span: statement_span(stmt),
+                        kind: SemanticErrorKind::UnusedResult,
+                    });
+                }
+                self.validate_expression(expr);
+            }
+            TypedStatementKind::Let(_, _, init) => {
+                if let Some(init) = init {
+                    self.validate_expression(init);
+                }
+            }
+            TypedStatementKind::Const(_, _, value)
+            | TypedStatementKind::Destructure(_, value)
+            | TypedStatementKind::Go(value) => {
+                self.validate_expression(value);
+            }
+            TypedStatementKind::Assignment(target, value) => {
+                self.validate_expression(target);
+                self.validate_expression(value);
+            }
+            TypedStatementKind::Return(value) | TypedStatementKind::Break(value) => {
+                if let Some(value) = value {
+                    self.validate_expression(value);
+                }
+            }
+            TypedStatementKind::Continue => {}
+            TypedStatementKind::If(condition, then_block, else_block) => {
+                self.validate_expression(condition);
+                self.validate_block(then_block, is_tail);
+                if let Some(else_block) = else_block {
+                    self.validate_block(else_block, is_tail);
+                }
+            }
+            TypedStatementKind::While(condition, body) => {
+                self.validate_expression(condition);
+                self.validate_block(body, false);
+            }
+            TypedStatementKind::For(_, iterable, body) => {
+                self.validate_expression(iterable);
+                self.validate_block(body, false);
+            }
+            TypedStatementKind::Match(scrutinee, arms) => {
+                self.validate_expression(scrutinee);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.validate_expression(guard);
+                    }
+                    self.validate_block(&arm.body, is_tail);
+                }
+            }
+        }
+    }
+
+    /// Look for blocks nested in an expression; their values are always used
+    fn validate_expression(&mut self, expr: &TypedExpression) {
+        match &expr.kind {
+            TypedExpressionKind::Literal(_)
+            | TypedExpressionKind::Identifier(_)
+            | TypedExpressionKind::Instantiate(_, _) => {}
+            TypedExpressionKind::Binary(left, _, right) | TypedExpressionKind::Index(left, right) => {
+                self.validate_expression(left);
+                self.validate_expression(right);
+            }
+            TypedExpressionKind::Unary(_, operand)
+            | TypedExpressionKind::Field(operand, _)
+            | TypedExpressionKind::Upcast(operand) => self.validate_expression(operand),
+            TypedExpressionKind::Call(callee, args) => {
+                self.validate_expression(callee);
+                for arg in args {
+                    self.validate_expression(arg);
+                }
+            }
+            TypedExpressionKind::Array(elements)
+            | TypedExpressionKind::Tuple(elements)
+            | TypedExpressionKind::Interpolated(elements)
+            | TypedExpressionKind::Variant(_, _, elements) => {
+                for element in elements {
+                    self.validate_expression(element);
+                }
+            }
+            TypedExpressionKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.validate_expression(key);
+                    self.validate_expression(value);
+                }
+            }
+            TypedExpressionKind::Range(start, end, step, _) => {
+                self.validate_expression(start);
+                self.validate_expression(end);
+                if let Some(step) = step {
+                    self.validate_expression(step);
+                }
+            }
+            TypedExpressionKind::StructLiteral(_, fields) => {
+                for (_, value) in fields {
+                    self.validate_expression(value);
+                }
+            }
+            TypedExpressionKind::Match(scrutinee, arms) => {
+                self.validate_expression(scrutinee);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.validate_expression(guard);
+                    }
+                    self.validate_block(&arm.body, true);
+                }
+            }
+            TypedExpressionKind::If(condition, then_block, else_block) => {
+                self.validate_expression(condition);
+                self.validate_block(then_block, true);
+                if let Some(else_block) = else_block {
+                    self.validate_block(else_block, true);
+                }
+            }
+            TypedExpressionKind::Block(block) => self.validate_block(block, true),
+            TypedExpressionKind::Closure(closure) => self.validate_expression(&closure.body),
+        }
+    }
+}
+
+fn statement_span(stmt: &TypedStatement) -> Span {
+    stmt.span.unwrap_or_else(|| Span::single(Position::start()))
+}
+
+/// Whether control never reaches the statement after this one
+fn diverges(stmt: &TypedStatement) -> bool {
+    match &stmt.kind {
+        TypedStatementKind::Return(_) | TypedStatementKind::Break(_) | TypedStatementKind::Continue => true,
+        TypedStatementKind::While(condition, body) => loops_forever(condition, body),
+        TypedStatementKind::If(_, then_block, Some(else_block)) => {
+            block_diverges(then_block) && block_diverges(else_block)
+        }
+        TypedStatementKind::Match(_, arms) => {
+            !arms.is_empty() && arms.iter().all(|arm| block_diverges(&arm.body))
+        }
+        _ => false,
+    }
+}
+
+fn block_diverges(block: &TypedBlock) -> bool {
+    block.statements.iter().any(diverges)
+}
+
+/// Whether every path through a function body returns or ends in a value
+fn produces_value(block: &TypedBlock) -> bool {
+    if block.statements.iter().any(returns) {
+        return true;
+    }
+    match block.statements.last().map(|stmt| &stmt.kind) {
+        Some(TypedStatementKind::Expression(expr)) => expr.type_ != Type::Unit,
+        Some(TypedStatementKind::If(_, then_block, Some(else_block))) => {
+            produces_value(then_block) && produces_value(else_block)
+        }
+        Some(TypedStatementKind::Match(_, arms)) => {
+            !arms.is_empty() && arms.iter().all(|arm| produces_value(&arm.body))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a statement returns from the function on every path through it
+fn returns(stmt: &TypedStatement) -> bool {
+    let block_returns = |block: &TypedBlock| block.statements.iter().any(returns);
+    match &stmt.kind {
+        TypedStatementKind::Return(_) => true,
+        // A loop that never ends never reaches the end of the function either
+        TypedStatementKind::While(condition, body) => loops_forever(condition, body),
+        TypedStatementKind::If(_, then_block, Some(else_block)) => {
+            block_returns(then_block) && block_returns(else_block)
+        }
+        TypedStatementKind::Match(_, arms) => {
+            !arms.is_empty() && arms.iter().all(|arm| block_returns(&arm.body))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a `while` loop can only be left by returning: its condition is
+/// `true` and nothing in its body breaks out of it
+fn loops_forever(condition: &TypedExpression, body: &TypedBlock) -> bool {
+    matches!(condition.kind, TypedExpressionKind::Literal(Literal::Boolean(true))) && !block_breaks(body)
+}
+
+/// Whether a block has a `break` of the loop around it; those in nested
+/// loops leave only the nested loop
+fn block_breaks(block: &TypedBlock) -> bool {
+    block.statements.iter().any(|stmt| match &stmt.kind {
+        TypedStatementKind::Break(_) => true,
+        TypedStatementKind::If(_, then_block, else_block) => {
+            block_breaks(then_block) || else_block.as_ref().is_some_and(block_breaks)
+        }
+        TypedStatementKind::Match(_, arms) => arms.iter().any(|arm| block_breaks(&arm.body)),
+        TypedStatementKind::Expression(expr) => expression_breaks(expr),
+        TypedStatementKind::Let(_, _, Some(expr)) => expression_breaks(expr),
+        _ => false,
+    })
+}
+
+fn expression_breaks(expr: &TypedExpression) -> bool {
+    match &expr.kind {
+        TypedExpressionKind::Block(block) => block_breaks(block),
+        TypedExpressionKind::If(_, then_block, else_block) => {
+            block_breaks(then_block) || else_block.as_ref().is_some_and(block_breaks)
+        }
+        TypedExpressionKind::Match(_, arms) => arms.iter().any(|arm| block_breaks(&arm.body)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{SemanticError, SemanticErrorKind};
+    use crate::lexer::FluxLexer;
+    use crate::parser::{FluxParser, Parser};
+    use crate::semantic::FluxSemanticAnalyzer;
+
+    type Diagnostics = Vec<(usize, String)>;
+
+    /// Errors and warnings of a program, as (line, message) pairs
+    fn diagnostics(source: &str) -> (Diagnostics, Diagnostics) {
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = parser.parse_program().unwrap();
+        let mut analyzer = FluxSemanticAnalyzer::new();
+        let (_, errors) = analyzer.analyze_with_errors(program);
+        let lines = |diagnostics: &[SemanticError]| diagnostics.iter()
+            .map(|d| (d.span.start.line, d.kind.to_string()))
+            .collect();
+        (lines(&errors), lines(analyzer.warnings()))
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let (errors, _) = diagnostics("func f(n: int) -> int {\n if n > 0 { return 1; }\n}");
+        assert_eq!(errors, vec![(1, SemanticErrorKind::MissingReturn { function: "f".to_string() }.to_string())]);
+
+        // Both branches return, a trailing match gives a value, or the body ends in a value
+        let (errors, _) = diagnostics(r#"
+func sign(n: int) -> int {
+    if n > 0 { return 1; } else { return -1; }
+}
+func pick(n: int) -> string {
+    match n {
+        0 => { "zero" }
+        _ => { return "many"; }
+    }
+}
+func double(n: int) -> int {
+    n * 2
+}
+func nothing() {
+}
+"#);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // A return inside a loop may never run
+        let (errors, _) = diagnostics("func f(n: int) -> int {\n while n > 0 { return 1; }\n}");
+        assert_eq!(errors.len(), 1);
+        let (errors, _) = diagnostics("func f() -> int {\n while true { if false { break; } return 1; }\n}");
+        assert_eq!(errors.len(), 1);
+
+        // A `while true` loop is only left by returning, unless it breaks
+        let (errors, _) = diagnostics(r#"
+func f() -> int {
+    while true { return 1; }
+}
+func g(n: int) -> int {
+    while true {
+        for i in [1, 2] { break; }
+        if n > 0 { return n; }
+    }
+}
+"#);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let (errors, warnings) = diagnostics(r#"
+func f(n: int) -> int {
+    for i in [1, 2] {
+        continue;
+        println("skipped");
+    }
+    if n > 0 { return 1; } else { return 2; }
+    let x = 3;
+    return x;
+}
+"#);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(warnings, vec![(5, SemanticErrorKind::UnreachableCode.to_string()), (8, SemanticErrorKind::UnreachableCode.to_string())]);
+
+        // A bare return ends at the line break
+        let (_, warnings) = diagnostics("func g() { }\nfunc f() {\n return\n g()\n}");
+        assert_eq!(warnings, vec![(4, SemanticErrorKind::UnreachableCode.to_string())]);
+    }
+
+    #[test]
+    fn test_break_and_continue_outside_loops() {
+        let (errors, _) = diagnostics("func f() {\n break;\n}");
+        assert_eq!(errors, vec![(2, SemanticErrorKind::BreakOutsideLoop.to_string())]);
+
+        let (errors, _) = diagnostics("func f() {\n for i in [1] {\n let g = || { continue; };\n }\n}");
+        assert_eq!(errors, vec![(3, SemanticErrorKind::ContinueOutsideLoop.to_string())]);
+    }
+
+    #[test]
+    fn test_unused_result() {
+        let (errors, warnings) = diagnostics(r#"
+func parse(n: int) -> Result<int, string> {
+    if n < 0 { return Err("negative"); }
+    return Ok(n);
+}
+func main() {
+    parse(1);
+    let used = parse(2);
+}
+"#);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(warnings, vec![(7, SemanticErrorKind::UnusedResult.to_string())]);
+    }
+}
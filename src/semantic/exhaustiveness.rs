@@ -0,0 +1,309 @@
+//! Exhaustiveness checking for `match`
+//!
+//! Works on a matrix of patterns, one row per unguarded arm, and searches for
+//! values that no row matches. Each such value is reported as a witness
+//! pattern such as `Shape::Circle(_)`.
+
+use crate::parser::ast::{Enum, Field, Literal, Pattern, ResultPattern, Type, VariantPayload};
+use std::collections::HashMap;
+
+static WILDCARD: Pattern = Pattern::Wildcard;
+
+/// A way of building a value of a type with finitely many shapes
+struct Constructor {
+    /// Name a pattern uses to select this constructor
+    name: String,
+    /// How a witness for this constructor is printed
+    display: String,
+    /// Types of the constructor's payload
+    sub_types: Vec<Type>,
+    /// Field names when the payload is matched by name
+    field_names: Option<Vec<String>>,
+}
+
+/// Finds values not covered by a set of patterns
+pub struct ExhaustivenessChecker<'a> {
+    enums: &'a HashMap<String, Enum>,
+    fields: &'a HashMap<String, Vec<Field>>,
+}
+
+impl<'a> ExhaustivenessChecker<'a> {
+    /// Create a checker that knows the given enum and struct definitions
+    pub fn new(enums: &'a HashMap<String, Enum>, fields: &'a HashMap<String, Vec<Field>>) -> Self {
+        Self { enums, fields }
+    }
+
+    /// Patterns describing the values of `type_` that none of `patterns` match
+    pub fn missing_patterns(&self, type_: &Type, patterns: &[&Pattern]) -> Vec<String> {
+        let rows: Vec<Vec<&Pattern>> = patterns.iter().map(|pattern| vec![*pattern]).collect();
+        self.missing(std::slice::from_ref(type_), &rows)
+            .into_iter()
+            .filter_map(|witness| witness.into_iter().next())
+            .collect()
+    }
+
+    /// Witness rows for the values of `types` that no row of `rows` matches
+    fn missing(&self, types: &[Type], rows: &[Vec<&Pattern>]) -> Vec<Vec<String>> {
+        let (first_type, rest_types) = match types.split_first() {
+            Some(split) => split,
+            None => return if rows.is_empty() { vec![Vec::new()] } else { Vec::new() },
+        };
+
+        // Nothing matches: every shape of the column is missing
+        if rows.is_empty() {
+            let rest = vec!["_".to_string(); rest_types.len()];
+            return match self.constructors(first_type) {
+                Some(constructors) => constructors.iter()
+                    .map(|constructor| {
+                        let payload = vec!["_".to_string(); constructor.sub_types.len()];
+                        prepend(render(constructor, &payload), rest.clone())
+                    })
+                    .collect(),
+                None => vec![prepend("_".to_string(), rest)],
+            };
+        }
+
+        let has_constructor = rows.iter().any(|row| head_name(row[0]).is_some());
+        let constructors = if has_constructor { self.constructors(first_type) } else { None };
+
+        let constructors = match constructors {
+            Some(constructors) => constructors,
+            None => {
+                // Only catch-all patterns can cover this column
+                let default: Vec<Vec<&Pattern>> = rows.iter()
+                    .filter(|row| is_catch_all(row[0]))
+                    .map(|row| row[1..].to_vec())
+                    .collect();
+                return self.missing(rest_types, &default)
+                    .into_iter()
+                    .map(|witness| prepend("_".to_string(), witness))
+                    .collect();
+            }
+        };
+
+        let mut witnesses = Vec::new();
+        for constructor in &constructors {
+            let arity = constructor.sub_types.len();
+            let specialized: Vec<Vec<&Pattern>> = rows.iter()
+                .filter_map(|row| self.specialize(row, constructor))
+                .collect();
+
+            let mut sub_types = constructor.sub_types.clone();
+            sub_types.extend_from_slice(rest_types);
+
+            // One witness per constructor keeps the report readable
+            if let Some(witness) = self.missing(&sub_types, &specialized).into_iter().next() {
+                let (payload, rest) = witness.split_at(arity);
+                let head = render(constructor, payload);
+                witnesses.push(prepend(head, rest.to_vec()));
+            }
+        }
+        witnesses
+    }
+
+    /// Rows that match `constructor`, with its payload patterns spliced in
+    fn specialize<'p>(&self, row: &[&'p Pattern], constructor: &Constructor) -> Option<Vec<&'p Pattern>> {
+        let arity = constructor.sub_types.len();
+        let mut specialized: Vec<&Pattern> = match row[0] {
+            Pattern::Wildcard | Pattern::Identifier(_) => vec![&WILDCARD; arity],
+            pattern if head_name(pattern).as_deref() != Some(constructor.name.as_str()) => return None,
+            Pattern::Variant(_, patterns) | Pattern::Tuple(patterns) => patterns.iter().collect(),
+            Pattern::Struct(_, field_patterns) => {
+                let names = constructor.field_names.as_deref().unwrap_or(&[]);
+                names.iter()
+                    .map(|name| field_patterns.iter()
+                        .find(|(field_name, _)| field_name == name)
+                        .map(|(_, pattern)| pattern)
+                        .unwrap_or(&WILDCARD))
+                    .collect()
+            }
+            Pattern::Result(ResultPattern::Ok(pattern)) | Pattern::Result(ResultPattern::Err(pattern)) => {
+                vec![pattern.as_ref()]
+            }
+            _ => Vec::new(),
+        };
+
+        // Arity mismatches are reported by the type checker
+        specialized.resize(arity, &WILDCARD);
+        specialized.extend_from_slice(&row[1..]);
+        Some(specialized)
+    }
+
+    /// Constructors of a type with finitely many shapes
+    fn constructors(&self, type_: &Type) -> Option<Vec<Constructor>> {
+        match type_ {
+            Type::Bool => Some([true, false].iter()
+                .map(|value| Constructor {
+                    name: value.to_string(),
+                    display: value.to_string(),
+                    sub_types: Vec::new(),
+                    field_names: None,
+                })
+                .collect()),
+            // A tuple has one shape, printed as `(a, b)`
+            Type::Tuple(types) => Some(vec![Constructor {
+                name: "()".to_string(),
+                display: String::new(),
+                sub_types: types.clone(),
+                field_names: None,
+            }]),
+            Type::Result(ok, err) => Some(vec![
+                Constructor { name: "Ok".to_string(), display: "Ok".to_string(), sub_types: vec![(**ok).clone()], field_names: None },
+                Constructor { name: "Err".to_string(), display: "Err".to_string(), sub_types: vec![(**err).clone()], field_names: None },
+            ]),
+            Type::Named(name) => {
+                if let Some(enum_def) = self.enums.get(name) {
+                    return Some(enum_def.variants.iter()
+                        .map(|variant| {
+                            let (sub_types, field_names) = match &variant.payload {
+                                VariantPayload::Unit => (Vec::new(), None),
+                                VariantPayload::Tuple(types) => (types.clone(), None),
+                                VariantPayload::Struct(fields) => (
+                                    fields.iter().map(|f| f.type_.clone()).collect(),
+                                    Some(fields.iter().map(|f| f.name.clone()).collect()),
+                                ),
+                            };
+                            Constructor {
+                                name: variant.name.clone(),
+                                display: format!("{}::{}", name, variant.name),
+                                sub_types,
+                                field_names,
+                            }
+                        })
+                        .collect());
+                }
+
+                let fields = self.fields.get(name)?;
+                Some(vec![Constructor {
+                    name: last_segment(name).to_string(),
+                    display: name.clone(),
+                    sub_types: fields.iter().map(|f| f.type_.clone()).collect(),
+                    field_names: Some(fields.iter().map(|f| f.name.clone()).collect()),
+                }])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Name of the constructor a pattern selects, if it is not a catch-all
+fn head_name(pattern: &Pattern) -> Option<String> {
+    match pattern {
+        Pattern::Variant(path, _) => path.last().cloned(),
+        Pattern::Struct(name, _) => Some(last_segment(name).to_string()),
+        Pattern::Tuple(_) => Some("()".to_string()),
+        Pattern::Literal(Literal::Boolean(value), _) => Some(value.to_string()),
+        Pattern::Result(ResultPattern::Ok(_)) => Some("Ok".to_string()),
+        Pattern::Result(ResultPattern::Err(_)) => Some("Err".to_string()),
+        _ => None,
+    }
+}
+
+fn is_catch_all(pattern: &Pattern) -> bool {
+    matches!(pattern, Pattern::Wildcard | Pattern::Identifier(_))
+}
+
+fn last_segment(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn prepend(head: String, mut rest: Vec<String>) -> Vec<String> {
+    rest.insert(0, head);
+    rest
+}
+
+fn render(constructor: &Constructor, payload: &[String]) -> String {
+    match &constructor.field_names {
+        Some(names) if !names.is_empty() => {
+            let fields: Vec<String> = names.iter()
+                .zip(payload)
+                .map(|(name, witness)| format!("{}: {}", name, witness))
+                .collect();
+            format!("{} {{ {} }}", constructor.display, fields.join(", "))
+        }
+        _ if payload.is_empty() => constructor.display.clone(),
+        _ => format!("{}({})", constructor.display, payload.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::{Spelling, Variant, Visibility};
+    use crate::position::{Position, Span};
+
+    fn span() -> Span {
+        Span::single(Position::start())
+    }
+
+    fn shape() -> Enum {
+        Enum {
+            name: "Shape".to_string(),
+            variants: vec![
+                Variant { name: "Circle".to_string(), payload: VariantPayload::Tuple(vec![Type::Float]), doc: None, span: span() },
+                Variant {
+                    name: "Rect".to_string(),
+                    payload: VariantPayload::Struct(vec![
+                        Field { name: "w".to_string(), type_: Type::Float, visibility: Visibility::Private, is_mutable: false, doc: None, span: span() },
+                        Field { name: "h".to_string(), type_: Type::Float, visibility: Visibility::Private, is_mutable: false, doc: None, span: span() },
+                    ]),
+                    doc: None,
+                    span: span(),
+                },
+                Variant { name: "Empty".to_string(), payload: VariantPayload::Unit, doc: None, span: span() },
+            ],
+            visibility: Visibility::Private,
+            doc: None,
+            span: span(),
+        }
+    }
+
+    fn variant(name: &str, patterns: Vec<Pattern>) -> Pattern {
+        Pattern::Variant(vec!["Shape".to_string(), name.to_string()], patterns)
+    }
+
+    #[test]
+    fn test_missing_variants() {
+        let enums = HashMap::from([("Shape".to_string(), shape())]);
+        let fields = HashMap::new();
+        let checker = ExhaustivenessChecker::new(&enums, &fields);
+        let shape_type = Type::Named("Shape".to_string());
+
+        let circle = variant("Circle", vec![Pattern::Identifier("r".to_string())]);
+        let empty = variant("Empty", vec![]);
+        assert_eq!(
+            checker.missing_patterns(&shape_type, &[&circle, &empty]),
+            vec!["Shape::Rect { w: _, h: _ }".to_string()]
+        );
+
+        let rect = Pattern::Struct("Shape::Rect".to_string(), vec![]);
+        assert!(checker.missing_patterns(&shape_type, &[&circle, &rect, &empty]).is_empty());
+        assert!(checker.missing_patterns(&shape_type, &[&Pattern::Wildcard]).is_empty());
+        assert_eq!(checker.missing_patterns(&shape_type, &[]).len(), 3);
+    }
+
+    #[test]
+    fn test_nested_and_primitive_patterns() {
+        let enums = HashMap::new();
+        let fields = HashMap::new();
+        let checker = ExhaustivenessChecker::new(&enums, &fields);
+
+        let yes = Pattern::Literal(Literal::Boolean(true), Spelling::default());
+        assert_eq!(checker.missing_patterns(&Type::Bool, &[&yes]), vec!["false".to_string()]);
+
+        // Integers have no finite set of constructors
+        let zero = Pattern::Literal(Literal::Integer(0), Spelling::default());
+        assert_eq!(checker.missing_patterns(&Type::Int, &[&zero]), vec!["_".to_string()]);
+
+        let result_type = Type::Result(Box::new(Type::Bool), Box::new(Type::String));
+        let ok_true = Pattern::Result(ResultPattern::Ok(Box::new(yes.clone())));
+        let err = Pattern::Result(ResultPattern::Err(Box::new(Pattern::Wildcard)));
+        assert_eq!(checker.missing_patterns(&result_type, &[&ok_true, &err]), vec!["Ok(false)".to_string()]);
+
+        let pair_type = Type::Tuple(vec![Type::Bool, Type::Int]);
+        let first_true = Pattern::Tuple(vec![yes.clone(), Pattern::Wildcard]);
+        assert_eq!(checker.missing_patterns(&pair_type, &[&first_true]), vec!["(false, _)".to_string()]);
+        let any_pair = Pattern::Tuple(vec![Pattern::Identifier("a".to_string()), Pattern::Wildcard]);
+        assert!(checker.missing_patterns(&pair_type, &[&any_pair]).is_empty());
+    }
+}
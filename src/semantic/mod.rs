@@ -4,16 +4,21 @@
 
 use crate::error::{SemanticError, SemanticErrorKind};
 use crate::parser::ast::{
-    Program, Item, Function, Struct, Class, Const, Import, Visibility,
-    Type, Pattern, Literal, BinaryOp, UnaryOp, Block, Expression, Statement, ResultPattern
+    Program, Item, Function, Struct, Class, Impl, Const, Import, Visibility,
+    Type, Pattern, Literal, BinaryOp, UnaryOp, Block, Expression, ExpressionKind, Statement, StatementKind, MatchArm, ResultPattern, StringPart
 };
 use crate::semantic::symbol_table::ScopeType;
 use crate::position::Span;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+pub mod captures;
+pub mod control_flow;
+pub mod exhaustiveness;
+pub mod modules;
 pub mod symbol_table;
 pub mod type_checker;
 
+pub use modules::*;
 pub use symbol_table::*;
 pub use type_checker::*;
 
@@ -36,6 +41,11 @@ pub trait SemanticAnalyzer {
 pub struct FluxSemanticAnalyzer {
     symbol_table: SymbolTable,
     type_checker: TypeChecker,
+    /// Name resolution errors in items and statements, which are resolved
+    /// past their first error
+    errors: Vec<SemanticError>,
+    /// Diagnostics that do not stop compilation, such as unreachable code
+    warnings: Vec<SemanticError>,
 }
 
 impl FluxSemanticAnalyzer {
@@ -44,6 +54,65 @@ impl FluxSemanticAnalyzer {
         Self {
             symbol_table: SymbolTable::new(),
             type_checker: TypeChecker::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl FluxSemanticAnalyzer {
+    /// Make an imported module available under its binding name
+    pub fn register_module(&mut self, name: &str, exports: ModuleExports) -> Result<(), SemanticError> {
+        self.type_checker.declare_module(name, &exports);
+        self.symbol_table.define_module(name.to_string(), exports)
+    }
+    
+    /// Analyze statements entered at the top level of an interactive session.
+    /// Bindings persist across calls, each call opening a scope of its own.
+    pub fn analyze_statements(&mut self, statements: &[Statement]) -> Result<Vec<TypedStatement>, SemanticError> {
+        self.symbol_table.enter_scope(ScopeType::Block);
+        for stmt in statements {
+            self.resolve_statement(stmt);
+        }
+        if let Some(error) = self.take_errors().into_iter().next() {
+            return Err(error);
+        }
+        self.type_checker.check_statements(statements)
+    }
+    
+    /// Analyze a program without stopping at the first error. Each item and
+    /// statement is analyzed past the errors of the ones before it; bindings
+    /// that fail to check are poisoned so their uses are not reported again.
+    /// Returns the typed program for the items that checked, along with all
+    /// errors sorted by span
+    pub fn analyze_with_errors(&mut self, mut program: Program) -> (TypedProgram, Vec<SemanticError>) {
+        self.resolve_items(&mut program);
+        let typed_program = self.check_items(&program);
+        self.validate_items(&typed_program);
+        (typed_program, self.take_errors())
+    }
+    
+    /// Warnings found by the analyses run so far, sorted by span
+    pub fn warnings(&self) -> &[SemanticError] {
+        &self.warnings
+    }
+    
+    /// Take the errors of all phases, in source order
+    fn take_errors(&mut self) -> Vec<SemanticError> {
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.extend(self.type_checker.take_errors());
+        errors.sort_by_key(|error| (error.span.start.offset, error.span.end.offset));
+        // Name resolution and type checking both report some mistakes, such
+        // as an undefined name; each location is reported once
+        errors.dedup_by(|later, earlier| later.span == earlier.span);
+        errors
+    }
+    
+    /// Fail with the first error of the phase just run, if any
+    fn first_error(&mut self) -> Result<(), SemanticError> {
+        match self.take_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
         }
     }
 }
@@ -55,114 +124,166 @@ impl Default for FluxSemanticAnalyzer {
 }
 
 impl SemanticAnalyzer for FluxSemanticAnalyzer {
-    fn analyze(&mut self, mut program: Program) -> Result<TypedProgram, SemanticError> {
-        // Phase 1: Name resolution
-        self.resolve_names(&mut program)?;
-        
-        // Phase 2: Type checking
-        let typed_program = self.check_types(&program)?;
-        
-        // Phase 3: Semantic validation
-        self.validate_semantics(&typed_program)?;
-        
-        Ok(typed_program)
+    fn analyze(&mut self, program: Program) -> Result<TypedProgram, SemanticError> {
+        // Name resolution, type checking and semantic validation all run,
+        // and the first error in the source is reported
+        let (typed_program, errors) = self.analyze_with_errors(program);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(typed_program),
+        }
     }
     
     fn resolve_names(&mut self, program: &mut Program) -> Result<(), SemanticError> {
+        self.resolve_items(program);
+        self.first_error()
+    }
+    
+    fn check_types(&mut self, program: &Program) -> Result<TypedProgram, SemanticError> {
+        let typed_program = self.check_items(program);
+        self.first_error()?;
+        Ok(typed_program)
+    }
+    
+    fn validate_semantics(&mut self, program: &TypedProgram) -> Result<(), SemanticError> {
+        self.validate_items(program);
+        self.first_error()
+    }
+}
+
+impl FluxSemanticAnalyzer {
+    /// Resolve names in every item, recording errors
+    fn resolve_items(&mut self, program: &mut Program) {
         // First pass: Define all top-level items in global scope
         for item in &program.items {
-            match item {
+            let defined = match item {
                 Item::Function(func) => {
-                    self.symbol_table.define_function(func.name.clone(), func.clone())?;
+                    self.symbol_table.define_function(func.name.clone(), func.clone())
                 }
                 Item::Struct(struct_def) => {
-                    self.symbol_table.define_struct(struct_def.name.clone(), struct_def.clone())?;
+                    self.symbol_table.define_struct(struct_def.name.clone(), struct_def.clone())
                 }
                 Item::Class(class_def) => {
-                    self.symbol_table.define_class(class_def.name.clone(), class_def.clone())?;
+                    self.symbol_table.define_class(class_def.name.clone(), class_def.clone())
+                }
+                Item::Enum(enum_def) => {
+                    self.symbol_table.define_enum(enum_def.name.clone(), enum_def.clone())
+                }
+                Item::Interface(interface) => {
+                    self.symbol_table.define_interface(interface.name.clone(), interface.clone())
+                }
+                Item::Impl(_) => {
+                    // Impl blocks add methods to an existing type and declare no name
+                    Ok(())
                 }
                 Item::Const(const_def) => {
-                    self.symbol_table.define_const(const_def.name.clone(), const_def.clone())?;
+                    self.symbol_table.define_const(const_def.name.clone(), const_def.clone())
                 }
                 Item::ExternFunction(extern_func) => {
                     // Register extern function in symbol table
-                    self.symbol_table.define_extern_function(extern_func.name.clone(), extern_func.clone())?;
+                    self.symbol_table.define_extern_function(extern_func.name.clone(), extern_func.clone())
                 }
+            };
+            if let Err(error) = defined {
+                self.errors.push(error.with_span(item.span()));
             }
         }
         
         // Second pass: Resolve names within each item
+        let mut implemented = HashSet::new();
         for item in &program.items {
-            match item {
-                Item::Function(func) => {
-                    self.resolve_function_names(func)?;
-                }
-                Item::Struct(struct_def) => {
-                    self.resolve_struct_names(struct_def)?;
-                }
-                Item::Class(class_def) => {
-                    self.resolve_class_names(class_def)?;
-                }
-                Item::Const(const_def) => {
-                    self.resolve_const_names(const_def)?;
-                }
-                Item::ExternFunction(_extern_func) => {
-                    // Extern functions don't have bodies to resolve
+            let depth = self.symbol_table.scope_depth();
+            let resolved = match item {
+                Item::Function(func) => self.resolve_function_names(func),
+                Item::Struct(struct_def) => self.resolve_struct_names(struct_def),
+                Item::Class(class_def) => self.resolve_class_names(class_def),
+                // Variant payload types are plain type annotations
+                Item::Enum(_enum_def) => Ok(()),
+                // Interfaces only declare signatures
+                Item::Interface(_interface) => Ok(()),
+                Item::Impl(impl_block) => {
+                    if implemented.insert((impl_block.interface.clone(), impl_block.type_name.clone())) {
+                        self.resolve_impl_names(impl_block)
+                    } else {
+                        Err(SemanticError {
+                            span: impl_block.span,
+                            kind: SemanticErrorKind::DuplicateDefinition {
+                                name: format!("impl {} for {}", impl_block.interface, impl_block.type_name),
+                            },
+                        })
+                    }
                 }
+                Item::Const(const_def) => self.resolve_const_names(const_def),
+                // Extern functions don't have bodies to resolve
+                Item::ExternFunction(_extern_func) => Ok(()),
+            };
+            if let Err(error) = resolved {
+                self.restore_scope(depth);
+                self.errors.push(error);
             }
         }
-        
-        Ok(())
     }
     
-    fn check_types(&mut self, program: &Program) -> Result<TypedProgram, SemanticError> {
+    /// Type check every item, recording errors in the type checker
+    fn check_items(&mut self, program: &Program) -> TypedProgram {
         let mut typed_items = Vec::new();
         
+        // Declare signatures first so items can refer to each other in any order
+        self.type_checker.declare_items(&program.items);
+        
         for item in &program.items {
-            match item {
-                Item::Function(func) => {
-                    let typed_func = self.type_checker.check_function(func)?;
-                    typed_items.push(TypedItem::Function(typed_func));
-                }
-                Item::Struct(struct_def) => {
-                    let typed_struct = self.type_checker.check_struct(struct_def)?;
-                    typed_items.push(TypedItem::Struct(typed_struct));
-                }
-                Item::Class(class_def) => {
-                    let typed_class = self.type_checker.check_class(class_def)?;
-                    typed_items.push(TypedItem::Class(typed_class));
-                }
-                Item::Const(const_def) => {
-                    let typed_const = self.type_checker.check_const(const_def)?;
-                    typed_items.push(TypedItem::Const(typed_const));
-                }
+            let typed_item = match item {
+                Item::Function(func) => self.type_checker.check_function(func).map(TypedItem::Function),
+                Item::Struct(struct_def) => self.type_checker.check_struct(struct_def).map(TypedItem::Struct),
+                Item::Class(class_def) => self.type_checker.check_class(class_def).map(TypedItem::Class),
+                Item::Enum(enum_def) => self.type_checker.check_enum(enum_def).map(TypedItem::Enum),
+                Item::Interface(interface) => self.type_checker.check_interface(interface).map(TypedItem::Interface),
+                Item::Impl(impl_block) => self.type_checker.check_impl(impl_block).map(TypedItem::Impl),
+                Item::Const(const_def) => self.type_checker.check_const(const_def).map(TypedItem::Const),
+                // Extern functions don't need type checking, just validation
                 Item::ExternFunction(extern_func) => {
-                    // Extern functions don't need type checking, just validation
-                    let typed_extern = self.type_checker.check_extern_function(extern_func)?;
-                    typed_items.push(TypedItem::ExternFunction(typed_extern));
+                    self.type_checker.check_extern_function(extern_func).map(TypedItem::ExternFunction)
                 }
+            };
+            match typed_item {
+                Ok(typed_item) => typed_items.push(typed_item),
+                Err(error) => self.type_checker.report(error),
             }
         }
         
-        Ok(TypedProgram {
+        TypedProgram {
             package: program.package.clone(),
             imports: program.imports.clone(),
             items: typed_items,
-        })
+        }
     }
     
-    fn validate_semantics(&mut self, _program: &TypedProgram) -> Result<(), SemanticError> {
-        // Placeholder for semantic validation
-        // This would check things like:
-        // - All code paths return a value
-        // - No unreachable code
-        // - Proper error handling
-        // - etc.
-        Ok(())
+    /// Validate control flow in every item, recording errors and warnings
+    fn validate_items(&mut self, program: &TypedProgram) {
+        let mut validator = control_flow::ControlFlowValidator::new();
+        validator.validate_program(program);
+        let (errors, warnings) = validator.into_diagnostics();
+        self.errors.extend(errors);
+        self.warnings.extend(warnings);
+        self.warnings.sort_by_key(|warning| (warning.span.start.offset, warning.span.end.offset));
+    }
+    
+    /// Leave the scopes entered by an item or statement that failed to resolve
+    fn restore_scope(&mut self, depth: usize) {
+        while self.symbol_table.scope_depth() > depth {
+            self.symbol_table.exit_scope();
+        }
+    }
+    
+    /// Resolve names in a statement, recording its error
+    fn resolve_statement(&mut self, stmt: &Statement) {
+        let depth = self.symbol_table.scope_depth();
+        if let Err(error) = self.resolve_statement_names(stmt) {
+            self.restore_scope(depth);
+            self.errors.push(error);
+        }
     }
-}
 
-impl FluxSemanticAnalyzer {
     fn resolve_function_names(&mut self, func: &Function) -> Result<(), SemanticError> {
         // Enter function scope
         self.symbol_table.enter_function_scope(func.name.clone());
@@ -174,7 +295,7 @@ impl FluxSemanticAnalyzer {
                 param.type_.clone(),
                 param.is_mutable,
                 index
-            )?;
+            ).map_err(|e| e.with_span(param.span))?;
         }
         
         // Resolve names in function body
@@ -195,20 +316,46 @@ impl FluxSemanticAnalyzer {
     fn resolve_class_names(&mut self, class_def: &Class) -> Result<(), SemanticError> {
         // Resolve method names
         for method in &class_def.methods {
-            self.resolve_method_names(method)?;
+            self.resolve_method_names(method, &class_def.name)?;
         }
         Ok(())
     }
     
-    fn resolve_method_names(&mut self, method: &crate::parser::ast::Method) -> Result<(), SemanticError> {
+    fn resolve_impl_names(&mut self, impl_block: &Impl) -> Result<(), SemanticError> {
+        if !matches!(self.symbol_table.lookup(&impl_block.interface), Some(Symbol::Interface(_))) {
+            return Err(SemanticError {
+                span: impl_block.span,
+                kind: SemanticErrorKind::UndefinedVariable { name: impl_block.interface.clone() },
+            });
+        }
+        
+        match self.symbol_table.lookup(&impl_block.type_name) {
+            Some(Symbol::Struct(_)) | Some(Symbol::Class(_)) | Some(Symbol::Enum(_)) => {}
+            _ => return Err(SemanticError {
+                span: impl_block.span,
+                kind: SemanticErrorKind::UndefinedVariable { name: impl_block.type_name.clone() },
+            }),
+        }
+        
+        for method in &impl_block.methods {
+            self.resolve_method_names(method, &impl_block.type_name)?;
+        }
+        Ok(())
+    }
+    
+    fn resolve_method_names(&mut self, method: &crate::parser::ast::Method, class_name: &str) -> Result<(), SemanticError> {
         // Enter function scope for method
         self.symbol_table.enter_function_scope(method.name.clone());
         
         // Define parameters (including implicit 'self' if not static)
         let mut param_index = 0;
         if !method.is_static {
-            // Add implicit 'self' parameter
-            // Note: In a real implementation, we'd need the class type here
+            self.symbol_table.define_parameter(
+                "self".to_string(),
+                Type::Named(class_name.to_string()),
+                false,
+                param_index
+            ).map_err(|e| e.with_span(method.span))?;
             param_index += 1;
         }
         
@@ -218,7 +365,7 @@ impl FluxSemanticAnalyzer {
                 param.type_.clone(),
                 param.is_mutable,
                 param_index
-            )?;
+            ).map_err(|e| e.with_span(param.span))?;
             param_index += 1;
         }
         
@@ -241,9 +388,9 @@ impl FluxSemanticAnalyzer {
         // Enter block scope
         self.symbol_table.enter_scope(ScopeType::Block);
         
-        // Resolve each statement
+        // Resolve each statement, continuing past errors
         for stmt in &block.statements {
-            self.resolve_statement_names(stmt)?;
+            self.resolve_statement(stmt);
         }
         
         // Exit block scope
@@ -253,53 +400,70 @@ impl FluxSemanticAnalyzer {
     }
     
     fn resolve_statement_names(&mut self, stmt: &Statement) -> Result<(), SemanticError> {
-        match stmt {
-            Statement::Expression(expr) => {
+        match &stmt.kind {
+            StatementKind::Expression(expr) => {
                 self.resolve_expression_names(expr)?;
             }
-            Statement::Let(name, type_annotation, init) => {
-                // Resolve initializer first (if present)
-                if let Some(init_expr) = init {
-                    self.resolve_expression_names(init_expr)?;
-                }
-                
-                // Define the variable
-                let var_type = type_annotation.clone().unwrap_or(Type::Unit); // Placeholder
-                self.symbol_table.define_variable(name.clone(), var_type, true)?;
+            StatementKind::Let(pattern, type_annotation, init) => {
+                // Resolve initializer first (if present); the variables are
+                // defined even if it fails, so their uses are not reported
+                let resolved = match init {
+                    Some(init_expr) => self.resolve_expression_names(init_expr),
+                    None => Ok(()),
+                };
                 
-                // Mark as initialized if there's an initializer
-                if init.is_some() {
-                    self.symbol_table.mark_initialized(name)?;
+                match pattern {
+                    Pattern::Identifier(name) => {
+                        // Define the variable
+                        let var_type = type_annotation.clone().unwrap_or(Type::Unit); // Placeholder
+                        self.symbol_table.define_variable(name.clone(), var_type, true)
+                            .map_err(|e| e.with_span(stmt.span))?;
+                        
+                        // Mark as initialized if there's an initializer
+                        if init.is_some() {
+                            self.symbol_table.mark_initialized(name)?;
+                        }
+                    }
+                    // Destructuring always has a value
+                    _ => self.resolve_pattern_names(pattern, true).map_err(|e| e.with_span(stmt.span))?,
                 }
+                resolved?;
             }
-            Statement::Const(name, type_, value) => {
+            StatementKind::Const(name, type_, value) => {
                 // Resolve the value expression
                 self.resolve_expression_names(value)?;
                 
                 // Define the constant
-                self.symbol_table.define_variable(name.clone(), type_.clone(), false)?;
+                self.symbol_table.define_variable(name.clone(), type_.clone(), false)
+                    .map_err(|e| e.with_span(stmt.span))?;
                 self.symbol_table.mark_initialized(name)?;
             }
-            Statement::Assignment(target, value) => {
-                // Resolve both sides
-                self.resolve_expression_names(target)?;
+            StatementKind::Assignment(target, value) => {
+                // Assigning a variable is not a use of it, so a variable
+                // declared without a value may be initialized here
                 self.resolve_expression_names(value)?;
+                if !matches!(target.kind, ExpressionKind::Identifier(_)) {
+                    self.resolve_expression_names(target)?;
+                }
                 
                 // Check if target is assignable (if it's an identifier)
-                if let Expression::Identifier(name) = target {
-                    if !self.symbol_table.can_assign(name)? {
+                if let ExpressionKind::Identifier(name) = &target.kind {
+                    let can_assign = self.symbol_table.can_assign(name).map_err(|e| e.with_span(target.span))?;
+                    if !can_assign {
+                        let help = match self.symbol_table.lookup(name) {
+                            Some(Symbol::Parameter { .. }) => format!("declare the parameter as `mut {}`", name),
+                            _ => "copy it into a `let` binding to change it".to_string(),
+                        };
                         return Err(SemanticError {
-                            span: Span::single(crate::position::Position::start()),
-                            kind: SemanticErrorKind::InvalidOperation {
-                                message: format!("Cannot assign to immutable variable '{}'", name),
-                            },
+                            span: stmt.span,
+                            kind: SemanticErrorKind::AssignToImmutable { name: name.clone(), help },
                         });
                     }
                     // Mark as initialized after assignment
                     self.symbol_table.mark_initialized(name)?;
                 }
             }
-            Statement::Return(expr) => {
+            StatementKind::Return(expr) => {
                 if let Some(e) = expr {
                     self.resolve_expression_names(e)?;
                 }
@@ -307,14 +471,14 @@ impl FluxSemanticAnalyzer {
                 // Check if we're in a function
                 if !self.symbol_table.in_function() {
                     return Err(SemanticError {
-                        span: Span::single(crate::position::Position::start()),
+                        span: stmt.span,
                         kind: SemanticErrorKind::InvalidOperation {
                             message: "Return statement outside function".to_string(),
                         },
                     });
                 }
             }
-            Statement::Break(expr) => {
+            StatementKind::Break(expr) => {
                 if let Some(e) = expr {
                     self.resolve_expression_names(e)?;
                 }
@@ -322,74 +486,56 @@ impl FluxSemanticAnalyzer {
                 // Check if we're in a loop
                 if !self.symbol_table.in_loop() {
                     return Err(SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::InvalidOperation {
-                            message: "Break statement outside loop".to_string(),
-                        },
+                        span: stmt.span,
+                        kind: SemanticErrorKind::BreakOutsideLoop,
                     });
                 }
             }
-            Statement::Continue => {
+            StatementKind::Continue => {
                 // Check if we're in a loop
                 if !self.symbol_table.in_loop() {
                     return Err(SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::InvalidOperation {
-                            message: "Continue statement outside loop".to_string(),
-                        },
+                        span: stmt.span,
+                        kind: SemanticErrorKind::ContinueOutsideLoop,
                     });
                 }
             }
-            Statement::Go(expr) => {
+            StatementKind::Go(expr) => {
                 self.resolve_expression_names(expr)?;
             }
-            Statement::If(cond, then_block, else_block) => {
+            StatementKind::If(cond, then_block, else_block) => {
                 self.resolve_expression_names(cond)?;
-                self.resolve_block_names(then_block)?;
-                if let Some(else_b) = else_block {
-                    self.resolve_block_names(else_b)?;
-                }
+                self.resolve_if_branches(then_block, else_block.as_ref())?;
             }
-            Statement::While(cond, body) => {
+            StatementKind::While(cond, body) => {
                 self.resolve_expression_names(cond)?;
                 
-                // Enter loop scope
+                // Enter loop scope; the body may not run at all, so its
+                // assignments do not initialize variables after the loop
+                let before = self.symbol_table.uninitialized_variables();
                 self.symbol_table.enter_scope(ScopeType::Loop);
                 self.resolve_block_names(body)?;
                 self.symbol_table.exit_scope();
+                self.symbol_table.restore_initialized(&before);
             }
-            Statement::For(var, iter, body) => {
+            StatementKind::For(pattern, iter, body) => {
                 // Resolve iterator expression
                 self.resolve_expression_names(iter)?;
                 
                 // Enter loop scope and define loop variable
+                let before = self.symbol_table.uninitialized_variables();
                 self.symbol_table.enter_scope(ScopeType::Loop);
                 
-                // Define loop variable (type would be inferred from iterator)
-                self.symbol_table.define_variable(var.clone(), Type::Unit, false)?; // Placeholder type
-                self.symbol_table.mark_initialized(var)?;
+                // Define loop variables (types would be inferred from iterator)
+                self.resolve_pattern_names(pattern, false).map_err(|e| e.with_span(stmt.span))?;
                 
                 self.resolve_block_names(body)?;
                 self.symbol_table.exit_scope();
+                self.symbol_table.restore_initialized(&before);
             }
-            Statement::Match(expr, arms) => {
+            StatementKind::Match(expr, arms) => {
                 self.resolve_expression_names(expr)?;
-                
-                for arm in arms {
-                    // Enter match scope for pattern bindings
-                    self.symbol_table.enter_scope(ScopeType::Match);
-                    
-                    // Define pattern variables (simplified)
-                    self.resolve_pattern_names(&arm.pattern)?;
-                    
-                    if let Some(guard) = &arm.guard {
-                        self.resolve_expression_names(guard)?;
-                    }
-                    
-                    self.resolve_block_names(&arm.body)?;
-                    
-                    self.symbol_table.exit_scope();
-                }
+                self.resolve_match_arms(arms)?;
             }
         }
         
@@ -397,124 +543,289 @@ impl FluxSemanticAnalyzer {
     }
     
     fn resolve_expression_names(&mut self, expr: &Expression) -> Result<(), SemanticError> {
-        match expr {
-            Expression::Literal(_) => {
+        match &expr.kind {
+            ExpressionKind::Literal(_) => {
                 // Literals don't have names to resolve
                 Ok(())
             }
-            Expression::Identifier(name) => {
+            ExpressionKind::Identifier(name) => {
+                // Builtins live in the type environment and can be shadowed
+                if self.symbol_table.lookup(name).is_none() && type_checker::BUILTINS.contains(&name.as_str()) {
+                    return Ok(());
+                }
+                
                 // Resolve the identifier
-                self.symbol_table.resolve_name(name)?;
+                self.symbol_table.resolve_name(name)
+                    .map_err(|e| e.with_span(expr.span))?;
                 Ok(())
             }
-            Expression::Binary(left, _op, right) => {
+            ExpressionKind::Path(segments) => {
+                // Qualified names refer to static methods, enum variants, or
+                // public items of imported modules
+                if let [type_name, member] = segments.as_slice() {
+                    match self.symbol_table.lookup(type_name) {
+                        Some(Symbol::Class(_)) => {
+                            self.symbol_table.resolve_static_method(type_name, member)
+                                .map_err(|e| e.with_span(expr.span))?;
+                            return Ok(());
+                        }
+                        Some(Symbol::Enum(_)) => {
+                            self.symbol_table.resolve_variant(type_name, member)
+                                .map_err(|e| e.with_span(expr.span))?;
+                            return Ok(());
+                        }
+                        _ => {}
+                    }
+                }
+                self.symbol_table.resolve_path(segments)
+                    .map_err(|e| e.with_span(expr.span))?;
+                Ok(())
+            }
+            ExpressionKind::StructLiteral(name, fields) => {
+                self.resolve_type_name(name)
+                    .map_err(|e| e.with_span(expr.span))?;
+                for (_field_name, value) in fields {
+                    self.resolve_expression_names(value)?;
+                }
+                Ok(())
+            }
+            ExpressionKind::Binary(left, _op, right) => {
                 self.resolve_expression_names(left)?;
                 self.resolve_expression_names(right)?;
                 Ok(())
             }
-            Expression::Unary(_op, expr) => {
+            ExpressionKind::Unary(_op, expr) => {
                 self.resolve_expression_names(expr)?;
                 Ok(())
             }
-            Expression::Call(func, args) => {
+            ExpressionKind::Call(func, args) => {
                 self.resolve_expression_names(func)?;
                 for arg in args {
                     self.resolve_expression_names(arg)?;
                 }
                 Ok(())
             }
-            Expression::Index(array, index) => {
+            ExpressionKind::Index(array, index) => {
                 self.resolve_expression_names(array)?;
                 self.resolve_expression_names(index)?;
                 Ok(())
             }
-            Expression::Field(obj, _field) => {
+            ExpressionKind::Field(obj, _field) => {
                 self.resolve_expression_names(obj)?;
                 // Field resolution would require type information
                 Ok(())
             }
-            Expression::Match(expr, arms) => {
+            ExpressionKind::Match(expr, arms) => {
                 self.resolve_expression_names(expr)?;
-                
-                for arm in arms {
-                    self.symbol_table.enter_scope(ScopeType::Match);
-                    self.resolve_pattern_names(&arm.pattern)?;
-                    
-                    if let Some(guard) = &arm.guard {
-                        self.resolve_expression_names(guard)?;
-                    }
-                    
-                    self.resolve_block_names(&arm.body)?;
-                    self.symbol_table.exit_scope();
-                }
-                Ok(())
+                self.resolve_match_arms(arms)
             }
-            Expression::If(cond, then_block, else_block) => {
+            ExpressionKind::If(cond, then_block, else_block) => {
                 self.resolve_expression_names(cond)?;
-                self.resolve_block_names(then_block)?;
-                if let Some(else_b) = else_block {
-                    self.resolve_block_names(else_b)?;
-                }
-                Ok(())
+                self.resolve_if_branches(then_block, else_block.as_ref())
             }
-            Expression::Block(block) => {
+            ExpressionKind::Block(block) => {
                 self.resolve_block_names(block)?;
                 Ok(())
             }
-            Expression::Array(elements) => {
+            ExpressionKind::Array(elements) => {
                 for elem in elements {
                     self.resolve_expression_names(elem)?;
                 }
                 Ok(())
             }
-            Expression::Map(pairs) => {
+            ExpressionKind::Map(pairs) => {
                 for (key, value) in pairs {
                     self.resolve_expression_names(key)?;
                     self.resolve_expression_names(value)?;
                 }
                 Ok(())
             }
-            Expression::Tuple(elements) => {
+            ExpressionKind::Tuple(elements) => {
                 for elem in elements {
                     self.resolve_expression_names(elem)?;
                 }
                 Ok(())
             }
+            ExpressionKind::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expression(expr) = part {
+                        self.resolve_expression_names(expr)?;
+                    }
+                }
+                Ok(())
+            }
+            ExpressionKind::Range(start, end, step, _inclusive) => {
+                self.resolve_expression_names(start)?;
+                self.resolve_expression_names(end)?;
+                if let Some(step) = step {
+                    self.resolve_expression_names(step)?;
+                }
+                Ok(())
+            }
+            ExpressionKind::Lambda(params, _return_type, body) => {
+                // A lambda body is a function scope of its own; enclosing
+                // variables stay visible and become captures
+                self.symbol_table.enter_function_scope("<closure>".to_string());
+                for (index, param) in params.iter().enumerate() {
+                    self.symbol_table.define_parameter(
+                        param.name.clone(),
+                        param.type_.clone().unwrap_or(Type::Unit), // Placeholder when inferred
+                        param.is_mutable,
+                        index
+                    ).map_err(|e| e.with_span(param.span))?;
+                }
+                let result = self.resolve_expression_names(body);
+                self.symbol_table.exit_scope();
+                result
+            }
+        }
+    }
+    
+    /// Resolve the branches of an `if`. A variable is initialized after it
+    /// only if both branches assign it; a missing `else` assigns nothing
+    fn resolve_if_branches(&mut self, then_block: &Block, else_block: Option<&Block>) -> Result<(), SemanticError> {
+        let before = self.symbol_table.uninitialized_variables();
+        let mut outcomes = Vec::new();
+        
+        self.resolve_block_names(then_block)?;
+        self.end_branch(&before, then_block, &mut outcomes);
+        match else_block {
+            Some(else_block) => {
+                self.resolve_block_names(else_block)?;
+                self.end_branch(&before, else_block, &mut outcomes);
+            }
+            None => outcomes.push(before),
+        }
+        
+        self.join_branches(outcomes);
+        Ok(())
+    }
+    
+    /// Resolve the arms of a `match`, each in its own scope for the pattern
+    /// bindings. A variable is initialized after it only if every arm assigns it
+    fn resolve_match_arms(&mut self, arms: &[MatchArm]) -> Result<(), SemanticError> {
+        let before = self.symbol_table.uninitialized_variables();
+        let mut outcomes = Vec::new();
+        
+        for arm in arms {
+            self.symbol_table.enter_scope(ScopeType::Match);
+            self.resolve_pattern_names(&arm.pattern, false)
+                .map_err(|e| e.with_span(arm.span))?;
+            
+            if let Some(guard) = &arm.guard {
+                self.resolve_expression_names(guard)?;
+            }
+            
+            self.resolve_block_names(&arm.body)?;
+            self.symbol_table.exit_scope();
+            self.end_branch(&before, &arm.body, &mut outcomes);
+        }
+        
+        if arms.is_empty() {
+            outcomes.push(before);
+        }
+        self.join_branches(outcomes);
+        Ok(())
+    }
+    
+    /// Record the variables a branch leaves uninitialized, then rewind to
+    /// the state before the branch. A branch that leaves the enclosing code
+    /// through `return`, `break` or `continue` does not reach the join
+    fn end_branch(&mut self, before: &InitState, body: &Block, outcomes: &mut Vec<InitState>) {
+        if !block_diverges(body) {
+            outcomes.push(self.symbol_table.uninitialized_variables());
+        }
+        self.symbol_table.restore_initialized(before);
+    }
+    
+    /// Continue after branches with each variable uninitialized if any
+    /// branch reaching this point left it uninitialized
+    fn join_branches(&mut self, outcomes: Vec<InitState>) {
+        let joined: InitState = outcomes.into_iter().flatten().collect();
+        self.symbol_table.restore_initialized(&joined);
+    }
+    
+    /// Resolve the name used by a struct literal or struct pattern: a struct,
+    /// a class, an enum variant, or a public type of an imported module
+    fn resolve_type_name(&self, name: &str) -> Result<(), SemanticError> {
+        let segments: Vec<String> = name.split("::").map(str::to_string).collect();
+        match segments.as_slice() {
+            [type_name] => match self.symbol_table.lookup(type_name) {
+                Some(Symbol::Struct(_)) | Some(Symbol::Class(_)) => Ok(()),
+                _ => Err(SemanticError {
+                    span: Span::single(crate::position::Position::start()),
+                    kind: SemanticErrorKind::UndefinedVariable { name: name.to_string() },
+                }),
+            },
+            [enum_name, variant_name] if matches!(self.symbol_table.lookup(enum_name), Some(Symbol::Enum(_))) => {
+                self.symbol_table.resolve_variant(enum_name, variant_name).map(|_| ())
+            }
+            _ => self.symbol_table.resolve_path(&segments).map(|_| ()),
         }
     }
     
-    fn resolve_pattern_names(&mut self, pattern: &Pattern) -> Result<(), SemanticError> {
+    /// Resolve the names in a pattern and define the variables it binds,
+    /// which are mutable in a destructuring `let`
+    fn resolve_pattern_names(&mut self, pattern: &Pattern, mutable: bool) -> Result<(), SemanticError> {
         match pattern {
-            Pattern::Literal(_) => Ok(()),
+            Pattern::Literal(..) => Ok(()),
             Pattern::Identifier(name) => {
                 // Pattern identifiers bind new variables
-                self.symbol_table.define_variable(name.clone(), Type::Unit, false)?; // Placeholder type
+                self.symbol_table.define_variable(name.clone(), Type::Unit, mutable)?; // Placeholder type
                 self.symbol_table.mark_initialized(name)?;
                 Ok(())
             }
             Pattern::Wildcard => Ok(()),
             Pattern::Tuple(patterns) => {
                 for p in patterns {
-                    self.resolve_pattern_names(p)?;
+                    self.resolve_pattern_names(p, mutable)?;
                 }
                 Ok(())
             }
-            Pattern::Struct(_name, fields) => {
+            Pattern::Struct(name, fields) => {
+                self.resolve_type_name(name)?;
                 for (_field_name, pattern) in fields {
-                    self.resolve_pattern_names(pattern)?;
+                    self.resolve_pattern_names(pattern, mutable)?;
+                }
+                Ok(())
+            }
+            Pattern::Variant(path, patterns) => {
+                // Unqualified variants are resolved against the scrutinee's type later
+                if let [enum_name, variant_name] = path.as_slice() {
+                    if let Some(Symbol::Enum(_)) = self.symbol_table.lookup(enum_name) {
+                        self.symbol_table.resolve_variant(enum_name, variant_name)?;
+                    }
+                }
+                for p in patterns {
+                    self.resolve_pattern_names(p, mutable)?;
                 }
                 Ok(())
             }
             Pattern::Result(result_pattern) => {
                 match result_pattern {
-                    ResultPattern::Ok(pattern) => self.resolve_pattern_names(pattern),
-                    ResultPattern::Err(pattern) => self.resolve_pattern_names(pattern),
+                    ResultPattern::Ok(pattern) => self.resolve_pattern_names(pattern, mutable),
+                    ResultPattern::Err(pattern) => self.resolve_pattern_names(pattern, mutable),
                 }
             }
         }
     }
 }
 
+/// Whether a block always leaves the enclosing code through `return`,
+/// `break` or `continue`
+fn block_diverges(block: &Block) -> bool {
+    block.statements.iter().any(|stmt| match &stmt.kind {
+        StatementKind::Return(_) | StatementKind::Break(_) | StatementKind::Continue => true,
+        StatementKind::If(_, then_block, Some(else_block)) => {
+            block_diverges(then_block) && block_diverges(else_block)
+        }
+        StatementKind::Match(_, arms) => {
+            !arms.is_empty() && arms.iter().all(|arm| block_diverges(&arm.body))
+        }
+        _ => false,
+    })
+}
+
 /// Typed version of the AST after semantic analysis
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedProgram {
@@ -528,6 +839,9 @@ pub enum TypedItem {
     Function(TypedFunction),
     Struct(TypedStruct),
     Class(TypedClass),
+    Enum(TypedEnum),
+    Interface(TypedInterface),
+    Impl(TypedImpl),
     Const(TypedConst),
     ExternFunction(TypedExternFunction),
 }
@@ -535,11 +849,14 @@ pub enum TypedItem {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedFunction {
     pub name: String,
+    /// Type parameters; a generic function is compiled once per instantiation
+    pub type_params: Vec<String>,
     pub parameters: Vec<TypedParameter>,
     pub return_type: Type,
     pub body: TypedBlock,
     pub is_async: bool,
     pub visibility: Visibility,
+    pub span: Option<crate::position::Span>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -552,6 +869,7 @@ pub struct TypedParameter {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedStruct {
     pub name: String,
+    pub type_params: Vec<String>,
     pub fields: Vec<TypedField>,
     pub visibility: Visibility,
 }
@@ -559,11 +877,27 @@ pub struct TypedStruct {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedClass {
     pub name: String,
+    pub type_params: Vec<String>,
     pub fields: Vec<TypedField>,
     pub methods: Vec<TypedMethod>,
     pub visibility: Visibility,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedEnum {
+    pub name: String,
+    pub variants: Vec<TypedVariant>,
+    pub visibility: Visibility,
+}
+
+/// Enum variant; tuple payload fields are named by position (`0`, `1`, ...)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedVariant {
+    pub name: String,
+    pub tag: usize,
+    pub fields: Vec<TypedField>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedField {
     pub name: String,
@@ -572,6 +906,30 @@ pub struct TypedField {
     pub is_mutable: bool,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedInterface {
+    pub name: String,
+    pub methods: Vec<TypedMethodSignature>,
+    pub visibility: Visibility,
+}
+
+/// Method required by an interface; its position is the method's vtable slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedMethodSignature {
+    pub name: String,
+    pub parameters: Vec<TypedParameter>,
+    pub return_type: Type,
+}
+
+/// Implementation of an interface for a type, with methods in the order the
+/// interface declares them
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedImpl {
+    pub interface: String,
+    pub type_name: String,
+    pub methods: Vec<TypedMethod>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedMethod {
     pub name: String,
@@ -580,6 +938,7 @@ pub struct TypedMethod {
     pub body: TypedBlock,
     pub visibility: Visibility,
     pub is_static: bool,
+    pub span: Option<crate::position::Span>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -616,6 +975,8 @@ pub struct TypedStatement {
 pub enum TypedStatementKind {
     Expression(TypedExpression),
     Let(String, Type, Option<TypedExpression>),
+    /// `let (a, b) = value`, whose pattern matches every value of its type
+    Destructure(Pattern, TypedExpression),
     Const(String, Type, TypedExpression),
     Assignment(TypedExpression, TypedExpression),
     Return(Option<TypedExpression>),
@@ -657,4 +1018,39 @@ pub enum TypedExpressionKind {
     Array(Vec<TypedExpression>),
     Map(Vec<(TypedExpression, TypedExpression)>),
     Tuple(Vec<TypedExpression>),
+    /// Interpolated string: its text as string literals and its
+    /// expressions, in order
+    Interpolated(Vec<TypedExpression>),
+    /// Integer range: start, end, optional step, and whether the end is included
+    Range(Box<TypedExpression>, Box<TypedExpression>, Option<Box<TypedExpression>>, bool),
+    /// Struct or class construction, fields in declaration order
+    StructLiteral(String, Vec<(String, TypedExpression)>),
+    /// Enum variant construction: enum name, variant name, payload in declaration order
+    Variant(String, String, Vec<TypedExpression>),
+    /// Conversion of a value to the interface type of this node, pairing it
+    /// with the vtable of its concrete type
+    Upcast(Box<TypedExpression>),
+    /// Generic function referenced with the type arguments inferred at the
+    /// call site
+    Instantiate(String, Vec<Type>),
+    /// Lambda together with the variables it captures
+    Closure(TypedClosure),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedClosure {
+    pub parameters: Vec<TypedParameter>,
+    pub return_type: Type,
+    pub body: Box<TypedExpression>,
+    pub captures: Vec<TypedCapture>,
+}
+
+/// Variable of an enclosing function used inside a closure
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedCapture {
+    pub name: String,
+    pub type_: Type,
+    /// Shared with the enclosing function because it is assigned somewhere;
+    /// otherwise the closure holds a copy taken when it is created
+    pub by_reference: bool,
 }
\ No newline at end of file
@@ -0,0 +1,565 @@
+//! Module graph for multi-file programs
+//!
+//! Loads a program together with the modules it imports, gives every module its
+//! own namespace, orders modules so dependencies are analyzed first, and reports
+//! import cycles with the full cycle path.
+
+use crate::error::{FluxError, FluxResult, SemanticError, SemanticErrorKind};
+use crate::lexer::FluxLexer;
+use crate::parser::ast::{Item, Program, Visibility};
+use crate::parser::{FluxParser, Parser};
+use crate::position::{Position, Span};
+use crate::semantic::{FluxSemanticAnalyzer, TypedProgram};
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Items a module makes available to its importers
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleExports {
+    /// Import path of the module (e.g. `geometry/shapes`)
+    pub module: String,
+    /// Package name declared by the module
+    pub package: String,
+    items: HashMap<String, Item>,
+}
+
+impl ModuleExports {
+    /// Collect the top-level items of a module. Impl blocks declare no name
+    /// and are not exported.
+    pub fn from_program(module: impl Into<String>, program: &Program) -> Self {
+        let items = program.items.iter()
+            .filter(|item| !matches!(item, Item::Impl(_)))
+            .map(|item| (item.name().to_string(), item.clone()))
+            .collect();
+
+        Self {
+            module: module.into(),
+            package: program.package.clone(),
+            items,
+        }
+    }
+
+    /// Look up an item by name, regardless of visibility
+    pub fn get(&self, name: &str) -> Option<&Item> {
+        self.items.get(name)
+    }
+
+    /// Iterate over the items visible outside the module
+    pub fn public_items(&self) -> impl Iterator<Item = &Item> {
+        self.items.values().filter(|item| *item.visibility() == Visibility::Public)
+    }
+}
+
+/// Errors of a module graph, each with the import path of the module it is in
+pub type ModuleErrors = Vec<(String, SemanticError)>;
+
+/// A single module in the graph
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// Import path the module is known by
+    pub path: String,
+    /// Source files the module was loaded from, if any
+    pub files: Vec<PathBuf>,
+    /// Parsed program for the module
+    pub program: Program,
+}
+
+/// Graph of modules connected by their import declarations
+#[derive(Debug, Clone)]
+pub struct ModuleGraph {
+    root: String,
+    modules: IndexMap<String, Module>,
+}
+
+impl ModuleGraph {
+    /// Create a graph whose root module is the given program
+    pub fn new(root: Program) -> Self {
+        let root_path = root.package.clone();
+        let mut modules = IndexMap::new();
+        modules.insert(root_path.clone(), Module {
+            path: root_path.clone(),
+            files: Vec::new(),
+            program: root,
+        });
+
+        Self {
+            root: root_path,
+            modules,
+        }
+    }
+
+    /// Load an entry file and, transitively, every module it imports.
+    ///
+    /// Import paths are resolved relative to the directory of the entry file:
+    /// `import "geometry/shapes"` loads `geometry/shapes.flux`, or every `.flux`
+    /// file in `geometry/shapes/` when that is a directory.
+    pub fn load<P: AsRef<Path>>(entry: P) -> FluxResult<Self> {
+        let entry = entry.as_ref();
+        let source_root = entry.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        let root_program = parse_file(entry)?;
+        let mut pending = pending_imports(&root_program, entry);
+        let mut graph = Self::new(root_program);
+        graph.modules[0].files.push(entry.to_path_buf());
+
+        while let Some(import) = pending.pop() {
+            if is_builtin_module(&import.path) || graph.modules.contains_key(&import.path) {
+                continue;
+            }
+
+            let files = resolve_module_files(&source_root, &import)?;
+            let (program, imports) = parse_module_files(&import.path, &files)?;
+
+            pending.extend(imports);
+            let path = import.path;
+            graph.modules.insert(path.clone(), Module { path, files, program });
+        }
+
+        Ok(graph)
+    }
+
+    /// Add a module that was parsed elsewhere
+    pub fn add_module(&mut self, path: impl Into<String>, program: Program) {
+        let path = path.into();
+        self.modules.insert(path.clone(), Module {
+            path,
+            files: Vec::new(),
+            program,
+        });
+    }
+
+    /// Get the root module
+    pub fn root(&self) -> &Module {
+        &self.modules[&self.root]
+    }
+
+    /// Look up a module by import path
+    pub fn get(&self, path: &str) -> Option<&Module> {
+        self.modules.get(path)
+    }
+
+    /// Iterate over all modules in load order
+    pub fn modules(&self) -> impl Iterator<Item = &Module> {
+        self.modules.values()
+    }
+
+    /// Number of modules in the graph
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Check whether the graph has no modules
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Order modules so that every module comes after the modules it imports.
+    ///
+    /// Fails if an import cannot be resolved or the imports form a cycle.
+    pub fn topological_order(&self) -> Result<Vec<&Module>, SemanticError> {
+        self.dependency_order().map_err(|(_, error)| *error)
+    }
+
+    /// Order modules like [`ModuleGraph::topological_order`], pairing an
+    /// error with the path of the module whose import caused it
+    fn dependency_order(&self) -> Result<Vec<&Module>, (String, Box<SemanticError>)> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = Vec::new();
+
+        let start = Span::single(Position::start());
+        let mut result = self.visit(&self.root, start, &mut visited, &mut stack, &mut order);
+
+        // Modules that are not reachable from the root still get analyzed
+        for path in self.modules.keys() {
+            if result.is_err() {
+                break;
+            }
+            result = self.visit(path, start, &mut visited, &mut stack, &mut order);
+        }
+
+        // A failed visit leaves the importing module on top of the stack
+        match result {
+            Ok(()) => Ok(order.into_iter().map(|path| &self.modules[path]).collect()),
+            Err(error) => {
+                let importer = stack.last().copied().unwrap_or(&self.root);
+                Err((importer.to_string(), Box::new(error)))
+            }
+        }
+    }
+
+    /// Visit the module at `path`, imported by the declaration at `span`
+    fn visit<'a>(
+        &'a self,
+        path: &'a str,
+        span: Span,
+        visited: &mut HashSet<&'a str>,
+        stack: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> Result<(), SemanticError> {
+        if let Some(start) = stack.iter().position(|p| *p == path) {
+            let mut cycle: Vec<String> = stack[start..].iter().map(|p| p.to_string()).collect();
+            cycle.push(path.to_string());
+            return Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::ImportCycle { cycle },
+            });
+        }
+
+        if visited.contains(path) {
+            return Ok(());
+        }
+
+        let module = self.modules.get(path).ok_or_else(|| SemanticError {
+            span,
+            kind: SemanticErrorKind::UnresolvedImport { path: path.to_string() },
+        })?;
+
+        stack.push(path);
+        for import in &module.program.imports {
+            if !is_builtin_module(&import.path) {
+                self.visit(&import.path, import.span, visited, stack, order)?;
+            }
+        }
+        stack.pop();
+
+        visited.insert(path);
+        order.push(path);
+        Ok(())
+    }
+
+    /// Analyze every module in dependency order.
+    ///
+    /// Each module is checked in its own namespace; its imports are visible only
+    /// through their binding names, and only their public items can be used.
+    /// The typed programs are returned in dependency order, so the root module
+    /// comes last. Every module is analyzed even when others have errors, and
+    /// the errors of all modules are returned together, each with the path of
+    /// the module it is in. They are grouped by module in dependency order and
+    /// sorted by span within each module.
+    pub fn analyze(&self) -> Result<Vec<TypedProgram>, ModuleErrors> {
+        let analyzed = self.analyze_with_warnings()?;
+        Ok(analyzed.into_iter().map(|(typed_program, _)| typed_program).collect())
+    }
+
+    /// Analyze every module like [`ModuleGraph::analyze`], pairing each typed
+    /// program with the warnings found in its module
+    pub fn analyze_with_warnings(&self) -> Result<Vec<(TypedProgram, Vec<SemanticError>)>, ModuleErrors> {
+        let order = self.dependency_order().map_err(|(module, error)| vec![(module, *error)])?;
+        let mut analyzed = Vec::new();
+        let mut errors = Vec::new();
+
+        for module in order {
+            let mut analyzer = FluxSemanticAnalyzer::new();
+            let mut module_errors = Vec::new();
+
+            for import in &module.program.imports {
+                if is_builtin_module(&import.path) {
+                    continue;
+                }
+
+                let dependency = &self.modules[&import.path];
+                let exports = ModuleExports::from_program(&import.path, &dependency.program);
+                if let Err(error) = analyzer.register_module(import.binding_name(), exports) {
+                    module_errors.push(error);
+                }
+            }
+
+            let (typed_program, program_errors) = analyzer.analyze_with_errors(module.program.clone());
+            analyzed.push((typed_program, analyzer.warnings().to_vec()));
+            module_errors.extend(program_errors);
+            module_errors.sort_by_key(|error| (error.span.start.offset, error.span.end.offset));
+            errors.extend(module_errors.into_iter().map(|error| (module.path.clone(), error)));
+        }
+
+        if errors.is_empty() {
+            Ok(analyzed)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Standard library modules are provided by the runtime rather than loaded from source
+pub fn is_builtin_module(path: &str) -> bool {
+    path == "std" || path.starts_with("std/")
+}
+
+/// An import still to be loaded, with the file that declares it
+struct PendingImport {
+    path: String,
+    span: Span,
+    file: PathBuf,
+}
+
+fn pending_imports(program: &Program, file: &Path) -> Vec<PendingImport> {
+    program.imports.iter()
+        .map(|import| PendingImport {
+            path: import.path.clone(),
+            span: import.span,
+            file: file.to_path_buf(),
+        })
+        .collect()
+}
+
+fn resolve_module_files(source_root: &Path, import: &PendingImport) -> FluxResult<Vec<PathBuf>> {
+    let path = import.path.as_str();
+    let file = source_root.join(format!("{}.flux", path));
+    if file.is_file() {
+        return Ok(vec![file]);
+    }
+
+    let dir = source_root.join(path);
+    if dir.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+            .map_err(|e| FluxError::Io(format!("Failed to read {}: {}", dir.display(), e)))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "flux"))
+            .collect();
+        files.sort();
+
+        if !files.is_empty() {
+            return Ok(files);
+        }
+    }
+
+    // Reported at the import, in the file that declares it
+    Err(FluxError::SemanticErrors(vec![(import.file.clone(), SemanticError {
+        span: import.span,
+        kind: SemanticErrorKind::UnresolvedImport { path: path.to_string() },
+    })]))
+}
+
+fn parse_file(file: &Path) -> FluxResult<Program> {
+    let source = fs::read_to_string(file)
+        .map_err(|e| FluxError::Io(format!("Failed to read {}: {}", file.display(), e)))?;
+    let lexer = FluxLexer::new(source);
+    let mut parser = FluxParser::new(lexer)?;
+    Ok(parser.parse_program()?)
+}
+
+/// Parse the files of a module and merge them into a single program,
+/// returning it with the imports of every file
+fn parse_module_files(path: &str, files: &[PathBuf]) -> FluxResult<(Program, Vec<PendingImport>)> {
+    let mut merged: Option<Program> = None;
+    let mut imports = Vec::new();
+
+    for file in files {
+        let program = parse_file(file)?;
+        imports.extend(pending_imports(&program, file));
+
+        match &mut merged {
+            None => merged = Some(program),
+            Some(module) => {
+                if module.package != program.package {
+                    return Err(FluxError::Semantic(SemanticError {
+                        span: Span::single(crate::position::Position::start()),
+                        kind: SemanticErrorKind::InvalidOperation {
+                            message: format!(
+                                "Module \"{}\" mixes packages '{}' and '{}' ({})",
+                                path, module.package, program.package, file.display()
+                            ),
+                        },
+                    }));
+                }
+
+                for import in program.imports {
+                    if !module.imports.contains(&import) {
+                        module.imports.push(import);
+                    }
+                }
+                module.items.extend(program.items);
+            }
+        }
+    }
+
+    let merged = merged.ok_or_else(|| FluxError::Semantic(SemanticError {
+        span: Span::single(crate::position::Position::start()),
+        kind: SemanticErrorKind::UnresolvedImport { path: path.to_string() },
+    }))?;
+    Ok((merged, imports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Program {
+        let lexer = FluxLexer::new(source.to_string());
+        let mut parser = FluxParser::new(lexer).unwrap();
+        parser.parse_program().unwrap()
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let mut graph = ModuleGraph::new(parse("import \"geometry\"\nimport \"math\"\nfunc main() { }"));
+        graph.add_module("geometry", parse("package geometry\nimport \"math\"\npub func area() { }"));
+        graph.add_module("math", parse("package math\npub func sqrt() { }"));
+
+        let order: Vec<&str> = graph.topological_order().unwrap()
+            .iter()
+            .map(|module| module.path.as_str())
+            .collect();
+        assert_eq!(order, vec!["math", "geometry", "main"]);
+    }
+
+    #[test]
+    fn test_import_cycle_reports_full_path() {
+        let mut graph = ModuleGraph::new(parse("import \"a\"\nfunc main() { }"));
+        graph.add_module("a", parse("package a\nimport \"b\""));
+        graph.add_module("b", parse("package b\nimport \"c\""));
+        graph.add_module("c", parse("package c\nimport \"a\""));
+
+        let error = graph.topological_order().unwrap_err();
+        match &error.kind {
+            SemanticErrorKind::ImportCycle { cycle } => {
+                assert_eq!(cycle, &vec!["a", "b", "c", "a"]);
+            }
+            _ => panic!("Expected import cycle error"),
+        }
+        assert!(error.to_string().contains("a -> b -> c -> a"));
+        // Reported at the import in `c` that closes the cycle
+        assert_eq!(error.span.start.line, 2);
+    }
+
+    #[test]
+    fn test_unresolved_import() {
+        let graph = ModuleGraph::new(parse("package main\nimport \"missing\"\nfunc main() { }"));
+        let error = graph.topological_order().unwrap_err();
+        assert!(matches!(error.kind, SemanticErrorKind::UnresolvedImport { ref path } if path == "missing"));
+        assert_eq!((error.span.start.line, error.span.start.column), (2, 1));
+    }
+
+    #[test]
+    fn test_unresolved_import_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main.flux");
+        fs::write(&main, "import \"util\"\nfunc main() { }").unwrap();
+        fs::write(dir.path().join("util.flux"), "package util\n\nimport \"missing\"\n").unwrap();
+
+        // Reported at the import, in the file that declares it
+        match ModuleGraph::load(&main).unwrap_err() {
+            FluxError::SemanticErrors(errors) => {
+                assert_eq!(errors.len(), 1);
+                let (file, error) = &errors[0];
+                assert_eq!(file, &dir.path().join("util.flux"));
+                assert!(matches!(&error.kind, SemanticErrorKind::UnresolvedImport { path } if path == "missing"));
+                assert_eq!((error.span.start.line, error.span.start.column), (3, 1));
+            }
+            other => panic!("Expected an unresolved import, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_builtin_modules_are_not_loaded() {
+        let graph = ModuleGraph::new(parse("import \"std/io\"\nfunc main() { }"));
+        assert_eq!(graph.topological_order().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_qualified_call_across_modules() {
+        let mut graph = ModuleGraph::new(parse(
+            "import \"math\"\nfunc main() -> int { return math::square(3) }"
+        ));
+        graph.add_module("math", parse(
+            "package math\npub func square(x: int) -> int { return x * x }"
+        ));
+
+        let typed = graph.analyze().unwrap();
+        assert_eq!(typed.len(), 2);
+        assert_eq!(typed[1].package, "main");
+    }
+
+    #[test]
+    fn test_struct_types_across_modules() {
+        // Types in exported signatures are the same types as `geo::Point`
+        let mut graph = ModuleGraph::new(parse(r#"import "geo"
+func norm(p: geo::Point) -> int { return p.x + p.y }
+func main() -> int {
+    let p = geo::make(1, 2);
+    let q: geo::Point = geo::shift(p);
+    return p.x + norm(geo::make(3, 4)) + q.y
+}"#));
+        graph.add_module("geo", parse(r#"package geo
+pub struct Point { x: int, y: int }
+pub func make(x: int, y: int) -> Point { return Point { x: x, y: y } }
+pub func shift(p: Point) -> Point { return Point { x: p.x + 1, y: p.y } }"#));
+
+        let typed = graph.analyze().unwrap();
+        assert_eq!(typed.len(), 2);
+    }
+
+    #[test]
+    fn test_private_items_are_not_visible() {
+        let mut graph = ModuleGraph::new(parse(
+            "import \"math\"\nfunc main() -> int { return math::helper(3) }"
+        ));
+        graph.add_module("math", parse(
+            "package math\nfunc helper(x: int) -> int { return x }"
+        ));
+
+        let errors = graph.analyze().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        match errors.into_iter().next().unwrap().1.kind {
+            SemanticErrorKind::PrivateItem { name, module } => {
+                assert_eq!(name, "helper");
+                assert_eq!(module, "math");
+            }
+            other => panic!("Expected private item error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_modules_have_separate_namespaces() {
+        // Both modules define `helper`; neither sees the other's unqualified
+        let mut graph = ModuleGraph::new(parse(
+            "import \"util\" as u\nfunc helper() -> int { return u::helper() }"
+        ));
+        graph.add_module("util", parse(
+            "package util\npub func helper() -> int { return 1 }"
+        ));
+        assert!(graph.analyze().is_ok());
+
+        let mut graph = ModuleGraph::new(parse(
+            "import \"util\"\nfunc main() -> int { return helper() }"
+        ));
+        graph.add_module("util", parse(
+            "package util\npub func helper() -> int { return 1 }"
+        ));
+        assert!(graph.analyze().is_err());
+    }
+
+    #[test]
+    fn test_warnings_of_each_module() {
+        let mut graph = ModuleGraph::new(parse(
+            "import \"util\"\nfunc main() -> int { return util::helper(1) }"
+        ));
+        graph.add_module("util", parse(
+            "package util\npub func helper(x: int) -> int {\n    return x\n    x + 1\n}"
+        ));
+
+        let analyzed = graph.analyze_with_warnings().unwrap();
+        assert_eq!(analyzed.len(), 2);
+        assert_eq!(analyzed[0].1.iter().map(|warning| warning.span.start.line).collect::<Vec<_>>(), vec![4]);
+        assert!(analyzed[1].1.is_empty());
+    }
+
+    #[test]
+    fn test_errors_of_all_modules_are_reported() {
+        let mut graph = ModuleGraph::new(parse(
+            "import \"util\"\nfunc main() -> int {\n    let a: string = 1;\n    return util::helper(missing)\n}"
+        ));
+        graph.add_module("util", parse(
+            "package util\npub func helper(x: int) -> int { return x + true }"
+        ));
+
+        // Errors are grouped by module, dependencies first
+        let errors = graph.analyze().unwrap_err();
+        let positions: Vec<_> = errors.iter()
+            .map(|(module, error)| (module.as_str(), error.span.start.line, error.span.start.column))
+            .collect();
+        assert_eq!(positions, vec![("util", 2, 41), ("main", 3, 5), ("main", 4, 25)]);
+    }
+}
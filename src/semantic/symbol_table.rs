@@ -3,9 +3,14 @@
 //! Provides hierarchical symbol tables for managing variable, function, and type bindings.
 
 use crate::error::{SemanticError, SemanticErrorKind};
-use crate::parser::ast::{Type, Function, Struct, Class, Const, ExternFunction};
+use crate::parser::ast::{Type, Function, Struct, Class, Enum, Variant, Interface, Method, Const, ExternFunction, Item, Visibility};
+use crate::semantic::modules::ModuleExports;
 use crate::position::Span;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Variables declared without a value that some path has not yet assigned,
+/// identified by scope level and name
+pub type InitState = HashSet<(usize, String)>;
 
 /// Symbol information stored in the symbol table
 #[derive(Debug, Clone)]
@@ -19,6 +24,8 @@ pub enum Symbol {
     Function(Function),
     Struct(Struct),
     Class(Class),
+    Enum(Enum),
+    Interface(Interface),
     Const(Const),
     ExternFunction(ExternFunction),
     Parameter {
@@ -26,6 +33,7 @@ pub enum Symbol {
         is_mutable: bool,
         index: usize, // Parameter index for code generation
     },
+    Module(ModuleExports),
 }
 
 /// Scope type for better error reporting
@@ -114,9 +122,13 @@ impl SymbolTable {
         self.current_function.is_some()
     }
     
-    /// Check if we're currently in a loop
+    /// Check if we're currently in a loop of the innermost function; a
+    /// lambda body cannot break out of a loop around it
     pub fn in_loop(&self) -> bool {
-        self.scopes.iter().any(|scope| scope.scope_type == ScopeType::Loop)
+        self.scopes.iter()
+            .rev()
+            .take_while(|scope| scope.scope_type != ScopeType::Function)
+            .any(|scope| scope.scope_type == ScopeType::Loop)
     }
     
     /// Get the current function name
@@ -197,6 +209,31 @@ impl SymbolTable {
         false
     }
     
+    /// Variables in scope that are not yet initialized
+    pub fn uninitialized_variables(&self) -> InitState {
+        let mut state = InitState::new();
+        for (level, scope) in self.scopes.iter().enumerate() {
+            for (name, symbol) in &scope.symbols {
+                if let Symbol::Variable { is_initialized: false, .. } = symbol {
+                    state.insert((level, name.clone()));
+                }
+            }
+        }
+        state
+    }
+    
+    /// Set which variables in scope are initialized, as after a branch or
+    /// loop whose paths assign different variables
+    pub fn restore_initialized(&mut self, state: &InitState) {
+        for (level, scope) in self.scopes.iter_mut().enumerate() {
+            for (name, symbol) in scope.symbols.iter_mut() {
+                if let Symbol::Variable { is_initialized, .. } = symbol {
+                    *is_initialized = !state.contains(&(level, name.clone()));
+                }
+            }
+        }
+    }
+    
     /// Define a function in the current scope
     pub fn define_function(&mut self, name: String, func: Function) -> Result<(), SemanticError> {
         let current_scope = self.scopes.last_mut().unwrap();
@@ -257,6 +294,36 @@ impl SymbolTable {
         Ok(())
     }
     
+    /// Define an enum in the current scope
+    pub fn define_enum(&mut self, name: String, enum_def: Enum) -> Result<(), SemanticError> {
+        let current_scope = self.scopes.last_mut().unwrap();
+        
+        if current_scope.symbols.contains_key(&name) {
+            return Err(SemanticError {
+                span: Span::single(crate::position::Position::start()), // Placeholder
+                kind: SemanticErrorKind::DuplicateDefinition { name },
+            });
+        }
+        
+        current_scope.symbols.insert(name, Symbol::Enum(enum_def));
+        Ok(())
+    }
+    
+    /// Define an interface in the current scope
+    pub fn define_interface(&mut self, name: String, interface: Interface) -> Result<(), SemanticError> {
+        let current_scope = self.scopes.last_mut().unwrap();
+        
+        if current_scope.symbols.contains_key(&name) {
+            return Err(SemanticError {
+                span: Span::single(crate::position::Position::start()), // Placeholder
+                kind: SemanticErrorKind::DuplicateDefinition { name },
+            });
+        }
+        
+        current_scope.symbols.insert(name, Symbol::Interface(interface));
+        Ok(())
+    }
+    
     /// Define a constant in the current scope
     pub fn define_const(&mut self, name: String, const_def: Const) -> Result<(), SemanticError> {
         let current_scope = self.scopes.last_mut().unwrap();
@@ -272,6 +339,21 @@ impl SymbolTable {
         Ok(())
     }
     
+    /// Define an imported module in the current scope
+    pub fn define_module(&mut self, name: String, exports: ModuleExports) -> Result<(), SemanticError> {
+        let current_scope = self.scopes.last_mut().unwrap();
+        
+        if current_scope.symbols.contains_key(&name) {
+            return Err(SemanticError {
+                span: Span::single(crate::position::Position::start()), // Placeholder
+                kind: SemanticErrorKind::DuplicateDefinition { name },
+            });
+        }
+        
+        current_scope.symbols.insert(name, Symbol::Module(exports));
+        Ok(())
+    }
+    
     /// Look up a symbol by name, searching from innermost to outermost scope
     pub fn lookup(&self, name: &str) -> Option<&Symbol> {
         for scope in self.scopes.iter().rev() {
@@ -316,9 +398,7 @@ impl SymbolTable {
                     Symbol::Variable { is_initialized, .. } if !is_initialized => {
                         Err(SemanticError {
                             span: Span::single(crate::position::Position::start()),
-                            kind: SemanticErrorKind::InvalidOperation {
-                                message: format!("Use of uninitialized variable '{}'", name),
-                            },
+                            kind: SemanticErrorKind::UninitializedVariable { name: name.to_string() },
                         })
                     }
                     _ => Ok(symbol),
@@ -331,6 +411,75 @@ impl SymbolTable {
         }
     }
     
+    /// Resolve a qualified name such as `math::sqrt` through an imported module
+    pub fn resolve_path(&self, segments: &[String]) -> Result<&Item, SemanticError> {
+        let undefined = || SemanticError {
+            span: Span::single(crate::position::Position::start()),
+            kind: SemanticErrorKind::UndefinedVariable { name: segments.join("::") },
+        };
+        
+        let (module_name, member) = match segments {
+            [module_name, member] => (module_name, member),
+            _ => return Err(undefined()),
+        };
+        
+        let exports = match self.lookup(module_name) {
+            Some(Symbol::Module(exports)) => exports,
+            _ => return Err(undefined()),
+        };
+        
+        let item = exports.get(member).ok_or_else(undefined)?;
+        if *item.visibility() != Visibility::Public {
+            return Err(SemanticError {
+                span: Span::single(crate::position::Position::start()),
+                kind: SemanticErrorKind::PrivateItem {
+                    name: member.clone(),
+                    module: exports.module.clone(),
+                },
+            });
+        }
+        
+        Ok(item)
+    }
+    
+    /// Resolve `Class::method` to a static method of a class in scope
+    pub fn resolve_static_method(&self, class_name: &str, method_name: &str) -> Result<&Method, SemanticError> {
+        let class_def = match self.lookup(class_name) {
+            Some(Symbol::Class(class_def)) => class_def,
+            _ => return Err(SemanticError {
+                span: Span::single(crate::position::Position::start()),
+                kind: SemanticErrorKind::UndefinedVariable { name: class_name.to_string() },
+            }),
+        };
+        
+        match class_def.methods.iter().find(|method| method.name == method_name) {
+            Some(method) if method.is_static => Ok(method),
+            Some(_) => Err(SemanticError {
+                span: Span::single(crate::position::Position::start()),
+                kind: SemanticErrorKind::InvalidOperation {
+                    message: format!("'{}::{}' is an instance method and needs a receiver", class_name, method_name),
+                },
+            }),
+            None => Err(SemanticError {
+                span: Span::single(crate::position::Position::start()),
+                kind: SemanticErrorKind::UndefinedVariable { name: format!("{}::{}", class_name, method_name) },
+            }),
+        }
+    }
+    
+    /// Resolve `Enum::Variant` to a variant of an enum in scope
+    pub fn resolve_variant(&self, enum_name: &str, variant_name: &str) -> Result<&Variant, SemanticError> {
+        let undefined = || SemanticError {
+            span: Span::single(crate::position::Position::start()),
+            kind: SemanticErrorKind::UndefinedVariable { name: format!("{}::{}", enum_name, variant_name) },
+        };
+        
+        match self.lookup(enum_name) {
+            Some(Symbol::Enum(enum_def)) => enum_def.variant(variant_name).ok_or_else(undefined),
+            _ => Err(undefined()),
+        }
+    }
+    
     /// Check if a name can be assigned to (is mutable)
     pub fn can_assign(&self, name: &str) -> Result<bool, SemanticError> {
         match self.lookup(name) {
@@ -386,19 +535,25 @@ mod tests {
     fn create_test_function(name: &str) -> Function {
         Function {
             name: name.to_string(),
+            type_params: vec![],
             parameters: vec![],
             return_type: Some(Type::Unit),
-            body: crate::parser::ast::Block { statements: vec![] },
+            body: crate::parser::ast::Block { statements: vec![], span: Span::single(crate::position::Position::start()) },
             is_async: false,
             visibility: Visibility::Private,
+            doc: None,
+            span: Span::single(crate::position::Position::start()),
         }
     }
 
     fn create_test_struct(name: &str) -> Struct {
         Struct {
             name: name.to_string(),
+            type_params: vec![],
             fields: vec![],
             visibility: Visibility::Private,
+            doc: None,
+            span: Span::single(crate::position::Position::start()),
         }
     }
 
@@ -541,6 +696,12 @@ mod tests {
         table.exit_scope();
         assert!(table.in_loop());
         
+        // A closure body inside the loop is not itself in a loop
+        table.enter_function_scope("<closure>".to_string());
+        assert!(!table.in_loop());
+        table.exit_scope();
+        assert!(table.in_loop());
+        
         // Exit loop scope
         table.exit_scope();
         assert!(!table.in_loop());
@@ -597,6 +758,12 @@ mod tests {
         
         // Now resolving should work
         assert!(table.resolve_name("x").is_ok());
+        
+        // Rewinding to an earlier state uninitializes it again
+        let state = InitState::from([(0, "x".to_string())]);
+        table.restore_initialized(&state);
+        assert!(!table.is_initialized("x"));
+        assert_eq!(table.uninitialized_variables(), state);
     }
 
     #[test]
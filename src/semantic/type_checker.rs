@@ -4,12 +4,15 @@
 
 use crate::error::{SemanticError, SemanticErrorKind};
 use crate::parser::ast::{
-    Type, Expression, Statement, Block, Literal, BinaryOp, UnaryOp, 
-    Function, Struct, Class, Const, Method, Parameter, Field, Visibility, ExternFunction
+    Type, Expression, ExpressionKind, Statement, StatementKind, Block, Literal, BinaryOp, UnaryOp, 
+    Function, Struct, Class, Enum, Variant, VariantPayload, Interface, MethodSignature, Impl, Const, Method, Parameter, Field,
+    Visibility, ExternFunction, MatchArm, Pattern, ResultPattern, LambdaParameter, StringPart, Spelling
 };
-use crate::position::Span;
+use crate::position::{Position, Span};
 use crate::semantic::*;
-use std::collections::HashMap;
+use crate::semantic::captures;
+use crate::semantic::exhaustiveness::ExhaustivenessChecker;
+use std::collections::{HashMap, HashSet};
 
 /// Type variable for generic type inference
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -24,6 +27,172 @@ pub enum InferType {
     Variable(TypeVar),
     /// Function type with parameters and return type
     Function(Vec<InferType>, Box<InferType>),
+    /// Type constructor applied to types that may contain variables, such as `List<T>`
+    Applied(String, Vec<InferType>),
+    /// Type of a binding whose declaration failed to check. It unifies with
+    /// anything, and uses of the binding are not reported again
+    Error,
+}
+
+/// Split a compound type into the name of its constructor and its arguments
+fn type_constructor(type_: &Type) -> Option<(&str, Vec<&Type>)> {
+    match type_ {
+        Type::Array(t) => Some(("[]", vec![t])),
+        Type::List(t) => Some(("List", vec![t])),
+        Type::Set(t) => Some(("Set", vec![t])),
+        Type::Map(k, v) => Some(("Map", vec![k, v])),
+        Type::Tuple(elements) => Some(("()", elements.iter().collect())),
+        Type::Nullable(t) => Some(("?", vec![t])),
+        Type::Result(ok, err) => Some(("Result", vec![ok, err])),
+        Type::Generic(name, args) => Some((name, args.iter().collect())),
+        _ => None,
+    }
+}
+
+/// Names a pattern binds, in order
+fn pattern_bindings(pattern: &Pattern) -> Vec<String> {
+    match pattern {
+        Pattern::Identifier(name) => vec![name.clone()],
+        Pattern::Tuple(patterns) | Pattern::Variant(_, patterns) => {
+            patterns.iter().flat_map(pattern_bindings).collect()
+        }
+        Pattern::Struct(_, fields) => fields.iter().flat_map(|(_, p)| pattern_bindings(p)).collect(),
+        Pattern::Result(ResultPattern::Ok(inner)) | Pattern::Result(ResultPattern::Err(inner)) => pattern_bindings(inner),
+        Pattern::Literal(..) | Pattern::Wildcard => Vec::new(),
+    }
+}
+
+/// Rebuild a compound type from its constructor and arguments
+fn apply_constructor(name: &str, mut args: Vec<Type>) -> Type {
+    let mut next = || Box::new(args.remove(0));
+    match name {
+        "[]" => Type::Array(next()),
+        "List" => Type::List(next()),
+        "Set" => Type::Set(next()),
+        "Map" => Type::Map(next(), next()),
+        "()" => Type::Tuple(args),
+        "?" => Type::Nullable(next()),
+        "Result" => Type::Result(next(), next()),
+        _ => Type::Generic(name.to_string(), args),
+    }
+}
+
+/// Name the non-null value of a nullable expression is bound to in the
+/// matches that `?.` and `??` are lowered to. It cannot clash with a name
+/// in the source.
+const NON_NULL_VALUE: &str = "$value";
+
+/// Name of the element a `for` loop with a destructuring pattern binds
+/// before the pattern takes it apart
+const LOOP_ELEMENT: &str = "$element";
+
+/// Type of the elements a `for` loop over a collection binds; a map gives
+/// its `(key, value)` pairs
+fn element_type(collection: &Type) -> Option<Type> {
+    match collection {
+        Type::Array(elem) | Type::List(elem) | Type::Set(elem) => Some((**elem).clone()),
+        Type::Map(key, value) => Some(Type::Tuple(vec![(**key).clone(), (**value).clone()])),
+        Type::Range => Some(Type::Int),
+        _ => None,
+    }
+}
+
+/// Whether values of a type have a text form to interpolate into strings;
+/// unit and functions do not, nor do collections of them
+fn is_printable(type_: &Type) -> bool {
+    match type_ {
+        Type::Unit | Type::Function(_, _) => false,
+        Type::Array(elem) | Type::List(elem) | Type::Set(elem) | Type::Nullable(elem) => is_printable(elem),
+        Type::Map(a, b) | Type::Result(a, b) => is_printable(a) && is_printable(b),
+        Type::Tuple(types) | Type::Generic(_, types) => types.iter().all(is_printable),
+        _ => true,
+    }
+}
+
+/// Variables that a condition proves are not null when it holds, and when
+/// it does not
+fn null_checks(cond: &Expression) -> (Vec<String>, Vec<String>) {
+    match &cond.kind {
+        ExpressionKind::Binary(left, op @ (BinaryOp::Equal | BinaryOp::NotEqual), right) => {
+            let checked = match (&left.kind, &right.kind) {
+                (ExpressionKind::Identifier(name), ExpressionKind::Literal(Literal::Null))
+                | (ExpressionKind::Literal(Literal::Null), ExpressionKind::Identifier(name)) => vec![name.clone()],
+                _ => Vec::new(),
+            };
+            match op {
+                BinaryOp::NotEqual => (checked, Vec::new()),
+                _ => (Vec::new(), checked),
+            }
+        }
+        ExpressionKind::Binary(left, BinaryOp::And, right) => {
+            let (mut when_true, _) = null_checks(left);
+            when_true.extend(null_checks(right).0);
+            (when_true, Vec::new())
+        }
+        ExpressionKind::Binary(left, BinaryOp::Or, right) => {
+            let (_, mut when_false) = null_checks(left);
+            when_false.extend(null_checks(right).1);
+            (Vec::new(), when_false)
+        }
+        ExpressionKind::Unary(UnaryOp::Not, operand) => {
+            let (when_true, when_false) = null_checks(operand);
+            (when_false, when_true)
+        }
+        _ => (Vec::new(), Vec::new()),
+    }
+}
+
+fn nullable_dereference(type_: &Type, span: Span) -> SemanticError {
+    SemanticError {
+        span,
+        kind: SemanticErrorKind::NullableDereference { type_name: format!("{}", type_) },
+    }
+}
+
+/// The non-null value of a nullable expression, as bound by a lowered match
+fn non_null_value(type_: &Type, span: Option<Span>) -> TypedExpression {
+    TypedExpression {
+        kind: TypedExpressionKind::Identifier(NON_NULL_VALUE.to_string()),
+        type_: type_.clone(),
+        span,
+    }
+}
+
+/// A block holding one expression, whose value is the block's
+fn expression_block(expr: TypedExpression) -> TypedBlock {
+    TypedBlock {
+        type_: expr.type_.clone(),
+        statements: vec![TypedStatement { span: expr.span, kind: TypedStatementKind::Expression(expr) }],
+    }
+}
+
+/// A match expression with one arm for `null` and one for any other value
+fn null_match(scrutinee: TypedExpression, if_null: TypedBlock, if_value: TypedBlock, type_: Type, span: Span) -> TypedExpression {
+    let arm = |pattern: Pattern, body: TypedBlock| TypedMatchArm { pattern, guard: None, body };
+    TypedExpression {
+        kind: TypedExpressionKind::Match(Box::new(scrutinee), vec![
+            arm(Pattern::Literal(Literal::Null, Spelling::default()), if_null),
+            arm(Pattern::Identifier(NON_NULL_VALUE.to_string()), if_value),
+        ]),
+        type_,
+        span: Some(span),
+    }
+}
+
+/// Lower `receiver?.member` to a match that gives `null` for a null
+/// receiver and the member access otherwise. A member that is itself
+/// nullable is not wrapped twice.
+fn safe_navigation(receiver: TypedExpression, access: TypedExpression, span: Span) -> TypedExpression {
+    let type_ = match &access.type_ {
+        Type::Nullable(_) => access.type_.clone(),
+        other => Type::Nullable(Box::new(other.clone())),
+    };
+    let null = TypedExpression {
+        kind: TypedExpressionKind::Literal(Literal::Null),
+        type_: type_.clone(),
+        span: Some(span),
+    };
+    null_match(receiver, expression_block(null), expression_block(access), type_, span)
 }
 
 impl InferType {
@@ -31,7 +200,7 @@ impl InferType {
     pub fn to_concrete(&self) -> Option<Type> {
         match self {
             InferType::Concrete(t) => Some(t.clone()),
-            InferType::Variable(_) => None,
+            InferType::Variable(_) | InferType::Error => None,
             InferType::Function(params, ret) => {
                 let concrete_params: Option<Vec<Type>> = params.iter()
                     .map(|p| p.to_concrete())
@@ -39,9 +208,24 @@ impl InferType {
                 let concrete_ret = ret.to_concrete()?;
                 Some(Type::Function(concrete_params?, Box::new(concrete_ret)))
             }
+            InferType::Applied(name, args) => {
+                let concrete_args: Option<Vec<Type>> = args.iter()
+                    .map(|a| a.to_concrete())
+                    .collect();
+                Some(apply_constructor(name, concrete_args?))
+            }
         }
     }
     
+    /// View a compound concrete type as an applied constructor
+    pub fn decompose(type_: &Type) -> Option<InferType> {
+        let (name, args) = type_constructor(type_)?;
+        Some(InferType::Applied(
+            name.to_string(),
+            args.into_iter().map(|a| InferType::Concrete(a.clone())).collect(),
+        ))
+    }
+    
     /// Check if this type contains the given type variable
     pub fn occurs_check(&self, var: &TypeVar) -> bool {
         match self {
@@ -53,6 +237,7 @@ impl InferType {
             InferType::Function(params, ret) => {
                 params.iter().any(|p| p.occurs_check(var)) || ret.occurs_check(var)
             }
+            InferType::Applied(_, args) => args.iter().any(|a| a.occurs_check(var)),
             _ => false,
         }
     }
@@ -93,6 +278,9 @@ impl Substitution {
                 let new_ret = Box::new(self.apply(ret));
                 InferType::Function(new_params, new_ret)
             }
+            InferType::Applied(name, args) => {
+                InferType::Applied(name.clone(), args.iter().map(|a| self.apply(a)).collect())
+            }
             InferType::Concrete(Type::Function(params, ret)) => {
                 let new_params = params.iter().map(|p| {
                     match self.apply(&InferType::Concrete(p.clone())) {
@@ -129,10 +317,23 @@ impl Substitution {
     }
 }
 
+/// Names the type environment provides before any item is declared
+pub const BUILTINS: &[&str] = &["print", "println", "Some", "Ok", "Err"];
+
+/// Builtin constructors and the type parameters they are generic over
+const GENERIC_BUILTINS: &[(&str, &[&str])] = &[
+    ("Some", &["T"]),
+    ("Ok", &["T", "E"]),
+    ("Err", &["T", "E"]),
+];
+
 /// Type environment for managing type bindings with scope support
 #[derive(Debug, Clone)]
 pub struct TypeEnvironment {
     scopes: Vec<HashMap<String, InferType>>,
+    /// Declared types of the nullable variables each scope binds to their
+    /// non-null type, after a check against `null`
+    narrowed: Vec<HashMap<String, Type>>,
     next_var_id: u32,
 }
 
@@ -141,6 +342,7 @@ impl TypeEnvironment {
     pub fn new() -> Self {
         let mut env = Self {
             scopes: vec![HashMap::new()],
+            narrowed: vec![HashMap::new()],
             next_var_id: 0,
         };
         
@@ -162,33 +364,93 @@ impl TypeEnvironment {
             Box::new(InferType::Concrete(Type::Unit))
         ));
         
-        // Built-in type constructors
-        let fresh_var = self.fresh_var();
-        self.bind("Some".to_string(), InferType::Function(
-            vec![InferType::Variable(fresh_var)],
-            Box::new(InferType::Concrete(Type::Nullable(Box::new(Type::Generic("T".to_string(), vec![])))))
+        // Built-in type constructors, generic over the parameters listed in
+        // `GENERIC_BUILTINS`
+        let t = || Box::new(Type::Named("T".to_string()));
+        let e = || Box::new(Type::Named("E".to_string()));
+        self.bind("Some".to_string(), InferType::Concrete(
+            Type::Function(vec![*t()], Box::new(Type::Nullable(t())))
+        ));
+        self.bind("Ok".to_string(), InferType::Concrete(
+            Type::Function(vec![*t()], Box::new(Type::Result(t(), e())))
+        ));
+        self.bind("Err".to_string(), InferType::Concrete(
+            Type::Function(vec![*e()], Box::new(Type::Result(t(), e())))
         ));
     }
     
     /// Enter a new scope
     pub fn enter_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.narrowed.push(HashMap::new());
+    }
+    
+    /// Enter the scope of a closure body. Variables narrowed outside the
+    /// closure have their declared type in it again, since it may run after
+    /// they are assigned `null`.
+    pub fn enter_closure_scope(&mut self) {
+        let names: Vec<String> = self.narrowed.iter().flat_map(|narrowed| narrowed.keys().cloned()).collect();
+        let declared: Vec<(String, Type)> = names
+            .into_iter()
+            .filter_map(|name| self.declared_type(&name).cloned().map(|declared| (name, declared)))
+            .collect();
+        self.enter_scope();
+        for (name, declared) in declared {
+            self.bind(name, InferType::Concrete(declared));
+        }
     }
     
     /// Exit the current scope
     pub fn exit_scope(&mut self) {
         if self.scopes.len() > 1 {
             self.scopes.pop();
+            self.narrowed.pop();
         }
     }
     
     /// Bind a name to a type in the current scope
     pub fn bind(&mut self, name: String, type_: InferType) {
+        if let Some(narrowed) = self.narrowed.last_mut() {
+            narrowed.remove(&name);
+        }
         if let Some(current_scope) = self.scopes.last_mut() {
             current_scope.insert(name, type_);
         }
     }
     
+    /// Bind a variable of nullable type to the type of its non-null values
+    /// in the current scope
+    pub fn narrow(&mut self, name: String, declared: Type) {
+        let Type::Nullable(inner) = &declared else { return };
+        if let Some(current_scope) = self.scopes.last_mut() {
+            current_scope.insert(name.clone(), InferType::Concrete((**inner).clone()));
+        }
+        if let Some(narrowed) = self.narrowed.last_mut() {
+            narrowed.insert(name, declared);
+        }
+    }
+    
+    /// The declared type of a variable that is narrowed where it is used
+    pub fn declared_type(&self, name: &str) -> Option<&Type> {
+        let depth = self.binding_depth(name)?;
+        self.narrowed[depth].get(name)
+    }
+    
+    /// Undo the narrowing of a variable, in every scope that narrows it
+    pub fn widen(&mut self, name: &str) {
+        for depth in (0..self.scopes.len()).rev() {
+            if !self.scopes[depth].contains_key(name) {
+                continue;
+            }
+            match self.narrowed[depth].remove(name) {
+                Some(declared) => {
+                    self.scopes[depth].insert(name.to_string(), InferType::Concrete(declared));
+                }
+                None => break,
+            }
+        }
+    }
+    
     /// Look up the type of a name, searching from innermost to outermost scope
     pub fn lookup(&self, name: &str) -> Option<&InferType> {
         for scope in self.scopes.iter().rev() {
@@ -199,6 +461,11 @@ impl TypeEnvironment {
         None
     }
     
+    /// Index of the innermost scope binding `name`; scope 0 holds globals
+    pub fn binding_depth(&self, name: &str) -> Option<usize> {
+        self.scopes.iter().rposition(|scope| scope.contains_key(name))
+    }
+    
     /// Generate a fresh type variable
     pub fn fresh_var(&mut self) -> TypeVar {
         let var = TypeVar(self.next_var_id);
@@ -210,6 +477,13 @@ impl TypeEnvironment {
     pub fn scope_depth(&self) -> usize {
         self.scopes.len()
     }
+    
+    /// Remove the poisoned bindings of the current scope
+    pub fn unbind_poisoned(&mut self) {
+        if let Some(current_scope) = self.scopes.last_mut() {
+            current_scope.retain(|_, type_| *type_ != InferType::Error);
+        }
+    }
 }
 
 impl Default for TypeEnvironment {
@@ -218,11 +492,133 @@ impl Default for TypeEnvironment {
     }
 }
 
+/// Type arguments of one use of a generic function or type, solved by
+/// unifying the declared types of its parameters or fields with the types of
+/// the values supplied for them
+#[derive(Debug)]
+struct Instantiation {
+    vars: HashMap<String, TypeVar>,
+    subst: Substitution,
+    params: Vec<String>,
+}
+
+impl Instantiation {
+    fn new(params: &[String], env: &mut TypeEnvironment) -> Self {
+        Self {
+            vars: params.iter().map(|param| (param.clone(), env.fresh_var())).collect(),
+            subst: Substitution::new(),
+            params: params.to_vec(),
+        }
+    }
+    
+    /// Instantiation of an item without type parameters
+    fn none() -> Self {
+        Self { vars: HashMap::new(), subst: Substitution::new(), params: Vec::new() }
+    }
+    
+    /// Replace the type parameters in a declared type by their variables
+    fn open(&self, type_: &Type) -> InferType {
+        if let Type::Named(name) = type_ {
+            if let Some(var) = self.vars.get(name) {
+                return InferType::Variable(var.clone());
+            }
+        }
+        if let Type::Function(params, ret) = type_ {
+            return InferType::Function(
+                params.iter().map(|p| self.open(p)).collect(),
+                Box::new(self.open(ret)),
+            );
+        }
+        match type_constructor(type_) {
+            Some((name, args)) => InferType::Applied(
+                name.to_string(),
+                args.into_iter().map(|a| self.open(a)).collect(),
+            ),
+            None => InferType::Concrete(type_.clone()),
+        }
+    }
+    
+    /// Whether a declared type mentions any of the type parameters
+    fn mentions_params(&self, type_: &Type) -> bool {
+        !self.vars.is_empty() && self.open(type_).to_concrete().as_ref() != Some(type_)
+    }
+    
+    /// Require a value of type `actual` where `declared` is expected,
+    /// learning the type arguments this implies
+    fn expect(&mut self, checker: &TypeChecker, declared: &Type, actual: &Type, span: Span) -> Result<(), SemanticError> {
+        let compatible = if self.mentions_params(declared) {
+            let expected = self.subst.apply(&self.open(declared));
+            match checker.unify(&expected, &InferType::Concrete(actual.clone()), span) {
+                Ok(unified) => {
+                    self.subst = self.subst.compose(&unified);
+                    true
+                }
+                Err(_) => false,
+            }
+        } else {
+            checker.types_compatible(actual, declared)
+        };
+        
+        if compatible {
+            Ok(())
+        } else {
+            Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: format!("{}", self.resolve(declared).unwrap_or_else(|| declared.clone())),
+                    found: format!("{}", actual),
+                },
+            })
+        }
+    }
+    
+    /// A declared type with the type arguments learned so far, if they are
+    /// all known
+    fn resolve(&self, type_: &Type) -> Option<Type> {
+        self.subst.apply(&self.open(type_)).to_concrete()
+    }
+    
+    /// The inferred type arguments in declaration order
+    fn type_args(&self, span: Span) -> Result<Vec<Type>, SemanticError> {
+        self.params.iter()
+            .map(|param| self.resolve(&Type::Named(param.clone())))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SemanticError { span, kind: SemanticErrorKind::CannotInferType })
+    }
+}
+
 /// Type checker for Flux programs with unification-based inference
 #[derive(Debug)]
 pub struct TypeChecker {
     type_env: TypeEnvironment,
     constraints: Vec<(InferType, InferType, Span)>,
+    variadic_functions: HashSet<String>,
+    fields: HashMap<String, Vec<Field>>,
+    methods: HashMap<String, Vec<Method>>,
+    enums: HashMap<String, Enum>,
+    interfaces: HashMap<String, Interface>,
+    /// (type, interface) pairs with an impl block
+    impls: HashSet<(String, String)>,
+    /// Type parameters of generic functions, structs and classes
+    type_params: HashMap<String, Vec<String>>,
+    /// Variables assigned in the function being checked; closures capture
+    /// them by reference
+    assigned_variables: HashSet<String>,
+    /// Types left to inference in the function being inferred: unannotated
+    /// bindings, lambda parameters and empty collection literals
+    inference_sites: Vec<(Span, InferType)>,
+    /// Solved types of the inference sites of the function being checked
+    inferred: HashMap<Span, Type>,
+    /// Return types of the enclosing function and lambdas during inference
+    return_types: Vec<InferType>,
+    /// Return types of the enclosing function and lambdas during checking,
+    /// innermost last; `None` for a lambda whose return type is unknown
+    enclosing_returns: Vec<Option<Type>>,
+    /// Errors in statements and items, which are checked past their first error
+    errors: Vec<SemanticError>,
+    /// Spans of errors that only repeat an earlier, reported error: uses of
+    /// poisoned bindings and blocks with statements that failed to check
+    poisoned: HashSet<Span>,
 }
 
 impl TypeChecker {
@@ -231,6 +627,154 @@ impl TypeChecker {
         Self {
             type_env: TypeEnvironment::new(),
             constraints: Vec::new(),
+            variadic_functions: HashSet::new(),
+            fields: HashMap::new(),
+            methods: HashMap::new(),
+            enums: HashMap::new(),
+            interfaces: HashMap::new(),
+            impls: HashSet::new(),
+            type_params: GENERIC_BUILTINS.iter()
+                .map(|(name, params)| (name.to_string(), params.iter().map(|p| p.to_string()).collect()))
+                .collect(),
+            assigned_variables: HashSet::new(),
+            inference_sites: Vec::new(),
+            inferred: HashMap::new(),
+            return_types: Vec::new(),
+            enclosing_returns: Vec::new(),
+            errors: Vec::new(),
+            poisoned: HashSet::new(),
+        }
+    }
+    
+    /// Record an error and carry on checking, unless it only follows from
+    /// an earlier error
+    pub fn report(&mut self, error: SemanticError) {
+        if !self.poisoned.remove(&error.span) {
+            self.errors.push(error);
+        }
+    }
+    
+    /// An error at `span` that follows from an error already reported
+    fn poisoned_error(&mut self, span: Span) -> SemanticError {
+        self.poisoned.insert(span);
+        SemanticError { span, kind: SemanticErrorKind::CannotInferType }
+    }
+    
+    /// Take the errors reported so far
+    pub fn take_errors(&mut self) -> Vec<SemanticError> {
+        std::mem::take(&mut self.errors)
+    }
+    
+    /// The type of a binding during checking
+    fn binding_type(&mut self, name: &str, span: Span) -> Result<Type, SemanticError> {
+        match self.type_env.lookup(name) {
+            Some(InferType::Error) => Err(self.poisoned_error(span)),
+            Some(type_) => type_.to_concrete().ok_or(SemanticError {
+                span,
+                kind: SemanticErrorKind::CannotInferType,
+            }),
+            None => Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::UndefinedVariable { name: name.to_string() },
+            }),
+        }
+    }
+    
+    /// Declare a function signature so calls can be checked before its body
+    pub fn declare_function(&mut self, name: String, parameters: &[Parameter], return_type: &Option<Type>) {
+        let param_types = parameters.iter()
+            .map(|p| InferType::Concrete(p.type_.clone()))
+            .collect();
+        let return_type = return_type.clone().unwrap_or(Type::Unit);
+        self.type_env.bind(name, InferType::Function(param_types, Box::new(InferType::Concrete(return_type))));
+    }
+    
+    /// Declare the top-level items of a program in the global scope
+    pub fn declare_items(&mut self, items: &[Item]) {
+        for item in items {
+            self.declare_item(item.name().to_string(), item);
+        }
+    }
+    
+    /// Declare the public items of an imported module under qualified names.
+    /// The types the module declares are qualified in the signatures too, so
+    /// they name the same types as `name::Type` in the importing module.
+    pub fn declare_module(&mut self, name: &str, exports: &ModuleExports) {
+        let qualify = |type_: &Type| qualify_type(type_, &|type_name| {
+            matches!(
+                exports.get(type_name),
+                Some(Item::Struct(_) | Item::Class(_) | Item::Enum(_) | Item::Interface(_))
+            ).then(|| format!("{}::{}", name, type_name))
+        });
+        for item in exports.public_items() {
+            self.declare_item(format!("{}::{}", name, item.name()), &map_item_types(item, &qualify));
+        }
+    }
+    
+    fn declare_item(&mut self, name: String, item: &Item) {
+        match item {
+            Item::Function(func) => {
+                if !func.type_params.is_empty() {
+                    self.type_params.insert(name.clone(), func.type_params.clone());
+                }
+                self.declare_function(name, &func.parameters, &func.return_type);
+            }
+            Item::ExternFunction(extern_func) => {
+                if extern_func.is_variadic {
+                    self.variadic_functions.insert(name.clone());
+                }
+                self.declare_function(name, &extern_func.parameters, &extern_func.return_type);
+            }
+            Item::Const(const_def) => {
+                self.type_env.bind(name, InferType::Concrete(const_def.type_.clone()));
+            }
+            Item::Struct(struct_def) => {
+                if !struct_def.type_params.is_empty() {
+                    self.type_params.insert(name.clone(), struct_def.type_params.clone());
+                }
+                self.fields.insert(name, struct_def.fields.clone());
+            }
+            Item::Class(class_def) => {
+                // Static methods are values reachable as `Class::method`, generic
+                // over the class's type parameters
+                for method in class_def.methods.iter().filter(|m| m.is_static) {
+                    let method_name = format!("{}::{}", name, method.name);
+                    if !class_def.type_params.is_empty() {
+                        self.type_params.insert(method_name.clone(), class_def.type_params.clone());
+                    }
+                    self.declare_function(method_name, &method.parameters, &method.return_type);
+                }
+                if !class_def.type_params.is_empty() {
+                    self.type_params.insert(name.clone(), class_def.type_params.clone());
+                }
+                self.fields.insert(name.clone(), class_def.fields.clone());
+                self.methods.entry(name).or_default().extend(class_def.methods.iter().cloned());
+            }
+            Item::Interface(interface) => {
+                self.interfaces.insert(name, interface.clone());
+            }
+            Item::Impl(impl_block) => {
+                // Implemented methods are called like the type's own methods
+                self.impls.insert((name.clone(), impl_block.interface.clone()));
+                self.methods.entry(name).or_default().extend(impl_block.methods.iter().cloned());
+            }
+            Item::Enum(enum_def) => {
+                // Unit and tuple variants are values reachable as `Enum::Variant`;
+                // struct-like variants are built with a struct literal
+                let enum_type = Type::Named(name.clone());
+                for variant in &enum_def.variants {
+                    let constructor = match &variant.payload {
+                        VariantPayload::Unit => InferType::Concrete(enum_type.clone()),
+                        VariantPayload::Tuple(types) => InferType::Function(
+                            types.iter().map(|t| InferType::Concrete(t.clone())).collect(),
+                            Box::new(InferType::Concrete(enum_type.clone())),
+                        ),
+                        VariantPayload::Struct(_) => continue,
+                    };
+                    self.type_env.bind(format!("{}::{}", name, variant.name), constructor);
+                }
+                self.enums.insert(name, enum_def.clone());
+            }
         }
     }
     
@@ -260,6 +804,9 @@ impl TypeChecker {
                 Ok(Substitution::new())
             }
             
+            // A poisoned type already caused an error
+            (InferType::Error, _) | (_, InferType::Error) => Ok(Substitution::new()),
+            
             // Variable unifies with any type (occurs check)
             (InferType::Variable(var), ty) | (ty, InferType::Variable(var)) => {
                 if ty.occurs_check(var) {
@@ -366,45 +913,81 @@ impl TypeChecker {
                 Ok(subst)
             }
             
+            // Applied constructors unify if their arguments unify
+            (InferType::Applied(name1, args1), InferType::Applied(name2, args2))
+                if name1 == name2 && args1.len() == args2.len() =>
+            {
+                let mut subst = Substitution::new();
+                for (a1, a2) in args1.iter().zip(args2.iter()) {
+                    let arg_subst = self.unify(&subst.apply(a1), &subst.apply(a2), span)?;
+                    subst = subst.compose(&arg_subst);
+                }
+                Ok(subst)
+            }
+            
+            // A compound concrete type unifies with an applied constructor
+            (InferType::Applied(..), InferType::Concrete(concrete)) => match InferType::decompose(concrete) {
+                Some(decomposed) => self.unify(t1, &decomposed, span),
+                None => Err(Self::unify_mismatch(t1, t2, span)),
+            },
+            (InferType::Concrete(concrete), InferType::Applied(..)) => match InferType::decompose(concrete) {
+                Some(decomposed) => self.unify(&decomposed, t2, span),
+                None => Err(Self::unify_mismatch(t1, t2, span)),
+            },
+            
             // Types don't unify
-            _ => Err(SemanticError {
-                span,
-                kind: SemanticErrorKind::TypeMismatch {
-                    expected: format!("{:?}", t1),
-                    found: format!("{:?}", t2),
-                },
-            })
+            _ => Err(Self::unify_mismatch(t1, t2, span)),
+        }
+    }
+
+    /// The error for two types that don't unify
+    fn unify_mismatch(t1: &InferType, t2: &InferType, span: Span) -> SemanticError {
+        SemanticError {
+            span,
+            kind: SemanticErrorKind::TypeMismatch {
+                expected: format!("{:?}", t1),
+                found: format!("{:?}", t2),
+            },
         }
     }
     
     /// Infer the type of an expression using constraint-based inference
     pub fn infer_expression(&mut self, expr: &Expression) -> Result<InferType, SemanticError> {
-        let span = Span::single(crate::position::Position::start()); // Placeholder
+        let span = expr.span;
         
-        match expr {
-            Expression::Literal(lit) => Ok(InferType::Concrete(self.literal_type(lit))),
+        match &expr.kind {
+            ExpressionKind::Literal(lit) => Ok(InferType::Concrete(self.literal_type(lit))),
             
-            Expression::Identifier(name) => {
-                self.type_env.lookup(name)
+            ExpressionKind::Identifier(name) => {
+                let type_ = self.type_env.lookup(name)
                     .cloned()
                     .ok_or_else(|| SemanticError {
                         span,
                         kind: SemanticErrorKind::UndefinedVariable { name: name.clone() },
-                    })
+                    })?;
+                Ok(self.instantiate(name, type_))
+            }
+            
+            ExpressionKind::Path(segments) => {
+                let name = segments.join("::");
+                match self.type_env.lookup(&name).cloned() {
+                    Some(type_) => Ok(self.instantiate(&name, type_)),
+                    None => Ok(InferType::Variable(self.type_env.fresh_var())),
+                }
             }
             
-            Expression::Binary(left, op, right) => {
+            ExpressionKind::Binary(left, op, right) => {
                 let left_type = self.infer_expression(left)?;
                 let right_type = self.infer_expression(right)?;
                 self.infer_binary_op(&left_type, op, &right_type, span)
             }
             
-            Expression::Unary(op, expr) => {
+            ExpressionKind::Unary(op, expr) => {
                 let expr_type = self.infer_expression(expr)?;
                 self.infer_unary_op(op, &expr_type, span)
             }
             
-            Expression::Call(func, args) => {
+            ExpressionKind::Call(func, args) => {
                 let func_type = self.infer_expression(func)?;
                 let arg_types: Result<Vec<_>, _> = args.iter()
                     .map(|arg| self.infer_expression(arg))
@@ -421,10 +1004,16 @@ impl TypeChecker {
                 // Add constraint that function type matches expected
                 self.add_constraint(func_type, expected_func_type, span);
                 
+                // Type arguments of a generic callee may only be implied by
+                // how the result is used, as with the error type of `Ok(x)`
+                if matches!(&func.kind, ExpressionKind::Identifier(name) if self.type_params.contains_key(name)) {
+                    self.inference_sites.push((span, InferType::Variable(return_var.clone())));
+                }
+                
                 Ok(InferType::Variable(return_var))
             }
             
-            Expression::If(cond, then_block, else_block) => {
+            ExpressionKind::If(cond, then_block, else_block) => {
                 let cond_type = self.infer_expression(cond)?;
                 
                 // Condition must be boolean
@@ -453,68 +1042,180 @@ impl TypeChecker {
                 }
             }
             
-            Expression::Block(block) => self.infer_block(block),
+            ExpressionKind::Block(block) => self.infer_block(block),
             
-            Expression::Array(elements) => {
+            ExpressionKind::Array(elements) => {
+                // All elements share one type
+                let elem_type = InferType::Variable(self.type_env.fresh_var());
+                for elem in elements {
+                    let t = self.infer_expression(elem)?;
+                    self.add_constraint(elem_type.clone(), t, elem.span);
+                }
+                
+                let array_type = InferType::Applied("[]".to_string(), vec![elem_type]);
                 if elements.is_empty() {
-                    // Empty array gets fresh type variable
-                    let _elem_var = self.type_env.fresh_var();
-                    Ok(InferType::Concrete(Type::Array(Box::new(Type::Generic("T".to_string(), vec![])))))
-                } else {
-                    let first_type = self.infer_expression(&elements[0])?;
-                    
-                    // All elements must have same type
-                    for elem in &elements[1..] {
-                        let elem_type = self.infer_expression(elem)?;
-                        self.add_constraint(first_type.clone(), elem_type, span.clone());
-                    }
-                    
-                    // Solve constraints to get concrete type
-                    let subst = self.solve_constraints()?;
-                    let resolved_first = subst.apply(&first_type);
-                    
-                    if let Some(concrete) = resolved_first.to_concrete() {
-                        Ok(InferType::Concrete(Type::Array(Box::new(concrete))))
-                    } else {
-                        Ok(InferType::Concrete(Type::Array(Box::new(Type::Generic("T".to_string(), vec![])))))
+                    self.inference_sites.push((span, array_type.clone()));
+                }
+                Ok(array_type)
+            }
+            
+            ExpressionKind::Tuple(elements) => {
+                let element_types = elements.iter()
+                    .map(|element| self.infer_expression(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(InferType::Applied("()".to_string(), element_types))
+            }
+            
+            ExpressionKind::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expression(hole) = part {
+                        self.infer_expression(hole)?;
                     }
                 }
+                Ok(InferType::Concrete(Type::String))
+            }
+            
+            ExpressionKind::Range(start, end, step, _) => {
+                for bound in [Some(start), Some(end), step.as_ref()].into_iter().flatten() {
+                    let t = self.infer_expression(bound)?;
+                    self.add_constraint(t, InferType::Concrete(Type::Int), bound.span);
+                }
+                Ok(InferType::Concrete(Type::Range))
+            }
+            
+            ExpressionKind::Map(pairs) => {
+                let key_type = InferType::Variable(self.type_env.fresh_var());
+                let value_type = InferType::Variable(self.type_env.fresh_var());
+                for (key, value) in pairs {
+                    let t = self.infer_expression(key)?;
+                    self.add_constraint(key_type.clone(), t, key.span);
+                    let t = self.infer_expression(value)?;
+                    self.add_constraint(value_type.clone(), t, value.span);
+                }
+                
+                let map_type = InferType::Applied("Map".to_string(), vec![key_type, value_type]);
+                if pairs.is_empty() {
+                    self.inference_sites.push((span, map_type.clone()));
+                }
+                Ok(map_type)
             }
             
-            Expression::Index(array, index) => {
-                let array_type = self.infer_expression(array)?;
+            ExpressionKind::Index(collection, index) => {
+                let collection_type = self.infer_expression(collection)?;
                 let index_type = self.infer_expression(index)?;
+                let elem_type = InferType::Variable(self.type_env.fresh_var());
                 
-                // Index must be integer
-                self.add_constraint(
-                    index_type,
-                    InferType::Concrete(Type::Int),
-                    span.clone()
-                );
+                // Maps are indexed by key, anything else is an array indexed by int
+                let is_map = match &collection_type {
+                    InferType::Concrete(Type::Map(_, _)) => true,
+                    InferType::Applied(name, _) => name == "Map",
+                    _ => false,
+                };
+                if is_map {
+                    let key_type = InferType::Variable(self.type_env.fresh_var());
+                    self.add_constraint(
+                        collection_type,
+                        InferType::Applied("Map".to_string(), vec![key_type.clone(), elem_type.clone()]),
+                        span
+                    );
+                    self.add_constraint(index_type, key_type, index.span);
+                } else {
+                    self.add_constraint(
+                        collection_type,
+                        InferType::Applied("[]".to_string(), vec![elem_type.clone()]),
+                        span
+                    );
+                    self.add_constraint(index_type, InferType::Concrete(Type::Int), index.span);
+                }
                 
-                // Create fresh type variable for element type
-                let elem_var = self.type_env.fresh_var();
-                let expected_array_type = InferType::Concrete(Type::Array(
-                    Box::new(Type::Generic("T".to_string(), vec![]))
-                ));
+                Ok(elem_type)
+            }
+            
+            ExpressionKind::Field(obj, field) => {
+                let obj_type = self.infer_expression(obj)?;
+                
+                // Members can be looked up when the object's type is already known
+                match obj_type.to_concrete().and_then(|t| self.member_type(&t, field)) {
+                    Some(member_type) => Ok(InferType::Concrete(member_type)),
+                    None => Ok(InferType::Variable(self.type_env.fresh_var())),
+                }
+            }
+            
+            ExpressionKind::StructLiteral(name, inits) => {
+                if let Some((enum_name, _)) = self.lookup_variant(name) {
+                    for (_, value) in inits {
+                        self.infer_expression(value)?;
+                    }
+                    return Ok(InferType::Concrete(Type::Named(enum_name)));
+                }
                 
-                self.add_constraint(array_type, expected_array_type, span);
+                // Type arguments of a generic struct come from its field values
+                let fields = self.fields.get(name).cloned().unwrap_or_default();
+                let params = self.type_params.get(name).cloned().unwrap_or_default();
+                let instantiation = Instantiation::new(&params, &mut self.type_env);
+                for (field_name, value) in inits {
+                    let value_type = self.infer_expression(value)?;
+                    if let Some(field) = fields.iter().find(|f| &f.name == field_name) {
+                        self.add_constraint(instantiation.open(&field.type_), value_type, value.span);
+                    }
+                }
                 
-                Ok(InferType::Variable(elem_var))
+                if params.is_empty() {
+                    Ok(InferType::Concrete(Type::Named(name.clone())))
+                } else {
+                    let args = params.iter().map(|p| instantiation.open(&Type::Named(p.clone()))).collect();
+                    Ok(InferType::Applied(name.clone(), args))
+                }
             }
             
-            Expression::Field(obj, field) => {
-                let _obj_type = self.infer_expression(obj)?;
-                // Field access requires struct/class type information
-                // For now, return a fresh type variable
-                let field_var = self.type_env.fresh_var();
-                Ok(InferType::Variable(field_var))
+            ExpressionKind::Match(scrutinee, arms) => {
+                self.infer_expression(scrutinee)?;
+                
+                // Every arm produces the type of the match
+                let match_type = InferType::Variable(self.type_env.fresh_var());
+                for arm in arms {
+                    self.type_env.enter_scope();
+                    let arm_type = self.infer_match_arm(arm);
+                    self.type_env.exit_scope();
+                    self.add_constraint(match_type.clone(), arm_type?, arm.span);
+                }
+                Ok(match_type)
             }
             
-            _ => {
-                // For unimplemented expression types, return fresh type variable
-                let var = self.type_env.fresh_var();
-                Ok(InferType::Variable(var))
+            ExpressionKind::Lambda(params, return_type, body) => {
+                self.type_env.enter_scope();
+                let mut param_types = Vec::new();
+                for param in params {
+                    let param_type = match &param.type_ {
+                        Some(t) => InferType::Concrete(t.clone()),
+                        None => {
+                            let var = InferType::Variable(self.type_env.fresh_var());
+                            self.inference_sites.push((param.span, var.clone()));
+                            var
+                        }
+                    };
+                    self.type_env.bind(param.name.clone(), param_type.clone());
+                    param_types.push(param_type);
+                }
+                
+                let declared = match return_type {
+                    Some(t) => InferType::Concrete(t.clone()),
+                    None => InferType::Variable(self.type_env.fresh_var()),
+                };
+                self.return_types.push(declared.clone());
+                let body_type = self.infer_expression(body);
+                self.return_types.pop();
+                self.type_env.exit_scope();
+                let body_type = body_type?;
+                
+                // Without an annotation, the lambda returns the type of its body
+                let result_type = if return_type.is_some() {
+                    declared
+                } else {
+                    self.inference_sites.push((span, body_type.clone()));
+                    body_type
+                };
+                Ok(InferType::Function(param_types, Box::new(result_type)))
             }
         }
     }
@@ -526,11 +1227,11 @@ impl TypeChecker {
         let mut block_type = InferType::Concrete(Type::Unit);
         
         for stmt in &block.statements {
-            match stmt {
-                Statement::Expression(expr) => {
+            match &stmt.kind {
+                StatementKind::Expression(expr) => {
                     block_type = self.infer_expression(expr)?;
                 }
-                Statement::Let(name, type_annotation, init) => {
+                StatementKind::Let(pattern, type_annotation, init) => {
                     let var_type = if let Some(init_expr) = init {
                         let inferred = self.infer_expression(init_expr)?;
                         
@@ -539,7 +1240,7 @@ impl TypeChecker {
                             self.add_constraint(
                                 inferred.clone(),
                                 annotated.clone(),
-                                Span::single(crate::position::Position::start())
+                                stmt.span
                             );
                             annotated
                         } else {
@@ -548,24 +1249,80 @@ impl TypeChecker {
                     } else if let Some(annotation) = type_annotation {
                         InferType::Concrete(annotation.clone())
                     } else {
-                        return Err(SemanticError {
-                            span: Span::single(crate::position::Position::start()),
-                            kind: SemanticErrorKind::CannotInferType,
-                        });
+                        // The type comes from the values assigned later
+                        let var = InferType::Variable(self.type_env.fresh_var());
+                        self.inference_sites.push((stmt.span, var.clone()));
+                        var
                     };
                     
-                    self.type_env.bind(name.clone(), var_type);
+                    self.infer_pattern(pattern, var_type, stmt.span);
                     block_type = InferType::Concrete(Type::Unit);
                 }
-                Statement::Return(expr) => {
+                StatementKind::Return(expr) => {
                     if let Some(e) = expr {
                         block_type = self.infer_expression(e)?;
                     } else {
                         block_type = InferType::Concrete(Type::Unit);
                     }
+                    if let Some(return_type) = self.return_types.last().cloned() {
+                        self.add_constraint(return_type, block_type.clone(), stmt.span);
+                    }
+                }
+                StatementKind::Const(name, type_, value) => {
+                    let value_type = self.infer_expression(value)?;
+                    self.add_constraint(InferType::Concrete(type_.clone()), value_type, value.span);
+                    self.type_env.bind(name.clone(), InferType::Concrete(type_.clone()));
+                    block_type = InferType::Concrete(Type::Unit);
+                }
+                StatementKind::Assignment(target, value) => {
+                    let target_type = self.infer_expression(target)?;
+                    let value_type = self.infer_expression(value)?;
+                    self.add_constraint(target_type, value_type, value.span);
+                    block_type = InferType::Concrete(Type::Unit);
+                }
+                StatementKind::If(cond, then_block, else_block) => {
+                    let cond_type = self.infer_expression(cond)?;
+                    self.add_constraint(cond_type, InferType::Concrete(Type::Bool), cond.span);
+                    self.infer_block(then_block)?;
+                    if let Some(else_block) = else_block {
+                        self.infer_block(else_block)?;
+                    }
+                    block_type = InferType::Concrete(Type::Unit);
+                }
+                StatementKind::While(cond, body) => {
+                    let cond_type = self.infer_expression(cond)?;
+                    self.add_constraint(cond_type, InferType::Concrete(Type::Bool), cond.span);
+                    self.infer_block(body)?;
+                    block_type = InferType::Concrete(Type::Unit);
+                }
+                StatementKind::For(pattern, iter, body) => {
+                    let iter_type = self.infer_expression(iter)?;
+                    let elem_type = match iter_type.to_concrete().as_ref().and_then(element_type) {
+                        Some(t) => InferType::Concrete(t),
+                        None => InferType::Variable(self.type_env.fresh_var()),
+                    };
+                    self.type_env.enter_scope();
+                    self.infer_pattern(pattern, elem_type, stmt.span);
+                    let body_type = self.infer_block(body);
+                    self.type_env.exit_scope();
+                    body_type?;
+                    block_type = InferType::Concrete(Type::Unit);
+                }
+                StatementKind::Match(scrutinee, arms) => {
+                    self.infer_expression(scrutinee)?;
+                    for arm in arms {
+                        self.type_env.enter_scope();
+                        let arm_type = self.infer_match_arm(arm);
+                        self.type_env.exit_scope();
+                        arm_type?;
+                    }
+                    block_type = InferType::Concrete(Type::Unit);
+                }
+                StatementKind::Go(expr) | StatementKind::Break(Some(expr)) => {
+                    self.infer_expression(expr)?;
+                    block_type = InferType::Concrete(Type::Unit);
                 }
-                _ => {
-                    // Other statements return unit
+                StatementKind::Break(None) | StatementKind::Continue => {
                     block_type = InferType::Concrete(Type::Unit);
                 }
             }
@@ -575,7 +1332,115 @@ impl TypeChecker {
         Ok(block_type)
     }
     
-    /// Infer type for binary operations
+    /// Bind the names of a `let` or `for` pattern to the parts of the type
+    /// of the value it takes apart
+    fn infer_pattern(&mut self, pattern: &Pattern, type_: InferType, span: Span) {
+        match pattern {
+            Pattern::Identifier(name) => self.type_env.bind(name.clone(), type_),
+            Pattern::Tuple(patterns) => {
+                let element_types: Vec<InferType> = match &type_ {
+                    InferType::Concrete(Type::Tuple(types)) if types.len() == patterns.len() => {
+                        types.iter().cloned().map(InferType::Concrete).collect()
+                    }
+                    _ => {
+                        let vars: Vec<InferType> = patterns.iter()
+                            .map(|_| InferType::Variable(self.type_env.fresh_var()))
+                            .collect();
+                        self.add_constraint(type_, InferType::Applied("()".to_string(), vars.clone()), span);
+                        vars
+                    }
+                };
+                for (pattern, element_type) in patterns.iter().zip(element_types) {
+                    self.infer_pattern(pattern, element_type, span);
+                }
+            }
+            Pattern::Struct(name, field_patterns) => {
+                for (field_name, pattern) in field_patterns {
+                    let field = self.fields.get(name).and_then(|fields| fields.iter().find(|f| &f.name == field_name));
+                    let field_type = match field {
+                        Some(field) => InferType::Concrete(field.type_.clone()),
+                        None => InferType::Variable(self.type_env.fresh_var()),
+                    };
+                    self.infer_pattern(pattern, field_type, span);
+                }
+            }
+            // Refutable patterns are rejected when the statement is checked
+            _ => {
+                for name in pattern_bindings(pattern) {
+                    let var = InferType::Variable(self.type_env.fresh_var());
+                    self.type_env.bind(name, var);
+                }
+            }
+        }
+    }
+    
+    /// Infer the type of a match arm's body, with the names its pattern binds
+    /// in scope
+    fn infer_match_arm(&mut self, arm: &MatchArm) -> Result<InferType, SemanticError> {
+        for name in pattern_bindings(&arm.pattern) {
+            let var = InferType::Variable(self.type_env.fresh_var());
+            self.type_env.bind(name, var);
+        }
+        if let Some(guard) = &arm.guard {
+            let guard_type = self.infer_expression(guard)?;
+            self.add_constraint(guard_type, InferType::Concrete(Type::Bool), guard.span);
+        }
+        self.infer_block(&arm.body)
+    }
+    
+    /// The type of a name, with fresh variables for the type parameters of a
+    /// generic function
+    fn instantiate(&mut self, name: &str, type_: InferType) -> InferType {
+        match (self.type_params.get(name), type_.to_concrete()) {
+            (Some(params), Some(concrete)) => Instantiation::new(params, &mut self.type_env).open(&concrete),
+            _ => type_,
+        }
+    }
+    
+    /// Infer the types of a function body before it is checked. Constraints
+    /// are solved together, so a binding's type may come from any later use;
+    /// the solution for each inference site is recorded in `inferred`.
+    /// Constraints that do not unify, and errors, are left to the checking
+    /// pass, which reports them against the typed tree.
+    fn infer_body(&mut self, params: &[(String, Type)], return_type: &Type, body: &Block) {
+        self.constraints.clear();
+        self.inference_sites.clear();
+        self.inferred.clear();
+        
+        let depth = self.type_env.scope_depth();
+        self.type_env.enter_scope();
+        for (name, type_) in params {
+            self.type_env.bind(name.clone(), InferType::Concrete(type_.clone()));
+        }
+        self.return_types.push(InferType::Concrete(return_type.clone()));
+        let _ = self.infer_block(body);
+        self.return_types.clear();
+        while self.type_env.scope_depth() > depth {
+            self.type_env.exit_scope();
+        }
+        
+        let mut subst = Substitution::new();
+        for (t1, t2, span) in std::mem::take(&mut self.constraints) {
+            if let Ok(unified) = self.unify(&subst.apply(&t1), &subst.apply(&t2), span) {
+                subst = subst.compose(&unified);
+            }
+        }
+        for (span, type_) in std::mem::take(&mut self.inference_sites) {
+            if let Some(type_) = subst.apply(&type_).to_concrete() {
+                self.inferred.insert(span, type_);
+            }
+        }
+    }
+    
+    /// The type inferred for a binding or literal without a written type
+    fn inferred_type(&self, span: Span) -> Result<Type, SemanticError> {
+        self.inferred.get(&span).cloned().ok_or(SemanticError {
+            span,
+            kind: SemanticErrorKind::CannotInferType,
+        })
+    }
+    
+    /// Infer type for binary operations
     fn infer_binary_op(&mut self, left: &InferType, op: &BinaryOp, right: &InferType, span: Span) -> Result<InferType, SemanticError> {
         match op {
             BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => {
@@ -610,6 +1475,9 @@ impl TypeChecker {
                 Ok(InferType::Concrete(Type::Bool))
             }
             
+            // The default stands in for a null left operand
+            BinaryOp::Coalesce => Ok(right.clone()),
+            
             _ => {
                 // Other binary operations not yet implemented
                 let result_var = self.type_env.fresh_var();
@@ -632,6 +1500,26 @@ impl TypeChecker {
                 Ok(InferType::Concrete(Type::Bool))
             }
             
+            UnaryOp::Try => {
+                if let InferType::Concrete(Type::Nullable(inner)) = operand {
+                    return Ok(InferType::Concrete((**inner).clone()));
+                }
+                // Otherwise the operand is a `Result` and the expression its `Ok` value
+                let ok_var = InferType::Variable(self.type_env.fresh_var());
+                let err_var = InferType::Variable(self.type_env.fresh_var());
+                self.add_constraint(
+                    operand.clone(),
+                    InferType::Applied("Result".to_string(), vec![ok_var.clone(), err_var]),
+                    span
+                );
+                Ok(ok_var)
+            }
+            
+            UnaryOp::Unwrap => match operand {
+                InferType::Concrete(Type::Nullable(inner)) => Ok(InferType::Concrete((**inner).clone())),
+                _ => Ok(InferType::Variable(self.type_env.fresh_var())),
+            },
+            
             _ => {
                 // Other unary operations not yet implemented
                 let result_var = self.type_env.fresh_var();
@@ -647,7 +1535,7 @@ impl TypeChecker {
         let resolved = subst.apply(&inferred);
         
         resolved.to_concrete().ok_or_else(|| SemanticError {
-            span: Span::single(crate::position::Position::start()),
+            span: expr.span,
             kind: SemanticErrorKind::CannotInferType,
         })
     }
@@ -659,7 +1547,7 @@ impl TypeChecker {
             Ok(())
         } else {
             Err(SemanticError {
-                span: Span::single(crate::position::Position::start()),
+                span: expr.span,
                 kind: SemanticErrorKind::TypeMismatch {
                     expected: format!("{}", expected),
                     found: format!("{}", actual),
@@ -688,6 +1576,11 @@ impl TypeChecker {
     
     /// Type check a function
     pub fn check_function(&mut self, func: &Function) -> Result<TypedFunction, SemanticError> {
+        let params: Vec<_> = func.parameters.iter().map(|p| (p.name.clone(), p.type_.clone())).collect();
+        self.infer_body(&params, &func.return_type.clone().unwrap_or(Type::Unit), &func.body);
+        
+        self.type_env.enter_scope();
+        
         // Add parameters to type environment
         let mut typed_params = Vec::new();
         for param in &func.parameters {
@@ -700,18 +1593,26 @@ impl TypeChecker {
         }
         
         // Type check function body
-        let typed_body = self.check_block(&func.body)?;
-        
-        // Determine return type
         let return_type = func.return_type.clone().unwrap_or(Type::Unit);
+        self.assigned_variables = captures::assigned_variables(&func.body);
+        self.enclosing_returns.push(Some(return_type.clone()));
+        let typed_body = self.check_block(&func.body);
+        self.enclosing_returns.pop();
+        self.type_env.exit_scope();
+        let typed_body = typed_body?;
+        if let Err(error) = self.check_tail(&typed_body, &return_type) {
+            self.report(error);
+        }
         
         Ok(TypedFunction {
             name: func.name.clone(),
+            type_params: func.type_params.clone(),
             parameters: typed_params,
             return_type,
             body: typed_body,
             is_async: func.is_async,
             visibility: func.visibility.clone(),
+            span: Some(func.span),
         })
     }
     
@@ -753,6 +1654,7 @@ impl TypeChecker {
         
         Ok(TypedStruct {
             name: struct_def.name.clone(),
+            type_params: struct_def.type_params.clone(),
             fields: typed_fields,
             visibility: struct_def.visibility.clone(),
         })
@@ -773,22 +1675,146 @@ impl TypeChecker {
         }
         
         for method in &class_def.methods {
-            let typed_method = self.check_method(method)?;
+            let typed_method = self.check_method(&class_def.name, method)?;
             typed_methods.push(typed_method);
         }
         
         Ok(TypedClass {
             name: class_def.name.clone(),
+            type_params: class_def.type_params.clone(),
             fields: typed_fields,
             methods: typed_methods,
             visibility: class_def.visibility.clone(),
         })
     }
     
+    /// Type check an enum
+    pub fn check_enum(&mut self, enum_def: &Enum) -> Result<TypedEnum, SemanticError> {
+        let variants = enum_def.variants.iter()
+            .enumerate()
+            .map(|(tag, variant)| {
+                let fields = match &variant.payload {
+                    VariantPayload::Unit => Vec::new(),
+                    VariantPayload::Tuple(types) => types.iter()
+                        .enumerate()
+                        .map(|(index, type_)| TypedField {
+                            name: index.to_string(),
+                            type_: type_.clone(),
+                            visibility: Visibility::Public,
+                            is_mutable: false,
+                        })
+                        .collect(),
+                    VariantPayload::Struct(fields) => fields.iter()
+                        .map(|field| TypedField {
+                            name: field.name.clone(),
+                            type_: field.type_.clone(),
+                            visibility: field.visibility.clone(),
+                            is_mutable: field.is_mutable,
+                        })
+                        .collect(),
+                };
+                TypedVariant { name: variant.name.clone(), tag, fields }
+            })
+            .collect();
+        
+        Ok(TypedEnum {
+            name: enum_def.name.clone(),
+            variants,
+            visibility: enum_def.visibility.clone(),
+        })
+    }
+    
+    /// Type check an interface
+    pub fn check_interface(&mut self, interface: &Interface) -> Result<TypedInterface, SemanticError> {
+        let methods = interface.methods.iter()
+            .map(|method| TypedMethodSignature {
+                name: method.name.clone(),
+                parameters: method.parameters.iter()
+                    .map(|param| TypedParameter {
+                        name: param.name.clone(),
+                        type_: param.type_.clone(),
+                        is_mutable: param.is_mutable,
+                    })
+                    .collect(),
+                return_type: method.return_type.clone().unwrap_or(Type::Unit),
+            })
+            .collect();
+        
+        Ok(TypedInterface {
+            name: interface.name.clone(),
+            methods,
+            visibility: interface.visibility.clone(),
+        })
+    }
+    
+    /// Type check an impl block and its conformance to the interface
+    pub fn check_impl(&mut self, impl_block: &Impl) -> Result<TypedImpl, SemanticError> {
+        let interface = self.interfaces.get(&impl_block.interface).cloned().ok_or_else(|| SemanticError {
+            span: impl_block.span,
+            kind: SemanticErrorKind::UndefinedVariable { name: impl_block.interface.clone() },
+        })?;
+        let not_implemented = |span: Span, reason: String| SemanticError {
+            span,
+            kind: SemanticErrorKind::InterfaceNotImplemented {
+                type_name: impl_block.type_name.clone(),
+                interface: interface.name.clone(),
+                reason,
+            },
+        };
+        
+        for method in &impl_block.methods {
+            let required = interface.method(&method.name).ok_or_else(|| not_implemented(
+                method.span,
+                format!("method '{}' is not declared by the interface", method.name),
+            ))?;
+            if method.is_static {
+                return Err(not_implemented(method.span, format!("method '{}' must not be static", method.name)));
+            }
+            
+            let param_types: Vec<&Type> = method.parameters.iter().map(|p| &p.type_).collect();
+            let required_types: Vec<&Type> = required.parameters.iter().map(|p| &p.type_).collect();
+            if param_types != required_types || method.return_type != required.return_type {
+                return Err(not_implemented(
+                    method.span,
+                    format!("method '{}' should have signature '{}'", method.name, required),
+                ));
+            }
+        }
+        
+        // Methods are ordered by vtable slot
+        let mut typed_methods = Vec::new();
+        for required in &interface.methods {
+            let method = impl_block.methods.iter()
+                .find(|method| method.name == required.name)
+                .ok_or_else(|| not_implemented(impl_block.span, format!("missing method '{}'", required.name)))?;
+            typed_methods.push(self.check_method(&impl_block.type_name, method)?);
+        }
+        
+        Ok(TypedImpl {
+            interface: impl_block.interface.clone(),
+            type_name: impl_block.type_name.clone(),
+            methods: typed_methods,
+        })
+    }
+    
     /// Type check a method
-    pub fn check_method(&mut self, method: &Method) -> Result<TypedMethod, SemanticError> {
+    pub fn check_method(&mut self, class_name: &str, method: &Method) -> Result<TypedMethod, SemanticError> {
+        let mut params: Vec<_> = method.parameters.iter().map(|p| (p.name.clone(), p.type_.clone())).collect();
+        if !method.is_static {
+            params.insert(0, ("self".to_string(), self.self_type(class_name)));
+        }
+        self.infer_body(&params, &method.return_type.clone().unwrap_or(Type::Unit), &method.body);
+        
+        self.type_env.enter_scope();
+        
+        // Instance methods receive the object as an implicit `self`
+        if !method.is_static {
+            self.type_env.bind("self".to_string(), InferType::Concrete(self.self_type(class_name)));
+        }
+        
         let mut typed_params = Vec::new();
         for param in &method.parameters {
+            self.type_env.bind(param.name.clone(), InferType::Concrete(param.type_.clone()));
             typed_params.push(TypedParameter {
                 name: param.name.clone(),
                 type_: param.type_.clone(),
@@ -796,8 +1822,16 @@ impl TypeChecker {
             });
         }
         
-        let typed_body = self.check_block(&method.body)?;
         let return_type = method.return_type.clone().unwrap_or(Type::Unit);
+        self.assigned_variables = captures::assigned_variables(&method.body);
+        self.enclosing_returns.push(Some(return_type.clone()));
+        let typed_body = self.check_block(&method.body);
+        self.enclosing_returns.pop();
+        self.type_env.exit_scope();
+        let typed_body = typed_body?;
+        if let Err(error) = self.check_tail(&typed_body, &return_type) {
+            self.report(error);
+        }
         
         Ok(TypedMethod {
             name: method.name.clone(),
@@ -806,6 +1840,7 @@ impl TypeChecker {
             body: typed_body,
             visibility: method.visibility.clone(),
             is_static: method.is_static,
+            span: Some(method.span),
         })
     }
     
@@ -821,10 +1856,11 @@ impl TypeChecker {
         match lit {
             Literal::Integer(_) => Type::Int,
             Literal::Float(_) => Type::Float,
+            Literal::Byte(_) => Type::Byte,
             Literal::String(_) => Type::String,
             Literal::Boolean(_) => Type::Bool,
             Literal::Character(_) => Type::Char,
-            Literal::Null => Type::Nullable(Box::new(Type::Unit)),
+            Literal::Null => Type::Nullable(Box::new(Type::Never)),
         }
     }
     
@@ -836,7 +1872,7 @@ impl TypeChecker {
         // Check that it matches the declared type
         if !self.types_compatible(&inferred_type, &const_def.type_) {
             return Err(SemanticError {
-                span: Span::single(crate::position::Position::start()),
+                span: const_def.value.span,
                 kind: SemanticErrorKind::TypeMismatch {
                     expected: format!("{}", const_def.type_),
                     found: format!("{}", inferred_type),
@@ -846,17 +1882,17 @@ impl TypeChecker {
         
         // Create typed expression for the value
         let typed_value = TypedExpression {
-            kind: match &const_def.value {
-                Expression::Literal(lit) => TypedExpressionKind::Literal(lit.clone()),
+            kind: match &const_def.value.kind {
+                ExpressionKind::Literal(lit) => TypedExpressionKind::Literal(lit.clone()),
                 _ => return Err(SemanticError {
-                    span: Span::single(crate::position::Position::start()),
+                    span: const_def.value.span,
                     kind: SemanticErrorKind::UnsupportedFeature {
                         feature: "Non-literal constant expressions".to_string(),
                     },
                 }),
             },
             type_: inferred_type,
-            span: None,
+            span: Some(const_def.value.span),
         };
         
         Ok(TypedConst {
@@ -869,37 +1905,166 @@ impl TypeChecker {
     
     /// Simple type check for expressions (without full inference)
     pub fn check_expression(&mut self, expr: &Expression) -> Result<TypedExpression, SemanticError> {
-        match expr {
-            Expression::Literal(lit) => {
+        match &expr.kind {
+            ExpressionKind::Literal(lit) => {
                 let type_ = self.literal_type(lit);
                 Ok(TypedExpression {
                     kind: TypedExpressionKind::Literal(lit.clone()),
                     type_,
-                    span: None,
+                    span: Some(expr.span),
                 })
             }
-            Expression::Identifier(name) => {
-                let type_ = self.type_env.lookup(name)
-                    .ok_or_else(|| SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::UndefinedVariable { name: name.clone() },
-                    })?
-                    .to_concrete()
-                    .ok_or_else(|| SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::CannotInferType,
-                    })?;
+            ExpressionKind::Identifier(name) => {
+                let type_ = self.binding_type(name, expr.span)?;
                 
                 Ok(TypedExpression {
                     kind: TypedExpressionKind::Identifier(name.clone()),
                     type_,
-                    span: None,
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Path(segments) => {
+                let name = segments.join("::");
+                if let Some((enum_name, variant)) = self.lookup_variant(&name) {
+                    if variant.payload == VariantPayload::Unit {
+                        return Ok(TypedExpression {
+                            kind: TypedExpressionKind::Variant(enum_name.clone(), variant.name, Vec::new()),
+                            type_: Type::Named(enum_name),
+                            span: Some(expr.span),
+                        });
+                    }
+                }
+                
+                let type_ = self.binding_type(&name, expr.span)?;
+                
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Identifier(name),
+                    type_,
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Call(func, args) => {
+                // `receiver?.method(args)` calls the method of a nullable
+                // receiver only when it is not null
+                if let ExpressionKind::Field(object, name) = &func.kind {
+                    if let ExpressionKind::Unary(UnaryOp::Try, receiver) = &object.kind {
+                        let typed_receiver = self.check_expression(receiver)?;
+                        if let Type::Nullable(inner) = &typed_receiver.type_ {
+                            let value = non_null_value(inner, typed_receiver.span);
+                            let callee = self.check_field(value, name, func.span)?;
+                            let call = self.check_call(callee, args, expr)?;
+                            return Ok(safe_navigation(typed_receiver, call, expr.span));
+                        }
+                        let typed_object = self.check_unary(UnaryOp::Try, typed_receiver, object.span)?;
+                        let callee = self.check_field(typed_object, name, func.span)?;
+                        return self.check_call(callee, args, expr);
+                    }
+                }
+                
+                let typed_func = self.check_expression(func)?;
+                self.check_call(typed_func, args, expr)
+            }
+            ExpressionKind::StructLiteral(name, inits) => {
+                if let Some((enum_name, variant)) = self.lookup_variant(name) {
+                    let fields = match &variant.payload {
+                        VariantPayload::Struct(fields) => fields.clone(),
+                        _ => return Err(SemanticError {
+                            span: expr.span,
+                            kind: SemanticErrorKind::InvalidOperation {
+                                message: format!("Variant '{}' has no named fields", name),
+                            },
+                        }),
+                    };
+                    let typed_fields = self.check_field_inits(name, &fields, inits, &mut Instantiation::none(), expr.span)?;
+                    return Ok(TypedExpression {
+                        kind: TypedExpressionKind::Variant(
+                            enum_name.clone(),
+                            variant.name,
+                            typed_fields.into_iter().map(|(_, value)| value).collect(),
+                        ),
+                        type_: Type::Named(enum_name),
+                        span: Some(expr.span),
+                    });
+                }
+                
+                let fields = self.fields.get(name).cloned().ok_or_else(|| SemanticError {
+                    span: expr.span,
+                    kind: SemanticErrorKind::UndefinedVariable { name: name.clone() },
+                })?;
+                
+                // A generic struct's type arguments are inferred from its fields
+                let mut instantiation = match self.type_params.get(name) {
+                    Some(params) => Instantiation::new(params, &mut self.type_env),
+                    None => Instantiation::none(),
+                };
+                let typed_fields = self.check_field_inits(name, &fields, inits, &mut instantiation, expr.span)?;
+                let type_ = if self.type_params.contains_key(name) {
+                    Type::Generic(name.clone(), instantiation.type_args(expr.span)?)
+                } else {
+                    Type::Named(name.clone())
+                };
+                
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::StructLiteral(name.clone(), typed_fields),
+                    type_,
+                    span: Some(expr.span),
                 })
             }
-            Expression::Binary(left, op, right) => {
+            ExpressionKind::Field(object, name) => {
+                // `receiver?.field` reads the field of a nullable receiver
+                // only when it is not null
+                if let ExpressionKind::Unary(UnaryOp::Try, receiver) = &object.kind {
+                    let typed_receiver = self.check_expression(receiver)?;
+                    if let Type::Nullable(inner) = &typed_receiver.type_ {
+                        let value = non_null_value(inner, typed_receiver.span);
+                        let field = self.check_field(value, name, expr.span)?;
+                        return Ok(safe_navigation(typed_receiver, field, expr.span));
+                    }
+                    let typed_object = self.check_unary(UnaryOp::Try, typed_receiver, object.span)?;
+                    return self.check_field(typed_object, name, expr.span);
+                }
+                
+                let typed_object = self.check_expression(object)?;
+                self.check_field(typed_object, name, expr.span)
+            }
+            ExpressionKind::Binary(left, BinaryOp::Coalesce, right) => {
                 let typed_left = self.check_expression(left)?;
+                let inner = match &typed_left.type_ {
+                    Type::Nullable(inner) => (**inner).clone(),
+                    other => return Err(SemanticError {
+                        span: left.span,
+                        kind: SemanticErrorKind::TypeMismatch {
+                            expected: "nullable type".to_string(),
+                            found: format!("{}", other),
+                        },
+                    }),
+                };
                 let typed_right = self.check_expression(right)?;
-                let result_type = self.binary_op_result_type(&typed_left.type_, op, &typed_right.type_)?;
+                
+                // The default may itself be null, and then so may the result
+                let type_ = if self.types_compatible(&typed_right.type_, &inner) {
+                    inner.clone()
+                } else {
+                    self.expect_compatible(&typed_right.type_, &typed_left.type_, right.span)?;
+                    typed_left.type_.clone()
+                };
+                // Lowered to a match, so the default only runs for a null value
+                let value = non_null_value(&inner, typed_left.span);
+                Ok(null_match(typed_left, expression_block(typed_right), expression_block(value), type_, expr.span))
+            }
+            ExpressionKind::Binary(left, op, right) => {
+                let typed_left = self.check_expression(left)?;
+                
+                // The right operand of `&&` only runs when the left one holds,
+                // and that of `||` when it does not
+                let (when_true, when_false) = null_checks(left);
+                let narrowed = match op {
+                    BinaryOp::And => when_true,
+                    BinaryOp::Or => when_false,
+                    _ => Vec::new(),
+                };
+                let typed_right = self.with_narrowed(&narrowed, |checker| checker.check_expression(right))?;
+                let result_type = self.binary_op_result_type(&typed_left.type_, op, &typed_right.type_, expr.span)?;
                 
                 Ok(TypedExpression {
                     kind: TypedExpressionKind::Binary(
@@ -908,159 +2073,1347 @@ impl TypeChecker {
                         Box::new(typed_right)
                     ),
                     type_: result_type,
-                    span: None,
+                    span: Some(expr.span),
                 })
             }
-            _ => Err(SemanticError {
-                span: Span::single(crate::position::Position::start()),
-                kind: SemanticErrorKind::UnsupportedFeature {
-                    feature: format!("Expression: {:?}", expr),
-                },
-            }),
-        }
-    }
-    
-    /// Get the result type of a binary operation
-    fn binary_op_result_type(&self, left: &Type, op: &BinaryOp, right: &Type) -> Result<Type, SemanticError> {
-        match op {
-            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
-                if self.types_compatible(left, right) && self.is_numeric_type(left) {
-                    Ok(left.clone())
-                } else {
-                    Err(SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::TypeMismatch {
-                            expected: "numeric types".to_string(),
-                            found: format!("{:?} and {:?}", left, right),
+            ExpressionKind::Block(block) => {
+                self.type_env.enter_scope();
+                let typed_block = self.check_block(block);
+                self.type_env.exit_scope();
+                let typed_block = typed_block?;
+                
+                Ok(TypedExpression {
+                    type_: typed_block.type_.clone(),
+                    kind: TypedExpressionKind::Block(typed_block),
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Lambda(params, return_type, body) => {
+                self.check_lambda(params, return_type, body, expr.span)
+            }
+            ExpressionKind::Unary(op, operand) => {
+                let typed_operand = self.check_expression(operand)?;
+                self.check_unary(op.clone(), typed_operand, expr.span)
+            }
+            ExpressionKind::Array(elements) => {
+                let typed_elements = elements.iter()
+                    .map(|elem| self.check_expression(elem))
+                    .collect::<Result<Vec<_>, _>>()?;
+                
+                // An empty literal takes its element type from how it is used
+                let type_ = match typed_elements.first() {
+                    Some(first) => {
+                        let elem_type = first.type_.clone();
+                        for elem in &typed_elements[1..] {
+                            self.expect_compatible(&elem.type_, &elem_type, elem.span.unwrap_or(expr.span))?;
+                        }
+                        Type::Array(Box::new(elem_type))
+                    }
+                    None => self.inferred_type(expr.span)?,
+                };
+                
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Array(typed_elements),
+                    type_,
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Tuple(elements) => {
+                let typed_elements = elements.iter()
+                    .map(|element| self.check_expression(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(TypedExpression {
+                    type_: Type::Tuple(typed_elements.iter().map(|element| element.type_.clone()).collect()),
+                    kind: TypedExpressionKind::Tuple(typed_elements),
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Interpolated(parts) => {
+                // Text becomes string literals between the holes
+                let mut typed_parts = Vec::with_capacity(parts.len());
+                for part in parts {
+                    typed_parts.push(match part {
+                        StringPart::Text(text) => TypedExpression {
+                            kind: TypedExpressionKind::Literal(Literal::String(text.clone())),
+                            type_: Type::String,
+                            span: None,
                         },
-                    })
+                        StringPart::Expression(hole) => {
+                            let typed_hole = self.check_expression(hole)?;
+                            if !is_printable(&typed_hole.type_) {
+                                return Err(SemanticError {
+                                    span: hole.span,
+                                    kind: SemanticErrorKind::NotPrintable {
+                                        type_name: typed_hole.type_.to_string(),
+                                    },
+                                });
+                            }
+                            typed_hole
+                        }
+                    });
                 }
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Interpolated(typed_parts),
+                    type_: Type::String,
+                    span: Some(expr.span),
+                })
             }
-            BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::Greater => {
-                if self.types_compatible(left, right) {
-                    Ok(Type::Bool)
-                } else {
-                    Err(SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::TypeMismatch {
-                            expected: "compatible types".to_string(),
-                            found: format!("{:?} and {:?}", left, right),
-                        },
-                    })
+            ExpressionKind::Range(start, end, step, inclusive) => {
+                // Bounds and step are integers
+                let mut check_bound = |bound: &Expression| {
+                    let typed_bound = self.check_expression(bound)?;
+                    self.expect_compatible(&typed_bound.type_, &Type::Int, bound.span)?;
+                    Ok::<_, SemanticError>(Box::new(typed_bound))
+                };
+                let typed_start = check_bound(start)?;
+                let typed_end = check_bound(end)?;
+                let typed_step = step.as_deref().map(&mut check_bound).transpose()?;
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Range(typed_start, typed_end, typed_step, *inclusive),
+                    type_: Type::Range,
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Map(pairs) => {
+                let mut typed_pairs = Vec::new();
+                for (key, value) in pairs {
+                    typed_pairs.push((self.check_expression(key)?, self.check_expression(value)?));
                 }
+                
+                let type_ = match typed_pairs.first() {
+                    Some((first_key, first_value)) => {
+                        let (key_type, value_type) = (first_key.type_.clone(), first_value.type_.clone());
+                        for (key, value) in &typed_pairs[1..] {
+                            self.expect_compatible(&key.type_, &key_type, key.span.unwrap_or(expr.span))?;
+                            self.expect_compatible(&value.type_, &value_type, value.span.unwrap_or(expr.span))?;
+                        }
+                        Type::Map(Box::new(key_type), Box::new(value_type))
+                    }
+                    None => self.inferred_type(expr.span)?,
+                };
+                
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Map(typed_pairs),
+                    type_,
+                    span: Some(expr.span),
+                })
+            }
+            ExpressionKind::Index(collection, index) => {
+                let typed_collection = self.check_expression(collection)?;
+                let typed_index = self.check_expression(index)?;
+                
+                let (key_type, elem_type) = match &typed_collection.type_ {
+                    Type::Array(elem) | Type::List(elem) => (Type::Int, (**elem).clone()),
+                    Type::Map(key, value) => ((**key).clone(), (**value).clone()),
+                    Type::Nullable(_) => return Err(nullable_dereference(&typed_collection.type_, expr.span)),
+                    other => return Err(SemanticError {
+                        span: expr.span,
+                        kind: SemanticErrorKind::InvalidOperation {
+                            message: format!("Type {} cannot be indexed", other),
+                        },
+                    }),
+                };
+                self.expect_compatible(&typed_index.type_, &key_type, index.span)?;
+                
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Index(Box::new(typed_collection), Box::new(typed_index)),
+                    type_: elem_type,
+                    span: Some(expr.span),
+                })
             }
             _ => Err(SemanticError {
-                span: Span::single(crate::position::Position::start()),
+                span: expr.span,
                 kind: SemanticErrorKind::UnsupportedFeature {
-                    feature: format!("Binary operator: {:?}", op),
+                    feature: format!("Expression: {:?}", expr),
                 },
             }),
         }
     }
     
-
-    
-    /// Type check a block
-    pub fn check_block(&mut self, block: &Block) -> Result<TypedBlock, SemanticError> {
-        let mut typed_statements = Vec::new();
-        let mut block_type = Type::Unit;
-        
-        for stmt in &block.statements {
-            let typed_stmt = self.check_statement(stmt)?;
-            
-            // If this is an expression statement, it might determine the block type
-            if let TypedStatementKind::Expression(ref expr) = typed_stmt.kind {
-                block_type = expr.type_.clone();
+    /// Type check access to a field or method of an already checked object
+    fn check_field(&mut self, typed_object: TypedExpression, name: &str, span: Span) -> Result<TypedExpression, SemanticError> {
+        let type_ = match self.member_type(&typed_object.type_, name) {
+            Some(type_) => type_,
+            None if matches!(typed_object.type_, Type::Nullable(_)) => {
+                return Err(nullable_dereference(&typed_object.type_, span));
             }
-            
-            typed_statements.push(typed_stmt);
+            None => return Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::InvalidOperation {
+                    message: format!("Type {} has no field or method '{}'", typed_object.type_, name),
+                },
+            }),
+        };
+        
+        Ok(TypedExpression {
+            kind: TypedExpressionKind::Field(Box::new(typed_object), name.to_string()),
+            type_,
+            span: Some(span),
+        })
+    }
+    
+    /// Type check a unary operation on an already checked operand
+    fn check_unary(&mut self, op: UnaryOp, typed_operand: TypedExpression, span: Span) -> Result<TypedExpression, SemanticError> {
+        if op == UnaryOp::Try {
+            return self.check_try(typed_operand, span);
         }
+        let type_ = self.unary_op_result_type(&op, &typed_operand.type_, span)?;
         
-        Ok(TypedBlock {
-            statements: typed_statements,
-            type_: block_type,
+        Ok(TypedExpression {
+            kind: TypedExpressionKind::Unary(op, Box::new(typed_operand)),
+            type_,
+            span: Some(span),
         })
     }
     
-    /// Type check a statement
-    pub fn check_statement(&mut self, stmt: &Statement) -> Result<TypedStatement, SemanticError> {
-        match stmt {
-            Statement::Expression(expr) => {
-                let typed_expr = self.check_expression(expr)?;
-                Ok(TypedStatement {
-                    kind: TypedStatementKind::Expression(typed_expr),
-                    span: None,
+    /// Type check `operand?`. An `Err` is returned from the enclosing function,
+    /// whose error type it must convert to, and `null` from an enclosing
+    /// function returning a nullable type.
+    fn check_try(&mut self, typed_operand: TypedExpression, span: Span) -> Result<TypedExpression, SemanticError> {
+        // Top-level statements run as if in a function returning unit
+        let enclosing = self.enclosing_returns.last().cloned().unwrap_or(Some(Type::Unit));
+        let invalid_try = |operand: &Type, return_type: &Type| SemanticError {
+            span,
+            kind: SemanticErrorKind::InvalidTry {
+                operand: format!("{}", operand),
+                return_type: format!("{}", return_type),
+            },
+        };
+        
+        match typed_operand.type_.clone() {
+            Type::Result(ok, err) => {
+                match &enclosing {
+                    Some(Type::Result(_, expected)) if !self.types_compatible(&err, expected) => {
+                        return Err(SemanticError {
+                            span,
+                            kind: SemanticErrorKind::ErrorConversion {
+                                from: format!("{}", err),
+                                to: format!("{}", expected),
+                            },
+                        });
+                    }
+                    Some(Type::Result(_, _)) | None => {}
+                    Some(other) => return Err(invalid_try(&typed_operand.type_, other)),
+                }
+                Ok(TypedExpression {
+                    kind: TypedExpressionKind::Unary(UnaryOp::Try, Box::new(typed_operand)),
+                    type_: *ok,
+                    span: Some(span),
                 })
             }
-            Statement::Let(name, type_annotation, init) => {
-                let var_type = if let Some(init_expr) = init {
-                    let typed_init = self.check_expression(init_expr)?;
-                    
-                    if let Some(annotation) = type_annotation {
-                        if !self.types_compatible(&typed_init.type_, annotation) {
-                            return Err(SemanticError {
-                                span: Span::single(crate::position::Position::start()),
-                                kind: SemanticErrorKind::TypeMismatch {
-                                    expected: format!("{}", annotation),
-                                    found: format!("{}", typed_init.type_),
-                                },
-                            });
-                        }
-                        annotation.clone()
-                    } else {
-                        typed_init.type_.clone()
-                    }
-                } else if let Some(annotation) = type_annotation {
-                    annotation.clone()
+            Type::Nullable(inner) => {
+                match &enclosing {
+                    Some(Type::Nullable(_)) | None => {}
+                    Some(other) => return Err(invalid_try(&typed_operand.type_, other)),
+                }
+                // Lowered to a match whose `null` arm returns early
+                let null = TypedExpression {
+                    kind: TypedExpressionKind::Literal(Literal::Null),
+                    type_: Type::Nullable(Box::new(Type::Never)),
+                    span: Some(span),
+                };
+                let early_return = TypedBlock {
+                    statements: vec![TypedStatement { kind: TypedStatementKind::Return(Some(null)), span: Some(span) }],
+                    type_: Type::Never,
+                };
+                let value = non_null_value(&inner, typed_operand.span);
+                Ok(null_match(typed_operand, early_return, expression_block(value), *inner, span))
+            }
+            other => Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: "Result or nullable type".to_string(),
+                    found: format!("{}", other),
+                },
+            }),
+        }
+    }
+    
+    /// Type check a call to an already checked callee
+    fn check_call(&mut self, typed_func: TypedExpression, args: &[Expression], expr: &Expression) -> Result<TypedExpression, SemanticError> {
+        let typed_args = args.iter()
+            .map(|arg| self.check_expression(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let (param_types, return_type) = match &typed_func.type_ {
+            Type::Function(params, ret) => (params.clone(), (**ret).clone()),
+            Type::Nullable(_) => return Err(nullable_dereference(&typed_func.type_, expr.span)),
+            other => return Err(SemanticError {
+                span: expr.span,
+                kind: SemanticErrorKind::InvalidOperation {
+                    message: format!("Type {} is not callable", other),
+                },
+            }),
+        };
+        
+        let is_variadic = matches!(
+            &typed_func.kind,
+            TypedExpressionKind::Identifier(name) if self.variadic_functions.contains(name)
+        );
+        let arity_ok = if is_variadic {
+            typed_args.len() >= param_types.len()
+        } else {
+            typed_args.len() == param_types.len()
+        };
+        if !arity_ok {
+            return Err(SemanticError {
+                span: expr.span,
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: format!("{} argument(s)", param_types.len()),
+                    found: format!("{}", typed_args.len()),
+                },
+            });
+        }
+        
+        // Type arguments of a generic callee are inferred from the arguments
+        let generic_name = match &typed_func.kind {
+            TypedExpressionKind::Identifier(name) if self.type_params.contains_key(name) => Some(name.clone()),
+            _ => None,
+        };
+        let mut instantiation = match &generic_name {
+            Some(name) => Instantiation::new(&self.type_params[name], &mut self.type_env),
+            None => Instantiation::none(),
+        };
+        for (param_type, arg) in param_types.iter().zip(&typed_args) {
+            instantiation.expect(self, param_type, &arg.type_, expr.span)?;
+        }
+        if let Some(inferred) = self.inferred.get(&expr.span).cloned() {
+            if generic_name.is_some() && instantiation.type_args(expr.span).is_err() {
+                instantiation.expect(self, &return_type, &inferred, expr.span)?;
+            }
+        }
+        
+        let (typed_func, param_types, return_type) = match generic_name {
+            // `Ok` and `Err` construct the builtin `Result` enum
+            Some(name) if name == "Ok" || name == "Err" => {
+                let return_type = instantiation.resolve(&return_type).ok_or(SemanticError {
+                    span: expr.span,
+                    kind: SemanticErrorKind::CannotInferType,
+                })?;
+                return Ok(TypedExpression {
+                    kind: TypedExpressionKind::Variant("Result".to_string(), name, typed_args),
+                    type_: return_type,
+                    span: Some(expr.span),
+                });
+            }
+            Some(name) => {
+                let type_args = instantiation.type_args(expr.span)?;
+                let resolve = |type_: &Type| instantiation.resolve(type_).unwrap_or_else(|| type_.clone());
+                let param_types: Vec<Type> = param_types.iter().map(resolve).collect();
+                let return_type = resolve(&return_type);
+                let callee = TypedExpression {
+                    kind: TypedExpressionKind::Instantiate(name, type_args),
+                    type_: Type::Function(param_types.clone(), Box::new(return_type.clone())),
+                    span: typed_func.span,
+                };
+                (callee, param_types, return_type)
+            }
+            None => (typed_func, param_types, return_type),
+        };
+        let typed_args = typed_args.into_iter()
+            .enumerate()
+            .map(|(index, arg)| match param_types.get(index) {
+                Some(param_type) => self.coerce(arg, param_type),
+                None => arg,
+            })
+            .collect::<Vec<_>>();
+        
+        // Calling a tuple variant constructs the enum
+        if let TypedExpressionKind::Identifier(name) = &typed_func.kind {
+            if let Some((enum_name, variant)) = self.lookup_variant(name) {
+                return Ok(TypedExpression {
+                    kind: TypedExpressionKind::Variant(enum_name, variant.name, typed_args),
+                    type_: return_type,
+                    span: Some(expr.span),
+                });
+            }
+        }
+        
+        Ok(TypedExpression {
+            kind: TypedExpressionKind::Call(Box::new(typed_func), typed_args),
+            type_: return_type,
+            span: Some(expr.span),
+        })
+    }
+    
+    /// Find the enum variant named by a qualified name such as `Shape::Circle`
+    fn lookup_variant(&self, name: &str) -> Option<(String, Variant)> {
+        let (enum_name, variant_name) = name.rsplit_once("::")?;
+        let variant = self.enums.get(enum_name)?.variant(variant_name)?;
+        Some((enum_name.to_string(), variant.clone()))
+    }
+    
+    /// Check the field initializers of a struct literal against the declared
+    /// fields, returning them in declaration order
+    fn check_field_inits(
+        &mut self,
+        name: &str,
+        fields: &[Field],
+        inits: &[(String, Expression)],
+        instantiation: &mut Instantiation,
+        span: Span,
+    ) -> Result<Vec<(String, TypedExpression)>, SemanticError> {
+        for (index, (field_name, _)) in inits.iter().enumerate() {
+            if !fields.iter().any(|f| &f.name == field_name) {
+                return Err(SemanticError {
+                    span,
+                    kind: SemanticErrorKind::InvalidOperation {
+                        message: format!("'{}' has no field '{}'", name, field_name),
+                    },
+                });
+            }
+            if inits[..index].iter().any(|(earlier, _)| earlier == field_name) {
+                return Err(SemanticError {
+                    span,
+                    kind: SemanticErrorKind::DuplicateDefinition { name: field_name.clone() },
+                });
+            }
+        }
+        
+        let mut typed_fields = Vec::new();
+        for field in fields {
+            let value = inits.iter()
+                .find(|(field_name, _)| field_name == &field.name)
+                .map(|(_, value)| value)
+                .ok_or_else(|| SemanticError {
+                    span,
+                    kind: SemanticErrorKind::InvalidOperation {
+                        message: format!("Missing field '{}' in '{}'", field.name, name),
+                    },
+                })?;
+            let typed_value = self.check_expression(value)?;
+            instantiation.expect(self, &field.type_, &typed_value.type_, value.span)?;
+            typed_fields.push((field.name.clone(), typed_value));
+        }
+        
+        Ok(typed_fields)
+    }
+    
+    /// Type of `self` in the methods of a struct or class: a generic type is
+    /// applied to its own type parameters
+    fn self_type(&self, type_name: &str) -> Type {
+        match self.type_params.get(type_name) {
+            Some(params) => Type::Generic(
+                type_name.to_string(),
+                params.iter().map(|param| Type::Named(param.clone())).collect(),
+            ),
+            None => Type::Named(type_name.to_string()),
+        }
+    }
+    
+    /// Type of a field or instance method accessed on a value of the given type
+    fn member_type(&self, object_type: &Type, name: &str) -> Option<Type> {
+        let (type_name, type_args) = match object_type {
+            Type::Named(type_name) => (type_name, &[][..]),
+            Type::Generic(type_name, args) => (type_name, args.as_slice()),
+            _ => return None,
+        };
+        
+        // Members of a generic type mention its parameters
+        let bindings: HashMap<String, Type> = self.type_params.get(type_name)
+            .map(|params| params.iter().cloned().zip(type_args.iter().cloned()).collect())
+            .unwrap_or_default();
+        self.declared_member_type(type_name, name).map(|type_| type_.substitute(&bindings))
+    }
+    
+    /// The name of the struct or class declaring `name` as a field without `mut`
+    fn immutable_field_owner<'a>(&self, object_type: &'a Type, name: &str) -> Option<&'a str> {
+        let type_name = match object_type {
+            Type::Named(type_name) | Type::Generic(type_name, _) => type_name,
+            _ => return None,
+        };
+        let field = self.fields.get(type_name)?.iter().find(|f| f.name == name)?;
+        (!field.is_mutable).then_some(type_name.as_str())
+    }
+    
+    fn declared_member_type(&self, type_name: &str, name: &str) -> Option<Type> {        
+        // Values of an interface type expose the interface's methods
+        if let Some(interface) = self.interfaces.get(type_name) {
+            return interface.method(name).map(|method| Type::Function(
+                method.parameters.iter().map(|p| p.type_.clone()).collect(),
+                Box::new(method.return_type.clone().unwrap_or(Type::Unit)),
+            ));
+        }
+        
+        if let Some(field) = self.fields.get(type_name).and_then(|fields| fields.iter().find(|f| f.name == name)) {
+            return Some(field.type_.clone());
+        }
+        
+        self.methods.get(type_name)
+            .and_then(|methods| methods.iter().find(|m| m.name == name && !m.is_static))
+            .map(|method| Type::Function(
+                method.parameters.iter().map(|p| p.type_.clone()).collect(),
+                Box::new(method.return_type.clone().unwrap_or(Type::Unit)),
+            ))
+    }
+    
+    /// Get the result type of a binary operation
+    fn binary_op_result_type(&self, left: &Type, op: &BinaryOp, right: &Type, span: Span) -> Result<Type, SemanticError> {
+        match op {
+            // `+` also concatenates strings
+            BinaryOp::Add if *left == Type::String && *right == Type::String => Ok(Type::String),
+            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => {
+                if self.types_compatible(left, right) && self.is_numeric_type(left) {
+                    Ok(left.clone())
                 } else {
-                    return Err(SemanticError {
-                        span: Span::single(crate::position::Position::start()),
-                        kind: SemanticErrorKind::CannotInferType,
-                    });
+                    Err(SemanticError {
+                        span,
+                        kind: SemanticErrorKind::TypeMismatch {
+                            expected: "numeric types".to_string(),
+                            found: format!("{:?} and {:?}", left, right),
+                        },
+                    })
+                }
+            }
+            // A nullable value compares with `null` or a value of its non-null type
+            BinaryOp::Equal | BinaryOp::NotEqual if self.types_compatible(right, left) => Ok(Type::Bool),
+            BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::Greater
+            | BinaryOp::LessEqual | BinaryOp::GreaterEqual => {
+                if self.types_compatible(left, right) {
+                    Ok(Type::Bool)
+                } else {
+                    Err(SemanticError {
+                        span,
+                        kind: SemanticErrorKind::TypeMismatch {
+                            expected: "compatible types".to_string(),
+                            found: format!("{:?} and {:?}", left, right),
+                        },
+                    })
+                }
+            }
+            BinaryOp::And | BinaryOp::Or => {
+                if *left == Type::Bool && *right == Type::Bool {
+                    Ok(Type::Bool)
+                } else {
+                    Err(SemanticError {
+                        span,
+                        kind: SemanticErrorKind::TypeMismatch {
+                            expected: "bool operands".to_string(),
+                            found: format!("{:?} and {:?}", left, right),
+                        },
+                    })
+                }
+            }
+            _ => Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::UnsupportedFeature {
+                    feature: format!("Binary operator: {:?}", op),
+                },
+            }),
+        }
+    }
+    
+
+    
+    /// Get the result type of a unary operation
+    fn unary_op_result_type(&self, op: &UnaryOp, operand: &Type, span: Span) -> Result<Type, SemanticError> {
+        let mismatch = |expected: &str| SemanticError {
+            span,
+            kind: SemanticErrorKind::TypeMismatch {
+                expected: expected.to_string(),
+                found: format!("{}", operand),
+            },
+        };
+        
+        match op {
+            UnaryOp::Minus | UnaryOp::Plus if self.is_numeric_type(operand) => Ok(operand.clone()),
+            UnaryOp::Minus | UnaryOp::Plus => Err(mismatch("numeric type")),
+            UnaryOp::Not if *operand == Type::Bool => Ok(Type::Bool),
+            UnaryOp::Not => Err(mismatch("bool")),
+            UnaryOp::BitwiseNot if *operand == Type::Int => Ok(Type::Int),
+            UnaryOp::BitwiseNot => Err(mismatch("int")),
+            // `expr?` unwraps an `Ok` and returns an `Err` from the function
+            UnaryOp::Try => match operand {
+                Type::Result(ok, _) => Ok((**ok).clone()),
+                _ => Err(mismatch("Result")),
+            },
+            // `expr!` asserts that a nullable value is not null
+            UnaryOp::Unwrap => match operand {
+                Type::Nullable(inner) => Ok((**inner).clone()),
+                _ => Err(mismatch("nullable type")),
+            },
+        }
+    }
+    
+    /// Type check a lambda and decide how it captures each variable of the
+    /// enclosing function that its body uses
+    fn check_lambda(&mut self, params: &[LambdaParameter], return_type: &Option<Type>, body: &Expression, span: Span) -> Result<TypedExpression, SemanticError> {
+        // Globals such as functions and constants are referenced, not captured
+        let mut captures = Vec::new();
+        for name in captures::free_variables(params, body) {
+            if !matches!(self.type_env.binding_depth(&name), Some(depth) if depth > 0) {
+                continue;
+            }
+            let type_ = match self.type_env.declared_type(&name) {
+                Some(declared) => declared.clone(),
+                None => self.binding_type(&name, span)?,
+            };
+            captures.push(TypedCapture {
+                by_reference: self.assigned_variables.contains(&name),
+                name,
+                type_,
+            });
+        }
+        
+        self.type_env.enter_closure_scope();
+        let mut typed_params = Vec::new();
+        for param in params {
+            // Unannotated parameters take the type inferred from the lambda's uses
+            let type_ = match &param.type_ {
+                Some(type_) => type_.clone(),
+                None => match self.inferred_type(param.span) {
+                    Ok(type_) => type_,
+                    Err(error) => {
+                        self.type_env.exit_scope();
+                        return Err(error);
+                    }
+                },
+            };
+            self.type_env.bind(param.name.clone(), InferType::Concrete(type_.clone()));
+            typed_params.push(TypedParameter {
+                name: param.name.clone(),
+                type_,
+                is_mutable: param.is_mutable,
+            });
+        }
+        // An unannotated lambda returns the type inferred from its body
+        self.enclosing_returns.push(return_type.clone().or_else(|| self.inferred.get(&span).cloned()));
+        let typed_body = self.check_expression(body);
+        self.enclosing_returns.pop();
+        self.type_env.exit_scope();
+        let typed_body = typed_body?;
+        if let Some(declared) = return_type {
+            match &typed_body.kind {
+                TypedExpressionKind::Block(block) => self.check_tail(block, declared)?,
+                _ => self.expect_compatible(&typed_body.type_, declared, body.span)?,
+            }
+        }
+        
+        let return_type = return_type.clone().unwrap_or_else(|| typed_body.type_.clone());
+        let param_types = typed_params.iter().map(|param| param.type_.clone()).collect();
+        
+        Ok(TypedExpression {
+            type_: Type::Function(param_types, Box::new(return_type.clone())),
+            kind: TypedExpressionKind::Closure(TypedClosure {
+                parameters: typed_params,
+                return_type,
+                body: Box::new(typed_body),
+                captures,
+            }),
+            span: Some(span),
+        })
+    }
+    
+    /// Type check statements at the top level of an interactive session. Their
+    /// bindings are made in a new scope that stays open, so later inputs see
+    /// them and may shadow them.
+    pub fn check_statements(&mut self, statements: &[Statement]) -> Result<Vec<TypedStatement>, SemanticError> {
+        let span = match (statements.first(), statements.last()) {
+            (Some(first), Some(last)) => Span::new(first.span.start, last.span.end),
+            _ => return Ok(Vec::new()),
+        };
+        self.infer_body(&[], &Type::Unit, &Block { statements: statements.to_vec(), span });
+        
+        self.type_env.enter_scope();
+        let mut typed_statements = Vec::new();
+        for stmt in statements {
+            match self.check_statement(stmt) {
+                Ok(typed_stmt) => typed_statements.push(typed_stmt),
+                Err(error) => self.report(error),
+            }
+        }
+        
+        // Bindings that failed to check are not kept for later inputs
+        self.type_env.unbind_poisoned();
+        let mut errors = self.take_errors();
+        errors.sort_by_key(|error| error.span.start.offset);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(typed_statements),
+        }
+    }
+    
+    /// Type check a block. A statement that fails to check is reported and
+    /// checking goes on with the next one; the block then fails as a whole,
+    /// with an error that is not reported again
+    pub fn check_block(&mut self, block: &Block) -> Result<TypedBlock, SemanticError> {
+        let mut typed_statements = Vec::new();
+        let mut block_type = Type::Unit;
+        let mut failed = false;
+        
+        for stmt in &block.statements {
+            let depth = self.type_env.scope_depth();
+            let typed_stmt = match self.check_statement(stmt) {
+                Ok(typed_stmt) => typed_stmt,
+                Err(error) => {
+                    while self.type_env.scope_depth() > depth {
+                        self.type_env.exit_scope();
+                    }
+                    self.report(error);
+                    failed = true;
+                    continue;
+                }
+            };
+            
+            // If this is an expression statement, it might determine the block type
+            if let TypedStatementKind::Expression(ref expr) = typed_stmt.kind {
+                block_type = expr.type_.clone();
+            }
+            
+            // After `if x == null { return; }`, `x` is not null
+            if let StatementKind::If(cond, then_block, None) = &stmt.kind {
+                if block_diverges(then_block) {
+                    self.narrow(&null_checks(cond).1);
+                }
+            }
+            
+            typed_statements.push(typed_stmt);
+        }
+        
+        if failed {
+            return Err(self.poisoned_error(block.span));
+        }
+        
+        Ok(TypedBlock {
+            statements: typed_statements,
+            type_: block_type,
+        })
+    }
+    
+    /// Type check a statement
+    pub fn check_statement(&mut self, stmt: &Statement) -> Result<TypedStatement, SemanticError> {
+        match &stmt.kind {
+            StatementKind::Expression(expr) => {
+                let typed_expr = self.check_expression(expr)?;
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::Expression(typed_expr),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::Let(pattern, type_annotation, init) => {
+                let name = match pattern {
+                    Pattern::Identifier(name) => name,
+                    _ => return self.check_destructuring(pattern, type_annotation.as_ref(), init.as_ref(), stmt.span),
                 };
                 
-                self.type_env.bind(name.clone(), InferType::Concrete(var_type.clone()));
+                // A binding that fails to check keeps its written type, or is
+                // poisoned so that its uses do not repeat the error
+                let placeholder = match type_annotation {
+                    Some(annotation) => InferType::Concrete(annotation.clone()),
+                    None => InferType::Error,
+                };
+                let typed_init = match init.as_ref().map(|init_expr| self.check_expression(init_expr)).transpose() {
+                    Ok(typed_init) => typed_init,
+                    Err(error) => {
+                        self.type_env.bind(name.clone(), placeholder);
+                        return Err(error);
+                    }
+                };
                 
-                let typed_init = if let Some(init_expr) = init {
-                    Some(self.check_expression(init_expr)?)
-                } else {
-                    None
+                let var_type = match (&typed_init, type_annotation) {
+                    (Some(typed_init), Some(annotation)) => {
+                        if !self.types_compatible(&typed_init.type_, annotation) {
+                            self.type_env.bind(name.clone(), placeholder);
+                            return Err(SemanticError {
+                                span: stmt.span,
+                                kind: SemanticErrorKind::TypeMismatch {
+                                    expected: format!("{}", annotation),
+                                    found: format!("{}", typed_init.type_),
+                                },
+                            });
+                        }
+                        annotation.clone()
+                    }
+                    (Some(typed_init), None) => typed_init.type_.clone(),
+                    (None, Some(annotation)) => annotation.clone(),
+                    // Declared without a value: the type comes from later assignments
+                    (None, None) => match self.inferred_type(stmt.span) {
+                        Ok(type_) => type_,
+                        Err(error) => {
+                            self.type_env.bind(name.clone(), placeholder);
+                            return Err(error);
+                        }
+                    },
                 };
                 
+                self.type_env.bind(name.clone(), InferType::Concrete(var_type.clone()));
+                let typed_init = typed_init.map(|typed_init| self.coerce(typed_init, &var_type));
+                
                 Ok(TypedStatement {
                     kind: TypedStatementKind::Let(name.clone(), var_type, typed_init),
-                    span: None,
+                    span: Some(stmt.span),
                 })
             }
-            Statement::Return(expr) => {
-                let typed_expr = if let Some(e) = expr {
-                    Some(self.check_expression(e)?)
-                } else {
-                    None
+            StatementKind::Return(expr) => {
+                // Outside a function, such as in the REPL, or in one that
+                // declares no return type, any value may be returned
+                let expected = self.enclosing_returns.last().cloned().flatten();
+                let typed_expr = match (expr, &expected) {
+                    (Some(e), Some(expected)) if *expected != Type::Unit => {
+                        let typed = self.check_expression(e)?;
+                        self.expect_compatible(&typed.type_, expected, e.span)?;
+                        Some(self.coerce(typed, expected))
+                    }
+                    (Some(e), _) => Some(self.check_expression(e)?),
+                    (None, Some(expected)) if *expected != Type::Unit => {
+                        return Err(SemanticError {
+                            span: stmt.span,
+                            kind: SemanticErrorKind::TypeMismatch {
+                                expected: format!("{}", expected),
+                                found: format!("{}", Type::Unit),
+                            },
+                        });
+                    }
+                    (None, _) => None,
                 };
                 Ok(TypedStatement {
                     kind: TypedStatementKind::Return(typed_expr),
-                    span: None,
+                    span: Some(stmt.span),
                 })
             }
-            _ => {
-                // Placeholder for other statement types
-                Err(SemanticError {
-                    span: Span::single(crate::position::Position::start()),
-                    kind: SemanticErrorKind::InvalidOperation {
-                        message: "Statement type not yet implemented".to_string(),
-                    },
+            StatementKind::Assignment(target, value) => {
+                let typed_target = self.check_expression(target)?;
+                let typed_value = self.check_expression(value)?;
+                
+                // Only fields declared `mut` can be assigned
+                if let TypedExpressionKind::Field(object, field_name) = &typed_target.kind {
+                    if let Some(type_name) = self.immutable_field_owner(&object.type_, field_name) {
+                        return Err(SemanticError {
+                            span: stmt.span,
+                            kind: SemanticErrorKind::AssignToImmutable {
+                                name: format!("{}.{}", type_name, field_name),
+                                help: format!("declare the field as `mut {}`", field_name),
+                            },
+                        });
+                    }
+                }
+                
+                // A narrowed variable takes any value of its declared type,
+                // and a value that may be null ends the narrowing
+                let mut target_type = typed_target.type_.clone();
+                if let ExpressionKind::Identifier(name) = &target.kind {
+                    if let Some(declared) = self.type_env.declared_type(name).cloned() {
+                        if !self.types_compatible(&typed_value.type_, &target_type) {
+                            self.type_env.widen(name);
+                        }
+                        target_type = declared;
+                    }
+                }
+                
+                if !self.types_compatible(&typed_value.type_, &target_type) {
+                    return Err(SemanticError {
+                        span: value.span,
+                        kind: SemanticErrorKind::TypeMismatch {
+                            expected: format!("{}", target_type),
+                            found: format!("{}", typed_value.type_),
+                        },
+                    });
+                }
+                let typed_value = self.coerce(typed_value, &target_type);
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::Assignment(typed_target, typed_value),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::Go(expr) => {
+                // `go` spawns a call to a named function or a closure
+                if !matches!(expr.kind, ExpressionKind::Call(_, _)) {
+                    return Err(SemanticError {
+                        span: expr.span,
+                        kind: SemanticErrorKind::InvalidOperation {
+                            message: "go expects a function call".to_string(),
+                        },
+                    });
+                }
+                let typed_expr = self.check_expression(expr)?;
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::Go(typed_expr),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::Match(expr, arms) => {
+                let typed_expr = self.check_expression(expr)?;
+                
+                // Every arm is checked before the first failure is returned.
+                // Arms after an unguarded `null` arm only match values that
+                // are not null
+                let mut typed_arms = Vec::new();
+                let mut arm_type = typed_expr.type_.clone();
+                for arm in arms {
+                    self.type_env.enter_scope();
+                    let typed_arm = self.check_match_arm(arm, &arm_type);
+                    self.type_env.exit_scope();
+                    typed_arms.push(typed_arm);
+                    
+                    if arm.guard.is_none() && arm.pattern == Pattern::Literal(Literal::Null, Spelling::default()) {
+                        if let Type::Nullable(inner) = arm_type {
+                            arm_type = *inner;
+                        }
+                    }
+                }
+                let typed_arms = typed_arms.into_iter().collect::<Result<Vec<_>, _>>()?;
+                
+                self.check_exhaustive(&typed_expr.type_, arms, stmt.span)?;
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::Match(typed_expr, typed_arms),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::Const(name, type_, value) => {
+                // The declared type stands even if the value fails to check
+                let typed_value = self.check_expression(value);
+                self.type_env.bind(name.clone(), InferType::Concrete(type_.clone()));
+                let typed_value = typed_value?;
+                self.expect_compatible(&typed_value.type_, type_, value.span)?;
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::Const(name.clone(), type_.clone(), typed_value),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::If(cond, then_block, else_block) => {
+                // Both branches are checked even when the condition fails.
+                // Variables the condition compares with `null` are narrowed
+                // in the branch where they are known not to be null
+                let typed_cond = self.check_condition(cond);
+                let (when_true, when_false) = null_checks(cond);
+                let typed_then = self.with_narrowed(&when_true, |checker| checker.check_block(then_block));
+                let typed_else = else_block.as_ref()
+                    .map(|else_block| self.with_narrowed(&when_false, |checker| checker.check_block(else_block)))
+                    .transpose();
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::If(typed_cond?, typed_then?, typed_else?),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::While(cond, body) => {
+                // Each iteration starts with the condition holding, so the
+                // body is narrowed like the `then` branch of an `if`
+                let typed_cond = self.check_condition(cond);
+                let (when_true, _) = null_checks(cond);
+                let typed_body = self.with_narrowed(&when_true, |checker| checker.check_block(body));
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::While(typed_cond?, typed_body?),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::For(pattern, iter, body) => {
+                // The body is checked even when the iterable fails, with the
+                // loop variables poisoned
+                let typed_iter = self.check_expression(iter).and_then(|typed_iter| {
+                    match element_type(&typed_iter.type_) {
+                        Some(elem_type) => Ok((typed_iter, elem_type)),
+                        None => Err(SemanticError {
+                            span: iter.span,
+                            kind: SemanticErrorKind::InvalidOperation {
+                                message: format!("Type {} is not iterable", typed_iter.type_),
+                            },
+                        }),
+                    }
+                });
+                
+                // A destructuring pattern takes apart a hidden loop variable
+                // at the start of each iteration
+                self.type_env.enter_scope();
+                let (var, destructure) = match (pattern, &typed_iter) {
+                    (Pattern::Identifier(name), Ok((_, elem_type))) => {
+                        self.type_env.bind(name.clone(), InferType::Concrete(elem_type.clone()));
+                        (name.clone(), Ok(None))
+                    }
+                    (_, Ok((_, elem_type))) => {
+                        let element = TypedExpression {
+                            kind: TypedExpressionKind::Identifier(LOOP_ELEMENT.to_string()),
+                            type_: elem_type.clone(),
+                            span: Some(stmt.span),
+                        };
+                        let destructure = self.bind_pattern(pattern, element, stmt.span);
+                        (LOOP_ELEMENT.to_string(), destructure.map(Some))
+                    }
+                    (_, Err(_)) => {
+                        for name in pattern_bindings(pattern) {
+                            self.type_env.bind(name, InferType::Error);
+                        }
+                        (LOOP_ELEMENT.to_string(), Ok(None))
+                    }
+                };
+                let typed_body = self.check_block(body);
+                self.type_env.exit_scope();
+                
+                let (typed_iter, _) = typed_iter?;
+                let mut typed_body = typed_body?;
+                if let Some(destructure) = destructure? {
+                    typed_body.statements.insert(0, destructure);
+                }
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::For(var, typed_iter, typed_body),
+                    span: Some(stmt.span),
                 })
             }
+            StatementKind::Break(expr) => {
+                let typed_expr = match expr {
+                    Some(expr) => Some(self.check_expression(expr)?),
+                    None => None,
+                };
+                
+                Ok(TypedStatement {
+                    kind: TypedStatementKind::Break(typed_expr),
+                    span: Some(stmt.span),
+                })
+            }
+            StatementKind::Continue => Ok(TypedStatement {
+                kind: TypedStatementKind::Continue,
+                span: Some(stmt.span),
+            }),
+        }
+    }
+    
+    /// Type check `let pattern = init`, whose pattern must match every value
+    /// of the initializer's type
+    fn check_destructuring(&mut self, pattern: &Pattern, type_annotation: Option<&Type>, init: Option<&Expression>, span: Span) -> Result<TypedStatement, SemanticError> {
+        let init = init.ok_or_else(|| SemanticError {
+            span,
+            kind: SemanticErrorKind::InvalidOperation {
+                message: "A destructuring 'let' needs a value".to_string(),
+            },
+        })?;
+        
+        let typed_init = self.check_expression(init).and_then(|typed_init| match type_annotation {
+            Some(annotation) if !self.types_compatible(&typed_init.type_, annotation) => Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: format!("{}", annotation),
+                    found: format!("{}", typed_init.type_),
+                },
+            }),
+            Some(annotation) => Ok(self.coerce(typed_init, annotation)),
+            None => Ok(typed_init),
+        });
+        match typed_init {
+            Ok(typed_init) => self.bind_pattern(pattern, typed_init, span),
+            Err(error) => {
+                for name in pattern_bindings(pattern) {
+                    self.type_env.bind(name, InferType::Error);
+                }
+                Err(error)
+            }
+        }
+    }
+    
+    /// Bind the variables of a destructuring pattern to the parts of a value.
+    /// The pattern must match every value of the value's type; on failure
+    /// its variables are poisoned.
+    fn bind_pattern(&mut self, pattern: &Pattern, value: TypedExpression, span: Span) -> Result<TypedStatement, SemanticError> {
+        let checked = self.check_pattern(pattern, &value.type_, span).and_then(|()| {
+            let missing = ExhaustivenessChecker::new(&self.enums, &self.fields)
+                .missing_patterns(&value.type_, &[pattern]);
+            match missing.is_empty() {
+                true => Ok(()),
+                false => Err(SemanticError {
+                    span,
+                    kind: SemanticErrorKind::RefutablePattern { missing },
+                }),
+            }
+        });
+        if let Err(error) = checked {
+            for name in pattern_bindings(pattern) {
+                self.type_env.bind(name, InferType::Error);
+            }
+            return Err(error);
+        }
+        
+        Ok(TypedStatement {
+            kind: TypedStatementKind::Destructure(pattern.clone(), value),
+            span: Some(span),
+        })
+    }
+    
+    /// Type check the condition of an `if` or `while`
+    fn check_condition(&mut self, cond: &Expression) -> Result<TypedExpression, SemanticError> {
+        let typed_cond = self.check_expression(cond)?;
+        self.expect_compatible(&typed_cond.type_, &Type::Bool, cond.span)?;
+        Ok(typed_cond)
+    }
+    
+    /// Require the value a function body ends in to fit its declared return
+    /// type, on every path that ends in a value
+    fn check_tail(&self, block: &TypedBlock, expected: &Type) -> Result<(), SemanticError> {
+        if *expected == Type::Unit {
+            return Ok(());
+        }
+        let Some(stmt) = block.statements.last() else {
+            return Ok(());
+        };
+        match &stmt.kind {
+            TypedStatementKind::Expression(expr) if !matches!(expr.type_, Type::Unit | Type::Never) => {
+                let span = expr.span.or(stmt.span).unwrap_or_else(|| Span::single(Position::start()));
+                self.expect_compatible(&expr.type_, expected, span)
+            }
+            TypedStatementKind::If(_, then_block, Some(else_block)) => {
+                self.check_tail(then_block, expected)?;
+                self.check_tail(else_block, expected)
+            }
+            TypedStatementKind::Match(_, arms) => {
+                arms.iter().try_for_each(|arm| self.check_tail(&arm.body, expected))
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    /// Run a check in a new scope where the nullable variables among `names`
+    /// have their non-null type
+    fn with_narrowed<T>(&mut self, names: &[String], check: impl FnOnce(&mut Self) -> T) -> T {
+        self.type_env.enter_scope();
+        self.narrow(names);
+        let result = check(self);
+        self.type_env.exit_scope();
+        result
+    }
+    
+    /// Bind the nullable variables among `names` to their non-null type in
+    /// the current scope
+    fn narrow(&mut self, names: &[String]) {
+        for name in names {
+            if let Some(InferType::Concrete(declared @ Type::Nullable(_))) = self.type_env.lookup(name).cloned() {
+                self.type_env.narrow(name.clone(), declared);
+            }
         }
     }
     
 
     
+    /// Type check a match arm against the type of the scrutinee
+    fn check_match_arm(&mut self, arm: &MatchArm, scrutinee_type: &Type) -> Result<TypedMatchArm, SemanticError> {
+        self.check_pattern(&arm.pattern, scrutinee_type, arm.span)?;
+        
+        let guard = match &arm.guard {
+            Some(guard) => {
+                let typed_guard = self.check_expression(guard)?;
+                if typed_guard.type_ != Type::Bool {
+                    return Err(SemanticError {
+                        span: guard.span,
+                        kind: SemanticErrorKind::TypeMismatch {
+                            expected: "bool".to_string(),
+                            found: format!("{}", typed_guard.type_),
+                        },
+                    });
+                }
+                Some(typed_guard)
+            }
+            None => None,
+        };
+        
+        Ok(TypedMatchArm {
+            pattern: arm.pattern.clone(),
+            guard,
+            body: self.check_block(&arm.body)?,
+        })
+    }
+    
+    /// Check a pattern against the type it matches and bind its variables
+    fn check_pattern(&mut self, pattern: &Pattern, expected: &Type, span: Span) -> Result<(), SemanticError> {
+        let mismatch = |found: String| SemanticError {
+            span,
+            kind: SemanticErrorKind::TypeMismatch { expected: format!("{}", expected), found },
+        };
+        
+        match pattern {
+            Pattern::Literal(lit, _) => {
+                let lit_type = self.literal_type(lit);
+                if !self.types_compatible(&lit_type, expected) {
+                    return Err(mismatch(format!("{}", lit_type)));
+                }
+                Ok(())
+            }
+            Pattern::Identifier(name) => {
+                self.type_env.bind(name.clone(), InferType::Concrete(expected.clone()));
+                Ok(())
+            }
+            Pattern::Wildcard => Ok(()),
+            Pattern::Tuple(patterns) => match expected {
+                Type::Tuple(types) if types.len() == patterns.len() => {
+                    for (pattern, type_) in patterns.iter().zip(types) {
+                        self.check_pattern(pattern, type_, span)?;
+                    }
+                    Ok(())
+                }
+                _ => Err(SemanticError {
+                    span,
+                    kind: SemanticErrorKind::TuplePatternMismatch { elements: patterns.len(), found: expected.to_string() },
+                }),
+            },
+            Pattern::Variant(path, patterns) => {
+                let variant = self.pattern_variant(path, expected, span)?;
+                let types = match &variant.payload {
+                    VariantPayload::Unit => Vec::new(),
+                    VariantPayload::Tuple(types) => types.clone(),
+                    VariantPayload::Struct(_) => return Err(SemanticError {
+                        span,
+                        kind: SemanticErrorKind::InvalidOperation {
+                            message: format!("Variant '{}' has named fields; match it with '{} {{ .. }}'", variant.name, path.join("::")),
+                        },
+                    }),
+                };
+                if types.len() != patterns.len() {
+                    return Err(mismatch(format!("{} with {} field(s)", path.join("::"), patterns.len())));
+                }
+                for (pattern, type_) in patterns.iter().zip(&types) {
+                    self.check_pattern(pattern, type_, span)?;
+                }
+                Ok(())
+            }
+            Pattern::Struct(name, field_patterns) => {
+                let path: Vec<String> = name.split("::").map(str::to_string).collect();
+                let fields = match expected {
+                    Type::Named(type_name) if self.enums.contains_key(type_name) => {
+                        match self.pattern_variant(&path, expected, span)?.payload {
+                            VariantPayload::Struct(fields) => fields,
+                            _ => return Err(SemanticError {
+                                span,
+                                kind: SemanticErrorKind::InvalidOperation {
+                                    message: format!("Variant '{}' has no named fields", name),
+                                },
+                            }),
+                        }
+                    }
+                    Type::Named(type_name) if type_name == name => {
+                        self.fields.get(name).cloned().ok_or_else(|| mismatch(name.clone()))?
+                    }
+                    _ => return Err(mismatch(name.clone())),
+                };
+                for (field_name, pattern) in field_patterns {
+                    let field = fields.iter().find(|f| &f.name == field_name).ok_or_else(|| SemanticError {
+                        span,
+                        kind: SemanticErrorKind::InvalidOperation {
+                            message: format!("'{}' has no field '{}'", name, field_name),
+                        },
+                    })?;
+                    self.check_pattern(pattern, &field.type_, span)?;
+                }
+                Ok(())
+            }
+            Pattern::Result(result_pattern) => {
+                let (ok_type, err_type) = match expected {
+                    Type::Result(ok, err) => (ok.as_ref().clone(), err.as_ref().clone()),
+                    _ => return Err(mismatch("Result".to_string())),
+                };
+                match result_pattern {
+                    ResultPattern::Ok(pattern) => self.check_pattern(pattern, &ok_type, span),
+                    ResultPattern::Err(pattern) => self.check_pattern(pattern, &err_type, span),
+                }
+            }
+        }
+    }
+    
+    /// Resolve the variant a pattern names, given the enum type being matched.
+    /// The enum prefix may be omitted: `Circle(r)` matches `Shape::Circle`.
+    fn pattern_variant(&self, path: &[String], expected: &Type, span: Span) -> Result<Variant, SemanticError> {
+        let joined = path.join("::");
+        let enum_def = match expected {
+            Type::Named(type_name) => self.enums.get(type_name),
+            _ => None,
+        }.ok_or_else(|| SemanticError {
+            span,
+            kind: SemanticErrorKind::TypeMismatch {
+                expected: format!("{}", expected),
+                found: joined.clone(),
+            },
+        })?;
+        
+        let (prefix, variant_name) = match path.split_last() {
+            Some((variant_name, prefix)) => (prefix.join("::"), variant_name),
+            None => (String::new(), &joined),
+        };
+        let prefix_matches = prefix.is_empty()
+            || prefix == enum_def.name
+            || enum_def.name.ends_with(&format!("::{}", prefix));
+        if !prefix_matches {
+            return Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: enum_def.name.clone(),
+                    found: prefix,
+                },
+            });
+        }
+        
+        enum_def.variant(variant_name).cloned().ok_or_else(|| SemanticError {
+            span,
+            kind: SemanticErrorKind::UndefinedVariable { name: format!("{}::{}", enum_def.name, variant_name) },
+        })
+    }
+    
+    /// Report values of the scrutinee type that no unguarded arm matches
+    fn check_exhaustive(&self, scrutinee_type: &Type, arms: &[MatchArm], span: Span) -> Result<(), SemanticError> {
+        let patterns: Vec<&Pattern> = arms.iter()
+            .filter(|arm| arm.guard.is_none())
+            .map(|arm| &arm.pattern)
+            .collect();
+        
+        let missing = ExhaustivenessChecker::new(&self.enums, &self.fields)
+            .missing_patterns(scrutinee_type, &patterns);
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::NonExhaustiveMatch { missing },
+            })
+        }
+    }
+    
     /// Check if two types are compatible
     fn types_compatible(&self, t1: &Type, t2: &Type) -> bool {
-        // Simplified type compatibility check
-        t1 == t2
+        match (t1, t2) {
+            _ if t1 == t2 => true,
+            // `null` is a value of every nullable type, and so is every
+            // value of its non-null type
+            (Type::Nullable(inner), Type::Nullable(_)) if **inner == Type::Never => true,
+            (_, Type::Nullable(inner)) => self.types_compatible(t1, inner),
+            _ => self.implements(t1, t2),
+        }
+    }
+    
+    /// Whether `type_` is a concrete type with an impl of the interface `interface`
+    fn implements(&self, type_: &Type, interface: &Type) -> bool {
+        match (type_, interface) {
+            (Type::Named(type_name), Type::Named(interface_name)) => {
+                self.impls.contains(&(type_name.clone(), interface_name.clone()))
+            }
+            _ => false,
+        }
+    }
+    
+    /// Require a value of type `actual` where `expected` is needed
+    fn expect_compatible(&self, actual: &Type, expected: &Type, span: Span) -> Result<(), SemanticError> {
+        if self.types_compatible(actual, expected) {
+            Ok(())
+        } else {
+            Err(SemanticError {
+                span,
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: format!("{}", expected),
+                    found: format!("{}", actual),
+                },
+            })
+        }
+    }
+    
+    /// Wrap a value used where an interface is expected so backends can attach
+    /// the vtable of its concrete type
+    fn coerce(&self, expr: TypedExpression, expected: &Type) -> TypedExpression {
+        if !self.implements(&expr.type_, expected) {
+            return expr;
+        }
+        TypedExpression {
+            span: expr.span,
+            type_: expected.clone(),
+            kind: TypedExpressionKind::Upcast(Box::new(expr)),
+        }
     }
 }
 
@@ -1070,6 +3423,84 @@ impl Default for TypeChecker {
     }
 }
 
+/// Rename the user-defined types that `rename` gives a new name for
+fn qualify_type(type_: &Type, rename: &dyn Fn(&str) -> Option<String>) -> Type {
+    let map = |t: &Type| qualify_type(t, rename);
+    match type_ {
+        Type::Named(name) => Type::Named(rename(name).unwrap_or_else(|| name.clone())),
+        Type::Generic(name, args) => Type::Generic(
+            rename(name).unwrap_or_else(|| name.clone()),
+            args.iter().map(map).collect(),
+        ),
+        Type::Array(t) => Type::Array(Box::new(map(t))),
+        Type::List(t) => Type::List(Box::new(map(t))),
+        Type::Set(t) => Type::Set(Box::new(map(t))),
+        Type::Map(k, v) => Type::Map(Box::new(map(k)), Box::new(map(v))),
+        Type::Tuple(elements) => Type::Tuple(elements.iter().map(map).collect()),
+        Type::Nullable(t) => Type::Nullable(Box::new(map(t))),
+        Type::Result(ok, err) => Type::Result(Box::new(map(ok)), Box::new(map(err))),
+        Type::Function(params, ret) => Type::Function(params.iter().map(map).collect(), Box::new(map(ret))),
+        _ => type_.clone(),
+    }
+}
+
+/// Copy of a declaration with `map` applied to the types of its signature
+/// and fields; bodies are left as they are
+fn map_item_types(item: &Item, map: &dyn Fn(&Type) -> Type) -> Item {
+    let parameters = |parameters: &[Parameter]| -> Vec<Parameter> {
+        parameters.iter().map(|p| Parameter { type_: map(&p.type_), ..p.clone() }).collect()
+    };
+    let fields = |fields: &[Field]| -> Vec<Field> {
+        fields.iter().map(|f| Field { type_: map(&f.type_), ..f.clone() }).collect()
+    };
+    let methods = |methods: &[Method]| -> Vec<Method> {
+        methods.iter().map(|m| Method {
+            parameters: parameters(&m.parameters),
+            return_type: m.return_type.as_ref().map(map),
+            ..m.clone()
+        }).collect()
+    };
+    match item {
+        Item::Function(func) => Item::Function(Function {
+            parameters: parameters(&func.parameters),
+            return_type: func.return_type.as_ref().map(map),
+            ..func.clone()
+        }),
+        Item::ExternFunction(func) => Item::ExternFunction(ExternFunction {
+            parameters: parameters(&func.parameters),
+            return_type: func.return_type.as_ref().map(map),
+            ..func.clone()
+        }),
+        Item::Const(const_def) => Item::Const(Const { type_: map(&const_def.type_), ..const_def.clone() }),
+        Item::Struct(struct_def) => Item::Struct(Struct { fields: fields(&struct_def.fields), ..struct_def.clone() }),
+        Item::Class(class_def) => Item::Class(Class {
+            fields: fields(&class_def.fields),
+            methods: methods(&class_def.methods),
+            ..class_def.clone()
+        }),
+        Item::Interface(interface) => Item::Interface(Interface {
+            methods: interface.methods.iter().map(|m| MethodSignature {
+                parameters: parameters(&m.parameters),
+                return_type: m.return_type.as_ref().map(map),
+                ..m.clone()
+            }).collect(),
+            ..interface.clone()
+        }),
+        Item::Impl(impl_block) => Item::Impl(Impl { methods: methods(&impl_block.methods), ..impl_block.clone() }),
+        Item::Enum(enum_def) => Item::Enum(Enum {
+            variants: enum_def.variants.iter().map(|variant| Variant {
+                payload: match &variant.payload {
+                    VariantPayload::Unit => VariantPayload::Unit,
+                    VariantPayload::Tuple(types) => VariantPayload::Tuple(types.iter().map(map).collect()),
+                    VariantPayload::Struct(variant_fields) => VariantPayload::Struct(fields(variant_fields)),
+                },
+                ..variant.clone()
+            }).collect(),
+            ..enum_def.clone()
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1116,4 +3547,26 @@ mod tests {
         assert!(!checker.is_numeric_type(&Type::String));
         assert!(!checker.is_numeric_type(&Type::Bool));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_spans_carried_into_typed_nodes() {
+        use crate::lexer::FluxLexer;
+        use crate::parser::{FluxParser, Parser};
+
+        let parse = |source: &str| {
+            let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+            parser.parse_expression().unwrap()
+        };
+        let mut checker = TypeChecker::new();
+
+        let expr = parse("1 + 2 * 3");
+        let typed = checker.check_expression(&expr).unwrap();
+        assert_eq!(typed.span, Some(expr.span));
+        assert_eq!(typed.span.unwrap().end.column, 10);
+
+        let expr = parse("1 + true");
+        let error = checker.check_expression(&expr).unwrap_err();
+        assert_eq!(error.span, expr.span);
+        assert_ne!(error.span, create_span());
+    }
+}
@@ -2,15 +2,18 @@
 //! 
 //! This module provides code formatting, linting, testing, and benchmarking tools.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use colored::*;
 
-use crate::error::{FluxResult, FluxError};
+use crate::error::{FluxResult, FluxError, SemanticErrorKind};
 use crate::lexer::{FluxLexer, Token};
+use crate::lexer::trivia::{self, SyntaxToken};
 use crate::parser::{FluxParser, Parser, ast};
+use crate::position::{Position, Span};
 use crate::cli::CliContext;
+use crate::semantic::FluxSemanticAnalyzer;
 
 /// Code formatter for Flux source files
 pub struct Formatter {
@@ -86,8 +89,10 @@ impl Formatter {
         let mut parser = FluxParser::new(lexer)?;
         let program = parser.parse_program()?;
 
-        // Format the AST back to source code
-        let mut formatter = SourceFormatter::new(&self.config);
+        // Format the AST back to source code, keeping the comments and
+        // blank lines it does not record
+        let layout = SourceLayout::new(source, &trivia::tokenize(source)?, &documented_starts(&program));
+        let mut formatter = SourceFormatter::new(&self.config, layout);
         Ok(formatter.format_program(&program))
     }
 
@@ -139,60 +144,314 @@ impl FormatResults {
     }
 }
 
-/// AST-based source code formatter
+/// Comment in the source being formatted
+struct SourceComment {
+    text: String,
+    span: Span,
+    /// Whether a token precedes it on its line
+    trailing: bool,
+}
+
+/// What the AST does not record about the source: its comments, including
+/// doc comments no declaration takes, and which lines hold neither tokens
+/// nor comments
+struct SourceLayout {
+    /// Text of the source, for code written as it is
+    source: String,
+    comments: Vec<SourceComment>,
+    occupied_lines: HashSet<usize>,
+    /// Line of the first token, where the formatted file begins
+    first_line: usize,
+}
+
+impl SourceLayout {
+    /// `documented` holds the offsets where declarations with a doc comment
+    /// start; the doc comments before anything else are kept as comments
+    fn new(source: &str, tokens: &[SyntaxToken], documented: &HashSet<usize>) -> Self {
+        let mut comments = Vec::new();
+        let mut occupied_lines = HashSet::new();
+        let mut last_token_line = 0;
+        // Doc comments before the next token, which takes them if it starts
+        // a documented declaration
+        let mut docs = Vec::new();
+
+        for token in tokens {
+            if let Token::DocComment(_) = token.token {
+                docs.push(SourceComment {
+                    text: token.text.clone(),
+                    span: token.span,
+                    trailing: token.span.start.line == last_token_line,
+                });
+            } else if !documented.contains(&token.span.start.offset) {
+                comments.append(&mut docs);
+            } else {
+                docs.clear();
+            }
+
+            for trivia in token.leading_trivia.iter().filter(|trivia| trivia.is_comment()) {
+                comments.push(SourceComment {
+                    text: trivia.text.clone(),
+                    span: trivia.span,
+                    trailing: trivia.span.start.line == last_token_line,
+                });
+                occupied_lines.extend(trivia.span.start.line..=trivia.span.end.line);
+            }
+            if token.token != Token::Eof {
+                occupied_lines.extend(token.span.start.line..=token.span.end.line);
+                last_token_line = token.span.end.line;
+            }
+        }
+
+        let first_line = tokens.first()
+            .filter(|token| token.token != Token::Eof)
+            .map_or(usize::MAX, |token| token.span.start.line);
+        Self { source: source.to_string(), comments, occupied_lines, first_line }
+    }
+
+    /// Whether the source has a blank line between two lines
+    fn has_blank_line_between(&self, after: usize, before: usize) -> bool {
+        (after + 1..before).any(|line| !self.occupied_lines.contains(&line))
+    }
+}
+
+/// Start offsets of the declarations that have a doc comment
+fn documented_starts(program: &ast::Program) -> HashSet<usize> {
+    fn add(starts: &mut HashSet<usize>, doc: &Option<String>, span: Span) {
+        if doc.is_some() {
+            starts.insert(span.start.offset);
+        }
+    }
+    fn add_fields(starts: &mut HashSet<usize>, fields: &[ast::Field]) {
+        for field in fields {
+            add(starts, &field.doc, field.span);
+        }
+    }
+    fn add_methods(starts: &mut HashSet<usize>, methods: &[ast::Method]) {
+        for method in methods {
+            add(starts, &method.doc, method.span);
+        }
+    }
+
+    let mut starts = HashSet::new();
+    for item in &program.items {
+        match item {
+            ast::Item::Function(func) => add(&mut starts, &func.doc, func.span),
+            ast::Item::Struct(struct_def) => {
+                add(&mut starts, &struct_def.doc, struct_def.span);
+                add_fields(&mut starts, &struct_def.fields);
+            }
+            ast::Item::Class(class_def) => {
+                add(&mut starts, &class_def.doc, class_def.span);
+                add_fields(&mut starts, &class_def.fields);
+                add_methods(&mut starts, &class_def.methods);
+            }
+            ast::Item::Enum(enum_def) => {
+                add(&mut starts, &enum_def.doc, enum_def.span);
+                // Fields of struct variants are written on the variant's line,
+                // so their doc comments stay comments
+                for variant in &enum_def.variants {
+                    add(&mut starts, &variant.doc, variant.span);
+                }
+            }
+            ast::Item::Interface(interface) => {
+                add(&mut starts, &interface.doc, interface.span);
+                for method in &interface.methods {
+                    add(&mut starts, &method.doc, method.span);
+                }
+            }
+            ast::Item::Impl(impl_block) => add_methods(&mut starts, &impl_block.methods),
+            ast::Item::Const(const_def) => add(&mut starts, &const_def.doc, const_def.span),
+            ast::Item::ExternFunction(_) => {}
+        }
+    }
+    starts
+}
+
+/// AST-based source code formatter. Comments are written before the
+/// declaration or statement that follows them, or after the one whose last
+/// line they end, and single blank lines between them are kept. A statement
+/// or signature with comments inside keeps its source lines.
 struct SourceFormatter<'a> {
     config: &'a FormatterConfig,
     indent_level: usize,
     output: String,
+    layout: SourceLayout,
+    /// Index of the first comment not written yet
+    next_comment: usize,
+    /// Last source line written
+    last_line: usize,
+    /// Whether a blank line would be out of place: at the start of a block
+    /// or after one already written
+    skip_blank_line: bool,
 }
 
 impl<'a> SourceFormatter<'a> {
-    fn new(config: &'a FormatterConfig) -> Self {
+    fn new(config: &'a FormatterConfig, layout: SourceLayout) -> Self {
         Self {
             config,
             indent_level: 0,
             output: String::new(),
+            layout,
+            next_comment: 0,
+            last_line: 0,
+            skip_blank_line: true,
         }
     }
 
     fn format_program(&mut self, program: &ast::Program) -> String {
+        // Comments heading the file stay above everything else
+        let first_line = self.layout.first_line;
+        self.write_comments_before(first_line);
+        self.write_blank_line_before(first_line);
+
         // Format package declaration
         if !program.package.is_empty() {
             self.write_line(&format!("package {};", program.package));
-            self.write_line("");
+            self.write_separator();
         }
 
         // Format imports
         for import in &program.imports {
-            self.write_line(&format!("import {};", import.path));
+            self.begin_node(import.span);
+            self.write_line(&format!("{};", import));
+            self.end_node(import.span);
         }
         if !program.imports.is_empty() {
-            self.write_line("");
+            self.write_separator();
         }
 
         // Format items
         for (i, item) in program.items.iter().enumerate() {
             if i > 0 {
-                self.write_line("");
+                self.write_separator();
             }
+            self.begin_node(item.span());
             self.format_item(item);
+            self.end_node(item.span());
         }
 
+        self.write_comments_before(usize::MAX);
         self.output.clone()
     }
 
+    /// Write the comments that start before `line`
+    fn write_comments_before(&mut self, line: usize) {
+        while let Some(comment) = self.layout.comments.get(self.next_comment) {
+            if comment.span.start.line >= line {
+                break;
+            }
+            let (text, span) = (comment.text.clone(), comment.span);
+            self.next_comment += 1;
+
+            self.write_blank_line_before(span.start.line);
+            // Lines after the first of a block comment are kept as they are
+            let mut lines = text.split('\n');
+            self.write_line(lines.next().unwrap_or_default());
+            for rest in lines {
+                self.output.push_str(rest);
+                self.output.push('\n');
+            }
+            self.last_line = span.end.line;
+        }
+    }
+
+    /// Write a blank line if the source has one between the last line
+    /// written and `line`
+    fn write_blank_line_before(&mut self, line: usize) {
+        if !self.skip_blank_line && self.layout.has_blank_line_between(self.last_line, line) {
+            self.write_line("");
+        }
+        self.skip_blank_line = false;
+    }
+
+    /// Write the blank line the formatter always puts between sections
+    fn write_separator(&mut self) {
+        self.write_line("");
+        self.skip_blank_line = true;
+    }
+
+    /// Prepare to write a declaration or statement spanning `span`
+    fn begin_node(&mut self, span: Span) {
+        self.write_comments_before(span.start.line);
+        self.write_blank_line_before(span.start.line);
+    }
+
+    /// Finish a declaration or statement spanning `span`, moving a comment
+    /// that follows it on its last line to the end of the written line
+    fn end_node(&mut self, span: Span) {
+        self.last_line = span.end.line;
+        let Some(comment) = self.layout.comments.get(self.next_comment) else {
+            return;
+        };
+        if comment.trailing && comment.span.start.line == span.end.line {
+            self.output.pop(); // the line break
+            self.output.push(' ');
+            self.output.push_str(&comment.text);
+            self.output.push('\n');
+            self.last_line = comment.span.end.line;
+            self.next_comment += 1;
+        }
+    }
+
+    /// Write the source from `start` to `end` as it is if comments lie
+    /// inside it, so each stays next to the code it describes. The first
+    /// line is indented like any other, and the rest are kept as they are.
+    /// Returns whether the source was written.
+    fn write_original(&mut self, start: Position, end: Position) -> bool {
+        // Comments before `start` were written before the node
+        let inner = self.layout.comments[self.next_comment..].iter()
+            .take_while(|comment| comment.span.start.offset < end.offset)
+            .count();
+        if inner == 0 {
+            return false;
+        }
+
+        let text = self.layout.source[start.offset..end.offset].to_string();
+        let mut lines = text.split('\n');
+        self.write_line(lines.next().unwrap_or_default().trim_end());
+        for rest in lines {
+            self.output.push_str(rest.trim_end());
+            self.output.push('\n');
+        }
+        self.next_comment += inner;
+        true
+    }
+
+    /// Write the line opening a declaration or statement whose block starts
+    /// at `block`, or its source lines if comments lie inside it
+    fn write_header(&mut self, line: &str, start: Position, block: Position) {
+        if !self.write_original(start, block.advanced('{')) {
+            self.write_line(line);
+        }
+    }
+
+    /// Prepare to write the contents of a block starting on `line`
+    fn begin_block(&mut self, line: usize) {
+        self.last_line = line;
+        self.skip_blank_line = true;
+    }
+
+    /// Finish the contents of a block whose closing brace is on `line`
+    fn end_block(&mut self, line: usize) {
+        self.write_comments_before(line);
+    }
+
     fn format_item(&mut self, item: &ast::Item) {
         match item {
             ast::Item::Function(func) => self.format_function(func),
             ast::Item::Struct(struct_def) => self.format_struct(struct_def),
             ast::Item::Class(class_def) => self.format_class(class_def),
+            ast::Item::Enum(enum_def) => self.format_enum(enum_def),
+            ast::Item::Interface(interface) => self.format_interface(interface),
+            ast::Item::Impl(impl_block) => self.format_impl(impl_block),
             ast::Item::Const(const_def) => self.format_const(const_def),
             ast::Item::ExternFunction(extern_func) => self.format_extern_function(extern_func),
         }
     }
 
     fn format_function(&mut self, func: &ast::Function) {
-        let mut line = String::new();
+        self.write_doc(&func.doc);
+        let mut line = func.visibility.to_string();
         
         if func.is_async {
             line.push_str("async ");
@@ -200,26 +459,25 @@ impl<'a> SourceFormatter<'a> {
         
         line.push_str("func ");
         line.push_str(&func.name);
+        line.push_str(&ast::type_params(&func.type_params));
         line.push('(');
 
         for (i, param) in func.parameters.iter().enumerate() {
             if i > 0 {
                 line.push_str(", ");
             }
-            line.push_str(&param.name);
-            line.push_str(": ");
-            line.push_str(&format!("{:?}", param.type_));
+            line.push_str(&param.to_string());
         }
 
         line.push(')');
 
         if let Some(return_type) = &func.return_type {
             line.push_str(" -> ");
-            line.push_str(&format!("{:?}", return_type));
+            line.push_str(&return_type.to_string());
         }
 
         line.push_str(" {");
-        self.write_line(&line);
+        self.write_header(&line, func.span.start, func.body.span.start);
 
         self.indent();
         self.format_block(&func.body);
@@ -229,7 +487,7 @@ impl<'a> SourceFormatter<'a> {
     }
 
     fn format_extern_function(&mut self, extern_func: &ast::ExternFunction) {
-        let mut line = String::new();
+        let mut line = extern_func.visibility.to_string();
         
         line.push_str("extern");
         
@@ -247,9 +505,7 @@ impl<'a> SourceFormatter<'a> {
             if i > 0 {
                 line.push_str(", ");
             }
-            line.push_str(&param.name);
-            line.push_str(": ");
-            line.push_str(&format!("{:?}", param.type_));
+            line.push_str(&param.to_string());
         }
         
         if extern_func.is_variadic {
@@ -263,7 +519,7 @@ impl<'a> SourceFormatter<'a> {
 
         if let Some(return_type) = &extern_func.return_type {
             line.push_str(" -> ");
-            line.push_str(&format!("{:?}", return_type));
+            line.push_str(&return_type.to_string());
         }
 
         line.push(';');
@@ -271,122 +527,216 @@ impl<'a> SourceFormatter<'a> {
     }
 
     fn format_struct(&mut self, struct_def: &ast::Struct) {
-        let line = format!("struct {} {{", struct_def.name);
+        self.write_doc(&struct_def.doc);
+        let line = format!("{}struct {}{} {{", struct_def.visibility, struct_def.name, ast::type_params(&struct_def.type_params));
         self.write_line(&line);
 
         self.indent();
+        self.begin_block(struct_def.span.start.line);
         for field in &struct_def.fields {
-            let field_line = format!("{}: {},", field.name, format!("{:?}", field.type_));
+            self.begin_node(field.span);
+            self.write_doc(&field.doc);
+            let field_line = format!("{},", field);
             self.write_line(&field_line);
+            self.end_node(field.span);
+        }
+        self.end_block(struct_def.span.end.line);
+        self.dedent();
+
+        self.write_line("}");
+    }
+
+    fn format_enum(&mut self, enum_def: &ast::Enum) {
+        self.write_doc(&enum_def.doc);
+        let line = format!("{}enum {} {{", enum_def.visibility, enum_def.name);
+        self.write_line(&line);
+
+        self.indent();
+        self.begin_block(enum_def.span.start.line);
+        for variant in &enum_def.variants {
+            self.begin_node(variant.span);
+            self.write_doc(&variant.doc);
+            // Comments between the fields of a struct variant keep its lines
+            if self.write_original(variant.span.start, variant.span.end) {
+                self.output.pop(); // the line break
+                self.output.push_str(",\n");
+            } else {
+                let variant_line = format!("{},", variant);
+                self.write_line(&variant_line);
+            }
+            self.end_node(variant.span);
         }
+        self.end_block(enum_def.span.end.line);
         self.dedent();
 
         self.write_line("}");
     }
 
     fn format_class(&mut self, class_def: &ast::Class) {
-        let mut line = format!("class {}", class_def.name);
+        self.write_doc(&class_def.doc);
+        let mut line = format!("{}class {}{}", class_def.visibility, class_def.name, ast::type_params(&class_def.type_params));
         
         line.push_str(" {");
         self.write_line(&line);
 
         self.indent();
+        self.begin_block(class_def.span.start.line);
         
         // Format fields
         for field in &class_def.fields {
-            let field_line = format!("{}: {},", field.name, format!("{:?}", field.type_));
+            self.begin_node(field.span);
+            self.write_doc(&field.doc);
+            let field_line = format!("{},", field);
             self.write_line(&field_line);
+            self.end_node(field.span);
         }
 
         if !class_def.fields.is_empty() && !class_def.methods.is_empty() {
-            self.write_line("");
+            self.write_separator();
         }
 
         // Format methods
         for (i, method) in class_def.methods.iter().enumerate() {
             if i > 0 {
-                self.write_line("");
+                self.write_separator();
             }
             self.format_method(method);
         }
         
+        self.end_block(class_def.span.end.line);
+        self.dedent();
+        self.write_line("}");
+    }
+
+    fn format_interface(&mut self, interface: &ast::Interface) {
+        self.write_doc(&interface.doc);
+        let line = format!("{}interface {} {{", interface.visibility, interface.name);
+        self.write_line(&line);
+
+        self.indent();
+        self.begin_block(interface.span.start.line);
+        for method in &interface.methods {
+            self.begin_node(method.span);
+            self.write_doc(&method.doc);
+            let method_line = format!("{};", method);
+            self.write_line(&method_line);
+            self.end_node(method.span);
+        }
+        self.end_block(interface.span.end.line);
+        self.dedent();
+
+        self.write_line("}");
+    }
+
+    fn format_impl(&mut self, impl_block: &ast::Impl) {
+        let line = format!("impl {} for {} {{", impl_block.interface, impl_block.type_name);
+        self.write_line(&line);
+
+        self.indent();
+        self.begin_block(impl_block.span.start.line);
+        for (i, method) in impl_block.methods.iter().enumerate() {
+            if i > 0 {
+                self.write_separator();
+            }
+            self.format_method(method);
+        }
+        self.end_block(impl_block.span.end.line);
         self.dedent();
+
         self.write_line("}");
     }
 
     fn format_const(&mut self, const_def: &ast::Const) {
-        let line = format!("const {}: {} = {};", 
+        self.write_doc(&const_def.doc);
+        let line = format!("{}const {}: {} = {};", 
+            const_def.visibility,
             const_def.name, 
-            format!("{:?}", const_def.type_), 
-            format!("{:?}", const_def.value)
+            const_def.type_, 
+            const_def.value
         );
         self.write_line(&line);
     }
 
     fn format_block(&mut self, block: &ast::Block) {
+        self.begin_block(block.span.start.line);
         for stmt in &block.statements {
+            self.begin_node(stmt.span);
             self.format_statement(stmt);
+            self.end_node(stmt.span);
         }
+        self.end_block(block.span.end.line);
     }
 
     fn format_statement(&mut self, stmt: &ast::Statement) {
-        match stmt {
-            ast::Statement::Expression(expr) => {
-                let line = format!("{};", format!("{:?}", expr));
+        let has_block = matches!(
+            stmt.kind,
+            ast::StatementKind::If(..) | ast::StatementKind::While(..) | ast::StatementKind::For(..) | ast::StatementKind::Match(..)
+        );
+        if !has_block && self.write_original(stmt.span.start, stmt.span.end) {
+            if !self.output.ends_with(";\n") {
+                self.output.pop(); // the line break
+                self.output.push_str(";\n");
+            }
+            return;
+        }
+
+        match &stmt.kind {
+            ast::StatementKind::Expression(expr) => {
+                let line = format!("{};", expr);
                 self.write_line(&line);
             }
-            ast::Statement::Let(name, type_, value) => {
-                let mut line = format!("let {}", name);
+            ast::StatementKind::Let(pattern, type_, value) => {
+                let mut line = format!("let {}", pattern);
                 if let Some(t) = type_ {
-                    line.push_str(&format!(": {}", format!("{:?}", t)));
+                    line.push_str(&format!(": {}", t));
                 }
                 if let Some(v) = value {
-                    line.push_str(&format!(" = {}", format!("{:?}", v)));
+                    line.push_str(&format!(" = {}", v));
                 }
                 line.push(';');
                 self.write_line(&line);
             }
-            ast::Statement::Const(name, type_, value) => {
+            ast::StatementKind::Const(name, type_, value) => {
                 let line = format!("const {}: {} = {};", 
                     name, 
-                    format!("{:?}", type_), 
-                    format!("{:?}", value)
+                    type_, 
+                    value
                 );
                 self.write_line(&line);
             }
-            ast::Statement::Assignment(target, value) => {
+            ast::StatementKind::Assignment(target, value) => {
                 let line = format!("{} = {};", 
-                    format!("{:?}", target), 
-                    format!("{:?}", value)
+                    target, 
+                    value
                 );
                 self.write_line(&line);
             }
-            ast::Statement::Return(value) => {
+            ast::StatementKind::Return(value) => {
                 let line = if let Some(v) = value {
-                    format!("return {};", format!("{:?}", v))
+                    format!("return {};", v)
                 } else {
                     "return;".to_string()
                 };
                 self.write_line(&line);
             }
-            ast::Statement::Break(value) => {
+            ast::StatementKind::Break(value) => {
                 let line = if let Some(v) = value {
-                    format!("break {};", format!("{:?}", v))
+                    format!("break {};", v)
                 } else {
                     "break;".to_string()
                 };
                 self.write_line(&line);
             }
-            ast::Statement::Continue => {
+            ast::StatementKind::Continue => {
                 self.write_line("continue;");
             }
-            ast::Statement::Go(expr) => {
-                let line = format!("go {};", format!("{:?}", expr));
+            ast::StatementKind::Go(expr) => {
+                let line = format!("go {};", expr);
                 self.write_line(&line);
             }
-            ast::Statement::If(cond, then_block, else_block) => {
-                let line = format!("if {} {{", format!("{:?}", cond));
-                self.write_line(&line);
+            ast::StatementKind::If(cond, then_block, else_block) => {
+                let line = format!("if {} {{", cond);
+                self.write_header(&line, stmt.span.start, then_block.span.start);
                 self.indent();
                 self.format_block(then_block);
                 self.dedent();
@@ -398,30 +748,42 @@ impl<'a> SourceFormatter<'a> {
                 }
                 self.write_line("}");
             }
-            ast::Statement::While(cond, body) => {
-                let line = format!("while {} {{", format!("{:?}", cond));
-                self.write_line(&line);
+            ast::StatementKind::While(cond, body) => {
+                let line = format!("while {} {{", cond);
+                self.write_header(&line, stmt.span.start, body.span.start);
                 self.indent();
                 self.format_block(body);
                 self.dedent();
                 self.write_line("}");
             }
-            ast::Statement::For(var, iter, body) => {
-                let line = format!("for {} in {} {{", var, format!("{:?}", iter));
-                self.write_line(&line);
+            ast::StatementKind::For(pattern, iter, body) => {
+                let line = format!("for {} in {} {{", pattern, iter);
+                self.write_header(&line, stmt.span.start, body.span.start);
                 self.indent();
                 self.format_block(body);
                 self.dedent();
                 self.write_line("}");
             }
-            ast::Statement::Match(expr, arms) => {
-                let line = format!("match {} {{", format!("{:?}", expr));
+            ast::StatementKind::Match(expr, arms) => {
+                let line = format!("match {} {{", expr);
                 self.write_line(&line);
                 self.indent();
+                self.begin_block(stmt.span.start.line);
                 for arm in arms {
-                    let arm_line = format!("{} => {},", format!("{:?}", arm.pattern), format!("{:?}", arm.body));
+                    self.begin_node(arm.span);
+                    let mut arm_line = arm.pattern.to_string();
+                    if let Some(guard) = &arm.guard {
+                        arm_line.push_str(&format!(" if {}", guard));
+                    }
+                    arm_line.push_str(" => {");
                     self.write_line(&arm_line);
+                    self.indent();
+                    self.format_block(&arm.body);
+                    self.dedent();
+                    self.write_line("}");
+                    self.end_node(arm.span);
                 }
+                self.end_block(stmt.span.end.line);
                 self.dedent();
                 self.write_line("}");
             }
@@ -429,7 +791,13 @@ impl<'a> SourceFormatter<'a> {
     }
 
     fn format_method(&mut self, method: &ast::Method) {
-        let mut line = String::new();
+        self.begin_node(method.span);
+        self.write_doc(&method.doc);
+        let mut line = method.visibility.to_string();
+        
+        if method.is_static {
+            line.push_str("static ");
+        }
         
         line.push_str("func ");
         line.push_str(&method.name);
@@ -439,24 +807,25 @@ impl<'a> SourceFormatter<'a> {
             if i > 0 {
                 line.push_str(", ");
             }
-            line.push_str(&param.name);
-            line.push_str(": ");
-            line.push_str(&format!("{:?}", param.type_));
+            line.push_str(&param.to_string());
         }
 
         line.push(')');
 
-        line.push_str(" -> ");
-        line.push_str(&format!("{:?}", method.return_type));
+        if let Some(return_type) = &method.return_type {
+            line.push_str(" -> ");
+            line.push_str(&return_type.to_string());
+        }
 
         line.push_str(" {");
-        self.write_line(&line);
+        self.write_header(&line, method.span.start, method.body.span.start);
 
         self.indent();
         self.format_block(&method.body);
         self.dedent();
 
         self.write_line("}");
+        self.end_node(method.span);
     }
 
     fn write_line(&mut self, line: &str) {
@@ -467,6 +836,17 @@ impl<'a> SourceFormatter<'a> {
         self.output.push('\n');
     }
 
+    /// Write a doc comment as `///` lines
+    fn write_doc(&mut self, doc: &Option<String>) {
+        for line in doc.iter().flat_map(|doc| doc.split('\n')) {
+            if line.is_empty() {
+                self.write_line("///");
+            } else {
+                self.write_line(&format!("/// {}", line));
+            }
+        }
+    }
+
     fn write_indent(&mut self) {
         if self.config.use_tabs {
             for _ in 0..self.indent_level {
@@ -579,26 +959,46 @@ impl Linter {
             }
         };
         
-        match parser.parse_program() {
-            Ok(program) => {
-                // Run AST-based linting rules
-                issues.extend(self.check_naming_conventions(&program, file_path));
-                issues.extend(self.check_function_complexity(&program, file_path));
-                issues.extend(self.check_documentation(&program, file_path));
-            }
-            Err(e) => {
-                issues.push(LintIssue {
-                    file: file_path.to_path_buf(),
-                    line: 1,
-                    column: 1,
-                    severity: LintSeverity::Error,
-                    rule: "syntax-error".to_string(),
-                    message: format!("Syntax error: {}", e),
-                    suggestion: None,
-                });
-            }
+        // Every syntax error is reported, and the declarations that did parse
+        // are still checked
+        let (program, errors) = parser.parse_program_with_errors();
+        for e in errors {
+            issues.push(LintIssue {
+                file: file_path.to_path_buf(),
+                line: e.span.start.line,
+                column: e.span.start.column,
+                severity: LintSeverity::Error,
+                rule: "syntax-error".to_string(),
+                message: format!("Syntax error: {}", e),
+                suggestion: None,
+            });
+        }
+
+        // Warnings of the semantic analysis, such as unreachable code. The
+        // file is analyzed alone, so errors from its imports are left out.
+        let mut analyzer = FluxSemanticAnalyzer::new();
+        analyzer.analyze_with_errors(program.clone());
+        for warning in analyzer.warnings() {
+            issues.push(LintIssue {
+                file: file_path.to_path_buf(),
+                line: warning.span.start.line,
+                column: warning.span.start.column,
+                severity: LintSeverity::Warning,
+                rule: match warning.kind {
+                    SemanticErrorKind::UnreachableCode => "unreachable-code",
+                    SemanticErrorKind::UnusedResult => "unused-result",
+                    _ => "semantic-warning",
+                }.to_string(),
+                message: warning.kind.to_string(),
+                suggestion: None,
+            });
         }
 
+        // Run AST-based linting rules
+        issues.extend(self.check_naming_conventions(&program, file_path));
+        issues.extend(self.check_function_complexity(&program, file_path));
+        issues.extend(self.check_documentation(&program, file_path));
+
         // Run text-based linting rules
         issues.extend(self.check_line_length(source, file_path));
         issues.extend(self.check_whitespace(source, file_path));
@@ -614,13 +1014,14 @@ impl Linter {
         }
 
         for item in &program.items {
+            let start = item.span().start;
             match item {
                 ast::Item::Function(func) => {
                     if !is_snake_case(&func.name) {
                         issues.push(LintIssue {
                             file: file_path.to_path_buf(),
-                            line: 1, // TODO: Add position tracking
-                            column: 1,
+                            line: start.line,
+                            column: start.column,
                             severity: LintSeverity::Warning,
                             rule: "naming-convention".to_string(),
                             message: format!("Function '{}' should use snake_case", func.name),
@@ -632,8 +1033,8 @@ impl Linter {
                     if !is_pascal_case(&struct_def.name) {
                         issues.push(LintIssue {
                             file: file_path.to_path_buf(),
-                            line: 1,
-                            column: 1,
+                            line: start.line,
+                            column: start.column,
                             severity: LintSeverity::Warning,
                             rule: "naming-convention".to_string(),
                             message: format!("Struct '{}' should use PascalCase", struct_def.name),
@@ -645,8 +1046,8 @@ impl Linter {
                     if !is_pascal_case(&class_def.name) {
                         issues.push(LintIssue {
                             file: file_path.to_path_buf(),
-                            line: 1,
-                            column: 1,
+                            line: start.line,
+                            column: start.column,
                             severity: LintSeverity::Warning,
                             rule: "naming-convention".to_string(),
                             message: format!("Class '{}' should use PascalCase", class_def.name),
@@ -654,12 +1055,41 @@ impl Linter {
                         });
                     }
                 }
+                ast::Item::Interface(interface) => {
+                    if !is_pascal_case(&interface.name) {
+                        issues.push(LintIssue {
+                            file: file_path.to_path_buf(),
+                            line: start.line,
+                            column: start.column,
+                            severity: LintSeverity::Warning,
+                            rule: "naming-convention".to_string(),
+                            message: format!("Interface '{}' should use PascalCase", interface.name),
+                            suggestion: Some(format!("Consider renaming to '{}'", to_pascal_case(&interface.name))),
+                        });
+                    }
+                }
+                ast::Item::Impl(_) => {
+                    // Names are checked where the interface and type are declared
+                }
+                ast::Item::Enum(enum_def) => {
+                    if !is_pascal_case(&enum_def.name) {
+                        issues.push(LintIssue {
+                            file: file_path.to_path_buf(),
+                            line: start.line,
+                            column: start.column,
+                            severity: LintSeverity::Warning,
+                            rule: "naming-convention".to_string(),
+                            message: format!("Enum '{}' should use PascalCase", enum_def.name),
+                            suggestion: Some(format!("Consider renaming to '{}'", to_pascal_case(&enum_def.name))),
+                        });
+                    }
+                }
                 ast::Item::Const(const_def) => {
                     if !is_screaming_snake_case(&const_def.name) {
                         issues.push(LintIssue {
                             file: file_path.to_path_buf(),
-                            line: 1,
-                            column: 1,
+                            line: start.line,
+                            column: start.column,
                             severity: LintSeverity::Warning,
                             rule: "naming-convention".to_string(),
                             message: format!("Constant '{}' should use SCREAMING_SNAKE_CASE", const_def.name),
@@ -671,8 +1101,8 @@ impl Linter {
                     if !is_snake_case(&extern_func.name) {
                         issues.push(LintIssue {
                             file: file_path.to_path_buf(),
-                            line: 1,
-                            column: 1,
+                            line: start.line,
+                            column: start.column,
                             severity: LintSeverity::Warning,
                             rule: "naming-convention".to_string(),
                             message: format!("Extern function '{}' should use snake_case", extern_func.name),
@@ -695,8 +1125,8 @@ impl Linter {
                 if line_count > self.config.max_function_length {
                     issues.push(LintIssue {
                         file: file_path.to_path_buf(),
-                        line: 1,
-                        column: 1,
+                        line: func.span.start.line,
+                        column: func.span.start.column,
                         severity: LintSeverity::Warning,
                         rule: "function-length".to_string(),
                         message: format!("Function '{}' is too long ({} lines, max {})", 
@@ -717,22 +1147,45 @@ impl Linter {
             return issues;
         }
 
+        let mut check = |kind: &str, name: &str, doc: &Option<String>, span: Span| {
+            if doc.is_none() {
+                issues.push(LintIssue {
+                    file: file_path.to_path_buf(),
+                    line: span.start.line,
+                    column: span.start.column,
+                    severity: LintSeverity::Info,
+                    rule: "missing-documentation".to_string(),
+                    message: format!("{} '{}' is missing documentation", kind, name),
+                    suggestion: Some(format!("Add a '///' comment above the {}", kind.to_lowercase())),
+                });
+            }
+        };
+
+        // Public declarations make up the API, and `main` is its entry point
+        let public = |visibility: &ast::Visibility| *visibility == ast::Visibility::Public;
         for item in &program.items {
             match item {
-                ast::Item::Function(func) => {
-                    // TODO: Check if function has documentation comment
-                    if func.name != "main" { // Skip main function
-                        issues.push(LintIssue {
-                            file: file_path.to_path_buf(),
-                            line: 1,
-                            column: 1,
-                            severity: LintSeverity::Info,
-                            rule: "missing-documentation".to_string(),
-                            message: format!("Function '{}' is missing documentation", func.name),
-                            suggestion: Some("Add a documentation comment above the function".to_string()),
-                        });
+                ast::Item::Function(func) if public(&func.visibility) && func.name != "main" => {
+                    check("Function", &func.name, &func.doc, func.span);
+                }
+                ast::Item::Struct(struct_def) if public(&struct_def.visibility) => {
+                    check("Struct", &struct_def.name, &struct_def.doc, struct_def.span);
+                    for field in struct_def.fields.iter().filter(|field| public(&field.visibility)) {
+                        check("Field", &field.name, &field.doc, field.span);
                     }
                 }
+                ast::Item::Class(class_def) if public(&class_def.visibility) => {
+                    check("Class", &class_def.name, &class_def.doc, class_def.span);
+                    for field in class_def.fields.iter().filter(|field| public(&field.visibility)) {
+                        check("Field", &field.name, &field.doc, field.span);
+                    }
+                    for method in class_def.methods.iter().filter(|method| public(&method.visibility)) {
+                        check("Method", &method.name, &method.doc, method.span);
+                    }
+                }
+                ast::Item::Const(const_def) if public(&const_def.visibility) => {
+                    check("Constant", &const_def.name, &const_def.doc, const_def.span);
+                }
                 _ => {}
             }
         }
@@ -863,6 +1316,108 @@ impl TestRunner {
     }
 }
 
+/// Documentation generator: renders the doc comments of a Flux file's
+/// declarations as Markdown
+pub struct DocGenerator {
+    context: CliContext,
+    /// Whether private declarations are documented too
+    include_private: bool,
+}
+
+impl DocGenerator {
+    pub fn new(context: CliContext, include_private: bool) -> Self {
+        Self { context, include_private }
+    }
+
+    /// Generate documentation for a single file, titled with its name
+    pub fn document_file(&self, path: &Path) -> FluxResult<String> {
+        self.context.verbose(&format!("Documenting file: {:?}", path));
+
+        let source = fs::read_to_string(path)
+            .map_err(|e| FluxError::Io(format!("Failed to read file {:?}: {}", path, e)))?;
+        let title = path.file_stem().and_then(|s| s.to_str()).unwrap_or("module");
+
+        self.document_source(title, &source)
+    }
+
+    /// Generate documentation for source code
+    pub fn document_source(&self, title: &str, source: &str) -> FluxResult<String> {
+        let lexer = FluxLexer::new(source.to_string());
+        let mut parser = FluxParser::new(lexer)?;
+        let program = parser.parse_program()?;
+
+        let mut output = format!("# {}\n", title);
+        for item in &program.items {
+            match item {
+                ast::Item::Function(func) if self.is_documented(&func.visibility) => {
+                    let signature = format!("{}{}func {}{}", func.visibility, if func.is_async { "async " } else { "" },
+                        func.name, ast::type_params(&func.type_params));
+                    write_doc_entry(&mut output, "##", &signature_with(&signature, &func.parameters, &func.return_type), &func.doc);
+                }
+                ast::Item::Struct(struct_def) if self.is_documented(&struct_def.visibility) => {
+                    let signature = format!("{}struct {}{}", struct_def.visibility, struct_def.name, ast::type_params(&struct_def.type_params));
+                    write_doc_entry(&mut output, "##", &signature, &struct_def.doc);
+                    self.document_fields(&mut output, &struct_def.fields);
+                }
+                ast::Item::Class(class_def) if self.is_documented(&class_def.visibility) => {
+                    let signature = format!("{}class {}{}", class_def.visibility, class_def.name, ast::type_params(&class_def.type_params));
+                    write_doc_entry(&mut output, "##", &signature, &class_def.doc);
+                    self.document_fields(&mut output, &class_def.fields);
+                    for method in class_def.methods.iter().filter(|method| self.is_documented(&method.visibility)) {
+                        let signature = format!("{}{}func {}", method.visibility, if method.is_static { "static " } else { "" }, method.name);
+                        write_doc_entry(&mut output, "###", &signature_with(&signature, &method.parameters, &method.return_type), &method.doc);
+                    }
+                }
+                ast::Item::Const(const_def) if self.is_documented(&const_def.visibility) => {
+                    let signature = format!("{}const {}: {}", const_def.visibility, const_def.name, const_def.type_);
+                    write_doc_entry(&mut output, "##", &signature, &const_def.doc);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn document_fields(&self, output: &mut String, fields: &[ast::Field]) {
+        let fields: Vec<_> = fields.iter().filter(|field| self.is_documented(&field.visibility)).collect();
+        if fields.is_empty() {
+            return;
+        }
+
+        output.push_str("\nFields:\n\n");
+        for field in fields {
+            output.push_str(&format!("- `{}`", field));
+            if let Some(doc) = &field.doc {
+                output.push_str(&format!(": {}", doc.replace('\n', " ")));
+            }
+            output.push('\n');
+        }
+    }
+
+    fn is_documented(&self, visibility: &ast::Visibility) -> bool {
+        self.include_private || *visibility == ast::Visibility::Public
+    }
+}
+
+/// Append a `(params) -> type` list to the start of a function signature
+fn signature_with(start: &str, parameters: &[ast::Parameter], return_type: &Option<ast::Type>) -> String {
+    let parameters: Vec<String> = parameters.iter().map(|param| param.to_string()).collect();
+    let mut signature = format!("{}({})", start, parameters.join(", "));
+    if let Some(return_type) = return_type {
+        signature.push_str(&format!(" -> {}", return_type));
+    }
+    signature
+}
+
+/// Append a heading holding a declaration's signature, then its doc text
+fn write_doc_entry(output: &mut String, heading: &str, signature: &str, doc: &Option<String>) {
+    output.push_str(&format!("\n{} `{}`\n", heading, signature));
+    if let Some(doc) = doc {
+        output.push_str(&format!("\n{}\n", doc));
+    }
+}
+
 // Helper functions for naming conventions
 fn is_snake_case(s: &str) -> bool {
     s.chars().all(|c| c.is_lowercase() || c.is_numeric() || c == '_')
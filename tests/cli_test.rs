@@ -5,8 +5,9 @@
 use std::fs;
 use std::path::Path;
 use tempfile::TempDir;
-use flux_compiler::cli::{Cli, Commands, BuildMode, CliContext, CompilerDriver};
-use flux_compiler::tools::{Formatter, Linter, TestRunner};
+use flux_compiler::cli::{Cli, Commands, BuildMode, CliContext, CompilerDriver, CompilationTarget, EmitKind};
+use flux_compiler::codegen::bytecode::BytecodeProgram;
+use flux_compiler::tools::{DocGenerator, Formatter, Linter, LinterConfig, TestRunner};
 use flux_compiler::package::{Project, ProjectInstance};
 
 /// Create a temporary Flux project for testing
@@ -74,6 +75,7 @@ fn test_build_command() {
         output: None,
         optimize: false,
         check: false,
+        emit: None,
         progress: false,
         path: project_path,
     };
@@ -95,6 +97,7 @@ fn test_check_command() {
         output: None,
         optimize: false,
         check: true,
+        emit: None,
         progress: false,
         path: project_path,
     };
@@ -128,13 +131,16 @@ fn test_linter() {
     // Test linting a simple Flux source
     let source = r#"func main() {
     println("Hello, World!");
+}
+
+func printGreeting() {
 }"#;
     
     let issues = linter.lint_source(Path::new("test.flux"), source);
     assert!(issues.is_ok(), "Linting failed: {:?}", issues);
     
     let issues = issues.unwrap();
-    // Should have some issues (like missing documentation)
+    // Should have some issues (like naming convention violations)
     assert!(!issues.is_empty(), "Expected some lint issues");
 }
 
@@ -165,6 +171,7 @@ fn test_build_modes() {
         output: None,
         optimize: false,
         check: false,
+        emit: None,
         progress: false,
         path: project_path.clone(),
     };
@@ -177,12 +184,46 @@ fn test_build_modes() {
         output: None,
         optimize: true,
         check: false,
+        emit: None,
         progress: false,
         path: project_path.clone(),
     };
     assert!(driver.build(&release_command).is_ok());
 }
 
+#[test]
+fn test_bytecode_target() {
+    let (_temp_dir, project_path) = create_test_project();
+    let context = CliContext::new(false, true);
+    let driver = CompilerDriver::new(context);
+
+    let build_command = Commands::Build {
+        mode: BuildMode::Debug,
+        target: Some(CompilationTarget::Bytecode),
+        output: None,
+        optimize: false,
+        check: false,
+        emit: Some(EmitKind::Bytecode),
+        progress: false,
+        path: project_path.clone(),
+    };
+    let result = driver.build(&build_command);
+    assert!(result.is_ok(), "Bytecode build failed: {:?}", result);
+
+    let bytes = fs::read(project_path.join("target/debug/test-project.fbc")).expect("Missing bytecode output");
+    let program = BytecodeProgram::decode(&bytes).expect("Invalid bytecode output");
+    assert!(program.to_string().contains("main (arity 0"));
+
+    let run_command = Commands::Run {
+        mode: BuildMode::Debug,
+        bytecode: true,
+        args: vec![],
+        path: project_path,
+    };
+    let result = driver.run(&run_command);
+    assert!(result.is_ok(), "Bytecode run failed: {:?}", result);
+}
+
 #[test]
 fn test_progress_reporting() {
     let context = CliContext::new(true, false); // verbose mode
@@ -252,6 +293,7 @@ fn test_compilation_phases() {
         output: None,
         optimize: false,
         check: false,
+        emit: None,
         progress: true, // Enable progress to test phases
         path: project_path,
     };
@@ -259,4 +301,142 @@ fn test_compilation_phases() {
     // This should go through all compilation phases
     let result = driver.build(&build_command);
     assert!(result.is_ok(), "Compilation phases failed: {:?}", result);
-}
\ No newline at end of file
+}
+#[test]
+fn test_build_reports_all_syntax_errors() {
+    let (_temp_dir, project_path) = create_test_project();
+    let source = r#"func main() {
+    let x = ;
+    println("still parsed");
+}
+
+func broken(a: ) { }
+
+struct Point { x: }
+"#;
+    fs::write(project_path.join("src").join("main.flux"), source).expect("Failed to write main.flux");
+
+    let project = ProjectInstance::load(&project_path).expect("Failed to load project");
+    let errors = project.syntax_errors().expect("Failed to read sources");
+    assert_eq!(errors.iter().map(|(_, e)| e.span.start.line).collect::<Vec<_>>(), vec![2, 6, 8]);
+    assert!(errors.iter().all(|(file, _)| file.ends_with("main.flux")));
+
+    let context = CliContext::new(false, true);
+    let driver = CompilerDriver::new(context);
+    let build_command = Commands::Build {
+        mode: BuildMode::Debug,
+        target: None,
+        output: None,
+        optimize: false,
+        check: false,
+        emit: None,
+        progress: false,
+        path: project_path,
+    };
+    let error = driver.build(&build_command).unwrap_err();
+    assert!(error.to_string().contains("3 syntax errors"), "{}", error);
+}
+
+#[test]
+fn test_linter_reports_all_syntax_errors() {
+    let context = CliContext::new(false, true);
+    let linter = Linter::new(context);
+
+    let source = "func first() { let = 1 }\nfunc second(x) { }\nfunc BadName() { }\n";
+    let issues = linter.lint_source(Path::new("test.flux"), source).expect("Linting failed");
+
+    let syntax_lines: Vec<_> = issues.iter()
+        .filter(|issue| issue.rule == "syntax-error")
+        .map(|issue| issue.line)
+        .collect();
+    assert_eq!(syntax_lines, vec![1, 2]);
+
+    // Declarations that parsed are still linted
+    assert!(issues.iter().any(|issue| issue.rule == "naming-convention" && issue.message.contains("BadName")));
+}
+
+#[test]
+fn test_semantic_warnings_are_reported() {
+    let source = r#"func main() {
+    return
+    println("never printed")
+}
+"#;
+
+    let context = CliContext::new(false, true);
+    let issues = Linter::new(context).lint_source(Path::new("test.flux"), source).expect("Linting failed");
+    let unreachable: Vec<_> = issues.iter().filter(|issue| issue.rule == "unreachable-code").collect();
+    assert_eq!(unreachable.len(), 1);
+    assert_eq!((unreachable[0].line, unreachable[0].column), (3, 5));
+
+    // Builds report the warnings of the project and still succeed
+    let (_temp_dir, project_path) = create_test_project();
+    fs::write(project_path.join("src").join("main.flux"), source).expect("Failed to write main.flux");
+    let project = ProjectInstance::load(&project_path).expect("Failed to load project");
+    let analyzed = project.analyze().expect("Failed to analyze project");
+    let warnings = analyzed.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].0.ends_with("main.flux"));
+    assert_eq!(warnings[0].1.span.start.line, 3);
+
+    let driver = CompilerDriver::new(CliContext::new(false, true));
+    let build_command = Commands::Build {
+        mode: BuildMode::Debug,
+        target: None,
+        output: None,
+        optimize: false,
+        check: false,
+        emit: None,
+        progress: false,
+        path: project_path,
+    };
+    assert!(driver.build(&build_command).is_ok());
+}
+
+#[test]
+fn test_doc_comments_in_tools() {
+    let source = r#"/// A point
+pub struct Point {
+    /// Horizontal
+    pub x: float,
+    pub y: float,
+}
+
+/// Add two numbers
+pub func add(a: int, b: int) -> int {
+    return a + b;
+}
+
+pub func sub(a: int, b: int) -> int {
+    return a - b;
+}
+
+func helper() {
+}
+"#;
+
+    // Only undocumented public declarations are reported
+    let config = LinterConfig { require_documentation: true, ..LinterConfig::default() };
+    let linter = Linter::with_config(CliContext::new(false, true), config);
+    let issues = linter.lint_source(Path::new("test.flux"), source).expect("Linting failed");
+    let missing: Vec<_> = issues.iter()
+        .filter(|issue| issue.rule == "missing-documentation")
+        .map(|issue| issue.message.as_str())
+        .collect();
+    assert_eq!(missing, vec!["Field 'y' is missing documentation", "Function 'sub' is missing documentation"]);
+
+    // Formatting keeps doc comments
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).expect("Formatting failed");
+    assert!(formatted.contains("/// A point\npub struct Point {\n    /// Horizontal\n    pub x: float,"), "{}", formatted);
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+
+    let docs = DocGenerator::new(CliContext::new(false, true), false)
+        .document_source("geometry", source)
+        .expect("Documentation failed");
+    assert!(docs.starts_with("# geometry\n"), "{}", docs);
+    assert!(docs.contains("## `pub struct Point`\n\nA point\n"), "{}", docs);
+    assert!(docs.contains("- `pub x: float`: Horizontal\n"), "{}", docs);
+    assert!(docs.contains("## `pub func add(a: int, b: int) -> int`\n\nAdd two numbers\n"), "{}", docs);
+    assert!(!docs.contains("helper"), "{}", docs);
+}
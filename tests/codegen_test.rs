@@ -11,6 +11,7 @@ fn test_stub_code_generator() {
     // Create a simple typed program
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -24,6 +25,7 @@ fn test_stub_code_generator() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -46,6 +48,7 @@ fn test_basic_arithmetic_codegen() {
     // Create a function that adds two numbers: func add() -> int { return 1 + 2 }
     let typed_func = TypedFunction {
         name: "add".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -69,6 +72,7 @@ fn test_basic_arithmetic_codegen() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -92,6 +96,7 @@ fn test_function_with_parameters() {
     // Create a function with parameters: func multiply(a: int, b: int) -> int { return a * b }
     let typed_func = TypedFunction {
         name: "multiply".to_string(),
+        type_params: vec![],
         parameters: vec![
             TypedParameter {
                 name: "a".to_string(),
@@ -126,6 +131,7 @@ fn test_function_with_parameters() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -148,6 +154,7 @@ fn test_variable_assignment() {
     // Create a function with variable assignment: func test() -> int { let x = 10; return x }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -169,6 +176,7 @@ fn test_variable_assignment() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -191,6 +199,7 @@ fn test_comparison_operations() {
     // Create a function with comparison: func test() -> bool { return 5 > 3 }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Bool,
         body: TypedBlock {
@@ -214,6 +223,7 @@ fn test_comparison_operations() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -236,6 +246,7 @@ fn test_logical_operations() {
     // Create a function with logical operations: func test() -> bool { return true && false }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Bool,
         body: TypedBlock {
@@ -259,6 +270,7 @@ fn test_logical_operations() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -281,6 +293,7 @@ fn test_unary_operations() {
     // Create a function with unary operations: func test() -> int { return -42 }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -300,6 +313,7 @@ fn test_unary_operations() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -322,6 +336,7 @@ fn test_if_statement() {
     // Create a function with if statement: func test() -> int { if true { return 1 } else { return 0 } }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -355,6 +370,7 @@ fn test_if_statement() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -377,6 +393,7 @@ fn test_while_loop() {
     // Create a function with while loop: func test() -> int { let x = 0; while x < 10 { x = x + 1 } return x }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -439,6 +456,7 @@ fn test_while_loop() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -461,6 +479,7 @@ fn test_for_loop() {
     // Create a function with for loop: func test() -> int { for i in 5 { } return 0 }
     let typed_func = TypedFunction {
         name: "test".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -485,6 +504,7 @@ fn test_for_loop() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -507,6 +527,7 @@ fn test_function_call() {
     // Create two functions where one calls the other
     let add_func = TypedFunction {
         name: "add".to_string(),
+        type_params: vec![],
         parameters: vec![
             TypedParameter {
                 name: "a".to_string(),
@@ -541,10 +562,12 @@ fn test_function_call() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let main_func = TypedFunction {
         name: "main".to_string(),
+        type_params: vec![],
         parameters: vec![],
         return_type: Type::Int,
         body: TypedBlock {
@@ -573,6 +596,7 @@ fn test_function_call() {
         },
         is_async: false,
         visibility: Visibility::Public,
+        span: None,
     };
     
     let typed_program = TypedProgram {
@@ -609,6 +633,7 @@ mod llvm_tests {
         // Create a simple struct: struct Point { x: int, y: int }
         let struct_def = TypedStruct {
             name: "Point".to_string(),
+            type_params: vec![],
             fields: vec![
                 TypedField {
                     name: "x".to_string(),
@@ -645,6 +670,7 @@ mod llvm_tests {
         // Create a struct with mutable and immutable fields
         let struct_def = TypedStruct {
             name: "Person".to_string(),
+            type_params: vec![],
             fields: vec![
                 TypedField {
                     name: "name".to_string(),
@@ -682,6 +708,7 @@ mod llvm_tests {
         // Create a simple class
         let class_def = TypedClass {
             name: "Rectangle".to_string(),
+            type_params: vec![],
             fields: vec![
                 TypedField {
                     name: "width".to_string(),
@@ -719,6 +746,7 @@ mod llvm_tests {
         
         let class_def = TypedClass {
             name: "Calculator".to_string(),
+            type_params: vec![],
             fields: vec![],
             methods: vec![
                 TypedMethod {
@@ -757,6 +785,7 @@ mod llvm_tests {
                     },
                     visibility: Visibility::Public,
                     is_static: false,
+                    span: None,
                 }
             ],
             visibility: Visibility::Public,
@@ -780,6 +809,7 @@ mod llvm_tests {
         
         let class_def = TypedClass {
             name: "Math".to_string(),
+            type_params: vec![],
             fields: vec![],
             methods: vec![
                 TypedMethod {
@@ -813,6 +843,7 @@ mod llvm_tests {
                     },
                     visibility: Visibility::Public,
                     is_static: true,
+                    span: None,
                 }
             ],
             visibility: Visibility::Public,
@@ -860,6 +891,7 @@ mod llvm_tests {
         // Test a complete struct with constructor and methods
         let struct_def = TypedStruct {
             name: "Vector2D".to_string(),
+            type_params: vec![],
             fields: vec![
                 TypedField {
                     name: "x".to_string(),
@@ -939,6 +971,7 @@ mod llvm_tests {
         // Create a program with struct, class, const, and function
         let struct_def = TypedStruct {
             name: "Point".to_string(),
+            type_params: vec![],
             fields: vec![
                 TypedField {
                     name: "x".to_string(),
@@ -958,6 +991,7 @@ mod llvm_tests {
         
         let class_def = TypedClass {
             name: "Shape".to_string(),
+            type_params: vec![],
             fields: vec![
                 TypedField {
                     name: "area".to_string(),
@@ -982,6 +1016,7 @@ mod llvm_tests {
                     },
                     visibility: Visibility::Public,
                     is_static: false,
+                    span: None,
                 }
             ],
             visibility: Visibility::Public,
@@ -999,6 +1034,7 @@ mod llvm_tests {
         
         let func_def = TypedFunction {
             name: "main".to_string(),
+            type_params: vec![],
             parameters: vec![],
             return_type: Type::Int,
             body: TypedBlock {
@@ -1012,6 +1048,7 @@ mod llvm_tests {
             },
             is_async: false,
             visibility: Visibility::Public,
+            span: None,
         };
         
         let typed_program = TypedProgram {
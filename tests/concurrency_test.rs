@@ -261,6 +261,29 @@ fn test_scheduler_stats_display() {
     assert!(display_str.contains("Executed: 10"));
 }
 
+#[test]
+fn test_hosted_goroutines() {
+    // Hosted goroutines take turns through `next_ready` without worker threads
+    let scheduler = Scheduler::with_worker_count(0);
+    let first = scheduler.add_hosted();
+    let second = scheduler.add_hosted();
+    
+    assert_eq!(scheduler.next_ready(), Some(first));
+    scheduler.yield_now();
+    assert_eq!(scheduler.next_ready(), Some(second));
+    scheduler.finish_current();
+    assert_eq!(scheduler.next_ready(), Some(first));
+    assert_eq!(scheduler.stats().running_count, 1);
+    scheduler.finish_current();
+    assert_eq!(scheduler.next_ready(), None);
+    
+    let stats = scheduler.stats();
+    assert_eq!(stats.total_goroutines, 2);
+    assert_eq!(stats.finished_count, 2);
+    assert_eq!((stats.ready_count, stats.running_count), (0, 0));
+    assert_eq!(scheduler.active_goroutine_count(), 0);
+}
+
 #[test]
 fn test_goroutine_handle() {
     let handle = GoroutineHandle { id: 42 };
@@ -6,6 +6,7 @@ use flux_compiler::ffi::safety::*;
 use flux_compiler::ffi::c_types::*;
 use flux_compiler::ffi::error::*;
 use flux_compiler::parser::ast::{Type as FluxType, ExternFunction as ASTExternFunction, Parameter, Visibility};
+use flux_compiler::position::{Position, Span};
 
 #[test]
 fn test_ffi_registry_basic() {
@@ -17,11 +18,13 @@ fn test_ffi_registry_basic() {
             name: "str".to_string(),
             type_: FluxType::String,
             is_mutable: false,
+            span: Span::single(Position::start()),
         }],
         return_type: Some(FluxType::Int),
         library: Some("C".to_string()),
         is_variadic: false,
         visibility: Visibility::Public,
+        span: Span::single(Position::start()),
     };
     
     // Convert to FFI representation
@@ -5,6 +5,25 @@ use flux_compiler::lexer::{FluxLexer, Token};
 use flux_compiler::parser::{FluxParser, Parser};
 use flux_compiler::semantic::{FluxSemanticAnalyzer, SemanticAnalyzer};
 use flux_compiler::position::Position;
+use flux_compiler::error::SemanticError;
+use flux_compiler::parser::ast::Program;
+use flux_compiler::semantic::TypedProgram;
+
+/// Parse a program that is known to be syntactically valid
+fn parse(source: &str) -> Program {
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    parser.parse_program().unwrap()
+}
+
+/// Analyze a program, stopping at its first semantic error
+fn analyze(source: &str) -> Result<TypedProgram, SemanticError> {
+    FluxSemanticAnalyzer::new().analyze(parse(source))
+}
+
+/// Every semantic error found analyzing a program
+fn analyze_errors(source: &str) -> Vec<SemanticError> {
+    FluxSemanticAnalyzer::new().analyze_with_errors(parse(source)).1
+}
 
 #[test]
 fn test_basic_lexer() {
@@ -91,6 +110,7 @@ mod llvm_tests {
         // Create a simple function: func add(a: int, b: int) -> int { return a + b }
         let typed_func = TypedFunction {
             name: "add".to_string(),
+            type_params: vec![],
             parameters: vec![
                 TypedParameter {
                     name: "a".to_string(),
@@ -125,6 +145,7 @@ mod llvm_tests {
             },
             is_async: false,
             visibility: Visibility::Public,
+            span: None,
         };
         
         let typed_program = TypedProgram {
@@ -150,6 +171,7 @@ mod llvm_tests {
         // Create a simple function that returns a literal
         let typed_func = TypedFunction {
             name: "get_answer".to_string(),
+            type_params: vec![],
             parameters: vec![],
             return_type: Type::Int,
             body: TypedBlock {
@@ -163,6 +185,7 @@ mod llvm_tests {
             },
             is_async: false,
             visibility: Visibility::Public,
+            span: None,
         };
         
         let typed_program = TypedProgram {
@@ -187,6 +210,7 @@ mod llvm_tests {
         // Create a function with variable assignment: func test() { let x = 10; x = 20; return x }
         let typed_func = TypedFunction {
             name: "test".to_string(),
+            type_params: vec![],
             parameters: vec![],
             return_type: Type::Int,
             body: TypedBlock {
@@ -218,6 +242,7 @@ mod llvm_tests {
             },
             is_async: false,
             visibility: Visibility::Public,
+            span: None,
         };
         
         let typed_program = TypedProgram {
@@ -243,6 +268,7 @@ mod llvm_tests {
         // Create a function that returns a boolean comparison: func test() -> bool { return 5 < 10 }
         let typed_func = TypedFunction {
             name: "test".to_string(),
+            type_params: vec![],
             parameters: vec![],
             return_type: Type::Bool,
             body: TypedBlock {
@@ -266,6 +292,7 @@ mod llvm_tests {
             },
             is_async: false,
             visibility: Visibility::Public,
+            span: None,
         };
         
         let typed_program = TypedProgram {
@@ -290,6 +317,7 @@ mod llvm_tests {
         // Create a function with float arithmetic: func test() -> float { return 3.14 + 2.86 }
         let typed_func = TypedFunction {
             name: "test".to_string(),
+            type_params: vec![],
             parameters: vec![],
             return_type: Type::Float,
             body: TypedBlock {
@@ -313,6 +341,7 @@ mod llvm_tests {
             },
             is_async: false,
             visibility: Visibility::Public,
+            span: None,
         };
         
         let typed_program = TypedProgram {
@@ -328,4 +357,1543 @@ mod llvm_tests {
         assert!(ir.contains("fadd"));
         assert!(ir.contains("ret double"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_range_loop_ending_at_max() {
+        use inkwell::execution_engine::JitFunction;
+        use inkwell::memory_buffer::MemoryBuffer;
+        use inkwell::OptimizationLevel;
+
+        // Counting stops at the end rather than wrapping around past it
+        let source = r#"
+func main(n: int, k: int) -> int {
+    let count = 0;
+    for i in n - 2..=n { count = count + 1; }
+    for i in n - 7..=n step k { count = count + 1; }
+    for i in -n..=-n - 1 step -1 { count = count + 1; }
+    return count;
+}
+"#;
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+        let context = Context::create();
+        let ir = LLVMCodeGenerator::new(&context, "ranges").generate(program).unwrap();
+
+        let buffer = MemoryBuffer::create_from_memory_range_copy(ir.as_bytes(), "ranges");
+        let module = context.create_module_from_ir(buffer).unwrap();
+        let engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+        let main: JitFunction<unsafe extern "C" fn(i64, i64) -> i64> = unsafe { engine.get_function("main") }.unwrap();
+        assert_eq!(unsafe { main.call(i64::MAX, 5) }, 3 + 2 + 2);
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        use inkwell::execution_engine::JitFunction;
+        use inkwell::memory_buffer::MemoryBuffer;
+        use inkwell::OptimizationLevel;
+        use std::ffi::{c_char, CStr};
+
+        let source = r#"
+func main(n: int, flag: bool) -> string {
+    let name = "flux";
+    return "{name}: {n}, {flag} and 100%";
+}
+"#;
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+        let context = Context::create();
+        let ir = LLVMCodeGenerator::new(&context, "interpolation").generate(program).unwrap();
+
+        let buffer = MemoryBuffer::create_from_memory_range_copy(ir.as_bytes(), "interpolation");
+        let module = context.create_module_from_ir(buffer).unwrap();
+        let engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+        let main: JitFunction<unsafe extern "C" fn(i64, bool) -> *const c_char> = unsafe { engine.get_function("main") }.unwrap();
+        let text = unsafe { CStr::from_ptr(main.call(-42, true)) };
+        assert_eq!(text.to_str().unwrap(), "flux: -42, true and 100%");
+    }
+}
+#[test]
+fn test_module_graph_from_disk() {
+    use flux_compiler::semantic::ModuleGraph;
+    use ::std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("geometry")).unwrap();
+    fs::write(dir.path().join("main.flux"), r#"
+import "math"
+import "geometry"
+
+func main() -> int {
+    return geometry::double(math::square(3))
+}
+"#).unwrap();
+    fs::write(dir.path().join("math.flux"), r#"
+package math
+
+pub func square(x: int) -> int { return x * x }
+"#).unwrap();
+    // Directory modules merge every file in the directory
+    fs::write(dir.path().join("geometry").join("a.flux"), r#"
+package geometry
+
+pub func double(x: int) -> int { return twice(x) }
+"#).unwrap();
+    fs::write(dir.path().join("geometry").join("b.flux"), r#"
+package geometry
+
+func twice(x: int) -> int { return x + x }
+"#).unwrap();
+
+    let graph = ModuleGraph::load(dir.path().join("main.flux")).unwrap();
+    assert_eq!(graph.len(), 3);
+
+    let typed = graph.analyze().unwrap();
+    assert_eq!(typed.last().unwrap().package, "main");
+}
+
+#[test]
+fn test_class_formatter_round_trip() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let source = r#"
+pub class Counter {
+    pub mut count: int
+    step: int,
+
+    pub static func zero() -> int {
+        return 0;
+    }
+
+    func next(by: int) -> int {
+        return self.count + self.step * by;
+    }
+
+    func label() -> string {
+        return "count: \"n\"";
+    }
+}
+"#;
+    let parse = |source: &str| {
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        parser.parse_program().unwrap()
+    };
+
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).unwrap();
+    assert!(formatted.contains("pub static func zero() -> int {"), "{}", formatted);
+
+    // Formatting preserves the tree and is stable
+    assert_eq!(parse(&formatted), parse(source));
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_class_semantic_analysis() {
+    let typed = analyze(r#"
+class Counter {
+    count: int
+
+    static func start() -> int { return 1 }
+    func get() -> int { return self.count }
+    func offset(by: int) -> int { return self.get() + by }
+}
+
+func main() -> int {
+    return Counter::start()
+}
+"#).unwrap();
+    assert_eq!(typed.items.len(), 2);
+
+    // Static methods have no receiver
+    assert!(analyze("class A { x: int\n static func f() -> int { return self.x } }").is_err());
+    // Instance methods are not reachable through the class name
+    assert!(analyze("class A { func f() { }\n }\nfunc main() { A::f() }").is_err());
+    // Unknown members are rejected
+    assert!(analyze("class A { x: int\n func f() -> int { return self.y } }").is_err());
+}
+
+#[test]
+fn test_enum_semantic_analysis() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let shape = r#"
+enum Shape {
+    Circle(float),
+    Rect { w: float, h: float },
+    Empty,
+}
+"#;
+
+    let typed = analyze(&format!("{}{}", shape, r#"
+func area(s: Shape) -> float {
+    match s {
+        Circle(r) => { return r * r; }
+        Shape::Rect { w, h } => { return w * h; }
+        Shape::Empty => { return 0.0; }
+    }
+    return 0.0;
+}
+
+func main() -> float {
+    let big = Shape::Rect { h: 2.0, w: 3.0 };
+    return area(Shape::Circle(1.5));
+}
+"#)).unwrap();
+    assert_eq!(typed.items.len(), 3);
+
+    // A missing variant is reported by name
+    let error = analyze(&format!("{}{}", shape, r#"
+func area(s: Shape) -> float {
+    match s {
+        Shape::Circle(r) => { return r; }
+        Shape::Empty => { return 0.0; }
+    }
+    return 0.0;
+}
+"#)).unwrap_err();
+    match error.kind {
+        SemanticErrorKind::NonExhaustiveMatch { missing } => {
+            assert_eq!(missing, vec!["Shape::Rect { w: _, h: _ }".to_string()]);
+        }
+        other => panic!("expected non-exhaustive match, got {:?}", other),
+    }
+
+    // Guarded arms do not count towards exhaustiveness
+    assert!(analyze(&format!("{}{}", shape, r#"
+func f(s: Shape) {
+    match s {
+        Shape::Circle(r) if r > 1.0 => { }
+        Shape::Rect { w, h } => { }
+        Shape::Empty => { }
+    }
+}
+"#)).is_err());
+
+    // Payloads are type checked
+    assert!(analyze(&format!("{}func f() {{ let s = Shape::Circle(\"big\"); }}", shape)).is_err());
+    assert!(analyze(&format!("{}func f() {{ let s = Shape::Rect {{ w: 1.0 }}; }}", shape)).is_err());
+    assert!(analyze(&format!("{}func f(s: Shape) {{ match s {{ Shape::Circle(a, b) => {{ }}\n _ => {{ }} }} }}", shape)).is_err());
+}
+
+#[test]
+fn test_enum_formatter_round_trip() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let source = "pub enum Shape { Circle(float), Rect { w: float, h: float }, Empty }\n";
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).unwrap();
+    assert_eq!(formatted, "package main;\n\npub enum Shape {\n    Circle(float),\n    Rect { w: float, h: float },\n    Empty,\n}\n");
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_interface_conformance() {
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::semantic::{TypedExpressionKind, TypedItem, TypedStatementKind};
+
+    let shapes = r#"
+interface Shape {
+    func area() -> float;
+    func grow(by: float) -> float;
+}
+
+struct Square { side: float }
+"#;
+
+    let typed = analyze(&format!("{}{}", shapes, r#"
+impl Shape for Square {
+    func grow(by: float) -> float { return self.side + by; }
+    func area() -> float { return self.side * self.side; }
+}
+
+func total(s: Shape) -> float {
+    return s.area() + s.grow(1.0);
+}
+
+func main(square: Square) -> float {
+    let direct = square.area();
+    return total(square);
+}
+"#)).unwrap();
+
+    // Impl methods are ordered by vtable slot, and passing a Square where a
+    // Shape is expected is an explicit upcast
+    let impl_def = typed.items.iter().find_map(|item| match item {
+        TypedItem::Impl(impl_def) => Some(impl_def),
+        _ => None,
+    }).unwrap();
+    assert_eq!(impl_def.methods.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["area", "grow"]);
+    let main = typed.items.iter().find_map(|item| match item {
+        TypedItem::Function(func) if func.name == "main" => Some(func),
+        _ => None,
+    }).unwrap();
+    match &main.body.statements[1].kind {
+        TypedStatementKind::Return(Some(call)) => match &call.kind {
+            TypedExpressionKind::Call(_, args) => {
+                assert!(matches!(args[0].kind, TypedExpressionKind::Upcast(_)));
+                assert_eq!(args[0].type_, parser::ast::Type::Named("Shape".to_string()));
+            }
+            other => panic!("expected call, got {:?}", other),
+        },
+        other => panic!("expected return, got {:?}", other),
+    }
+
+    let conformance_error = |impl_source: &str| match analyze(&format!("{}{}", shapes, impl_source)) {
+        Err(error) => match error.kind {
+            SemanticErrorKind::InterfaceNotImplemented { reason, .. } => reason,
+            other => panic!("expected conformance error, got {:?}", other),
+        },
+        Ok(_) => panic!("expected conformance error"),
+    };
+    assert_eq!(
+        conformance_error("impl Shape for Square { func area() -> float { return 1.0; } }"),
+        "missing method 'grow'"
+    );
+    assert_eq!(
+        conformance_error("impl Shape for Square { func area() -> int { return 1; }\n func grow(by: float) -> float { return by; } }"),
+        "method 'area' should have signature 'func area() -> float'"
+    );
+    assert!(conformance_error(
+        "impl Shape for Square { func area() -> float { return 1.0; }\n func grow(by: float) -> float { return by; }\n func extra() { } }"
+    ).contains("not declared by the interface"));
+
+    // Types without an impl are not Shapes
+    assert!(analyze(&format!("{}func total(s: Shape) -> float {{ return 0.0; }}\nfunc f(q: Square) -> float {{ return total(q); }}", shapes)).is_err());
+    // Unknown interfaces and duplicate impls are rejected
+    assert!(analyze(&format!("{}impl Drawable for Square {{ }}", shapes)).is_err());
+    let full_impl = "impl Shape for Square { func area() -> float { return 1.0; }\n func grow(by: float) -> float { return by; } }\n";
+    assert!(analyze(&format!("{}{}{}", shapes, full_impl, full_impl)).is_err());
+}
+
+#[test]
+fn test_interface_formatter_round_trip() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let source = "pub interface Shape { func area() -> float; func grow(by: float) }\nimpl Shape for Square { func area() -> float { return 1.0; } func grow(by: float) { } }\n";
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).unwrap();
+    assert!(formatted.contains("pub interface Shape {\n    func area() -> float;\n    func grow(by: float);\n}\n"), "{}", formatted);
+    assert!(formatted.contains("impl Shape for Square {\n    func area() -> float {"), "{}", formatted);
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_generic_instantiation() {
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::parser::ast::Type;
+    use flux_compiler::semantic::{TypedExpressionKind, TypedItem, TypedStatementKind};
+
+    let typed = analyze(r#"
+func identity<T>(x: T) -> T { return x; }
+func apply<T, U>(x: T, f: (T) -> U) -> U { return f(x); }
+func describe(n: int) -> string { return "n"; }
+struct Box<T> { value: T }
+
+func main() -> string {
+    let n = identity(42);
+    let b = Box { value: n };
+    let v: int = b.value;
+    return apply(v, describe);
+}
+"#).unwrap();
+
+    let main = typed.items.iter().find_map(|item| match item {
+        TypedItem::Function(func) if func.name == "main" => Some(func),
+        _ => None,
+    }).unwrap();
+    let init = |index: usize| match &main.body.statements[index].kind {
+        TypedStatementKind::Let(_, _, Some(init)) => init.clone(),
+        other => panic!("expected let, got {:?}", other),
+    };
+
+    // Type arguments are inferred from the arguments and recorded on the callee
+    let n = init(0);
+    assert_eq!(n.type_, Type::Int);
+    match &n.kind {
+        TypedExpressionKind::Call(callee, _) => {
+            assert_eq!(callee.kind, TypedExpressionKind::Instantiate("identity".to_string(), vec![Type::Int]));
+        }
+        other => panic!("expected call, got {:?}", other),
+    }
+    assert_eq!(init(1).type_, Type::Generic("Box".to_string(), vec![Type::Int]));
+    match &main.body.statements[3].kind {
+        TypedStatementKind::Return(Some(call)) => match &call.kind {
+            TypedExpressionKind::Call(callee, _) => {
+                assert_eq!(callee.kind, TypedExpressionKind::Instantiate("apply".to_string(), vec![Type::Int, Type::String]));
+                assert_eq!(call.type_, Type::String);
+            }
+            other => panic!("expected call, got {:?}", other),
+        },
+        other => panic!("expected return, got {:?}", other),
+    }
+
+    // Every use of a type parameter must agree
+    match analyze("func pick<T>(a: T, b: T) -> T { return a; }\nfunc f() -> int { return pick(1, \"x\"); }") {
+        Err(error) => assert!(
+            matches!(&error.kind, SemanticErrorKind::TypeMismatch { expected, found } if expected == "int" && found == "string"),
+            "{:?}", error
+        ),
+        Ok(_) => panic!("expected type mismatch"),
+    }
+    // A type parameter that no argument determines cannot be inferred
+    match analyze("func none<T>(n: int) -> int { return n; }\nfunc f() -> int { return none(1); }") {
+        Err(error) => assert!(matches!(error.kind, SemanticErrorKind::CannotInferType), "{:?}", error),
+        Ok(_) => panic!("expected inference failure"),
+    }
+}
+
+#[test]
+fn test_generic_formatter_round_trip() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let source = "func map<T, U>(xs: List<T>, f: (T) -> U) -> List<U> { return xs; }\nstruct Pair<A, B> { first: A, second: B }\n";
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).unwrap();
+    assert!(formatted.contains("func map<T, U>(xs: List<T>, f: (T) -> U) -> List<U> {"), "{}", formatted);
+    assert!(formatted.contains("struct Pair<A, B> {\n    first: A,\n    second: B,\n}"), "{}", formatted);
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_closure_captures() {
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::parser::ast::Type;
+    use flux_compiler::semantic::{TypedCapture, TypedExpressionKind, TypedItem, TypedStatementKind};
+
+    let typed = analyze(r#"
+func counter(mut start: int, step: int) -> () -> int {
+    let bump = || { start = start + step; start };
+    return bump;
+}
+
+func add_two(x: int) -> int {
+    let add = |y: int| x + y;
+    return add(2);
+}
+"#).unwrap();
+
+    let function = |name: &str| typed.items.iter().find_map(|item| match item {
+        TypedItem::Function(func) if func.name == name => Some(func.clone()),
+        _ => None,
+    }).unwrap();
+
+    // A variable assigned anywhere in the enclosing function is shared with
+    // the closure; any other capture is copied
+    let counter = function("counter");
+    match &counter.body.statements[0].kind {
+        TypedStatementKind::Let(_, type_, Some(init)) => {
+            assert_eq!(*type_, Type::Function(vec![], Box::new(Type::Int)));
+            match &init.kind {
+                TypedExpressionKind::Closure(closure) => assert_eq!(closure.captures, vec![
+                    TypedCapture { name: "start".to_string(), type_: Type::Int, by_reference: true },
+                    TypedCapture { name: "step".to_string(), type_: Type::Int, by_reference: false },
+                ]),
+                other => panic!("expected closure, got {:?}", other),
+            }
+        }
+        other => panic!("expected let, got {:?}", other),
+    }
+
+    // Closures are called like functions
+    let add_two = function("add_two");
+    match &add_two.body.statements[1].kind {
+        TypedStatementKind::Return(Some(call)) => {
+            assert!(matches!(call.kind, TypedExpressionKind::Call(_, _)));
+            assert_eq!(call.type_, Type::Int);
+        }
+        other => panic!("expected return, got {:?}", other),
+    }
+
+    // Captured variables keep their mutability
+    match analyze("func f(n: int) { let g = || { n = 1; }; }") {
+        Err(error) => assert!(
+            matches!(&error.kind, SemanticErrorKind::AssignToImmutable { name, .. } if name == "n"),
+            "{:?}", error
+        ),
+        Ok(_) => panic!("expected assignment error"),
+    }
+    match analyze("func f() -> int { let g = |s: string| 1; return g(2); }") {
+        Err(error) => assert!(matches!(error.kind, SemanticErrorKind::TypeMismatch { .. }), "{:?}", error),
+        Ok(_) => panic!("expected type mismatch"),
+    }
+
+    // `go` spawns calls to closures as well as named functions
+    assert!(analyze("func f(n: int) { go (|| n + 1)(); }").is_ok());
+    assert!(analyze("func f(n: int) { go n; }").is_err());
+}
+
+#[test]
+fn test_closure_formatter_round_trip() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let source = "func f(x: int) -> int { let add = |y: int| x + y; return add(1); }\n";
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).unwrap();
+    assert!(formatted.contains("let add = |y: int| (x + y);"), "{}", formatted);
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_inferred_local_types() {
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::parser::ast::Type;
+    use flux_compiler::semantic::{TypedExpressionKind, TypedItem, TypedStatementKind};
+
+    let typed = analyze(r#"
+func from_later_use() {
+    let xs = [];
+    let ys: [int] = xs;
+}
+
+func from_return() -> [string] {
+    return [];
+}
+
+func count(m: Map<string, int>) -> int {
+    return 0;
+}
+
+func from_argument() -> int {
+    return count({});
+}
+
+func from_assignment() -> int {
+    let x;
+    x = 5;
+    return x;
+}
+
+func from_call() -> string {
+    let id = |x| x;
+    return id("s");
+}
+"#).unwrap();
+
+    let function = |name: &str| typed.items.iter().find_map(|item| match item {
+        TypedItem::Function(func) if func.name == name => Some(func.clone()),
+        _ => None,
+    }).unwrap();
+    let let_type = |name: &str, index: usize| match &function(name).body.statements[index].kind {
+        TypedStatementKind::Let(_, type_, _) => type_.clone(),
+        other => panic!("expected let, got {:?}", other),
+    };
+    let returned = |name: &str, index: usize| match &function(name).body.statements[index].kind {
+        TypedStatementKind::Return(Some(expr)) => expr.clone(),
+        other => panic!("expected return, got {:?}", other),
+    };
+
+    // Empty literals and unannotated bindings take their types from later uses
+    assert_eq!(let_type("from_later_use", 0), Type::Array(Box::new(Type::Int)));
+    assert_eq!(returned("from_return", 0).type_, Type::Array(Box::new(Type::String)));
+    match &returned("from_argument", 0).kind {
+        TypedExpressionKind::Call(_, args) => assert_eq!(
+            args[0].type_,
+            Type::Map(Box::new(Type::String), Box::new(Type::Int))
+        ),
+        other => panic!("expected call, got {:?}", other),
+    }
+    assert_eq!(let_type("from_assignment", 0), Type::Int);
+
+    // Lambda parameters are inferred from how the lambda is called
+    assert_eq!(
+        let_type("from_call", 0),
+        Type::Function(vec![Type::String], Box::new(Type::String))
+    );
+
+    // Nothing constrains these types
+    for source in ["func f() { let xs = []; }", "func f() { let g = |x| x; }", "func f() { let x; }"] {
+        match analyze(source) {
+            Err(error) => assert!(matches!(error.kind, SemanticErrorKind::CannotInferType), "{:?}", error),
+            Ok(_) => panic!("expected an inference error for {}", source),
+        }
+    }
+}
+
+#[test]
+fn test_inferred_lambda_formatter_round_trip() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let source = "func f() -> int { let m = {}; let inc = |x| x + 1; return inc(1); }\n";
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let formatted = formatter.format_source(source).unwrap();
+    assert!(formatted.contains("let inc = |x| (x + 1);"), "{}", formatted);
+    assert!(formatted.contains("let m = {};"), "{}", formatted);
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_control_flow_and_result_typing() {
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::parser::ast::Type;
+    use flux_compiler::semantic::{TypedExpressionKind, TypedItem, TypedStatementKind};
+
+    let typed = analyze(r#"
+func parse(n: int) -> Result<int, string> {
+    if n < 0 {
+        return Err("negative");
+    }
+    return Ok(n);
+}
+
+func sum(xs: [int]) -> Result<int, string> {
+    let total = 0;
+    for x in xs {
+        if x == 0 { continue; }
+        total = total + parse(x)?;
+    }
+    while total > 100 && total % 2 == 0 {
+        break;
+    }
+    println("sum: " + "done");
+    return Ok(total);
+}
+"#).unwrap();
+
+    // `Ok` and `Err` take the function's result type and build `Result` variants
+    let parse = match &typed.items[0] {
+        TypedItem::Function(func) => func,
+        other => panic!("expected function, got {:?}", other),
+    };
+    match &parse.body.statements[1].kind {
+        TypedStatementKind::Return(Some(expr)) => {
+            assert!(matches!(&expr.kind, TypedExpressionKind::Variant(name, variant, _) if name == "Result" && variant == "Ok"));
+            assert_eq!(expr.type_, Type::Result(Box::new(Type::Int), Box::new(Type::String)));
+        }
+        other => panic!("expected return, got {:?}", other),
+    }
+
+    assert!(analyze("func f(n: int) -> int { return n?; }").is_err());
+    assert!(analyze("func f() { if 1 { } }").is_err());
+    assert!(analyze("func f() { for x in 5 { } }").is_err());
+    assert!(analyze("func f() -> bool { return !1; }").is_err());
+
+    // Returned values and the value a body ends in must fit the declared type
+    let mismatches = |source: &str| -> Vec<(usize, String, String)> {
+        analyze_errors(source).into_iter()
+            .map(|e| match e.kind {
+                SemanticErrorKind::TypeMismatch { expected, found } => (e.span.start.line, expected, found),
+                other => panic!("expected type mismatch, got {:?}", other),
+            })
+            .collect()
+    };
+    assert_eq!(mismatches(r#"
+func g() -> int { return "a"; }
+func f() -> int { "s" }
+func h() -> Result<int, string> { return 5; }
+func k(n: int) -> int {
+    if n > 0 { 1 } else { true }
+}
+func l() -> int { return; }
+func m() -> int {
+    let add = |a: int| -> int { "no" };
+    return add(1);
+}
+"#), vec![
+        (2, "int".to_string(), "string".to_string()),
+        (3, "int".to_string(), "string".to_string()),
+        (4, "Result<int, string>".to_string(), "int".to_string()),
+        (6, "int".to_string(), "bool".to_string()),
+        (8, "int".to_string(), "()".to_string()),
+        (10, "int".to_string(), "string".to_string()),
+    ]);
+    assert!(analyze_errors("func f() -> int {\n while true { return 1; }\n}").is_empty());
+}
+
+#[test]
+fn test_semantic_errors_are_collected() {
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::semantic::TypedItem;
+
+    // Errors in separate items and statements are all reported, in source order
+    let (typed, errors) = FluxSemanticAnalyzer::new().analyze_with_errors(parse(r#"
+func first() -> int {
+    let a: int = "text";
+    return missing;
+}
+
+func fine() -> int {
+    return 1;
+}
+
+func second() {
+    if true {
+        let b: bool = 1;
+    } else {
+        let c = 1 + "two";
+    }
+}
+"#));
+    let lines: Vec<usize> = errors.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![3, 4, 13, 15]);
+    assert!(typed.items.iter().any(|item| matches!(item, TypedItem::Function(f) if f.name == "fine")));
+
+    // A binding whose declaration failed is not reported again at its uses
+    let (_, errors) = FluxSemanticAnalyzer::new().analyze_with_errors(parse("func f() -> int {\n let x = undefined_thing;\n let y = x + 1;\n return y * x;\n}"));
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0].kind, SemanticErrorKind::UndefinedVariable { name } if name == "undefined_thing"));
+
+    // `analyze` still fails with the first error
+    let error = analyze("func f() { let a: int = true;\n let b: int = false; }").unwrap_err();
+    assert_eq!(error.span.start.line, 1);
+}
+
+#[test]
+fn test_definite_assignment() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let uninitialized_lines = |source: &str| -> Vec<usize> {
+        analyze_errors(source).iter()
+            .filter(|e| matches!(e.kind, SemanticErrorKind::UninitializedVariable { .. }))
+            .map(|e| e.span.start.line)
+            .collect()
+    };
+
+    // Assigned on every path, or on a path that leaves the function otherwise
+    assert!(analyze_errors(r#"
+func f(n: int) -> int {
+    let x: int;
+    if n > 0 { x = 1; } else { x = 2; }
+    let y: int;
+    match n {
+        0 => { y = 0; }
+        _ => { return x; }
+    }
+    return x + y;
+}
+"#).is_empty());
+
+    // Missing else, one match arm, or a loop body that may not run
+    assert_eq!(uninitialized_lines(r#"
+func f(n: int) -> int {
+    let x: int;
+    if n > 0 { x = 1; }
+    let a = x;
+    let y: int;
+    match n {
+        0 => { y = 0; }
+        _ => { }
+    }
+    let b = y;
+    let z: int;
+    while n > 0 { z = n; }
+    return a + b + z;
+}
+"#), vec![5, 11, 14]);
+
+    // Reassigning a parameter that is not `mut` suggests adding it
+    let found = analyze_errors("func f(n: int) {\n n = 1;\n}");
+    assert_eq!(found.len(), 1);
+    match &found[0].kind {
+        SemanticErrorKind::AssignToImmutable { name, help } => {
+            assert_eq!(name, "n");
+            assert!(help.contains("mut n"), "{}", help);
+        }
+        other => panic!("expected immutable assignment error, got {:?}", other),
+    }
+    assert!(analyze_errors("func f(mut n: int) {\n n = 1;\n}").is_empty());
+
+    let found = analyze_errors("func f() {\n for i in [1, 2] { i = 3; }\n}");
+    assert!(matches!(&found[0].kind, SemanticErrorKind::AssignToImmutable { name, .. } if name == "i"));
+
+    // Only fields declared `mut` can be assigned
+    let found = analyze_errors("struct P { x: int }
+func f(p: P) {
+ p.x = 5;
+}");
+    assert_eq!(found.len(), 1);
+    match &found[0].kind {
+        SemanticErrorKind::AssignToImmutable { name, help } => {
+            assert_eq!(name, "P.x");
+            assert!(help.contains("mut x"), "{}", help);
+        }
+        other => panic!("expected immutable assignment error, got {:?}", other),
+    }
+    assert!(analyze_errors("struct P { mut x: int }\nfunc f(p: P) {\n p.x = 5;\n}").is_empty());
+}
+
+#[test]
+fn test_nullable_types() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let dereference_lines = |source: &str| -> Vec<usize> {
+        analyze_errors(source).iter()
+            .filter(|e| matches!(e.kind, SemanticErrorKind::NullableDereference { .. }))
+            .map(|e| e.span.start.line)
+            .collect()
+    };
+    let structs = "struct User { name: string, boss: User? }\n";
+
+    // Members of a nullable value are reached through a null check, `?.`, `!` or `match`
+    assert!(analyze_errors(&format!("{}{}", structs, r#"
+func describe(user: User?, fallback: string) -> string {
+    if user != null && user.boss != null {
+        return user.boss!.name;
+    }
+    let name: string? = user?.name;
+    let label = user?.boss?.name ?? name ?? fallback;
+    match user {
+        null => { return label; }
+        found => { return found.name; }
+    }
+}
+func first(user: User?) -> string {
+    if user == null { return ""; }
+    return user.name;
+}
+"#)).is_empty());
+
+    assert_eq!(dereference_lines(&format!("{}{}", structs, r#"
+func f(user: User?) -> string {
+    let a = user.name;
+    if user == null {
+        let b = user.name;
+    } else {
+        let c = user.name;
+    }
+    if user != null || true {
+        let d = user.name;
+    }
+    return user.boss?.name ?? "";
+}
+"#)), vec![4, 6, 11, 13]);
+
+    // A narrowed variable may be reassigned with null, which ends the narrowing
+    assert_eq!(dereference_lines(&format!("{}{}", structs, r#"
+func f(user: User?) -> string {
+    let current = user;
+    if current != null {
+        current = current.boss;
+        return current.name;
+    }
+    return "";
+}
+"#)), vec![7]);
+
+    // A closure may run after a narrowed variable it captures is set to null
+    assert_eq!(dereference_lines(&format!("{}{}", structs, r#"
+func f(user: User?) -> string {
+    let current = user;
+    if current != null {
+        let g = || current.name;
+        current = null;
+        return g();
+    }
+    return "";
+}
+"#)), vec![6]);
+
+    // Each iteration of a `while` loop starts with its condition holding
+    assert!(analyze_errors(r#"
+struct Node { v: int, next: Node? }
+func total(head: Node?) -> int {
+    let cur = head;
+    let total = 0;
+    while cur != null {
+        total = total + cur.v;
+        cur = cur.next;
+    }
+    return total;
+}
+"#).is_empty());
+
+    // `null` only fits nullable types, and `??` needs a nullable left side
+    let found = analyze_errors("func f() {\n let n: int = null;\n let m = 1 ?? 2;\n let k: int? = 3;\n let s = k + 1;\n}");
+    assert_eq!(found.len(), 3, "{:?}", found);
+    assert!(found.iter().all(|e| matches!(e.kind, SemanticErrorKind::TypeMismatch { .. })));
+    assert!(matches!(&found[0].kind, SemanticErrorKind::TypeMismatch { found: type_name, .. } if type_name == "null"));
+
+    // A function or lambda only returns `null` if its return type is nullable
+    let found = analyze_errors(r#"
+struct P { v: int }
+func g() -> P { return null; }
+func h() -> P { null }
+func k() -> int {
+    let make = |n: int| -> P { null };
+    return make(1).v;
+}
+func ok() -> P? { return null; }
+func also_ok() -> P? { null }
+"#);
+    let lines: Vec<usize> = found.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![3, 4, 6], "{:?}", found);
+    assert!(found.iter().all(|e| matches!(&e.kind, SemanticErrorKind::TypeMismatch { found, .. } if found == "null")));
+}
+
+#[test]
+fn test_try_operator() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let items = r#"
+interface Problem { func describe() -> string; }
+struct Missing { key: string }
+impl Problem for Missing { func describe() -> string { return self.key; } }
+func parse(s: string) -> Result<int, string> { return Ok(1); }
+func lookup(key: string) -> Result<int, Missing> { return Err(Missing { key: key }); }
+"#;
+
+    // `?` fits a function returning a Result with a convertible error type,
+    // and a nullable in a function returning a nullable type
+    let valid = analyze_errors(&format!("{}{}", items, r#"
+func sum(a: string, b: string) -> Result<int, string> {
+    let total = parse(a)? + parse(b)?;
+    return Ok(total);
+}
+func find(key: string) -> Result<int, Problem> {
+    return Ok(lookup(key)?);
+}
+func half(n: int?) -> int? {
+    let value = n?;
+    return value / 2;
+}
+func twice() -> Result<int, string> {
+    let f = |s: string| -> Result<int, string> { return Ok(parse(s)? * 2); };
+    return f("1");
+}
+"#));
+    assert!(valid.is_empty(), "{:?}", valid);
+
+    let found = analyze_errors(&format!("{}{}", items, r#"
+func a(s: string) -> int {
+    return parse(s)?;
+}
+func b(key: string) -> Result<int, string> {
+    return Ok(lookup(key)?);
+}
+func c(n: int?) -> int {
+    return n?;
+}
+func d(n: int) -> Result<int, string> {
+    return Ok(n?);
+}
+func e() -> Result<int, string> {
+    let g = |s: string| parse(s)? + 1;
+    return Ok(g("1"));
+}
+"#));
+    let lines: Vec<usize> = found.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![9, 12, 15, 18, 21], "{:?}", found);
+    assert!(matches!(found[0].kind, SemanticErrorKind::InvalidTry { .. }));
+    assert!(matches!(found[1].kind, SemanticErrorKind::ErrorConversion { .. }));
+    assert!(matches!(found[2].kind, SemanticErrorKind::InvalidTry { .. }));
+    assert!(matches!(found[3].kind, SemanticErrorKind::TypeMismatch { .. }));
+    // A lambda returns early from itself, not from the enclosing function
+    assert!(matches!(found[4].kind, SemanticErrorKind::InvalidTry { .. }));
+}
+
+#[test]
+fn test_destructuring() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let valid = analyze_errors(r#"
+struct Point { x: int, y: int }
+func split(p: Point) -> (int, int) {
+    let Point { x, y } = p;
+    return (x, y);
+}
+func total(scores: Map<string, int>) -> int {
+    let sum = 0;
+    for (name, score) in scores {
+        let (a, b): (int, int) = split(Point { x: score, y: 0 });
+        sum = sum + a + b;
+    }
+    return sum;
+}
+"#);
+    assert!(valid.is_empty(), "{:?}", valid);
+
+    let found = analyze_errors(r#"
+func parse(s: string) -> Result<int, string> { return Ok(1); }
+func a() {
+    let Ok(n) = parse("1");
+}
+func b() {
+    let (x, y, z) = (1, 2);
+}
+func c(names: List<string>) {
+    for (i, name) in names {
+    }
+}
+"#);
+    let lines: Vec<usize> = found.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![4, 7, 10], "{:?}", found);
+    assert!(matches!(found[0].kind, SemanticErrorKind::RefutablePattern { .. }));
+    assert_eq!(found[1].kind.to_string(), "Pattern has 3 elements but value is (int, int)");
+    assert_eq!(found[2].kind.to_string(), "Pattern has 2 elements but value is string");
+}
+
+#[test]
+fn test_range_typing() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let valid = analyze_errors(r#"
+func evens(n: int) -> Range {
+    return 0..=n step 2;
+}
+func sum(n: int) -> int {
+    let total = 0;
+    for i in evens(n) {
+        total = total + i;
+    }
+    for i in n..0 step -1 {
+        total = total + i;
+    }
+    return total;
+}
+"#);
+    assert!(valid.is_empty(), "{:?}", valid);
+
+    let found = analyze_errors(r#"
+func a(n: float) {
+    for i in 0..n { }
+}
+func b(s: string) -> Range {
+    return 0..10 step s;
+}
+func c() {
+    for i in 0..3 {
+        let flag: bool = i;
+    }
+}
+"#);
+    let lines: Vec<usize> = found.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![3, 6, 10], "{:?}", found);
+    assert!(found.iter().all(|e| matches!(e.kind, SemanticErrorKind::TypeMismatch { .. })));
+}
+
+#[test]
+fn test_interpolation_typing() {
+    use flux_compiler::error::SemanticErrorKind;
+
+    let valid = analyze_errors(r#"
+struct User { name: string, age: int }
+func greet(user: User, tags: List<string>, score: float?) -> string {
+    let line = "{user.name} ({user.age}) {tags} {score ?? 0.0}";
+    return "{line}: {user}";
+}
+"#);
+    assert!(valid.is_empty(), "{:?}", valid);
+
+    let found = analyze_errors(r#"
+func log(message: string) { }
+func a() -> string {
+    return "logged {log("x")}";
+}
+func b() -> string {
+    let double = |x: int| x * 2;
+    return "{double}";
+}
+func c() {
+    let n: int = "{1}";
+}
+"#);
+    let lines: Vec<usize> = found.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![4, 8, 11], "{:?}", found);
+    assert!(matches!(&found[0].kind, SemanticErrorKind::NotPrintable { type_name } if type_name == "()"));
+    assert!(matches!(found[1].kind, SemanticErrorKind::NotPrintable { .. }));
+    assert!(matches!(found[2].kind, SemanticErrorKind::TypeMismatch { .. }));
+}
+
+#[test]
+fn test_numeric_literal_typing() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::error::SemanticErrorKind;
+    use flux_compiler::tools::Formatter;
+
+    let valid = analyze_errors(r#"
+const MASK: int = 0xFFFF_0000
+const MIN: int = -9223372036854775808
+func header(flags: byte) -> bool {
+    let version: byte = 0x2u8;
+    let ratio = 0.5f32 * 2.0;
+    return flags == 0b1000_0001u8 && version != 0o7u8 && ratio > 0.0;
+}
+"#);
+    assert!(valid.is_empty(), "{:?}", valid);
+
+    // Integer literals are ints unless their suffix makes them bytes
+    let found = analyze_errors(r#"
+func a() {
+    let b: byte = 255;
+}
+func b() {
+    let n: int = 0xFFu8;
+}
+func c(flags: byte) -> bool {
+    return flags == 1;
+}
+"#);
+    let lines: Vec<usize> = found.iter().map(|e| e.span.start.line).collect();
+    assert_eq!(lines, vec![3, 6, 9], "{:?}", found);
+    assert!(found.iter().all(|e| matches!(e.kind, SemanticErrorKind::TypeMismatch { .. })));
+
+    // Literals that do not fit are reported where they are written
+    let parse_error = |source: &str| {
+        FluxParser::new(FluxLexer::new(source.to_string())).unwrap().parse_program().unwrap_err()
+    };
+    let error = parse_error("func main() {\n    let v = 256u8\n    println(\"{v}\")\n}");
+    assert!(error.to_string().contains("Number 256u8 is out of range for byte"), "{}", error);
+    assert_eq!((error.span.start.line, error.span.start.column), (2, 13));
+    let error = parse_error("func main() {\n    let v = 1 + 9223372036854775808\n}");
+    assert!(error.to_string().contains("Number 9223372036854775808 is out of range for int"), "{}", error);
+    assert_eq!((error.span.start.line, error.span.start.column), (2, 17));
+
+    // The formatter prints numbers the way they are written
+    let formatter = Formatter::new(CliContext::new(false, true));
+    let source = r#"package main;
+
+const MASK: int = 0xFF_FF;
+
+func main() {
+    let bits = ((0b1010_1010 + 1_000) - 0o17);
+    let ratio = ((2.5f32 * 1e3) + 1.50);
+    let min = -9223372036854775808;
+    match bits {
+        0x0A => {
+            println("ten");
+        }
+        _ => {
+        }
+    }
+}
+"#;
+    assert_eq!(formatter.format_source(source).unwrap(), source);
+}
+
+#[test]
+fn test_formatter_keeps_comments() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let formatter = Formatter::new(CliContext::new(false, true));
+
+    // A formatted file comes back byte for byte
+    let source = r#"// Geometry helpers
+
+package main;
+
+import "std/math"; // for sqrt
+
+/// A point
+pub struct Point {
+    // Horizontal
+    pub x: float, // may be negative
+
+    pub y: float,
+}
+
+/* Entry
+   point */
+func main() {
+    let a = 1;
+
+    // Second value
+    let b = 2; /* fixed */
+    match a {
+        // The common case
+        1 => {
+            println("one");
+        }
+        _ => {
+        }
+    }
+    // Nothing after this
+} // end of main
+
+// End of file
+"#;
+    assert_eq!(formatter.format_source(source).unwrap(), source);
+
+    // Comments in unformatted code are kept; a statement with comments
+    // inside keeps its source lines
+    let source = "func main() {\n\n\n  let a = 1 // one\n  // two\n\n\n\n  let b = a + /* three */ 2\n}";
+    let formatted = formatter.format_source(source).unwrap();
+    assert_eq!(formatted, "package main;\n\nfunc main() {\n    let a = 1; // one\n    // two\n\n    let b = a + /* three */ 2;\n}\n");
+    assert_eq!(formatter.format_source(&formatted).unwrap(), formatted);
+
+    // Comments inside parameter lists and expressions stay where they are
+    let source = r#"package main;
+
+pub func f(a: int, // first
+           b: int) -> int {
+    let xs = [1, // one
+              2];
+    f(1, /* inline */ 2);
+    if a > 0 && // positive
+       b > 0 {
+        return a;
+    }
+    return b;
+}
+"#;
+    assert_eq!(formatter.format_source(source).unwrap(), source);
+}
+
+#[test]
+fn test_formatter_keeps_string_forms() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let formatter = Formatter::new(CliContext::new(false, true));
+
+    // Raw and `"""` strings are printed as they are written
+    let source = r##"package main;
+
+func main() {
+    let raw = r#"raw "q" {x}"#;
+    let block = """
+        hello
+          world
+        """;
+    match raw {
+        r"a\b" => {
+            println("tab\t{block}");
+        }
+        _ => {
+        }
+    }
+}
+"##;
+    assert_eq!(formatter.format_source(source).unwrap(), source);
+}
+
+#[test]
+fn test_formatter_keeps_doc_comments() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let formatter = Formatter::new(CliContext::new(false, true));
+
+    // Doc comments are kept on every declaration, and as comments where
+    // nothing takes them
+    let source = r#"package main;
+
+/// Shapes
+pub enum Shape {
+    /// A circle
+    Circle(float),
+    /// A box
+    Rect {
+        /// Width
+        w: float,
+    },
+    Empty,
+}
+
+/// Has an area
+interface Area {
+    /// The area
+    func area() -> float;
+}
+
+/// Printing
+impl Area for Shape {
+    /// Computes it
+    func area() -> float {
+        return 0.0;
+    }
+}
+
+/// Writes a line
+extern func puts(s: string) -> int;
+
+func main() {
+    /// The answer
+    let a = 42;
+    match a {
+        /// Zero
+        0 => {
+        }
+        _ => {
+        }
+    }
+    let b = 1; /// trailing
+    /// Last
+}
+/// End
+"#;
+    assert_eq!(formatter.format_source(source).unwrap(), source);
+}
+
+#[test]
+fn test_formatter_round_trips_formatted_source() {
+    use flux_compiler::cli::CliContext;
+    use flux_compiler::tools::Formatter;
+
+    let formatter = Formatter::new(CliContext::new(false, true));
+
+    // A formatted file is left byte for byte as it is
+    let source = r##"package main;
+
+/// Bit masks
+const MASK: int = 0xFF_FF;
+
+/// Shapes
+enum Shape {
+    /// A circle
+    Circle(float),
+    /// A box
+    Rect {
+        /// Width
+        w: float,
+    },
+}
+
+/// Has an area
+interface Area {
+    /// The area
+    func area() -> float;
+}
+
+func main() {
+    /// Bits
+    let bits = 0b1010_1010;
+    let ratio = 2.5f32;
+    let big = 1_000_000;
+    let raw = r#"raw "q" {x}"#;
+    let block = """
+        hello
+        """;
+    let braces = "{}";
+    let mixed = "{} and {big}";
+    match bits {
+        0x0F => {
+            println("{}");
+        }
+        _ => {
+        }
+    }
+}
+"##;
+    assert_eq!(formatter.format_source(source).unwrap(), source);
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_range_loops() {
+    use flux_compiler::codegen::wasm::{WasmCodeGenerator, WasmRuntime};
+    use flux_compiler::error::CodeGenError;
+
+    let call = |source: &str, args: &[i64]| {
+        let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+        let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+        let bytes = WasmCodeGenerator::new().generate(program)?;
+        let mut runtime = WasmRuntime::new()?;
+        let instance = runtime.load_module(&bytes)?;
+        let args: Vec<_> = args.iter().map(|arg| wasmtime::Val::I64(*arg)).collect();
+        Ok::<_, CodeGenError>(runtime.call_function(&instance, "main", &args)?[0].unwrap_i64())
+    };
+
+    let counted = r#"
+func main(n: int, k: int) -> int {
+    let total = 0;
+    for i in 0..n { total = total + i; }
+    for i in 1..=3 { total = total + i; }
+    for i in 10..0 step -3 { total = total + i; }
+    for i in 0..n step k {
+        for j in 0..2 { total = total + 1000; }
+    }
+    for i in 0..10 {
+        if i == 3 { break; }
+        total = total + 100;
+    }
+    return total;
+}
+"#;
+    assert_eq!(call(counted, &[5, 2]).unwrap(), 10 + 6 + 22 + 6000 + 300);
+
+    // A step only known at run time decides the direction, and traps when zero
+    let stepped = r#"
+func main(n: int, k: int) -> int {
+    let total = 0;
+    for i in n..=0 step k {
+        if i == 2 { continue; }
+        total = total + i;
+    }
+    return total;
+}
+"#;
+    assert_eq!(call(stepped, &[4, -1]).unwrap(), 4 + 3 + 1);
+    assert!(call(stepped, &[4, 0]).is_err());
+
+    // Counting stops at the end rather than wrapping around past it
+    let bounded = r#"
+func main(n: int, k: int) -> int {
+    let count = 0;
+    for i in n - 2..=n { count = count + 1; }
+    for i in n - 7..=n step k { count = count + 1; }
+    for i in -n..=-n - 1 step -1 { count = count + 1; }
+    return count;
+}
+"#;
+    assert_eq!(call(bounded, &[i64::MAX, 5]).unwrap(), 3 + 2 + 2);
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn test_wasm_string_interpolation() {
+    use flux_compiler::codegen::wasm::{WasmCodeGenerator, WasmRuntime};
+
+    let source = r#"
+func main(n: int, small: byte, flag: bool) -> string {
+    let name = "flux";
+    return "{name}: {n}, {small}, {flag} and {-n}";
+}
+"#;
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+    let bytes = WasmCodeGenerator::new().generate(program).unwrap();
+    let mut runtime = WasmRuntime::new().unwrap();
+    let instance = runtime.load_module(&bytes).unwrap();
+    let mut describe = |n: i64, small: i32, flag: i32| {
+        let args = [wasmtime::Val::I64(n), wasmtime::Val::I32(small), wasmtime::Val::I32(flag)];
+        let pointer = runtime.call_function(&instance, "main", &args).unwrap()[0].unwrap_i32();
+        runtime.read_string(&instance, pointer).unwrap()
+    };
+    assert_eq!(describe(42, 7, 1), "flux: 42, 7, true and -42");
+    assert_eq!(describe(0, 255, 0), "flux: 0, 255, false and 0");
+    assert_eq!(describe(i64::MIN, 0, 1), "flux: -9223372036854775808, 0, true and -9223372036854775808");
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn test_wasm_unwrap() {
+    use flux_compiler::codegen::wasm::{WasmCodeGenerator, WasmRuntime};
+
+    // Unwrapping null traps instead of reading address 0
+    let source = r#"
+func main(found: bool) -> string {
+    let name: string? = null;
+    if found {
+        name = "flux";
+    }
+    return name!;
+}
+"#;
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+    let bytes = WasmCodeGenerator::new().generate(program).unwrap();
+    let mut runtime = WasmRuntime::new().unwrap();
+    let instance = runtime.load_module(&bytes).unwrap();
+
+    let pointer = runtime.call_function(&instance, "main", &[wasmtime::Val::I32(1)]).unwrap()[0].unwrap_i32();
+    assert_eq!(runtime.read_string(&instance, pointer).unwrap(), "flux");
+    assert!(runtime.call_function(&instance, "main", &[wasmtime::Val::I32(0)]).is_err());
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn test_wasm_generic_functions() {
+    use flux_compiler::codegen::wasm::{WasmCodeGenerator, WasmRuntime};
+
+    // Each instantiation is compiled separately
+    let source = r#"
+func pick<T>(first: bool, a: T, b: T) -> T {
+    if first {
+        return a;
+    }
+    return b;
+}
+
+func main(n: int) -> int {
+    let flag = pick(n > 0, true, false);
+    if flag {
+        return pick(true, n, 0) + pick(false, 0, 100);
+    }
+    return pick(false, n, -1);
+}
+"#;
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+    let bytes = WasmCodeGenerator::new().generate(program).unwrap();
+    let mut runtime = WasmRuntime::new().unwrap();
+    let instance = runtime.load_module(&bytes).unwrap();
+    let mut call = |n: i64| runtime.call_function(&instance, "main", &[wasmtime::Val::I64(n)]).unwrap()[0].unwrap_i64();
+    assert_eq!(call(5), 105);
+    assert_eq!(call(-5), -1);
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn test_wasm_closures() {
+    use flux_compiler::codegen::wasm::{WasmCodeGenerator, WasmRuntime};
+
+    // The lambda captures `offset` from main and is called through apply
+    let source = r#"
+func apply(f: (int) -> int, x: int) -> int {
+    return f(x);
+}
+
+func main(n: int) -> int {
+    let offset = n * 10;
+    let shift = |x: int| x + offset;
+    return apply(shift, 1) + shift(2);
+}
+"#;
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+    let bytes = WasmCodeGenerator::new().generate(program).unwrap();
+    let mut runtime = WasmRuntime::new().unwrap();
+    let instance = runtime.load_module(&bytes).unwrap();
+    let result = runtime.call_function(&instance, "main", &[wasmtime::Val::I64(3)]).unwrap();
+    assert_eq!(result[0].unwrap_i64(), 31 + 32);
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn test_wasm_interface_calls() {
+    use flux_compiler::codegen::wasm::{WasmCodeGenerator, WasmRuntime};
+
+    // Struct literals and field access are not lowered to WebAssembly yet,
+    // so the objects come from the host as opaque pointers and the methods
+    // do not read fields. Each call goes through the vtable of its type.
+    let source = r#"
+interface Shape {
+    func corners() -> int;
+}
+
+struct Square { side: int }
+struct Circle { radius: int }
+
+impl Shape for Square {
+    func corners() -> int { return 4; }
+}
+
+impl Shape for Circle {
+    func corners() -> int { return 0; }
+}
+
+func count(s: Shape) -> int {
+    return s.corners();
+}
+
+func main(square: Square, circle: Circle) -> int {
+    return count(square) * 10 + count(circle) + circle.corners();
+}
+"#;
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+    let bytes = WasmCodeGenerator::new().generate(program).unwrap();
+    let mut runtime = WasmRuntime::new().unwrap();
+    let instance = runtime.load_module(&bytes).unwrap();
+    let args = [wasmtime::Val::I32(64), wasmtime::Val::I32(128)];
+    let result = runtime.call_function(&instance, "main", &args).unwrap();
+    assert_eq!(result[0].unwrap_i64(), 40);
+
+    let source = r#"
+struct Square { side: int }
+func main() -> int {
+    let square = Square { side: 2 };
+    return 0;
+}
+"#;
+    let mut parser = FluxParser::new(FluxLexer::new(source.to_string())).unwrap();
+    let program = FluxSemanticAnalyzer::new().analyze(parser.parse_program().unwrap()).unwrap();
+    let error = WasmCodeGenerator::new().generate(program).unwrap_err();
+    assert!(error.to_string().contains("Struct literal 'Square'"), "{}", error);
+}
@@ -369,4 +369,147 @@ version = "1.0.0"
         }
         _ => panic!("Expected InvalidConfig error"),
     }
-}
\ No newline at end of file
+}
+/// Test running a project through the interpreter
+#[test]
+fn test_project_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_root = temp_dir.path();
+    
+    PackageManager::init_project(
+        project_root,
+        "test-project".to_string(),
+        "1.0.0".to_string(),
+    ).unwrap();
+    let project = ProjectInstance::load(project_root).unwrap();
+    
+    // The generated project runs without being compiled first
+    project.run(&[]).unwrap();
+    
+    // Runtime failures carry the failing location and the call stack
+    fs::write(project_root.join("src").join("main.flux"), r#"package main;
+
+func divide(a: int, b: int) -> int {
+    return a / b;
+}
+
+func main() {
+    divide(1, 0);
+}
+"#).unwrap();
+    match project.run(&[]) {
+        Err(FluxError::Runtime(RuntimeError { kind: RuntimeErrorKind::Reported(report) })) => {
+            // The report is printed once, under its own header
+            let error = FluxError::Runtime(RuntimeError { kind: RuntimeErrorKind::Reported(report.clone()) });
+            assert!(error.to_string().starts_with("Error: Runtime error: division by zero"));
+            assert_eq!(error.to_string().matches("Runtime error").count(), 1);
+            assert_eq!(report.location.unwrap().start.line, 4);
+            let functions: Vec<_> = report.stack_trace.iter().flatten().map(|frame| frame.function_name.as_str()).collect();
+            assert_eq!(functions, vec!["main", "divide"]);
+        }
+        other => panic!("Expected a reported runtime error, got {:?}", other),
+    }
+}
+
+/// Test that semantic errors name the file of the module they are in
+#[test]
+fn test_project_semantic_errors_per_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_root = temp_dir.path();
+    
+    PackageManager::init_project(
+        project_root,
+        "test-project".to_string(),
+        "1.0.0".to_string(),
+    ).unwrap();
+    let project = ProjectInstance::load(project_root).unwrap();
+    
+    let src = project_root.join("src");
+    fs::write(src.join("main.flux"), "import \"util\"\n\nfunc main() {\n    let a: string = 1;\n    util::helper(2);\n}\n").unwrap();
+    fs::write(src.join("util.flux"), "package util\n\npub func helper(x: int) -> int {\n    return x + true;\n}\n").unwrap();
+    
+    match project.analyze() {
+        Err(FluxError::SemanticErrors(errors)) => {
+            let found: Vec<_> = errors.iter()
+                .map(|(file, error)| (file.file_name().unwrap().to_string_lossy().into_owned(), error.span.start.line))
+                .collect();
+            assert_eq!(found, vec![("util.flux".to_string(), 4), ("main.flux".to_string(), 4)]);
+            let message = FluxError::SemanticErrors(errors).to_string();
+            assert!(message.lines().next().unwrap().contains("util.flux:4:"), "{}", message);
+        }
+        Err(other) => panic!("Expected semantic errors, got {:?}", other),
+        Ok(_) => panic!("Expected semantic errors"),
+    }
+}
+
+/// Test that types of different modules may share a name on the bytecode VM
+#[test]
+fn test_project_bytecode_types_per_module() {
+    use flux_compiler::runtime::Vm;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project_root = temp_dir.path();
+    
+    PackageManager::init_project(
+        project_root,
+        "test-project".to_string(),
+        "1.0.0".to_string(),
+    ).unwrap();
+    let project = ProjectInstance::load(project_root).unwrap();
+    
+    let src = project_root.join("src");
+    fs::write(src.join("geo.flux"), r#"package geo
+
+pub class Point {
+    pub x: int,
+    y: int,
+
+    pub func sum() -> int {
+        return self.x + self.y;
+    }
+}
+
+pub enum Shape {
+    Dot,
+    Line(int),
+}
+
+pub func origin() -> Point {
+    return Point { x: 1, y: 2 };
+}
+
+pub func line() -> Shape {
+    return Shape::Line(3);
+}
+"#).unwrap();
+    fs::write(src.join("main.flux"), r#"import "geo"
+
+class Point {
+    z: int,
+
+    func sum() -> int {
+        return self.z * 10;
+    }
+}
+
+enum Shape {
+    Line(int, int),
+}
+
+func main() {
+    let p = Point { z: 7 };
+    let q = geo::origin();
+    let r = geo::Point { x: 4, y: 5 };
+    println("{p.sum()} {q.sum()} {r.sum()} {q.x}");
+    match Shape::Line(4, 5) {
+        Shape::Line(a, b) => { println("{a} {b}") }
+    }
+    println("{geo::line()}");
+}
+"#).unwrap();
+    
+    let mut vm = Vm::new(project.compile_bytecode().unwrap());
+    vm.capture_output();
+    vm.run_main(&[]).unwrap();
+    assert_eq!(vm.captured_output(), Some("70 3 9 1\n4 5\nShape::Line(3)\n"));
+}
@@ -35,6 +35,7 @@ fn test_wasm_function_generation() {
         items: vec![
             TypedItem::Function(TypedFunction {
                 name: "add".to_string(),
+                type_params: vec![],
                 parameters: vec![
                     TypedParameter {
                         name: "a".to_string(),
@@ -93,6 +94,7 @@ fn test_wasm_literal_generation() {
         items: vec![
             TypedItem::Function(TypedFunction {
                 name: "test_literals".to_string(),
+                type_params: vec![],
                 parameters: vec![],
                 return_type: Type::Int,
                 body: TypedBlock {
@@ -141,6 +143,7 @@ fn test_wasm_binary_operations() {
             items: vec![
                 TypedItem::Function(TypedFunction {
                     name: "test_op".to_string(),
+                    type_params: vec![],
                     parameters: vec![],
                     return_type: if matches!(op, BinaryOp::Equal | BinaryOp::Less | BinaryOp::Greater) {
                         Type::Bool
@@ -194,6 +197,7 @@ fn test_js_interop_generation() {
     // Add a test function
     let test_func = TypedFunction {
         name: "greet".to_string(),
+        type_params: vec![],
         parameters: vec![
             TypedParameter {
                 name: "name".to_string(),
@@ -225,6 +229,7 @@ fn test_typescript_definitions() {
     // Add a test function
     let test_func = TypedFunction {
         name: "calculate".to_string(),
+        type_params: vec![],
         parameters: vec![
             TypedParameter {
                 name: "x".to_string(),
@@ -358,6 +363,7 @@ fn create_test_program() -> TypedProgram {
         items: vec![
             TypedItem::Function(TypedFunction {
                 name: "main".to_string(),
+                type_params: vec![],
                 parameters: vec![],
                 return_type: Type::Unit,
                 body: TypedBlock {
@@ -393,6 +399,7 @@ fn test_wasm_error_handling() {
             items: vec![
                 TypedItem::Function(TypedFunction {
                     name: "test".to_string(),
+                    type_params: vec![],
                     parameters: vec![],
                     return_type: Type::Unit,
                     body: TypedBlock {